@@ -0,0 +1,264 @@
+use crate::blocklist::{Blocklist, BlocklistMatching};
+use crate::engine::RecordingProfile;
+use crate::private_browsing::PrivateBrowsingDetector;
+use crate::system_events::SuspendReason;
+use crate::virtual_desktop::{VirtualDesktop, VirtualDesktopDetector};
+use active_win_pos_rs::get_active_window;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::{debug, error, warn};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActivityLog {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub app_name: String,
+    pub window_title: String,
+    pub is_captured: bool,
+    /// Whether the recording indicator was shown on the monitor, a toggle starts a new block.
+    pub indicator_visible: bool,
+    /// The virtual desktop the window was on, a switch starts a new block. `None` when the
+    /// platform doesn't tell, and in logs of older versions.
+    #[serde(default)]
+    pub virtual_desktop: Option<VirtualDesktop>,
+    /// Set on the blocks of the time the machine slept or was locked, with nothing captured
+    /// and no app or window.
+    #[serde(default)]
+    pub gap: Option<SuspendReason>,
+}
+
+/// Apps never captured by default, compared after [`crate::normalize_for_matching`].
+pub const DEFAULT_BLOCKED_APPS: &[&str] = &["spotify", "slack", "line", "discord"];
+/// Window titles never captured by default, compared after [`crate::normalize_for_matching`].
+pub const DEFAULT_BLOCKED_TITLES: &[&str] = &["private", "incognito", "secret"];
+
+pub struct ActivityMonitor {
+    current_log: Option<ActivityLog>,
+    /// Where the activity blocks are appended as JSONL, not written when `None`.
+    log_file_path: Option<PathBuf>,
+    blocklist: Blocklist,
+    /// Blocks private browsing windows the title keywords miss, when set.
+    private_browsing: Option<PrivateBrowsingDetector>,
+    virtual_desktops: VirtualDesktopDetector,
+    /// Ids, numbers or names of the desktops never captured while shown.
+    private_desktops: Vec<String>,
+}
+
+impl ActivityMonitor {
+    pub fn new(
+        log_file_path: Option<PathBuf>,
+        blocked_apps: &[String],
+        blocked_titles: &[String],
+        blocklist_matching: BlocklistMatching,
+        detect_private_browsing: bool,
+        private_desktops: &[String],
+    ) -> Self {
+        let virtual_desktops = VirtualDesktopDetector::default();
+        if !private_desktops.is_empty() && !virtual_desktops.is_available() {
+            warn!("Virtual desktops can't be read here, nothing is captured while private desktops are set");
+        }
+        Self {
+            current_log: None,
+            log_file_path,
+            // ブラックリスト（NFKC + ケースフォールディングで比較）
+            blocklist: Blocklist::new(blocked_apps, blocked_titles, blocklist_matching),
+            private_browsing: detect_private_browsing.then(PrivateBrowsingDetector::default),
+            virtual_desktops,
+            private_desktops: private_desktops.to_vec(),
+        }
+    }
+
+    /// Blocks what `profile` does, logging nothing until [`Self::set_log_file_path`]. Captures
+    /// made outside of the recording go through it too, for them to leave out what it does.
+    pub fn for_profile(profile: &RecordingProfile) -> Self {
+        Self::new(
+            None,
+            &profile.blocked_apps,
+            &profile.blocked_titles,
+            profile.blocklist_matching,
+            profile.detect_private_browsing,
+            &profile.private_desktops,
+        )
+    }
+
+    /// Name of the app focused now, without checking or logging it.
+    pub fn focused_app() -> Option<String> {
+        get_active_window().ok().map(|window| window.app_name)
+    }
+
+    /// The activity block in progress, as of the last [`Self::check_activity`].
+    pub fn current(&self) -> Option<&ActivityLog> {
+        self.current_log.as_ref()
+    }
+
+    /// 現在のアクティブウィンドウをチェックし、ログを更新する
+    /// 戻り値: モニター `monitor_id` のキャプチャを許可するかどうか (true: 許可, false: 禁止)
+    pub fn check_activity(&mut self, monitor_id: u32, indicator_visible: bool) -> bool {
+        let now = Utc::now();
+        let virtual_desktop = self.virtual_desktops.current();
+        let on_private_desktop = self.shows_private_desktop(monitor_id);
+        let active_window = match get_active_window() {
+            Ok(window) => window,
+            Err(_) => {
+                // ウィンドウ情報が取れない場合はデフォルト許可、または前回の状態を維持
+                // ここでは安全側に倒して許可し、ログは "Unknown" とする
+                // ただしウィンドウのないプライベートデスクトップは許可しない
+                return !on_private_desktop;
+            }
+        };
+
+        let is_blocked = on_private_desktop
+            || self.is_blocked(&active_window.app_name, &active_window.title)
+            || self.private_browsing.as_mut().is_some_and(|detector| {
+                detector
+                    .is_private(
+                        &active_window.app_name,
+                        &active_window.title,
+                        active_window.process_id,
+                        &active_window.process_path,
+                        &active_window.window_id,
+                    )
+                    .unwrap_or(false)
+            });
+        let app_name = active_window.app_name;
+        let window_title = active_window.title;
+
+        // 状態が変わったかチェック
+        let changed = if let Some(current) = &self.current_log {
+            current.app_name != app_name
+                || current.window_title != window_title
+                || current.is_captured != !is_blocked // is_blocked == true なら is_captured == false
+                || current.indicator_visible != indicator_visible
+                || current.virtual_desktop != virtual_desktop
+        } else {
+            true
+        };
+
+        if changed {
+            // 前回のログを確定して書き出し
+            if let Some(mut log) = self.current_log.take() {
+                log.end_time = now;
+                self.write_log(&log);
+            }
+
+            // 新しいログを開始
+            self.current_log = Some(ActivityLog {
+                start_time: now,
+                end_time: now, // 一旦現在時刻
+                app_name,
+                window_title,
+                is_captured: !is_blocked,
+                indicator_visible,
+                virtual_desktop,
+                gap: None,
+            });
+        } else {
+            // 継続中：end_timeのみ更新（メモリ上）
+            if let Some(log) = &mut self.current_log {
+                log.end_time = now;
+            }
+        }
+
+        !is_blocked
+    }
+
+    fn is_blocked(&self, app_name: &str, title: &str) -> bool {
+        if let Some(pattern) = self.blocklist.blocked_app(app_name) {
+            debug!("Blocked app detected: {} ({:?})", app_name, pattern);
+            return true;
+        }
+        if let Some(pattern) = self.blocklist.blocked_title(title) {
+            // titles are kept in their field, left out of diagnostics bundles
+            debug!(
+                window_title = title,
+                "Blocked title detected ({:?})", pattern
+            );
+            return true;
+        }
+        false
+    }
+
+    /// Whether the monitor `monitor_id` shows a private desktop, or may: a desktop that can't
+    /// be read counts as private while there are some.
+    fn shows_private_desktop(&self, monitor_id: u32) -> bool {
+        if self.private_desktops.is_empty() {
+            return false;
+        }
+        let Some(desktop) = self.virtual_desktops.shown_on(monitor_id) else {
+            debug!(
+                "Desktop of monitor {} unknown, taken as private",
+                monitor_id
+            );
+            return true;
+        };
+        let private = self
+            .private_desktops
+            .iter()
+            .any(|designation| desktop.matches(designation));
+        if private {
+            debug!(
+                "Private desktop shown on monitor {}: {:?}",
+                monitor_id, desktop
+            );
+        }
+        private
+    }
+
+    fn write_log(&self, log: &ActivityLog) {
+        let Some(log_file_path) = &self.log_file_path else {
+            return;
+        };
+        let json = match serde_json::to_string(log) {
+            Ok(j) => j,
+            Err(e) => {
+                error!("Failed to serialize activity log: {}", e);
+                return;
+            }
+        };
+
+        let mut file = match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file_path)
+        {
+            Ok(f) => f,
+            Err(e) => {
+                error!("Failed to open log file: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = writeln!(file, "{}", json) {
+            error!("Failed to write to log file: {}", e);
+        }
+    }
+
+    /// Writes a block of the time between `start` and `end` the machine slept or was locked.
+    pub fn log_gap(&self, start: DateTime<Utc>, end: DateTime<Utc>, reason: SuspendReason) {
+        self.write_log(&ActivityLog {
+            start_time: start,
+            end_time: end,
+            app_name: String::new(),
+            window_title: String::new(),
+            is_captured: false,
+            indicator_visible: false,
+            virtual_desktop: None,
+            gap: Some(reason),
+        });
+    }
+
+    /// Appends the next blocks to `log_file_path`, once the block in progress is flushed.
+    pub fn set_log_file_path(&mut self, log_file_path: Option<PathBuf>) {
+        self.log_file_path = log_file_path;
+    }
+
+    // アプリケーション終了時に呼び出して最後のログを書き込む
+    pub fn flush(&mut self) {
+        if let Some(log) = self.current_log.take() {
+            self.write_log(&log);
+        }
+    }
+}
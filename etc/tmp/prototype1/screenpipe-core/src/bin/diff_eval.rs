@@ -0,0 +1,106 @@
+//! Replays recorded segments through the diff metrics and reports the frames each keeps and
+//! drops, to tune the metric and threshold of a recording profile:
+//!
+//! `cargo run -p screenpipe-core --bin diff_eval -- monitor_1_2024-11-28T01-00-00Z.mp4
+//! [--metrics luma,rgb,lab] [--threshold 0.006] [--frames]`
+
+use anyhow::{anyhow, Context, Result};
+use screenpipe_core::{evaluate_segment, DiffMetric, RecordingProfile};
+
+struct Args {
+    segments: Vec<String>,
+    metrics: Vec<DiffMetric>,
+    threshold: f64,
+    frames: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args {
+        segments: Vec::new(),
+        metrics: DiffMetric::ALL.to_vec(),
+        threshold: RecordingProfile::default().diff_threshold,
+        frames: false,
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--metrics" => {
+                args.metrics = argv
+                    .next()
+                    .context("--metrics needs a value")?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_>>()?;
+            }
+            "--threshold" => {
+                args.threshold = argv
+                    .next()
+                    .context("--threshold needs a value")?
+                    .parse()
+                    .context("invalid --threshold")?;
+            }
+            "--frames" => args.frames = true,
+            _ if arg.starts_with("--") => return Err(anyhow!("unknown option {}", arg)),
+            _ => args.segments.push(arg),
+        }
+    }
+    if args.segments.is_empty() {
+        return Err(anyhow!(
+            "usage: diff_eval <segment.mp4>... [--metrics luma,rgb,lab] [--threshold 0.006] [--frames]"
+        ));
+    }
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args()?;
+
+    for segment in &args.segments {
+        let reports = evaluate_segment(segment, &args.metrics, args.threshold).await?;
+        println!("{} (threshold {})", segment, args.threshold);
+        println!(
+            "  {:<6} {:>8} {:>8} {:>7}",
+            "metric", "kept", "dropped", "kept %"
+        );
+        for report in &reports {
+            let total = report.kept + report.dropped;
+            println!(
+                "  {:<6} {:>8} {:>8} {:>6.1}%",
+                report.metric.to_string(),
+                report.kept,
+                report.dropped,
+                if total == 0 {
+                    0.0
+                } else {
+                    report.kept as f64 * 100.0 / total as f64
+                }
+            );
+        }
+
+        if args.frames {
+            print!("  {:>6}", "frame");
+            for report in &reports {
+                print!(" {:>13}", report.metric.to_string());
+            }
+            println!();
+            let frame_count = reports.first().map_or(0, |report| report.frames.len());
+            for i in 0..frame_count {
+                print!("  {:>6}", reports[0].frames[i].frame_number);
+                for report in &reports {
+                    let decision = &report.frames[i];
+                    let diff = decision
+                        .diff
+                        .map_or_else(|| "-".to_string(), |diff| format!("{:.4}", diff));
+                    print!(
+                        " {:>8} {:>4}",
+                        diff,
+                        if decision.kept { "keep" } else { "drop" }
+                    );
+                }
+                println!();
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! Shows what changed on a monitor between two times: the recorded frames nearest each time,
+//! side by side with the changed tiles highlighted, written as a PNG:
+//!
+//! `cargo run -p screenpipe-core --bin snapshot_diff -- ~/.work_recorder --monitor 1
+//! --from 2024-11-28T09:00 --to 2024-11-28T11:30 [--tile 64] [--metric lab]
+//! [--threshold 0.01] [-o diff.png]`
+//!
+//! Times are RFC 3339, or the local time as `YYYY-MM-DDTHH:MM[:SS]`.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use screenpipe_core::{snapshot_diff, SnapshotDiffOptions};
+use std::path::PathBuf;
+
+struct Args {
+    dir: PathBuf,
+    monitor_id: u32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    options: SnapshotDiffOptions,
+    output: PathBuf,
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .with_context(|| format!("invalid time {}", value))?;
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .with_context(|| format!("{} doesn't exist in the local time zone", value))
+}
+
+fn parse_args() -> Result<Args> {
+    const USAGE: &str = "usage: snapshot_diff <recordings dir> --monitor <id> --from <time> --to <time> [--tile 64] [--metric lab] [--threshold 0.01] [-o diff.png]";
+    let mut dir = None;
+    let mut monitor_id = None;
+    let mut from = None;
+    let mut to = None;
+    let mut options = SnapshotDiffOptions::default();
+    let mut output = PathBuf::from("snapshot_diff.png");
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || {
+            argv.next()
+                .with_context(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--monitor" => monitor_id = Some(value()?.parse().context("invalid --monitor")?),
+            "--from" => from = Some(parse_time(&value()?)?),
+            "--to" => to = Some(parse_time(&value()?)?),
+            "--tile" => options.tile_size = value()?.parse().context("invalid --tile")?,
+            "--metric" => options.metric = value()?.parse()?,
+            "--threshold" => options.threshold = value()?.parse().context("invalid --threshold")?,
+            "-o" | "--output" => output = PathBuf::from(value()?),
+            _ if arg.starts_with('-') => return Err(anyhow!("unknown option {}", arg)),
+            _ => dir = Some(PathBuf::from(arg)),
+        }
+    }
+    match (dir, monitor_id, from, to) {
+        (Some(dir), Some(monitor_id), Some(from), Some(to)) => Ok(Args {
+            dir,
+            monitor_id,
+            from,
+            to,
+            options,
+            output,
+        }),
+        _ => Err(anyhow!(USAGE)),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args()?;
+    let diff = snapshot_diff(
+        &args.dir,
+        args.monitor_id,
+        args.from,
+        args.to,
+        &args.options,
+    )
+    .await?;
+    diff.image
+        .save(&args.output)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    for (label, snapshot) in [("before", &diff.before), ("after", &diff.after)] {
+        println!(
+            "{:<6} {} ({} frame {})",
+            label,
+            snapshot.time_zone.display(snapshot.time),
+            snapshot.video,
+            snapshot.index
+        );
+    }
+    println!(
+        "{} tiles changed ({:.1}% of the screen), written to {}",
+        diff.diff.tiles.len(),
+        diff.diff.changed_share * 100.0,
+        args.output.display()
+    );
+    Ok(())
+}
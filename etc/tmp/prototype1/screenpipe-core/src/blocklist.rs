@@ -0,0 +1,110 @@
+use caseless::default_case_fold_str;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// Patterns shorter than this, in characters, are always matched exactly: one edit in "line"
+/// would block every app with "lime" or "link" in its name.
+pub const FUZZY_MIN_CHARS: usize = 5;
+
+/// Puts `text` in the form the blocklist compares: NFKC, so that full-width "ＳＬＡＣＫ" and
+/// half-width "ｼｰｸﾚｯﾄ" read as "SLACK" and "シークレット", then case folded, with runs of
+/// whitespace, ideographic spaces included, collapsed to one space.
+pub fn normalize_for_matching(text: &str) -> String {
+    let folded = default_case_fold_str(&text.nfkc().collect::<String>());
+    // folding can undo the composition, "ǰ" folds to "j" and a combining caron
+    let normalized: String = folded.nfkc().collect();
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// How app names and window titles are matched against [`Blocklist`] patterns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlocklistMatching {
+    /// Edits, in characters, a part of the name or title may be away from a pattern and still
+    /// match it, for titles with typos or a character the app replaced. `0`, the default, only
+    /// matches the patterns as they are, after normalization.
+    pub fuzzy_distance: usize,
+}
+
+/// Apps and window titles never captured, matched anywhere in the name or title after
+/// [`normalize_for_matching`].
+#[derive(Debug, Clone, Default)]
+pub struct Blocklist {
+    apps: Vec<Vec<char>>,
+    titles: Vec<Vec<char>>,
+    matching: BlocklistMatching,
+}
+
+impl Blocklist {
+    pub fn new(apps: &[String], titles: &[String], matching: BlocklistMatching) -> Self {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| normalize_for_matching(pattern).chars().collect::<Vec<_>>())
+                // an empty pattern would block everything
+                .filter(|pattern| !pattern.is_empty())
+                .collect()
+        };
+        Self {
+            apps: compile(apps),
+            titles: compile(titles),
+            matching,
+        }
+    }
+
+    /// The app pattern matching `app_name`, as normalized, if any.
+    pub fn blocked_app(&self, app_name: &str) -> Option<String> {
+        self.find(&self.apps, app_name)
+    }
+
+    /// The title pattern matching `title`, as normalized, if any.
+    pub fn blocked_title(&self, title: &str) -> Option<String> {
+        self.find(&self.titles, title)
+    }
+
+    pub fn is_blocked(&self, app_name: &str, title: &str) -> bool {
+        self.blocked_app(app_name).is_some() || self.blocked_title(title).is_some()
+    }
+
+    fn find(&self, patterns: &[Vec<char>], text: &str) -> Option<String> {
+        let text: Vec<char> = normalize_for_matching(text).chars().collect();
+        patterns
+            .iter()
+            .find(|pattern| {
+                let distance = if pattern.len() < FUZZY_MIN_CHARS {
+                    0
+                } else {
+                    self.matching.fuzzy_distance
+                };
+                contains_within(&text, pattern, distance)
+            })
+            .map(|pattern| pattern.iter().collect())
+    }
+}
+
+/// Whether some part of `text` is at most `distance` insertions, deletions or substitutions
+/// away from `pattern`, by Sellers' approximate substring matching.
+fn contains_within(text: &[char], pattern: &[char], distance: usize) -> bool {
+    if distance == 0 {
+        return text.windows(pattern.len()).any(|window| window == pattern);
+    }
+    if pattern.len() <= distance {
+        return true;
+    }
+    // edits[j]: fewest edits for pattern[..j] to end at the current position of the text,
+    // starting anywhere
+    let mut edits: Vec<usize> = (0..=pattern.len()).collect();
+    for &c in text {
+        let mut diagonal = edits[0];
+        for j in 1..=pattern.len() {
+            let above = edits[j];
+            edits[j] = (diagonal + usize::from(pattern[j - 1] != c))
+                .min(above + 1)
+                .min(edits[j - 1] + 1);
+            diagonal = above;
+        }
+        if edits[pattern.len()] <= distance {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,715 @@
+use crate::activity::ActivityMonitor;
+use crate::diff::{compare_with_previous_image, MaxAverageFrame};
+use crate::engine::{Events, RecordingControls, RecordingEvent, RecordingProfile, SegmentEncoding};
+use crate::pipeline::{CapturedFrame, Item, KeptFrame, Pipeline};
+use crate::session::RecordingSession;
+use crate::sink::FrameSink;
+use crate::system_events::{SuspendReason, SystemEvent, SLEEP_GAP};
+use anyhow::{Context, Error, Result};
+use chrono::{DateTime, Utc};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, error, info, warn};
+use xcap::Monitor;
+
+// --- SafeMonitor Implementation (from screenpipe-vision) ---
+
+#[derive(Clone)]
+pub struct SafeMonitor {
+    monitor_id: u32,
+    monitor_data: Arc<MonitorData>,
+}
+
+#[derive(Clone)]
+pub struct MonitorData {
+    pub width: u32,
+    pub height: u32,
+    pub name: String,
+    /// Position of the top left corner, in pixels.
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f32,
+    #[allow(dead_code)]
+    pub is_primary: bool,
+}
+
+impl SafeMonitor {
+    pub fn new(monitor: Monitor) -> Self {
+        let monitor_id = monitor.id().unwrap();
+        let monitor_data = Arc::new(MonitorData {
+            width: monitor.width().unwrap(),
+            height: monitor.height().unwrap(),
+            name: monitor.name().unwrap().to_string(),
+            x: monitor.x().unwrap(),
+            y: monitor.y().unwrap(),
+            scale_factor: monitor.scale_factor().unwrap(),
+            is_primary: monitor.is_primary().unwrap(),
+        });
+
+        Self {
+            monitor_id,
+            monitor_data,
+        }
+    }
+
+    pub async fn capture_image(&self) -> Result<DynamicImage> {
+        let monitor_id = self.monitor_id;
+
+        let image = std::thread::spawn(move || -> Result<DynamicImage> {
+            let monitor = Monitor::all()
+                .map_err(Error::from)?
+                .into_iter()
+                .find(|m| m.id().unwrap() == monitor_id)
+                .ok_or_else(|| anyhow::anyhow!("Monitor not found"))?;
+
+            if monitor.width().unwrap() == 0 || monitor.height().unwrap() == 0 {
+                return Err(anyhow::anyhow!("Invalid monitor dimensions"));
+            }
+
+            monitor
+                .capture_image()
+                .map_err(Error::from)
+                .map(DynamicImage::ImageRgba8)
+        })
+        .join()
+        .unwrap()?;
+
+        Ok(image)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.monitor_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.monitor_data.name
+    }
+
+    pub fn width(&self) -> u32 {
+        self.monitor_data.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.monitor_data.height
+    }
+
+    pub fn x(&self) -> i32 {
+        self.monitor_data.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.monitor_data.y
+    }
+
+    /// Pixels per point of the monitor.
+    pub fn scale_factor(&self) -> f32 {
+        self.monitor_data.scale_factor
+    }
+}
+
+pub async fn list_monitors() -> Vec<SafeMonitor> {
+    tokio::task::spawn_blocking(|| {
+        Monitor::all()
+            .unwrap_or_default()
+            .into_iter()
+            .map(SafeMonitor::new)
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+pub async fn get_monitor_by_id(id: u32) -> Option<SafeMonitor> {
+    tokio::task::spawn_blocking(move || match Monitor::all() {
+        Ok(monitors) => monitors
+            .into_iter()
+            .find(|m| m.id().unwrap() == id)
+            .map(SafeMonitor::new),
+        Err(_) => None,
+    })
+    .await
+    .unwrap_or(None)
+}
+
+// --- Recorder Implementation ---
+
+/// How long an app whose encoding differs from the segment's stays focused before a new
+/// segment is started for it, so that a glance at another window doesn't cut the video.
+const ENCODING_SWITCH_DELAY: Duration = Duration::from_secs(5);
+
+/// `image` at `scale` of its resolution, for the apps recorded downscaled.
+fn downscale(image: DynamicImage, scale: f64) -> DynamicImage {
+    if scale >= 1.0 {
+        return image;
+    }
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    image.resize_exact(width, height, FilterType::Triangle)
+}
+
+type Sinks = Arc<Mutex<Vec<Box<dyn FrameSink>>>>;
+
+/// Compares the captured frames to the last kept one, until the queue is closed, and queues
+/// those that changed for the sinks. The first frame after a flush, that of a new segment, is
+/// always kept.
+async fn diff_frames(
+    monitor_id: u32,
+    profile: Arc<RecordingProfile>,
+    pipeline: Arc<Pipeline>,
+    events: Events,
+) {
+    let mut frame_counter: u64 = 0;
+    let mut previous_image: Option<Arc<DynamicImage>> = None;
+    let mut max_average: Option<MaxAverageFrame> = None;
+    let mut max_avg_value = 0.0;
+
+    while let Some(item) = pipeline.diff.pop().await {
+        let frame = match item {
+            Item::Frame(frame) => frame,
+            Item::Flush(done) => {
+                previous_image = None;
+                pipeline.encode.push_flush(done);
+                continue;
+            }
+        };
+        let current_average = compare_with_previous_image(
+            previous_image.as_deref(),
+            &frame.image,
+            profile.diff_metric,
+            &mut max_average,
+            frame_counter,
+            &mut max_avg_value,
+        )
+        .unwrap_or(1.0); // Default to changed if diff fails
+        frame_counter += 1;
+
+        // Force first frame or if diff is significant, by the threshold of the frame's app
+        if previous_image.is_none() || current_average >= frame.diff_threshold {
+            previous_image = Some(frame.image.clone());
+            pipeline
+                .encode
+                .push_frame(KeptFrame {
+                    image: frame.image,
+                    frame_number: frame_counter,
+                    diff: current_average,
+                    app_name: frame.app_name,
+                    window_title: frame.window_title,
+                })
+                .await;
+        } else {
+            debug!(
+                "Skipping frame {} (diff: {:.4})",
+                frame_counter, current_average
+            );
+            events.emit(RecordingEvent::FrameSkipped {
+                monitor_id,
+                frame_number: frame_counter,
+                diff: current_average,
+            });
+        }
+    }
+    pipeline.encode.close();
+}
+
+/// Writes the kept frames to the sinks, until the queue is closed. A frame failing to be
+/// written is left to the capture loop to write again in a new segment, and the frames after
+/// it are dropped until the loop flushes the pipeline.
+async fn encode_frames(monitor_id: u32, sinks: Sinks, pipeline: Arc<Pipeline>, events: Events) {
+    let mut failed = false;
+    while let Some(item) = pipeline.encode.pop().await {
+        let frame = match item {
+            Item::Frame(frame) => frame,
+            Item::Flush(done) => {
+                failed = false;
+                let _ = done.send(());
+                continue;
+            }
+        };
+        if failed {
+            debug!("Dropping frame {} after a failed write", frame.frame_number);
+            pipeline.encode.note_dropped();
+            continue;
+        }
+        let written = write_to_sinks(&mut sinks.lock().await, &frame.image).await;
+        match written {
+            Ok(()) => emit_written(&events, monitor_id, frame),
+            Err(e) => {
+                failed = true;
+                pipeline.fail_write(frame, e);
+            }
+        }
+    }
+}
+
+async fn write_to_sinks(sinks: &mut [Box<dyn FrameSink>], image: &DynamicImage) -> Result<()> {
+    for sink in sinks {
+        sink.write_frame(image).await?;
+    }
+    Ok(())
+}
+
+fn emit_written(events: &Events, monitor_id: u32, frame: KeptFrame) {
+    debug!(
+        "Frame {} written (diff: {:.4})",
+        frame.frame_number, frame.diff
+    );
+    events.emit(RecordingEvent::FrameWritten {
+        monitor_id,
+        frame_number: frame.frame_number,
+        diff: frame.diff,
+        app_name: frame.app_name,
+        window_title: frame.window_title,
+    });
+}
+
+/// Records a monitor into its sinks, started by [`crate::RecordingEngine::start`]. Capture
+/// runs here, the diff and the encoding in tasks of their own fed through the [`Pipeline`].
+pub(crate) struct Recorder {
+    monitor: SafeMonitor,
+    profile: Arc<RecordingProfile>,
+    /// Written by the encoding task, opened and closed here once the pipeline is flushed.
+    sinks: Sinks,
+    pipeline: Arc<Pipeline>,
+    activity_log_dir: Option<PathBuf>,
+    /// Id of the recording, shared with the recorders of the other monitors.
+    session_id: String,
+    /// The segment being recorded, `None` while suspended.
+    session: Option<RecordingSession>,
+    /// The app focused at the last tick, whose override the next segment is encoded by.
+    focused_app: Option<String>,
+    /// How the frames of the segment are encoded.
+    encoding: SegmentEncoding,
+    /// Indicator and pause, set through [`crate::RecordingHandle`].
+    controls: Arc<RecordingControls>,
+    events: Events,
+}
+
+impl Recorder {
+    pub(crate) fn new(
+        monitor: SafeMonitor,
+        profile: Arc<RecordingProfile>,
+        sinks: Vec<Box<dyn FrameSink>>,
+        pipeline: Arc<Pipeline>,
+        activity_log_dir: Option<PathBuf>,
+        session_id: String,
+        controls: Arc<RecordingControls>,
+        events: Events,
+    ) -> Self {
+        let encoding = profile.encoding_for(None);
+        Self {
+            monitor,
+            profile,
+            sinks: Arc::new(Mutex::new(sinks)),
+            pipeline,
+            activity_log_dir,
+            session_id,
+            session: None,
+            focused_app: None,
+            encoding,
+            controls,
+            events,
+        }
+    }
+
+    /// Where the activity blocks of `session` go, its session file written next to them.
+    fn activity_log_path(&self, session: &RecordingSession) -> Result<Option<PathBuf>> {
+        let Some(dir) = &self.activity_log_dir else {
+            return Ok(None);
+        };
+        let path = dir.join(session.log_path());
+        let log_dir = path.parent().unwrap_or(dir);
+        std::fs::create_dir_all(log_dir).context(format!(
+            "Failed to create activity log directory: {}",
+            log_dir.display()
+        ))?;
+        session.write(dir)?;
+        Ok(Some(path))
+    }
+
+    /// Whether files of `session` already exist, its activity log or those of its sinks.
+    async fn taken(&self, session: &RecordingSession) -> bool {
+        let log_taken = self.activity_log_dir.as_ref().is_some_and(|dir| {
+            dir.join(session.log_path()).exists()
+                || dir.join(session.sidecar_path("session.json")).exists()
+        });
+        log_taken
+            || self
+                .sinks
+                .lock()
+                .await
+                .iter()
+                .any(|sink| sink.taken(session))
+    }
+
+    async fn open_sinks(&mut self, session: &RecordingSession) -> Result<()> {
+        for sink in self.sinks.lock().await.iter_mut() {
+            sink.open(&self.monitor, &self.profile, session).await?;
+        }
+        Ok(())
+    }
+
+    /// Closes every sink even when one fails, returning the first error.
+    async fn close_sinks(&mut self, session: &RecordingSession) -> Result<()> {
+        let mut result = Ok(());
+        for sink in self.sinks.lock().await.iter_mut() {
+            if let Err(e) = sink.close(session).await {
+                error!("Failed to close sink: {}", e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Starts a new segment of the recording of the monitor, its own video and activity log,
+    /// once the last one is finished. It's encoded as the focused app's override says.
+    async fn start_segment(&mut self, activity_monitor: &mut ActivityMonitor) -> Result<()> {
+        let encoding = self.profile.encoding_for(self.focused_app.as_deref());
+        let mut session =
+            RecordingSession::start(self.monitor.id(), &self.session_id, &self.profile.naming);
+        session.crf = Some(encoding.crf);
+        session.scale = (encoding.scale < 1.0).then_some(encoding.scale);
+        // names are only unique to the second, after a wake or a restart they may be taken
+        while self.taken(&session).await {
+            session.sequence += 1;
+        }
+        activity_monitor.flush();
+        activity_monitor.set_log_file_path(self.activity_log_path(&session)?);
+        self.open_sinks(&session).await?;
+        self.session = Some(session);
+        self.encoding = encoding;
+        Ok(())
+    }
+
+    /// Ends the segment being recorded: the block in progress ends, the frames queued are
+    /// written, the sinks are closed, and the activity log renamed with the end when the
+    /// naming has it. Blocks written after, such as the gap of a suspension, still go to that
+    /// log.
+    async fn finish_segment(&mut self, activity_monitor: &mut ActivityMonitor) -> Result<()> {
+        activity_monitor.flush();
+        let Some(mut session) = self.session.take() else {
+            return Ok(());
+        };
+        self.pipeline.flush().await;
+        if let Some((frame, e)) = self.pipeline.take_failed_write() {
+            warn!("Frame {} lost with its segment: {}", frame.frame_number, e);
+            self.pipeline.encode.note_dropped();
+        }
+        session.ended_at = Some(Utc::now());
+        let result = self.close_sinks(&session).await;
+        if let Some(dir) = &self.activity_log_dir {
+            match session
+                .rename_ended(dir, RecordingSession::log_path)
+                .and_then(|path| session.write(dir).map(|_| path))
+            {
+                Ok(path) => activity_monitor.set_log_file_path(Some(path)),
+                Err(e) => warn!("Failed to finish the activity log: {}", e),
+            }
+        }
+        result
+    }
+
+    /// Stops capturing for `reason`: the block in progress ends and the video is finalized,
+    /// with the last frame before rather than a write failing after.
+    async fn suspend(
+        &mut self,
+        activity_monitor: &mut ActivityMonitor,
+        reason: SuspendReason,
+    ) -> (SuspendReason, DateTime<Utc>) {
+        let monitor_id = self.monitor.id();
+        info!(
+            "Recording suspended on monitor {}: {:?}",
+            monitor_id, reason
+        );
+        if let Err(e) = self.finish_segment(activity_monitor).await {
+            warn!(
+                "Failed to finalize the recording before {:?}: {}",
+                reason, e
+            );
+        }
+        self.events
+            .emit(RecordingEvent::Suspended { monitor_id, reason });
+        (reason, Utc::now())
+    }
+
+    pub(crate) async fn run(
+        mut self,
+        mut stop_rx: broadcast::Receiver<()>,
+        mut system_rx: broadcast::Receiver<SystemEvent>,
+    ) -> Result<()> {
+        let monitor_id = self.monitor.id();
+        info!("Starting recording for monitor {}", monitor_id);
+
+        // the log of the first segment is set as it starts
+        let mut activity_monitor = ActivityMonitor::for_profile(&self.profile);
+        self.focused_app = ActivityMonitor::focused_app();
+
+        self.start_segment(&mut activity_monitor).await?;
+        let stages = [
+            tokio::spawn(diff_frames(
+                monitor_id,
+                self.profile.clone(),
+                self.pipeline.clone(),
+                self.events.clone(),
+            )),
+            tokio::spawn(encode_frames(
+                monitor_id,
+                self.sinks.clone(),
+                self.pipeline.clone(),
+                self.events.clone(),
+            )),
+        ];
+        self.events.emit(RecordingEvent::Started { monitor_id });
+
+        let mut indicator_visible = self.controls.indicator_visible.load(Ordering::Relaxed);
+        let mut paused = false;
+        // why and since when nothing is captured, the sinks closed
+        let mut suspended: Option<(SuspendReason, DateTime<Utc>)> = None;
+        let interval = Duration::from_secs_f64(1.0 / self.profile.fps);
+        let mut next_tick = Instant::now();
+        let mut last_tick = Utc::now();
+        // system events received while waiting for the next tick, handled right away
+        let mut pending = Vec::new();
+        let mut system_open = true;
+        let mut early = false;
+        // an encoding other than the segment's and since when its app is focused
+        let mut switching: Option<(SegmentEncoding, Instant)> = None;
+        let mut result = Ok(());
+
+        'recording: loop {
+            // Check for stop signal
+            if stop_rx.try_recv().is_ok() {
+                info!("Stop signal received");
+                break;
+            }
+
+            if let Some((frame, e)) = self.pipeline.take_failed_write() {
+                // e.g. ffmpeg gone after a sleep: the frame goes to a new segment, the
+                // recording stops if that fails too
+                warn!("Failed to write frame, starting a new segment: {}", e);
+                let _ = self.finish_segment(&mut activity_monitor).await;
+                let retried = match self.start_segment(&mut activity_monitor).await {
+                    Ok(()) => write_to_sinks(&mut self.sinks.lock().await, &frame.image).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = retried {
+                    error!("Failed to write frame: {}", e);
+                    result = Err(e);
+                    break; // Stop on write error
+                }
+                emit_written(&self.events, monitor_id, frame);
+            }
+
+            // the wall clock keeps going while the machine sleeps, the loop's sleeps don't
+            let now = Utc::now();
+            let previous_tick = std::mem::replace(&mut last_tick, now);
+            let mut changes = Vec::new();
+            // cleared once a wake is handled, the one of logind may follow
+            let mut slept =
+                (now - previous_tick).to_std().unwrap_or_default() > interval + SLEEP_GAP;
+            if slept {
+                changes.push(SystemEvent::Woke);
+            }
+            changes.append(&mut pending);
+            loop {
+                match system_rx.try_recv() {
+                    Ok(event) => changes.push(event),
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+
+            let pause_when_locked = self.profile.pause_when_locked;
+            for change in changes {
+                let resumed = match (change, suspended) {
+                    // the machine sleeps once every recorder dropped the delay, its video final
+                    (SystemEvent::Sleeping(delay), None) => {
+                        suspended = Some(
+                            self.suspend(&mut activity_monitor, SuspendReason::Sleep)
+                                .await,
+                        );
+                        drop(delay);
+                        None
+                    }
+                    (SystemEvent::Locked, None) if pause_when_locked => {
+                        suspended = Some(
+                            self.suspend(&mut activity_monitor, SuspendReason::Lock)
+                                .await,
+                        );
+                        None
+                    }
+                    // locked on wake, suspended until unlocked
+                    (SystemEvent::Locked, Some((SuspendReason::Sleep, since)))
+                        if pause_when_locked =>
+                    {
+                        suspended = Some((SuspendReason::Lock, since));
+                        None
+                    }
+                    (SystemEvent::Woke, Some((SuspendReason::Sleep, since))) => {
+                        slept = false;
+                        Some((SuspendReason::Sleep, since))
+                    }
+                    (SystemEvent::Unlocked, Some((SuspendReason::Lock, since))) => {
+                        Some((SuspendReason::Lock, since))
+                    }
+                    // a sleep nobody announced: the recording goes on in a new segment, the
+                    // ffmpeg pipe may not have survived it
+                    (SystemEvent::Woke, None) if slept => {
+                        slept = false;
+                        self.suspend(&mut activity_monitor, SuspendReason::Sleep)
+                            .await;
+                        Some((SuspendReason::Sleep, previous_tick))
+                    }
+                    _ => None,
+                };
+                let Some((reason, since)) = resumed else {
+                    continue;
+                };
+                activity_monitor.log_gap(since, now, reason);
+                if let Err(e) = self.start_segment(&mut activity_monitor).await {
+                    error!("Failed to restart recording after {:?}: {}", reason, e);
+                    result = Err(e);
+                    break 'recording;
+                }
+                suspended = None;
+                info!(
+                    "Recording resumed on monitor {} after {:?}",
+                    monitor_id, reason
+                );
+                self.events.emit(RecordingEvent::SuspensionEnded {
+                    monitor_id,
+                    reason,
+                    gap_secs: (now - since).num_milliseconds() as f64 / 1000.0,
+                });
+            }
+
+            if self.controls.paused.load(Ordering::Relaxed) != paused {
+                paused = !paused;
+                if paused {
+                    info!("Recording paused on monitor {}", monitor_id);
+                    // the block in progress ends at the last tick before the pause
+                    activity_monitor.flush();
+                    self.events.emit(RecordingEvent::Paused { monitor_id });
+                } else {
+                    info!("Recording resumed on monitor {}", monitor_id);
+                    self.events.emit(RecordingEvent::Resumed { monitor_id });
+                }
+            }
+
+            let visible = self.controls.indicator_visible.load(Ordering::Relaxed);
+            if visible != indicator_visible {
+                indicator_visible = visible;
+                info!(
+                    "Recording indicator {} on monitor {}",
+                    if visible { "shown" } else { "hidden" },
+                    monitor_id
+                );
+                self.events.emit(RecordingEvent::IndicatorToggled {
+                    monitor_id,
+                    visible,
+                });
+            }
+
+            if early || paused || suspended.is_some() {
+                // nothing is captured or logged, the loop keeps its timing; after a system
+                // event between ticks, the frame is still due at the next tick
+            } else if !activity_monitor.check_activity(monitor_id, indicator_visible) {
+                debug!("Capture blocked due to restricted activity");
+                // Skip capture, but sleep to maintain loop timing
+                // We do NOT write to the sinks here (VFR behavior)
+                // Log is updated inside check_activity
+                self.events
+                    .emit(RecordingEvent::CaptureBlocked { monitor_id });
+            } else {
+                self.focused_app = activity_monitor.current().map(|log| log.app_name.clone());
+                let wanted = self.profile.encoding_for(self.focused_app.as_deref());
+                match switching {
+                    _ if wanted == self.encoding => switching = None,
+                    Some((encoding, since)) if encoding == wanted => {
+                        if since.elapsed() >= ENCODING_SWITCH_DELAY {
+                            switching = None;
+                            info!(
+                                "Starting a new segment on monitor {} for {:?}",
+                                monitor_id, wanted
+                            );
+                            if let Err(e) = self.finish_segment(&mut activity_monitor).await {
+                                warn!("Failed to finalize the segment: {}", e);
+                            }
+                            if let Err(e) = self.start_segment(&mut activity_monitor).await {
+                                error!("Failed to start a new segment: {}", e);
+                                result = Err(e);
+                                break 'recording;
+                            }
+                        }
+                    }
+                    _ => switching = Some((wanted, Instant::now())),
+                }
+
+                // Capture, the diff and the encoding follow in their own tasks
+                match self.monitor.capture_image().await {
+                    Ok(image) => {
+                        let image = downscale(image, self.encoding.scale);
+                        let activity = activity_monitor.current();
+                        let app_name = activity.map(|log| log.app_name.clone());
+                        let frame = CapturedFrame {
+                            image: Arc::new(image),
+                            diff_threshold: self.profile.diff_threshold_for(app_name.as_deref()),
+                            app_name,
+                            window_title: activity.map(|log| log.window_title.clone()),
+                        };
+                        self.pipeline.diff.push_frame(frame).await;
+                    }
+                    Err(e) => {
+                        warn!("Failed to capture image: {}", e);
+                    }
+                }
+            }
+
+            // Sleep logic
+            if !early {
+                next_tick += interval;
+            }
+            early = false;
+            let now = Instant::now();
+            if next_tick > now {
+                tokio::select! {
+                    _ = tokio::time::sleep(next_tick - now) => {}
+                    event = system_rx.recv(), if system_open => {
+                        early = true;
+                        match event {
+                            Ok(event) => pending.push(event),
+                            Err(broadcast::error::RecvError::Lagged(_)) => {}
+                            Err(broadcast::error::RecvError::Closed) => system_open = false,
+                        }
+                    }
+                }
+            } else {
+                // We are behind, reset next_tick to avoid burst
+                next_tick = now;
+            }
+        }
+
+        // Flush final log entry and the queued frames, and cleanup the sinks, all of them even
+        // when one fails, unless closed for a suspension
+        if let Err(e) = self.finish_segment(&mut activity_monitor).await {
+            if result.is_ok() {
+                result = Err(e);
+            }
+        }
+        self.pipeline.diff.close();
+        for stage in stages {
+            let _ = stage.await;
+        }
+
+        if result.is_ok() {
+            self.events.emit(RecordingEvent::Stopped { monitor_id });
+        }
+        result
+    }
+}
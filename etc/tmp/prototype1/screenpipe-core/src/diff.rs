@@ -0,0 +1,208 @@
+use image::{DynamicImage, GrayImage, Luma, RgbImage};
+use image_compare::{Algorithm, Metric, Similarity};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::str::FromStr;
+use tracing::debug;
+
+/// Color difference in CIELAB above which a pixel visibly changed, the just noticeable
+/// difference of ΔE*76.
+pub const LAB_JND: f64 = 2.3;
+
+/// How much a frame changed from the previous one, from 0 (same) to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffMetric {
+    /// Average of the histogram and SSIM differences of the luma. Dark-mode UIs have little
+    /// luma contrast, so their changes come out small.
+    #[default]
+    Luma,
+    /// The luma metric on each of the red, green and blue channels, keeping the largest, so
+    /// that a change of color at the same brightness counts.
+    Rgb,
+    /// Share of the pixels whose CIELAB color visibly changed, more than [`LAB_JND`]. Lightness
+    /// is perceptually uniform in CIELAB, so changes between dark grays weigh as much as
+    /// between light ones.
+    Lab,
+}
+
+impl DiffMetric {
+    pub const ALL: [DiffMetric; 3] = [DiffMetric::Luma, DiffMetric::Rgb, DiffMetric::Lab];
+}
+
+impl fmt::Display for DiffMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DiffMetric::Luma => "luma",
+            DiffMetric::Rgb => "rgb",
+            DiffMetric::Lab => "lab",
+        })
+    }
+}
+
+impl FromStr for DiffMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        DiffMetric::ALL
+            .into_iter()
+            .find(|metric| metric.to_string() == s.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("unknown diff metric {}, use luma, rgb or lab", s))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaxAverageFrame {
+    pub frame_number: u64,
+    #[allow(dead_code)]
+    pub average: f64,
+}
+
+#[allow(dead_code)]
+pub fn calculate_hash(image: &DynamicImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.as_bytes().hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn compare_images_histogram(
+    image1: &DynamicImage,
+    image2: &DynamicImage,
+) -> anyhow::Result<f64> {
+    let image_one = image1.to_luma8();
+    let image_two = image2.to_luma8();
+    image_compare::gray_similarity_histogram(Metric::Hellinger, &image_one, &image_two)
+        .map_err(|e| anyhow::anyhow!("Failed to compare images: {}", e))
+}
+
+pub fn compare_images_ssim(image1: &DynamicImage, image2: &DynamicImage) -> f64 {
+    let image_one = image1.to_luma8();
+    let image_two = image2.to_luma8();
+    let result: Similarity =
+        image_compare::gray_similarity_structure(&Algorithm::MSSIMSimple, &image_one, &image_two)
+            .expect("Images had different dimensions");
+    result.score
+}
+
+fn compare_gray(image_one: &GrayImage, image_two: &GrayImage) -> anyhow::Result<f64> {
+    let histogram_diff =
+        image_compare::gray_similarity_histogram(Metric::Hellinger, image_one, image_two)
+            .map_err(|e| anyhow::anyhow!("Failed to compare images: {}", e))?;
+    let ssim_diff = 1.0
+        - image_compare::gray_similarity_structure(&Algorithm::MSSIMSimple, image_one, image_two)
+            .map_err(|e| anyhow::anyhow!("Failed to compare images: {}", e))?
+            .score;
+    Ok((histogram_diff + ssim_diff) / 2.0)
+}
+
+fn channel(image: &RgbImage, channel: usize) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        Luma([image.get_pixel(x, y)[channel]])
+    })
+}
+
+pub fn compare_images_rgb(image1: &DynamicImage, image2: &DynamicImage) -> anyhow::Result<f64> {
+    let image_one = image1.to_rgb8();
+    let image_two = image2.to_rgb8();
+    let mut max_diff: f64 = 0.0;
+    for c in 0..3 {
+        max_diff = max_diff.max(compare_gray(
+            &channel(&image_one, c),
+            &channel(&image_two, c),
+        )?);
+    }
+    Ok(max_diff)
+}
+
+/// sRGB to CIELAB under D65.
+fn to_lab(linear: &[f64; 256], [r, g, b]: [u8; 3]) -> [f64; 3] {
+    let (r, g, b) = (linear[r as usize], linear[g as usize], linear[b as usize]);
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        const DELTA: f64 = 6.0 / 29.0;
+        if t > DELTA * DELTA * DELTA {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub fn compare_images_lab(image1: &DynamicImage, image2: &DynamicImage) -> anyhow::Result<f64> {
+    let image_one = image1.to_rgb8();
+    let image_two = image2.to_rgb8();
+    if image_one.dimensions() != image_two.dimensions() {
+        return Err(anyhow::anyhow!("Images had different dimensions"));
+    }
+    let linear: [f64; 256] = std::array::from_fn(|v| {
+        let v = v as f64 / 255.0;
+        if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    });
+
+    let mut changed = 0u64;
+    for (one, two) in image_one.pixels().zip(image_two.pixels()) {
+        if one == two {
+            continue;
+        }
+        let [l1, a1, b1] = to_lab(&linear, one.0);
+        let [l2, a2, b2] = to_lab(&linear, two.0);
+        let delta_e = ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt();
+        if delta_e > LAB_JND {
+            changed += 1;
+        }
+    }
+    let pixels = image_one.width() as u64 * image_one.height() as u64;
+    Ok(if pixels == 0 {
+        0.0
+    } else {
+        changed as f64 / pixels as f64
+    })
+}
+
+/// Difference between two frames by `metric`, from 0 (same) to 1.
+pub fn frame_diff(
+    metric: DiffMetric,
+    previous: &DynamicImage,
+    current: &DynamicImage,
+) -> anyhow::Result<f64> {
+    match metric {
+        DiffMetric::Luma => {
+            let histogram_diff = compare_images_histogram(previous, current)?;
+            let ssim_diff = 1.0 - compare_images_ssim(previous, current);
+            Ok((histogram_diff + ssim_diff) / 2.0)
+        }
+        DiffMetric::Rgb => compare_images_rgb(previous, current),
+        DiffMetric::Lab => compare_images_lab(previous, current),
+    }
+}
+
+pub fn compare_with_previous_image(
+    previous_image: Option<&DynamicImage>,
+    current_image: &DynamicImage,
+    metric: DiffMetric,
+    max_average: &mut Option<MaxAverageFrame>,
+    frame_number: u64,
+    max_avg_value: &mut f64,
+) -> anyhow::Result<f64> {
+    let mut current_average = 0.0;
+    if let Some(prev_image) = previous_image {
+        current_average = frame_diff(metric, prev_image, current_image)?;
+        let max_avg_frame_number = max_average.as_ref().map_or(0, |frame| frame.frame_number);
+        debug!(
+            "Frame {}: {} diff: {:.3}, Max_avr: {:.3} Fr: {}",
+            frame_number, metric, current_average, *max_avg_value, max_avg_frame_number
+        );
+    } else {
+        debug!("No previous image to compare for frame {}", frame_number);
+    }
+    Ok(current_average)
+}
@@ -1,12 +1,14 @@
-use anyhow::{Result, Context};
-use std::process::Stdio;
-use tokio::process::{Child, Command, ChildStdin};
-use tokio::io::AsyncWriteExt;
-use tracing::{info, warn, debug};
-use std::time::Duration;
+use anyhow::{Context, Result};
 use image::DynamicImage;
-use std::io::Cursor;
 use image::ImageFormat;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+use tracing::{debug, info, warn};
 
 #[allow(dead_code)]
 pub struct CaptureResult {
@@ -19,7 +21,25 @@ const FFMPEG_EXE: &str = "ffmpeg.exe";
 #[cfg(not(windows))]
 const FFMPEG_EXE: &str = "ffmpeg";
 
+/// The ffmpeg set with [`set_ffmpeg_path`], looked for before any other.
+static FFMPEG_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Makes [`find_ffmpeg_path`] return `path` while it exists, e.g. an ffmpeg the user picked or
+/// downloaded. `None` looks for it as usual again.
+pub fn set_ffmpeg_path(path: Option<PathBuf>) {
+    *FFMPEG_PATH.write().unwrap() = path;
+}
+
 pub fn find_ffmpeg_path() -> Option<String> {
+    // 0. The one set by the app
+    if let Some(path) = FFMPEG_PATH.read().unwrap().as_ref() {
+        if path.is_file() {
+            debug!("Using the ffmpeg set: {:?}", path);
+            return path.to_str().map(|s| s.to_string());
+        }
+        warn!("The ffmpeg set doesn't exist: {:?}", path);
+    }
+
     // 1. Check in project directory (etc/tmp/prototype1/ffmpeg/)
     if let Ok(exe_path) = std::env::current_exe() {
         if let Some(exe_dir) = exe_path.parent() {
@@ -31,14 +51,17 @@ pub fn find_ffmpeg_path() -> Option<String> {
                     debug!("Found ffmpeg in project directory: {:?}", ffmpeg_in_project);
                     return ffmpeg_in_project.to_str().map(|s| s.to_string());
                 }
-                
+
                 // Also check in same directory as executable
                 let ffmpeg_in_exe_dir = current.join(FFMPEG_EXE);
                 if ffmpeg_in_exe_dir.exists() {
-                    debug!("Found ffmpeg in executable directory: {:?}", ffmpeg_in_exe_dir);
+                    debug!(
+                        "Found ffmpeg in executable directory: {:?}",
+                        ffmpeg_in_exe_dir
+                    );
                     return ffmpeg_in_exe_dir.to_str().map(|s| s.to_string());
                 }
-                
+
                 if let Some(parent) = current.parent() {
                     current = parent;
                 } else {
@@ -47,7 +70,7 @@ pub fn find_ffmpeg_path() -> Option<String> {
             }
         }
     }
-    
+
     // 2. Check in current working directory
     if let Ok(cwd) = std::env::current_dir() {
         let ffmpeg_in_cwd = cwd.join("ffmpeg").join(FFMPEG_EXE);
@@ -55,14 +78,17 @@ pub fn find_ffmpeg_path() -> Option<String> {
             debug!("Found ffmpeg in current directory: {:?}", ffmpeg_in_cwd);
             return ffmpeg_in_cwd.to_str().map(|s| s.to_string());
         }
-        
+
         let ffmpeg_direct = cwd.join(FFMPEG_EXE);
         if ffmpeg_direct.exists() {
-            debug!("Found ffmpeg directly in current directory: {:?}", ffmpeg_direct);
+            debug!(
+                "Found ffmpeg directly in current directory: {:?}",
+                ffmpeg_direct
+            );
             return ffmpeg_direct.to_str().map(|s| s.to_string());
         }
     }
-    
+
     // 3. Check in PATH as fallback
     #[cfg(windows)]
     {
@@ -78,29 +104,42 @@ pub fn find_ffmpeg_path() -> Option<String> {
             return path.to_str().map(|s| s.to_string());
         }
     }
-    
+
     warn!("FFmpeg not found. Please place ffmpeg binary in project directory (ffmpeg/{} or same directory as executable)", FFMPEG_EXE);
     None
 }
 
-pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child> {
+pub async fn start_ffmpeg_process(output_file: &str, fps: f64, crf: u8) -> Result<Child> {
     let ffmpeg_path = find_ffmpeg_path().context("FFmpeg not found")?;
     info!("Starting FFmpeg process for file: {}", output_file);
-    
+
     let fps_str = fps.to_string();
+    let crf_str = crf.to_string();
     let mut command = Command::new(ffmpeg_path);
+    // the file is checked not to exist, ffmpeg would otherwise ask from the frames' pipe
     let args = vec![
-        "-f", "image2pipe",
-        "-vcodec", "png",
-        "-r", &fps_str,
-        "-i", "-",
-        "-vf", "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2",
-        "-vcodec", "libx265",
-        "-tag:v", "hvc1",
-        "-preset", "ultrafast",
-        "-crf", "23",
-        "-pix_fmt", "yuv420p",
-        output_file
+        "-y",
+        "-f",
+        "image2pipe",
+        "-vcodec",
+        "png",
+        "-r",
+        &fps_str,
+        "-i",
+        "-",
+        "-vf",
+        "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2",
+        "-vcodec",
+        "libx265",
+        "-tag:v",
+        "hvc1",
+        "-preset",
+        "ultrafast",
+        "-crf",
+        &crf_str,
+        "-pix_fmt",
+        "yuv420p",
+        output_file,
     ];
 
     command
@@ -115,22 +154,20 @@ pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child>
     Ok(child)
 }
 
-pub async fn write_frame_to_ffmpeg(
-    stdin: &mut ChildStdin,
-    image: &DynamicImage,
-) -> Result<()> {
+pub async fn write_frame_to_ffmpeg(stdin: &mut ChildStdin, image: &DynamicImage) -> Result<()> {
     let mut buffer = Vec::new();
-    image.write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+    image
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
         .context("Failed to encode frame to PNG")?;
 
-    stdin.write_all(&buffer).await.context("Failed to write frame to ffmpeg stdin")?;
+    stdin
+        .write_all(&buffer)
+        .await
+        .context("Failed to write frame to ffmpeg stdin")?;
     Ok(())
 }
 
-pub async fn write_frame_with_retry(
-    stdin: &mut ChildStdin,
-    image: &DynamicImage,
-) -> Result<()> {
+pub async fn write_frame_with_retry(stdin: &mut ChildStdin, image: &DynamicImage) -> Result<()> {
     const MAX_RETRIES: usize = 3;
     const RETRY_DELAY: Duration = Duration::from_millis(100);
 
@@ -143,12 +180,59 @@ pub async fn write_frame_with_retry(
                 if retries >= MAX_RETRIES {
                     return Err(anyhow::anyhow!("Failed to write frame to ffmpeg: {}", e));
                 } else {
-                    warn!("Failed to write frame to ffmpeg (attempt {}): {}. Retrying...", retries, e);
+                    warn!(
+                        "Failed to write frame to ffmpeg (attempt {}): {}. Retrying...",
+                        retries, e
+                    );
                     tokio::time::sleep(RETRY_DELAY).await;
                 }
             }
         }
     }
-    Err(anyhow::anyhow!("Failed to write frame to ffmpeg after max retries"))
+    Err(anyhow::anyhow!(
+        "Failed to write frame to ffmpeg after max retries"
+    ))
 }
 
+/// Re-encodes the first `frames` frames of the mp4 at `path` in place of it.
+pub async fn trim_video(path: &Path, frames: usize, crf: u8) -> Result<()> {
+    let ffmpeg_path = find_ffmpeg_path().context("FFmpeg not found")?;
+    let output = path.with_extension("trim.mp4");
+    let frames_str = frames.to_string();
+    let crf_str = crf.to_string();
+    let status = Command::new(ffmpeg_path)
+        .arg("-y")
+        .arg("-i")
+        .arg(path)
+        .args([
+            "-frames:v",
+            &frames_str,
+            "-fps_mode",
+            "passthrough",
+            "-vcodec",
+            "libx265",
+            "-tag:v",
+            "hvc1",
+            "-crf",
+            &crf_str,
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(&output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .context("Failed to spawn ffmpeg")?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&output);
+        return Err(anyhow::anyhow!(
+            "ffmpeg exited with {} trimming {}",
+            status,
+            path.display()
+        ));
+    }
+    std::fs::rename(&output, path)?;
+    Ok(())
+}
@@ -0,0 +1,561 @@
+use crate::activity::{DEFAULT_BLOCKED_APPS, DEFAULT_BLOCKED_TITLES};
+use crate::blocklist::{normalize_for_matching, BlocklistMatching};
+use crate::capture::{list_monitors, Recorder, SafeMonitor};
+use crate::diff::DiffMetric;
+use crate::naming::OutputNaming;
+use crate::pipeline::{BackpressurePolicy, Pipeline, PipelineStats};
+use crate::session::upgrade_recording_names;
+use crate::sink::{FrameSink, SinkFactory, VideoFileSink};
+use crate::system_events::{spawn_watcher, SuspendReason};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Monitors a [`RecordingEngine`] records.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Targets {
+    /// Every monitor connected when the recording starts.
+    #[default]
+    AllMonitors,
+    /// Monitors by id, as given by [`SafeMonitor::id`].
+    Monitors(Vec<u32>),
+}
+
+/// How the screens are recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingProfile {
+    /// Frames captured per second.
+    pub fps: f64,
+    /// How frames are compared to the last kept one.
+    pub diff_metric: DiffMetric,
+    /// Difference to the last kept frame, by `diff_metric`, below which a frame is dropped as
+    /// unchanged.
+    pub diff_threshold: f64,
+    /// Settings used in place of the profile's while an app is focused, the first override
+    /// matching the app of the [`ActivityMonitor`](crate::ActivityMonitor) wins.
+    pub app_overrides: Vec<AppOverride>,
+    /// CRF of the H.265 encoding of [`VideoFileSink`], lower is better looking and bigger.
+    pub crf: u8,
+    /// Frames each monitor queues for the diff, and as many for the sinks, at most. Each is
+    /// a whole screenshot in memory.
+    pub queue_capacity: usize,
+    /// What capture does when the diff or the sinks fall behind and their queue is full.
+    pub backpressure: BackpressurePolicy,
+    /// Apps never captured while focused, matched anywhere in the name, ignoring case and
+    /// full-width or half-width forms.
+    pub blocked_apps: Vec<String>,
+    /// Windows never captured while focused, matched in the title like `blocked_apps`.
+    pub blocked_titles: Vec<String>,
+    /// Whether the blocked apps and titles also match names a few edits away.
+    pub blocklist_matching: BlocklistMatching,
+    /// Whether private browsing windows are never captured either, detected per browser from
+    /// its process flags, localized titles and, on macOS, AppleScript window mode.
+    pub detect_private_browsing: bool,
+    /// Virtual desktops, Spaces on macOS, whose monitors are never captured while they show
+    /// them, by the id, number or name of [`VirtualDesktop`](crate::VirtualDesktop) as the
+    /// activity log records it. When macOS displays have separate Spaces, each monitor is
+    /// skipped while its own display shows one. Where the desktops can't be read, as on
+    /// Wayland, nothing is captured while any is set.
+    pub private_desktops: Vec<String>,
+    /// Whether capture stops while the screen is locked, as it does while the machine sleeps.
+    /// The video of each monitor is finalized first, and a new one started once unlocked.
+    pub pause_when_locked: bool,
+    /// How the videos, their frame times and session files, and the activity logs are named
+    /// in their directories, e.g. in a directory per recording with `{session_id}/`.
+    pub naming: OutputNaming,
+    /// Whether a recording indicator, a red dot and the monitor's name, is shown on the
+    /// recorded monitors from the start. It can be toggled while recording with
+    /// [`RecordingHandle::set_indicator_visible`].
+    pub show_indicator: bool,
+    /// Whether the recording starts paused, nothing captured until
+    /// [`RecordingHandle::set_paused`] resumes it, e.g. when started again after a pause.
+    pub start_paused: bool,
+}
+
+impl Default for RecordingProfile {
+    fn default() -> Self {
+        Self {
+            fps: 1.0,
+            diff_metric: DiffMetric::default(),
+            diff_threshold: 0.006,
+            app_overrides: Vec::new(),
+            crf: 23,
+            queue_capacity: 4,
+            backpressure: BackpressurePolicy::default(),
+            blocked_apps: DEFAULT_BLOCKED_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            blocked_titles: DEFAULT_BLOCKED_TITLES
+                .iter()
+                .map(|title| title.to_string())
+                .collect(),
+            blocklist_matching: BlocklistMatching::default(),
+            detect_private_browsing: true,
+            private_desktops: Vec::new(),
+            pause_when_locked: true,
+            naming: OutputNaming::default(),
+            show_indicator: false,
+            start_paused: false,
+        }
+    }
+}
+
+impl RecordingProfile {
+    /// The override of the focused app `app_name`, if any.
+    pub fn app_override(&self, app_name: &str) -> Option<&AppOverride> {
+        let app_name = normalize_for_matching(app_name);
+        self.app_overrides.iter().find(|app_override| {
+            let pattern = normalize_for_matching(&app_override.app);
+            !pattern.is_empty() && app_name.contains(&pattern)
+        })
+    }
+
+    /// The diff threshold while `app_name` is focused, `diff_threshold` unless overridden.
+    pub fn diff_threshold_for(&self, app_name: Option<&str>) -> f64 {
+        app_name
+            .and_then(|app_name| self.app_override(app_name))
+            .and_then(|app_override| app_override.diff_threshold)
+            .unwrap_or(self.diff_threshold)
+    }
+
+    /// How segments are encoded while `app_name` is focused, at `crf` and full resolution
+    /// unless overridden.
+    pub fn encoding_for(&self, app_name: Option<&str>) -> SegmentEncoding {
+        let app_override = app_name.and_then(|app_name| self.app_override(app_name));
+        SegmentEncoding {
+            crf: app_override
+                .and_then(|app_override| app_override.crf)
+                .unwrap_or(self.crf),
+            scale: app_override
+                .and_then(|app_override| app_override.scale)
+                .unwrap_or(1.0),
+        }
+    }
+}
+
+/// How the video of a segment is encoded, see [`RecordingProfile::encoding_for`]. A video is
+/// encoded one way, the recorder starts a new segment once an app needing another stays
+/// focused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentEncoding {
+    pub crf: u8,
+    /// Fraction of the monitor's resolution the frames are downscaled to, 1 at full.
+    pub scale: f64,
+}
+
+/// How frames are recorded while an app is focused, in place of the [`RecordingProfile`]'s
+/// settings, e.g. a higher diff threshold for a terminal whose cursor blinks and a lower one
+/// for a dashboard that barely changes, or a low CRF at full resolution for the text of an
+/// IDE and a heavy downscale for a video player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppOverride {
+    /// Matched anywhere in the name of the focused app, like
+    /// [`RecordingProfile::blocked_apps`].
+    pub app: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crf: Option<u8>,
+    /// Fraction of the monitor's resolution the frames are downscaled to, above 0 and up to
+    /// 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+}
+
+impl AppOverride {
+    /// Fails on an override without an app name, with a diff threshold out of 0 to 1, a CRF
+    /// over 51 or a scale out of 0 to 1.
+    pub fn validate(&self) -> Result<()> {
+        if normalize_for_matching(&self.app).is_empty() {
+            return Err(anyhow!("an app override needs an app name"));
+        }
+        if let Some(threshold) = self.diff_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(anyhow!(
+                    "diff threshold must be between 0 and 1, got {}",
+                    threshold
+                ));
+            }
+        }
+        if let Some(crf) = self.crf {
+            if crf > 51 {
+                return Err(anyhow!("crf must be at most 51, got {}", crf));
+            }
+        }
+        if let Some(scale) = self.scale {
+            if !(scale > 0.0 && scale <= 1.0) {
+                return Err(anyhow!("scale must be above 0 and up to 1, got {}", scale));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What happens while recording, as given to the event subscribers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordingEvent {
+    Started {
+        monitor_id: u32,
+    },
+    /// A frame was handed to the sinks, with the focused window it showed.
+    FrameWritten {
+        monitor_id: u32,
+        frame_number: u64,
+        diff: f64,
+        app_name: Option<String>,
+        window_title: Option<String>,
+    },
+    /// A frame was dropped for being too close to the last kept one.
+    FrameSkipped {
+        monitor_id: u32,
+        frame_number: u64,
+        diff: f64,
+    },
+    /// Capture stopped until [`RecordingEvent::Resumed`], with nothing logged meanwhile.
+    Paused {
+        monitor_id: u32,
+    },
+    Resumed {
+        monitor_id: u32,
+    },
+    /// The recording indicator was shown or hidden.
+    IndicatorToggled {
+        monitor_id: u32,
+        visible: bool,
+    },
+    /// Nothing was captured because a blocked app or window was focused.
+    CaptureBlocked {
+        monitor_id: u32,
+    },
+    /// The machine is about to sleep or the screen was locked: the video is finalized and
+    /// nothing is captured until [`RecordingEvent::SuspensionEnded`].
+    Suspended {
+        monitor_id: u32,
+        reason: SuspendReason,
+    },
+    /// Recording went on in a new video after a sleep or lock, noted as a gap in the activity
+    /// log.
+    SuspensionEnded {
+        monitor_id: u32,
+        reason: SuspendReason,
+        gap_secs: f64,
+    },
+    Stopped {
+        monitor_id: u32,
+    },
+    /// The recording of a monitor ended on an error.
+    Failed {
+        monitor_id: u32,
+        error: String,
+    },
+}
+
+/// What a [`RecordingHandle`] changes while recording, read by the recorders every tick.
+#[derive(Default)]
+pub(crate) struct RecordingControls {
+    pub(crate) indicator_visible: AtomicBool,
+    pub(crate) paused: AtomicBool,
+}
+
+/// Called for every [`RecordingEvent`], from the recording tasks, so it should return quickly.
+pub type EventSubscriber = Arc<dyn Fn(&RecordingEvent) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub(crate) struct Events(Arc<Vec<EventSubscriber>>);
+
+impl Events {
+    pub(crate) fn emit(&self, event: RecordingEvent) {
+        for subscriber in self.0.iter() {
+            subscriber(&event);
+        }
+    }
+}
+
+/// Builds a [`RecordingEngine`], see [`RecordingEngine::builder`].
+#[derive(Default)]
+pub struct RecordingEngineBuilder {
+    targets: Targets,
+    profile: RecordingProfile,
+    sinks: Vec<SinkFactory>,
+    activity_log_dir: Option<PathBuf>,
+    output_dirs: Vec<PathBuf>,
+    subscribers: Vec<EventSubscriber>,
+}
+
+impl RecordingEngineBuilder {
+    /// Monitors to record, all of them by default.
+    pub fn targets(mut self, targets: Targets) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    pub fn profile(mut self, profile: RecordingProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Adds a sink, built for each monitor when its recording starts.
+    pub fn sink<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&SafeMonitor) -> Box<dyn FrameSink> + Send + Sync + 'static,
+    {
+        self.sinks.push(Arc::new(factory));
+        self
+    }
+
+    /// Adds a [`VideoFileSink`] writing an mp4 per monitor and recording into `output_dir`.
+    pub fn video_output(mut self, output_dir: impl AsRef<Path>) -> Self {
+        self.output_dirs.push(output_dir.as_ref().to_path_buf());
+        self.sinks.push(VideoFileSink::factory(output_dir));
+        self
+    }
+
+    /// Appends the activity blocks, the focused app and window over time, as a JSONL file per
+    /// monitor and segment into `dir`, named by [`OutputNaming::log`].
+    pub fn activity_log(mut self, dir: impl AsRef<Path>) -> Self {
+        self.activity_log_dir = Some(dir.as_ref().to_path_buf());
+        self.output_dirs.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Calls `subscriber` for every [`RecordingEvent`].
+    pub fn subscribe<F>(mut self, subscriber: F) -> Self
+    where
+        F: Fn(&RecordingEvent) + Send + Sync + 'static,
+    {
+        self.subscribers.push(Arc::new(subscriber));
+        self
+    }
+
+    pub fn build(self) -> Result<RecordingEngine> {
+        if !(self.profile.fps > 0.0 && self.profile.fps.is_finite()) {
+            return Err(anyhow!("fps must be positive, got {}", self.profile.fps));
+        }
+        if self.profile.crf > 51 {
+            return Err(anyhow!("crf must be at most 51, got {}", self.profile.crf));
+        }
+        if self.profile.queue_capacity == 0 {
+            return Err(anyhow!("queue capacity must be at least 1"));
+        }
+        for app_override in &self.profile.app_overrides {
+            app_override
+                .validate()
+                .with_context(|| format!("invalid override of {}", app_override.app))?;
+        }
+        self.profile
+            .naming
+            .validate()
+            .context("invalid output naming")?;
+        if self.sinks.is_empty() && self.activity_log_dir.is_none() {
+            return Err(anyhow!("a recording needs a sink or an activity log"));
+        }
+        Ok(RecordingEngine {
+            targets: self.targets,
+            profile: Arc::new(self.profile),
+            sinks: self.sinks,
+            activity_log_dir: self.activity_log_dir,
+            output_dirs: self.output_dirs,
+            events: Events(Arc::new(self.subscribers)),
+        })
+    }
+}
+
+/// Records screens into sinks: captures the target monitors at the profile's fps, drops
+/// frames that didn't change or show a blocked app, and hands the rest to the sinks.
+///
+/// ```no_run
+/// use screenpipe_core::{RecordingEngine, RecordingProfile, Targets};
+///
+/// # async fn record() -> anyhow::Result<()> {
+/// let engine = RecordingEngine::builder()
+///     .targets(Targets::AllMonitors)
+///     .profile(RecordingProfile { fps: 0.5, ..Default::default() })
+///     .video_output("recordings")
+///     .activity_log("recordings")
+///     .subscribe(|event| println!("{:?}", event))
+///     .build()?;
+/// let recording = engine.start().await?;
+/// // ...
+/// recording.stop();
+/// recording.join().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordingEngine {
+    targets: Targets,
+    profile: Arc<RecordingProfile>,
+    sinks: Vec<SinkFactory>,
+    activity_log_dir: Option<PathBuf>,
+    /// Directories of the video output and activity log, whose older recordings are renamed
+    /// to UTC names when the first recording starts.
+    output_dirs: Vec<PathBuf>,
+    events: Events,
+}
+
+impl RecordingEngine {
+    pub fn builder() -> RecordingEngineBuilder {
+        RecordingEngineBuilder::default()
+    }
+
+    pub fn profile(&self) -> &RecordingProfile {
+        &self.profile
+    }
+
+    /// The connected monitors the targets select.
+    pub async fn monitors(&self) -> Vec<SafeMonitor> {
+        let monitors = list_monitors().await;
+        match &self.targets {
+            Targets::AllMonitors => monitors,
+            Targets::Monitors(ids) => monitors
+                .into_iter()
+                .filter(|monitor| ids.contains(&monitor.id()))
+                .collect(),
+        }
+    }
+
+    /// Starts recording every target monitor on the current tokio runtime, until
+    /// [`RecordingHandle::stop`].
+    pub async fn start(&self) -> Result<RecordingHandle> {
+        let monitors = self.monitors().await;
+        if monitors.is_empty() {
+            return Err(anyhow!("no monitor to record"));
+        }
+        for dir in &self.output_dirs {
+            if let Err(e) = upgrade_recording_names(dir) {
+                warn!("Failed to rename recordings in {}: {}", dir.display(), e);
+            }
+        }
+
+        // shared by the segments of every monitor until stopped, for `{session_id}`
+        let session_id = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let (stop_tx, _) = broadcast::channel(1);
+        let (system_tx, _) = broadcast::channel(16);
+        let controls = Arc::new(RecordingControls {
+            indicator_visible: AtomicBool::new(self.profile.show_indicator),
+            paused: AtomicBool::new(self.profile.start_paused),
+        });
+        let mut tasks = Vec::new();
+        let mut monitor_ids = Vec::new();
+        let mut pipelines = Vec::new();
+        for monitor in monitors {
+            let monitor_id = monitor.id();
+            let sinks = self.sinks.iter().map(|factory| factory(&monitor)).collect();
+            let pipeline = Arc::new(Pipeline::new(
+                monitor_id,
+                self.profile.queue_capacity,
+                self.profile.backpressure,
+            ));
+            pipelines.push(pipeline.clone());
+            let recorder = Recorder::new(
+                monitor,
+                self.profile.clone(),
+                sinks,
+                pipeline,
+                self.activity_log_dir.clone(),
+                session_id.clone(),
+                controls.clone(),
+                self.events.clone(),
+            );
+            let stop_rx = stop_tx.subscribe(); // Each recorder gets a subscriber
+            let system_rx = system_tx.subscribe();
+            let events = self.events.clone();
+            tasks.push(tokio::spawn(async move {
+                match recorder.run(stop_rx, system_rx).await {
+                    Ok(_) => info!("Recording finished successfully for monitor {}", monitor_id),
+                    Err(e) => {
+                        error!("Recording failed for monitor {}: {}", monitor_id, e);
+                        events.emit(RecordingEvent::Failed {
+                            monitor_id,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }));
+            monitor_ids.push(monitor_id);
+        }
+
+        // subscribed first, so that no recorder misses an event
+        let system_events = spawn_watcher(system_tx, self.profile.pause_when_locked);
+
+        Ok(RecordingHandle {
+            stop_tx,
+            system_events,
+            tasks,
+            monitor_ids,
+            pipelines,
+            controls,
+        })
+    }
+}
+
+/// A running recording, which keeps going when dropped until [`Self::stop`] is called.
+pub struct RecordingHandle {
+    stop_tx: broadcast::Sender<()>,
+    /// Watches sleep and lock changes for the recorders.
+    system_events: JoinHandle<()>,
+    tasks: Vec<JoinHandle<()>>,
+    monitor_ids: Vec<u32>,
+    pipelines: Vec<Arc<Pipeline>>,
+    controls: Arc<RecordingControls>,
+}
+
+impl RecordingHandle {
+    /// Monitors being recorded.
+    pub fn monitor_ids(&self) -> &[u32] {
+        &self.monitor_ids
+    }
+
+    /// How many frames each monitor has queued between capture, the diff and the sinks, and
+    /// how many were lost so far, dropped for room or after a failed write.
+    pub fn pipeline_stats(&self) -> Vec<PipelineStats> {
+        self.pipelines
+            .iter()
+            .map(|pipeline| pipeline.stats())
+            .collect()
+    }
+
+    /// Whether the recording indicator should be shown on the recorded monitors. Drawing it
+    /// is up to the app, as the engine has no windows.
+    pub fn indicator_visible(&self) -> bool {
+        self.controls.indicator_visible.load(Ordering::Relaxed)
+    }
+
+    /// Shows or hides the recording indicator, noted in the activity logs from the next tick.
+    pub fn set_indicator_visible(&self, visible: bool) {
+        self.controls
+            .indicator_visible
+            .store(visible, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.controls.paused.load(Ordering::Relaxed)
+    }
+
+    /// Stops capturing, and logging the activity, from the next tick until resumed. The
+    /// videos stay open, a pause only leaves out time.
+    pub fn set_paused(&self, paused: bool) {
+        self.controls.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Asks every monitor's recorder to finish, its sinks are closed before it returns.
+    pub fn stop(&self) {
+        let _ = self.stop_tx.send(());
+        self.system_events.abort();
+    }
+
+    /// Waits for the recorders to return, after [`Self::stop`] or an error.
+    pub async fn join(self) {
+        for task in self.tasks {
+            let _ = task.await;
+        }
+        self.system_events.abort();
+    }
+}
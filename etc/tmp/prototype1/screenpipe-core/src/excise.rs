@@ -0,0 +1,191 @@
+use crate::activity::ActivityLog;
+use crate::encode::trim_video;
+use crate::session::{read_frame_times, read_sessions};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Redaction markers, appended as JSONL in the recordings directory.
+pub const REDACTIONS_FILE: &str = "redactions.jsonl";
+
+/// What [`excise_recordings`] removed, logged to [`REDACTIONS_FILE`]: file names and counts,
+/// nothing of what was recorded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Redaction {
+    pub time: DateTime<Utc>,
+    /// Everything recorded from then on was removed.
+    pub start_time: DateTime<Utc>,
+    /// Files deleted, their recording having started in the range.
+    pub removed: Vec<String>,
+    /// Videos whose tail was cut.
+    pub trimmed: Vec<String>,
+    /// Videos recorded in the range that couldn't be trimmed, from before the capture time of
+    /// their frames was written.
+    pub untrimmed: Vec<String>,
+    /// Activity blocks removed or cut short.
+    pub activity_blocks: usize,
+    /// Why the excision stopped partway, what it removed until then listed above.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Replaces the content of `path` through a temporary file renamed over it, so a crash leaves
+/// either the old content or the new one.
+pub fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file =
+        File::create(&tmp).with_context(|| format!("Failed to create {}", tmp.display()))?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Drops the blocks of an activity log from `start_time` on and ends the one going on then.
+fn excise_activity(path: &Path, start_time: DateTime<Utc>) -> Result<usize> {
+    let content = std::fs::read_to_string(path)?;
+    let mut kept = Vec::new();
+    let mut excised = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut log: ActivityLog = serde_json::from_str(line)
+            .with_context(|| format!("Invalid activity log {}", path.display()))?;
+        if log.start_time >= start_time {
+            excised += 1;
+            continue;
+        }
+        if log.end_time > start_time {
+            log.end_time = start_time;
+            excised += 1;
+        }
+        kept.push(serde_json::to_string(&log)?);
+    }
+    if excised > 0 {
+        let mut content = kept.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        write_atomically(path, content.as_bytes())?;
+    }
+    Ok(excised)
+}
+
+fn remove(path: &Path, redaction: &mut Redaction) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        redaction.removed.push(file_name(path));
+    }
+    Ok(())
+}
+
+/// Removes everything recorded in `dir` from `start_time` on: recordings started since are
+/// deleted, the videos and activity logs of the earlier ones are cut at `start_time`, the
+/// videos re-encoded at the CRF they were recorded at, `crf` for those from before it was
+/// noted. The recordings must be stopped first, a video being written
+/// can't be trimmed. The redaction is appended to [`REDACTIONS_FILE`], also when a step fails,
+/// with the error and what was removed before it.
+pub async fn excise_recordings(
+    dir: &Path,
+    start_time: DateTime<Utc>,
+    crf: u8,
+) -> Result<Redaction> {
+    let mut redaction = Redaction {
+        time: Utc::now(),
+        start_time,
+        ..Default::default()
+    };
+    let result = excise_into(dir, start_time, crf, &mut redaction).await;
+    if let Err(e) = &result {
+        redaction.error = Some(format!("{:#}", e));
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(REDACTIONS_FILE))?;
+    writeln!(file, "{}", serde_json::to_string(&redaction)?)?;
+    result?;
+    info!(
+        "Excised recordings since {}: {} files removed, {} videos trimmed, {} activity blocks",
+        start_time,
+        redaction.removed.len(),
+        redaction.trimmed.len(),
+        redaction.activity_blocks
+    );
+    Ok(redaction)
+}
+
+/// Does what [`excise_recordings`] does, noting each removal into `redaction` as it's made.
+async fn excise_into(
+    dir: &Path,
+    start_time: DateTime<Utc>,
+    crf: u8,
+    redaction: &mut Redaction,
+) -> Result<()> {
+    for session in read_sessions(dir)? {
+        let video = dir.join(session.video_path());
+        let frames = dir.join(session.sidecar_path("frames"));
+        let activity = dir.join(session.log_path());
+
+        if session.started_at >= start_time {
+            if activity.exists() {
+                redaction.activity_blocks += excise_activity(&activity, start_time)?;
+            }
+            for path in [&video, &frames, &activity] {
+                remove(path, redaction)?;
+            }
+            remove(&dir.join(session.sidecar_path("session.json")), redaction)?;
+            continue;
+        }
+
+        if activity.exists() {
+            redaction.activity_blocks += excise_activity(&activity, start_time)?;
+        }
+        if !video.exists() {
+            continue;
+        }
+        if !frames.exists() {
+            let modified = std::fs::metadata(&video)?.modified()?;
+            if DateTime::<Utc>::from(modified) >= start_time {
+                warn!(
+                    "{} has no frame times, it can't be trimmed",
+                    video.display()
+                );
+                redaction.untrimmed.push(file_name(&video));
+            }
+            continue;
+        }
+        let times = read_frame_times(&frames)?;
+        let kept = times.iter().filter(|time| **time < start_time).count();
+        if kept == times.len() {
+            continue;
+        }
+        if kept == 0 {
+            remove(&video, redaction)?;
+            remove(&frames, redaction)?;
+            continue;
+        }
+        trim_video(&video, kept, session.crf.unwrap_or(crf)).await?;
+        let mut content = times[..kept]
+            .iter()
+            .map(|time| time.to_rfc3339())
+            .collect::<Vec<_>>()
+            .join("\n");
+        content.push('\n');
+        write_atomically(&frames, content.as_bytes())?;
+        redaction.trimmed.push(file_name(&video));
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Screen recorder behind the prototype: captures monitors, drops unchanged frames and those
+//! of blocked apps, and hands the rest to sinks such as the ffmpeg [`VideoFileSink`], while
+//! logging the focused app and window.
+//!
+//! Recording is set up with [`RecordingEngine::builder`], see [`RecordingEngine`].
+
+mod activity;
+mod blocklist;
+mod capture;
+mod diff;
+mod encode;
+mod engine;
+mod excise;
+mod naming;
+mod pipeline;
+mod private_browsing;
+mod replay;
+mod session;
+mod sink;
+mod snapshot;
+mod system_events;
+mod virtual_desktop;
+
+pub use activity::{ActivityLog, ActivityMonitor, DEFAULT_BLOCKED_APPS, DEFAULT_BLOCKED_TITLES};
+pub use blocklist::{normalize_for_matching, Blocklist, BlocklistMatching, FUZZY_MIN_CHARS};
+pub use capture::{get_monitor_by_id, list_monitors, MonitorData, SafeMonitor};
+pub use diff::{frame_diff, DiffMetric, LAB_JND};
+pub use encode::{find_ffmpeg_path, set_ffmpeg_path};
+pub use engine::{
+    AppOverride, EventSubscriber, RecordingEngine, RecordingEngineBuilder, RecordingEvent,
+    RecordingHandle, RecordingProfile, SegmentEncoding, Targets,
+};
+pub use excise::{excise_recordings, write_atomically, Redaction, REDACTIONS_FILE};
+pub use naming::{
+    hostname, NamingTemplate, NamingValues, OutputNaming, DEFAULT_EXPORT_TEMPLATE,
+    DEFAULT_LOG_TEMPLATE, DEFAULT_SEGMENT_TEMPLATE, OPEN_END,
+};
+pub use pipeline::{BackpressurePolicy, PipelineStats};
+pub use private_browsing::{BrowserHandler, PrivateBrowsingDetector, BROWSERS};
+pub use replay::{evaluate_segment, DiffEvaluation, FrameDecision, MetricReport, SegmentReplay};
+pub use session::{upgrade_recording_names, RecordingSession, SessionTimeZone, UTC_NAMES_MARKER};
+pub use sink::{recording_file_name, FrameSink, SinkFactory, VideoFileSink};
+pub use snapshot::{
+    find_snapshot, render_snapshot_diff, snapshot_diff, tile_diff, ChangedTile, Snapshot,
+    SnapshotDiff, SnapshotDiffOptions, TileDiff,
+};
+pub use system_events::SuspendReason;
+pub use virtual_desktop::{VirtualDesktop, VirtualDesktopDetector};
@@ -0,0 +1,448 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Names of the videos, `{start}` in UTC as in [`crate::recording_file_name`].
+pub const DEFAULT_SEGMENT_TEMPLATE: &str = "monitor_{id}_{start}.mp4";
+pub const DEFAULT_LOG_TEMPLATE: &str = "monitor_{id}_{start}.jsonl";
+pub const DEFAULT_EXPORT_TEMPLATE: &str = "screenshots/monitor_{id}_{start}.png";
+
+/// What `{end}` is while the segment is recorded, its files renamed once it ends.
+pub const OPEN_END: &str = "recording";
+
+/// Characters Windows doesn't allow in file names, refused everywhere so that recordings can
+/// be copied between machines.
+const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\\'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Hostname,
+    Date,
+    Time,
+    SessionId,
+    MonitorId,
+    Start,
+    End,
+}
+
+impl Field {
+    const NAMES: &'static [(&'static str, Field)] = &[
+        ("hostname", Field::Hostname),
+        ("date", Field::Date),
+        ("time", Field::Time),
+        ("session_id", Field::SessionId),
+        ("id", Field::MonitorId),
+        ("start", Field::Start),
+        ("end", Field::End),
+    ];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, field)| *field)
+    }
+
+    /// The strftime format of the times, when none is given after a colon.
+    fn default_format(self) -> Option<&'static str> {
+        match self {
+            Field::Date => Some("%Y-%m-%d"),
+            Field::Time => Some("%H-%M-%S"),
+            Field::Start | Field::End => Some("%Y-%m-%dT%H-%M-%SZ"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(Field, Option<String>),
+}
+
+/// Values of the placeholders of a [`NamingTemplate`].
+#[derive(Debug, Clone)]
+pub struct NamingValues<'a> {
+    pub hostname: &'a str,
+    /// Id of the recording, from start to stop, every monitor and segment of it sharing it.
+    pub session_id: &'a str,
+    pub monitor_id: u32,
+    /// Start of the segment, or time of the export. `{date}` and `{time}` are its date and
+    /// time.
+    pub start: DateTime<Utc>,
+    /// End of the segment, `None` while it's recorded.
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl NamingValues<'static> {
+    /// Values standing for any, to check what templates give.
+    fn sample() -> NamingValues<'static> {
+        let start = Utc.with_ymd_and_hms(2024, 11, 28, 1, 2, 3).unwrap();
+        NamingValues {
+            hostname: "host",
+            session_id: "20241128T010000Z",
+            monitor_id: 1,
+            start,
+            end: Some(start + Duration::hours(1)),
+        }
+    }
+}
+
+/// The relative path of an output file, with placeholders: `{hostname}`, `{session_id}`,
+/// `{id}` (of the monitor), `{date}`, `{time}`, `{start}` and `{end}`. The times are in UTC
+/// and take a strftime format after a colon, e.g. `{start:%H%M%S}`. Directories are
+/// separated by `/`.
+///
+/// ```
+/// use screenpipe_core::NamingTemplate;
+///
+/// let template: NamingTemplate = "{session_id}/monitor_{id}_{start}–{end}.mp4".parse().unwrap();
+/// assert_eq!(template.as_str(), "{session_id}/monitor_{id}_{start}–{end}.mp4");
+/// assert!("monitor_{id}_{start:%Y/%m}.mp4".parse::<NamingTemplate>().is_ok());
+/// assert!("monitor_{monitor}.mp4".parse::<NamingTemplate>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NamingTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl NamingTemplate {
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    fn has(&self, field: Field) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Field(f, _) if *f == field))
+    }
+
+    /// The path the template gives for `values`, relative to the output directory. Host names
+    /// and session ids are kept to letters, digits, `-`, `_` and `.`.
+    pub fn render(&self, values: &NamingValues) -> PathBuf {
+        self.render_str(values)
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .collect()
+    }
+
+    fn render_str(&self, values: &NamingValues) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => rendered.push_str(literal),
+                Part::Field(field, format) => {
+                    let format = format.as_deref().or(field.default_format());
+                    let time =
+                        |at: DateTime<Utc>| at.format(format.unwrap_or_default()).to_string();
+                    rendered.push_str(&match field {
+                        Field::Hostname => sanitize(values.hostname),
+                        Field::SessionId => sanitize(values.session_id),
+                        Field::MonitorId => values.monitor_id.to_string(),
+                        Field::Date | Field::Time | Field::Start => time(values.start),
+                        Field::End => values.end.map_or(OPEN_END.to_string(), time),
+                    });
+                }
+            }
+        }
+        rendered
+    }
+
+    /// Refuses paths that aren't relative, or go up, or have characters not allowed in file
+    /// names, once the placeholders are replaced. `{end}` is only allowed in the file name,
+    /// the directories of a segment being created as it starts.
+    fn check_paths(&self) -> Result<()> {
+        if self.source.starts_with('/') {
+            bail!("must be a relative path");
+        }
+        let sample = self.render_str(&NamingValues::sample());
+        // times formatted with a `/` give directories
+        for component in sample.split('/') {
+            if component.is_empty() || component == "." || component == ".." {
+                bail!("has an empty, `.` or `..` path component");
+            }
+            if let Some(c) = component
+                .chars()
+                .find(|c| INVALID_CHARS.contains(c) || c.is_control())
+            {
+                bail!("gives {:?}, which isn't allowed in file names", c);
+            }
+        }
+        let after_end = self
+            .parts
+            .iter()
+            .skip_while(|part| !matches!(part, Part::Field(Field::End, _)));
+        for part in after_end {
+            let separates = match part {
+                Part::Literal(literal) => literal.contains('/'),
+                Part::Field(field, format) => format
+                    .as_deref()
+                    .or(field.default_format())
+                    .is_some_and(|format| format.contains('/')),
+            };
+            if separates {
+                bail!("{{end}} must be in the file name, not a directory");
+            }
+        }
+        Ok(())
+    }
+
+    fn require(&self, fields: &[Field]) -> Result<()> {
+        for field in fields {
+            if !self.has(*field) {
+                bail!("must contain {}", placeholder(*field));
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuses templates giving the same path for start times a second apart, e.g. with
+    /// `{start:%Y-%m-%d}`: segments started the same day would share their files.
+    fn require_seconds(&self) -> Result<()> {
+        let sample = NamingValues::sample();
+        let next = NamingValues {
+            start: sample.start + Duration::seconds(1),
+            ..sample.clone()
+        };
+        if self.render(&sample) == self.render(&next) {
+            bail!("must format the start time down to the second");
+        }
+        Ok(())
+    }
+
+    fn refuse(&self, fields: &[Field]) -> Result<()> {
+        for field in fields {
+            if self.has(*field) {
+                bail!("can't contain {}", placeholder(*field));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn placeholder(field: Field) -> String {
+    let name = Field::NAMES
+        .iter()
+        .find(|(_, f)| *f == field)
+        .map(|(name, _)| *name)
+        .unwrap_or_default();
+    format!("{{{}}}", name)
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl std::str::FromStr for NamingTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => bail!("unclosed {{ in {:?}", source),
+                            Some(c) => inner.push(c),
+                        }
+                    }
+                    let (name, format) = match inner.split_once(':') {
+                        Some((name, format)) => (name, Some(format)),
+                        None => (inner.as_str(), None),
+                    };
+                    let field = Field::parse(name).ok_or_else(|| {
+                        anyhow!(
+                            "unknown placeholder {{{}}} in {:?}, expected one of {}",
+                            name,
+                            source,
+                            Field::NAMES
+                                .iter()
+                                .map(|(_, field)| placeholder(*field))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    })?;
+                    if let Some(format) = format {
+                        if field.default_format().is_none() {
+                            bail!("{} in {:?} takes no format", placeholder(field), source);
+                        }
+                        if format.is_empty()
+                            || StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+                        {
+                            bail!("invalid time format {:?} in {:?}", format, source);
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field, format.map(str::to_string)));
+                }
+                '}' => bail!("unmatched }} in {:?}", source),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        let template = Self {
+            source: source.to_string(),
+            parts,
+        };
+        template
+            .check_paths()
+            .with_context(|| format!("invalid template {:?}", source))?;
+        Ok(template)
+    }
+}
+
+impl TryFrom<String> for NamingTemplate {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        source.parse()
+    }
+}
+
+impl From<NamingTemplate> for String {
+    fn from(template: NamingTemplate) -> Self {
+        template.source
+    }
+}
+
+impl std::fmt::Display for NamingTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// How the output files are named, relative to their output directory. The frame times and
+/// session file of a segment are named after its video, `.mp4` replaced by `.frames` and
+/// `.session.json`. The defaults are the names recordings always had.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputNaming {
+    /// Videos of the segments, ending in `.mp4`, with `{id}` and `{start}`.
+    pub segment: NamingTemplate,
+    /// Activity logs of the segments, with `{id}` and `{start}`.
+    pub log: NamingTemplate,
+    /// Screenshots, ending in `.png` or `.jpg`, with `{id}` and `{start}`, the time they're
+    /// taken. They aren't part of a recording, so have no `{session_id}` or `{end}`.
+    pub export: NamingTemplate,
+}
+
+impl Default for OutputNaming {
+    fn default() -> Self {
+        Self {
+            segment: DEFAULT_SEGMENT_TEMPLATE.parse().unwrap(),
+            log: DEFAULT_LOG_TEMPLATE.parse().unwrap(),
+            export: DEFAULT_EXPORT_TEMPLATE.parse().unwrap(),
+        }
+    }
+}
+
+impl OutputNaming {
+    /// Checks that every file gets a name of its own: the templates give one per monitor and
+    /// start time, which must be formatted down to the second, and the logs are named apart
+    /// from the files of the videos.
+    pub fn validate(&self) -> Result<()> {
+        let segment = &self.segment;
+        segment
+            .require(&[Field::MonitorId, Field::Start])
+            .and_then(|_| segment.require_seconds())
+            .and_then(|_| {
+                if segment.source.ends_with(".mp4") {
+                    Ok(())
+                } else {
+                    Err(anyhow!("must end in .mp4"))
+                }
+            })
+            .with_context(|| format!("invalid segment template {:?}", segment.source))?;
+
+        let sample = NamingValues::sample();
+        let video = segment.render(&sample);
+        let log = self.log.render(&sample);
+        self.log
+            .require(&[Field::MonitorId, Field::Start])
+            .and_then(|_| self.log.require_seconds())
+            .and_then(|_| {
+                let clashes = ["mp4", "frames", "session.json"]
+                    .iter()
+                    .any(|extension| sidecar_path(&video, extension) == log);
+                if clashes {
+                    Err(anyhow!("names the logs like the files of the videos"))
+                } else {
+                    Ok(())
+                }
+            })
+            .with_context(|| format!("invalid log template {:?}", self.log.source))?;
+
+        let export = &self.export;
+        export
+            .require(&[Field::MonitorId, Field::Start])
+            .and_then(|_| export.require_seconds())
+            .and_then(|_| export.refuse(&[Field::SessionId, Field::End]))
+            .and_then(|_| {
+                let source = export.source.to_lowercase();
+                if [".png", ".jpg", ".jpeg"]
+                    .iter()
+                    .any(|extension| source.ends_with(extension))
+                {
+                    Ok(())
+                } else {
+                    Err(anyhow!("must end in .png or .jpg"))
+                }
+            })
+            .with_context(|| format!("invalid export template {:?}", export.source))?;
+        Ok(())
+    }
+}
+
+/// `path` with `_{sequence}` before its extension, from the second segment starting within
+/// the same second on.
+pub(crate) fn numbered(path: PathBuf, sequence: u32) -> PathBuf {
+    if sequence == 0 {
+        return path;
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}_{}.{}", stem, sequence, extension),
+        None => format!("{}_{}", name, sequence),
+    };
+    path.with_file_name(name)
+}
+
+/// `video` with `.mp4` replaced by `.{extension}`.
+pub(crate) fn sidecar_path(video: &Path, extension: &str) -> PathBuf {
+    let name = video
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = name.strip_suffix(".mp4").unwrap_or(&name);
+    video.with_file_name(format!("{}.{}", stem, extension))
+}
+
+/// Name of this machine, for `{hostname}`.
+pub fn hostname() -> String {
+    gethostname::gethostname().to_string_lossy().into_owned()
+}
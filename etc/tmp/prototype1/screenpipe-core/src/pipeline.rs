@@ -0,0 +1,225 @@
+use anyhow::Error;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, Notify};
+
+/// What capture does when the diff or the encoding of a monitor falls behind and the queue
+/// in front of it is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    /// Drop the oldest queued frame to make room, capture keeps its pace and a slow encoder
+    /// loses frames.
+    #[default]
+    DropOldest,
+    /// Wait for room, no frame is lost and capture slows down to the pace of the slowest
+    /// stage.
+    Block,
+}
+
+/// The queues of a monitor's pipeline, see [`crate::RecordingHandle::pipeline_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PipelineStats {
+    pub monitor_id: u32,
+    /// Frames captured waiting to be compared to the last kept one.
+    pub diff_queue: usize,
+    /// Frames kept waiting to be written to the sinks.
+    pub encode_queue: usize,
+    /// Frames lost between capture and the sinks since the recording started, dropped for
+    /// room or after a failed write.
+    pub dropped_frames: u64,
+}
+
+/// A frame as captured, with the focused window and the diff threshold of its app.
+pub(crate) struct CapturedFrame {
+    pub(crate) image: Arc<DynamicImage>,
+    pub(crate) diff_threshold: f64,
+    pub(crate) app_name: Option<String>,
+    pub(crate) window_title: Option<String>,
+}
+
+/// A frame kept by the diff, to be written to the sinks.
+pub(crate) struct KeptFrame {
+    pub(crate) image: Arc<DynamicImage>,
+    pub(crate) frame_number: u64,
+    pub(crate) diff: f64,
+    pub(crate) app_name: Option<String>,
+    pub(crate) window_title: Option<String>,
+}
+
+/// What goes down a queue, in order.
+pub(crate) enum Item<F> {
+    Frame(F),
+    /// Answered once every item before it went through the last stage, never dropped.
+    Flush(oneshot::Sender<()>),
+}
+
+struct QueueState<F> {
+    items: VecDeque<Item<F>>,
+    closed: bool,
+}
+
+impl<F> QueueState<F> {
+    fn frames(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| matches!(item, Item::Frame(_)))
+            .count()
+    }
+}
+
+/// A queue between two stages, of at most `capacity` frames past which its
+/// [`BackpressurePolicy`] applies. Flushes don't count.
+pub(crate) struct FrameQueue<F> {
+    state: Mutex<QueueState<F>>,
+    capacity: usize,
+    policy: BackpressurePolicy,
+    dropped: AtomicU64,
+    /// Woken when an item is pushed or the queue closed, for the one stage popping.
+    pushed: Notify,
+    /// Woken when an item is popped, for the one stage pushing.
+    popped: Notify,
+}
+
+impl<F> FrameQueue<F> {
+    fn new(capacity: usize, policy: BackpressurePolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                items: VecDeque::new(),
+                closed: false,
+            }),
+            capacity,
+            policy,
+            dropped: AtomicU64::new(0),
+            pushed: Notify::new(),
+            popped: Notify::new(),
+        }
+    }
+
+    /// Queues a frame. When full, the oldest frame waiting is dropped or this waits for room,
+    /// as the policy says; frames pushed once closed are dropped.
+    pub(crate) async fn push_frame(&self, frame: F) {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return;
+                }
+                let frames = state.frames();
+                if frames >= self.capacity && self.policy == BackpressurePolicy::DropOldest {
+                    if let Some(oldest) = state
+                        .items
+                        .iter()
+                        .position(|item| matches!(item, Item::Frame(_)))
+                    {
+                        state.items.remove(oldest);
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                if frames < self.capacity || self.policy == BackpressurePolicy::DropOldest {
+                    state.items.push_back(Item::Frame(frame));
+                    break;
+                }
+            }
+            self.popped.notified().await;
+        }
+        self.pushed.notify_one();
+    }
+
+    /// Queues a flush, never waiting for room.
+    pub(crate) fn push_flush(&self, done: oneshot::Sender<()>) {
+        self.state
+            .lock()
+            .unwrap()
+            .items
+            .push_back(Item::Flush(done));
+        self.pushed.notify_one();
+    }
+
+    /// The next item, `None` once closed and empty.
+    pub(crate) async fn pop(&self) -> Option<Item<F>> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(item) = state.items.pop_front() {
+                    drop(state);
+                    self.popped.notify_one();
+                    return Some(item);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.pushed.notified().await;
+        }
+    }
+
+    /// Ends the queue once the items in it are popped.
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.pushed.notify_one();
+        self.popped.notify_one();
+    }
+
+    /// Frames waiting.
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().frames()
+    }
+
+    /// Counts a frame lost after it was popped.
+    pub(crate) fn note_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The stages of a monitor's recording: capture, the diff and the encoding into the sinks,
+/// joined by bounded queues so that a slow encoder doesn't hold up capture.
+pub(crate) struct Pipeline {
+    monitor_id: u32,
+    /// Captured frames, to the diff.
+    pub(crate) diff: FrameQueue<CapturedFrame>,
+    /// Kept frames, to the sinks.
+    pub(crate) encode: FrameQueue<KeptFrame>,
+    /// The frame whose write failed, for capture to write again in a new segment.
+    failed_write: Mutex<Option<(KeptFrame, Error)>>,
+}
+
+impl Pipeline {
+    pub(crate) fn new(monitor_id: u32, capacity: usize, policy: BackpressurePolicy) -> Self {
+        Self {
+            monitor_id,
+            diff: FrameQueue::new(capacity, policy),
+            encode: FrameQueue::new(capacity, policy),
+            failed_write: Mutex::new(None),
+        }
+    }
+
+    /// Waits for the frames queued so far to go through every stage, or for the stages to
+    /// be gone.
+    pub(crate) async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        self.diff.push_flush(done);
+        let _ = flushed.await;
+    }
+
+    pub(crate) fn fail_write(&self, frame: KeptFrame, error: Error) {
+        *self.failed_write.lock().unwrap() = Some((frame, error));
+    }
+
+    pub(crate) fn take_failed_write(&self) -> Option<(KeptFrame, Error)> {
+        self.failed_write.lock().unwrap().take()
+    }
+
+    pub(crate) fn stats(&self) -> PipelineStats {
+        PipelineStats {
+            monitor_id: self.monitor_id,
+            diff_queue: self.diff.len(),
+            encode_queue: self.encode.len(),
+            dropped_frames: self.diff.dropped.load(Ordering::Relaxed)
+                + self.encode.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use crate::blocklist::normalize_for_matching;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use tracing::debug;
+
+/// How a browser shows that a window is private, beyond the window title keywords.
+#[derive(Debug, Clone, Copy)]
+pub struct BrowserHandler {
+    pub name: &'static str,
+    /// Lowercased parts of the app or executable name of the browser.
+    pub process_names: &'static [&'static str],
+    /// Command line flags starting the browser in private mode.
+    pub private_flags: &'static [&'static str],
+    /// What the browser puts in the titles of private windows, in the languages it ships.
+    pub title_markers: &'static [&'static str],
+    /// Name of the macOS app whose AppleScript windows have a `mode` property, `incognito`
+    /// for private ones, as Chromium browsers do.
+    pub applescript_app: Option<&'static str>,
+}
+
+impl BrowserHandler {
+    fn handles(&self, app_name: &str, process_path: &Path) -> bool {
+        let app_name = app_name.to_lowercase();
+        let executable = process_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.process_names
+            .iter()
+            .any(|name| app_name.contains(name) || executable.contains(name))
+    }
+}
+
+pub const BROWSERS: &[BrowserHandler] = &[
+    BrowserHandler {
+        name: "Microsoft Edge",
+        process_names: &["msedge", "microsoft edge"],
+        private_flags: &["--inprivate", "-inprivate"],
+        // "[InPrivate]" isn't translated
+        title_markers: &["inprivate"],
+        applescript_app: Some("Microsoft Edge"),
+    },
+    BrowserHandler {
+        name: "Brave",
+        process_names: &["brave"],
+        private_flags: &["--incognito", "--tor"],
+        title_markers: &[
+            "private window",
+            "プライベートウィンドウ",
+            "プライベート ウィンドウ",
+        ],
+        applescript_app: Some("Brave Browser"),
+    },
+    BrowserHandler {
+        name: "Google Chrome",
+        process_names: &["chrome", "chromium"],
+        private_flags: &["--incognito", "-incognito"],
+        title_markers: &[
+            "incognito",
+            "シークレット",
+            "navigation privée",
+            "inkognito",
+            "navegación de incógnito",
+            "无痕",
+        ],
+        applescript_app: Some("Google Chrome"),
+    },
+    BrowserHandler {
+        name: "Firefox",
+        process_names: &["firefox"],
+        private_flags: &[
+            "-private",
+            "--private",
+            "-private-window",
+            "--private-window",
+        ],
+        title_markers: &[
+            "private browsing",
+            "プライベートブラウジング",
+            "navigation privée",
+            "privater modus",
+            "navegación privada",
+            "navigazione anonima",
+            "navegação privativa",
+            "隐私浏览",
+        ],
+        applescript_app: None,
+    },
+    BrowserHandler {
+        name: "Opera",
+        process_names: &["opera"],
+        private_flags: &["--private", "-private"],
+        title_markers: &["private browsing", "プライベートブラウジング"],
+        applescript_app: None,
+    },
+];
+
+/// Past this many windows or processes seen, the caches are cleared.
+const MAX_CACHED: usize = 256;
+
+/// Detects private browsing windows from what each browser exposes: the flags its process
+/// was started with, the markers of its localized titles, and on macOS its AppleScript
+/// window mode. Process and window lookups are cached, as neither changes for a window,
+/// but only once they succeed: a lookup that fails counts the window as private.
+///
+/// Windows and Linux have no per-window mode to query, neither Win32 nor X11/Wayland
+/// telling a private window from its class, so a private window opened from a normal
+/// browser process is only detected there from the markers of its title.
+#[derive(Default)]
+pub struct PrivateBrowsingDetector {
+    started_private: HashMap<u64, bool>,
+    private_windows: HashMap<(u64, String), bool>,
+}
+
+impl PrivateBrowsingDetector {
+    /// Whether the focused window is a private browsing window, `None` when it isn't a
+    /// known browser.
+    pub fn is_private(
+        &mut self,
+        app_name: &str,
+        title: &str,
+        process_id: u64,
+        process_path: &Path,
+        window_id: &str,
+    ) -> Option<bool> {
+        let browser = BROWSERS
+            .iter()
+            .find(|browser| browser.handles(app_name, process_path))?;
+
+        // half-width "ｼｰｸﾚｯﾄ" or a full-width space in "プライベート　ウィンドウ" still match
+        let title = normalize_for_matching(title);
+        if browser
+            .title_markers
+            .iter()
+            .any(|marker| title.contains(&normalize_for_matching(marker)))
+        {
+            debug!("{} private window detected from its title", browser.name);
+            return Some(true);
+        }
+
+        if self.started_private.len() > MAX_CACHED {
+            self.started_private.clear();
+        }
+        let started_private = match self.started_private.get(&process_id) {
+            Some(started_private) => Some(*started_private),
+            None => process_args(process_id).map(|args| {
+                let started_private = args.iter().any(|arg| {
+                    let flag = arg.split('=').next().unwrap_or_default().to_lowercase();
+                    browser.private_flags.contains(&flag.as_str())
+                });
+                self.started_private.insert(process_id, started_private);
+                started_private
+            }),
+        };
+        match started_private {
+            Some(true) => {
+                debug!("{} started in private mode", browser.name);
+                return Some(true);
+            }
+            Some(false) => {}
+            None if PROCESS_ARGS_SUPPORTED => {
+                debug!(
+                    "{} process arguments unknown, treated as private",
+                    browser.name
+                );
+                return Some(true);
+            }
+            None => {}
+        }
+
+        if let Some(app) = browser
+            .applescript_app
+            .filter(|_| cfg!(target_os = "macos"))
+        {
+            if self.private_windows.len() > MAX_CACHED {
+                self.private_windows.clear();
+            }
+            let key = (process_id, window_id.to_string());
+            let private = match self.private_windows.get(&key) {
+                Some(private) => *private,
+                None => match front_window_mode(app) {
+                    Some(mode) => {
+                        let private = mode == "incognito";
+                        self.private_windows.insert(key, private);
+                        private
+                    }
+                    // e.g. the automation permission not granted yet, asked again next frame
+                    None => {
+                        debug!("{} window mode unknown, treated as private", browser.name);
+                        return Some(true);
+                    }
+                },
+            };
+            if private {
+                debug!("{} private window detected from its mode", browser.name);
+                return Some(true);
+            }
+        }
+
+        Some(false)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_args(process_id: u64) -> Option<Vec<String>> {
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", process_id)).ok()?;
+    Some(
+        cmdline
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn process_args(process_id: u64) -> Option<Vec<String>> {
+    let output = Command::new("ps")
+        .args(["-ww", "-o", "args=", "-p", &process_id.to_string()])
+        .output()
+        .ok()?;
+    output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+#[cfg(windows)]
+fn process_args(process_id: u64) -> Option<Vec<String>> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // the command line of another process is only exposed through WMI
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine",
+                process_id
+            ),
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+/// Whether [`process_args`] can tell the arguments of a process on this platform, its
+/// failures only counting windows as private where it can.
+const PROCESS_ARGS_SUPPORTED: bool = cfg!(any(target_os = "linux", target_os = "macos", windows));
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn process_args(_process_id: u64) -> Option<Vec<String>> {
+    None
+}
+
+/// `mode` of the front window of a Chromium browser on macOS: `normal` or `incognito`.
+fn front_window_mode(app: &str) -> Option<String> {
+    let output = Command::new("osascript")
+        .args([
+            "-e",
+            &format!("tell application \"{}\" to get mode of front window", app),
+        ])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,214 @@
+use crate::diff::{frame_diff, DiffMetric};
+use crate::encode::find_ffmpeg_path;
+use anyhow::{anyhow, Context, Result};
+use image::{DynamicImage, RgbImage};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncReadExt, BufReader};
+use tokio::process::{Child, ChildStdout, Command};
+use tracing::debug;
+
+/// Decodes the frames of a recorded segment in order, to replay them offline.
+pub struct SegmentReplay {
+    ffmpeg: Child,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl SegmentReplay {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::spawn(path.as_ref(), &[])
+    }
+
+    /// Decodes the frame `index` of the segment at `path`, counting from 0.
+    pub async fn frame_at(path: impl AsRef<Path>, index: usize) -> Result<DynamicImage> {
+        let select = format!("select=eq(n\\,{})", index);
+        let mut replay = Self::spawn(path.as_ref(), &["-vf", &select, "-frames:v", "1"])?;
+        let frame = replay.next_frame().await?;
+        replay.close().await?;
+        frame.with_context(|| {
+            format!(
+                "{} has no frame {}, or is still being recorded",
+                path.as_ref().display(),
+                index
+            )
+        })
+    }
+
+    fn spawn(path: &Path, output_args: &[&str]) -> Result<Self> {
+        let ffmpeg_path = find_ffmpeg_path().context("FFmpeg not found")?;
+        let mut command = Command::new(ffmpeg_path);
+        // PPM frames carry their size, so they can be split off the pipe without probing
+        command
+            .args(["-loglevel", "error", "-i"])
+            .arg(path)
+            .args(output_args)
+            .args(["-f", "image2pipe", "-c:v", "ppm", "-"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        debug!("FFmpeg command: {:?}", command);
+
+        let mut ffmpeg = command.spawn().context("Failed to spawn ffmpeg")?;
+        let stdout = ffmpeg
+            .stdout
+            .take()
+            .context("Failed to get ffmpeg stdout")?;
+        Ok(Self {
+            ffmpeg,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Next PPM header field, `None` at the end of the stream.
+    async fn read_field(&mut self) -> Result<Option<String>> {
+        let mut field = String::new();
+        loop {
+            let byte = match self.stdout.read_u8().await {
+                Ok(byte) => byte,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return Ok((!field.is_empty()).then_some(field));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if byte.is_ascii_whitespace() {
+                if !field.is_empty() {
+                    return Ok(Some(field));
+                }
+            } else {
+                field.push(byte as char);
+            }
+        }
+    }
+
+    /// The next frame, `None` once the segment has been replayed.
+    pub async fn next_frame(&mut self) -> Result<Option<DynamicImage>> {
+        let Some(magic) = self.read_field().await? else {
+            return Ok(None);
+        };
+        if magic != "P6" {
+            return Err(anyhow!("Unexpected frame format from ffmpeg: {}", magic));
+        }
+        let mut header = [0u32; 3];
+        for value in &mut header {
+            *value = self
+                .read_field()
+                .await?
+                .context("Truncated frame header")?
+                .parse()
+                .context("Invalid frame header")?;
+        }
+        let [width, height, max_value] = header;
+        if max_value != 255 {
+            return Err(anyhow!("Unsupported frame depth: {}", max_value));
+        }
+
+        let mut pixels = vec![0; width as usize * height as usize * 3];
+        self.stdout
+            .read_exact(&mut pixels)
+            .await
+            .context("Truncated frame")?;
+        let image = RgbImage::from_raw(width, height, pixels).context("Invalid frame size")?;
+        Ok(Some(DynamicImage::ImageRgb8(image)))
+    }
+
+    pub async fn close(mut self) -> Result<()> {
+        let status = self.ffmpeg.wait().await?;
+        if !status.success() {
+            return Err(anyhow!("FFmpeg exited with {}", status));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameDecision {
+    pub frame_number: u64,
+    /// Difference to the last kept frame, `None` for the first frame or when comparing failed.
+    pub diff: Option<f64>,
+    pub kept: bool,
+}
+
+/// What the recorder would have kept of a replay with one metric.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricReport {
+    pub metric: DiffMetric,
+    pub threshold: f64,
+    pub kept: u64,
+    pub dropped: u64,
+    pub frames: Vec<FrameDecision>,
+}
+
+struct MetricState {
+    last_kept: Option<DynamicImage>,
+    report: MetricReport,
+}
+
+/// Replays frames through several metrics at once, each dropping frames against the last one
+/// it kept as the recorder does, to compare what they keep before picking one.
+pub struct DiffEvaluation {
+    metrics: Vec<MetricState>,
+}
+
+impl DiffEvaluation {
+    pub fn new(metrics: &[DiffMetric], threshold: f64) -> Self {
+        Self {
+            metrics: metrics
+                .iter()
+                .map(|metric| MetricState {
+                    last_kept: None,
+                    report: MetricReport {
+                        metric: *metric,
+                        threshold,
+                        kept: 0,
+                        dropped: 0,
+                        frames: Vec::new(),
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    pub fn push(&mut self, frame_number: u64, image: &DynamicImage) {
+        for state in &mut self.metrics {
+            let report = &mut state.report;
+            let diff = state
+                .last_kept
+                .as_ref()
+                .and_then(|last_kept| frame_diff(report.metric, last_kept, image).ok());
+            // the first frame and those that fail to compare are kept, as when recording
+            let kept = diff.map_or(true, |diff| diff >= report.threshold);
+            if kept {
+                report.kept += 1;
+                state.last_kept = Some(image.clone());
+            } else {
+                report.dropped += 1;
+            }
+            report.frames.push(FrameDecision {
+                frame_number,
+                diff,
+                kept,
+            });
+        }
+    }
+
+    pub fn reports(self) -> Vec<MetricReport> {
+        self.metrics.into_iter().map(|state| state.report).collect()
+    }
+}
+
+/// Replays the segment at `path` through `metrics`.
+pub async fn evaluate_segment(
+    path: impl AsRef<Path>,
+    metrics: &[DiffMetric],
+    threshold: f64,
+) -> Result<Vec<MetricReport>> {
+    let mut replay = SegmentReplay::open(path).await?;
+    let mut evaluation = DiffEvaluation::new(metrics, threshold);
+    let mut frame_number = 0;
+    while let Some(image) = replay.next_frame().await? {
+        evaluation.push(frame_number, &image);
+        frame_number += 1;
+    }
+    replay.close().await?;
+    Ok(evaluation.reports())
+}
@@ -0,0 +1,336 @@
+use crate::naming::{hostname, numbered, sidecar_path, NamingValues, OutputNaming};
+use crate::sink::recording_file_name;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, FixedOffset, Local, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Format of the start time in the names of recordings before they were named in UTC, in
+/// the local time of the machine.
+const LEGACY_NAME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+/// Written in a directory once its recordings were renamed to UTC names.
+pub const UTC_NAMES_MARKER: &str = ".utc_names";
+
+/// Time zone a recording was made in. Times are stored in UTC, this is only to show them as
+/// the wall clock of where they were recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTimeZone {
+    /// IANA name, e.g. `Europe/Paris`, when the system gives one. Its rules give the offset
+    /// of any time, DST changes included.
+    pub name: Option<String>,
+    /// Offset from UTC, in seconds east, when the recording started, for the zones without a
+    /// known name.
+    pub utc_offset_secs: i32,
+}
+
+impl SessionTimeZone {
+    /// The time zone of this machine at `at`.
+    pub fn local_at(at: DateTime<Utc>) -> Self {
+        Self {
+            name: iana_time_zone::get_timezone().ok(),
+            utc_offset_secs: at.with_timezone(&Local).offset().fix().local_minus_utc(),
+        }
+    }
+
+    /// `at` on the wall clock of the recording, with the offset its zone had at `at`. Zones
+    /// without a known name keep the offset the recording started with.
+    pub fn wall_clock(&self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        if let Some(zone) = self
+            .name
+            .as_deref()
+            .and_then(|name| name.parse::<Tz>().ok())
+        {
+            return at.with_timezone(&zone).fixed_offset();
+        }
+        let offset = FixedOffset::east_opt(self.utc_offset_secs).unwrap_or_else(|| Utc.fix());
+        at.with_timezone(&offset)
+    }
+
+    /// `at` as shown to the user, on the wall clock of the recording with its offset and
+    /// zone, e.g. `2024-03-31 03:30:00 +02:00 (Europe/Paris)`.
+    pub fn display(&self, at: DateTime<Utc>) -> String {
+        let wall_clock = self.wall_clock(at).format("%Y-%m-%d %H:%M:%S %:z");
+        match &self.name {
+            Some(name) => format!("{} ({})", wall_clock, name),
+            None => wall_clock.to_string(),
+        }
+    }
+}
+
+/// Metadata of a segment of the recording of a monitor, written as `.session.json` next to
+/// its video and activity log, named after the video. Recordings go on in a new segment after
+/// a sleep or lock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingSession {
+    pub monitor_id: u32,
+    pub started_at: DateTime<Utc>,
+    pub time_zone: SessionTimeZone,
+    /// Id of the recording the segment is part of, shared by every monitor and segment from
+    /// start to stop. Empty for recordings from before.
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    /// `None` while the segment is recorded, or when it was cut off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    /// How the files of the segment are named, for them to be found again whatever the
+    /// naming is now.
+    #[serde(default)]
+    pub naming: OutputNaming,
+    /// Number of the segment among those of the monitor whose names would be the same, having
+    /// started within the same second, e.g. on a wake. From 1 on, its files are named with
+    /// `_{sequence}` before their extension.
+    #[serde(default, skip_serializing_if = "is_first")]
+    pub sequence: u32,
+    /// CRF the video was encoded at, after the override of the app focused as it started.
+    /// `None` for recordings from before, encoded at the profile's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crf: Option<u8>,
+    /// Fraction of the monitor's resolution the frames were downscaled to, `None` at full.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+}
+
+fn is_first(sequence: &u32) -> bool {
+    *sequence == 0
+}
+
+impl RecordingSession {
+    /// A segment of `monitor_id` of the recording `session_id` starting now, in this
+    /// machine's time zone.
+    pub fn start(monitor_id: u32, session_id: &str, naming: &OutputNaming) -> Self {
+        let started_at = Utc::now();
+        Self {
+            monitor_id,
+            started_at,
+            time_zone: SessionTimeZone::local_at(started_at),
+            session_id: session_id.to_string(),
+            hostname: hostname(),
+            ended_at: None,
+            naming: naming.clone(),
+            sequence: 0,
+            crf: None,
+            scale: None,
+        }
+    }
+
+    fn naming_values(&self) -> NamingValues {
+        NamingValues {
+            hostname: &self.hostname,
+            session_id: &self.session_id,
+            monitor_id: self.monitor_id,
+            start: self.started_at,
+            end: self.ended_at,
+        }
+    }
+
+    /// Path of the video, relative to the output directory.
+    pub fn video_path(&self) -> PathBuf {
+        numbered(
+            self.naming.segment.render(&self.naming_values()),
+            self.sequence,
+        )
+    }
+
+    /// Path of the file named after the video with `extension`, `frames` or `session.json`.
+    pub fn sidecar_path(&self, extension: &str) -> PathBuf {
+        sidecar_path(&self.video_path(), extension)
+    }
+
+    /// Path of the activity log, relative to its directory.
+    pub fn log_path(&self) -> PathBuf {
+        numbered(self.naming.log.render(&self.naming_values()), self.sequence)
+    }
+
+    /// The segment as it was while recorded.
+    fn recorded(&self) -> Self {
+        Self {
+            ended_at: None,
+            ..self.clone()
+        }
+    }
+
+    /// Renames the file of the ended segment `path` gives in `dir` from its name while it was
+    /// recorded, which differs with `{end}`, refusing to replace another. Returns its path.
+    pub(crate) fn rename_ended(
+        &self,
+        dir: &Path,
+        path: impl Fn(&Self) -> PathBuf,
+    ) -> Result<PathBuf> {
+        let recorded = dir.join(path(&self.recorded()));
+        let ended = dir.join(path(self));
+        if recorded != ended && recorded.exists() {
+            if ended.exists() {
+                bail!(
+                    "{} already exists, leaving {}",
+                    ended.display(),
+                    recorded.display()
+                );
+            }
+            std::fs::rename(&recorded, &ended)
+                .with_context(|| format!("Failed to rename {}", recorded.display()))?;
+        }
+        Ok(ended)
+    }
+
+    /// Writes the session's metadata into `dir`, replacing the session file written while it
+    /// was recorded once it ended.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(self.sidecar_path("session.json"));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write session file: {}", path.display()))?;
+        if self.ended_at.is_some() {
+            let recorded = dir.join(self.recorded().sidecar_path("session.json"));
+            if recorded != path && recorded.exists() {
+                std::fs::remove_file(&recorded)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The session files in `dir` and its subdirectories.
+fn find_session_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_session_files(&path, files)?;
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".session.json"))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The recordings in `dir`, from their session files, however deep the naming puts them.
+/// Unreadable ones are skipped.
+pub(crate) fn read_sessions(dir: &Path) -> Result<Vec<RecordingSession>> {
+    let mut files = Vec::new();
+    find_session_files(dir, &mut files)?;
+    let mut sessions = Vec::new();
+    for path in files {
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str::<RecordingSession>(&content)?))
+        {
+            Ok(session) => sessions.push(session),
+            Err(e) => warn!("Skipping session {}: {}", path.display(), e),
+        }
+    }
+    Ok(sessions)
+}
+
+/// Capture times of the frames of a video, from its `.frames` file.
+pub(crate) fn read_frame_times(path: &Path) -> Result<Vec<DateTime<Utc>>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            DateTime::parse_from_rfc3339(line.trim())
+                .map(|time| time.with_timezone(&Utc))
+                .with_context(|| format!("Invalid frame time in {}", path.display()))
+        })
+        .collect()
+}
+
+/// Monitor id, start time and extension of a recording named in local time,
+/// `monitor_{id}_{%Y-%m-%d_%H-%M-%S}.{extension}`.
+fn parse_legacy_name(file_name: &str) -> Option<(u32, NaiveDateTime, &str)> {
+    let rest = file_name.strip_prefix("monitor_")?;
+    let (id, rest) = rest.split_once('_')?;
+    let (stamp, extension) = rest.split_once('.')?;
+    let started_at = NaiveDateTime::parse_from_str(stamp, LEGACY_NAME_FORMAT).ok()?;
+    Some((id.parse().ok()?, started_at, extension))
+}
+
+/// UTC time of a start time written on the local clock. The hour repeated when DST ends is
+/// taken as its first occurrence, the one skipped when it starts can't have been written.
+fn legacy_start_time(local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match Local.from_local_datetime(&local) {
+        LocalResult::Single(at) => Some(at.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earliest, _) => {
+            warn!(
+                "{} happened twice on the local clock, taking the first",
+                local
+            );
+            Some(earliest.with_timezone(&Utc))
+        }
+        LocalResult::None => None,
+    }
+}
+
+/// Renames the recordings in `dir` named in local time, from before recordings were named in
+/// UTC, and writes their session files. Runs once per directory, returns the files renamed.
+pub fn upgrade_recording_names(dir: &Path) -> Result<usize> {
+    let marker = dir.join(UTC_NAMES_MARKER);
+    if marker.exists() || !dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut renamed = 0;
+    let mut sessions = BTreeSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some((monitor_id, local, extension)) = parse_legacy_name(file_name) else {
+            continue;
+        };
+        let Some(started_at) = legacy_start_time(local) else {
+            warn!("{} isn't a local time, leaving {}", local, path.display());
+            continue;
+        };
+        let target = dir.join(recording_file_name(monitor_id, started_at, extension));
+        if target.exists() {
+            warn!(
+                "{} already exists, leaving {}",
+                target.display(),
+                path.display()
+            );
+            continue;
+        }
+        std::fs::rename(&path, &target)
+            .with_context(|| format!("Failed to rename {}", path.display()))?;
+        renamed += 1;
+        sessions.insert((monitor_id, started_at));
+    }
+
+    for (monitor_id, started_at) in sessions {
+        let session = RecordingSession {
+            monitor_id,
+            started_at,
+            // the best guess, recordings were made on this machine
+            time_zone: SessionTimeZone::local_at(started_at),
+            session_id: String::new(),
+            hostname: hostname(),
+            ended_at: None,
+            // named as recordings were then
+            naming: OutputNaming::default(),
+            sequence: 0,
+            crf: None,
+            scale: None,
+        };
+        if !dir.join(session.sidecar_path("session.json")).exists() {
+            session.write(dir)?;
+        }
+    }
+
+    std::fs::write(&marker, "")?;
+    if renamed > 0 {
+        info!("Renamed {} recordings in {} to UTC", renamed, dir.display());
+    }
+    Ok(renamed)
+}
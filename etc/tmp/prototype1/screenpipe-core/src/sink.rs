@@ -0,0 +1,152 @@
+use crate::capture::SafeMonitor;
+use crate::encode::{start_ffmpeg_process, write_frame_with_retry};
+use crate::engine::RecordingProfile;
+use crate::session::RecordingSession;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use image::DynamicImage;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::{Child, ChildStdin};
+use tracing::{error, info};
+
+/// Where the frames a monitor's recorder keeps go. Each monitor gets its own sink, built by
+/// a [`SinkFactory`] when the recording starts.
+#[async_trait]
+pub trait FrameSink: Send {
+    /// Called once, before the first frame.
+    async fn open(
+        &mut self,
+        monitor: &SafeMonitor,
+        profile: &RecordingProfile,
+        session: &RecordingSession,
+    ) -> Result<()>;
+
+    /// Called for every frame that changed enough and isn't blocked. An error stops the
+    /// recording of the monitor.
+    async fn write_frame(&mut self, image: &DynamicImage) -> Result<()>;
+
+    /// Called once the segment ends, `session` with its end, to flush what the sink holds.
+    async fn close(&mut self, session: &RecordingSession) -> Result<()>;
+
+    /// Whether files `session` would be written to already exist, for the segment to be
+    /// numbered apart from the one they belong to.
+    fn taken(&self, _session: &RecordingSession) -> bool {
+        false
+    }
+}
+
+/// Builds a monitor's sink when its recording starts.
+pub type SinkFactory = Arc<dyn Fn(&SafeMonitor) -> Box<dyn FrameSink> + Send + Sync>;
+
+/// `monitor_{id}_{started_at}.{extension}`, the names of a recording's files with the default
+/// [`OutputNaming`](crate::OutputNaming), the start time in UTC so names sort and don't repeat
+/// across DST changes.
+pub fn recording_file_name(monitor_id: u32, started_at: DateTime<Utc>, extension: &str) -> String {
+    format!(
+        "monitor_{}_{}.{}",
+        monitor_id,
+        started_at.format("%Y-%m-%dT%H-%M-%SZ"),
+        extension
+    )
+}
+
+/// Encodes the frames into an H.265 mp4 per segment with ffmpeg, named by
+/// [`OutputNaming::segment`](crate::OutputNaming), and writes when each was captured, one
+/// RFC 3339 time per line, to its `.frames` file: frames are only kept when the screen
+/// changed, so the video's own timeline doesn't tell.
+pub struct VideoFileSink {
+    output_dir: PathBuf,
+    ffmpeg: Option<(Child, ChildStdin)>,
+    frame_times: Option<File>,
+}
+
+impl VideoFileSink {
+    pub fn new(output_dir: impl AsRef<Path>) -> Self {
+        Self {
+            output_dir: output_dir.as_ref().to_path_buf(),
+            ffmpeg: None,
+            frame_times: None,
+        }
+    }
+
+    /// A factory recording every monitor into `output_dir`.
+    pub fn factory(output_dir: impl AsRef<Path>) -> SinkFactory {
+        let output_dir = output_dir.as_ref().to_path_buf();
+        Arc::new(move |_| Box::new(VideoFileSink::new(&output_dir)))
+    }
+}
+
+#[async_trait]
+impl FrameSink for VideoFileSink {
+    async fn open(
+        &mut self,
+        _monitor: &SafeMonitor,
+        profile: &RecordingProfile,
+        session: &RecordingSession,
+    ) -> Result<()> {
+        let video_path = self.output_dir.join(session.video_path());
+        if video_path.exists() {
+            bail!("{} already exists", video_path.display());
+        }
+        // Ensure output directory exists, the naming may put segments in their own
+        let dir = video_path.parent().unwrap_or(&self.output_dir);
+        std::fs::create_dir_all(dir).context(format!(
+            "Failed to create output directory: {}",
+            dir.display()
+        ))?;
+        session.write(&self.output_dir)?;
+
+        let video_path_str = video_path.to_str().ok_or(anyhow::anyhow!("Invalid path"))?;
+
+        let crf = session.crf.unwrap_or(profile.crf);
+        let mut ffmpeg_child = start_ffmpeg_process(video_path_str, profile.fps, crf).await?;
+        let ffmpeg_stdin = ffmpeg_child
+            .stdin
+            .take()
+            .context("Failed to get ffmpeg stdin")?;
+        self.ffmpeg = Some((ffmpeg_child, ffmpeg_stdin));
+        self.frame_times = Some(File::create(
+            self.output_dir.join(session.sidecar_path("frames")),
+        )?);
+        Ok(())
+    }
+
+    async fn write_frame(&mut self, image: &DynamicImage) -> Result<()> {
+        let (_, stdin) = self.ffmpeg.as_mut().context("video sink isn't open")?;
+        write_frame_with_retry(stdin, image).await?;
+        if let Some(frame_times) = &mut self.frame_times {
+            writeln!(frame_times, "{}", Utc::now().to_rfc3339())?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self, session: &RecordingSession) -> Result<()> {
+        self.frame_times = None;
+        if let Some((mut ffmpeg_child, ffmpeg_stdin)) = self.ffmpeg.take() {
+            drop(ffmpeg_stdin); // Close stdin to signal EOF
+            match ffmpeg_child.wait().await {
+                Ok(status) => info!("FFmpeg finished with status: {}", status),
+                Err(e) => error!("Failed to wait for FFmpeg: {}", e),
+            }
+            // named with their end, when the naming has it, once finalized
+            session.rename_ended(&self.output_dir, RecordingSession::video_path)?;
+            session.rename_ended(&self.output_dir, |session| session.sidecar_path("frames"))?;
+            session.write(&self.output_dir)?;
+        }
+        Ok(())
+    }
+
+    fn taken(&self, session: &RecordingSession) -> bool {
+        [
+            session.video_path(),
+            session.sidecar_path("frames"),
+            session.sidecar_path("session.json"),
+        ]
+        .iter()
+        .any(|path| self.output_dir.join(path).exists())
+    }
+}
@@ -0,0 +1,249 @@
+use crate::diff::{frame_diff, DiffMetric};
+use crate::replay::SegmentReplay;
+use crate::session::{read_frame_times, read_sessions, SessionTimeZone};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Outline and tint of the changed tiles in a [`render_snapshot_diff`] image.
+const HIGHLIGHT: Rgb<u8> = Rgb([230, 40, 40]);
+
+/// Space between the two frames of a [`render_snapshot_diff`] image.
+const GAP: u32 = 8;
+
+/// What [`snapshot_diff`] compares.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiffOptions {
+    /// Side of the square tiles the frames are compared in, in pixels.
+    pub tile_size: u32,
+    /// `lab` by default: dashboards often change a colored value on a dark background, which
+    /// the luma metrics barely see.
+    pub metric: DiffMetric,
+    /// Difference of a tile above which it changed.
+    pub threshold: f64,
+}
+
+impl Default for SnapshotDiffOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 64,
+            metric: DiffMetric::Lab,
+            threshold: 0.01,
+        }
+    }
+}
+
+/// A recorded frame, in the video of its recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub monitor_id: u32,
+    /// When the frame was captured.
+    pub time: DateTime<Utc>,
+    /// Path of the video, relative to the recordings directory.
+    pub video: String,
+    /// Index of the frame in the video.
+    pub index: usize,
+    /// Time zone the frame was recorded in, to show `time` in.
+    pub time_zone: SessionTimeZone,
+}
+
+/// A tile of the frames that changed, in pixels of the frames.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChangedTile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub diff: f64,
+}
+
+/// The tiles that changed between two frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileDiff {
+    pub tiles: Vec<ChangedTile>,
+    /// Share of the tiles that changed, from 0 to 1.
+    pub changed_share: f64,
+}
+
+/// What changed on a monitor between two times.
+#[derive(Debug, Clone)]
+pub struct SnapshotDiff {
+    pub before: Snapshot,
+    pub after: Snapshot,
+    pub diff: TileDiff,
+    /// The two frames side by side, the changed tiles highlighted on both.
+    pub image: RgbImage,
+}
+
+/// The recorded frame of `monitor_id` in `dir` captured nearest to `at`, `None` when the
+/// monitor has no recording with frame times.
+pub fn find_snapshot(dir: &Path, monitor_id: u32, at: DateTime<Utc>) -> Result<Option<Snapshot>> {
+    let mut nearest: Option<(i64, Snapshot)> = None;
+    for session in read_sessions(dir)? {
+        if session.monitor_id != monitor_id {
+            continue;
+        }
+        let frames = dir.join(session.sidecar_path("frames"));
+        let video = session.video_path().to_string_lossy().into_owned();
+        if !frames.exists() || !dir.join(&video).exists() {
+            continue;
+        }
+        for (index, time) in read_frame_times(&frames)?.into_iter().enumerate() {
+            let distance = (time - at).num_milliseconds().abs();
+            if nearest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                continue;
+            }
+            nearest = Some((
+                distance,
+                Snapshot {
+                    monitor_id,
+                    time,
+                    video: video.clone(),
+                    index,
+                    time_zone: session.time_zone.clone(),
+                },
+            ));
+        }
+    }
+    Ok(nearest.map(|(_, snapshot)| snapshot))
+}
+
+/// Splits `length` into tiles of `size`, the last one taking the rest so that none is
+/// smaller than half a tile and the structural metrics have enough pixels to compare.
+fn tile_spans(length: u32, size: u32) -> Vec<(u32, u32)> {
+    let count = ((length + size / 2) / size).max(1);
+    (0..count)
+        .map(|i| {
+            let start = i * size;
+            let end = if i + 1 == count { length } else { start + size };
+            (start, end - start)
+        })
+        .collect()
+}
+
+/// Compares `before` and `after` tile by tile with `options.metric`.
+pub fn tile_diff(
+    before: &DynamicImage,
+    after: &DynamicImage,
+    options: &SnapshotDiffOptions,
+) -> Result<TileDiff> {
+    if before.dimensions() != after.dimensions() {
+        return Err(anyhow!(
+            "the frames have different sizes, {:?} and {:?}",
+            before.dimensions(),
+            after.dimensions()
+        ));
+    }
+    if options.tile_size == 0 {
+        return Err(anyhow!("the tile size must be positive"));
+    }
+    let (width, height) = before.dimensions();
+    let mut tiles = Vec::new();
+    let mut total = 0;
+    for (y, tile_height) in tile_spans(height, options.tile_size) {
+        for (x, tile_width) in tile_spans(width, options.tile_size) {
+            total += 1;
+            let diff = frame_diff(
+                options.metric,
+                &before.crop_imm(x, y, tile_width, tile_height),
+                &after.crop_imm(x, y, tile_width, tile_height),
+            )?;
+            if diff > options.threshold {
+                tiles.push(ChangedTile {
+                    x,
+                    y,
+                    width: tile_width,
+                    height: tile_height,
+                    diff,
+                });
+            }
+        }
+    }
+    Ok(TileDiff {
+        changed_share: tiles.len() as f64 / total as f64,
+        tiles,
+    })
+}
+
+fn highlight(image: &mut RgbImage, offset_x: u32, tile: &ChangedTile, tint: bool) {
+    for y in tile.y..tile.y + tile.height {
+        for x in tile.x..tile.x + tile.width {
+            let border = x < tile.x + 2
+                || y < tile.y + 2
+                || x + 2 >= tile.x + tile.width
+                || y + 2 >= tile.y + tile.height;
+            let pixel = image.get_pixel_mut(offset_x + x, y);
+            if border {
+                *pixel = HIGHLIGHT;
+            } else if tint {
+                for (channel, highlight) in pixel.0.iter_mut().zip(HIGHLIGHT.0) {
+                    *channel = ((*channel as u16 * 3 + highlight as u16) / 4) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// `before` and `after` side by side, the changed tiles outlined on both and tinted on
+/// `after`.
+pub fn render_snapshot_diff(
+    before: &DynamicImage,
+    after: &DynamicImage,
+    diff: &TileDiff,
+) -> RgbImage {
+    let (width, height) = before.dimensions();
+    let mut image = RgbImage::from_pixel(width * 2 + GAP, height, Rgb([32, 32, 32]));
+    image::imageops::replace(&mut image, &before.to_rgb8(), 0, 0);
+    image::imageops::replace(&mut image, &after.to_rgb8(), (width + GAP) as i64, 0);
+    for tile in &diff.tiles {
+        highlight(&mut image, 0, tile, false);
+        highlight(&mut image, width + GAP, tile, true);
+    }
+    image
+}
+
+/// `before` and `after` at the lower of their resolutions, for the frame of an app recorded
+/// downscaled to be compared with one at full resolution.
+fn at_same_size(before: DynamicImage, after: DynamicImage) -> (DynamicImage, DynamicImage) {
+    let (width, height) = before.dimensions().min(after.dimensions());
+    let resize = |image: DynamicImage| {
+        if image.dimensions() == (width, height) {
+            image
+        } else {
+            image.resize_exact(width, height, FilterType::Triangle)
+        }
+    };
+    (resize(before), resize(after))
+}
+
+/// What changed on `monitor_id` between `from` and `to`: the frames recorded in `dir`
+/// nearest each time, compared tile by tile. The video of a recording still going on can't be
+/// read until it's stopped.
+pub async fn snapshot_diff(
+    dir: &Path,
+    monitor_id: u32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    options: &SnapshotDiffOptions,
+) -> Result<SnapshotDiff> {
+    let find = |at| {
+        find_snapshot(dir, monitor_id, at)?
+            .with_context(|| format!("No recorded frame of monitor {}", monitor_id))
+    };
+    let (before, after) = (find(from)?, find(to)?);
+    let before_image = SegmentReplay::frame_at(dir.join(&before.video), before.index).await?;
+    let after_image = SegmentReplay::frame_at(dir.join(&after.video), after.index).await?;
+    let (before_image, after_image) = at_same_size(before_image, after_image);
+
+    let diff = tile_diff(&before_image, &after_image, options)?;
+    let image = render_snapshot_diff(&before_image, &after_image, &diff);
+    Ok(SnapshotDiff {
+        before,
+        after,
+        diff,
+        image,
+    })
+}
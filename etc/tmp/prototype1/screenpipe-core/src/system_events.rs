@@ -0,0 +1,660 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Wall-clock time between two ticks beyond their interval after which the machine slept,
+/// monotonic clocks, and so the recorder's sleeps, stop while it does. Sleeps the platform
+/// doesn't announce are noticed on wake by it.
+pub(crate) const SLEEP_GAP: Duration = Duration::from_secs(10);
+
+/// Longest Windows is kept from sleeping while the recorders finalize their videos, it only
+/// waits 2 seconds for the programs notified. logind and macOS enforce limits of their own.
+#[cfg(windows)]
+const SUSPEND_TIMEOUT: Duration = Duration::from_millis(1800);
+
+/// Why a recording stopped capturing on its own, with nothing recorded meanwhile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspendReason {
+    Sleep,
+    Lock,
+}
+
+/// Keeps the machine from sleeping until the last of its clones is dropped, by the recorders
+/// once their video is finalized.
+#[derive(Clone)]
+pub(crate) struct SleepDelay(Arc<SleepRelease>);
+
+struct SleepRelease(Mutex<Option<Box<dyn FnOnce() + Send>>>);
+
+impl Drop for SleepRelease {
+    fn drop(&mut self) {
+        if let Some(release) = self.0.get_mut().ok().and_then(Option::take) {
+            release();
+        }
+    }
+}
+
+impl SleepDelay {
+    /// A delay ended by `release`, called once.
+    fn new(release: impl FnOnce() + Send + 'static) -> Self {
+        Self(Arc::new(SleepRelease(Mutex::new(Some(Box::new(release))))))
+    }
+}
+
+impl fmt::Debug for SleepDelay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SleepDelay")
+    }
+}
+
+/// A power or session change, as the recorders get it.
+#[derive(Debug, Clone)]
+pub(crate) enum SystemEvent {
+    /// The machine is about to sleep, and waits for the delay to be dropped, or for as long
+    /// as the platform lets it.
+    Sleeping(SleepDelay),
+    Woke,
+    Locked,
+    Unlocked,
+}
+
+fn send(events: &broadcast::Sender<SystemEvent>, event: SystemEvent) {
+    debug!("System event: {:?}", event);
+    // without recorders, a sleep delay is dropped right away
+    let _ = events.send(event);
+}
+
+/// Watches for [`SystemEvent`]s until aborted, sending them to `events`. Lock changes are
+/// only watched with `watch_lock`.
+pub(crate) fn spawn_watcher(
+    events: broadcast::Sender<SystemEvent>,
+    watch_lock: bool,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = watch(&events, watch_lock).await {
+            debug!("System events aren't watched: {}", e);
+        }
+    })
+}
+
+/// A logind delay lock on sleep, held while `systemd-inhibit` runs.
+#[cfg(target_os = "linux")]
+struct SleepInhibitor(std::process::Child);
+
+#[cfg(target_os = "linux")]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn sleep_inhibitor() -> Option<SleepInhibitor> {
+    let inhibitor = std::process::Command::new("systemd-inhibit")
+        .args([
+            "--what=sleep",
+            "--mode=delay",
+            "--who=screenpipe",
+            "--why=Finalizing the recordings",
+            "sleep",
+            "infinity",
+        ])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    match inhibitor {
+        Ok(child) => Some(SleepInhibitor(child)),
+        Err(e) => {
+            debug!("Sleep doesn't wait for the recordings: {}", e);
+            None
+        }
+    }
+}
+
+/// Sleep and lock changes are signals of logind on the system bus, read with gdbus. A delay
+/// lock is held between sleeps, for logind to wait for the recorders before sleeping.
+#[cfg(target_os = "linux")]
+async fn watch(events: &broadcast::Sender<SystemEvent>, watch_lock: bool) -> anyhow::Result<()> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::process::Command;
+
+    let mut monitor = Command::new("gdbus")
+        .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = monitor
+        .stdout
+        .take()
+        .ok_or(anyhow::anyhow!("no gdbus output"))?;
+    let mut lines = BufReader::new(stdout).lines();
+    let mut inhibitor = sleep_inhibitor();
+    while let Some(line) = lines.next_line().await? {
+        // "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)"
+        let event = if line.contains("PrepareForSleep") {
+            Some(if line.contains("(true") {
+                let inhibitor = inhibitor.take();
+                SystemEvent::Sleeping(SleepDelay::new(move || drop(inhibitor)))
+            } else {
+                inhibitor = sleep_inhibitor();
+                SystemEvent::Woke
+            })
+        } else if watch_lock && line.contains("'LockedHint': <true>") {
+            Some(SystemEvent::Locked)
+        } else if watch_lock && line.contains("'LockedHint': <false>") {
+            Some(SystemEvent::Unlocked)
+        } else {
+            None
+        };
+        if let Some(event) = event {
+            send(events, event);
+        }
+    }
+    Ok(())
+}
+
+/// Sleeps and wakes are notified by the platform, and so are lock changes, to a thread of
+/// its own that stops once this is aborted.
+#[cfg(any(target_os = "macos", windows))]
+async fn watch(events: &broadcast::Sender<SystemEvent>, watch_lock: bool) -> anyhow::Result<()> {
+    let _watcher = native::Watcher::start(events.clone(), watch_lock)?;
+    std::future::pending().await
+}
+
+/// Sleeps are noticed on wake, from the wall clock having jumped.
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+async fn watch(_events: &broadcast::Sender<SystemEvent>, _watch_lock: bool) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// The console user's session reports `CGSSessionScreenIsLocked` while the screen is locked.
+/// Only read as the watcher starts, changes are notified.
+#[cfg(target_os = "macos")]
+fn is_screen_locked() -> bool {
+    std::process::Command::new("ioreg")
+        .args(["-n", "Root", "-d1"])
+        .output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout).contains("\"CGSSessionScreenIsLocked\"=Yes")
+        })
+}
+
+/// LogonUI runs while the lock screen is shown. Only read as the watcher starts, changes are
+/// notified.
+#[cfg(windows)]
+fn is_screen_locked() -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    std::process::Command::new("tasklist")
+        .args(["/FI", "IMAGENAME eq LogonUI.exe", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("LogonUI.exe"))
+}
+
+/// IOKit's system power notifications, the ones `NSWorkspaceWillSleepNotification` is sent
+/// from, which let the sleep wait until it's allowed, and the distributed notifications of
+/// the screen lock, on a run loop of their own.
+#[cfg(target_os = "macos")]
+mod native {
+    use super::{is_screen_locked, send, SleepDelay, SystemEvent};
+    use std::cell::Cell;
+    use std::ffi::{c_char, c_void};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use tokio::sync::broadcast;
+
+    type CFStringRef = *const c_void;
+    type IoConnect = u32;
+    type IoObject = u32;
+
+    const IO_MESSAGE_CAN_SYSTEM_SLEEP: u32 = 0xe000_0270;
+    const IO_MESSAGE_SYSTEM_WILL_SLEEP: u32 = 0xe000_0280;
+    const IO_MESSAGE_SYSTEM_HAS_POWERED_ON: u32 = 0xe000_0300;
+    const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const CF_NOTIFICATION_DELIVER_IMMEDIATELY: isize = 4;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IORegisterForSystemPower(
+            refcon: *mut c_void,
+            port: *mut *mut c_void,
+            callback: extern "C" fn(*mut c_void, IoObject, u32, *mut c_void),
+            notifier: *mut IoObject,
+        ) -> IoConnect;
+        fn IONotificationPortGetRunLoopSource(port: *mut c_void) -> *mut c_void;
+        fn IOAllowPowerChange(kernel_port: IoConnect, notification_id: isize) -> i32;
+        fn IODeregisterForSystemPower(notifier: *mut IoObject) -> i32;
+        fn IONotificationPortDestroy(port: *mut c_void);
+        fn IOServiceClose(connect: IoConnect) -> i32;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopDefaultMode: CFStringRef;
+        fn CFRunLoopGetCurrent() -> *mut c_void;
+        fn CFRunLoopAddSource(run_loop: *mut c_void, source: *mut c_void, mode: CFStringRef);
+        fn CFRunLoopRunInMode(mode: CFStringRef, seconds: f64, return_after_source: u8) -> i32;
+        fn CFNotificationCenterGetDistributedCenter() -> *mut c_void;
+        fn CFNotificationCenterAddObserver(
+            center: *mut c_void,
+            observer: *const c_void,
+            callback: extern "C" fn(
+                *mut c_void,
+                *mut c_void,
+                CFStringRef,
+                *const c_void,
+                *const c_void,
+            ),
+            name: CFStringRef,
+            object: *const c_void,
+            suspension_behavior: isize,
+        );
+        fn CFNotificationCenterRemoveEveryObserver(center: *mut c_void, observer: *const c_void);
+        fn CFStringCreateWithCString(
+            allocator: *const c_void,
+            string: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFRelease(object: *const c_void);
+    }
+
+    struct Context {
+        events: broadcast::Sender<SystemEvent>,
+        root_port: Cell<IoConnect>,
+    }
+
+    extern "C" fn on_power(
+        refcon: *mut c_void,
+        _service: IoObject,
+        message: u32,
+        argument: *mut c_void,
+    ) {
+        // SAFETY: the context outlives the run loop calling this
+        let context = unsafe { &*(refcon as *const Context) };
+        let root_port = context.root_port.get();
+        let notification = argument as isize;
+        match message {
+            IO_MESSAGE_CAN_SYSTEM_SLEEP => {
+                // SAFETY: answers the notification being handled
+                unsafe { IOAllowPowerChange(root_port, notification) };
+            }
+            IO_MESSAGE_SYSTEM_WILL_SLEEP => {
+                let delay = SleepDelay::new(move || {
+                    // SAFETY: the root port stays open until the sleep is allowed or timed out
+                    unsafe { IOAllowPowerChange(root_port, notification) };
+                });
+                send(&context.events, SystemEvent::Sleeping(delay));
+            }
+            IO_MESSAGE_SYSTEM_HAS_POWERED_ON => send(&context.events, SystemEvent::Woke),
+            _ => {}
+        }
+    }
+
+    extern "C" fn on_locked(
+        _center: *mut c_void,
+        observer: *mut c_void,
+        _name: CFStringRef,
+        _object: *const c_void,
+        _info: *const c_void,
+    ) {
+        // SAFETY: the context outlives the run loop calling this
+        let context = unsafe { &*(observer as *const Context) };
+        send(&context.events, SystemEvent::Locked);
+    }
+
+    extern "C" fn on_unlocked(
+        _center: *mut c_void,
+        observer: *mut c_void,
+        _name: CFStringRef,
+        _object: *const c_void,
+        _info: *const c_void,
+    ) {
+        // SAFETY: the context outlives the run loop calling this
+        let context = unsafe { &*(observer as *const Context) };
+        send(&context.events, SystemEvent::Unlocked);
+    }
+
+    /// The thread the notifications are delivered to, which stops when dropped.
+    pub(super) struct Watcher {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Watcher {
+        pub(super) fn start(
+            events: broadcast::Sender<SystemEvent>,
+            watch_lock: bool,
+        ) -> anyhow::Result<Self> {
+            let stopped = Arc::new(AtomicBool::new(false));
+            let (started_tx, started_rx) = mpsc::channel();
+            let stop = stopped.clone();
+            std::thread::Builder::new()
+                .name("system-events".to_string())
+                .spawn(move || run(events, watch_lock, &stop, started_tx))?;
+            started_rx.recv()??;
+            Ok(Self { stopped })
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn run(
+        events: broadcast::Sender<SystemEvent>,
+        watch_lock: bool,
+        stopped: &AtomicBool,
+        started: mpsc::Sender<anyhow::Result<()>>,
+    ) {
+        let context = Box::new(Context {
+            events,
+            root_port: Cell::new(0),
+        });
+        let refcon = &*context as *const Context as *mut c_void;
+        let mut port = std::ptr::null_mut();
+        let mut notifier: IoObject = 0;
+        // SAFETY: the context outlives the registration, ended below before it's dropped
+        let root_port =
+            unsafe { IORegisterForSystemPower(refcon, &mut port, on_power, &mut notifier) };
+        if root_port == 0 {
+            let _ = started.send(Err(anyhow::anyhow!("IORegisterForSystemPower failed")));
+            return;
+        }
+        context.root_port.set(root_port);
+        // SAFETY: the source of the port just created, added to this thread's run loop
+        unsafe {
+            CFRunLoopAddSource(
+                CFRunLoopGetCurrent(),
+                IONotificationPortGetRunLoopSource(port),
+                kCFRunLoopDefaultMode,
+            )
+        };
+
+        let center = unsafe { CFNotificationCenterGetDistributedCenter() };
+        let mut names = Vec::new();
+        if watch_lock {
+            let observers: [(&[u8], extern "C" fn(_, _, _, _, _)); 2] = [
+                (b"com.apple.screenIsLocked\0", on_locked),
+                (b"com.apple.screenIsUnlocked\0", on_unlocked),
+            ];
+            for (name, callback) in observers {
+                // SAFETY: the names are nul-terminated and released once the observers are
+                // removed, the context outlives the observers
+                unsafe {
+                    let name = CFStringCreateWithCString(
+                        std::ptr::null(),
+                        name.as_ptr() as *const c_char,
+                        CF_STRING_ENCODING_UTF8,
+                    );
+                    CFNotificationCenterAddObserver(
+                        center,
+                        refcon,
+                        callback,
+                        name,
+                        std::ptr::null(),
+                        CF_NOTIFICATION_DELIVER_IMMEDIATELY,
+                    );
+                    names.push(name);
+                }
+            }
+        }
+        let _ = started.send(Ok(()));
+        if watch_lock && is_screen_locked() {
+            send(&context.events, SystemEvent::Locked);
+        }
+
+        while !stopped.load(Ordering::Relaxed) {
+            // SAFETY: runs this thread's run loop for a second at most
+            unsafe { CFRunLoopRunInMode(kCFRunLoopDefaultMode, 1.0, 0) };
+        }
+
+        // SAFETY: ends what was registered above, before the context is dropped
+        unsafe {
+            CFNotificationCenterRemoveEveryObserver(center, refcon);
+            for name in names {
+                CFRelease(name);
+            }
+            IODeregisterForSystemPower(&mut notifier);
+            IOServiceClose(root_port);
+            IONotificationPortDestroy(port);
+        }
+    }
+}
+
+/// `WM_POWERBROADCAST` and `WM_WTSSESSION_CHANGE`, sent to a hidden window of a thread of its
+/// own. Windows waits for the window to handle `PBT_APMSUSPEND` before sleeping.
+#[cfg(windows)]
+mod native {
+    use super::{is_screen_locked, send, SleepDelay, SystemEvent, SUSPEND_TIMEOUT};
+    use std::cell::RefCell;
+    use std::ffi::c_void;
+    use std::sync::mpsc;
+    use tokio::sync::broadcast;
+
+    const WM_DESTROY: u32 = 0x0002;
+    const WM_CLOSE: u32 = 0x0010;
+    const WM_POWERBROADCAST: u32 = 0x0218;
+    const WM_WTSSESSION_CHANGE: u32 = 0x02B1;
+    const PBT_APMSUSPEND: usize = 0x0004;
+    const PBT_APMRESUMEAUTOMATIC: usize = 0x0012;
+    const WTS_SESSION_LOCK: usize = 0x7;
+    const WTS_SESSION_UNLOCK: usize = 0x8;
+    const NOTIFY_FOR_THIS_SESSION: u32 = 0;
+
+    type WindowProc = unsafe extern "system" fn(isize, u32, usize, isize) -> isize;
+
+    #[repr(C)]
+    struct WndClassExW {
+        size: u32,
+        style: u32,
+        window_proc: WindowProc,
+        class_extra: i32,
+        window_extra: i32,
+        instance: isize,
+        icon: isize,
+        cursor: isize,
+        background: isize,
+        menu_name: *const u16,
+        class_name: *const u16,
+        small_icon: isize,
+    }
+
+    #[repr(C)]
+    struct Msg {
+        hwnd: isize,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+        time: u32,
+        point: [i32; 2],
+        private: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn RegisterClassExW(class: *const WndClassExW) -> u16;
+        fn CreateWindowExW(
+            ex_style: u32,
+            class_name: *const u16,
+            window_name: *const u16,
+            style: u32,
+            x: i32,
+            y: i32,
+            width: i32,
+            height: i32,
+            parent: isize,
+            menu: isize,
+            instance: isize,
+            param: *const c_void,
+        ) -> isize;
+        fn DefWindowProcW(hwnd: isize, message: u32, wparam: usize, lparam: isize) -> isize;
+        fn GetMessageW(msg: *mut Msg, hwnd: isize, min: u32, max: u32) -> i32;
+        fn TranslateMessage(msg: *const Msg) -> i32;
+        fn DispatchMessageW(msg: *const Msg) -> isize;
+        fn PostMessageW(hwnd: isize, message: u32, wparam: usize, lparam: isize) -> i32;
+        fn PostQuitMessage(code: i32);
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleW(name: *const u16) -> isize;
+    }
+
+    #[link(name = "wtsapi32")]
+    extern "system" {
+        fn WTSRegisterSessionNotification(hwnd: isize, flags: u32) -> i32;
+        fn WTSUnRegisterSessionNotification(hwnd: isize) -> i32;
+    }
+
+    thread_local! {
+        /// Where the window of this thread sends the events.
+        static EVENTS: RefCell<Option<broadcast::Sender<SystemEvent>>> = const { RefCell::new(None) };
+    }
+
+    fn emit(event: SystemEvent) {
+        EVENTS.with(|events| {
+            if let Some(events) = &*events.borrow() {
+                send(events, event);
+            }
+        });
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: isize,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+    ) -> isize {
+        match (message, wparam) {
+            (WM_POWERBROADCAST, PBT_APMSUSPEND) => {
+                let (done_tx, done_rx) = mpsc::channel();
+                emit(SystemEvent::Sleeping(SleepDelay::new(move || {
+                    let _ = done_tx.send(());
+                })));
+                let _ = done_rx.recv_timeout(SUSPEND_TIMEOUT);
+                1
+            }
+            (WM_POWERBROADCAST, PBT_APMRESUMEAUTOMATIC) => {
+                emit(SystemEvent::Woke);
+                1
+            }
+            (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => {
+                emit(SystemEvent::Locked);
+                0
+            }
+            (WM_WTSSESSION_CHANGE, WTS_SESSION_UNLOCK) => {
+                emit(SystemEvent::Unlocked);
+                0
+            }
+            (WM_DESTROY, _) => {
+                WTSUnRegisterSessionNotification(hwnd);
+                PostQuitMessage(0);
+                0
+            }
+            _ => DefWindowProcW(hwnd, message, wparam, lparam),
+        }
+    }
+
+    /// The thread of the window, which it closes when dropped.
+    pub(super) struct Watcher {
+        hwnd: isize,
+    }
+
+    impl Watcher {
+        pub(super) fn start(
+            events: broadcast::Sender<SystemEvent>,
+            watch_lock: bool,
+        ) -> anyhow::Result<Self> {
+            let (started_tx, started_rx) = mpsc::channel();
+            std::thread::Builder::new()
+                .name("system-events".to_string())
+                .spawn(move || run(events, watch_lock, started_tx))?;
+            let hwnd = started_rx.recv()??;
+            Ok(Self { hwnd })
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            // SAFETY: the window is destroyed by its own thread, which then quits
+            unsafe { PostMessageW(self.hwnd, WM_CLOSE, 0, 0) };
+        }
+    }
+
+    fn run(
+        events: broadcast::Sender<SystemEvent>,
+        watch_lock: bool,
+        started: mpsc::Sender<anyhow::Result<isize>>,
+    ) {
+        let class_name: Vec<u16> = "screenpipe_system_events\0".encode_utf16().collect();
+        // SAFETY: a hidden top-level window, message-only ones aren't sent power broadcasts;
+        // registering the class again fails harmlessly when another recording did
+        let hwnd = unsafe {
+            let instance = GetModuleHandleW(std::ptr::null());
+            RegisterClassExW(&WndClassExW {
+                size: std::mem::size_of::<WndClassExW>() as u32,
+                style: 0,
+                window_proc,
+                class_extra: 0,
+                window_extra: 0,
+                instance,
+                icon: 0,
+                cursor: 0,
+                background: 0,
+                menu_name: std::ptr::null(),
+                class_name: class_name.as_ptr(),
+                small_icon: 0,
+            });
+            CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                class_name.as_ptr(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                instance,
+                std::ptr::null(),
+            )
+        };
+        if hwnd == 0 {
+            let _ = started.send(Err(anyhow::anyhow!("failed to create the events window")));
+            return;
+        }
+        EVENTS.with(|slot| *slot.borrow_mut() = Some(events));
+        // SAFETY: the window of this thread, unregistered as it's destroyed
+        if watch_lock
+            && unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) } == 0
+        {
+            tracing::debug!("Lock changes aren't notified");
+        }
+        let _ = started.send(Ok(hwnd));
+        if watch_lock && is_screen_locked() {
+            emit(SystemEvent::Locked);
+        }
+
+        let mut msg = std::mem::MaybeUninit::<Msg>::zeroed();
+        // SAFETY: the messages of this thread's window, until it's destroyed
+        unsafe {
+            while GetMessageW(msg.as_mut_ptr(), 0, 0, 0) > 0 {
+                TranslateMessage(msg.as_ptr());
+                DispatchMessageW(msg.as_ptr());
+            }
+        }
+        EVENTS.with(|slot| slot.borrow_mut().take());
+    }
+}
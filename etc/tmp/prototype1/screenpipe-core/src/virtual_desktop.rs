@@ -0,0 +1,308 @@
+use crate::blocklist::normalize_for_matching;
+use serde::{Deserialize, Serialize};
+#[cfg(any(target_os = "linux", windows))]
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a desktop read stays current. Every monitor checks the desktop at every frame, and
+/// on Linux and Windows reading it runs a command.
+const DESKTOP_REFRESH: Duration = Duration::from_secs(1);
+
+/// The desktops read last and when, by display: `None` for the desktop of the focused window,
+/// which is also the one of every monitor but on macOS with separate Spaces.
+type DesktopRead = (Option<u32>, Instant, Option<VirtualDesktop>);
+
+static DESKTOPS: Mutex<Vec<DesktopRead>> = Mutex::new(Vec::new());
+
+/// A virtual desktop, a Space on macOS.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VirtualDesktop {
+    /// Id given by the platform: the Space id on macOS, the desktop GUID on Windows and the
+    /// desktop index on Linux.
+    pub id: String,
+    /// Position among the desktops, from 1, when the platform tells it.
+    pub number: Option<u32>,
+    /// Name given by the user, when the platform has names.
+    pub name: Option<String>,
+}
+
+impl VirtualDesktop {
+    /// Whether `designation`, as set in [`crate::RecordingProfile::private_desktops`], is
+    /// this desktop: its id, its number or its name, ignoring case and width.
+    pub fn matches(&self, designation: &str) -> bool {
+        let designation = designation.trim();
+        if designation.is_empty() {
+            return false;
+        }
+        if self.id.eq_ignore_ascii_case(designation)
+            || self
+                .number
+                .is_some_and(|number| number.to_string() == designation)
+        {
+            return true;
+        }
+        self.name
+            .as_deref()
+            .is_some_and(|name| normalize_for_matching(name) == normalize_for_matching(designation))
+    }
+}
+
+/// Queries the desktop shown. The focused window is always on it, windows shown on every
+/// desktop included. Reads are shared by the detectors of every monitor for
+/// [`DESKTOP_REFRESH`].
+#[derive(Debug)]
+pub struct VirtualDesktopDetector {
+    available: bool,
+}
+
+impl Default for VirtualDesktopDetector {
+    fn default() -> Self {
+        Self {
+            // on Linux the desktops are read with xprop, not installed with every window manager
+            available: !cfg!(target_os = "linux") || which::which("xprop").is_ok(),
+        }
+    }
+}
+
+impl VirtualDesktopDetector {
+    /// Whether the platform tells the desktops apart, it doesn't on Wayland or without xprop.
+    pub fn is_available(&self) -> bool {
+        self.available
+    }
+
+    /// The desktop of the focused window, `None` when the platform doesn't tell, as on
+    /// Wayland. On macOS with "Displays have separate Spaces", the Space of the display with
+    /// the focused window.
+    pub fn current(&self) -> Option<VirtualDesktop> {
+        self.read(None, current_desktop)
+    }
+
+    /// The desktop shown on the monitor `monitor_id`. Desktops span every monitor but on macOS
+    /// with "Displays have separate Spaces", where each display shows a Space of its own.
+    pub fn shown_on(&self, monitor_id: u32) -> Option<VirtualDesktop> {
+        if cfg!(target_os = "macos") {
+            self.read(Some(monitor_id), || display_desktop(monitor_id))
+        } else {
+            self.current()
+        }
+    }
+
+    fn read(
+        &self,
+        display: Option<u32>,
+        query: impl FnOnce() -> Option<VirtualDesktop>,
+    ) -> Option<VirtualDesktop> {
+        if !self.available {
+            return None;
+        }
+        let mut desktops = DESKTOPS.lock().unwrap();
+        if let Some((_, read_at, desktop)) = desktops.iter().find(|(of, ..)| *of == display) {
+            if read_at.elapsed() < DESKTOP_REFRESH {
+                return desktop.clone();
+            }
+        }
+        let desktop = query();
+        desktops.retain(|(of, ..)| *of != display);
+        desktops.push((display, Instant::now(), desktop.clone()));
+        desktop
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn xprop(args: &[&str]) -> Option<String> {
+    let output = Command::new("xprop").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+fn current_desktop() -> Option<VirtualDesktop> {
+    // _NET_CURRENT_DESKTOP(CARDINAL) = 1
+    // _NET_DESKTOP_NAMES(UTF8_STRING) = "Main", "Private"
+    let output = xprop(&["-root", "_NET_CURRENT_DESKTOP", "_NET_DESKTOP_NAMES"])?;
+    let property = |name: &str| {
+        output
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.split_once('='))
+            .map(|(_, value)| value.trim())
+    };
+    let index: u32 = property("_NET_CURRENT_DESKTOP(")?.parse().ok()?;
+    let name = property("_NET_DESKTOP_NAMES(").and_then(|names| {
+        names
+            .split("\", \"")
+            .nth(index as usize)
+            .map(|name| name.trim().trim_matches('"').to_string())
+            .filter(|name| !name.is_empty())
+    });
+    Some(VirtualDesktop {
+        id: index.to_string(),
+        number: Some(index + 1),
+        name,
+    })
+}
+
+// Spaces have no public API, these are what Mission Control itself uses
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGSMainConnectionID() -> i32;
+    fn CGSGetActiveSpace(connection: i32) -> u64;
+    fn CGSManagedDisplayGetCurrentSpace(connection: i32, display: *const std::ffi::c_void) -> u64;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn CGDisplayCreateUUIDFromDisplayID(display: u32) -> *const std::ffi::c_void;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFUUIDCreateString(
+        allocator: *const std::ffi::c_void,
+        uuid: *const std::ffi::c_void,
+    ) -> *const std::ffi::c_void;
+    fn CFRelease(object: *const std::ffi::c_void);
+}
+
+#[cfg(target_os = "macos")]
+fn space(id: u64) -> Option<VirtualDesktop> {
+    (id != 0).then(|| VirtualDesktop {
+        id: id.to_string(),
+        number: None,
+        name: None,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn current_desktop() -> Option<VirtualDesktop> {
+    // SAFETY: both only read the window server state of this process' connection
+    space(unsafe { CGSGetActiveSpace(CGSMainConnectionID()) })
+}
+
+/// The Space shown on the display `display_id`, which the monitor ids of xcap are.
+#[cfg(target_os = "macos")]
+fn display_desktop(display_id: u32) -> Option<VirtualDesktop> {
+    // SAFETY: the UUID and its string are created here and released once read, the window
+    // server state is only read
+    let id = unsafe {
+        let uuid = CGDisplayCreateUUIDFromDisplayID(display_id);
+        if uuid.is_null() {
+            return None;
+        }
+        let name = CFUUIDCreateString(std::ptr::null(), uuid);
+        CFRelease(uuid);
+        if name.is_null() {
+            return None;
+        }
+        let id = CGSManagedDisplayGetCurrentSpace(CGSMainConnectionID(), name);
+        CFRelease(name);
+        id
+    };
+    // displays don't have Spaces of their own when "Displays have separate Spaces" is off
+    space(id).or_else(current_desktop)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn display_desktop(_display_id: u32) -> Option<VirtualDesktop> {
+    current_desktop()
+}
+
+#[cfg(windows)]
+const VIRTUAL_DESKTOPS_KEY: &str =
+    r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\VirtualDesktops";
+
+/// The values of the registry `key` by name, as `reg query` prints their data, read at once.
+#[cfg(windows)]
+fn reg_query(key: &str) -> Option<Vec<(String, String)>> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = Command::new("reg")
+        .args(["query", key])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // "    CurrentVirtualDesktop    REG_BINARY    6A0E...", subkeys are on lines of their own
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.trim().splitn(3, "    ");
+                let name = columns.next()?;
+                columns.next()?;
+                Some((
+                    name.to_string(),
+                    columns.next().unwrap_or_default().trim().to_string(),
+                ))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(windows)]
+fn reg_value<'a>(values: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    values
+        .iter()
+        .find(|(value, _)| value == name)
+        .map(|(_, data)| data.as_str())
+}
+
+/// The GUIDs in the hex of a `REG_BINARY`, as the registry names the desktop keys.
+#[cfg(windows)]
+fn guids(hex: &str) -> Vec<String> {
+    let bytes: Vec<u8> = (0..hex.len() / 2)
+        .filter_map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+        .collect();
+    bytes
+        .chunks_exact(16)
+        .map(|guid| {
+            format!(
+                "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}}}",
+                u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]]),
+                u16::from_le_bytes([guid[4], guid[5]]),
+                u16::from_le_bytes([guid[6], guid[7]]),
+                guid[8],
+                guid[9],
+                guid[10..]
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect::<String>()
+            )
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn current_desktop() -> Option<VirtualDesktop> {
+    // Windows 11 keeps the current desktop there, Windows 10 under a key per logon session
+    // that isn't read, its desktops are unknown
+    let values = reg_query(VIRTUAL_DESKTOPS_KEY)?;
+    let id = guids(reg_value(&values, "CurrentVirtualDesktop")?)
+        .into_iter()
+        .next()?;
+    let number = reg_value(&values, "VirtualDesktopIDs").and_then(|ids| {
+        guids(ids)
+            .iter()
+            .position(|desktop| *desktop == id)
+            .map(|index| index as u32 + 1)
+    });
+    // desktops the user never renamed have no name
+    let name = reg_query(&format!(r"{}\Desktops\{}", VIRTUAL_DESKTOPS_KEY, id))
+        .and_then(|values| reg_value(&values, "Name").map(str::to_string))
+        .filter(|name| !name.is_empty());
+    Some(VirtualDesktop { id, number, name })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn current_desktop() -> Option<VirtualDesktop> {
+    None
+}
@@ -0,0 +1,114 @@
+use screenpipe_core::{AppOverride, RecordingProfile, SegmentEncoding};
+
+fn profile() -> RecordingProfile {
+    RecordingProfile {
+        diff_threshold: 0.006,
+        app_overrides: vec![
+            AppOverride {
+                app: "Terminal".to_string(),
+                diff_threshold: Some(0.02),
+                crf: Some(18),
+                scale: None,
+            },
+            AppOverride {
+                app: "Grafana".to_string(),
+                diff_threshold: Some(0.001),
+                crf: None,
+                scale: None,
+            },
+            AppOverride {
+                app: "term".to_string(),
+                diff_threshold: Some(0.5),
+                crf: None,
+                scale: None,
+            },
+            AppOverride {
+                app: "VLC".to_string(),
+                diff_threshold: None,
+                crf: Some(35),
+                scale: Some(0.5),
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_diff_threshold_for_app() {
+    let profile = profile();
+    assert_eq!(profile.diff_threshold_for(Some("Terminal")), 0.02);
+    // matched like the blocklist, the first override wins
+    assert_eq!(
+        profile.diff_threshold_for(Some("ＧＮＯＭＥ Terminal")),
+        0.02
+    );
+    assert_eq!(profile.diff_threshold_for(Some("grafana - Chrome")), 0.001);
+    assert_eq!(profile.diff_threshold_for(Some("Code")), 0.006);
+    assert_eq!(profile.diff_threshold_for(None), 0.006);
+    assert_eq!(profile.diff_threshold_for(Some("VLC media player")), 0.006);
+}
+
+#[test]
+fn test_encoding_for_app() {
+    let profile = profile();
+    assert_eq!(
+        profile.encoding_for(Some("Terminal")),
+        SegmentEncoding {
+            crf: 18,
+            scale: 1.0
+        }
+    );
+    assert_eq!(
+        profile.encoding_for(Some("VLC media player")),
+        SegmentEncoding {
+            crf: 35,
+            scale: 0.5
+        }
+    );
+    // the first override matching wins even without a crf
+    assert_eq!(profile.encoding_for(Some("Grafana")).crf, profile.crf);
+    assert_eq!(
+        profile.encoding_for(None),
+        SegmentEncoding {
+            crf: profile.crf,
+            scale: 1.0
+        }
+    );
+}
+
+#[test]
+fn test_validate_app_override() {
+    let valid = AppOverride {
+        app: "Terminal".to_string(),
+        diff_threshold: Some(0.02),
+        crf: Some(18),
+        scale: Some(1.0),
+    };
+    assert!(valid.validate().is_ok());
+    assert!(AppOverride {
+        app: " ".to_string(),
+        ..valid.clone()
+    }
+    .validate()
+    .is_err());
+    assert!(AppOverride {
+        diff_threshold: Some(1.5),
+        ..valid.clone()
+    }
+    .validate()
+    .is_err());
+    assert!(AppOverride {
+        crf: Some(52),
+        ..valid.clone()
+    }
+    .validate()
+    .is_err());
+    for scale in [0.0, 1.5, f64::NAN] {
+        assert!(AppOverride {
+            scale: Some(scale),
+            ..valid.clone()
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use screenpipe_core::{normalize_for_matching, Blocklist, BlocklistMatching};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn blocklist(apps: &[&str], titles: &[&str], fuzzy_distance: usize) -> Blocklist {
+    Blocklist::new(
+        &strings(apps),
+        &strings(titles),
+        BlocklistMatching { fuzzy_distance },
+    )
+}
+
+#[test]
+fn test_normalize_for_matching() {
+    assert_eq!(normalize_for_matching("ＳＬＡＣＫ"), "slack");
+    assert_eq!(normalize_for_matching("ｼｰｸﾚｯﾄ ﾓｰﾄﾞ"), "シークレット モード");
+    assert_eq!(
+        normalize_for_matching("プライベート\u{3000}ウィンドウ"),
+        "プライベート ウィンドウ"
+    );
+    // full case folding, not only lowercasing
+    assert_eq!(
+        normalize_for_matching("Straße"),
+        normalize_for_matching("STRASSE")
+    );
+    assert_eq!(normalize_for_matching("  Secret\t\tNotes "), "secret notes");
+}
+
+#[test]
+fn test_blocks_cjk_titles_and_apps() {
+    let blocklist = blocklist(&["LINE", "カカオトーク"], &["秘密", "シークレット"], 0);
+
+    assert!(blocklist.is_blocked("ＬＩＮＥ", "トーク"));
+    assert!(blocklist.is_blocked("ｶｶｵﾄｰｸ", ""));
+    assert!(blocklist.is_blocked("Finder", "【秘密】議事録.docx"));
+    // half-width katakana in the title, full-width in the pattern
+    assert!(blocklist.is_blocked("Google Chrome", "新しいタブ - ｼｰｸﾚｯﾄ"));
+    assert_eq!(
+        blocklist.blocked_title("ｼｰｸﾚｯﾄ ﾓｰﾄﾞ"),
+        Some("シークレット".to_string())
+    );
+
+    assert!(!blocklist.is_blocked("Finder", "公開資料.pdf"));
+    // hiragana and katakana are different characters
+    assert!(!blocklist.is_blocked("Finder", "しーくれっと"));
+}
+
+#[test]
+fn test_ascii_matching_is_unchanged() {
+    let blocklist = blocklist(&["spotify", "slack"], &["private", "incognito"], 0);
+    assert!(blocklist.is_blocked("Spotify Premium", ""));
+    assert!(blocklist.is_blocked("Code", "New Incognito Tab"));
+    assert!(!blocklist.is_blocked("Code", "main.rs"));
+    // empty patterns never block everything
+    assert!(
+        !Blocklist::new(&strings(&["", " "]), &[], BlocklistMatching::default())
+            .is_blocked("Code", "main.rs")
+    );
+}
+
+#[test]
+fn test_fuzzy_matching() {
+    let exact = blocklist(&["discord"], &["incognito", "シークレット"], 0);
+    let fuzzy = blocklist(&["discord"], &["incognito", "シークレット"], 1);
+
+    for (app, title) in [
+        ("Discort", ""),
+        ("Code", "Incognlto window"),
+        ("Finder", "シ一クレット"),
+    ] {
+        assert!(!exact.is_blocked(app, title), "{} {}", app, title);
+        assert!(fuzzy.is_blocked(app, title), "{} {}", app, title);
+    }
+    assert!(!fuzzy.is_blocked("Code", "incorrect notion"));
+
+    // short patterns stay exact
+    let fuzzy = blocklist(&["line"], &[], 2);
+    assert!(fuzzy.is_blocked("LINE", ""));
+    assert!(!fuzzy.is_blocked("Lime", ""));
+}
@@ -0,0 +1,154 @@
+use chrono::{TimeZone, Utc};
+use screenpipe_core::{NamingTemplate, NamingValues, OutputNaming, RecordingSession};
+use std::path::PathBuf;
+
+fn values(end: bool) -> NamingValues<'static> {
+    let start = Utc.with_ymd_and_hms(2024, 11, 28, 1, 0, 0).unwrap();
+    NamingValues {
+        hostname: "work laptop",
+        session_id: "20241128T005900Z",
+        monitor_id: 2,
+        start,
+        end: end.then(|| Utc.with_ymd_and_hms(2024, 11, 28, 1, 30, 0).unwrap()),
+    }
+}
+
+#[test]
+fn test_render_template() {
+    let template: NamingTemplate = "{hostname}/{date}/{session_id}/monitor_{id}_{start}–{end}.mp4"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        template.render(&values(true)),
+        PathBuf::from("work_laptop/2024-11-28/20241128T005900Z")
+            .join("monitor_2_2024-11-28T01-00-00Z–2024-11-28T01-30-00Z.mp4")
+    );
+    // named as it ends
+    assert_eq!(
+        template.render(&values(false)).file_name().unwrap(),
+        "monitor_2_2024-11-28T01-00-00Z–recording.mp4"
+    );
+
+    let template: NamingTemplate = "{start:%Y/%m}/{id}_{time:%H%M%S}.jsonl".parse().unwrap();
+    assert_eq!(
+        template.render(&values(false)),
+        PathBuf::from("2024").join("11").join("2_010000.jsonl")
+    );
+}
+
+#[test]
+fn test_invalid_templates() {
+    for template in [
+        "monitor_{monitor}.mp4",
+        "monitor_{id.mp4",
+        "monitor_}{id}.mp4",
+        "/recordings/{id}_{start}.mp4",
+        "../{id}_{start}.mp4",
+        "{id}_{start:%H:%M}.mp4",
+        "{id}_{hostname:%Y}.mp4",
+        "{end}/{id}_{start}.mp4",
+    ] {
+        assert!(
+            template.parse::<NamingTemplate>().is_err(),
+            "{} was accepted",
+            template
+        );
+    }
+}
+
+#[test]
+fn test_validate_naming() {
+    assert!(OutputNaming::default().validate().is_ok());
+
+    let naming = |segment: &str, log: &str, export: &str| OutputNaming {
+        segment: segment.parse().unwrap(),
+        log: log.parse().unwrap(),
+        export: export.parse().unwrap(),
+    };
+    let valid = naming(
+        "{session_id}/monitor_{id}_{start}–{end}.mp4",
+        "{session_id}/monitor_{id}_{start}.jsonl",
+        "shots/{hostname}_{id}_{start}.jpg",
+    );
+    assert!(valid.validate().is_ok());
+
+    // monitors would overwrite each other's videos
+    let mut invalid = valid.clone();
+    invalid.segment = "{session_id}/{start}.mp4".parse().unwrap();
+    assert!(invalid.validate().is_err());
+    invalid.segment = "{session_id}/{id}_{start}.mkv".parse().unwrap();
+    assert!(invalid.validate().is_err());
+    // segments of the same day would
+    invalid.segment = "monitor_{id}_{start:%Y-%m-%d}.mp4".parse().unwrap();
+    assert!(invalid.validate().is_err());
+    invalid.segment = "{date}/monitor_{id}_{time}.mp4".parse().unwrap();
+    assert!(invalid.validate().is_ok());
+
+    // the log would be the session file
+    let mut invalid = valid.clone();
+    invalid.log = "{session_id}/monitor_{id}_{start}–{end}.session.json"
+        .parse()
+        .unwrap();
+    assert!(invalid.validate().is_err());
+
+    let mut invalid = valid;
+    invalid.export = "{session_id}/{id}_{start}.png".parse().unwrap();
+    assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn test_config_naming() {
+    let naming: OutputNaming =
+        serde_json::from_str(r#"{"segment": "{date}/monitor_{id}_{start}.mp4"}"#).unwrap();
+    assert_eq!(naming.segment.as_str(), "{date}/monitor_{id}_{start}.mp4");
+    assert_eq!(naming.log, OutputNaming::default().log);
+    assert!(serde_json::from_str::<OutputNaming>(r#"{"log": "{id}_{sart}.jsonl"}"#).is_err());
+}
+
+#[test]
+fn test_session_files() {
+    let naming = OutputNaming {
+        segment: "{session_id}/monitor_{id}_{start}–{end}.mp4"
+            .parse()
+            .unwrap(),
+        ..Default::default()
+    };
+    let mut session = RecordingSession::start(1, "20241128T005900Z", &naming);
+    let video = session.video_path();
+    assert!(video.starts_with("20241128T005900Z"));
+    assert!(video.to_string_lossy().ends_with("–recording.mp4"));
+    assert_eq!(
+        session.sidecar_path("frames"),
+        video.with_file_name(
+            video
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .replace(".mp4", ".frames")
+        )
+    );
+
+    // the session file follows the video as it's renamed at the end
+    let dir = std::env::temp_dir().join(format!("naming_test_{}", std::process::id()));
+    session.write(&dir).unwrap();
+    assert!(dir.join(session.sidecar_path("session.json")).exists());
+    let recorded = dir.join(session.sidecar_path("session.json"));
+    session.ended_at = Some(session.started_at + chrono::Duration::minutes(5));
+    session.write(&dir).unwrap();
+    assert!(!recorded.exists());
+    let written: RecordingSession = serde_json::from_str(
+        &std::fs::read_to_string(dir.join(session.sidecar_path("session.json"))).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(written, session);
+
+    // a segment starting within the same second is numbered apart
+    let next = RecordingSession {
+        sequence: 1,
+        ..session.clone()
+    };
+    assert_ne!(next.video_path(), session.video_path());
+    assert!(next.video_path().to_string_lossy().ends_with("_1.mp4"));
+    assert!(next.log_path().to_string_lossy().ends_with("_1.jsonl"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,33 @@
+use screenpipe_core::{BackpressurePolicy, RecordingEngine, RecordingProfile};
+
+#[test]
+fn test_backpressure_policy_names() {
+    assert_eq!(
+        BackpressurePolicy::default(),
+        BackpressurePolicy::DropOldest
+    );
+    assert_eq!(
+        serde_json::to_string(&BackpressurePolicy::DropOldest).unwrap(),
+        "\"drop_oldest\""
+    );
+    assert_eq!(
+        serde_json::from_str::<BackpressurePolicy>("\"block\"").unwrap(),
+        BackpressurePolicy::Block
+    );
+}
+
+#[test]
+fn test_queue_capacity_must_be_positive() {
+    let build = |queue_capacity| {
+        RecordingEngine::builder()
+            .profile(RecordingProfile {
+                queue_capacity,
+                backpressure: BackpressurePolicy::Block,
+                ..Default::default()
+            })
+            .activity_log(std::env::temp_dir())
+            .build()
+    };
+    assert!(build(0).is_err());
+    assert!(build(1).is_ok());
+}
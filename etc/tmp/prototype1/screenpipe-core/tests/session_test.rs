@@ -0,0 +1,33 @@
+use chrono::{TimeZone, Utc};
+use screenpipe_core::SessionTimeZone;
+
+#[test]
+fn test_wall_clock_follows_dst() {
+    // started in winter, CET
+    let paris = SessionTimeZone {
+        name: Some("Europe/Paris".to_string()),
+        utc_offset_secs: 3600,
+    };
+    let winter = Utc.with_ymd_and_hms(2024, 3, 31, 0, 30, 0).unwrap();
+    let summer = Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap();
+    assert_eq!(paris.wall_clock(winter).offset().local_minus_utc(), 3600);
+    assert_eq!(paris.wall_clock(summer).offset().local_minus_utc(), 7200);
+    assert_eq!(
+        paris.display(summer),
+        "2024-03-31 03:30:00 +02:00 (Europe/Paris)"
+    );
+
+    // without a zone name, the offset of the start is all there is
+    let unnamed = SessionTimeZone {
+        name: None,
+        utc_offset_secs: 3600,
+    };
+    assert_eq!(unnamed.display(summer), "2024-03-31 02:30:00 +01:00");
+
+    // an unknown name falls back to it too
+    let unknown = SessionTimeZone {
+        name: Some("Mars/Olympus".to_string()),
+        utc_offset_secs: 3600,
+    };
+    assert_eq!(unknown.wall_clock(summer).offset().local_minus_utc(), 3600);
+}
@@ -0,0 +1,23 @@
+use screenpipe_core::VirtualDesktop;
+
+#[test]
+fn test_private_desktop_designations() {
+    let desktop = VirtualDesktop {
+        id: "{A1B2C3D4-0000-4000-8000-00000000CAFE}".to_string(),
+        number: Some(3),
+        name: Some("プライベート".to_string()),
+    };
+    assert!(desktop.matches("{a1b2c3d4-0000-4000-8000-00000000cafe}"));
+    assert!(desktop.matches("3"));
+    assert!(desktop.matches(" ﾌﾟﾗｲﾍﾞｰﾄ "));
+    assert!(!desktop.matches("2"));
+    assert!(!desktop.matches(""));
+
+    let space = VirtualDesktop {
+        id: "42".to_string(),
+        number: None,
+        name: None,
+    };
+    assert!(space.matches("42"));
+    assert!(!space.matches("1"));
+}
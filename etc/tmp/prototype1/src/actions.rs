@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use screenpipe_core::{
+    hostname, write_atomically, ActivityMonitor, NamingValues, RecordingProfile, SafeMonitor,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Bookmarks, appended as JSONL in the recorder directory.
+pub const BOOKMARKS_FILE: &str = "bookmarks.jsonl";
+
+/// The screenshots taken, appended as JSONL in the recorder directory, for them to be found
+/// whatever the export naming was.
+pub const SCREENSHOTS_FILE: &str = "screenshots.jsonl";
+
+/// A moment marked while recording, to find it again in the videos and activity logs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub time: DateTime<Utc>,
+    /// Monitors being recorded, empty when nothing was.
+    pub monitor_ids: Vec<u32>,
+}
+
+/// A screenshot saved by [`save_screenshots`], as listed in [`SCREENSHOTS_FILE`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScreenshotRecord {
+    pub time: DateTime<Utc>,
+    /// Relative to the recorder directory.
+    pub path: PathBuf,
+}
+
+fn append_record(path: &Path, record: &impl Serialize) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+pub fn append_bookmark(dir: &Path, bookmark: &Bookmark) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    append_record(&dir.join(BOOKMARKS_FILE), bookmark)
+}
+
+/// What [`excise_actions`] removed.
+#[derive(Debug, Default, PartialEq)]
+pub struct ExcisedActions {
+    pub bookmarks: usize,
+    pub screenshots: usize,
+}
+
+/// Keeps the records of a JSONL file that `keep` returns true for, returns how many were
+/// dropped. Unreadable lines are kept as they are.
+fn retain_records<T: for<'de> Deserialize<'de>>(
+    path: &Path,
+    mut keep: impl FnMut(&T) -> bool,
+) -> Result<usize> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut kept = String::new();
+    let mut dropped = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<T>(line) {
+            Ok(record) if !keep(&record) => dropped += 1,
+            _ => {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+    }
+    if dropped > 0 {
+        write_atomically(path, kept.as_bytes())?;
+    }
+    Ok(dropped)
+}
+
+/// Removes the bookmarks and screenshots taken in `dir` from `start_time` on, along with the
+/// recordings. Screenshots taken before they were listed in [`SCREENSHOTS_FILE`] aren't found.
+pub fn excise_actions(dir: &Path, start_time: DateTime<Utc>) -> Result<ExcisedActions> {
+    let bookmarks = retain_records(&dir.join(BOOKMARKS_FILE), |bookmark: &Bookmark| {
+        bookmark.time < start_time
+    })?;
+    let mut removed = Vec::new();
+    let screenshots = retain_records(
+        &dir.join(SCREENSHOTS_FILE),
+        |screenshot: &ScreenshotRecord| {
+            if screenshot.time < start_time {
+                return true;
+            }
+            removed.push(dir.join(&screenshot.path));
+            false
+        },
+    )?;
+    for path in removed {
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e).with_context(|| format!("Failed to remove {}", path.display()));
+            }
+        }
+    }
+    Ok(ExcisedActions {
+        bookmarks,
+        screenshots,
+    })
+}
+
+/// Screenshots taken by [`save_screenshots`].
+#[derive(Debug, Default)]
+pub struct Screenshots {
+    pub saved: Vec<PathBuf>,
+    /// Monitors left out, for showing what `profile` doesn't record.
+    pub blocked: Vec<u32>,
+}
+
+/// Saves an image of each monitor into `dir`, named by the export naming of `profile`, in
+/// `screenshots/` by default. As in the recording, nothing is captured of a monitor while a
+/// window, desktop or private browsing window `profile` blocks is shown.
+pub async fn save_screenshots(
+    dir: &Path,
+    monitors: &[SafeMonitor],
+    profile: &RecordingProfile,
+) -> Result<Screenshots> {
+    let now = Utc::now();
+    let hostname = hostname();
+    let mut activity = ActivityMonitor::for_profile(profile);
+    let mut screenshots = Screenshots::default();
+    for monitor in monitors {
+        if !activity.check_activity(monitor.id(), false) {
+            screenshots.blocked.push(monitor.id());
+            continue;
+        }
+        let image = monitor.capture_image().await?;
+        let name = profile.naming.export.render(&NamingValues {
+            hostname: &hostname,
+            session_id: "",
+            monitor_id: monitor.id(),
+            start: now,
+            end: None,
+        });
+        let path = dir.join(&name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        image
+            .save(&path)
+            .with_context(|| format!("Failed to save {}", path.display()))?;
+        append_record(
+            &dir.join(SCREENSHOTS_FILE),
+            &ScreenshotRecord {
+                time: now,
+                path: name,
+            },
+        )?;
+        screenshots.saved.push(path);
+    }
+    Ok(screenshots)
+}
@@ -0,0 +1,221 @@
+use crate::hotkeys::HotkeyConfig;
+use anyhow::{Context, Result};
+use screenpipe_core::{
+    write_atomically, AppOverride, BackpressurePolicy, DiffMetric, OutputNaming, RecordingProfile,
+    Targets,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the app's config file, in [`recorder_dir`].
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Where logs and the config are kept, and the recordings unless the config puts them
+/// elsewhere: `$HOME/.work_recorder`.
+pub fn recorder_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|p| p.join(".work_recorder"))
+        .unwrap_or_else(|| PathBuf::from(".work_recorder"))
+}
+
+/// `config.json`, every key optional:
+///
+/// ```json
+/// {
+///   "logging": {"level": "info", "modules": {"screenpipe_core": "debug"}, "max_file_size_mb": 10, "max_files": 5},
+///   "hotkeys": {"start_stop": "Alt+Shift+R", "pause": "Alt+Shift+P", "bookmark": null, "screenshot": "CmdOrCtrl+F9"},
+///   "output_dir": "/Volumes/Archive/recordings",
+///   "monitor_ids": [1, 2],
+///   "fps": 1.0,
+///   "diff_metric": "lab",
+///   "diff_threshold": 0.006,
+///   "app_overrides": [{"app": "Terminal", "diff_threshold": 0.02, "crf": 18}, {"app": "VLC", "crf": 35, "scale": 0.5}],
+///   "backpressure": "drop_oldest",
+///   "ffmpeg_path": "/opt/homebrew/bin/ffmpeg",
+///   "excise_minutes": 5,
+///   "private_desktops": ["Private"],
+///   "naming": {"segment": "{hostname}/{date}/{session_id}/monitor_{id}_{start}–{end}.mp4", "log": "...", "export": "..."}
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub logging: LoggingConfig,
+    pub hotkeys: HotkeyConfig,
+    /// Where the recordings, bookmarks and screenshots go, [`recorder_dir`] when `None`.
+    pub output_dir: Option<PathBuf>,
+    /// Monitors recorded, by id, every connected one when empty.
+    pub monitor_ids: Vec<u32>,
+    /// Frames captured per second.
+    pub fps: f64,
+    /// How frames are compared to the last kept one: `luma`, `rgb` or `lab`.
+    pub diff_metric: DiffMetric,
+    /// Difference to the last kept frame, from 0 to 1 by `diff_metric`, below which a frame
+    /// is dropped as unchanged.
+    pub diff_threshold: f64,
+    /// Settings used in place of the ones above while an app is focused, the first matching
+    /// the app's name wins.
+    pub app_overrides: Vec<AppOverride>,
+    /// Whether capture drops the oldest queued frame, `drop_oldest`, or waits, `block`, when
+    /// the diff or the encoding falls behind.
+    pub backpressure: BackpressurePolicy,
+    /// The ffmpeg to encode with, looked for next to the app and in `PATH` when `None`.
+    pub ffmpeg_path: Option<PathBuf>,
+    /// How far back "Delete last minutes" deletes the recordings.
+    pub excise_minutes: u32,
+    /// How the recordings and screenshots are named in the output directory.
+    pub naming: OutputNaming,
+    /// Virtual desktops, Spaces on macOS, never captured while shown, by id, number or name.
+    pub private_desktops: Vec<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            logging: LoggingConfig::default(),
+            hotkeys: HotkeyConfig::default(),
+            output_dir: None,
+            monitor_ids: Vec::new(),
+            fps: 1.0,
+            diff_metric: RecordingProfile::default().diff_metric,
+            diff_threshold: RecordingProfile::default().diff_threshold,
+            app_overrides: Vec::new(),
+            backpressure: BackpressurePolicy::default(),
+            ffmpeg_path: None,
+            excise_minutes: 5,
+            naming: OutputNaming::default(),
+            private_desktops: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Level of the modules not in `modules`: `error`, `warn`, `info`, `debug` or `trace`.
+    pub level: String,
+    /// Levels by module path, e.g. `screenpipe_core::capture`.
+    pub modules: BTreeMap<String, String>,
+    /// Size past which the log file is rotated.
+    pub max_file_size_mb: u64,
+    /// Rotated log files kept besides the current one.
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::from([
+                ("prototype1".to_string(), "debug".to_string()),
+                ("screenpipe_core".to_string(), "debug".to_string()),
+            ]),
+            max_file_size_mb: 10,
+            max_files: 5,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// The levels as `tracing_subscriber::EnvFilter` directives.
+    pub fn directives(&self) -> String {
+        std::iter::once(self.level.clone())
+            .chain(
+                self.modules
+                    .iter()
+                    .map(|(module, level)| format!("{}={}", module, level)),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Whether the app hasn't been set up in `dir` yet, there being no config.
+pub fn is_first_run(dir: &Path) -> bool {
+    !dir.join(CONFIG_FILE).exists()
+}
+
+impl AppConfig {
+    /// Reads the config in `dir`, the defaults when there's none. An invalid config is
+    /// reported and ignored, so the app still starts, and so is a naming that wouldn't give
+    /// every file a name of its own, an fps that isn't positive, a diff threshold out of 0 to
+    /// 1 or an app override without an app, with such a threshold, a CRF over 51 or a scale
+    /// out of 0 to 1.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(CONFIG_FILE);
+        let mut config: Self = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                // logging isn't set up yet
+                eprintln!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        if let Err(e) = config.naming.validate() {
+            eprintln!("Ignoring invalid naming in {}: {:#}", path.display(), e);
+            config.naming = OutputNaming::default();
+        }
+        if !(config.fps > 0.0 && config.fps.is_finite()) {
+            eprintln!("Ignoring invalid fps in {}: {}", path.display(), config.fps);
+            config.fps = Self::default().fps;
+        }
+        if !(0.0..=1.0).contains(&config.diff_threshold) {
+            eprintln!(
+                "Ignoring invalid diff_threshold in {}: {}",
+                path.display(),
+                config.diff_threshold
+            );
+            config.diff_threshold = Self::default().diff_threshold;
+        }
+        config
+            .app_overrides
+            .retain(|app_override| match app_override.validate() {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!(
+                        "Ignoring invalid app override in {}: {:#}",
+                        path.display(),
+                        e
+                    );
+                    false
+                }
+            });
+        config
+    }
+
+    /// Writes the config into `dir`, as the setup does on the first run, replacing the
+    /// previous one at once so a crash never leaves half of it.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(CONFIG_FILE);
+        write_atomically(&path, serde_json::to_string_pretty(self)?.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn output_dir(&self) -> PathBuf {
+        self.output_dir.clone().unwrap_or_else(recorder_dir)
+    }
+
+    /// How the screens are recorded with this config.
+    pub fn recording_profile(&self) -> RecordingProfile {
+        RecordingProfile {
+            fps: self.fps,
+            diff_metric: self.diff_metric,
+            diff_threshold: self.diff_threshold,
+            app_overrides: self.app_overrides.clone(),
+            backpressure: self.backpressure,
+            naming: self.naming.clone(),
+            private_desktops: self.private_desktops.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn targets(&self) -> Targets {
+        if self.monitor_ids.is_empty() {
+            Targets::AllMonitors
+        } else {
+            Targets::Monitors(self.monitor_ids.clone())
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use crate::actions::{append_bookmark, excise_actions, save_screenshots, Bookmark};
+use crate::config::{recorder_dir, AppConfig};
+use crate::ipc::{endpoint, Connection, DaemonStatus, Listener, Request, Response};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use screenpipe_core::{
+    excise_recordings, list_monitors, RecordingEngine, RecordingEvent, RecordingHandle,
+    RecordingProfile, SessionTimeZone,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use tracing::{error, info, warn};
+
+/// Argument starting the app as the recorder daemon instead of the GUI.
+pub const DAEMON_ARG: &str = "--daemon";
+
+/// How long a connection has to send its request once connected.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the indicator counts as drawn after the GUI drawing it last asked for the status,
+/// which it does every half second while open.
+const INDICATOR_HEARTBEAT: Duration = Duration::from_secs(3);
+
+/// The recording engine, kept running in its own process so that closing or crashing the GUI
+/// doesn't interrupt a recording. The GUI controls it through [`crate::ipc`].
+#[derive(Default)]
+struct Daemon {
+    recording: Option<RecordingHandle>,
+    frames_written: Arc<AtomicU64>,
+    /// Whether the recording indicator is asked for, kept while no GUI draws it.
+    indicator_wanted: bool,
+    /// Until when the GUI that last asked for the status draws the indicator.
+    indicator_drawn_until: Option<Instant>,
+    /// Read when the daemon started.
+    config: AppConfig,
+}
+
+impl Daemon {
+    /// Whether the indicator is asked for and a GUI draws it.
+    fn indicator_drawn(&self) -> bool {
+        self.indicator_wanted
+            && self
+                .indicator_drawn_until
+                .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Tells the recording whether the indicator is drawn, for the activity logs to note
+    /// it only while a GUI shows it.
+    fn sync_indicator(&self) {
+        let drawn = self.indicator_drawn();
+        if let Some(recording) = &self.recording {
+            if recording.indicator_visible() != drawn {
+                recording.set_indicator_visible(drawn);
+            }
+        }
+    }
+
+    fn status(&self) -> DaemonStatus {
+        match &self.recording {
+            Some(recording) => DaemonStatus {
+                recording: true,
+                paused: recording.is_paused(),
+                indicator_visible: self.indicator_wanted,
+                indicator_drawn: recording.indicator_visible(),
+                monitor_ids: recording.monitor_ids().to_vec(),
+                frames_written: self.frames_written.load(Ordering::Relaxed),
+            },
+            None => DaemonStatus::default(),
+        }
+    }
+
+    /// Starts recording, `paused` until resumed.
+    async fn start(&mut self, show_indicator: bool, paused: bool) -> Result<String> {
+        if let Some(recording) = &self.recording {
+            return Ok(format!(
+                "Already recording {} monitor(s)",
+                recording.monitor_ids().len()
+            ));
+        }
+        if list_monitors().await.is_empty() {
+            return Err(anyhow!("No monitor to record"));
+        }
+        let output_dir = self.config.output_dir();
+        self.indicator_wanted = show_indicator;
+
+        self.frames_written.store(0, Ordering::Relaxed);
+        let frames_written = self.frames_written.clone();
+
+        // Start recording for all the configured monitors simultaneously
+        let recording = RecordingEngine::builder()
+            .targets(self.config.targets())
+            .profile(RecordingProfile {
+                show_indicator: self.indicator_drawn(),
+                start_paused: paused,
+                ..self.config.recording_profile()
+            })
+            .video_output(&output_dir)
+            .activity_log(&output_dir)
+            .subscribe(move |event| {
+                if let RecordingEvent::FrameWritten { .. } = event {
+                    frames_written.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build()?
+            .start()
+            .await?;
+        let message = format!("Recording {} monitor(s)", recording.monitor_ids().len());
+        self.recording = Some(recording);
+        Ok(message)
+    }
+
+    /// Stops the recording and waits for its videos to be finalized.
+    async fn stop(&mut self) -> Result<String> {
+        match self.recording.take() {
+            Some(recording) => {
+                recording.stop();
+                recording.join().await;
+                Ok("Stopped".to_string())
+            }
+            None => Ok("Not recording".to_string()),
+        }
+    }
+
+    fn recording(&self) -> Result<&RecordingHandle> {
+        self.recording
+            .as_ref()
+            .ok_or_else(|| anyhow!("Not recording"))
+    }
+
+    fn bookmark(&self) -> Result<String> {
+        let bookmark = Bookmark {
+            time: Utc::now(),
+            monitor_ids: self.status().monitor_ids,
+        };
+        append_bookmark(&self.config.output_dir(), &bookmark)?;
+        info!("Bookmarked {}", bookmark.time);
+        Ok(format!(
+            "Bookmarked {}",
+            SessionTimeZone::local_at(bookmark.time).display(bookmark.time)
+        ))
+    }
+
+    async fn screenshot(&self) -> Result<String> {
+        let screenshots = save_screenshots(
+            &self.config.output_dir(),
+            &list_monitors().await,
+            &self.config.recording_profile(),
+        )
+        .await?;
+        info!(
+            "Saved {} screenshot(s), {} monitor(s) blocked",
+            screenshots.saved.len(),
+            screenshots.blocked.len()
+        );
+        if screenshots.blocked.is_empty() {
+            Ok(format!("Saved {} screenshot(s)", screenshots.saved.len()))
+        } else {
+            Ok(format!(
+                "Saved {} screenshot(s), {} left out as blocked",
+                screenshots.saved.len(),
+                screenshots.blocked.len()
+            ))
+        }
+    }
+
+    /// Deletes the last minutes of the recordings. A recording going on is stopped for its
+    /// video to be trimmed, and started again after, paused if it was. It failing to start
+    /// again fails the request, the minutes deleted all the same.
+    async fn excise(&mut self, minutes: u32) -> Result<String> {
+        let start_time = Utc::now() - chrono::Duration::minutes(minutes as i64);
+        let restart = self
+            .recording
+            .as_ref()
+            .map(|recording| (self.indicator_wanted, recording.is_paused()));
+        self.stop().await?;
+
+        let output_dir = self.config.output_dir();
+        // re-encoded as they were recorded
+        let excised =
+            excise_recordings(&output_dir, start_time, self.config.recording_profile().crf).await;
+
+        let restarted = match restart {
+            Some((show_indicator, paused)) => self.start(show_indicator, paused).await.map(|_| ()),
+            None => Ok(()),
+        };
+        let deleted = excised.and_then(|redaction| {
+            let actions = excise_actions(&output_dir, start_time)?;
+            Ok(format!(
+                "Deleted the last {} minutes ({} files removed, {} trimmed, {} bookmarks, {} screenshots)",
+                minutes,
+                redaction.removed.len(),
+                redaction.trimmed.len(),
+                actions.bookmarks,
+                actions.screenshots
+            ))
+        });
+        match (deleted, restarted) {
+            (deleted, Ok(())) => deleted,
+            (Ok(message), Err(e)) => Err(anyhow!(
+                "{}, but the recording didn't start again: {:#}",
+                message,
+                e
+            )),
+            (Err(e), Err(restart)) => Err(anyhow!(
+                "{:#}, and the recording didn't start again: {:#}",
+                e,
+                restart
+            )),
+        }
+    }
+
+    async fn handle(&mut self, request: &Request) -> Response {
+        let result = match request {
+            Request::Status { draws_indicator } => {
+                if *draws_indicator {
+                    self.indicator_drawn_until = Some(Instant::now() + INDICATOR_HEARTBEAT);
+                }
+                Ok(String::new())
+            }
+            Request::Start { show_indicator } => self.start(*show_indicator, false).await,
+            Request::Stop | Request::Shutdown => self.stop().await,
+            Request::SetPaused { paused } => self.recording().map(|recording| {
+                recording.set_paused(*paused);
+                if *paused { "Paused" } else { "Resumed" }.to_string()
+            }),
+            Request::SetIndicatorVisible { visible } => self.recording().map(|_| {
+                if *visible {
+                    "Indicator shown"
+                } else {
+                    "Indicator hidden"
+                }
+                .to_string()
+            }),
+            Request::Bookmark => self.bookmark(),
+            Request::Screenshot => self.screenshot().await,
+            Request::Excise { minutes } => self.excise((*minutes).max(1)).await,
+        };
+        if let (Request::SetIndicatorVisible { visible }, Ok(_)) = (request, &result) {
+            self.indicator_wanted = *visible;
+        }
+        self.sync_indicator();
+        let (ok, message) = match result {
+            Ok(message) => (true, message),
+            Err(e) => {
+                error!("{:?} failed: {}", request, e);
+                (false, format!("{:#}", e))
+            }
+        };
+        Response {
+            ok,
+            message,
+            status: self.status(),
+        }
+    }
+}
+
+/// Reads the request of a connection, answers it and returns it.
+async fn answer(daemon: &mut Daemon, mut connection: Connection) -> Result<Option<Request>> {
+    // a client not sending its request would hold up every other one
+    let Some(request) = timeout(REQUEST_TIMEOUT, connection.read::<Request>()).await?? else {
+        return Ok(None);
+    };
+    if !matches!(request, Request::Status { .. }) {
+        info!("Request: {:?}", request);
+    }
+    let response = daemon.handle(&request).await;
+    if let Err(e) = connection.write(&response).await {
+        // the GUI went away meanwhile, the request was done anyway
+        warn!("Failed to answer {:?}: {}", request, e);
+    }
+    Ok(Some(request))
+}
+
+#[cfg(unix)]
+async fn terminated() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminated() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Runs the recorder daemon until it's asked to shut down or terminated, answering the GUI's
+/// requests one at a time, recording as `config` says. Fails when another daemon is already
+/// running.
+pub async fn run_daemon(config: AppConfig) -> Result<()> {
+    let endpoint = endpoint(&recorder_dir());
+    let mut listener = Listener::bind(&endpoint).await?;
+    info!("Recorder daemon listening at {}", endpoint.display());
+
+    let mut daemon = Daemon {
+        config,
+        ..Default::default()
+    };
+    let terminated = terminated();
+    tokio::pin!(terminated);
+    // the indicator is hidden once the GUI drawing it stops asking
+    let mut heartbeat = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = heartbeat.tick() => daemon.sync_indicator(),
+            connection = listener.accept() => {
+                let connection = match connection {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        warn!("Failed to accept a connection: {}", e);
+                        continue;
+                    }
+                };
+                match answer(&mut daemon, connection).await {
+                    Ok(Some(Request::Shutdown)) => break,
+                    Ok(_) => {}
+                    Err(e) => warn!("Invalid request: {}", e),
+                }
+            }
+            _ = &mut terminated => {
+                // finalizes the videos rather than leaving them cut off
+                daemon.stop().await?;
+                break;
+            }
+        }
+    }
+    info!("Recorder daemon stopped");
+    Ok(())
+}
@@ -1,61 +0,0 @@
-use image::DynamicImage;
-use image_compare::{Algorithm, Metric, Similarity};
-use tracing::debug;
-use std::hash::{DefaultHasher, Hash, Hasher};
-
-#[derive(Debug, Clone)]
-pub struct MaxAverageFrame {
-    pub frame_number: u64,
-    #[allow(dead_code)]
-    pub average: f64,
-}
-
-#[allow(dead_code)]
-pub fn calculate_hash(image: &DynamicImage) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    image.as_bytes().hash(&mut hasher);
-    hasher.finish()
-}
-
-pub fn compare_images_histogram(
-    image1: &DynamicImage,
-    image2: &DynamicImage,
-) -> anyhow::Result<f64> {
-    let image_one = image1.to_luma8();
-    let image_two = image2.to_luma8();
-    image_compare::gray_similarity_histogram(Metric::Hellinger, &image_one, &image_two)
-        .map_err(|e| anyhow::anyhow!("Failed to compare images: {}", e))
-}
-
-pub fn compare_images_ssim(image1: &DynamicImage, image2: &DynamicImage) -> f64 {
-    let image_one = image1.to_luma8();
-    let image_two = image2.to_luma8();
-    let result: Similarity =
-        image_compare::gray_similarity_structure(&Algorithm::MSSIMSimple, &image_one, &image_two)
-            .expect("Images had different dimensions");
-    result.score
-}
-
-pub fn compare_with_previous_image(
-    previous_image: Option<&DynamicImage>,
-    current_image: &DynamicImage,
-    max_average: &mut Option<MaxAverageFrame>,
-    frame_number: u64,
-    max_avg_value: &mut f64,
-) -> anyhow::Result<f64> {
-    let mut current_average = 0.0;
-    if let Some(prev_image) = previous_image {
-        let histogram_diff = compare_images_histogram(prev_image, current_image)?;
-        let ssim_diff = 1.0 - compare_images_ssim(prev_image, current_image);
-        current_average = (histogram_diff + ssim_diff) / 2.0;
-        let max_avg_frame_number = max_average.as_ref().map_or(0, |frame| frame.frame_number);
-        debug!(
-            "Frame {}: Histogram diff: {:.3}, SSIM diff: {:.3}, Current Average: {:.3}, Max_avr: {:.3} Fr: {}",
-            frame_number, histogram_diff, ssim_diff, current_average, *max_avg_value, max_avg_frame_number
-        );
-    } else {
-        debug!("No previous image to compare for frame {}", frame_number);
-    }
-    Ok(current_average)
-}
-
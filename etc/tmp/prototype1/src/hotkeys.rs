@@ -0,0 +1,165 @@
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver};
+use tracing::{info, warn};
+
+/// What a global hotkey does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HotkeyAction {
+    StartStop,
+    Pause,
+    Bookmark,
+    Screenshot,
+    /// Deletes the last minutes of the recordings, see `excise_minutes` in `config.json`.
+    Excise,
+}
+
+impl HotkeyAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::StartStop => "Start/stop recording",
+            HotkeyAction::Pause => "Pause/resume",
+            HotkeyAction::Bookmark => "Bookmark",
+            HotkeyAction::Screenshot => "Screenshot",
+            HotkeyAction::Excise => "Delete last minutes",
+        }
+    }
+}
+
+/// `hotkeys` in `config.json`: a binding per action such as `Alt+Shift+R` or `CmdOrCtrl+F9`,
+/// `null` to leave the action unbound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeyConfig {
+    pub start_stop: Option<String>,
+    pub pause: Option<String>,
+    pub bookmark: Option<String>,
+    pub screenshot: Option<String>,
+    pub excise: Option<String>,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            start_stop: Some("Alt+Shift+R".to_string()),
+            pause: Some("Alt+Shift+P".to_string()),
+            bookmark: Some("Alt+Shift+B".to_string()),
+            screenshot: Some("Alt+Shift+S".to_string()),
+            excise: Some("Alt+Shift+D".to_string()),
+        }
+    }
+}
+
+impl HotkeyConfig {
+    fn bindings(&self) -> Vec<(HotkeyAction, &str)> {
+        [
+            (HotkeyAction::StartStop, &self.start_stop),
+            (HotkeyAction::Pause, &self.pause),
+            (HotkeyAction::Bookmark, &self.bookmark),
+            (HotkeyAction::Screenshot, &self.screenshot),
+            (HotkeyAction::Excise, &self.excise),
+        ]
+        .into_iter()
+        .filter_map(|(action, binding)| binding.as_deref().map(|binding| (action, binding)))
+        .collect()
+    }
+}
+
+/// Hotkeys registered with the OS, firing even when the app's window isn't focused.
+pub struct Hotkeys {
+    // unregisters the hotkeys when dropped
+    _manager: Option<GlobalHotKeyManager>,
+    actions: HashMap<u32, HotkeyAction>,
+    events: Receiver<GlobalHotKeyEvent>,
+    /// Bindings that couldn't be registered: invalid, bound twice, or taken by another app.
+    pub conflicts: Vec<String>,
+    /// Registered bindings, for display.
+    pub bound: Vec<(HotkeyAction, String)>,
+}
+
+impl Hotkeys {
+    /// Registers the bindings of `config`, calling `wake` on every hotkey press so the app
+    /// handles it without waiting for its next frame. Must be called on the main thread.
+    pub fn register(config: &HotkeyConfig, wake: impl Fn() + Send + Sync + 'static) -> Self {
+        let (tx, events) = channel();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            let _ = tx.send(event);
+            wake();
+        }));
+
+        let mut hotkeys = Self {
+            _manager: None,
+            actions: HashMap::new(),
+            events,
+            conflicts: Vec::new(),
+            bound: Vec::new(),
+        };
+        let manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => manager,
+            Err(e) => {
+                // e.g. on Wayland, which has no global hotkeys
+                warn!("Global hotkeys unavailable: {}", e);
+                hotkeys
+                    .conflicts
+                    .push(format!("global hotkeys unavailable: {}", e));
+                return hotkeys;
+            }
+        };
+
+        let mut bound_to: HashMap<u32, HotkeyAction> = HashMap::new();
+        for (action, binding) in config.bindings() {
+            let hotkey = match HotKey::from_str(binding) {
+                Ok(hotkey) => hotkey,
+                Err(e) => {
+                    hotkeys.conflicts.push(format!(
+                        "{}: invalid binding {:?}: {}",
+                        action.label(),
+                        binding,
+                        e
+                    ));
+                    continue;
+                }
+            };
+            // spellings of the same keys, e.g. `Shift+Alt+R` and `alt+shift+KeyR`, share an id
+            if let Some(other) = bound_to.get(&hotkey.id()) {
+                hotkeys.conflicts.push(format!(
+                    "{}: {} is already bound to {}",
+                    action.label(),
+                    binding,
+                    other.label()
+                ));
+                continue;
+            }
+            if let Err(e) = manager.register(hotkey) {
+                hotkeys.conflicts.push(format!(
+                    "{}: {} is taken by another app: {}",
+                    action.label(),
+                    binding,
+                    e
+                ));
+                continue;
+            }
+            info!("Bound {} to {}", binding, action.label());
+            bound_to.insert(hotkey.id(), action);
+            hotkeys.bound.push((action, binding.to_string()));
+        }
+        for conflict in &hotkeys.conflicts {
+            warn!("Hotkey not bound: {}", conflict);
+        }
+        hotkeys.actions = bound_to;
+        hotkeys._manager = Some(manager);
+        hotkeys
+    }
+
+    /// Actions of the hotkeys pressed since the last call.
+    pub fn pressed(&self) -> Vec<HotkeyAction> {
+        self.events
+            .try_iter()
+            .filter(|event| event.state == HotKeyState::Pressed)
+            .filter_map(|event| self.actions.get(&event.id).copied())
+            .collect()
+    }
+}
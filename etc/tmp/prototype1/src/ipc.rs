@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Socket of the recorder daemon, in the recorder directory.
+#[cfg(unix)]
+pub const SOCKET_FILE: &str = "recorder.sock";
+
+/// Named pipe of the recorder daemon.
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\work_recorder";
+
+/// Where the daemon of the recorder directory `dir` listens: a Unix socket in it, or the named
+/// pipe on Windows.
+pub fn endpoint(dir: &Path) -> PathBuf {
+    #[cfg(unix)]
+    return dir.join(SOCKET_FILE);
+    #[cfg(windows)]
+    {
+        let _ = dir;
+        PathBuf::from(PIPE_NAME)
+    }
+}
+
+/// What the GUI asks the daemon, one request per connection, as a JSON line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    /// The daemon's status, from a GUI drawing the recording indicator while it's asked for
+    /// with `draws_indicator`: the recording only notes it shown while such a GUI keeps
+    /// asking.
+    Status {
+        #[serde(default)]
+        draws_indicator: bool,
+    },
+    Start {
+        show_indicator: bool,
+    },
+    Stop,
+    SetPaused {
+        paused: bool,
+    },
+    SetIndicatorVisible {
+        visible: bool,
+    },
+    Bookmark,
+    Screenshot,
+    /// Deletes the last `minutes` of the recordings, see `excise_recordings`.
+    Excise {
+        minutes: u32,
+    },
+    /// Stops the recording and the daemon.
+    Shutdown,
+}
+
+/// The recording of the daemon, as given with every response.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub recording: bool,
+    pub paused: bool,
+    /// Whether the recording indicator is asked for, for the GUI to draw.
+    pub indicator_visible: bool,
+    /// Whether a GUI draws it, as the activity logs note.
+    #[serde(default)]
+    pub indicator_drawn: bool,
+    /// Monitors being recorded, empty when nothing is.
+    pub monitor_ids: Vec<u32>,
+    /// Frames written since the recording started.
+    pub frames_written: u64,
+}
+
+/// The daemon's answer to a [`Request`], as a JSON line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    /// What the request did, or why it failed, to show in the GUI.
+    pub message: String,
+    /// The status after the request.
+    pub status: DaemonStatus,
+}
+
+/// Sends `request` to the daemon listening at `endpoint`, waiting `timeout` at most for its
+/// response.
+pub async fn send(endpoint: &Path, request: &Request, timeout: Duration) -> Result<Response> {
+    tokio::time::timeout(timeout, async {
+        let mut connection = Connection::new(connect(endpoint).await?);
+        connection.write(request).await?;
+        connection
+            .read::<Response>()
+            .await?
+            .context("the recorder daemon closed the connection")
+    })
+    .await
+    .context("the recorder daemon didn't answer in time")?
+}
+
+#[cfg(unix)]
+async fn connect(endpoint: &Path) -> Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(endpoint)
+        .await
+        .with_context(|| format!("no recorder daemon at {}", endpoint.display()))
+}
+
+#[cfg(windows)]
+async fn connect(endpoint: &Path) -> Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+    // ERROR_PIPE_BUSY: every instance is taken, until the daemon creates the next one
+    const ERROR_PIPE_BUSY: i32 = 231;
+    loop {
+        match ClientOptions::new().open(endpoint) {
+            Ok(client) => return Ok(client),
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {
+                tokio::time::sleep(Duration::from_millis(50)).await
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("no recorder daemon at {}", endpoint.display()))
+            }
+        }
+    }
+}
+
+/// A connection speaking JSON lines, on either end.
+pub struct Connection {
+    reader: BufReader<Box<dyn AsyncRead + Unpin + Send>>,
+    writer: Box<dyn AsyncWrite + Unpin + Send>,
+}
+
+impl Connection {
+    fn new<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(stream: S) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        Self {
+            reader: BufReader::new(Box::new(reader)),
+            writer: Box::new(writer),
+        }
+    }
+
+    /// Reads the next message, `None` once the other end closed the connection.
+    pub async fn read<T: for<'de> Deserialize<'de>>(&mut self) -> Result<Option<T>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(
+            serde_json::from_str(&line).context("invalid message from the recorder")?,
+        ))
+    }
+
+    pub async fn write<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+/// The daemon's end, accepting the GUI's connections. Only one daemon listens at an endpoint.
+pub struct Listener {
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    #[cfg(windows)]
+    next: tokio::net::windows::named_pipe::NamedPipeServer,
+    #[cfg(windows)]
+    endpoint: PathBuf,
+}
+
+impl Listener {
+    /// Listens at `endpoint`, failing when another daemon already does. A socket left behind
+    /// by a daemon that crashed is replaced.
+    #[cfg(unix)]
+    pub async fn bind(endpoint: &Path) -> Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+
+        if endpoint.exists() {
+            if tokio::net::UnixStream::connect(endpoint).await.is_ok() {
+                anyhow::bail!(
+                    "a recorder daemon already listens at {}",
+                    endpoint.display()
+                );
+            }
+            std::fs::remove_file(endpoint)
+                .with_context(|| format!("Failed to remove {}", endpoint.display()))?;
+        }
+        if let Some(dir) = endpoint.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let listener = tokio::net::UnixListener::bind(endpoint)
+            .with_context(|| format!("Failed to listen at {}", endpoint.display()))?;
+        // only the user's own GUI may control the recorder
+        std::fs::set_permissions(endpoint, std::fs::Permissions::from_mode(0o600))?;
+        Ok(Self { listener })
+    }
+
+    #[cfg(windows)]
+    pub async fn bind(endpoint: &Path) -> Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(endpoint)
+            .with_context(|| {
+                format!(
+                    "a recorder daemon already listens at {}, or it can't be created",
+                    endpoint.display()
+                )
+            })?;
+        Ok(Self {
+            next,
+            endpoint: endpoint.to_path_buf(),
+        })
+    }
+
+    pub async fn accept(&mut self) -> Result<Connection> {
+        #[cfg(unix)]
+        {
+            let (stream, _) = self.listener.accept().await?;
+            Ok(Connection::new(stream))
+        }
+        #[cfg(windows)]
+        {
+            use tokio::net::windows::named_pipe::ServerOptions;
+
+            self.next.connect().await?;
+            // the next client connects to a new instance of the pipe
+            let next = ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(&self.endpoint)?;
+            Ok(Connection::new(std::mem::replace(&mut self.next, next)))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Ok(address) = self.listener.local_addr() {
+            if let Some(path) = address.as_pathname() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::config::{LoggingConfig, CONFIG_FILE};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Current log file of the GUI, in the logs directory. Rotated ones get `.1`, `.2`...
+/// appended, `.1` being the most recent.
+pub const LOG_FILE: &str = "work_recorder.log";
+
+/// Current log file of the recorder daemon, rotated the same way.
+pub const DAEMON_LOG_FILE: &str = "work_recorder_daemon.log";
+
+/// What the names of the log files start with, the GUI's and the daemon's.
+const LOG_FILE_PREFIX: &str = "work_recorder";
+
+/// Fields of the log events left out of the diagnostics bundle. Window titles are only ever
+/// logged in `window_title`, never in the message, for them to be found here.
+const REDACTED_FIELDS: &[&str] = &["window_title"];
+
+const REDACTED: &str = "[redacted]";
+
+/// A log file rotated once it would grow past `max_bytes`, keeping `max_files` old ones.
+pub struct RotatingFile {
+    dir: PathBuf,
+    name: String,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path, name: &str, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(name))?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            max_bytes,
+            max_files,
+            file,
+            written,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.name, index))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            self.file = File::create(self.dir.join(&self.name))?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let _ = std::fs::rename(self.rotated(index), self.rotated(index + 1));
+            }
+            std::fs::rename(self.dir.join(&self.name), self.rotated(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.dir.join(&self.name))?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // an event is written at once, so lines aren't split across files
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Logs to the console and, as JSON lines, to `file_name` in `logs_dir`, rotated, at the
+/// levels of `config`. When the log file can't be opened, logs go to the console alone and
+/// the error is returned.
+pub fn init_logging(config: &LoggingConfig, logs_dir: &Path, file_name: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(config.directives()).unwrap_or_else(|e| {
+        eprintln!("Invalid log levels {:?}: {}", config.directives(), e);
+        EnvFilter::new("info")
+    });
+    let file = match RotatingFile::open(
+        logs_dir,
+        file_name,
+        config.max_file_size_mb.max(1) * 1024 * 1024,
+        config.max_files,
+    ) {
+        Ok(file) => file,
+        Err(e) => {
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(fmt::layer().with_writer(io::stderr))
+                .try_init()
+                .context("logging already set up")?;
+            return Err(anyhow::Error::from(e)
+                .context(format!("Failed to open log file in {}", logs_dir.display())));
+        }
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(
+            fmt::layer()
+                .json()
+                .with_current_span(false)
+                .with_writer(Mutex::new(file)),
+        )
+        .try_init()
+        .context("logging already set up")?;
+    Ok(())
+}
+
+/// A JSON log line with the values of its [`REDACTED_FIELDS`] replaced. A line that isn't
+/// JSON, from a file written by someone else, is replaced whole.
+pub fn redact_log_line(line: &str) -> String {
+    let Ok(mut event) = serde_json::from_str::<serde_json::Value>(line) else {
+        return REDACTED.to_string();
+    };
+    let redact = |fields: Option<&mut serde_json::Value>| {
+        let Some(fields) = fields.and_then(|fields| fields.as_object_mut()) else {
+            return;
+        };
+        for name in REDACTED_FIELDS {
+            if let Some(value) = fields.get_mut(*name) {
+                *value = REDACTED.into();
+            }
+        }
+    };
+    redact(event.get_mut("fields"));
+    // the fields of its spans, when they're logged
+    if let Some(spans) = event
+        .get_mut("spans")
+        .and_then(|spans| spans.as_array_mut())
+    {
+        spans.iter_mut().for_each(|span| redact(Some(span)));
+    }
+    event.to_string()
+}
+
+/// Zips the logs of the GUI and the daemon in `logs_dir`, window titles redacted, and the
+/// config in `dir` into `dir/diagnostics_{now}.zip`, to attach to bug reports. Returns the
+/// path of the zip.
+pub fn write_diagnostics_bundle(dir: &Path, logs_dir: &Path) -> Result<PathBuf> {
+    let path = dir.join(format!(
+        "diagnostics_{}.zip",
+        Utc::now().format("%Y-%m-%dT%H-%M-%SZ")
+    ));
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    let options = SimpleFileOptions::default();
+
+    let logs: Vec<(String, PathBuf)> = std::fs::read_dir(logs_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(LOG_FILE_PREFIX))
+        })
+        .map(|path| {
+            let name = format!("logs/{}", path.file_name().unwrap().to_string_lossy());
+            (name, path)
+        })
+        .collect();
+    for (name, file) in logs {
+        zip.start_file(name, options)?;
+        for line in BufReader::new(File::open(&file)?).lines() {
+            let line = line.unwrap_or_default();
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(zip, "{}", redact_log_line(&line))?;
+        }
+    }
+    let config = dir.join(CONFIG_FILE);
+    if config.exists() {
+        zip.start_file(CONFIG_FILE, options)?;
+        io::copy(&mut File::open(&config)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(path)
+}
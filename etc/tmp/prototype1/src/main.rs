@@ -1,61 +1,429 @@
+mod actions;
+mod config;
+mod daemon;
+mod hotkeys;
+mod ipc;
+mod logging;
+mod setup;
+
+use config::{is_first_run, recorder_dir, AppConfig};
+use daemon::{run_daemon, DAEMON_ARG};
 use eframe::egui;
-use tokio::sync::broadcast;
-use tracing::{info, error, Level};
-use tracing_subscriber::FmtSubscriber;
-use std::path::PathBuf;
-use crate::capture::{Recorder, list_monitors, SafeMonitor};
+use hotkeys::{HotkeyAction, Hotkeys};
+use ipc::{DaemonStatus, Request, Response};
+use logging::{init_logging, write_diagnostics_bundle, DAEMON_LOG_FILE, LOG_FILE};
+use screenpipe_core::{list_monitors, set_ffmpeg_path, SafeMonitor};
+use setup::SetupWizard;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
 
-mod capture;
-mod encode;
-mod diff;
-mod activity; // 追加
+/// How often the GUI asks the daemon for its status.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a status request may take.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long any other request may take, but deleting the last minutes.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long deleting the last minutes may take, videos being re-encoded.
+const EXCISE_TIMEOUT: Duration = Duration::from_secs(600);
+/// How long a daemon started by the GUI has to answer.
+const DAEMON_STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
 
 fn main() -> eframe::Result<()> {
-    // Setup logging
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::DEBUG)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)
-        .expect("setting default subscriber failed");
+    let config = AppConfig::load(&recorder_dir());
+    let is_daemon = std::env::args().any(|arg| arg == DAEMON_ARG);
+    let log_file = if is_daemon { DAEMON_LOG_FILE } else { LOG_FILE };
+    if let Err(e) = init_logging(&config.logging, &recorder_dir().join("logs"), log_file) {
+        eprintln!("Failed to set up logging: {:#}", e);
+    }
+    set_ffmpeg_path(config.ffmpeg_path.clone());
+
+    if is_daemon {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        if let Err(e) = rt.block_on(run_daemon(config.clone())) {
+            error!("Recorder daemon failed: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([400.0, 300.0]),
+        viewport: egui::ViewportBuilder::default().with_inner_size([400.0, 460.0]),
         ..Default::default()
     };
 
     eframe::run_native(
         "Screenpipe Prototype 1",
         options,
-        Box::new(|_cc| Ok(Box::new(MyApp::new()))),
+        Box::new(|cc| Ok(Box::new(MyApp::new(&cc.egui_ctx, &config)))),
     )
 }
 
+/// Starts the recorder daemon, detached from the GUI so that it outlives it.
+fn spawn_daemon() -> std::io::Result<()> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg(DAEMON_ARG)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // not killed with the GUI's process group, by a Ctrl+C in its terminal
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    command.spawn()?;
+    Ok(())
+}
+
+async fn daemon_answers(endpoint: &Path) -> bool {
+    let request = Request::Status {
+        draws_indicator: false,
+    };
+    ipc::send(endpoint, &request, STATUS_TIMEOUT).await.is_ok()
+}
+
+/// Starts the daemon unless it's running, waiting for it to answer. With `restart`, a daemon
+/// running read the previous config and is shut down first, unless it's recording. Returns
+/// what to show, or why it isn't reachable.
+async fn start_daemon(endpoint: PathBuf, restart: bool) -> Result<String, String> {
+    if restart {
+        let request = Request::Status {
+            draws_indicator: false,
+        };
+        if let Ok(response) = ipc::send(&endpoint, &request, STATUS_TIMEOUT).await {
+            if response.status.recording {
+                return Ok("Set up, applies once the recorder is restarted".to_string());
+            }
+            let _ = ipc::send(&endpoint, &Request::Shutdown, REQUEST_TIMEOUT).await;
+            let deadline = Instant::now() + DAEMON_STARTUP_TIMEOUT;
+            while daemon_answers(&endpoint).await && Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+    if daemon_answers(&endpoint).await {
+        return Ok("Ready".to_string());
+    }
+    info!("Starting the recorder daemon");
+    if let Err(e) = spawn_daemon() {
+        error!("Failed to start the recorder daemon: {}", e);
+        return Err(format!("Failed to start the recorder: {}", e));
+    }
+    let deadline = Instant::now() + DAEMON_STARTUP_TIMEOUT;
+    while Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if daemon_answers(&endpoint).await {
+            return Ok("Ready".to_string());
+        }
+    }
+    error!("The recorder daemon didn't answer after starting it");
+    Err(format!(
+        "The recorder didn't start, see logs/{}",
+        DAEMON_LOG_FILE
+    ))
+}
+
+/// What the task behind `receiver` sent, clearing it once it did: `None` while it runs,
+/// `Some(None)` when it ended without sending.
+fn take_sent<T>(receiver: &mut Option<Receiver<T>>) -> Option<Option<T>> {
+    let sent = match receiver.as_ref()?.try_recv() {
+        Ok(value) => Some(value),
+        Err(TryRecvError::Empty) => return None,
+        Err(TryRecvError::Disconnected) => None,
+    };
+    *receiver = None;
+    Some(sent)
+}
+
+/// Controls the recorder daemon, which does the recording, over [`ipc`]. Closing the GUI
+/// leaves a recording going on. Requests are sent off the UI thread, their responses taken
+/// by [`MyApp::poll_responses`].
 struct MyApp {
     monitors: Vec<SafeMonitor>,
-    is_recording: bool,
-    stop_tx: Option<broadcast::Sender<()>>,
     rt: tokio::runtime::Runtime,
+    endpoint: PathBuf,
+    /// Last status of the daemon, `None` while it isn't reachable.
+    daemon: Option<DaemonStatus>,
+    last_poll: Instant,
     status: String,
+    show_indicator: bool,
+    hotkeys: Hotkeys,
+    excise_minutes: u32,
+    config: AppConfig,
+    /// Shown instead of the controls until the app is set up, the recorder only started after.
+    setup: Option<SetupWizard>,
+    /// The daemon being started.
+    starting: Option<Receiver<Result<String, String>>>,
+    /// The status asked for.
+    polling: Option<Receiver<Option<DaemonStatus>>>,
+    /// The request sent, with its response once it's there.
+    request: Option<Receiver<(Request, Result<Response, String>)>>,
 }
 
 impl MyApp {
-    fn new() -> Self {
+    fn new(ctx: &egui::Context, config: &AppConfig) -> Self {
         let rt = tokio::runtime::Runtime::new().unwrap();
         let monitors = rt.block_on(list_monitors());
-        
-        Self {
+        let repaint = ctx.clone();
+        let hotkeys = Hotkeys::register(&config.hotkeys, move || repaint.request_repaint());
+        let setup =
+            is_first_run(&recorder_dir()).then(|| SetupWizard::new(config.clone(), &monitors));
+
+        let mut app = Self {
             monitors,
-            is_recording: false,
-            stop_tx: None,
             rt,
+            endpoint: ipc::endpoint(&recorder_dir()),
+            daemon: None,
+            last_poll: Instant::now(),
             status: "Ready".to_string(),
+            show_indicator: false,
+            hotkeys,
+            excise_minutes: config.excise_minutes.max(1),
+            config: config.clone(),
+            setup,
+            starting: None,
+            polling: None,
+            request: None,
+        };
+        if app.setup.is_none() {
+            app.connect(ctx, false);
+        }
+        app
+    }
+
+    /// Records as `config`, just written by the setup, says. A daemon already running read
+    /// the previous config, so it's restarted unless it's recording.
+    fn finish_setup(&mut self, ctx: &egui::Context, config: AppConfig) {
+        self.setup = None;
+        self.excise_minutes = config.excise_minutes.max(1);
+        self.config = config;
+        self.connect(ctx, true);
+    }
+
+    /// Connects to the daemon, starting it when it isn't running or, with `restart`, starting
+    /// it again unless it's recording. The daemon is waited for off the UI thread, by
+    /// [`MyApp::poll_starting`].
+    fn connect(&mut self, ctx: &egui::Context, restart: bool) {
+        if self.starting.is_some() {
+            return;
+        }
+        self.status = "Starting the recorder...".to_string();
+        let (tx, rx) = channel();
+        let endpoint = self.endpoint.clone();
+        let repaint = ctx.clone();
+        self.rt.spawn(async move {
+            let _ = tx.send(start_daemon(endpoint, restart).await);
+            repaint.request_repaint();
+        });
+        self.starting = Some(rx);
+    }
+
+    /// Takes the status of the daemon once it's started.
+    fn poll_starting(&mut self, ctx: &egui::Context) {
+        let Some(result) = take_sent(&mut self.starting) else {
+            return;
+        };
+        match result.unwrap_or_else(|| Err("The recorder didn't start".to_string())) {
+            Ok(message) => {
+                self.status = message;
+                self.poll_status(ctx);
+            }
+            Err(e) => self.status = e,
+        }
+    }
+
+    /// Asks the daemon for its status, unless already waiting for it or for a request the
+    /// daemon is busy with.
+    fn poll_status(&mut self, ctx: &egui::Context) {
+        if self.polling.is_some() || self.request.is_some() {
+            return;
+        }
+        self.last_poll = Instant::now();
+        // this GUI draws the indicator while recording
+        let request = Request::Status {
+            draws_indicator: true,
+        };
+        let (tx, rx) = channel();
+        let endpoint = self.endpoint.clone();
+        let repaint = ctx.clone();
+        self.rt.spawn(async move {
+            let response = ipc::send(&endpoint, &request, STATUS_TIMEOUT).await;
+            let _ = tx.send(response.ok().map(|response| response.status));
+            repaint.request_repaint();
+        });
+        self.polling = Some(rx);
+    }
+
+    /// Sends `request` to the daemon, a spinner shown until its response. Another request
+    /// meanwhile is dropped, the daemon answering one at a time.
+    fn send(&mut self, ctx: &egui::Context, request: Request) {
+        if self.request.is_some() {
+            info!("Dropping {:?}, the recorder is busy", request);
+            self.status = "Waiting for the recorder...".to_string();
+            return;
+        }
+        let timeout = match request {
+            Request::Excise { .. } => EXCISE_TIMEOUT,
+            _ => REQUEST_TIMEOUT,
+        };
+        let (tx, rx) = channel();
+        let endpoint = self.endpoint.clone();
+        let repaint = ctx.clone();
+        self.rt.spawn(async move {
+            let response = ipc::send(&endpoint, &request, timeout).await.map_err(|e| {
+                error!("Failed to send {:?} to the recorder: {:#}", request, e);
+                format!("Recorder not reachable: {:#}", e)
+            });
+            let _ = tx.send((request, response));
+            repaint.request_repaint();
+        });
+        self.request = Some(rx);
+    }
+
+    /// Takes the status and the response to the request once they're there.
+    fn poll_responses(&mut self) {
+        if let Some(status) = take_sent(&mut self.polling) {
+            // the response to a request sent meanwhile has the newer status
+            if self.request.is_none() {
+                self.daemon = status.flatten();
+            }
+        }
+        let Some(sent) = take_sent(&mut self.request) else {
+            return;
+        };
+        self.last_poll = Instant::now();
+        match sent {
+            Some((request, Ok(response))) => {
+                self.status = response.message;
+                self.daemon = (request != Request::Shutdown).then_some(response.status);
+            }
+            Some((_, Err(e))) => {
+                self.status = e;
+                self.daemon = None;
+            }
+            None => self.status = "The request to the recorder was dropped".to_string(),
+        }
+    }
+
+    fn recording(&self) -> Option<&DaemonStatus> {
+        self.daemon.as_ref().filter(|daemon| daemon.recording)
+    }
+
+    fn run(&mut self, ctx: &egui::Context, action: HotkeyAction) {
+        info!("Hotkey: {}", action.label());
+        match action {
+            HotkeyAction::StartStop => {
+                if self.recording().is_some() {
+                    self.send(ctx, Request::Stop);
+                } else {
+                    self.send(
+                        ctx,
+                        Request::Start {
+                            show_indicator: self.show_indicator,
+                        },
+                    );
+                }
+            }
+            HotkeyAction::Pause => {
+                if let Some(paused) = self.recording().map(|recording| recording.paused) {
+                    self.send(ctx, Request::SetPaused { paused: !paused });
+                }
+            }
+            HotkeyAction::Bookmark => self.send(ctx, Request::Bookmark),
+            HotkeyAction::Screenshot => self.send(ctx, Request::Screenshot),
+            HotkeyAction::Excise => self.send(
+                ctx,
+                Request::Excise {
+                    minutes: self.excise_minutes,
+                },
+            ),
         }
     }
 }
 
+/// Always on top, translucent and click-through window in the corner of a recorded monitor,
+/// so whoever sees the screen can tell it's being recorded.
+fn show_indicator(ctx: &egui::Context, monitor: &SafeMonitor) {
+    let scale = monitor.scale_factor().max(1.0);
+    let position = egui::pos2(
+        monitor.x() as f32 / scale + 16.0,
+        monitor.y() as f32 / scale + 16.0,
+    );
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of(("recording_indicator", monitor.id())),
+        egui::ViewportBuilder::default()
+            .with_title("Recording")
+            .with_position(position)
+            .with_inner_size([180.0, 28.0])
+            .with_decorations(false)
+            .with_transparent(true)
+            .with_always_on_top()
+            .with_mouse_passthrough(true)
+            .with_taskbar(false)
+            .with_resizable(false),
+        |ctx, _| {
+            let frame = egui::Frame::none()
+                .fill(egui::Color32::from_black_alpha(140))
+                .rounding(14.0)
+                .inner_margin(egui::Margin::symmetric(10.0, 6.0));
+            egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let (dot, _) =
+                        ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                    ui.painter().circle_filled(
+                        dot.center(),
+                        6.0,
+                        egui::Color32::from_rgb(230, 40, 40),
+                    );
+                    ui.label(
+                        egui::RichText::new(monitor.name())
+                            .color(egui::Color32::WHITE)
+                            .small(),
+                    );
+                });
+            });
+        },
+    );
+}
+
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(setup) = self.setup.as_mut() {
+            // the recorder is controlled once it's set up
+            let _ = self.hotkeys.pressed();
+            let mut finished = None;
+            egui::CentralPanel::default().show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    finished = setup.show(ui, &self.rt, &self.monitors);
+                });
+            });
+            if let Some(config) = finished {
+                self.finish_setup(ctx, config);
+            }
+            return;
+        }
+        self.poll_starting(ctx);
+        self.poll_responses();
+        for action in self.hotkeys.pressed() {
+            self.run(ctx, action);
+        }
+        if self.last_poll.elapsed() >= POLL_INTERVAL {
+            self.poll_status(ctx);
+        }
+        // keeps the status, and the overlays, up to date while the main window is idle
+        ctx.request_repaint_after(POLL_INTERVAL);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Screenpipe Prototype 1");
 
@@ -72,54 +440,131 @@ impl eframe::App for MyApp {
 
             ui.separator();
 
-            if self.is_recording {
-                ui.label(format!("Status: Recording... {}", self.status));
-                if ui.button("Stop Recording").clicked() {
-                    if let Some(tx) = &self.stop_tx {
-                        let _ = tx.send(());
+            if self.request.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Waiting for the recorder...");
+                });
+            }
+            match self.daemon.clone() {
+                None => {
+                    ui.label(format!("Status: Recorder not running. {}", self.status));
+                    if self.starting.is_some() {
+                        ui.spinner();
+                    } else if ui.button("Start recorder").clicked() {
+                        self.connect(ctx, false);
+                    }
+                }
+                Some(daemon) if daemon.recording => {
+                    ui.label(format!("Status: Recording... {}", self.status));
+                    ui.label(format!("Frames written: {}", daemon.frames_written));
+                    let mut visible = daemon.indicator_visible;
+                    if ui
+                        .checkbox(&mut visible, "Show recording indicator")
+                        .changed()
+                    {
+                        self.send(ctx, Request::SetIndicatorVisible { visible });
+                        self.show_indicator = visible;
+                    }
+                    if visible {
+                        for monitor in self
+                            .monitors
+                            .iter()
+                            .filter(|monitor| daemon.monitor_ids.contains(&monitor.id()))
+                        {
+                            show_indicator(ctx, monitor);
+                        }
                     }
-                    self.is_recording = false;
-                    self.stop_tx = None;
-                    self.status = "Stopped".to_string();
+                    ui.horizontal(|ui| {
+                        if ui.button("Stop Recording").clicked() {
+                            self.send(ctx, Request::Stop);
+                        }
+                        if ui
+                            .button(if daemon.paused { "Resume" } else { "Pause" })
+                            .clicked()
+                        {
+                            self.send(
+                                ctx,
+                                Request::SetPaused {
+                                    paused: !daemon.paused,
+                                },
+                            );
+                        }
+                    });
                 }
-            } else {
-                ui.label(format!("Status: {}", self.status));
-                let can_start = !self.monitors.is_empty();
-                if ui.add_enabled(can_start, egui::Button::new("Start Recording")).clicked() {
-                    // output dir is $HOME/.work_recorder
-                    let output_dir = dirs::home_dir()
-                        .map(|p| p.join(".work_recorder"))
-                        .unwrap_or_else(|| PathBuf::from(".work_recorder"))
-                        .to_string_lossy()
-                        .to_string();
-                    let fps = 1.0;
-                    
-                    let (tx, _rx) = broadcast::channel(1);
-                    self.stop_tx = Some(tx.clone());
-                    
-                    // Start recording for ALL monitors simultaneously
-                    for monitor in &self.monitors {
-                        let monitor_id = monitor.id();
-                        let output_dir_clone = output_dir.clone();
-                        let stop_rx = tx.subscribe(); // Each recorder gets a subscriber
-                        
-                        let recorder = Recorder::new(monitor_id, output_dir_clone, fps);
-                        
-                        self.rt.spawn(async move {
-                            match recorder.run(stop_rx).await {
-                                Ok(_) => info!("Recording finished successfully for monitor {}", monitor_id),
-                                Err(e) => error!("Recording failed for monitor {}: {}", monitor_id, e),
-                            }
-                        });
+                Some(_) => {
+                    ui.label(format!("Status: {}", self.status));
+                    ui.checkbox(&mut self.show_indicator, "Show recording indicator");
+                    let can_start = !self.monitors.is_empty();
+                    if ui
+                        .add_enabled(can_start, egui::Button::new("Start Recording"))
+                        .clicked()
+                    {
+                        self.send(
+                            ctx,
+                            Request::Start {
+                                show_indicator: self.show_indicator,
+                            },
+                        );
                     }
-                    
-                    self.is_recording = true;
-                    self.status = format!("Recording {} monitor(s)", self.monitors.len());
                 }
             }
-            
+
+            let connected = self.daemon.is_some();
+            ui.add_enabled_ui(connected, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Bookmark").clicked() {
+                        self.send(ctx, Request::Bookmark);
+                    }
+                    if ui.button("Screenshot").clicked() {
+                        self.send(ctx, Request::Screenshot);
+                    }
+                    if ui
+                        .button(format!("Delete last {} min", self.excise_minutes))
+                        .clicked()
+                    {
+                        self.send(
+                            ctx,
+                            Request::Excise {
+                                minutes: self.excise_minutes,
+                            },
+                        );
+                    }
+                });
+                // closing this window leaves the recorder running, this stops it
+                if ui.button("Quit recorder").clicked() {
+                    self.send(ctx, Request::Shutdown);
+                }
+            });
+
+            ui.separator();
+            ui.label("Hotkeys:");
+            for (action, binding) in &self.hotkeys.bound {
+                ui.label(format!(" - {}: {}", action.label(), binding));
+            }
+            for conflict in &self.hotkeys.conflicts {
+                ui.colored_label(ui.visuals().warn_fg_color, format!(" ! {}", conflict));
+            }
+
             ui.separator();
+            if ui.button("Run setup again").clicked() {
+                self.setup = Some(SetupWizard::new(self.config.clone(), &self.monitors));
+            }
             ui.label("Check console for detailed logs.");
+            if ui.button("Copy diagnostics bundle").clicked() {
+                match write_diagnostics_bundle(&recorder_dir(), &recorder_dir().join("logs")) {
+                    Ok(path) => {
+                        info!("Diagnostics bundle written to {}", path.display());
+                        let path = path.display().to_string();
+                        ctx.output_mut(|output| output.copied_text = path.clone());
+                        self.status = format!("Diagnostics bundle saved, path copied: {}", path);
+                    }
+                    Err(e) => {
+                        error!("Failed to write diagnostics bundle: {}", e);
+                        self.status = format!("Failed to write diagnostics bundle: {}", e);
+                    }
+                }
+            }
         });
     }
 }
@@ -0,0 +1,597 @@
+//! The first-run setup: where to record, which monitors, checking ffmpeg and a test recording
+//! played back, before the config is written.
+
+use crate::config::{recorder_dir, AppConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use eframe::egui;
+use image::DynamicImage;
+use screenpipe_core::{
+    find_ffmpeg_path, set_ffmpeg_path, RecordingEngine, RecordingEvent, SafeMonitor, SegmentReplay,
+};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// How long the test recording records.
+pub const TEST_RECORDING_DURATION: Duration = Duration::from_secs(10);
+
+/// Directory of the test recording in the output directory, removed once it's played back.
+const TEST_RECORDING_DIR: &str = ".setup_test";
+
+/// Creates `dir` if needed and checks that files can be written in it.
+pub fn check_output_dir(dir: &Path) -> Result<()> {
+    if dir.as_os_str().is_empty() {
+        bail!("No directory given");
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let probe = dir.join(".write_test");
+    std::fs::write(&probe, b"").with_context(|| format!("Can't write in {}", dir.display()))?;
+    let _ = std::fs::remove_file(probe);
+    Ok(())
+}
+
+/// The ffmpeg the recordings would be encoded with.
+#[derive(Debug, Clone)]
+pub struct FfmpegCheck {
+    pub path: PathBuf,
+    /// First line of `ffmpeg -version`.
+    pub version: String,
+}
+
+/// Finds ffmpeg as the recorder does and checks that it runs.
+pub fn check_ffmpeg() -> Result<FfmpegCheck> {
+    let path = PathBuf::from(find_ffmpeg_path().ok_or_else(|| anyhow!("FFmpeg not found"))?);
+    let output = std::process::Command::new(&path)
+        .arg("-version")
+        .output()
+        .with_context(|| format!("Failed to run {}", path.display()))?;
+    if !output.status.success() {
+        bail!("{} exited with {}", path.display(), output.status);
+    }
+    let version = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    Ok(FfmpegCheck { path, version })
+}
+
+/// A video of the test recording, played back.
+#[derive(Debug, Clone)]
+pub struct TestVideo {
+    pub name: String,
+    pub frames: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct TestRecording {
+    pub videos: Vec<TestVideo>,
+    /// First frame played back, to show what was recorded.
+    pub preview: Option<DynamicImage>,
+}
+
+/// Records for [`TEST_RECORDING_DURATION`] into the output directory of `config`, as the
+/// recorder would, and plays back every video recorded. The recording is deleted after.
+pub async fn run_test_recording(config: &AppConfig) -> Result<TestRecording> {
+    let dir = config.output_dir().join(TEST_RECORDING_DIR);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    let result = record_and_play_back(&dir, config).await;
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        warn!(
+            "Failed to remove the test recording {}: {}",
+            dir.display(),
+            e
+        );
+    }
+    result
+}
+
+async fn record_and_play_back(dir: &Path, config: &AppConfig) -> Result<TestRecording> {
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let recording = {
+        let failures = failures.clone();
+        RecordingEngine::builder()
+            .targets(config.targets())
+            .profile(config.recording_profile())
+            .video_output(dir)
+            .activity_log(dir)
+            .subscribe(move |event| {
+                if let RecordingEvent::Failed { monitor_id, error } = event {
+                    failures
+                        .lock()
+                        .unwrap()
+                        .push(format!("monitor {}: {}", monitor_id, error));
+                }
+            })
+            .build()?
+            .start()
+            .await?
+    };
+    tokio::time::sleep(TEST_RECORDING_DURATION).await;
+    recording.stop();
+    recording.join().await;
+
+    let videos = find_videos(dir)?;
+    if videos.is_empty() {
+        let failures = failures.lock().unwrap();
+        return Err(match failures.first() {
+            Some(failure) => anyhow!("The test recording failed on {}", failure),
+            None => anyhow!("The test recording wrote no video, was a blocked app focused?"),
+        });
+    }
+
+    let mut test = TestRecording::default();
+    for video in videos {
+        let name = video
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut replay = SegmentReplay::open(&video).await?;
+        let mut frames = 0;
+        while let Some(frame) = replay.next_frame().await? {
+            if test.preview.is_none() {
+                test.preview = Some(frame);
+            }
+            frames += 1;
+        }
+        replay
+            .close()
+            .await
+            .with_context(|| format!("Failed to play back {}", name))?;
+        if frames == 0 {
+            bail!("{} plays back no frame", name);
+        }
+        test.videos.push(TestVideo { name, frames });
+    }
+    Ok(test)
+}
+
+/// The videos in `dir` and its subdirectories, the naming may put them in some.
+fn find_videos(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut videos = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            videos.extend(find_videos(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "mp4") {
+            videos.push(path);
+        }
+    }
+    videos.sort();
+    Ok(videos)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    OutputDir,
+    Monitors,
+    Ffmpeg,
+    TestRecording,
+    Finish,
+}
+
+impl Step {
+    const ALL: [Step; 5] = [
+        Step::OutputDir,
+        Step::Monitors,
+        Step::Ffmpeg,
+        Step::TestRecording,
+        Step::Finish,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Step::OutputDir => "Output directory",
+            Step::Monitors => "Monitors",
+            Step::Ffmpeg => "FFmpeg",
+            Step::TestRecording => "Test recording",
+            Step::Finish => "Finish",
+        }
+    }
+
+    fn number(self) -> usize {
+        Step::ALL.iter().position(|step| *step == self).unwrap_or(0) + 1
+    }
+
+    fn next(self) -> Self {
+        Step::ALL[self.number().min(Step::ALL.len() - 1)]
+    }
+
+    fn previous(self) -> Self {
+        Step::ALL[self.number().saturating_sub(2)]
+    }
+}
+
+enum TestState {
+    NotRun,
+    Running {
+        started: Instant,
+        result: Receiver<Result<TestRecording>>,
+    },
+    Passed {
+        videos: Vec<TestVideo>,
+        preview: Option<egui::TextureHandle>,
+    },
+    Failed(String),
+    /// Skipped after failing, e.g. until screen recording is allowed on macOS.
+    Skipped,
+}
+
+/// The setup shown instead of the controls on the first run, or when asked for again. It
+/// edits a copy of the config, written when finished.
+pub struct SetupWizard {
+    step: Step,
+    config: AppConfig,
+    output_dir: String,
+    selected: BTreeSet<u32>,
+    ffmpeg_path: String,
+    ffmpeg: Option<Result<FfmpegCheck, String>>,
+    test: TestState,
+    error: Option<String>,
+}
+
+impl SetupWizard {
+    pub fn new(config: AppConfig, monitors: &[SafeMonitor]) -> Self {
+        let selected = if config.monitor_ids.is_empty() {
+            monitors.iter().map(|monitor| monitor.id()).collect()
+        } else {
+            config.monitor_ids.iter().copied().collect()
+        };
+        Self {
+            step: Step::OutputDir,
+            output_dir: config.output_dir().display().to_string(),
+            ffmpeg_path: config
+                .ffmpeg_path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            config,
+            selected,
+            ffmpeg: None,
+            test: TestState::NotRun,
+            error: None,
+        }
+    }
+
+    /// Shows the current step. Returns the config once it's written, the setup being done. The
+    /// app closes when the setup is quit, nothing written.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        rt: &tokio::runtime::Runtime,
+        monitors: &[SafeMonitor],
+    ) -> Option<AppConfig> {
+        self.poll_tasks(ui.ctx());
+
+        ui.heading("Set up the recorder");
+        ui.label(format!(
+            "Step {} of {}: {}",
+            self.step.number(),
+            Step::ALL.len(),
+            self.step.title()
+        ));
+        ui.separator();
+
+        let can_continue = match self.step {
+            Step::OutputDir => self.show_output_dir(ui),
+            Step::Monitors => self.show_monitors(ui, monitors),
+            Step::Ffmpeg => self.show_ffmpeg(ui),
+            Step::TestRecording => self.show_test_recording(ui, rt),
+            Step::Finish => self.show_finish(ui, monitors),
+        };
+
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.separator();
+
+        let mut finished = None;
+        let busy = matches!(self.test, TestState::Running { .. });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.step != Step::OutputDir && !busy,
+                    egui::Button::new("Back"),
+                )
+                .clicked()
+            {
+                self.error = None;
+                self.step = self.step.previous();
+            }
+            if self.step == Step::Finish {
+                if ui.button("Save and start").clicked() {
+                    finished = self.finish();
+                }
+            } else if ui
+                .add_enabled(can_continue && !busy, egui::Button::new("Next"))
+                .clicked()
+            {
+                self.advance();
+            }
+            if ui
+                .add_enabled(!busy, egui::Button::new("Quit"))
+                .on_hover_text("Closes the app, the setup is shown again on the next start")
+                .clicked()
+            {
+                info!("Setup quit");
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        });
+        finished
+    }
+
+    /// Collects the test recording if it finished since the last frame.
+    fn poll_tasks(&mut self, ctx: &egui::Context) {
+        if let TestState::Running { result, .. } = &self.test {
+            self.test = match result.try_recv() {
+                Ok(Ok(test)) => TestState::Passed {
+                    videos: test.videos,
+                    preview: test.preview.map(|image| {
+                        let image = image.to_rgba8();
+                        ctx.load_texture(
+                            "setup_test_preview",
+                            egui::ColorImage::from_rgba_unmultiplied(
+                                [image.width() as usize, image.height() as usize],
+                                image.as_raw(),
+                            ),
+                            Default::default(),
+                        )
+                    }),
+                },
+                Ok(Err(e)) => {
+                    error!("Test recording failed: {:#}", e);
+                    TestState::Failed(format!("{:#}", e))
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    TestState::Failed("The test recording stopped".to_string())
+                }
+            };
+        }
+    }
+
+    /// Checks the current step and moves on to the next one when it's fine.
+    fn advance(&mut self) {
+        self.error = None;
+        match self.step {
+            Step::OutputDir => {
+                let dir = PathBuf::from(self.output_dir.trim());
+                if let Err(e) = check_output_dir(&dir) {
+                    self.error = Some(format!("{:#}", e));
+                    return;
+                }
+                // recorded in the default directory unless another one was picked
+                self.config.output_dir = (dir != recorder_dir()).then_some(dir);
+            }
+            Step::Monitors => {
+                // the test recording records the monitors and fps picked
+                self.test = TestState::NotRun;
+                if self.ffmpeg.is_none() {
+                    self.ffmpeg = Some(check_ffmpeg().map_err(|e| format!("{:#}", e)));
+                }
+            }
+            _ => {}
+        }
+        self.step = self.step.next();
+    }
+
+    fn show_output_dir(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.label("Where the recordings, activity logs, bookmarks and screenshots are saved:");
+        ui.text_edit_singleline(&mut self.output_dir);
+        ui.small(format!(
+            "Logs and the config stay in {}.",
+            recorder_dir().display()
+        ));
+        !self.output_dir.trim().is_empty()
+    }
+
+    fn show_monitors(&mut self, ui: &mut egui::Ui, monitors: &[SafeMonitor]) -> bool {
+        ui.label("Monitors to record:");
+        for monitor in monitors {
+            let mut checked = self.selected.contains(&monitor.id());
+            let label = format!(
+                "{} ({}x{})",
+                monitor.name(),
+                monitor.width(),
+                monitor.height()
+            );
+            if ui.checkbox(&mut checked, label).changed() {
+                if checked {
+                    self.selected.insert(monitor.id());
+                } else {
+                    self.selected.remove(&monitor.id());
+                }
+            }
+        }
+        if monitors.is_empty() {
+            ui.colored_label(ui.visuals().warn_fg_color, "No monitor detected");
+        }
+        let all = monitors
+            .iter()
+            .all(|monitor| self.selected.contains(&monitor.id()));
+        // with every monitor picked, the ones connected later are recorded as well
+        self.config.monitor_ids = if all {
+            Vec::new()
+        } else {
+            self.selected.iter().copied().collect()
+        };
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label("Frames per second:");
+            ui.add(
+                egui::DragValue::new(&mut self.config.fps)
+                    .range(0.1..=10.0)
+                    .speed(0.1),
+            );
+        });
+        ui.small("Unchanged frames are dropped, so a higher fps mostly costs CPU.");
+        monitors
+            .iter()
+            .any(|monitor| self.selected.contains(&monitor.id()))
+    }
+
+    fn show_ffmpeg(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.label("The recordings are encoded with ffmpeg.");
+        match &self.ffmpeg {
+            Some(Ok(ffmpeg)) => {
+                ui.label(format!("Found {}", ffmpeg.path.display()));
+                ui.small(&ffmpeg.version);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().warn_fg_color, e);
+            }
+            None => {}
+        }
+
+        ui.add_space(8.0);
+        if ui.button("Check again").clicked() {
+            self.ffmpeg = Some(check_ffmpeg().map_err(|e| format!("{:#}", e)));
+        }
+        ui.label("Use another ffmpeg:");
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.ffmpeg_path);
+            if ui.button("Use").clicked() {
+                let path = self.ffmpeg_path.trim();
+                self.use_ffmpeg((!path.is_empty()).then(|| PathBuf::from(path)));
+            }
+        });
+        ui.small("Or install it, or bundle it next to the app in ffmpeg/ (see FFMPEG_SETUP.md).");
+        matches!(self.ffmpeg, Some(Ok(_)))
+    }
+
+    /// Records with `path`, or the ffmpeg found as usual when `None`, from now on.
+    fn use_ffmpeg(&mut self, path: Option<PathBuf>) {
+        set_ffmpeg_path(path.clone());
+        self.ffmpeg_path = path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.config.ffmpeg_path = path;
+        self.ffmpeg = Some(check_ffmpeg().map_err(|e| format!("{:#}", e)));
+    }
+
+    fn show_test_recording(&mut self, ui: &mut egui::Ui, rt: &tokio::runtime::Runtime) -> bool {
+        ui.label(format!(
+            "Records {} seconds of the picked monitors and plays them back, to check that \
+             recording works. Nothing of it is kept.",
+            TEST_RECORDING_DURATION.as_secs()
+        ));
+        ui.add_space(8.0);
+
+        let mut start = false;
+        let mut skip = false;
+        match &self.test {
+            TestState::NotRun => start = ui.button("Start test recording").clicked(),
+            TestState::Running { started, .. } => {
+                let left = TEST_RECORDING_DURATION.saturating_sub(started.elapsed());
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    if left.is_zero() {
+                        ui.label("Playing back...");
+                    } else {
+                        ui.label(format!("Recording... {}s left", left.as_secs() + 1));
+                    }
+                });
+                ui.ctx().request_repaint_after(Duration::from_millis(200));
+            }
+            TestState::Passed { videos, preview } => {
+                for video in videos {
+                    ui.label(format!(
+                        "{}: {} frame(s) played back",
+                        video.name, video.frames
+                    ));
+                }
+                if let Some(preview) = preview {
+                    ui.add(egui::Image::new(preview).max_width(ui.available_width()));
+                }
+                start = ui.button("Record again").clicked();
+            }
+            TestState::Failed(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+                #[cfg(target_os = "macos")]
+                ui.small(
+                    "Screen recording may need to be allowed in System Settings > Privacy & \
+                     Security, the app restarted after.",
+                );
+                ui.horizontal(|ui| {
+                    start = ui.button("Try again").clicked();
+                    skip = ui.button("Skip the test").clicked();
+                });
+            }
+            TestState::Skipped => {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "Skipped: recording may not work until the test passes.",
+                );
+                start = ui.button("Start test recording").clicked();
+            }
+        }
+
+        if skip {
+            warn!("Test recording skipped after failing");
+            self.test = TestState::Skipped;
+        }
+        if start {
+            let (tx, rx) = channel();
+            let config = self.config.clone();
+            let repaint = ui.ctx().clone();
+            rt.spawn(async move {
+                let _ = tx.send(run_test_recording(&config).await);
+                repaint.request_repaint();
+            });
+            self.test = TestState::Running {
+                started: Instant::now(),
+                result: rx,
+            };
+        }
+        matches!(self.test, TestState::Passed { .. } | TestState::Skipped)
+    }
+
+    fn show_finish(&self, ui: &mut egui::Ui, monitors: &[SafeMonitor]) -> bool {
+        ui.label("The recorder is set up:");
+        ui.label(format!(" - Output: {}", self.config.output_dir().display()));
+        let recorded = if self.config.monitor_ids.is_empty() {
+            "all".to_string()
+        } else {
+            monitors
+                .iter()
+                .filter(|monitor| self.config.monitor_ids.contains(&monitor.id()))
+                .map(|monitor| monitor.name().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        ui.label(format!(" - Monitors: {}", recorded));
+        ui.label(format!(" - Frames per second: {}", self.config.fps));
+        if let Some(Ok(ffmpeg)) = &self.ffmpeg {
+            ui.label(format!(" - FFmpeg: {}", ffmpeg.path.display()));
+        }
+        if matches!(self.test, TestState::Skipped) {
+            ui.colored_label(ui.visuals().warn_fg_color, " - Test recording skipped");
+        }
+        ui.small(format!(
+            "Saved to {}, where the naming, hotkeys and logging can be changed too.",
+            recorder_dir().join(crate::config::CONFIG_FILE).display()
+        ));
+        true
+    }
+
+    fn finish(&mut self) -> Option<AppConfig> {
+        match self.config.save(&recorder_dir()) {
+            Ok(()) => {
+                info!("Setup done, config written");
+                Some(self.config.clone())
+            }
+            Err(e) => {
+                error!("Failed to write the config: {:#}", e);
+                self.error = Some(format!("{:#}", e));
+                None
+            }
+        }
+    }
+}
@@ -16,6 +16,10 @@ import { useTimelineStore } from "@/lib/hooks/use-timeline-store";
 import { hasFramesForDate } from "@/lib/actions/has-frames-date";
 import { CommandShortcut } from "@/components/ui/command";
 import { CurrentFrameTimeline } from "@/components/current-frame-timeline";
+import { useBookmarks } from "@/lib/hooks/use-bookmarks";
+import { TimelineScrubber } from "@/components/timeline/timeline-scrubber";
+import { FrameTextPane } from "@/components/timeline/frame-text-pane";
+import { DayMosaic } from "@/components/timeline/day-mosaic";
 
 export interface StreamTimeSeriesResponse {
 	timestamp: string;
@@ -59,6 +63,8 @@ const easeOutCubic = (x: number): number => {
 
 export default function Timeline() {
 	const [currentIndex, setCurrentIndex] = useState(0);
+	const [scrubIndex, setScrubIndex] = useState<number | null>(null);
+	const [isTextPaneOpen, setIsTextPaneOpen] = useState(true);
 	const [isAiPanelExpanded, setIsAiPanelExpanded] = useState(false);
 	const containerRef = useRef<HTMLDivElement | null>(null);
 	const [aiPanelPosition, setAiPanelPosition] = useState({ x: 0, y: 0 });
@@ -80,6 +86,8 @@ export default function Timeline() {
 			setCurrentFrame(frame);
 		});
 
+	const { bookmarks, fetchBookmarks, addBookmark } = useBookmarks();
+
 	useEffect(() => {
 		const getStartDateAndSet = async () => {
 			const data = await getStartDate();
@@ -112,8 +120,41 @@ export default function Timeline() {
 			endTime.setHours(23, 59, 59, 999);
 		}
 		fetchTimeRange(startTime, endTime);
+
+		const endOfCurrentDay = new Date(currentDate);
+		endOfCurrentDay.setHours(23, 59, 59, 999);
+		fetchBookmarks(startTime, endOfCurrentDay);
 	}, [currentDate]);
 
+	const handleAddBookmark = useCallback(async () => {
+		if (!currentFrame) return;
+		const name = window.prompt("bookmark name", "bug repro here");
+		if (!name?.trim()) return;
+
+		await addBookmark({
+			name: name.trim(),
+			timestamp: new Date(currentFrame.timestamp),
+			device_name: currentFrame.devices[0]?.device_id,
+			frame_id: Number(currentFrame.devices[0]?.frame_id) || undefined,
+		});
+	}, [currentFrame, addBookmark]);
+
+	useEffect(() => {
+		const handleKeyDown = (e: KeyboardEvent) => {
+			if ((e.metaKey || e.ctrlKey) && e.key === "b") {
+				e.preventDefault();
+				handleAddBookmark();
+			}
+			if ((e.metaKey || e.ctrlKey) && e.key === "t") {
+				e.preventDefault();
+				setIsTextPaneOpen((open) => !open);
+			}
+		};
+
+		window.addEventListener("keydown", handleKeyDown);
+		return () => window.removeEventListener("keydown", handleKeyDown);
+	}, [handleAddBookmark]);
+
 	useEffect(() => {
 		if (currentFrame) {
 			const frameDate = new Date(currentFrame.timestamp);
@@ -139,8 +180,16 @@ export default function Timeline() {
 					const isWithinTimelineDialog =
 						e.target instanceof Node &&
 						document.querySelector('[role="dialog"]')?.contains(e.target);
-
-					if (isWithinAiPanel || isWithinAudioPanel || isWithinTimelineDialog) {
+					const isWithinTextPane =
+						e.target instanceof Node &&
+						document.querySelector(".frame-text-pane")?.contains(e.target);
+
+					if (
+						isWithinAiPanel ||
+						isWithinAudioPanel ||
+						isWithinTimelineDialog ||
+						isWithinTextPane
+					) {
 						return;
 					}
 
@@ -194,8 +243,16 @@ export default function Timeline() {
 			const isWithinTimelineDialog = document
 				.querySelector('[role="dialog"]')
 				?.contains(e.target as Node);
+			const isWithinTextPane = document
+				.querySelector(".frame-text-pane")
+				?.contains(e.target as Node);
 
-			if (!isWithinAiPanel && !isWithinAudioPanel && !isWithinTimelineDialog) {
+			if (
+				!isWithinAiPanel &&
+				!isWithinAudioPanel &&
+				!isWithinTimelineDialog &&
+				!isWithinTextPane
+			) {
 				e.preventDefault();
 			}
 		};
@@ -338,6 +395,7 @@ export default function Timeline() {
 							startAndEndDates={startAndEndDates}
 							onDateChange={handleDateChange}
 							onJumpToday={handleJumpToday}
+							onAddBookmark={currentFrame ? handleAddBookmark : undefined}
 							className="shadow-lg"
 						/>
 						{/* <TimelineSearch2
@@ -349,6 +407,14 @@ export default function Timeline() {
 							<CommandShortcut>⌘K</CommandShortcut>{" "}
 							<span className="text-xs text-muted-foreground">to search</span>
 						</div>
+						<div>
+							<CommandShortcut>⌘B</CommandShortcut>{" "}
+							<span className="text-xs text-muted-foreground">to bookmark</span>
+						</div>
+						<div>
+							<CommandShortcut>⌘T</CommandShortcut>{" "}
+							<span className="text-xs text-muted-foreground">text pane</span>
+						</div>
 					</div>
 				</div>
 
@@ -399,6 +465,15 @@ export default function Timeline() {
 						</div>
 					)}
 					{currentFrame && <CurrentFrameTimeline currentFrame={currentFrame} />}
+					{isTextPaneOpen && (
+						<FrameTextPane
+							frame={
+								(scrubIndex !== null ? frames[scrubIndex] : null) ??
+								currentFrame
+							}
+							isScrubbing={scrubIndex !== null}
+						/>
+					)}
 					{currentFrame && (
 						<AudioTranscript
 							frames={frames}
@@ -408,6 +483,20 @@ export default function Timeline() {
 					)}
 				</div>
 
+				<DayMosaic date={currentDate} onSelectTime={jumpToTime} />
+
+				<TimelineScrubber
+					frames={frames}
+					currentIndex={currentIndex}
+					onScrub={setScrubIndex}
+					onFrameChange={(index) => {
+						setCurrentIndex(index);
+						if (frames[index]) {
+							setCurrentFrame(frames[index]);
+						}
+					}}
+				/>
+
 				<TimelineSlider
 					frames={frames}
 					currentIndex={currentIndex}
@@ -420,6 +509,7 @@ export default function Timeline() {
 					fetchNextDayData={fetchNextDayData}
 					currentDate={currentDate}
 					startAndEndDates={startAndEndDates}
+					bookmarks={bookmarks}
 				/>
 
 				<AIPanel
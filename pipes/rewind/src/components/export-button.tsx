@@ -6,10 +6,30 @@ import { toast } from "./ui/use-toast";
 import { getScreenpipeAppSettings } from "@/lib/actions/get-screenpipe-app-settings";
 import { Settings } from "@screenpipe/js";
 import { parseInt } from "lodash";
+import {
+	Select,
+	SelectContent,
+	SelectItem,
+	SelectTrigger,
+	SelectValue,
+} from "./ui/select";
+
+type ExportFormat = "mp4" | "gif" | "apng";
+
+// gif and apng autoplay in chat apps, for clips up to the recorder's --max-animated-clip-secs
+const FORMATS: Record<
+	ExportFormat,
+	{ extension: string; mimeType: string; label: string }
+> = {
+	mp4: { extension: "mp4", mimeType: "video/mp4", label: "Video" },
+	gif: { extension: "gif", mimeType: "image/gif", label: "GIF" },
+	apng: { extension: "png", mimeType: "image/apng", label: "Animated PNG" },
+};
 
 export function ExportButton() {
 	const [isExporting, setIsExporting] = useState(false);
 	const [progress, setProgress] = useState(0);
+	const [format, setFormat] = useState<ExportFormat>("mp4");
 	const { selectionRange } = useTimelineSelection();
 
 	const handleExport = async () => {
@@ -53,7 +73,7 @@ export function ExportButton() {
 
 			// Create WebSocket connection
 			ws = new WebSocket(
-				`ws://localhost:3030/frames/export?frame_ids=${sortedFrameIds.join(",")}&fps=${settings.fps ?? 0.5}`,
+				`ws://localhost:3030/frames/export?frame_ids=${sortedFrameIds.join(",")}&fps=${settings.fps ?? 0.5}&format=${format}`,
 			);
 
 			// Set a timeout to handle connection issues
@@ -90,7 +110,7 @@ export function ExportButton() {
 								closeWebSocket();
 								const filename = `screenpipe_export_${new Date()
 									.toISOString()
-									.replace(/[:.]/g, "-")}.mp4`;
+									.replace(/[:.]/g, "-")}.${FORMATS[format].extension}`;
 
 								try {
 									if ("__TAURI__" in window) {
@@ -100,8 +120,8 @@ export function ExportButton() {
 										const filePath = await save({
 											filters: [
 												{
-													name: "Video",
-													extensions: ["mp4"],
+													name: FORMATS[format].label,
+													extensions: [FORMATS[format].extension],
 												},
 											],
 											defaultPath: filename,
@@ -120,7 +140,7 @@ export function ExportButton() {
 									} else {
 										// For browser (including Safari), handle the download differently
 										const blob = new Blob([new Uint8Array(data.video_data)], {
-											type: "video/mp4",
+											type: FORMATS[format].mimeType,
 										});
 
 										// Use a more Safari-friendly approach
@@ -224,21 +244,37 @@ export function ExportButton() {
 	};
 
 	return (
-		<Button
-			variant="outline"
-			onClick={handleExport}
-			className="h-auto px-3 py-1 bg-background hover:bg-accent border text-foreground text-xs rounded flex items-center gap-2 transition-colors"
-			disabled={isExporting || !selectionRange?.frameIds.length}
-		>
-			{isExporting ? (
-				<div className="flex items-center">
-					<Loader2 className="h-4 w-4 animate-spin mr-2" />
-					{progress > 0 && `${Math.round(progress)}%`}
-				</div>
-			) : (
-				<Video className="h-4 w-4 mr-2" />
-			)}
-			Export Video
-		</Button>
+		<div className="flex items-center gap-1">
+			<Select
+				value={format}
+				onValueChange={(value) => setFormat(value as ExportFormat)}
+				disabled={isExporting}
+			>
+				<SelectTrigger className="h-auto w-[72px] px-2 py-1 text-xs">
+					<SelectValue />
+				</SelectTrigger>
+				<SelectContent>
+					<SelectItem value="mp4">mp4</SelectItem>
+					<SelectItem value="gif">gif</SelectItem>
+					<SelectItem value="apng">apng</SelectItem>
+				</SelectContent>
+			</Select>
+			<Button
+				variant="outline"
+				onClick={handleExport}
+				className="h-auto px-3 py-1 bg-background hover:bg-accent border text-foreground text-xs rounded flex items-center gap-2 transition-colors"
+				disabled={isExporting || !selectionRange?.frameIds.length}
+			>
+				{isExporting ? (
+					<div className="flex items-center">
+						<Loader2 className="h-4 w-4 animate-spin mr-2" />
+						{progress > 0 && `${Math.round(progress)}%`}
+					</div>
+				) : (
+					<Video className="h-4 w-4 mr-2" />
+				)}
+				Export {format === "mp4" ? "Video" : format.toUpperCase()}
+			</Button>
+		</div>
 	);
 }
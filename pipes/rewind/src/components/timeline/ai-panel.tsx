@@ -19,6 +19,7 @@ import {
 } from "@/components/ui/tooltip";
 import { useSettings } from "@/lib/hooks/use-settings";
 import { ExportButton } from "../export-button";
+import { SummaryButton } from "./summary-panel";
 import { AIPresetsSelector } from "../ai-presets-selector";
 import { usePipeSettings } from "@/lib/hooks/use-pipe-settings";
 
@@ -383,6 +384,7 @@ export function AIPanel({
 							</span>
 						</button>
 						<ExportButton />
+						<SummaryButton />
 					</div>
 				)}
 			</div>
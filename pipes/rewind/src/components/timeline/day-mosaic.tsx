@@ -0,0 +1,86 @@
+import { useEffect, useState } from "react";
+import { format, startOfDay } from "date-fns";
+
+interface DayMosaicProps {
+	date: Date;
+	onSelectTime: (time: Date) => void;
+}
+
+const MINUTES_PER_DAY = 24 * 60;
+
+// one slice per minute of the day, rendered nightly by the server (or on demand for today)
+export function DayMosaic({ date, onSelectTime }: DayMosaicProps) {
+	const day = format(date, "yyyy-MM-dd");
+	const [src, setSrc] = useState<string | null>(null);
+	const [hoverMinute, setHoverMinute] = useState<number | null>(null);
+
+	useEffect(() => {
+		let cancelled = false;
+		const url = `http://localhost:3030/mosaic/${day}`;
+
+		fetch(url, { method: "HEAD" })
+			.then(async (response) => {
+				if (cancelled) return;
+				if (response.ok) {
+					setSrc(`${url}?t=${Date.now()}`);
+					return;
+				}
+				setSrc(null);
+				// today's strip doesn't exist until the nightly run, ask for one
+				if (response.status === 404 && day === format(new Date(), "yyyy-MM-dd")) {
+					await fetch(url, { method: "POST" });
+				}
+			})
+			.catch(() => !cancelled && setSrc(null));
+
+		return () => {
+			cancelled = true;
+		};
+	}, [day]);
+
+	if (!src) return null;
+
+	const minuteAt = (e: React.MouseEvent<HTMLDivElement>) => {
+		const rect = e.currentTarget.getBoundingClientRect();
+		const ratio = Math.min(Math.max((e.clientX - rect.left) / rect.width, 0), 1);
+		return Math.min(Math.floor(ratio * MINUTES_PER_DAY), MINUTES_PER_DAY - 1);
+	};
+
+	const timeOf = (minute: number) =>
+		new Date(startOfDay(date).getTime() + minute * 60 * 1000);
+
+	return (
+		<div className="relative w-full px-8 pt-2 select-none">
+			<div
+				className="relative h-8 w-full cursor-pointer overflow-hidden rounded border"
+				onMouseMove={(e) => setHoverMinute(minuteAt(e))}
+				onMouseLeave={() => setHoverMinute(null)}
+				onClick={(e) => onSelectTime(timeOf(minuteAt(e)))}
+			>
+				<img
+					src={src}
+					alt={`activity on ${day}`}
+					className="h-full w-full object-fill"
+					draggable={false}
+					onError={() => setSrc(null)}
+				/>
+				{hoverMinute !== null && (
+					<div
+						className="absolute top-0 h-full w-0.5 bg-foreground"
+						style={{ left: `${(hoverMinute / MINUTES_PER_DAY) * 100}%` }}
+					/>
+				)}
+			</div>
+			{hoverMinute !== null && (
+				<div
+					className="pointer-events-none absolute top-full mt-1 -translate-x-1/2 rounded border bg-background px-2 py-0.5 text-xs shadow"
+					style={{
+						left: `calc(2rem + (100% - 4rem) * ${hoverMinute / MINUTES_PER_DAY})`,
+					}}
+				>
+					{format(timeOf(hoverMinute), "HH:mm")}
+				</div>
+			)}
+		</div>
+	);
+}
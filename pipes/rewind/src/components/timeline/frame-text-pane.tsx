@@ -0,0 +1,62 @@
+import { StreamTimeSeriesResponse } from "@/app/page";
+import { Card } from "@/components/ui/card";
+import { ScrollArea } from "@/components/ui/scroll-area";
+import { useDebounce } from "@/lib/hooks/use-debounce";
+import { AppWindow, Clock, Monitor } from "lucide-react";
+
+interface FrameTextPaneProps {
+	frame: StreamTimeSeriesResponse | null;
+	isScrubbing: boolean;
+}
+
+export function FrameTextPane({ frame, isScrubbing }: FrameTextPaneProps) {
+	// only load the preview image once the cursor settles, the text updates immediately
+	const previewFrameId = useDebounce(frame?.devices[0]?.frame_id, 150);
+
+	if (!frame) return null;
+
+	return (
+		<Card className="frame-text-pane fixed left-4 top-20 bottom-52 z-40 flex w-80 flex-col gap-3 p-3">
+			{isScrubbing && previewFrameId && (
+				<img
+					src={`http://localhost:3030/frames/${previewFrameId}`}
+					className="w-full rounded border object-contain"
+					alt="frame preview"
+					loading="lazy"
+					decoding="async"
+				/>
+			)}
+			<div className="space-y-1 text-xs text-muted-foreground">
+				<div className="flex items-center gap-1">
+					<Clock className="h-3 w-3" />
+					<span>{new Date(frame.timestamp).toLocaleString()}</span>
+				</div>
+				{frame.devices.map((device) => (
+					<div key={device.device_id} className="space-y-1">
+						<div className="flex items-center gap-1">
+							<Monitor className="h-3 w-3" />
+							<span>{device.device_id}</span>
+						</div>
+						<div className="flex items-center gap-1">
+							<AppWindow className="h-3 w-3" />
+							<span className="truncate font-medium text-foreground">
+								{device.metadata.app_name || "unknown app"}
+							</span>
+						</div>
+						{device.metadata.window_name && (
+							<p className="truncate pl-4">{device.metadata.window_name}</p>
+						)}
+					</div>
+				))}
+			</div>
+			<ScrollArea className="flex-1 rounded border p-2">
+				<p className="whitespace-pre-wrap text-xs leading-relaxed">
+					{frame.devices
+						.map((device) => device.metadata.ocr_text)
+						.filter(Boolean)
+						.join("\n\n") || "no text detected on this frame"}
+				</p>
+			</ScrollArea>
+		</Card>
+	);
+}
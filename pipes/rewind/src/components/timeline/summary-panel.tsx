@@ -0,0 +1,118 @@
+import { useState } from "react";
+import { format } from "date-fns";
+import { FileText, Loader2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import {
+	Popover,
+	PopoverContent,
+	PopoverTrigger,
+} from "@/components/ui/popover";
+import { useToast } from "@/components/ui/use-toast";
+import { useTimelineSelection } from "@/lib/hooks/use-timeline-selection";
+
+interface Summary {
+	start_time: string;
+	end_time: string;
+	summary: string;
+	model: string;
+	apps: number;
+	screen_samples: number;
+	transcripts: number;
+}
+
+// summarizes the selected range with the llm configured on the server (--llm-model)
+export function SummaryButton() {
+	const { selectionRange } = useTimelineSelection();
+	const { toast } = useToast();
+	const [isLoading, setIsLoading] = useState(false);
+	const [summary, setSummary] = useState<Summary | null>(null);
+	const [open, setOpen] = useState(false);
+
+	const handleSummarize = async () => {
+		if (!selectionRange) return;
+		// the selection can be dragged either way
+		const [start, end] =
+			selectionRange.start < selectionRange.end
+				? [selectionRange.start, selectionRange.end]
+				: [selectionRange.end, selectionRange.start];
+
+		setIsLoading(true);
+		setSummary(null);
+		setOpen(true);
+		try {
+			const response = await fetch("http://localhost:3030/summarize", {
+				method: "POST",
+				headers: { "Content-Type": "application/json" },
+				body: JSON.stringify({
+					start_time: start.toISOString(),
+					end_time: end.toISOString(),
+				}),
+			});
+			const data = await response.json();
+			if (!response.ok) {
+				throw new Error(data.error || "failed to summarize");
+			}
+			setSummary(data);
+		} catch (error) {
+			console.error("summarize error:", error);
+			toast({
+				title: "Summary failed",
+				description:
+					error instanceof Error
+						? error.message
+						: "Failed to summarize the selected range",
+				variant: "destructive",
+			});
+			setOpen(false);
+		} finally {
+			setIsLoading(false);
+		}
+	};
+
+	return (
+		<Popover open={open} onOpenChange={setOpen}>
+			<PopoverTrigger asChild>
+				<Button
+					variant="outline"
+					onClick={(e) => {
+						e.preventDefault();
+						e.stopPropagation();
+						handleSummarize();
+					}}
+					className="h-auto px-3 py-1 bg-background hover:bg-accent border text-foreground text-xs rounded flex items-center gap-2 transition-colors"
+					disabled={isLoading || !selectionRange}
+				>
+					{isLoading ? (
+						<Loader2 className="h-4 w-4 animate-spin" />
+					) : (
+						<FileText className="h-4 w-4" />
+					)}
+					summarize
+				</Button>
+			</PopoverTrigger>
+			<PopoverContent
+				className="w-96 max-h-96 overflow-y-auto text-sm"
+				onClick={(e) => e.stopPropagation()}
+			>
+				{isLoading || !summary ? (
+					<div className="flex items-center gap-2 text-muted-foreground">
+						<Loader2 className="h-4 w-4 animate-spin" />
+						summarizing...
+					</div>
+				) : (
+					<div className="space-y-2">
+						<div className="text-xs text-muted-foreground">
+							{format(new Date(summary.start_time), "HH:mm")} –{" "}
+							{format(new Date(summary.end_time), "HH:mm")} · {summary.apps}{" "}
+							apps · {summary.screen_samples} screens · {summary.transcripts}{" "}
+							transcripts · {summary.model}
+						</div>
+						<p className="whitespace-pre-wrap leading-relaxed select-text">
+							{summary.summary}
+						</p>
+					</div>
+				)}
+			</PopoverContent>
+		</Popover>
+	);
+}
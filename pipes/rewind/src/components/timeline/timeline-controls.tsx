@@ -1,7 +1,7 @@
 "use client";
 
 import { Button } from "@/components/ui/button";
-import { ChevronLeft, ChevronRight, RefreshCw } from "lucide-react";
+import { BookmarkPlus, ChevronLeft, ChevronRight, RefreshCw } from "lucide-react";
 import {
 	endOfDay,
 	format,
@@ -24,6 +24,7 @@ interface TimelineControlsProps {
 	currentDate: Date;
 	onDateChange: (date: Date) => Promise<any>;
 	onJumpToday: () => void;
+	onAddBookmark?: () => void;
 	className?: string;
 }
 
@@ -32,6 +33,7 @@ export function TimelineControls({
 	currentDate,
 	onDateChange,
 	onJumpToday,
+	onAddBookmark,
 	className,
 }: TimelineControlsProps) {
 	const jumpDay = async (days: number) => {
@@ -120,6 +122,18 @@ export function TimelineControls({
 			>
 				<RefreshCw className="h-4 w-4" />
 			</Button>
+
+			{onAddBookmark && (
+				<Button
+					variant="ghost"
+					size="icon"
+					onClick={onAddBookmark}
+					className="h-8 w-8"
+					title="bookmark this moment"
+				>
+					<BookmarkPlus className="h-4 w-4" />
+				</Button>
+			)}
 		</div>
 	);
 }
@@ -0,0 +1,126 @@
+import { StreamTimeSeriesResponse } from "@/app/page";
+import { useCallback, useMemo, useRef, useState } from "react";
+import { throttle } from "lodash";
+import { useDebounce } from "@/lib/hooks/use-debounce";
+
+interface TimelineScrubberProps {
+	frames: StreamTimeSeriesResponse[];
+	currentIndex: number;
+	onScrub: (index: number | null) => void;
+	onFrameChange: (index: number) => void;
+}
+
+// frames are ordered newest first, the scrubber reads left (oldest) to right (newest)
+function indexFromPosition(ratio: number, length: number): number {
+	const clamped = Math.min(Math.max(ratio, 0), 1);
+	return Math.round((1 - clamped) * (length - 1));
+}
+
+// the screen at a wall-clock time, seeked frame-accurately in the recorded video
+export function frameAtUrl(timestamp: string, deviceName?: string): string {
+	const params = new URLSearchParams({ ts: new Date(timestamp).toISOString() });
+	if (deviceName) params.set("device_name", deviceName);
+	return `http://localhost:3030/frames?${params}`;
+}
+
+function positionFromIndex(index: number, length: number): number {
+	if (length <= 1) return 100;
+	return (1 - index / (length - 1)) * 100;
+}
+
+export function TimelineScrubber({
+	frames,
+	currentIndex,
+	onScrub,
+	onFrameChange,
+}: TimelineScrubberProps) {
+	const trackRef = useRef<HTMLDivElement>(null);
+	const [hoverIndex, setHoverIndex] = useState<number | null>(null);
+	const [isDragging, setIsDragging] = useState(false);
+
+	const getIndex = useCallback(
+		(clientX: number) => {
+			const track = trackRef.current;
+			if (!track || !frames.length) return null;
+			const rect = track.getBoundingClientRect();
+			return indexFromPosition((clientX - rect.left) / rect.width, frames.length);
+		},
+		[frames.length],
+	);
+
+	const handleMove = useMemo(
+		() =>
+			throttle(
+				(clientX: number, dragging: boolean) => {
+					const index = getIndex(clientX);
+					setHoverIndex(index);
+					onScrub(index);
+					if (dragging && index !== null) {
+						onFrameChange(index);
+					}
+				},
+				32,
+				{ leading: true, trailing: true },
+			),
+		[getIndex, onScrub, onFrameChange],
+	);
+
+	const hoveredFrame = hoverIndex !== null ? frames[hoverIndex] : null;
+	// only decode the frame the pointer rests on, not every one it passes over
+	const previewFrame = useDebounce(hoveredFrame, 150);
+
+	if (!frames.length) return null;
+
+	return (
+		<div className="relative w-full px-8 py-2 select-none">
+			<div
+				ref={trackRef}
+				className="relative h-3 w-full cursor-pointer rounded-full bg-muted"
+				onMouseMove={(e) => handleMove(e.clientX, isDragging)}
+				onMouseDown={(e) => {
+					setIsDragging(true);
+					const index = getIndex(e.clientX);
+					if (index !== null) onFrameChange(index);
+				}}
+				onMouseUp={() => setIsDragging(false)}
+				onMouseLeave={() => {
+					handleMove.cancel();
+					setIsDragging(false);
+					setHoverIndex(null);
+					onScrub(null);
+				}}
+			>
+				<div
+					className="absolute top-0 h-full rounded-full bg-primary/60"
+					style={{ width: `${positionFromIndex(currentIndex, frames.length)}%` }}
+				/>
+				{hoverIndex !== null && (
+					<div
+						className="absolute -top-1 h-5 w-0.5 bg-foreground"
+						style={{ left: `${positionFromIndex(hoverIndex, frames.length)}%` }}
+					/>
+				)}
+			</div>
+			{hoveredFrame && (
+				<div
+					className="pointer-events-none absolute bottom-full mb-1 -translate-x-1/2 rounded border bg-background px-2 py-0.5 text-xs shadow"
+					style={{
+						left: `calc(2rem + (100% - 4rem) * ${positionFromIndex(hoverIndex!, frames.length) / 100})`,
+					}}
+				>
+					{previewFrame && (
+						<img
+							src={frameAtUrl(
+								previewFrame.timestamp,
+								previewFrame.devices[0]?.device_id,
+							)}
+							className="mb-1 h-24 w-auto rounded"
+							alt="Frame preview"
+						/>
+					)}
+					{new Date(hoveredFrame.timestamp).toLocaleTimeString()}
+				</div>
+			)}
+		</div>
+	);
+}
@@ -2,7 +2,8 @@ import { StreamTimeSeriesResponse, TimeRange } from "@/app/page";
 import { useTimelineSelection } from "@/lib/hooks/use-timeline-selection";
 import { isAfter, subDays } from "date-fns";
 import { motion, useScroll, useTransform } from "framer-motion";
-import { AudioLinesIcon } from "lucide-react";
+import { AudioLinesIcon, BookmarkIcon } from "lucide-react";
+import { Bookmark } from "@/lib/hooks/use-bookmarks";
 import { useEffect, useMemo, useRef, useState } from "react";
 
 interface TimelineSliderProps {
@@ -13,6 +14,7 @@ interface TimelineSliderProps {
 	fetchNextDayData: (date: Date) => void;
 	currentDate: Date;
 	onSelectionChange?: (selectedFrames: StreamTimeSeriesResponse[]) => void;
+	bookmarks?: Bookmark[];
 }
 
 interface AppGroup {
@@ -43,6 +45,7 @@ export const TimelineSlider = ({
 	startAndEndDates,
 	currentDate,
 	onSelectionChange,
+	bookmarks = [],
 }: TimelineSliderProps) => {
 	const containerRef = useRef<HTMLDivElement>(null);
 	const observerTargetRef = useRef<HTMLDivElement>(null);
@@ -98,6 +101,27 @@ export const TimelineSlider = ({
 		return groups;
 	}, [visibleFrames]);
 
+	// attach each bookmark to the frame closest to its timestamp
+	const bookmarksByFrame = useMemo(() => {
+		const map = new Map<string, Bookmark[]>();
+		if (!frames.length) return map;
+
+		bookmarks.forEach((bookmark) => {
+			const target = new Date(bookmark.timestamp).getTime();
+			let closest = frames[0];
+			let closestDiff = Infinity;
+			frames.forEach((frame) => {
+				const diff = Math.abs(new Date(frame.timestamp).getTime() - target);
+				if (diff < closestDiff) {
+					closestDiff = diff;
+					closest = frame;
+				}
+			});
+			map.set(closest.timestamp, [...(map.get(closest.timestamp) || []), bookmark]);
+		});
+		return map;
+	}, [frames, bookmarks]);
+
 	useEffect(() => {
 		const observerTarget = observerTargetRef.current;
 		if (!observerTarget) return;
@@ -241,6 +265,7 @@ export const TimelineSlider = ({
 									frameDate <= selectionRange.end;
 
 								const hasAudio = Boolean(frame.devices[0].audio.length);
+								const frameBookmarks = bookmarksByFrame.get(frame.timestamp);
 
 								return (
 									<motion.div
@@ -276,6 +301,14 @@ export const TimelineSlider = ({
 												<AudioLinesIcon className="w-full h-full" />
 											</div>
 										)}
+										{frameBookmarks && (
+											<div
+												className="absolute -top-11 left-1/2 -translate-x-1/2 w-4 h-4 text-yellow-500"
+												title={frameBookmarks.map((b) => b.name).join(", ")}
+											>
+												<BookmarkIcon className="w-full h-full fill-current" />
+											</div>
+										)}
 										{(hoveredTimestamp === frame.timestamp ||
 											frames[currentIndex].timestamp === frame.timestamp) && (
 											<div className="absolute bottom-full left-1/2 z-50 -translate-x-1/2 mb-6 w-max bg-background border rounded-md px-2 py-1 text-xs shadow-lg">
@@ -285,6 +318,11 @@ export const TimelineSlider = ({
 												<p className="text-muted-foreground">
 													{new Date(frame.timestamp).toLocaleString()}
 												</p>
+												{frameBookmarks?.map((bookmark) => (
+													<p key={bookmark.id} className="text-yellow-600">
+														{bookmark.name}
+													</p>
+												))}
 											</div>
 										)}
 									</motion.div>
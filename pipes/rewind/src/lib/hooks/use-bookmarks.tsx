@@ -0,0 +1,101 @@
+import { create } from "zustand";
+
+export interface Bookmark {
+	id: number;
+	name: string;
+	timestamp: string;
+	device_name: string | null;
+	frame_id: number | null;
+	note: string | null;
+	created_at: string;
+}
+
+interface BookmarksState {
+	bookmarks: Bookmark[];
+	isLoading: boolean;
+	error: string | null;
+
+	fetchBookmarks: (startTime: Date, endTime: Date, query?: string) => Promise<void>;
+	addBookmark: (bookmark: {
+		name: string;
+		timestamp: Date;
+		device_name?: string;
+		frame_id?: number;
+	}) => Promise<Bookmark | null>;
+	removeBookmark: (id: number) => Promise<void>;
+}
+
+const API_URL = "http://localhost:3030";
+
+export const useBookmarks = create<BookmarksState>((set, get) => ({
+	bookmarks: [],
+	isLoading: false,
+	error: null,
+
+	fetchBookmarks: async (startTime, endTime, query) => {
+		set({ isLoading: true, error: null });
+		try {
+			const params = new URLSearchParams({
+				start_time: startTime.toISOString(),
+				end_time: endTime.toISOString(),
+				limit: "1000",
+			});
+			if (query) params.set("q", query);
+
+			const response = await fetch(`${API_URL}/bookmarks?${params}`);
+			if (!response.ok) {
+				throw new Error(`failed to fetch bookmarks: ${response.status}`);
+			}
+			const bookmarks: Bookmark[] = await response.json();
+			set({ bookmarks, isLoading: false });
+		} catch (error) {
+			console.error("failed to fetch bookmarks:", error);
+			set({ error: String(error), isLoading: false });
+		}
+	},
+
+	addBookmark: async ({ name, timestamp, device_name, frame_id }) => {
+		try {
+			const response = await fetch(`${API_URL}/bookmarks`, {
+				method: "POST",
+				headers: { "Content-Type": "application/json" },
+				body: JSON.stringify({
+					name,
+					timestamp: timestamp.toISOString(),
+					device_name,
+					frame_id,
+				}),
+			});
+			if (!response.ok) {
+				throw new Error(`failed to create bookmark: ${response.status}`);
+			}
+			const bookmark: Bookmark = await response.json();
+			set({
+				bookmarks: [...get().bookmarks, bookmark].sort(
+					(a, b) =>
+						new Date(a.timestamp).getTime() - new Date(b.timestamp).getTime(),
+				),
+			});
+			return bookmark;
+		} catch (error) {
+			console.error("failed to create bookmark:", error);
+			set({ error: String(error) });
+			return null;
+		}
+	},
+
+	removeBookmark: async (id) => {
+		try {
+			const response = await fetch(`${API_URL}/bookmarks/${id}`, {
+				method: "DELETE",
+			});
+			if (!response.ok) {
+				throw new Error(`failed to delete bookmark: ${response.status}`);
+			}
+			set({ bookmarks: get().bookmarks.filter((b) => b.id !== id) });
+		} catch (error) {
+			console.error("failed to delete bookmark:", error);
+			set({ error: String(error) });
+		}
+	},
+}));
@@ -1087,6 +1087,13 @@ export function SearchChat() {
                       <div className="flex justify-center mt-4">
                         <VideoComponent filePath={item.content.filePath} />
                       </div>
+                      {item.content.hostName && (
+                        <div className="flex flex-wrap items-center gap-2 mt-2">
+                          <Badge variant="outline" className="text-xs">
+                            {item.content.hostName}
+                          </Badge>
+                        </div>
+                      )}
                       {includeFrames && item.content.frame && (
                         <div className="mt-2 flex items-center">
                           <Dialog>
@@ -1580,7 +1587,7 @@ export function SearchChat() {
                   type="text"
                   value={query}
                   onChange={(e) => setQuery(e.target.value)}
-                  placeholder="search for specific keywords"
+                  placeholder='keywords or filters, e.g. app:chrome title:"jira"'
                   className="flex-grow"
                   autoCorrect="off"
                   autoComplete="off"
@@ -1592,6 +1599,10 @@ export function SearchChat() {
                     </TooltipTrigger>
                     <TooltipContent>
                       <p>search for specific keywords in all content</p>
+                      <p>
+                        filters: app:, title:, url:, type:, after:, before:,
+                        lang:, text:&quot;a phrase&quot; and is:focused
+                      </p>
                     </TooltipContent>
                   </Tooltip>
                 </TooltipProvider>
@@ -4,6 +4,7 @@ import { getStore, useSettings } from "@/lib/hooks/use-settings";
 
 import React, { useEffect, useState } from "react";
 import NotificationHandler from "@/components/notification-handler";
+import ConfigReloadHandler from "@/components/config-reload-handler";
 import Header from "@/components/header";
 import { useToast } from "@/components/ui/use-toast";
 import Onboarding from "@/components/onboarding";
@@ -231,6 +232,7 @@ export default function Home() {
       <LoginDialog />
       <ModelDownloadTracker />
       <NotificationHandler />
+      <ConfigReloadHandler />
       {showOnboarding ? (
         <Onboarding />
       ) : (
@@ -0,0 +1,68 @@
+import React, { useEffect } from "react";
+import { useToast } from "@/components/ui/use-toast";
+
+type ScreenpipeEvent = {
+  name: string;
+  data: { file?: string; error?: string };
+};
+
+const EVENTS_URL = "ws://127.0.0.1:3030/ws/events?images=false";
+
+// the name of the file, not the whole data dir path
+const fileName = (file?: string) => file?.split(/[\\/]/).pop() ?? "config";
+
+// shows a toast when the recorder applies, or rejects, a config file edited while recording
+const ConfigReloadHandler: React.FC = () => {
+  const { toast } = useToast();
+
+  useEffect(() => {
+    let socket: WebSocket | null = null;
+    let retry: ReturnType<typeof setTimeout> | null = null;
+    let closed = false;
+
+    const connect = () => {
+      socket = new WebSocket(EVENTS_URL);
+
+      socket.onmessage = (message) => {
+        let event: ScreenpipeEvent;
+        try {
+          event = JSON.parse(message.data);
+        } catch {
+          return;
+        }
+
+        if (event.name === "config_reloaded") {
+          toast({
+            title: "settings updated",
+            description: `applied the changes to ${fileName(event.data.file)}`,
+          });
+        } else if (event.name === "config_reload_failed") {
+          toast({
+            title: `invalid ${fileName(event.data.file)}`,
+            description: `keeping the previous settings: ${event.data.error}`,
+            variant: "destructive",
+          });
+        }
+      };
+
+      // the recorder may not be running yet, or restart
+      socket.onclose = () => {
+        if (!closed) {
+          retry = setTimeout(connect, 5000);
+        }
+      };
+    };
+
+    connect();
+
+    return () => {
+      closed = true;
+      if (retry) clearTimeout(retry);
+      socket?.close();
+    };
+  }, [toast]);
+
+  return null; // This component doesn't render anything
+};
+
+export default ConfigReloadHandler;
@@ -48,11 +48,14 @@ const HealthStatus = ({ className }: { className?: string }) => {
     frameStatus: string,
     audioStatus: string,
     uiStatus: string,
+    encoderStatus: string,
     audioDisabled: boolean,
     uiMonitoringEnabled: boolean
   ) => {
     if (status === "loading") return "bg-yellow-500";
-    const isVisionOk = frameStatus === "ok" || frameStatus === "disabled";
+    const isVisionOk =
+      (frameStatus === "ok" || frameStatus === "disabled") &&
+      encoderStatus !== "unhealthy";
     const isAudioOk =
       audioStatus === "ok" || audioStatus === "disabled" || audioDisabled;
     const isUiOk =
@@ -65,6 +68,7 @@ const HealthStatus = ({ className }: { className?: string }) => {
     frameStatus: string,
     audioStatus: string,
     uiStatus: string,
+    encoderStatus: string,
     audioDisabled: boolean,
     uiMonitoringEnabled: boolean
   ) => {
@@ -74,6 +78,7 @@ const HealthStatus = ({ className }: { className?: string }) => {
     let issues = [];
     if (frameStatus !== "ok" && frameStatus !== "disabled")
       issues.push("screen recording");
+    if (encoderStatus === "unhealthy") issues.push("video encoding");
     if (!audioDisabled && audioStatus !== "ok" && audioStatus !== "disabled")
       issues.push("audio recording");
     if (uiMonitoringEnabled && uiStatus !== "ok" && uiStatus !== "disabled")
@@ -92,6 +97,7 @@ const HealthStatus = ({ className }: { className?: string }) => {
     health?.frame_status ?? "",
     health?.audio_status ?? "",
     health?.ui_status ?? "",
+    health?.encoder_status ?? "",
     settings.disableAudio,
     settings.enableUiMonitoring
   );
@@ -100,6 +106,7 @@ const HealthStatus = ({ className }: { className?: string }) => {
     health?.frame_status ?? "",
     health?.audio_status ?? "",
     health?.ui_status ?? "",
+    health?.encoder_status ?? "",
     settings.disableAudio ?? "",
     settings.enableUiMonitoring
   );
@@ -182,6 +189,65 @@ const HealthStatus = ({ className }: { className?: string }) => {
                 </div>
               </div>
 
+              {/* Video Encoder Status */}
+              {health?.encoder_status &&
+                health.encoder_status !== "ok" &&
+                health.encoder_status !== "disabled" && (
+                  <div className="flex items-center gap-2">
+                    <div
+                      className={`w-2 h-2 rounded-full ${
+                        health.encoder_status === "degraded"
+                          ? "bg-yellow-500"
+                          : "bg-red-500"
+                      }`}
+                    />
+                    <span className="text-sm">video encoder</span>
+                    <span className="text-sm text-muted-foreground">
+                      status: {health.encoder_status}
+                      {health.encoders
+                        ?.flatMap((encoder) =>
+                          encoder.recent_errors
+                            .slice(-1)
+                            .map(
+                              (error) =>
+                                `, monitor ${encoder.monitor_id}: ${error}`
+                            )
+                        )
+                        .join("")}
+                    </span>
+                  </div>
+                )}
+
+              {/* Missing OS Permissions */}
+              {health?.permissions
+                ?.filter((check) => check.status === "denied")
+                .map((check) => (
+                  <div
+                    key={check.capability}
+                    className="flex items-center justify-between"
+                  >
+                    <div className="flex items-center gap-2">
+                      <div className="w-2 h-2 rounded-full bg-red-500" />
+                      <span className="text-sm">
+                        {check.capability.replace("_", " ")} permission
+                      </span>
+                      <span className="text-sm text-muted-foreground">
+                        missing, grant it and restart screenpipe
+                      </span>
+                    </div>
+                    {check.settings_url && (
+                      <Button
+                        variant="outline"
+                        size="sm"
+                        className="flex-shrink-0"
+                        onClick={() => openUrl(check.settings_url!)}
+                      >
+                        open settings
+                      </Button>
+                    )}
+                  </div>
+                ))}
+
               {/* Audio Recording Status */}
               <div className="flex items-center justify-between">
                 <div className="flex items-center gap-2">
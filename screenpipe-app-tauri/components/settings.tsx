@@ -13,6 +13,10 @@ import {
   Check,
   HardDrive,
   FolderInput,
+  ScrollText,
+  EyeOff,
+  Activity,
+  Timer,
   Settings as SettingsIcon,
 } from "lucide-react";
 import { DialogHeader, DialogTitle } from "./ui/dialog";
@@ -36,6 +40,10 @@ import { invoke } from "@tauri-apps/api/core";
 import { useProfiles } from "@/lib/hooks/use-profiles";
 import { toast } from "./ui/use-toast";
 import { DataImportSection } from "./settings/data-import-section";
+import { AccessLogSection } from "./settings/access-log-section";
+import { ExcludedRegionsSection } from "./settings/excluded-regions-section";
+import { RecordingStatsSection } from "./settings/recording-stats-section";
+import { SessionsSection } from "./settings/sessions-section";
 import { Dialog, DialogContent } from "./ui/dialog";
 import { useSettingsDialog } from "@/lib/hooks/use-settings-dialog";
 import { RecordingSettings } from "./settings/recording-settings";
@@ -48,7 +56,11 @@ type SettingsSection =
   | "recording"
   | "account"
   | "diskUsage"
-  | "dataImport";
+  | "dataImport"
+  | "accessLog"
+  | "excludedRegions"
+  | "recordingStats"
+  | "sessions";
 
 export function Settings() {
   const { isOpen, setIsOpen: setSettingsOpen } = useSettingsDialog();
@@ -122,6 +134,14 @@ export function Settings() {
         return <DiskUsage />;
       case "dataImport":
         return <DataImportSection />;
+      case "accessLog":
+        return <AccessLogSection />;
+      case "excludedRegions":
+        return <ExcludedRegionsSection />;
+      case "recordingStats":
+        return <RecordingStatsSection />;
+      case "sessions":
+        return <SessionsSection />;
     }
   };
 
@@ -256,6 +276,26 @@ export function Settings() {
                   label: "data import",
                   icon: <FolderInput className="h-4 w-4" />,
                 },
+                {
+                  id: "accessLog",
+                  label: "access log",
+                  icon: <ScrollText className="h-4 w-4" />,
+                },
+                {
+                  id: "excludedRegions",
+                  label: "excluded regions",
+                  icon: <EyeOff className="h-4 w-4" />,
+                },
+                {
+                  id: "recordingStats",
+                  label: "recording stats",
+                  icon: <Activity className="h-4 w-4" />,
+                },
+                {
+                  id: "sessions",
+                  label: "sessions",
+                  icon: <Timer className="h-4 w-4" />,
+                },
               ].map((section) => (
                 <button
                   key={section.id}
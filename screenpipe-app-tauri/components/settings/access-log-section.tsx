@@ -0,0 +1,152 @@
+"use client";
+import React, { useEffect, useState } from "react";
+import { RefreshCw } from "lucide-react";
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import { Skeleton } from "@/components/ui/skeleton";
+import { toast } from "@/components/ui/use-toast";
+import { useSettings } from "@/lib/hooks/use-settings";
+
+interface AccessLogEntry {
+  id: number;
+  timestamp: string;
+  action: string;
+  method: string;
+  path: string;
+  client: string;
+  remote_addr: string | null;
+  user_agent: string | null;
+  start_time: string | null;
+  end_time: string | null;
+  status: number;
+}
+
+const formatTime = (timestamp: string) => new Date(timestamp).toLocaleString();
+
+const formatRange = (entry: AccessLogEntry) => {
+  if (!entry.start_time && !entry.end_time) {
+    return "-";
+  }
+  return `${entry.start_time ? formatTime(entry.start_time) : "…"} → ${
+    entry.end_time ? formatTime(entry.end_time) : "…"
+  }`;
+};
+
+export function AccessLogSection() {
+  const { settings } = useSettings();
+  const [entries, setEntries] = useState<AccessLogEntry[]>([]);
+  const [loading, setLoading] = useState(false);
+
+  const fetchEntries = async () => {
+    setLoading(true);
+    try {
+      const response = await fetch(
+        "http://localhost:3030/access-log?limit=200"
+      );
+      if (!response.ok) {
+        throw new Error(`status ${response.status}`);
+      }
+      setEntries(await response.json());
+    } catch (error) {
+      console.error("failed to fetch access log:", error);
+      toast({
+        title: "error",
+        description: "failed to fetch the access log, is screenpipe running?",
+        variant: "destructive",
+      });
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    fetchEntries();
+  }, []);
+
+  return (
+    <div className="w-full space-y-6 py-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-2xl font-bold">access log</h1>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={fetchEntries}
+          disabled={loading}
+        >
+          <RefreshCw className="h-4 w-4 mr-2" />
+          refresh
+        </Button>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        reads of your recordings through the api: searches, frames, exports and
+        live streams, with the client that made them. clients are identified
+        by a fingerprint of their api key, or as local when no api key is set.
+      </p>
+
+      {!settings.enableAccessLog && (
+        <p className="text-sm text-muted-foreground">
+          the access log is disabled, enable it in recording settings to start
+          recording reads.
+        </p>
+      )}
+
+      {loading && entries.length === 0 ? (
+        <div className="space-y-2">
+          <Skeleton className="h-[40px] w-full" />
+          <Skeleton className="h-[40px] w-full" />
+          <Skeleton className="h-[40px] w-full" />
+        </div>
+      ) : entries.length === 0 ? (
+        <p className="text-sm text-muted-foreground">no reads recorded yet</p>
+      ) : (
+        <div className="border rounded-lg overflow-auto">
+          <table className="w-full text-sm">
+            <thead className="text-left text-muted-foreground">
+              <tr className="border-b">
+                <th className="p-2 font-medium">when</th>
+                <th className="p-2 font-medium">action</th>
+                <th className="p-2 font-medium">client</th>
+                <th className="p-2 font-medium">time range</th>
+                <th className="p-2 font-medium">status</th>
+              </tr>
+            </thead>
+            <tbody>
+              {entries.map((entry) => (
+                <tr key={entry.id} className="border-b last:border-0">
+                  <td className="p-2 whitespace-nowrap">
+                    {formatTime(entry.timestamp)}
+                  </td>
+                  <td className="p-2" title={`${entry.method} ${entry.path}`}>
+                    {entry.action}
+                  </td>
+                  <td
+                    className="p-2 font-mono"
+                    title={entry.user_agent ?? undefined}
+                  >
+                    {entry.client}
+                    {entry.remote_addr && (
+                      <span className="text-muted-foreground">
+                        {" "}
+                        ({entry.remote_addr})
+                      </span>
+                    )}
+                  </td>
+                  <td className="p-2 whitespace-nowrap">
+                    {formatRange(entry)}
+                  </td>
+                  <td className="p-2">
+                    <Badge
+                      variant={entry.status < 400 ? "outline" : "destructive"}
+                    >
+                      {entry.status}
+                    </Badge>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  );
+}
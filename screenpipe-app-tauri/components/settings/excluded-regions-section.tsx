@@ -0,0 +1,316 @@
+"use client";
+import React, { useEffect, useRef, useState } from "react";
+import { RefreshCw, Save, Trash2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Skeleton } from "@/components/ui/skeleton";
+import { toast } from "@/components/ui/use-toast";
+import { cn } from "@/lib/utils";
+
+interface MonitorInfo {
+  id: number;
+  name: string;
+  width: number;
+  height: number;
+  is_default: boolean;
+}
+
+// in points from the top left corner of the monitor, as the recorder expects them
+interface ExcludedRegion {
+  monitor_id: number | null;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  label: string | null;
+}
+
+interface Point {
+  x: number;
+  y: number;
+}
+
+const API = "http://localhost:3030";
+
+export function ExcludedRegionsSection() {
+  const [monitors, setMonitors] = useState<MonitorInfo[]>([]);
+  const [monitorId, setMonitorId] = useState<number | null>(null);
+  const [screenshot, setScreenshot] = useState<string | null>(null);
+  const [regions, setRegions] = useState<ExcludedRegion[]>([]);
+  const [dragStart, setDragStart] = useState<Point | null>(null);
+  const [dragEnd, setDragEnd] = useState<Point | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [saving, setSaving] = useState(false);
+  const imageRef = useRef<HTMLImageElement>(null);
+
+  const monitor = monitors.find((m) => m.id === monitorId);
+
+  const fetchScreenshot = async (id: number) => {
+    setLoading(true);
+    try {
+      const response = await fetch(
+        `${API}/screenshot?monitor_id=${id}&skip_ocr=true`
+      );
+      if (!response.ok) {
+        throw new Error(`status ${response.status}`);
+      }
+      const data = await response.json();
+      setScreenshot(`data:image/jpeg;base64,${data.image}`);
+    } catch (error) {
+      console.error("failed to capture screenshot:", error);
+      setScreenshot(null);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    const load = async () => {
+      try {
+        const [monitorsResponse, regionsResponse] = await Promise.all([
+          fetch(`${API}/vision/list`),
+          fetch(`${API}/excluded-regions`),
+        ]);
+        if (!monitorsResponse.ok || !regionsResponse.ok) {
+          throw new Error("failed to load monitors or regions");
+        }
+        const monitors: MonitorInfo[] = await monitorsResponse.json();
+        setMonitors(monitors);
+        setRegions((await regionsResponse.json()).regions);
+        const defaultMonitor =
+          monitors.find((m) => m.is_default) ?? monitors[0];
+        if (defaultMonitor) {
+          setMonitorId(defaultMonitor.id);
+        }
+      } catch (error) {
+        console.error("failed to load excluded regions:", error);
+        toast({
+          title: "error",
+          description:
+            "failed to load excluded regions, is screenpipe running?",
+          variant: "destructive",
+        });
+      }
+    };
+    load();
+  }, []);
+
+  useEffect(() => {
+    if (monitorId !== null) {
+      fetchScreenshot(monitorId);
+    }
+  }, [monitorId]);
+
+  // position of the pointer in monitor points
+  const toPoints = (e: React.MouseEvent): Point | null => {
+    const image = imageRef.current;
+    if (!image || !monitor) {
+      return null;
+    }
+    const rect = image.getBoundingClientRect();
+    const clamp = (value: number, max: number) =>
+      Math.min(Math.max(value, 0), max);
+    return {
+      x: clamp(
+        ((e.clientX - rect.left) / rect.width) * monitor.width,
+        monitor.width
+      ),
+      y: clamp(
+        ((e.clientY - rect.top) / rect.height) * monitor.height,
+        monitor.height
+      ),
+    };
+  };
+
+  const selection =
+    dragStart && dragEnd
+      ? {
+          x: Math.round(Math.min(dragStart.x, dragEnd.x)),
+          y: Math.round(Math.min(dragStart.y, dragEnd.y)),
+          width: Math.round(Math.abs(dragEnd.x - dragStart.x)),
+          height: Math.round(Math.abs(dragEnd.y - dragStart.y)),
+        }
+      : null;
+
+  const handleMouseUp = () => {
+    if (selection && selection.width > 0 && selection.height > 0) {
+      setRegions([
+        ...regions,
+        { ...selection, monitor_id: monitorId, label: null },
+      ]);
+    }
+    setDragStart(null);
+    setDragEnd(null);
+  };
+
+  const handleSave = async () => {
+    setSaving(true);
+    try {
+      const response = await fetch(`${API}/excluded-regions`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ regions }),
+      });
+      if (!response.ok) {
+        throw new Error((await response.json()).error);
+      }
+      toast({
+        title: "excluded regions saved",
+        description: "they are blacked out from the next frame on",
+      });
+    } catch (error) {
+      toast({
+        title: "error",
+        description: `failed to save excluded regions: ${error}`,
+        variant: "destructive",
+      });
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  // a region drawn over the screenshot, in percent of the monitor
+  const overlayStyle = (region: {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+  }) =>
+    monitor && {
+      left: `${(region.x / monitor.width) * 100}%`,
+      top: `${(region.y / monitor.height) * 100}%`,
+      width: `${(region.width / monitor.width) * 100}%`,
+      height: `${(region.height / monitor.height) * 100}%`,
+    };
+
+  const visibleRegions = regions
+    .map((region, index) => ({ region, index }))
+    .filter(
+      ({ region }) =>
+        region.monitor_id === null || region.monitor_id === monitorId
+    );
+
+  return (
+    <div className="w-full space-y-6 py-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-2xl font-bold">excluded regions</h1>
+        <div className="flex gap-2">
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={() => monitorId !== null && fetchScreenshot(monitorId)}
+            disabled={loading || monitorId === null}
+          >
+            <RefreshCw className="h-4 w-4 mr-2" />
+            refresh
+          </Button>
+          <Button size="sm" onClick={handleSave} disabled={saving}>
+            <Save className="h-4 w-4 mr-2" />
+            save
+          </Button>
+        </div>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        drag over the screen to black out a part of it on every frame, before
+        text is read from it and before it&apos;s stored. useful for where a
+        password manager docks or notifications pop up.
+      </p>
+
+      {monitors.length > 1 && (
+        <div className="flex gap-2">
+          {monitors.map((m) => (
+            <Button
+              key={m.id}
+              variant={m.id === monitorId ? "default" : "outline"}
+              size="sm"
+              onClick={() => setMonitorId(m.id)}
+            >
+              {m.name}
+            </Button>
+          ))}
+        </div>
+      )}
+
+      {loading && !screenshot ? (
+        <Skeleton className="w-full aspect-video" />
+      ) : screenshot ? (
+        <div
+          className="relative select-none cursor-crosshair border rounded-lg overflow-hidden"
+          onMouseDown={(e) => {
+            const point = toPoints(e);
+            setDragStart(point);
+            setDragEnd(point);
+          }}
+          onMouseMove={(e) => dragStart && setDragEnd(toPoints(e))}
+          onMouseUp={handleMouseUp}
+          onMouseLeave={handleMouseUp}
+        >
+          {/* eslint-disable-next-line @next/next/no-img-element */}
+          <img
+            ref={imageRef}
+            src={screenshot}
+            alt="current screen"
+            className="w-full pointer-events-none"
+            draggable={false}
+          />
+          {visibleRegions.map(({ region, index }) => (
+            <div
+              key={index}
+              className="absolute bg-red-500/40 border border-red-500"
+              style={overlayStyle(region) || undefined}
+            />
+          ))}
+          {selection && (
+            <div
+              className="absolute bg-blue-500/30 border border-blue-500"
+              style={overlayStyle(selection) || undefined}
+            />
+          )}
+        </div>
+      ) : (
+        <p className="text-sm text-muted-foreground">
+          no screenshot of this monitor, is screenpipe running?
+        </p>
+      )}
+
+      {visibleRegions.length > 0 && (
+        <div className="space-y-2">
+          {visibleRegions.map(({ region, index }) => (
+            <div key={index} className="flex items-center gap-2 text-sm">
+              <Input
+                value={region.label ?? ""}
+                placeholder="what it hides"
+                className="h-8 max-w-xs"
+                onChange={(e) =>
+                  setRegions(
+                    regions.map((r, i) =>
+                      i === index ? { ...r, label: e.target.value || null } : r
+                    )
+                  )
+                }
+              />
+              <span
+                className={cn(
+                  "font-mono text-muted-foreground",
+                  region.monitor_id === null && "italic"
+                )}
+              >
+                {region.width}×{region.height} at {region.x},{region.y}
+                {region.monitor_id === null && " on every monitor"}
+              </span>
+              <Button
+                variant="ghost"
+                size="icon"
+                onClick={() =>
+                  setRegions(regions.filter((_, i) => i !== index))
+                }
+              >
+                <Trash2 className="h-4 w-4" />
+              </Button>
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -42,6 +42,7 @@ import {
 import { Command as TauriCommand } from "@tauri-apps/plugin-shell";
 
 import {
+  RecordingFormat,
   Settings,
   useSettings,
   VadSensitivity,
@@ -477,6 +478,14 @@ export function RecordingSettings() {
     handleSettingsChange({ useChineseMirror: checked }, true);
   };
 
+  const handleRecordingFormatChange = (value: string) => {
+    handleSettingsChange({ recordingFormat: value as RecordingFormat }, true);
+  };
+
+  const handleAccessLogToggle = (checked: boolean) => {
+    handleSettingsChange({ enableAccessLog: checked }, true);
+  };
+
   const handleDataDirChange = async () => {
     if (clickTimeout) {
       // Double Click
@@ -824,6 +833,43 @@ export function RecordingSettings() {
                     </SelectContent>
                   </Select>
                 </div>
+                <div className="flex flex-col space-y-2">
+                  <Label
+                    htmlFor="recordingFormat"
+                    className="flex items-center space-x-2"
+                  >
+                    <Monitor className="h-4 w-4" />
+                    <span>recording format</span>
+                    <TooltipProvider>
+                      <Tooltip>
+                        <TooltipTrigger>
+                          <HelpCircle className="h-4 w-4 cursor-default" />
+                        </TooltipTrigger>
+                        <TooltipContent side="right">
+                          <p>
+                            video is the most compact. images keep every frame
+                            <br />
+                            as its own file, in a folder per hour, taking more
+                            space.
+                          </p>
+                        </TooltipContent>
+                      </Tooltip>
+                    </TooltipProvider>
+                  </Label>
+                  <Select
+                    onValueChange={handleRecordingFormatChange}
+                    value={settings.recordingFormat}
+                  >
+                    <SelectTrigger id="recordingFormat">
+                      <SelectValue placeholder="select recording format" />
+                    </SelectTrigger>
+                    <SelectContent>
+                      <SelectItem value="video">video (mp4)</SelectItem>
+                      <SelectItem value="webp">images (webp)</SelectItem>
+                      <SelectItem value="png">images (png)</SelectItem>
+                    </SelectContent>
+                  </Select>
+                </div>
                 <div className="flex flex-col space-y-2">
                   <Label htmlFor="fps" className="flex items-center space-x-2">
                     <span>frames per second (fps)</span>
@@ -1464,6 +1510,21 @@ export function RecordingSettings() {
               </div>
             )}
 
+            <div className="flex items-center justify-between">
+              <div className="space-y-1">
+                <h4 className="font-medium">enable access log</h4>
+                <p className="text-sm text-muted-foreground">
+                  record which client viewed or exported which time ranges of
+                  your recordings, shown in settings under access log
+                </p>
+              </div>
+              <Switch
+                id="access-log-toggle"
+                checked={settings.enableAccessLog}
+                onCheckedChange={handleAccessLogToggle}
+              />
+            </div>
+
             <div className="space-y-2">
               <div className="flex items-center gap-2 mb-2">
                 <Folder className="h-5 w-5" />
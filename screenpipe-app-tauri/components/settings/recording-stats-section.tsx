@@ -0,0 +1,182 @@
+"use client";
+import React, { useEffect, useState } from "react";
+import { RefreshCw } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Skeleton } from "@/components/ui/skeleton";
+
+interface MonitorStats {
+  monitor: string;
+  framesCaptured: number;
+  framesSkipped: number;
+  framesWritten: number;
+  captureSeconds: number;
+  ffmpegRestarts: number;
+  videoQueue: number;
+  ocrQueue: number;
+}
+
+// refresh interval of the panel, in ms
+const REFRESH_INTERVAL = 5000;
+
+// reads the monitors' series out of the prometheus text served at /metrics
+const parseMetrics = (text: string): MonitorStats[] => {
+  const monitors = new Map<string, MonitorStats>();
+  for (const line of text.split("\n")) {
+    const match = line.match(/^(\w+)\{([^}]*)\} (\S+)$/);
+    if (!match) {
+      continue;
+    }
+    const [, name, labelText, rawValue] = match;
+    const labels = Object.fromEntries(
+      labelText
+        .split(",")
+        .map((label) => label.split("="))
+        .map(([key, value]) => [key, value?.replace(/"/g, "")])
+    );
+    const monitor = labels.monitor;
+    if (monitor === undefined) {
+      continue;
+    }
+    const stats = monitors.get(monitor) ?? {
+      monitor,
+      framesCaptured: 0,
+      framesSkipped: 0,
+      framesWritten: 0,
+      captureSeconds: 0,
+      ffmpegRestarts: 0,
+      videoQueue: 0,
+      ocrQueue: 0,
+    };
+    const value = Number(rawValue);
+    switch (name) {
+      case "screenpipe_frames_captured_total":
+        stats.framesCaptured = value;
+        break;
+      case "screenpipe_frames_skipped_total":
+        stats.framesSkipped = value;
+        break;
+      case "screenpipe_frames_written_total":
+        stats.framesWritten = value;
+        break;
+      case "screenpipe_capture_duration_seconds_sum":
+        stats.captureSeconds = value;
+        break;
+      case "screenpipe_ffmpeg_restarts_total":
+        stats.ffmpegRestarts = value;
+        break;
+      case "screenpipe_queue_depth":
+        if (labels.queue === "video") {
+          stats.videoQueue = value;
+        } else if (labels.queue === "ocr") {
+          stats.ocrQueue = value;
+        }
+        break;
+    }
+    monitors.set(monitor, stats);
+  }
+  return Array.from(monitors.values()).sort((a, b) =>
+    a.monitor.localeCompare(b.monitor)
+  );
+};
+
+const averageLatency = (stats: MonitorStats) =>
+  stats.framesCaptured > 0
+    ? `${((stats.captureSeconds / stats.framesCaptured) * 1000).toFixed(0)} ms`
+    : "-";
+
+export function RecordingStatsSection() {
+  const [stats, setStats] = useState<MonitorStats[] | null>(null);
+  const [error, setError] = useState(false);
+  const [loading, setLoading] = useState(false);
+
+  const fetchStats = async () => {
+    setLoading(true);
+    try {
+      const response = await fetch("http://localhost:3030/metrics");
+      if (!response.ok) {
+        throw new Error(`status ${response.status}`);
+      }
+      setStats(parseMetrics(await response.text()));
+      setError(false);
+    } catch (error) {
+      console.error("failed to fetch metrics:", error);
+      setError(true);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    fetchStats();
+    const interval = setInterval(fetchStats, REFRESH_INTERVAL);
+    return () => clearInterval(interval);
+  }, []);
+
+  return (
+    <div className="w-full space-y-6 py-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-2xl font-bold">recording stats</h1>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={fetchStats}
+          disabled={loading}
+        >
+          <RefreshCw className="h-4 w-4 mr-2" />
+          refresh
+        </Button>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        what the recorder did with each monitor since it started. the same
+        numbers are served for prometheus at{" "}
+        <span className="font-mono">http://localhost:3030/metrics</span>.
+      </p>
+
+      {error ? (
+        <p className="text-sm text-muted-foreground">
+          failed to fetch the stats, is screenpipe running?
+        </p>
+      ) : stats === null ? (
+        <div className="space-y-2">
+          <Skeleton className="h-[40px] w-full" />
+          <Skeleton className="h-[40px] w-full" />
+        </div>
+      ) : stats.length === 0 ? (
+        <p className="text-sm text-muted-foreground">
+          no frames captured yet
+        </p>
+      ) : (
+        <div className="border rounded-lg overflow-auto">
+          <table className="w-full text-sm">
+            <thead className="text-left text-muted-foreground">
+              <tr className="border-b">
+                <th className="p-2 font-medium">monitor</th>
+                <th className="p-2 font-medium">captured</th>
+                <th className="p-2 font-medium">skipped</th>
+                <th className="p-2 font-medium">written</th>
+                <th className="p-2 font-medium">capture latency</th>
+                <th className="p-2 font-medium">ffmpeg restarts</th>
+                <th className="p-2 font-medium">queues (video / ocr)</th>
+              </tr>
+            </thead>
+            <tbody>
+              {stats.map((monitor) => (
+                <tr key={monitor.monitor} className="border-b last:border-0">
+                  <td className="p-2 font-mono">{monitor.monitor}</td>
+                  <td className="p-2">{monitor.framesCaptured}</td>
+                  <td className="p-2">{monitor.framesSkipped}</td>
+                  <td className="p-2">{monitor.framesWritten}</td>
+                  <td className="p-2">{averageLatency(monitor)}</td>
+                  <td className="p-2">{monitor.ffmpegRestarts}</td>
+                  <td className="p-2">
+                    {monitor.videoQueue} / {monitor.ocrQueue}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,254 @@
+"use client";
+import React, { useEffect, useState } from "react";
+import { Play, RefreshCw, Square } from "lucide-react";
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Skeleton } from "@/components/ui/skeleton";
+import { Textarea } from "@/components/ui/textarea";
+import { toast } from "@/components/ui/use-toast";
+
+const API = "http://localhost:3030";
+
+interface RecordingSession {
+  id: number;
+  name: string;
+  started_at: string;
+  ended_at: string | null;
+  end_reason: string | null;
+  notes: string | null;
+}
+
+interface SessionAppTime {
+  app_name: string;
+  seconds: number;
+  frame_count: number;
+}
+
+interface SessionDetails {
+  session: RecordingSession;
+  duration_secs: number;
+  apps: SessionAppTime[];
+  video_chunks: string[];
+}
+
+const formatTime = (timestamp: string) => new Date(timestamp).toLocaleString();
+
+const formatDuration = (secs: number) => {
+  const hours = Math.floor(secs / 3600);
+  const minutes = Math.floor((secs % 3600) / 60);
+  return hours > 0 ? `${hours}h ${minutes}m` : `${minutes}m ${secs % 60}s`;
+};
+
+const request = async <T,>(path: string, body?: object): Promise<T> => {
+  const response = await fetch(`${API}${path}`, {
+    method: body ? "POST" : "GET",
+    headers: body ? { "Content-Type": "application/json" } : undefined,
+    body: body ? JSON.stringify(body) : undefined,
+  });
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({}));
+    throw new Error(error.error ?? `status ${response.status}`);
+  }
+  return response.json();
+};
+
+const showError = (action: string) => (error: unknown) => {
+  console.error(`failed to ${action}:`, error);
+  toast({
+    title: "error",
+    description: `failed to ${action}: ${
+      error instanceof Error ? error.message : error
+    }`,
+    variant: "destructive",
+  });
+};
+
+export function SessionsSection() {
+  const [sessions, setSessions] = useState<RecordingSession[]>([]);
+  const [selected, setSelected] = useState<SessionDetails | null>(null);
+  const [name, setName] = useState("");
+  const [notes, setNotes] = useState("");
+  const [loading, setLoading] = useState(false);
+
+  const fetchSessions = async () => {
+    setLoading(true);
+    try {
+      setSessions(await request<RecordingSession[]>("/sessions?limit=50"));
+    } catch (error) {
+      showError("fetch the sessions, is screenpipe running?")(error);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const selectSession = async (id: number) => {
+    try {
+      const details = await request<SessionDetails>(`/sessions/${id}`);
+      setSelected(details);
+      setName(details.session.name);
+      setNotes(details.session.notes ?? "");
+    } catch (error) {
+      showError("load the session")(error);
+    }
+  };
+
+  const saveSession = async () => {
+    if (!selected) return;
+    try {
+      await request<RecordingSession>(`/sessions/${selected.session.id}`, {
+        name,
+        notes,
+      });
+      toast({ title: "session saved" });
+      await fetchSessions();
+      await selectSession(selected.session.id);
+    } catch (error) {
+      showError("save the session")(error);
+    }
+  };
+
+  const startSession = async () => {
+    try {
+      const session = await request<RecordingSession>("/sessions/start", {});
+      await fetchSessions();
+      await selectSession(session.id);
+    } catch (error) {
+      showError("start a session")(error);
+    }
+  };
+
+  const stopSession = async () => {
+    try {
+      const session = await request<RecordingSession>("/sessions/stop", {});
+      await fetchSessions();
+      await selectSession(session.id);
+    } catch (error) {
+      showError("stop the session")(error);
+    }
+  };
+
+  useEffect(() => {
+    fetchSessions();
+  }, []);
+
+  return (
+    <div className="w-full space-y-6 py-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-2xl font-bold">sessions</h1>
+        <div className="flex gap-2">
+          <Button variant="outline" size="sm" onClick={startSession}>
+            <Play className="h-4 w-4 mr-2" />
+            start
+          </Button>
+          <Button variant="outline" size="sm" onClick={stopSession}>
+            <Square className="h-4 w-4 mr-2" />
+            stop
+          </Button>
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={fetchSessions}
+            disabled={loading}
+          >
+            <RefreshCw className="h-4 w-4 mr-2" />
+            refresh
+          </Button>
+        </div>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        the recordings of each sitting, from every monitor. a session starts
+        with the first frame and ends after a long idle gap, at midnight or
+        when you stop it. start one to begin a new sitting right away.
+      </p>
+
+      {selected && (
+        <div className="border rounded-lg p-4 space-y-4">
+          <div className="flex items-center justify-between text-sm">
+            <span className="text-muted-foreground">
+              {formatTime(selected.session.started_at)} ·{" "}
+              {formatDuration(selected.duration_secs)} ·{" "}
+              {selected.video_chunks.length} videos
+            </span>
+            {selected.session.ended_at ? (
+              <Badge variant="outline">{selected.session.end_reason}</Badge>
+            ) : (
+              <Badge>recording</Badge>
+            )}
+          </div>
+          <Input
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            placeholder="title"
+          />
+          <Textarea
+            value={notes}
+            onChange={(e) => setNotes(e.target.value)}
+            placeholder="notes"
+            rows={4}
+          />
+          <div className="flex justify-end">
+            <Button size="sm" onClick={saveSession} disabled={!name.trim()}>
+              save
+            </Button>
+          </div>
+          {selected.apps.length > 0 && (
+            <table className="w-full text-sm">
+              <tbody>
+                {selected.apps.map((app) => (
+                  <tr key={app.app_name} className="border-b last:border-0">
+                    <td className="p-2">{app.app_name}</td>
+                    <td className="p-2 text-right whitespace-nowrap">
+                      {formatDuration(app.seconds)}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+        </div>
+      )}
+
+      {loading && sessions.length === 0 ? (
+        <div className="space-y-2">
+          <Skeleton className="h-[40px] w-full" />
+          <Skeleton className="h-[40px] w-full" />
+          <Skeleton className="h-[40px] w-full" />
+        </div>
+      ) : sessions.length === 0 ? (
+        <p className="text-sm text-muted-foreground">no sessions recorded yet</p>
+      ) : (
+        <div className="border rounded-lg overflow-auto">
+          <table className="w-full text-sm">
+            <thead className="text-left text-muted-foreground">
+              <tr className="border-b">
+                <th className="p-2 font-medium">title</th>
+                <th className="p-2 font-medium">started</th>
+                <th className="p-2 font-medium">ended</th>
+              </tr>
+            </thead>
+            <tbody>
+              {sessions.map((session) => (
+                <tr
+                  key={session.id}
+                  className="border-b last:border-0 cursor-pointer hover:bg-black/5"
+                  onClick={() => selectSession(session.id)}
+                >
+                  <td className="p-2" title={session.notes ?? undefined}>
+                    {session.name}
+                  </td>
+                  <td className="p-2 whitespace-nowrap">
+                    {formatTime(session.started_at)}
+                  </td>
+                  <td className="p-2 whitespace-nowrap">
+                    {session.ended_at ? formatTime(session.ended_at) : "-"}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  );
+}
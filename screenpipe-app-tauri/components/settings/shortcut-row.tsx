@@ -91,6 +91,7 @@ const ShortcutRow = ({
     stopRecordingShortcut: string;
     startAudioShortcut: string;
     stopAudioShortcut: string;
+    bookmarkShortcut: string;
     profileShortcuts: Record<string, string>;
     pipeShortcuts: Record<string, string>;
   }) => {
@@ -100,6 +101,7 @@ const ShortcutRow = ({
       stopShortcut: updatedShortcuts.stopRecordingShortcut,
       startAudioShortcut: updatedShortcuts.startAudioShortcut,
       stopAudioShortcut: updatedShortcuts.stopAudioShortcut,
+      bookmarkShortcut: updatedShortcuts.bookmarkShortcut,
       profileShortcuts: updatedShortcuts.profileShortcuts,
       pipeShortcuts: updatedShortcuts.pipeShortcuts,
     });
@@ -111,6 +113,7 @@ const ShortcutRow = ({
       stopShortcut: updatedShortcuts.stopRecordingShortcut,
       startAudioShortcut: updatedShortcuts.startAudioShortcut,
       stopAudioShortcut: updatedShortcuts.stopAudioShortcut,
+      bookmarkShortcut: updatedShortcuts.bookmarkShortcut,
       profileShortcuts: updatedShortcuts.profileShortcuts,
       pipeShortcuts: updatedShortcuts.pipeShortcuts,
     });
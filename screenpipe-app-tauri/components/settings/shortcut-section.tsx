@@ -75,6 +75,14 @@ const ShortcutSection = () => {
           value={settings.stopAudioShortcut}
         />
 
+        <ShortcutRow
+          type="global"
+          shortcut="bookmarkShortcut"
+          title="drop bookmark"
+          description="global shortcut to bookmark the current moment on the timeline"
+          value={settings.bookmarkShortcut}
+        />
+
         {profiles.length > 1 && (
           <>
             <div className="mt-8 mb-4">
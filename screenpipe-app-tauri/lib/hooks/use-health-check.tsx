@@ -1,6 +1,22 @@
 import { useState, useEffect, useCallback, useRef } from "react";
 import { debounce } from "lodash";
 
+export interface EncoderHealth {
+  monitor_id: number;
+  segment: string;
+  state: "ok" | "degraded" | "unhealthy";
+  warnings: number;
+  errors: number;
+  recent_errors: string[];
+  updated_at: string;
+}
+
+export interface PermissionCheck {
+  capability: "screen_recording" | "accessibility";
+  status: "granted" | "denied" | "not_needed";
+  settings_url: string | null;
+}
+
 interface HealthCheckResponse {
   status: string;
   status_code: number;
@@ -10,6 +26,9 @@ interface HealthCheckResponse {
   frame_status: string;
   audio_status: string;
   ui_status: string;
+  encoder_status?: string;
+  encoders?: EncoderHealth[];
+  permissions?: PermissionCheck[];
   message: string;
   verbose_instructions?: string | null;
   device_status_details?: string | null;
@@ -29,6 +48,7 @@ function isHealthChanged(
     oldHealth.frame_status !== newHealth.frame_status ||
     oldHealth.audio_status !== newHealth.audio_status ||
     oldHealth.ui_status !== newHealth.ui_status ||
+    oldHealth.encoder_status !== newHealth.encoder_status ||
     oldHealth.message !== newHealth.message
   );
 }
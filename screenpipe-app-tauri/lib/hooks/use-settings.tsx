@@ -18,6 +18,8 @@ import localforage from "localforage";
 
 export type VadSensitivity = "low" | "medium" | "high";
 
+export type RecordingFormat = "video" | "webp" | "png";
+
 export type AIProviderType =
 	| "native-ollama"
 	| "openai"
@@ -107,6 +109,7 @@ export type Settings = {
 	aiMaxContextChars: number;
 	fps: number;
 	vadSensitivity: VadSensitivity;
+	recordingFormat: RecordingFormat;
 	analyticsEnabled: boolean;
 	audioChunkDuration: number; // new field
 	useChineseMirror: boolean; // Add this line
@@ -117,6 +120,7 @@ export type Settings = {
 	autoStartEnabled: boolean;
 	enableFrameCache: boolean; // Add this line
 	enableUiMonitoring: boolean; // Add this line
+	enableAccessLog: boolean;
 	platform: string; // Add this line
 	disabledShortcuts: Shortcut[];
 	user: User;
@@ -125,6 +129,7 @@ export type Settings = {
 	stopRecordingShortcut: string;
 	startAudioShortcut: string;
 	stopAudioShortcut: string;
+	bookmarkShortcut: string;
 	pipeShortcuts: Record<string, string>;
 	enableRealtimeAudioTranscription: boolean;
 	realtimeAudioTranscriptionEngine: string;
@@ -173,6 +178,7 @@ const DEFAULT_SETTINGS: Settings = {
 	aiMaxContextChars: 512000,
 	fps: 0.5,
 	vadSensitivity: "high",
+	recordingFormat: "video",
 	analyticsEnabled: true,
 	audioChunkDuration: 30, // default to 10 seconds
 	useChineseMirror: false, // Add this line
@@ -187,6 +193,7 @@ const DEFAULT_SETTINGS: Settings = {
 	autoStartEnabled: true,
 	enableFrameCache: true, // Add this line
 	enableUiMonitoring: false, // Change from true to false
+	enableAccessLog: false,
 	platform: "unknown", // Add this line
 	disabledShortcuts: [],
 	user: {},
@@ -195,6 +202,7 @@ const DEFAULT_SETTINGS: Settings = {
 	stopRecordingShortcut: "Super+Alt+X",
 	startAudioShortcut: "",
 	stopAudioShortcut: "",
+	bookmarkShortcut: "Super+Alt+B",
 	pipeShortcuts: {},
 	enableRealtimeAudioTranscription: false,
 	realtimeAudioTranscriptionEngine: "deepgram",
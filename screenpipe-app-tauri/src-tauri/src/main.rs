@@ -81,6 +81,7 @@ struct ShortcutConfig {
     stop: String,
     start_audio: String,
     stop_audio: String,
+    bookmark: String,
     profile_shortcuts: HashMap<String, String>,
     pipe_shortcuts: HashMap<String, String>,
     disabled: Vec<String>,
@@ -151,6 +152,10 @@ impl ShortcutConfig {
                 .get("stopAudioShortcut")
                 .and_then(|v| v.as_str().map(String::from))
                 .unwrap_or_default(),
+            bookmark: store
+                .get("bookmarkShortcut")
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_else(|| "Super+Alt+B".to_string()),
             profile_shortcuts,
             pipe_shortcuts,
             disabled: store
@@ -199,6 +204,7 @@ async fn update_global_shortcuts(
     stop_shortcut: String,
     start_audio_shortcut: String,
     stop_audio_shortcut: String,
+    bookmark_shortcut: String,
     profile_shortcuts: HashMap<String, String>,
     pipe_shortcuts: HashMap<String, String>,
 ) -> Result<(), String> {
@@ -208,6 +214,7 @@ async fn update_global_shortcuts(
         stop: stop_shortcut,
         start_audio: start_audio_shortcut,
         stop_audio: stop_audio_shortcut,
+        bookmark: bookmark_shortcut,
         profile_shortcuts,
         pipe_shortcuts,
         disabled: ShortcutConfig::from_store(&app).await?.disabled,
@@ -310,6 +317,26 @@ async fn apply_shortcuts(app: &AppHandle, config: &ShortcutConfig) -> Result<(),
     )
     .await?;
 
+    // Register bookmark shortcut
+    register_shortcut(
+        app,
+        &config.bookmark,
+        config.is_disabled("bookmark"),
+        |app| {
+            info!("bookmark shortcut triggered");
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                match create_bookmark(&app).await {
+                    Ok(bookmark) => {
+                        let _ = app.emit("shortcut-bookmark", bookmark);
+                    }
+                    Err(e) => error!("failed to create bookmark: {}", e),
+                }
+            });
+        },
+    )
+    .await?;
+
     info!("pipe_shortcuts: {:?}", config.pipe_shortcuts);
 
     // Register pipe shortcuts
@@ -360,6 +387,27 @@ async fn get_pipe_port(pipe_id: &str) -> anyhow::Result<u16> {
         .ok_or_else(|| anyhow::anyhow!("no port found for pipe {}", pipe_id))
 }
 
+async fn create_bookmark(app: &AppHandle) -> anyhow::Result<Value> {
+    let port = get_store(app, None)?
+        .get("port")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(3030);
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://localhost:{}/bookmarks", port))
+        .json(&json!({
+            "name": format!("bookmark {}", chrono::Local::now().format("%H:%M:%S")),
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    Ok(response)
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct LogFile {
     name: String,
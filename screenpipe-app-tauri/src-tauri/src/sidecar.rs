@@ -241,6 +241,11 @@ fn spawn_sidecar(
         .and_then(|v| v.as_str().map(String::from))
         .unwrap_or(String::from("high"));
 
+    let recording_format = store
+        .get("recordingFormat")
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or(String::from("video"));
+
     let audio_chunk_duration = store
         .get("audioChunkDuration")
         .and_then(|v| v.as_u64())
@@ -276,6 +281,11 @@ fn spawn_sidecar(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
+    let enable_access_log = store
+        .get("enableAccessLog")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
     let data_dir = store
         .get("dataDir")
         .and_then(|v| v.as_str().map(String::from))
@@ -386,6 +396,11 @@ fn spawn_sidecar(
         args.push(vad_sensitivity.as_str());
     }
 
+    if recording_format != "video" {
+        args.push("--recording-format");
+        args.push(recording_format.as_str());
+    }
+
     let audio_chunk_duration_str = audio_chunk_duration.to_string();
     if audio_chunk_duration != 30 {
         args.push("--audio-chunk-duration");
@@ -408,6 +423,10 @@ fn spawn_sidecar(
         args.push("--enable-ui-monitoring");
     }
 
+    if enable_access_log {
+        args.push("--enable-access-log");
+    }
+
     if data_dir != "default" && !data_dir.is_empty() {
         args.push("--data-dir");
         args.push(data_dir.as_str());
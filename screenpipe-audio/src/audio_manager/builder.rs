@@ -32,6 +32,8 @@ pub struct AudioManagerOptions {
     pub deepgram_url: Option<String>,
     pub deepgram_websocket_url: Option<String>,
     pub output_path: Option<PathBuf>,
+    /// Store the audio as it's recorded and queue its transcription for the job workers.
+    pub defer_transcription: bool,
 }
 
 impl Default for AudioManagerOptions {
@@ -56,6 +58,7 @@ impl Default for AudioManagerOptions {
             db_path: None,
             deepgram_url,
             deepgram_websocket_url,
+            defer_transcription: false,
         }
     }
 }
@@ -137,6 +140,11 @@ impl AudioManagerBuilder {
         self
     }
 
+    pub fn defer_transcription(mut self, defer_transcription: bool) -> Self {
+        self.options.defer_transcription = defer_transcription;
+        self
+    }
+
     pub async fn build(&mut self, db: Arc<DatabaseManager>) -> Result<AudioManager> {
         self.validate_options()?;
         let options = &mut self.options;
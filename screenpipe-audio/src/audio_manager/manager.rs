@@ -3,7 +3,7 @@ use dashmap::DashMap;
 use std::{
     collections::HashSet,
     path::PathBuf,
-    sync::{atomic::Ordering, Arc},
+    sync::{atomic::Ordering, Arc, Mutex as StdMutex},
 };
 use tokio::{
     join,
@@ -19,12 +19,14 @@ use super::{start_device_monitor, stop_device_monitor, AudioManagerOptions};
 use crate::{
     core::{
         device::{parse_audio_device, AudioDevice},
+        engine::AudioTranscriptionEngine,
         record_and_transcribe,
     },
     device::device_manager::DeviceManager,
     segmentation::segmentation_manager::SegmentationManager,
     transcription::{
         deepgram::streaming::stream_transcription_deepgram,
+        deferred::{defer_audio_input, transcribe_deferred_chunk, AudioChunkJobPayload},
         handle_new_transcript,
         stt::process_audio_input,
         whisper::model::{create_whisper_context_parameters, download_whisper_model},
@@ -58,6 +60,7 @@ pub struct AudioManager {
     transcription_receiver_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
     recording_receiver_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
     stt_model_path: PathBuf,
+    whisper_context: Arc<StdMutex<Option<Arc<WhisperContext>>>>,
 }
 
 impl AudioManager {
@@ -93,6 +96,7 @@ impl AudioManager {
             recording_receiver_handle: Arc::new(RwLock::new(None)),
             transcription_receiver_handle: Arc::new(RwLock::new(None)),
             stt_model_path,
+            whisper_context: Arc::new(StdMutex::new(None)),
         };
 
         Ok(manager)
@@ -310,19 +314,23 @@ impl AudioManager {
         let languages = options.languages.clone();
         let deepgram_api_key = options.deepgram_api_key.clone();
         let audio_transcription_engine = options.transcription_engine.clone();
+        let defer_transcription = options.defer_transcription;
         let vad_engine = self.vad_engine.clone();
         let whisper_receiver = self.recording_receiver.clone();
-        let context_param = create_whisper_context_parameters(audio_transcription_engine.clone())?;
-
-        let quantized_path = self.stt_model_path.clone();
-        let whisper_context = Arc::new(
-            WhisperContext::new_with_params(&quantized_path.to_string_lossy(), context_param)
-                .expect("failed to load model"),
-        );
+        let whisper_context = self.whisper_context(audio_transcription_engine.clone())?;
+        let db = self.db.clone();
 
         Ok(tokio::spawn(async move {
             while let Ok(audio) = whisper_receiver.recv() {
                 info!("Received audio from device: {:?}", audio.device.name);
+                if defer_transcription {
+                    if let Err(e) =
+                        defer_audio_input(&db, audio, &output_path.clone().unwrap()).await
+                    {
+                        error!("Error storing audio for later transcription: {:?}", e);
+                    }
+                    continue;
+                }
                 if let Err(e) = process_audio_input(
                     audio.clone(),
                     vad_engine.clone(),
@@ -344,6 +352,47 @@ impl AudioManager {
         }))
     }
 
+    /// The whisper model, loaded on first use.
+    fn whisper_context(
+        &self,
+        engine: Arc<AudioTranscriptionEngine>,
+    ) -> Result<Arc<WhisperContext>> {
+        let mut whisper_context = self.whisper_context.lock().unwrap();
+        if let Some(context) = whisper_context.as_ref() {
+            return Ok(context.clone());
+        }
+        let context_param = create_whisper_context_parameters(engine)?;
+        let context = Arc::new(
+            WhisperContext::new_with_params(&self.stt_model_path.to_string_lossy(), context_param)
+                .expect("failed to load model"),
+        );
+        *whisper_context = Some(context.clone());
+        Ok(context)
+    }
+
+    /// Runs a [`TRANSCRIBE_AUDIO_CHUNK_JOB`] of `payload`, queued while transcription is
+    /// deferred.
+    ///
+    /// [`TRANSCRIBE_AUDIO_CHUNK_JOB`]: crate::transcription::deferred::TRANSCRIBE_AUDIO_CHUNK_JOB
+    pub async fn transcribe_deferred(&self, payload: &str) -> Result<()> {
+        let payload: AudioChunkJobPayload = serde_json::from_str(payload)?;
+        let options = self.options.read().await.clone();
+        let whisper_context = self.whisper_context(options.transcription_engine.clone())?;
+        transcribe_deferred_chunk(
+            self.db.clone(),
+            &payload,
+            self.vad_engine.clone(),
+            &self.segmentation_manager.segmentation_model_path,
+            self.segmentation_manager.embedding_manager.clone(),
+            self.segmentation_manager.embedding_extractor.clone(),
+            options.transcription_engine,
+            options.deepgram_api_key,
+            options.languages,
+            whisper_context,
+        )
+        .await
+    }
+
     async fn start_transcription_receiver_handler(&self) -> Result<JoinHandle<()>> {
         let transcription_receiver = self.transcription_receiver.clone();
         let db = self.db.clone();
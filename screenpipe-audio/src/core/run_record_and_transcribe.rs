@@ -3,10 +3,11 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Result};
+use screenpipe_core::capture_policy::audio_mute_reason;
 use tracing::{debug, error, info, warn};
 
 use crate::{core::update_device_capture_time, AudioInput};
@@ -38,6 +39,9 @@ pub async fn run_record_and_transcribe(
     while is_running.load(Ordering::Relaxed)
         && !audio_stream.is_disconnected.load(Ordering::Relaxed)
     {
+        // the overlap kept from the previous segment was recorded before now
+        let segment_start = SystemTime::now()
+            - Duration::from_secs_f64(collected_audio.len() as f64 / sample_rate as f64);
         while collected_audio.len() < max_samples && is_running.load(Ordering::Relaxed) {
             match receiver.recv().await {
                 Ok(chunk) => {
@@ -51,6 +55,12 @@ pub async fn run_record_and_transcribe(
             }
         }
 
+        if let Some(reason) = audio_mute_reason(segment_start) {
+            info!("dropping audio segment of {}: {}", device_name, reason);
+            collected_audio.clear();
+            continue;
+        }
+
         if !collected_audio.is_empty() {
             debug!("sending audio segment to audio model");
             match whisper_sender.try_send(AudioInput {
@@ -9,6 +9,7 @@ use deepgram::common::options::Encoding;
 use deepgram::common::stream_response::StreamResponse;
 use futures::channel::mpsc::{self, Receiver as FuturesReceiver};
 use futures::{SinkExt, TryStreamExt};
+use screenpipe_core::capture_policy::audio_mute_reason;
 use screenpipe_core::Language;
 use screenpipe_events::send_event;
 use serde::{Deserialize, Serialize};
@@ -238,6 +239,9 @@ fn get_stream(
         let mut total_bytes = 0;
         
         while let Ok(data) = stream.recv().await {
+            if audio_mute_reason(SystemTime::now()).is_some() {
+                continue;
+            }
             if device_type == DeviceType::Output {
                 let sum_squares: f32 = data.iter().map(|&x| x * x).sum();
                 let rms = (sum_squares / data.len() as f32).sqrt();
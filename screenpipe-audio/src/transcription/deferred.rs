@@ -0,0 +1,131 @@
+//! Transcription left to the job workers of the server, to spare the CPU while recording: the
+//! audio of each chunk is stored as it's recorded and a [`TRANSCRIBE_AUDIO_CHUNK_JOB`] queued
+//! for it, run by [`crate::audio_manager::AudioManager::transcribe_deferred`].
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use screenpipe_core::Language;
+use screenpipe_db::DatabaseManager;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use whisper_rs::WhisperContext;
+
+use crate::core::device::parse_audio_device;
+use crate::core::engine::AudioTranscriptionEngine;
+use crate::speaker::embedding::EmbeddingExtractor;
+use crate::speaker::embedding_manager::EmbeddingManager;
+use crate::speaker::prepare_segments;
+use crate::transcription::handle_new_transcript;
+use crate::transcription::stt::{transcribe_segments, SAMPLE_RATE};
+use crate::utils::audio::{pcm_decode, resample};
+use crate::utils::ffmpeg::{get_new_file_path, write_audio_to_file};
+use crate::vad::VadEngine;
+use crate::AudioInput;
+
+/// Transcribes an audio chunk stored while transcription is deferred.
+pub const TRANSCRIBE_AUDIO_CHUNK_JOB: &str = "transcribe_audio_chunk";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioChunkJobPayload {
+    pub file_path: String,
+    /// The device recorded, as `name (input)` or `name (output)`.
+    pub device: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Stores `audio` in `output_path` and queues its transcription.
+pub async fn defer_audio_input(
+    db: &DatabaseManager,
+    audio: AudioInput,
+    output_path: &PathBuf,
+) -> Result<()> {
+    let audio_data = if audio.sample_rate != SAMPLE_RATE {
+        resample(audio.data.as_ref(), audio.sample_rate, SAMPLE_RATE)?
+    } else {
+        audio.data.as_ref().to_vec()
+    };
+    let device = audio.device.to_string();
+    let file_path = get_new_file_path(&device, output_path);
+    write_audio_to_file(&audio_data, SAMPLE_RATE, &PathBuf::from(&file_path), false)?;
+
+    let payload = AudioChunkJobPayload {
+        file_path,
+        device,
+        recorded_at: Utc::now(),
+    };
+    db.enqueue_job(
+        TRANSCRIBE_AUDIO_CHUNK_JOB,
+        &serde_json::to_string(&payload)?,
+        3,
+    )
+    .await?;
+    debug!("queued transcription of {}", payload.file_path);
+    Ok(())
+}
+
+/// Transcribes the chunk of a [`TRANSCRIBE_AUDIO_CHUNK_JOB`] as the recorder does when it
+/// isn't deferred, its transcripts dated from when it was recorded. Like the chunks the
+/// recorder finds no speech in, a chunk without speech isn't kept.
+#[allow(clippy::too_many_arguments)]
+pub async fn transcribe_deferred_chunk(
+    db: Arc<DatabaseManager>,
+    payload: &AudioChunkJobPayload,
+    vad_engine: Arc<Mutex<Box<dyn VadEngine + Send>>>,
+    segmentation_model_path: &PathBuf,
+    embedding_manager: EmbeddingManager,
+    embedding_extractor: Arc<StdMutex<EmbeddingExtractor>>,
+    audio_transcription_engine: Arc<AudioTranscriptionEngine>,
+    deepgram_api_key: Option<String>,
+    languages: Vec<Language>,
+    whisper_context: Arc<WhisperContext>,
+) -> Result<()> {
+    let path = payload.file_path.clone();
+    let (audio_data, sample_rate) = tokio::task::spawn_blocking(move || pcm_decode(path)).await??;
+    let audio_data = if sample_rate != SAMPLE_RATE {
+        resample(&audio_data, sample_rate, SAMPLE_RATE)?
+    } else {
+        audio_data
+    };
+    let device = Arc::new(parse_audio_device(&payload.device)?);
+
+    let (mut segments, speech_ratio_ok) = prepare_segments(
+        &audio_data,
+        vad_engine,
+        segmentation_model_path,
+        embedding_manager,
+        embedding_extractor,
+        &payload.device,
+    )
+    .await?;
+    if !speech_ratio_ok {
+        if let Err(e) = std::fs::remove_file(&payload.file_path) {
+            warn!("failed to remove {}: {}", payload.file_path, e);
+        }
+        return Ok(());
+    }
+
+    let (sender, receiver) = crossbeam::channel::unbounded();
+    transcribe_segments(
+        &mut segments,
+        device,
+        payload.file_path.clone(),
+        payload.recorded_at.timestamp() as u64,
+        audio_transcription_engine.clone(),
+        deepgram_api_key,
+        languages,
+        &sender,
+        whisper_context,
+    )
+    .await?;
+    drop(sender);
+    handle_new_transcript(db.clone(), Arc::new(receiver), audio_transcription_engine).await;
+
+    let audio_chunk_id = db.get_or_insert_audio_chunk(&payload.file_path).await?;
+    db.set_audio_chunk_recorded_at(audio_chunk_id, payload.recorded_at)
+        .await?;
+    Ok(())
+}
@@ -145,6 +145,34 @@ pub async fn process_audio_input(
         error!("Error writing audio to file: {:?}", e);
     }
 
+    transcribe_segments(
+        &mut segments,
+        audio.device.clone(),
+        new_file_path,
+        timestamp,
+        audio_transcription_engine,
+        deepgram_api_key,
+        languages,
+        output_sender,
+        whisper_context,
+    )
+    .await
+}
+
+/// Transcribes the speech `segments` of the audio stored at `path`, recorded at `timestamp`,
+/// sending the transcripts to `output_sender`.
+#[allow(clippy::too_many_arguments)]
+pub async fn transcribe_segments(
+    segments: &mut tokio::sync::mpsc::Receiver<SpeechSegment>,
+    device: Arc<AudioDevice>,
+    new_file_path: String,
+    timestamp: u64,
+    audio_transcription_engine: Arc<AudioTranscriptionEngine>,
+    deepgram_api_key: Option<String>,
+    languages: Vec<Language>,
+    output_sender: &crossbeam::channel::Sender<TranscriptionResult>,
+    whisper_context: Arc<WhisperContext>,
+) -> Result<()> {
     while let Some(segment) = segments.recv().await {
         let path = new_file_path.clone();
         let transcription_result = if cfg!(target_os = "macos") {
@@ -154,7 +182,7 @@ pub async fn process_audio_input(
                 autoreleasepool(|| {
                     run_stt(
                         segment,
-                        audio.device.clone(),
+                        device.clone(),
                         audio_transcription_engine.clone(),
                         deepgram_api_key.clone(),
                         languages.clone(),
@@ -172,7 +200,7 @@ pub async fn process_audio_input(
         } else {
             run_stt(
                 segment,
-                audio.device.clone(),
+                device.clone(),
                 audio_transcription_engine.clone(),
                 deepgram_api_key.clone(),
                 languages.clone(),
@@ -0,0 +1,235 @@
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Which windows may be recorded, and when the microphone and speakers must be muted. Screen
+/// and audio capture both read the one policy, so their privacy rules can't drift apart.
+#[derive(Debug, Clone, Default)]
+pub struct CapturePolicy {
+    ignored_windows: Vec<String>,
+    included_windows: Vec<String>,
+    /// Also mute audio while a window the screen recording skips is focused.
+    mute_audio_on_ignored_windows: bool,
+    /// Meetings whose app or window title matches one of these are not recorded.
+    private_meetings: Vec<String>,
+}
+
+fn lowercase(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .map(|pattern| pattern.to_lowercase())
+        .collect()
+}
+
+fn find_match<'a>(patterns: &'a [String], app_name: &str, title: &str) -> Option<&'a str> {
+    let (app_name, title) = (app_name.to_lowercase(), title.to_lowercase());
+    patterns
+        .iter()
+        .find(|pattern| app_name.contains(pattern.as_str()) || title.contains(pattern.as_str()))
+        .map(String::as_str)
+}
+
+impl CapturePolicy {
+    /// Patterns match case-insensitively anywhere in the app name or window title.
+    pub fn new(ignored_windows: &[String], included_windows: &[String]) -> Self {
+        CapturePolicy {
+            ignored_windows: lowercase(ignored_windows),
+            included_windows: lowercase(included_windows),
+            ..Default::default()
+        }
+    }
+
+    pub fn mute_audio_on_ignored_windows(mut self, mute: bool) -> Self {
+        self.mute_audio_on_ignored_windows = mute;
+        self
+    }
+
+    pub fn private_meetings(mut self, patterns: &[String]) -> Self {
+        self.private_meetings = lowercase(patterns);
+        self
+    }
+
+    /// The policy with other window lists, its audio rules kept.
+    pub fn with_windows(mut self, ignored_windows: &[String], included_windows: &[String]) -> Self {
+        self.ignored_windows = lowercase(ignored_windows);
+        self.included_windows = lowercase(included_windows);
+        self
+    }
+
+    /// The include list, lowercased.
+    pub fn included_windows(&self) -> &[String] {
+        &self.included_windows
+    }
+
+    /// Whether a window may be recorded: included windows always are, ignored ones never, and
+    /// once an include list is set nothing else is.
+    pub fn allows_window(&self, app_name: &str, title: &str) -> bool {
+        if find_match(&self.included_windows, app_name, title).is_some() {
+            return true;
+        }
+        if find_match(&self.ignored_windows, app_name, title).is_some() {
+            return false;
+        }
+        self.included_windows.is_empty()
+    }
+
+    /// The pattern that makes a meeting held in this window private, if any.
+    pub fn private_meeting_match(&self, app_name: &str, title: &str) -> Option<&str> {
+        find_match(&self.private_meetings, app_name, title)
+    }
+
+    /// Why audio must be muted in `state`, if it must.
+    pub fn audio_mute_reason(&self, state: &CaptureState) -> Option<MuteReason> {
+        if let Some(meeting) = state.meeting.as_ref().filter(|meeting| meeting.private) {
+            return Some(MuteReason::PrivateMeeting(meeting.app_name.clone()));
+        }
+        match &state.focused {
+            Some((app_name, title))
+                if self.mute_audio_on_ignored_windows && !self.allows_window(app_name, title) =>
+            {
+                Some(MuteReason::IgnoredWindow(app_name.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MuteReason {
+    /// An app the screen recording skips is focused.
+    IgnoredWindow(String),
+    /// A meeting marked private is in progress in this app.
+    PrivateMeeting(String),
+}
+
+impl fmt::Display for MuteReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MuteReason::IgnoredWindow(app) => write!(f, "ignored window of {} focused", app),
+            MuteReason::PrivateMeeting(app) => write!(f, "private meeting in {}", app),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Meeting {
+    pub app_name: String,
+    pub private: bool,
+}
+
+/// What capture currently sees, as far as the policy cares.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureState {
+    pub focused: Option<(String, String)>,
+    /// The monitor the focused window is on, once the capture of that monitor reported it.
+    pub focused_monitor: Option<u32>,
+    pub meeting: Option<Meeting>,
+    /// The last mute and when it was last in effect, so audio recorded during it is muted even
+    /// when it is only handed over after the mute ended.
+    pub last_mute: Option<(MuteReason, SystemTime)>,
+}
+
+impl CaptureState {
+    /// Applies `change` and refreshes the mute bookkeeping.
+    pub fn update(&mut self, policy: &CapturePolicy, change: impl FnOnce(&mut Self)) {
+        let was_muted = policy.audio_mute_reason(self);
+        change(self);
+
+        // a private meeting stays private when its window is renamed or loses focus
+        if let (Some(meeting), Some((app_name, title))) = (&mut self.meeting, &self.focused) {
+            meeting.private |= policy.private_meeting_match(app_name, title).is_some();
+        }
+
+        if let Some(reason) = policy.audio_mute_reason(self).or(was_muted) {
+            self.last_mute = Some((reason, SystemTime::now()));
+        }
+    }
+
+    /// Why audio recorded from `since` until now must be dropped, if it must.
+    pub fn audio_mute_reason_since(
+        &self,
+        policy: &CapturePolicy,
+        since: SystemTime,
+    ) -> Option<MuteReason> {
+        policy.audio_mute_reason(self).or_else(|| {
+            self.last_mute
+                .as_ref()
+                .filter(|(_, at)| *at >= since)
+                .map(|(reason, _)| reason.clone())
+        })
+    }
+}
+
+static CAPTURE_POLICY: Lazy<RwLock<Arc<CapturePolicy>>> = Lazy::new(Default::default);
+static CAPTURE_STATE: Lazy<RwLock<CaptureState>> = Lazy::new(Default::default);
+
+/// Sets the policy of this process, screen and audio capture follow it from their next
+/// window or chunk on.
+pub fn set_capture_policy(policy: CapturePolicy) {
+    *CAPTURE_POLICY.write().unwrap() = Arc::new(policy);
+}
+
+pub fn capture_policy() -> Arc<CapturePolicy> {
+    CAPTURE_POLICY.read().unwrap().clone()
+}
+
+fn update_capture_state(change: impl FnOnce(&mut CaptureState)) {
+    if let Ok(mut state) = CAPTURE_STATE.write() {
+        state.update(&capture_policy(), change);
+    }
+}
+
+/// Called by screen capture with the focused window, whether or not it is recorded, every
+/// monitor's capture seeing it. `monitor_id` is the monitor of the capture when the window is
+/// on it, the other monitors don't unset it.
+pub fn report_focused_window(app_name: &str, title: &str, monitor_id: Option<u32>) {
+    update_capture_state(|state| {
+        let focused = Some((app_name.to_string(), title.to_string()));
+        if monitor_id.is_some() || state.focused != focused {
+            state.focused_monitor = monitor_id;
+        }
+        state.focused = focused;
+    });
+}
+
+/// The window screen capture last saw focused, as `(app_name, title)`.
+pub fn focused_window() -> Option<(String, String)> {
+    CAPTURE_STATE
+        .read()
+        .ok()
+        .and_then(|state| state.focused.clone())
+}
+
+/// The focused window, as `(app_name, title)`, when it's on `monitor_id`.
+pub fn focused_window_on(monitor_id: u32) -> Option<(String, String)> {
+    CAPTURE_STATE
+        .read()
+        .ok()
+        .filter(|state| state.focused_monitor == Some(monitor_id))
+        .and_then(|state| state.focused.clone())
+}
+
+pub fn report_meeting_started(app_name: &str) {
+    let private = capture_policy()
+        .private_meeting_match(app_name, "")
+        .is_some();
+    update_capture_state(|state| {
+        state.meeting = Some(Meeting {
+            app_name: app_name.to_string(),
+            private,
+        });
+    });
+}
+
+pub fn report_meeting_ended() {
+    update_capture_state(|state| state.meeting = None);
+}
+
+/// Why audio recorded from `since` until now must be dropped, if it must.
+pub fn audio_mute_reason(since: SystemTime) -> Option<MuteReason> {
+    CAPTURE_STATE
+        .read()
+        .ok()
+        .and_then(|state| state.audio_mute_reason_since(&capture_policy(), since))
+}
@@ -15,12 +15,50 @@ const EXECUTABLE_NAME: &str = "ffmpeg";
 #[cfg(windows)]
 const EXECUTABLE_NAME: &str = "ffmpeg.exe";
 
+#[cfg(not(windows))]
+const FFPROBE_EXECUTABLE_NAME: &str = "ffprobe";
+
+#[cfg(windows)]
+const FFPROBE_EXECUTABLE_NAME: &str = "ffprobe.exe";
+
 static FFMPEG_PATH: Lazy<Option<PathBuf>> = Lazy::new(find_ffmpeg_path_internal);
 
+static FFPROBE_PATH: Lazy<Option<PathBuf>> = Lazy::new(find_ffprobe_path_internal);
+
 pub fn find_ffmpeg_path() -> Option<PathBuf> {
     FFMPEG_PATH.as_ref().map(|p| p.clone())
 }
 
+/// Finds ffprobe, which isn't always installed next to the ffmpeg found.
+pub fn find_ffprobe_path() -> Option<PathBuf> {
+    FFPROBE_PATH.as_ref().map(|p| p.clone())
+}
+
+fn find_ffprobe_path_internal() -> Option<PathBuf> {
+    // the packages ffmpeg is installed from ship both
+    if let Some(ffprobe) = find_ffmpeg_path()
+        .map(|ffmpeg| ffmpeg.with_file_name(FFPROBE_EXECUTABLE_NAME))
+        .filter(|ffprobe| ffprobe.is_file())
+    {
+        debug!("Found ffprobe next to ffmpeg: {:?}", ffprobe);
+        return Some(ffprobe);
+    }
+    if let Ok(path) = which(FFPROBE_EXECUTABLE_NAME) {
+        debug!("Found ffprobe in PATH: {:?}", path);
+        return Some(path);
+    }
+    if let Some(ffprobe) = sidecar_dir()
+        .ok()
+        .map(|dir| dir.join(FFPROBE_EXECUTABLE_NAME))
+        .filter(|ffprobe| ffprobe.is_file())
+    {
+        debug!("Found ffprobe in directory: {:?}", ffprobe);
+        return Some(ffprobe);
+    }
+    error!("ffprobe not found");
+    None
+}
+
 fn find_ffmpeg_path_internal() -> Option<PathBuf> {
     debug!("Starting search for ffmpeg executable");
 
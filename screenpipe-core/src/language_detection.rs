@@ -0,0 +1,289 @@
+use crate::Language;
+
+/// Fewer letters than this are too little to tell languages apart, e.g. a lone button label.
+const MIN_LETTERS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Kana,
+    Han,
+    Hangul,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Thai,
+    Devanagari,
+}
+
+const SCRIPTS: [Script; 10] = [
+    Script::Latin,
+    Script::Kana,
+    Script::Han,
+    Script::Hangul,
+    Script::Cyrillic,
+    Script::Greek,
+    Script::Arabic,
+    Script::Hebrew,
+    Script::Thai,
+    Script::Devanagari,
+];
+
+fn script_of(c: char) -> Option<Script> {
+    match c as u32 {
+        0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Some(Script::Latin),
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => Some(Script::Kana),
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF => Some(Script::Han),
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Some(Script::Hangul),
+        0x400..=0x4FF => Some(Script::Cyrillic),
+        0x370..=0x3FF => Some(Script::Greek),
+        0x600..=0x6FF | 0x750..=0x77F => Some(Script::Arabic),
+        0x590..=0x5FF => Some(Script::Hebrew),
+        0xE00..=0xE7F => Some(Script::Thai),
+        0x900..=0x97F => Some(Script::Devanagari),
+        _ => None,
+    }
+}
+
+/// Frequent short words of languages written in Latin script, which tell them apart on the
+/// little text a screen usually holds.
+const LATIN_STOPWORDS: [(Language, &[&str]); 10] = [
+    (
+        Language::English,
+        &[
+            "the", "and", "of", "to", "is", "in", "for", "with", "you", "this", "that", "are",
+        ],
+    ),
+    (
+        Language::German,
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "mit", "sie", "ein", "auf", "für", "ich",
+        ],
+    ),
+    (
+        Language::French,
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "pour", "pas", "vous", "dans", "avec",
+        ],
+    ),
+    (
+        Language::Spanish,
+        &[
+            "el", "los", "las", "y", "que", "es", "por", "una", "para", "con", "del", "está",
+        ],
+    ),
+    (
+        Language::Portuguese,
+        &[
+            "o", "os", "e", "que", "não", "uma", "para", "com", "do", "da", "você", "é",
+        ],
+    ),
+    (
+        Language::Italian,
+        &[
+            "il", "di", "che", "è", "per", "non", "una", "sono", "gli", "della", "con", "questo",
+        ],
+    ),
+    (
+        Language::Dutch,
+        &[
+            "de", "het", "een", "en", "van", "niet", "dat", "ik", "zijn", "voor", "met", "je",
+        ],
+    ),
+    (
+        Language::Swedish,
+        &[
+            "och", "att", "det", "som", "är", "inte", "för", "med", "jag", "på", "en", "av",
+        ],
+    ),
+    (
+        Language::Polish,
+        &[
+            "i", "w", "nie", "się", "na", "jest", "że", "do", "to", "jak", "dla", "od",
+        ],
+    ),
+    (
+        Language::Turkish,
+        &[
+            "ve", "bir", "bu", "için", "ile", "da", "de", "değil", "çok", "ne", "olarak", "daha",
+        ],
+    ),
+];
+
+fn detect_latin(text: &str) -> Option<Language> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut best: Option<(Language, usize)> = None;
+    for (language, stopwords) in LATIN_STOPWORDS.iter() {
+        let hits = words
+            .iter()
+            .filter(|word| stopwords.contains(&word.as_str()))
+            .count();
+        if hits > best.as_ref().map_or(0, |(_, best_hits)| *best_hits) {
+            best = Some((language.clone(), hits));
+        }
+    }
+    best.map(|(language, _)| language)
+}
+
+fn script_counts(text: &str) -> [usize; SCRIPTS.len()] {
+    let mut counts = [0usize; SCRIPTS.len()];
+    for script in text.chars().filter_map(script_of) {
+        counts[script as usize] += 1;
+    }
+    counts
+}
+
+/// The script most of the letters are in, kana and kanji counting together since japanese
+/// mixes them.
+fn dominant_script(counts: &[usize; SCRIPTS.len()]) -> Option<Script> {
+    if counts.iter().sum::<usize>() == 0 {
+        return None;
+    }
+    let cjk = counts[Script::Kana as usize] + counts[Script::Han as usize];
+    SCRIPTS.iter().copied().max_by_key(|script| match script {
+        Script::Kana | Script::Han => cjk,
+        script => counts[*script as usize],
+    })
+}
+
+fn is_cjk(script: Script) -> bool {
+    matches!(script, Script::Kana | Script::Han)
+}
+
+/// The script `language` is written in, as far as detection tells scripts apart.
+fn script_of_language(language: &Language) -> Option<Script> {
+    match language {
+        Language::Japanese => Some(Script::Kana),
+        Language::Chinese => Some(Script::Han),
+        Language::Korean => Some(Script::Hangul),
+        Language::Russian
+        | Language::Ukrainian
+        | Language::Bulgarian
+        | Language::Serbian
+        | Language::Macedonian
+        | Language::Belarusian
+        | Language::Kazakh
+        | Language::Mongolian
+        | Language::Tatar => Some(Script::Cyrillic),
+        Language::Greek => Some(Script::Greek),
+        Language::Arabic | Language::Persian | Language::Urdu | Language::Pashto => {
+            Some(Script::Arabic)
+        }
+        Language::Hebrew | Language::Yiddish => Some(Script::Hebrew),
+        Language::Thai => Some(Script::Thai),
+        Language::Hindi | Language::Marathi | Language::Nepali | Language::Sanskrit => {
+            Some(Script::Devanagari)
+        }
+        Language::English
+        | Language::German
+        | Language::Spanish
+        | Language::French
+        | Language::Portuguese
+        | Language::Turkish
+        | Language::Polish
+        | Language::Catalan
+        | Language::Dutch
+        | Language::Swedish
+        | Language::Italian
+        | Language::Indonesian
+        | Language::Finnish
+        | Language::Malay
+        | Language::Czech
+        | Language::Romanian
+        | Language::Danish
+        | Language::Hungarian
+        | Language::Norwegian
+        | Language::Croatian
+        | Language::Lithuanian
+        | Language::Latin
+        | Language::Welsh
+        | Language::Slovak
+        | Language::Latvian
+        | Language::Azerbaijani
+        | Language::Slovenian
+        | Language::Estonian
+        | Language::Bosnian
+        | Language::Albanian
+        | Language::Swahili
+        | Language::Galician
+        | Language::Afrikaans
+        | Language::Uzbek
+        | Language::Faroese
+        | Language::Maltese
+        | Language::Luxembourgish
+        | Language::Tagalog
+        | Language::Hausa
+        | Language::Javanese => Some(Script::Latin),
+        _ => None,
+    }
+}
+
+/// Guesses the language of a piece of screen text from the scripts it is written in and, for
+/// Latin text, its most frequent words. Returns `None` when there is too little
+/// text or nothing gives the language away.
+pub fn detect_language(text: &str) -> Option<Language> {
+    let counts = script_counts(text);
+    let count = |script: Script| counts[script as usize];
+
+    if counts.iter().sum::<usize>() < MIN_LETTERS {
+        return None;
+    }
+
+    // japanese mixes kanji with kana, chinese has no kana at all
+    let (kana, han) = (count(Script::Kana), count(Script::Han));
+    let cjk = kana + han;
+    let dominant = dominant_script(&counts)?;
+
+    match dominant {
+        Script::Kana | Script::Han if kana * 10 >= cjk => Some(Language::Japanese),
+        Script::Kana | Script::Han => Some(Language::Chinese),
+        Script::Hangul => Some(Language::Korean),
+        Script::Cyrillic if text.contains(['і', 'ї', 'є', 'ґ']) => Some(Language::Ukrainian),
+        Script::Cyrillic => Some(Language::Russian),
+        Script::Greek => Some(Language::Greek),
+        Script::Arabic if text.contains(['پ', 'چ', 'ژ', 'گ']) => Some(Language::Persian),
+        Script::Arabic => Some(Language::Arabic),
+        Script::Hebrew => Some(Language::Hebrew),
+        Script::Thai => Some(Language::Thai),
+        Script::Devanagari => Some(Language::Hindi),
+        Script::Latin => detect_latin(text),
+    }
+}
+
+/// Like [`detect_language`], but answers one of `languages`, the ones OCR is configured for.
+/// Text the detector can't place, like a short label, or places in a language that isn't
+/// configured is taken as the only configured language written in its script, so kanji
+/// without kana is japanese when japanese is configured and chinese isn't. Any language may
+/// be answered when `languages` is empty.
+pub fn detect_language_among(text: &str, languages: &[Language]) -> Option<Language> {
+    let detected = detect_language(text);
+    if languages.is_empty()
+        || detected
+            .as_ref()
+            .is_some_and(|language| languages.contains(language))
+    {
+        return detected;
+    }
+
+    let counts = script_counts(text);
+    let script = dominant_script(&counts)?;
+    let has_kana = counts[Script::Kana as usize] > 0;
+    let mut candidates = languages
+        .iter()
+        .filter(|language| match script_of_language(language) {
+            // chinese has no kana at all
+            Some(Script::Han) if has_kana => false,
+            Some(written) => written == script || (is_cjk(written) && is_cjk(script)),
+            None => false,
+        });
+    match (candidates.next(), candidates.next()) {
+        (Some(language), None) => Some(language.clone()),
+        _ => None,
+    }
+}
@@ -1,5 +1,5 @@
 pub mod ffmpeg;
-pub use ffmpeg::find_ffmpeg_path;
+pub use ffmpeg::{find_ffmpeg_path, find_ffprobe_path};
 #[cfg(feature = "llm")]
 pub mod llm;
 #[cfg(feature = "llm")]
@@ -23,6 +23,7 @@ pub use llama::*;
 pub mod pipes;
 pub use pipes::*;
 mod language;
+mod language_detection;
 #[cfg(feature = "security")]
 pub mod pii_removal;
 
@@ -34,8 +35,10 @@ pub use pii_removal::*;
 
 pub mod network;
 pub use network::*;
+pub mod capture_policy;
 
 pub use language::{Language, TESSERACT_LANGUAGES};
+pub use language_detection::{detect_language, detect_language_among};
 pub mod embedding;
 pub use embedding::*;
 
@@ -0,0 +1,93 @@
+use screenpipe_core::capture_policy::{CapturePolicy, CaptureState, Meeting, MuteReason};
+use std::time::{Duration, SystemTime};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn test_window_rules() {
+    let policy = CapturePolicy::new(&strings(&["1Password", "private"]), &[]);
+    assert!(policy.allows_window("Safari", "news"));
+    assert!(!policy.allows_window("1password", "Vault"));
+    assert!(!policy.allows_window("Firefox", "Private Browsing"));
+
+    // included windows win over ignored ones, and nothing else is recorded
+    let policy = CapturePolicy::new(&strings(&["private"]), &strings(&["firefox"]));
+    assert!(policy.allows_window("Firefox", "Private Browsing"));
+    assert!(!policy.allows_window("Safari", "news"));
+}
+
+#[test]
+fn test_audio_muted_while_ignored_window_focused() {
+    let ignored = strings(&["1Password"]);
+    let mut state = CaptureState::default();
+    let focus = |app: &str| {
+        let app = app.to_string();
+        move |state: &mut CaptureState| state.focused = Some((app, "window".to_string()))
+    };
+
+    // opt-in: ignoring a window for the screen doesn't mute audio by itself
+    let policy = CapturePolicy::new(&ignored, &[]);
+    state.update(&policy, focus("1Password"));
+    assert_eq!(policy.audio_mute_reason(&state), None);
+
+    let policy = CapturePolicy::new(&ignored, &[]).mute_audio_on_ignored_windows(true);
+    let before = SystemTime::now() - Duration::from_secs(1);
+    state.update(&policy, focus("1Password"));
+    assert_eq!(
+        policy.audio_mute_reason(&state),
+        Some(MuteReason::IgnoredWindow("1Password".to_string()))
+    );
+
+    // audio recorded while it was focused is still muted after switching away
+    state.update(&policy, focus("Code"));
+    assert_eq!(policy.audio_mute_reason(&state), None);
+    assert!(state.audio_mute_reason_since(&policy, before).is_some());
+    let after = SystemTime::now() + Duration::from_secs(1);
+    assert_eq!(state.audio_mute_reason_since(&policy, after), None);
+}
+
+#[test]
+fn test_private_meetings_stay_muted_until_they_end() {
+    let policy = CapturePolicy::default().private_meetings(&strings(&["interview"]));
+    let mut state = CaptureState::default();
+
+    state.update(&policy, |state| {
+        state.meeting = Some(Meeting {
+            app_name: "zoom.us".to_string(),
+            private: false,
+        })
+    });
+    assert_eq!(policy.audio_mute_reason(&state), None);
+
+    state.update(&policy, |state| {
+        state.focused = Some(("zoom.us".to_string(), "Interview - Jane".to_string()))
+    });
+    assert_eq!(
+        policy.audio_mute_reason(&state),
+        Some(MuteReason::PrivateMeeting("zoom.us".to_string()))
+    );
+
+    state.update(&policy, |state| {
+        state.focused = Some(("Code".to_string(), "main.rs".to_string()))
+    });
+    assert!(policy.audio_mute_reason(&state).is_some());
+
+    state.update(&policy, |state| state.meeting = None);
+    assert_eq!(policy.audio_mute_reason(&state), None);
+}
+
+#[test]
+fn test_with_windows_keeps_audio_rules() {
+    let policy = CapturePolicy::new(&strings(&["1Password"]), &[])
+        .private_meetings(&strings(&["standup"]))
+        .with_windows(&strings(&["Slack"]), &[]);
+
+    assert!(policy.allows_window("1Password", "Vault"));
+    assert!(!policy.allows_window("slack", "general"));
+    assert_eq!(
+        policy.private_meeting_match("Zoom", "Standup"),
+        Some("standup")
+    );
+}
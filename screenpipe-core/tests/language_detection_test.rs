@@ -0,0 +1,103 @@
+use screenpipe_core::{detect_language, detect_language_among, Language};
+
+#[test]
+fn test_detect_language_by_script() {
+    assert_eq!(
+        detect_language("料金プランの比較はこちらをご覧ください"),
+        Some(Language::Japanese)
+    );
+    assert_eq!(
+        detect_language("我们的价格方案适合所有团队使用"),
+        Some(Language::Chinese)
+    );
+    assert_eq!(
+        detect_language("요금제를 비교해 보세요 지금 바로"),
+        Some(Language::Korean)
+    );
+    assert_eq!(
+        detect_language("Сравните тарифные планы для команды"),
+        Some(Language::Russian)
+    );
+}
+
+#[test]
+fn test_detect_language_by_stopwords() {
+    assert_eq!(
+        detect_language("Compare the plans and pick the one that is right for you"),
+        Some(Language::English)
+    );
+    assert_eq!(
+        detect_language("Vergleichen Sie die Tarife und wählen Sie das Paket, das für Sie passt"),
+        Some(Language::German)
+    );
+    assert_eq!(
+        detect_language("Comparez les offres et choisissez celle qui est faite pour vous"),
+        Some(Language::French)
+    );
+}
+
+#[test]
+fn test_detect_language_needs_enough_text() {
+    assert_eq!(detect_language("OK"), None);
+    assert_eq!(detect_language("12:45 PM 98%"), None);
+    assert_eq!(detect_language("Xcode Terminal Finder"), None);
+}
+
+#[test]
+fn test_detect_language_among_configured_languages() {
+    let japanese_and_english = [Language::Japanese, Language::English];
+
+    // kanji without kana would be chinese, but only japanese is configured
+    assert_eq!(
+        detect_language("東京都渋谷区道玄坂一丁目"),
+        Some(Language::Chinese)
+    );
+    assert_eq!(
+        detect_language_among("東京都渋谷区道玄坂一丁目", &japanese_and_english),
+        Some(Language::Japanese)
+    );
+    // labels too short to detect by themselves go by their script
+    assert_eq!(
+        detect_language_among("ファイル", &japanese_and_english),
+        Some(Language::Japanese)
+    );
+    assert_eq!(
+        detect_language_among("Settings", &japanese_and_english),
+        Some(Language::English)
+    );
+    // what the detector finds is kept when it's configured
+    assert_eq!(
+        detect_language_among(
+            "Compare the plans and pick the one that is right for you",
+            &japanese_and_english
+        ),
+        Some(Language::English)
+    );
+}
+
+#[test]
+fn test_detect_language_among_ambiguous_languages() {
+    // two configured languages in latin script, a short label could be either
+    assert_eq!(
+        detect_language_among("Settings", &[Language::English, Language::German]),
+        None
+    );
+    // no configured language is written in cyrillic
+    assert_eq!(
+        detect_language_among(
+            "Сравните тарифные планы для команды",
+            &[Language::Japanese, Language::English]
+        ),
+        None
+    );
+    // kana is never chinese
+    assert_eq!(
+        detect_language_among("ファイル", &[Language::Chinese, Language::English]),
+        None
+    );
+    // nothing configured, any language goes
+    assert_eq!(
+        detect_language_among("我们的价格方案适合所有团队使用", &[]),
+        Some(Language::Chinese)
+    );
+}
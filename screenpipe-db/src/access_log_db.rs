@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+
+use crate::{AccessLogEntry, DatabaseManager, NewAccessLogEntry};
+
+impl DatabaseManager {
+    pub async fn insert_access_log(&self, entry: &NewAccessLogEntry) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO access_log (
+                timestamp, action, method, path, client, remote_addr, user_agent,
+                start_time, end_time, status
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+        )
+        .bind(entry.timestamp)
+        .bind(&entry.action)
+        .bind(&entry.method)
+        .bind(&entry.path)
+        .bind(&entry.client)
+        .bind(&entry.remote_addr)
+        .bind(&entry.user_agent)
+        .bind(entry.start_time)
+        .bind(entry.end_time)
+        .bind(entry.status as i64)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Lists the access log, most recent first, optionally filtered by when the read happened
+    /// and by action or client.
+    pub async fn list_access_log(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        action: Option<&str>,
+        client: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AccessLogEntry>, sqlx::Error> {
+        sqlx::query_as::<_, AccessLogEntry>(
+            r#"
+            SELECT *
+            FROM access_log
+            WHERE (?1 IS NULL OR timestamp >= ?1)
+            AND (?2 IS NULL OR timestamp <= ?2)
+            AND (?3 IS NULL OR action = ?3)
+            AND (?4 IS NULL OR client = ?4)
+            ORDER BY timestamp DESC, id DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(action)
+        .bind(client)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+
+use crate::{Bookmark, DatabaseManager};
+
+impl DatabaseManager {
+    /// Inserts a named bookmark at `timestamp`. When `device_name` or `frame_id` are not
+    /// provided, the closest frame recorded at or before `timestamp` is used to fill them in.
+    pub async fn insert_bookmark(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        device_name: Option<&str>,
+        frame_id: Option<i64>,
+        note: Option<&str>,
+    ) -> Result<Bookmark, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let closest_frame: Option<(i64, String)> = if frame_id.is_none() {
+            sqlx::query_as(
+                r#"
+                SELECT id, device_name
+                FROM frames
+                WHERE timestamp <= ?1
+                AND (?2 IS NULL OR device_name = ?2)
+                ORDER BY timestamp DESC
+                LIMIT 1
+                "#,
+            )
+            .bind(timestamp)
+            .bind(device_name)
+            .fetch_optional(&mut *tx)
+            .await?
+        } else {
+            None
+        };
+
+        let frame_id = frame_id.or(closest_frame.as_ref().map(|(id, _)| *id));
+        let device_name = device_name
+            .map(String::from)
+            .or(closest_frame.map(|(_, device)| device));
+
+        let id = sqlx::query(
+            "INSERT INTO bookmarks (name, timestamp, device_name, frame_id, note, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(name)
+        .bind(timestamp)
+        .bind(&device_name)
+        .bind(frame_id)
+        .bind(note)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+        let bookmark = sqlx::query_as::<_, Bookmark>("SELECT * FROM bookmarks WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(bookmark)
+    }
+
+    /// Lists bookmarks ordered by timestamp, optionally filtered by time range, monitor and a
+    /// case-insensitive substring match on the name or note.
+    pub async fn list_bookmarks(
+        &self,
+        query: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        device_name: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Bookmark>, sqlx::Error> {
+        let pattern = query
+            .filter(|q| !q.trim().is_empty())
+            .map(|q| format!("%{}%", q.trim()));
+
+        sqlx::query_as::<_, Bookmark>(
+            r#"
+            SELECT *
+            FROM bookmarks
+            WHERE (?1 IS NULL OR name LIKE ?1 COLLATE NOCASE OR note LIKE ?1 COLLATE NOCASE)
+            AND (?2 IS NULL OR timestamp >= ?2)
+            AND (?3 IS NULL OR timestamp <= ?3)
+            AND (?4 IS NULL OR device_name = ?4)
+            ORDER BY timestamp ASC
+            LIMIT ?5 OFFSET ?6
+            "#,
+        )
+        .bind(pattern)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(device_name)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn update_bookmark(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<Bookmark, sqlx::Error> {
+        sqlx::query(
+            "UPDATE bookmarks SET name = COALESCE(?1, name), note = COALESCE(?2, note) WHERE id = ?3",
+        )
+        .bind(name)
+        .bind(note)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query_as::<_, Bookmark>("SELECT * FROM bookmarks WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    pub async fn delete_bookmark(&self, id: i64) -> Result<(), sqlx::Error> {
+        let result = sqlx::query("DELETE FROM bookmarks WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
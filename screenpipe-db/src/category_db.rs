@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+
+use crate::DatabaseManager;
+
+impl DatabaseManager {
+    /// The app, window and browser url of the frames between `start` and `end`, as
+    /// `(id, app_name, window_name, browser_url)`, to categorize them again after the rules
+    /// changed.
+    pub async fn get_frames_to_categorize(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(i64, String, String, Option<String>)>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, COALESCE(app_name, ''), COALESCE(window_name, ''), browser_url
+            FROM frames
+            WHERE timestamp >= ?1 AND timestamp < ?2
+            ORDER BY timestamp
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Sets the categories of many frames at once, as `(frame_id, category)`.
+    pub async fn set_frame_categories(
+        &self,
+        categories: &[(i64, Option<String>)],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (frame_id, category) in categories {
+            sqlx::query("UPDATE frames SET category = ?1 WHERE id = ?2")
+                .bind(category)
+                .bind(frame_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+
+use crate::{ClipboardEntry, DatabaseManager, NewClipboardEntry};
+
+impl DatabaseManager {
+    pub async fn insert_clipboard_entry(
+        &self,
+        entry: &NewClipboardEntry,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO clipboard_entries (timestamp, text, app_name, window_name, redacted)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            RETURNING id
+            "#,
+        )
+        .bind(entry.timestamp)
+        .bind(&entry.text)
+        .bind(&entry.app_name)
+        .bind(&entry.window_name)
+        .bind(entry.redacted)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Searches the copied text and the windows it was copied in, most recent first.
+    pub async fn search_clipboard(
+        &self,
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ClipboardEntry>, sqlx::Error> {
+        let (base_sql, where_clause) = if query.is_empty() {
+            ("clipboard_entries", "WHERE ?1 = ?1")
+        } else {
+            (
+                "clipboard_fts JOIN clipboard_entries ON clipboard_fts.clipboard_id = clipboard_entries.id",
+                "WHERE clipboard_fts MATCH ?1",
+            )
+        };
+
+        let sql = format!(
+            r#"
+            SELECT clipboard_entries.*
+            FROM {}
+            {}
+                AND (?2 IS NULL OR clipboard_entries.timestamp >= ?2)
+                AND (?3 IS NULL OR clipboard_entries.timestamp <= ?3)
+            ORDER BY clipboard_entries.timestamp DESC
+            LIMIT ?4 OFFSET ?5
+            "#,
+            base_sql, where_clause
+        );
+
+        sqlx::query_as(&sql)
+            .bind(query)
+            .bind(start_time)
+            .bind(end_time)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+    }
+}
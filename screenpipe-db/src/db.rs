@@ -19,6 +19,7 @@ use zerocopy::AsBytes;
 
 use futures::future::try_join_all;
 
+use crate::language_db::ocr_fts_table;
 use crate::{
     AudioChunksResponse, AudioDevice, AudioEntry, AudioResult, AudioResultRaw, ContentType,
     DeviceType, FrameData, FrameRow, OCREntry, OCRResult, OCRResultRaw, OcrEngine, OcrTextBlock,
@@ -195,6 +196,28 @@ impl DatabaseManager {
         Ok(affected as i64)
     }
 
+    /// Dates the audio chunk `audio_chunk_id` and its transcriptions from `recorded_at`, for
+    /// chunks transcribed well after they were recorded.
+    pub async fn set_audio_chunk_recorded_at(
+        &self,
+        audio_chunk_id: i64,
+        recorded_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE audio_chunks SET timestamp = ?2 WHERE id = ?1")
+            .bind(audio_chunk_id)
+            .bind(recorded_at)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE audio_transcriptions SET timestamp = ?2 WHERE audio_chunk_id = ?1")
+            .bind(audio_chunk_id)
+            .bind(recorded_at)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn insert_speaker(&self, embedding: &[f32]) -> Result<Speaker, SqlxError> {
         let mut tx = self.pool.begin().await?;
 
@@ -297,6 +320,7 @@ impl DatabaseManager {
         Ok(id)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert_frame(
         &self,
         device_name: &str,
@@ -305,6 +329,7 @@ impl DatabaseManager {
         app_name: Option<&str>,
         window_name: Option<&str>,
         focused: bool,
+        category: Option<&str>,
     ) -> Result<i64, sqlx::Error> {
         let mut tx = self.pool.begin().await?;
         debug!("insert_frame Transaction started");
@@ -341,7 +366,7 @@ impl DatabaseManager {
 
         // Insert the new frame with file_path as name and app/window metadata
         let id = sqlx::query(
-            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, device_name) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, device_name, category) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         )
         .bind(video_chunk_id)
         .bind(offset_index)
@@ -352,6 +377,7 @@ impl DatabaseManager {
         .bind(window_name)
         .bind(focused)
         .bind(device_name)
+        .bind(category)
         .execute(&mut *tx)
         .await?
         .last_insert_rowid();
@@ -363,6 +389,63 @@ impl DatabaseManager {
         Ok(id)
     }
 
+    /// Indexes a frame stored as its own image file rather than in a video chunk.
+    ///
+    /// The image is recorded as a chunk of one frame, shared by the frames of every window of
+    /// the capture, so that frames are looked up the same way in both recording formats.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_image_frame(
+        &self,
+        device_name: &str,
+        image_path: &str,
+        timestamp: Option<DateTime<Utc>>,
+        browser_url: Option<&str>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        focused: bool,
+        category: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing: Option<i64> = sqlx::query_scalar(
+            "SELECT id FROM video_chunks WHERE file_path = ?1 AND device_name = ?2",
+        )
+        .bind(image_path)
+        .bind(device_name)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let video_chunk_id = match existing {
+            Some(id) => id,
+            None => {
+                sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
+                    .bind(image_path)
+                    .bind(device_name)
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_rowid()
+            }
+        };
+
+        let id = sqlx::query(
+            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, device_name, category) VALUES (?1, 0, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )
+        .bind(video_chunk_id)
+        .bind(timestamp.unwrap_or_else(Utc::now))
+        .bind(image_path)
+        .bind(browser_url)
+        .bind(app_name)
+        .bind(window_name)
+        .bind(focused)
+        .bind(device_name)
+        .bind(category)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
     pub async fn insert_ocr_text(
         &self,
         frame_id: i64,
@@ -403,11 +486,13 @@ impl DatabaseManager {
         frame_name: Option<&str>,
         browser_url: Option<&str>,
         focused: Option<bool>,
+        language: Option<&str>,
+        category: Option<&str>,
     ) -> Result<Vec<SearchResult>, sqlx::Error> {
         let mut results = Vec::new();
 
-        // if focused or browser_url is present, we run only on OCR
-        if focused.is_some() || browser_url.is_some() {
+        // if focused, browser_url, language or category is present, we run only on OCR
+        if focused.is_some() || browser_url.is_some() || language.is_some() || category.is_some() {
             content_type = ContentType::OCR;
         }
 
@@ -430,6 +515,8 @@ impl DatabaseManager {
                                 frame_name,
                                 browser_url,
                                 focused,
+                                language,
+                                category,
                             ),
                             self.search_audio(
                                 query,
@@ -468,6 +555,8 @@ impl DatabaseManager {
                                 frame_name,
                                 browser_url,
                                 focused,
+                                language,
+                                category,
                             ),
                             self.search_ui_monitoring(
                                 query,
@@ -503,6 +592,8 @@ impl DatabaseManager {
                         frame_name,
                         browser_url,
                         focused,
+                        language,
+                        category,
                     )
                     .await?;
                 results.extend(ocr_results.into_iter().map(SearchResult::OCR));
@@ -581,6 +672,8 @@ impl DatabaseManager {
                         frame_name,
                         browser_url,
                         focused,
+                        language,
+                        category,
                     )
                     .await?;
                 let ui_results = self
@@ -625,6 +718,8 @@ impl DatabaseManager {
                         frame_name,
                         browser_url,
                         focused,
+                        language,
+                        category,
                     )
                     .await?;
 
@@ -673,6 +768,8 @@ impl DatabaseManager {
         frame_name: Option<&str>,
         browser_url: Option<&str>,
         focused: Option<bool>,
+        language: Option<&str>,
+        category: Option<&str>,
     ) -> Result<Vec<OCRResult>, sqlx::Error> {
         let mut frame_fts_parts = Vec::new();
 
@@ -701,6 +798,7 @@ impl DatabaseManager {
         }
 
         let frame_query = frame_fts_parts.join(" ");
+        let ocr_fts = ocr_fts_table(query);
 
         let sql = format!(
             r#"
@@ -718,7 +816,10 @@ impl DatabaseManager {
             video_chunks.device_name,
             GROUP_CONCAT(tags.name, ',') as tags,
             frames.browser_url,
-            frames.focused
+            frames.focused,
+            ocr_text.language,
+            video_chunks.host_name,
+            frames.category
         FROM frames
         JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
         JOIN ocr_text ON frames.id = ocr_text.frame_id
@@ -733,6 +834,12 @@ impl DatabaseManager {
             AND (?3 IS NULL OR frames.timestamp <= ?3)
             AND (?4 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) >= ?4)
             AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
+            AND (?9 IS NULL OR ocr_text.language = ?9 OR EXISTS (
+                SELECT 1 FROM ocr_text_block_languages
+                WHERE ocr_text_block_languages.frame_id = frames.id
+                    AND ocr_text_block_languages.language = ?9
+            ))
+            AND (?10 IS NULL OR frames.category = ?10)
         GROUP BY frames.id
         ORDER BY frames.timestamp DESC
         LIMIT ?7 OFFSET ?8
@@ -743,9 +850,9 @@ impl DatabaseManager {
                 "JOIN frames_fts ON frames.id = frames_fts.id"
             },
             ocr_fts_join = if query.trim().is_empty() {
-                ""
+                String::new()
             } else {
-                "JOIN ocr_text_fts ON ocr_text.frame_id = ocr_text_fts.frame_id"
+                format!("JOIN {ocr_fts} ON ocr_text.frame_id = {ocr_fts}.frame_id")
             },
             frame_fts_condition = if frame_query.trim().is_empty() {
                 ""
@@ -753,9 +860,9 @@ impl DatabaseManager {
                 "AND frames_fts MATCH ?1"
             },
             ocr_fts_condition = if query.trim().is_empty() {
-                ""
+                String::new()
             } else {
-                "AND ocr_text_fts MATCH ?6"
+                format!("AND {ocr_fts} MATCH ?6")
             }
         );
 
@@ -778,6 +885,8 @@ impl DatabaseManager {
             })
             .bind(limit)
             .bind(offset)
+            .bind(language)
+            .bind(category)
             .fetch_all(&self.pool)
             .await?;
 
@@ -801,6 +910,9 @@ impl DatabaseManager {
                     .unwrap_or_default(),
                 browser_url: raw.browser_url,
                 focused: raw.focused,
+                language: raw.language,
+                host_name: raw.host_name,
+                category: raw.category,
             })
             .collect())
     }
@@ -983,9 +1095,11 @@ impl DatabaseManager {
         frame_name: Option<&str>,
         browser_url: Option<&str>,
         focused: Option<bool>,
+        language: Option<&str>,
+        category: Option<&str>,
     ) -> Result<usize, sqlx::Error> {
-        // if focused or browser_url is present, we run only on OCR
-        if focused.is_some() || browser_url.is_some() {
+        // if focused, browser_url, language or category is present, we run only on OCR
+        if focused.is_some() || browser_url.is_some() || language.is_some() || category.is_some() {
             content_type = ContentType::OCR;
         }
 
@@ -1004,6 +1118,8 @@ impl DatabaseManager {
                 frame_name,
                 browser_url,
                 focused,
+                language,
+                category,
             ));
 
             let ui_future = Box::pin(self.count_search_results(
@@ -1019,6 +1135,8 @@ impl DatabaseManager {
                 None,
                 None,
                 None,
+                None,
+                None,
             ));
 
             if app_name.is_none() && window_name.is_none() {
@@ -1035,6 +1153,8 @@ impl DatabaseManager {
                     None,
                     None,
                     None,
+                    None,
+                    None,
                 ));
 
                 let (ocr_count, audio_count, ui_count) =
@@ -1099,19 +1219,29 @@ impl DatabaseManager {
                        AND (?3 IS NULL OR frames.timestamp <= ?3)
                        AND (?4 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) >= ?4)
                        AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
-                       AND (?6 IS NULL OR frames.name LIKE '%' || ?6 || '%')"#,
+                       AND (?6 IS NULL OR frames.name LIKE '%' || ?6 || '%')
+                       AND (?7 IS NULL OR ocr_text.language = ?7 OR EXISTS (
+                           SELECT 1 FROM ocr_text_block_languages
+                           WHERE ocr_text_block_languages.frame_id = frames.id
+                               AND ocr_text_block_languages.language = ?7
+                       ))
+                       AND (?8 IS NULL OR frames.category = ?8)"#,
                 base_table = if ocr_query.is_empty() {
                     "frames
                      JOIN ocr_text ON frames.id = ocr_text.frame_id"
+                        .to_string()
                 } else {
-                    "ocr_text_fts
-                     JOIN ocr_text ON ocr_text_fts.frame_id = ocr_text.frame_id
-                     JOIN frames ON ocr_text.frame_id = frames.id"
+                    format!(
+                        "{ocr_fts}
+                     JOIN ocr_text ON {ocr_fts}.frame_id = ocr_text.frame_id
+                     JOIN frames ON ocr_text.frame_id = frames.id",
+                        ocr_fts = ocr_fts_table(query)
+                    )
                 },
                 where_clause = if ocr_query.is_empty() {
-                    "1=1"
+                    "1=1".to_string()
                 } else {
-                    "ocr_text_fts MATCH ?1"
+                    format!("{} MATCH ?1", ocr_fts_table(query))
                 }
             ),
             ContentType::UI => format!(
@@ -1172,6 +1302,8 @@ impl DatabaseManager {
                     .bind(min_length.map(|l| l as i64))
                     .bind(max_length.map(|l| l as i64))
                     .bind(frame_name)
+                    .bind(language)
+                    .bind(category)
                     .fetch_one(&self.pool)
                     .await?
             }
@@ -1476,7 +1608,8 @@ impl DatabaseManager {
             COALESCE(f.app_name, ot.app_name) as app_name,
             COALESCE(f.window_name, ot.window_name) as window_name,
             vc.device_name as screen_device,
-            vc.file_path as video_path
+            vc.file_path as video_path,
+            f.category
         FROM frames f
         JOIN video_chunks vc ON f.video_chunk_id = vc.id
         LEFT JOIN ocr_text ot ON f.id = ot.frame_id
@@ -1539,6 +1672,7 @@ impl DatabaseManager {
                     window_name: row.get("window_name"),
                     device_name: row.get("screen_device"),
                     video_file_path: row.get("video_path"),
+                    category: row.get("category"),
                 });
             }
         }
@@ -2062,7 +2196,12 @@ impl DatabaseManager {
                 ocr_text.ocr_engine,
                 frames.window_name,
                 GROUP_CONCAT(tags.name, ',') as tags,
-                frames.browser_url
+                frames.browser_url,
+                frames.focused,
+                video_chunks.device_name,
+                ocr_text.language,
+                video_chunks.host_name,
+                frames.category
             FROM embedding_matches
             JOIN ocr_text ON embedding_matches.frame_id = ocr_text.frame_id
             JOIN frames ON ocr_text.frame_id = frames.id
@@ -2102,6 +2241,9 @@ impl DatabaseManager {
                     .unwrap_or_default(),
                 browser_url: raw.browser_url,
                 focused: raw.focused,
+                language: raw.language,
+                host_name: raw.host_name,
+                category: raw.category,
             })
             .collect())
     }
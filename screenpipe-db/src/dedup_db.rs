@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, FrameHash};
+
+impl DatabaseManager {
+    pub async fn set_frame_perceptual_hash(
+        &self,
+        frame_id: i64,
+        perceptual_hash: u64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE frames SET perceptual_hash = ?1 WHERE id = ?2")
+            .bind(perceptual_hash as i64)
+            .bind(frame_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Inserts a frame showing the same screen as `canonical_id` that was not encoded again: it
+    /// points at the video and offset of the canonical frame, so the timeline keeps its true
+    /// duration while the screen is stored once.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_duplicate_frame(
+        &self,
+        canonical_id: i64,
+        timestamp: Option<DateTime<Utc>>,
+        browser_url: Option<&str>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        focused: bool,
+        perceptual_hash: u64,
+        category: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO frames (
+                video_chunk_id, offset_index, timestamp, name, browser_url, app_name,
+                window_name, focused, device_name, perceptual_hash, duplicate_of, category
+            )
+            SELECT video_chunk_id, offset_index, ?2, name, ?3, ?4, ?5, ?6, device_name, ?7, id, ?8
+            FROM frames
+            WHERE id = ?1
+            "#,
+        )
+        .bind(canonical_id)
+        .bind(timestamp.unwrap_or_else(Utc::now))
+        .bind(browser_url)
+        .bind(app_name)
+        .bind(window_name)
+        .bind(focused)
+        .bind(perceptual_hash as i64)
+        .bind(category)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Hashed frames not collapsed yet, by monitor and in recording order.
+    pub async fn get_frame_hashes(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FrameHash>, sqlx::Error> {
+        sqlx::query_as::<_, FrameHash>(
+            r#"
+            SELECT id, device_name, app_name, window_name, timestamp, perceptual_hash,
+                (SELECT text FROM ocr_text WHERE ocr_text.frame_id = frames.id LIMIT 1) AS text
+            FROM frames
+            WHERE perceptual_hash IS NOT NULL
+            AND duplicate_of IS NULL
+            AND timestamp >= ?1 AND timestamp <= ?2
+            ORDER BY device_name, timestamp, id
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Collapses each `(frame_id, canonical_id)` pair: the frame is marked as a duplicate of the
+    /// canonical one. Its text is kept, the frame staying searchable at its own time.
+    pub async fn collapse_duplicate_frames(
+        &self,
+        duplicates: &[(i64, i64)],
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut collapsed = 0;
+        for (frame_id, canonical_id) in duplicates {
+            collapsed += sqlx::query(
+                "UPDATE frames SET duplicate_of = ?2 WHERE id = ?1 AND duplicate_of IS NULL",
+            )
+            .bind(frame_id)
+            .bind(canonical_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(collapsed)
+    }
+}
@@ -0,0 +1,146 @@
+use futures::future::try_join_all;
+use zerocopy::AsBytes;
+
+use crate::{AudioResult, AudioResultRaw, DatabaseManager, DeviceType};
+
+impl DatabaseManager {
+    /// OCR text of frames that have no embedding yet, newest first.
+    pub async fn get_ocr_text_without_embeddings(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT ocr_text.frame_id, ocr_text.text
+            FROM ocr_text
+            LEFT JOIN ocr_text_embeddings ON ocr_text_embeddings.frame_id = ocr_text.frame_id
+            WHERE ocr_text_embeddings.id IS NULL
+            AND LENGTH(TRIM(ocr_text.text)) > 0
+            GROUP BY ocr_text.frame_id
+            ORDER BY ocr_text.frame_id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Transcriptions that have no embedding yet, newest first.
+    pub async fn get_transcriptions_without_embeddings(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT audio_transcriptions.id, audio_transcriptions.transcription
+            FROM audio_transcriptions
+            LEFT JOIN audio_transcription_embeddings
+                ON audio_transcription_embeddings.audio_transcription_id = audio_transcriptions.id
+            WHERE audio_transcription_embeddings.id IS NULL
+            AND LENGTH(TRIM(audio_transcriptions.transcription)) > 0
+            ORDER BY audio_transcriptions.id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn insert_transcription_embedding(
+        &self,
+        audio_transcription_id: i64,
+        embedding: String,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO audio_transcription_embeddings (audio_transcription_id, embedding) VALUES (?1, ?2)",
+        )
+        .bind(audio_transcription_id)
+        .bind(embedding)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Transcriptions closest to `embedding`, filtered by cosine distance below `threshold`.
+    pub async fn search_similar_transcriptions(
+        &self,
+        embedding: Vec<f32>,
+        limit: u32,
+        threshold: f32,
+    ) -> Result<Vec<AudioResult>, sqlx::Error> {
+        let sql = r#"
+            WITH embedding_matches AS (
+                SELECT
+                    audio_transcription_id,
+                    vec_distance_cosine(embedding, vec_f32(?1)) as similarity
+                FROM audio_transcription_embeddings
+                WHERE vec_distance_cosine(embedding, vec_f32(?1)) < ?2
+                ORDER BY similarity ASC
+                LIMIT ?3
+            )
+            SELECT
+                audio_transcriptions.audio_chunk_id,
+                audio_transcriptions.transcription,
+                audio_transcriptions.timestamp,
+                audio_chunks.file_path,
+                audio_transcriptions.offset_index,
+                audio_transcriptions.transcription_engine,
+                GROUP_CONCAT(tags.name, ',') as tags,
+                audio_transcriptions.device as device_name,
+                audio_transcriptions.is_input_device,
+                audio_transcriptions.speaker_id,
+                audio_transcriptions.start_time,
+                audio_transcriptions.end_time
+            FROM embedding_matches
+            JOIN audio_transcriptions ON embedding_matches.audio_transcription_id = audio_transcriptions.id
+            JOIN audio_chunks ON audio_transcriptions.audio_chunk_id = audio_chunks.id
+            LEFT JOIN audio_tags ON audio_chunks.id = audio_tags.audio_chunk_id
+            LEFT JOIN tags ON audio_tags.tag_id = tags.id
+            GROUP BY audio_transcriptions.id
+            ORDER BY embedding_matches.similarity ASC
+        "#;
+
+        let raw_results: Vec<AudioResultRaw> = sqlx::query_as(sql)
+            .bind(embedding.as_bytes())
+            .bind(threshold)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let futures: Vec<_> = raw_results
+            .into_iter()
+            .map(|raw| async move {
+                let speaker = match raw.speaker_id {
+                    Some(id) => self.get_speaker_by_id(id).await.ok(),
+                    None => None,
+                };
+
+                Ok::<AudioResult, sqlx::Error>(AudioResult {
+                    audio_chunk_id: raw.audio_chunk_id,
+                    transcription: raw.transcription,
+                    timestamp: raw.timestamp,
+                    file_path: raw.file_path,
+                    offset_index: raw.offset_index,
+                    transcription_engine: raw.transcription_engine,
+                    tags: raw
+                        .tags
+                        .map(|s| s.split(',').map(|s| s.to_owned()).collect())
+                        .unwrap_or_default(),
+                    device_name: raw.device_name,
+                    device_type: if raw.is_input_device {
+                        DeviceType::Input
+                    } else {
+                        DeviceType::Output
+                    },
+                    speaker,
+                    start_time: raw.start_time,
+                    end_time: raw.end_time,
+                })
+            })
+            .collect();
+
+        try_join_all(futures).await
+    }
+}
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DatabaseManager, Job, JobStatusCount, JOB_DONE, JOB_FAILED, JOB_PENDING, JOB_RUNNING};
+
+impl DatabaseManager {
+    pub async fn enqueue_job(
+        &self,
+        kind: &str,
+        payload: &str,
+        max_attempts: i64,
+    ) -> Result<Job, sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query_as::<_, Job>(
+            r#"
+            INSERT INTO jobs (kind, payload, status, max_attempts, run_after, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?5)
+            RETURNING *
+            "#,
+        )
+        .bind(kind)
+        .bind(payload)
+        .bind(JOB_PENDING)
+        .bind(max_attempts.max(1))
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Atomically marks the oldest due pending job of one of `kinds` as running and returns it.
+    pub async fn claim_next_job(
+        &self,
+        kinds: &[String],
+        now: DateTime<Utc>,
+    ) -> Result<Option<Job>, sqlx::Error> {
+        if kinds.is_empty() {
+            return Ok(None);
+        }
+        let placeholders = vec!["?"; kinds.len()].join(", ");
+        let sql = format!(
+            r#"
+            UPDATE jobs
+            SET status = ?, attempts = attempts + 1, updated_at = ?
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE status = ? AND run_after <= ? AND kind IN ({})
+                ORDER BY run_after, id
+                LIMIT 1
+            )
+            RETURNING *
+            "#,
+            placeholders
+        );
+
+        let mut query = sqlx::query_as::<_, Job>(&sql)
+            .bind(JOB_RUNNING)
+            .bind(now)
+            .bind(JOB_PENDING)
+            .bind(now);
+        for kind in kinds {
+            query = query.bind(kind);
+        }
+        query.fetch_optional(&self.pool).await
+    }
+
+    pub async fn update_job_progress(&self, id: i64, progress: f64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE jobs SET progress = ?1, updated_at = ?2 WHERE id = ?3")
+            .bind(progress.clamp(0.0, 1.0))
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn complete_job(&self, id: i64) -> Result<Job, sqlx::Error> {
+        sqlx::query_as::<_, Job>(
+            "UPDATE jobs SET status = ?1, progress = 1, error = NULL, updated_at = ?2 WHERE id = ?3 RETURNING *",
+        )
+        .bind(JOB_DONE)
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Records a failed attempt. The job is rescheduled after `retry_delay`, doubled on every
+    /// attempt, until it runs out of attempts and is marked failed.
+    pub async fn fail_job(
+        &self,
+        id: i64,
+        error: &str,
+        retry_delay: Duration,
+    ) -> Result<Job, sqlx::Error> {
+        let now = Utc::now();
+        let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let (status, run_after) = if job.attempts >= job.max_attempts {
+            (JOB_FAILED, job.run_after)
+        } else {
+            let backoff = 2_i32.saturating_pow(job.attempts.saturating_sub(1).clamp(0, 16) as u32);
+            (JOB_PENDING, now + retry_delay * backoff)
+        };
+
+        sqlx::query_as::<_, Job>(
+            "UPDATE jobs SET status = ?1, error = ?2, run_after = ?3, updated_at = ?4 WHERE id = ?5 RETURNING *",
+        )
+        .bind(status)
+        .bind(error)
+        .bind(run_after)
+        .bind(now)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Puts jobs that were running when the previous process stopped back in the queue.
+    pub async fn requeue_running_jobs(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE jobs SET status = ?1, updated_at = ?2 WHERE status = ?3")
+            .bind(JOB_PENDING)
+            .bind(Utc::now())
+            .bind(JOB_RUNNING)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_jobs(
+        &self,
+        status: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Job>, sqlx::Error> {
+        sqlx::query_as::<_, Job>(
+            r#"
+            SELECT * FROM jobs
+            WHERE ?1 IS NULL OR status = ?1
+            ORDER BY id DESC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn count_jobs_by_status(&self) -> Result<Vec<JobStatusCount>, sqlx::Error> {
+        sqlx::query_as::<_, JobStatusCount>(
+            "SELECT status, COUNT(*) AS count FROM jobs GROUP BY status ORDER BY status",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Pending or running jobs of `kind`, used to avoid queueing the same periodic work twice.
+    pub async fn count_unfinished_jobs(&self, kind: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE kind = ?1 AND status IN (?2, ?3)")
+            .bind(kind)
+            .bind(JOB_PENDING)
+            .bind(JOB_RUNNING)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Pending or running jobs of `kind` queued before job `id`, for jobs that must run in
+    /// the order they were queued.
+    pub async fn count_unfinished_jobs_of_kind_before(
+        &self,
+        kind: &str,
+        id: i64,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM jobs WHERE id < ?1 AND kind = ?2 AND status IN (?3, ?4)",
+        )
+        .bind(id)
+        .bind(kind)
+        .bind(JOB_PENDING)
+        .bind(JOB_RUNNING)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Pending or running jobs queued before job `id` with the same payload, i.e. about the
+    /// same chunk.
+    pub async fn count_unfinished_jobs_before(
+        &self,
+        id: i64,
+        payload: &str,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM jobs WHERE id < ?1 AND payload = ?2 AND status IN (?3, ?4)",
+        )
+        .bind(id)
+        .bind(payload)
+        .bind(JOB_PENDING)
+        .bind(JOB_RUNNING)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,102 @@
+use crate::DatabaseManager;
+
+impl DatabaseManager {
+    /// Sets the language of the OCR text just inserted for `frame_id`. Rows of the frame's other
+    /// windows already have theirs, so only the last one is updated.
+    pub async fn set_ocr_text_language(
+        &self,
+        frame_id: i64,
+        language: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE ocr_text SET language = ?1 WHERE frame_id = ?2 AND language IS NULL")
+            .bind(language)
+            .bind(frame_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Stores how many text blocks of the OCR text of `frame_id` are in each language, as
+    /// `(language, blocks)`.
+    pub async fn set_ocr_block_languages(
+        &self,
+        frame_id: i64,
+        languages: &[(String, u32)],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (language, blocks) in languages {
+            sqlx::query(
+                "INSERT OR REPLACE INTO ocr_text_block_languages (frame_id, language, blocks) VALUES (?1, ?2, ?3)",
+            )
+            .bind(frame_id)
+            .bind(language)
+            .bind(blocks)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The languages of the text blocks of `frame_id` as `(language, blocks)`, most blocks first.
+    pub async fn get_ocr_block_languages(
+        &self,
+        frame_id: i64,
+    ) -> Result<Vec<(String, u32)>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT language, blocks FROM ocr_text_block_languages WHERE frame_id = ?1 ORDER BY blocks DESC, language",
+        )
+        .bind(frame_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// OCR text recorded before language detection existed, as `(rowid, text)`.
+    pub async fn get_ocr_text_without_language(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as("SELECT rowid, text FROM ocr_text WHERE language IS NULL LIMIT ?1")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn count_ocr_text_without_language(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM ocr_text WHERE language IS NULL")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Stores the languages of a batch of `(rowid, language)` in one transaction.
+    pub async fn update_ocr_text_languages(
+        &self,
+        languages: &[(i64, String)],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (rowid, language) in languages {
+            sqlx::query("UPDATE ocr_text SET language = ?1 WHERE rowid = ?2")
+                .bind(language)
+                .bind(rowid)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// FTS table to match `query` against. All the text is also indexed by trigram, since
+/// unicode61 can't split CJK text into words, but trigrams only match queries of three
+/// characters or more.
+pub(crate) fn ocr_fts_table(query: &str) -> &'static str {
+    let is_cjk = |c: char| {
+        matches!(c as u32,
+            0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xFF66..=0xFF9F)
+    };
+    if query.chars().any(is_cjk) && query.chars().filter(|c| !c.is_whitespace()).count() >= 3 {
+        "ocr_text_trigram_fts"
+    } else {
+        "ocr_text_fts"
+    }
+}
@@ -1,6 +1,18 @@
+mod access_log_db;
+mod bookmark_db;
+mod category_db;
+mod clipboard_db;
 mod db;
+mod dedup_db;
+mod embedding_db;
+mod job_db;
+mod language_db;
 mod migration_worker;
+mod redaction_db;
+mod schema_db;
+mod session_db;
 mod types;
+mod ui_event_db;
 mod video_db;
 
 pub use db::DatabaseManager;
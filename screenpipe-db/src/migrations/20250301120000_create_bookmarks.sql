@@ -0,0 +1,14 @@
+-- Create bookmarks table for named markers on the timeline
+CREATE TABLE IF NOT EXISTS bookmarks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    timestamp DATETIME NOT NULL,
+    device_name TEXT,
+    frame_id INTEGER,
+    note TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_bookmarks_timestamp ON bookmarks(timestamp);
+CREATE INDEX IF NOT EXISTS idx_bookmarks_device_name ON bookmarks(device_name);
@@ -0,0 +1,10 @@
+-- Recording sessions, split automatically on long idle gaps and day boundaries
+CREATE TABLE IF NOT EXISTS recording_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    started_at DATETIME NOT NULL,
+    ended_at DATETIME,
+    end_reason TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_recording_sessions_started_at ON recording_sessions(started_at);
@@ -0,0 +1,11 @@
+-- Create audio_transcription_embeddings table, the transcript counterpart of ocr_text_embeddings
+CREATE TABLE IF NOT EXISTS audio_transcription_embeddings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    audio_transcription_id INTEGER NOT NULL,
+    embedding BLOB NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audio_transcription_id) REFERENCES audio_transcriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audio_transcription_embeddings_transcription_id ON audio_transcription_embeddings(audio_transcription_id);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_embeddings_frame_id ON ocr_text_embeddings(frame_id);
@@ -0,0 +1,43 @@
+-- Language of the OCR text of each frame as an ISO 639-1 code, 'und' when it couldn't be
+-- told and NULL until detection ran
+ALTER TABLE ocr_text ADD COLUMN language TEXT DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS idx_ocr_text_language ON ocr_text(language);
+
+-- unicode61 only splits on spaces and punctuation, so a whole CJK sentence ends up as a single
+-- token; all the text is also indexed by trigram so substrings can be searched, the CJK text
+-- of frames mostly in another language included
+CREATE VIRTUAL TABLE IF NOT EXISTS ocr_text_trigram_fts USING fts5(
+    text,
+    frame_id UNINDEXED,
+    tokenize='trigram'
+);
+
+INSERT INTO ocr_text_trigram_fts(frame_id, text)
+SELECT frame_id, text
+FROM ocr_text
+WHERE text IS NOT NULL AND text != '' AND frame_id IS NOT NULL;
+
+CREATE TRIGGER IF NOT EXISTS ocr_text_trigram_fts_insert AFTER INSERT ON ocr_text
+WHEN NEW.text IS NOT NULL AND NEW.text != '' AND NEW.frame_id IS NOT NULL
+BEGIN
+    INSERT INTO ocr_text_trigram_fts(frame_id, text) VALUES (NEW.frame_id, NEW.text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS ocr_text_trigram_fts_delete AFTER DELETE ON ocr_text
+BEGIN
+    DELETE FROM ocr_text_trigram_fts WHERE frame_id = OLD.frame_id;
+END;
+
+-- one trigger keeps both indexes in step with the text; setting the language doesn't change it
+DROP TRIGGER IF EXISTS ocr_text_update;
+CREATE TRIGGER IF NOT EXISTS ocr_text_update AFTER UPDATE OF text, app_name, window_name ON ocr_text
+WHEN NEW.text IS NOT NULL AND NEW.text != '' AND OLD.frame_id IS NOT NULL
+BEGIN
+    UPDATE ocr_text_fts
+    SET text = NEW.text,
+        app_name = COALESCE(NEW.app_name, ''),
+        window_name = COALESCE(NEW.window_name, '')
+    WHERE frame_id = OLD.frame_id;
+    DELETE FROM ocr_text_trigram_fts WHERE frame_id = OLD.frame_id;
+    INSERT INTO ocr_text_trigram_fts(frame_id, text) VALUES (NEW.frame_id, NEW.text);
+END;
@@ -0,0 +1,46 @@
+-- Structured UI context sampled from the accessibility tree of the focused window, linked to
+-- the frame recorded just before it
+CREATE TABLE IF NOT EXISTS ui_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER,
+    timestamp DATETIME NOT NULL,
+    event_type TEXT NOT NULL,
+    app_name TEXT NOT NULL,
+    window_name TEXT NOT NULL,
+    element_role TEXT,
+    element_name TEXT,
+    element_value TEXT,
+    controls TEXT,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ui_events_timestamp ON ui_events(timestamp);
+CREATE INDEX IF NOT EXISTS idx_ui_events_frame_id ON ui_events(frame_id);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS ui_events_fts USING fts5(
+    element_name,
+    element_value,
+    controls,
+    app,
+    window,
+    ui_event_id UNINDEXED,
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS ui_events_ai AFTER INSERT ON ui_events
+BEGIN
+    INSERT INTO ui_events_fts(ui_event_id, element_name, element_value, controls, app, window)
+    VALUES (
+        NEW.id,
+        COALESCE(NEW.element_name, ''),
+        COALESCE(NEW.element_value, ''),
+        COALESCE(NEW.controls, ''),
+        NEW.app_name,
+        NEW.window_name
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS ui_events_delete AFTER DELETE ON ui_events
+BEGIN
+    DELETE FROM ui_events_fts WHERE ui_event_id = OLD.id;
+END;
@@ -0,0 +1,16 @@
+-- Audit trail of the recorded data read through the api: who viewed or exported which time range
+CREATE TABLE IF NOT EXISTS access_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp DATETIME NOT NULL,
+    action TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    client TEXT NOT NULL,
+    remote_addr TEXT,
+    user_agent TEXT,
+    start_time DATETIME,
+    end_time DATETIME,
+    status INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_access_log_timestamp ON access_log(timestamp);
@@ -0,0 +1,6 @@
+-- Perceptual hash of the screen a frame shows, to find the same screen again across chunks
+ALTER TABLE frames ADD COLUMN perceptual_hash INTEGER;
+-- Earlier frame showing the same screen, the frame is collapsed into it and has no text of its own
+ALTER TABLE frames ADD COLUMN duplicate_of INTEGER REFERENCES frames(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_frames_duplicate_of ON frames(duplicate_of);
@@ -0,0 +1,10 @@
+-- Languages of the text blocks in the OCR text of each frame, with how many blocks are in each,
+-- so that a frame mixing languages is found by any of them and not only by its main one
+CREATE TABLE IF NOT EXISTS ocr_text_block_languages (
+    frame_id INTEGER NOT NULL,
+    language TEXT NOT NULL,
+    blocks INTEGER NOT NULL,
+    PRIMARY KEY (frame_id, language),
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_block_languages_language ON ocr_text_block_languages(language);
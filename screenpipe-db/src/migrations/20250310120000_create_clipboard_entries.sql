@@ -0,0 +1,30 @@
+-- Text copied to the clipboard, with secrets redacted, and the window focused when it was copied
+CREATE TABLE IF NOT EXISTS clipboard_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp DATETIME NOT NULL,
+    text TEXT NOT NULL,
+    app_name TEXT,
+    window_name TEXT,
+    redacted BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS idx_clipboard_entries_timestamp ON clipboard_entries(timestamp);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_fts USING fts5(
+    text,
+    app,
+    window,
+    clipboard_id UNINDEXED,
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS clipboard_entries_ai AFTER INSERT ON clipboard_entries
+BEGIN
+    INSERT INTO clipboard_fts(clipboard_id, text, app, window)
+    VALUES (NEW.id, NEW.text, COALESCE(NEW.app_name, ''), COALESCE(NEW.window_name, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS clipboard_entries_delete AFTER DELETE ON clipboard_entries
+BEGIN
+    DELETE FROM clipboard_fts WHERE clipboard_id = OLD.id;
+END;
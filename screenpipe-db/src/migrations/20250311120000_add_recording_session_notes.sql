@@ -0,0 +1,2 @@
+-- Notes the user keeps on a recording session, its title is the name
+ALTER TABLE recording_sessions ADD COLUMN notes TEXT;
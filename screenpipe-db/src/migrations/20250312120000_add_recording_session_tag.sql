@@ -0,0 +1,4 @@
+-- What a session was recorded for when it wasn't split by idle gaps, e.g. 'meeting'
+ALTER TABLE recording_sessions ADD COLUMN tag TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_recording_sessions_tag ON recording_sessions(tag);
@@ -0,0 +1,4 @@
+-- Machine a chunk pushed to this hub was recorded on, NULL for chunks recorded here
+ALTER TABLE video_chunks ADD COLUMN host_name TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_video_chunks_host_name ON video_chunks(host_name);
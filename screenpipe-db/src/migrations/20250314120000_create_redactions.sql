@@ -0,0 +1,14 @@
+-- Time ranges excised from the recordings, kept as a marker of what was removed and when
+CREATE TABLE IF NOT EXISTS redactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp DATETIME NOT NULL,
+    start_time DATETIME NOT NULL,
+    end_time DATETIME NOT NULL,
+    frames INTEGER NOT NULL,
+    audio_transcriptions INTEGER NOT NULL,
+    ui_events INTEGER NOT NULL,
+    clipboard_entries INTEGER NOT NULL,
+    reason TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_redactions_start_time ON redactions(start_time);
@@ -0,0 +1,4 @@
+-- Category of the activity shown in a frame, like "coding" or "client x", from the rules of
+-- categories.json at the time the frame was recorded
+ALTER TABLE frames ADD COLUMN category TEXT DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS idx_frames_category ON frames(category);
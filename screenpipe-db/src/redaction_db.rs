@@ -0,0 +1,274 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, Excision, Redaction};
+
+/// Tables holding the text of a frame, by `frame_id`.
+const FRAME_TEXT_TABLES: [&str; 3] = [
+    "ocr_text",
+    "ocr_text_embeddings",
+    "ocr_text_block_languages",
+];
+
+impl DatabaseManager {
+    /// Removes everything recorded between `start_time` and `end_time` from the index: frames
+    /// with their OCR text, transcriptions with their embeddings, accessibility text, UI events
+    /// and clipboard entries, then logs the range as a redaction. The files of the chunks it
+    /// went through are left to the caller.
+    pub async fn excise_time_range(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        reason: Option<&str>,
+    ) -> Result<Excision, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let video_chunks: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT file_path
+            FROM video_chunks
+            WHERE id IN (SELECT video_chunk_id FROM frames WHERE timestamp >= ?1 AND timestamp <= ?2)
+            OR id IN (SELECT MAX(id) FROM video_chunks GROUP BY device_name)
+            ORDER BY id
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_all(&mut *tx)
+        .await?;
+        for table in FRAME_TEXT_TABLES {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE frame_id IN (SELECT id FROM frames WHERE timestamp >= ?1 AND timestamp <= ?2)",
+                table
+            ))
+            .bind(start_time)
+            .bind(end_time)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let frames = sqlx::query("DELETE FROM frames WHERE timestamp >= ?1 AND timestamp <= ?2")
+            .bind(start_time)
+            .bind(end_time)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        let audio_chunks: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, file_path FROM audio_chunks WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY id",
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            DELETE FROM audio_transcription_embeddings
+            WHERE audio_transcription_id IN (
+                SELECT id FROM audio_transcriptions
+                WHERE (timestamp >= ?1 AND timestamp <= ?2)
+                OR audio_chunk_id IN (SELECT id FROM audio_chunks WHERE timestamp >= ?1 AND timestamp <= ?2)
+            )
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .execute(&mut *tx)
+        .await?;
+        let audio_transcriptions = sqlx::query(
+            r#"
+            DELETE FROM audio_transcriptions
+            WHERE (timestamp >= ?1 AND timestamp <= ?2)
+            OR audio_chunk_id IN (SELECT id FROM audio_chunks WHERE timestamp >= ?1 AND timestamp <= ?2)
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query("DELETE FROM audio_chunks WHERE timestamp >= ?1 AND timestamp <= ?2")
+            .bind(start_time)
+            .bind(end_time)
+            .execute(&mut *tx)
+            .await?;
+
+        let ui_events =
+            sqlx::query("DELETE FROM ui_events WHERE timestamp >= ?1 AND timestamp <= ?2")
+                .bind(start_time)
+                .bind(end_time)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        // the accessibility text, its fts rows going with it by trigger
+        sqlx::query(
+            r#"
+            DELETE FROM ui_monitoring_tags
+            WHERE ui_monitoring_id IN (SELECT id FROM ui_monitoring WHERE timestamp >= ?1 AND timestamp <= ?2)
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM ui_monitoring WHERE timestamp >= ?1 AND timestamp <= ?2")
+            .bind(start_time)
+            .bind(end_time)
+            .execute(&mut *tx)
+            .await?;
+        let clipboard_entries =
+            sqlx::query("DELETE FROM clipboard_entries WHERE timestamp >= ?1 AND timestamp <= ?2")
+                .bind(start_time)
+                .bind(end_time)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        let timestamp = Utc::now();
+        let id = sqlx::query(
+            r#"
+            INSERT INTO redactions (
+                timestamp, start_time, end_time, frames, audio_transcriptions, ui_events,
+                clipboard_entries, reason
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(timestamp)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(frames as i64)
+        .bind(audio_transcriptions as i64)
+        .bind(ui_events as i64)
+        .bind(clipboard_entries as i64)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        tx.commit().await?;
+
+        Ok(Excision {
+            redaction: Redaction {
+                id,
+                timestamp,
+                start_time,
+                end_time,
+                frames: frames as i64,
+                audio_transcriptions: audio_transcriptions as i64,
+                ui_events: ui_events as i64,
+                clipboard_entries: clipboard_entries as i64,
+                reason: reason.map(str::to_string),
+            },
+            video_chunks,
+            audio_chunks: audio_chunks.into_iter().map(|(_, path)| path).collect(),
+        })
+    }
+
+    /// Removes the frames of the chunk at `video_path` captured from `start_time` on, which
+    /// were indexed after their range was excised. Returns how many were.
+    pub async fn excise_video_chunk_tail(
+        &self,
+        video_path: &str,
+        start_time: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let tail = r#"
+            SELECT frames.id
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE video_chunks.file_path = ?1 AND frames.timestamp >= ?2
+        "#;
+        for table in FRAME_TEXT_TABLES {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE frame_id IN ({})",
+                table, tail
+            ))
+            .bind(video_path)
+            .bind(start_time)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let frames = sqlx::query(&format!("DELETE FROM frames WHERE id IN ({})", tail))
+            .bind(video_path)
+            .bind(start_time)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(frames)
+    }
+
+    /// Removes the frames the peer `host_name` pushed to this hub that it captured between
+    /// `start_time` and `end_time`, with their OCR text, when the peer excised the range.
+    /// Returns the chunks they were in, whose copies on the hub are left to the caller.
+    pub async fn excise_remote_time_range(
+        &self,
+        host_name: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let range = r#"
+            SELECT frames.id
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE video_chunks.host_name = ?1 AND frames.timestamp >= ?2 AND frames.timestamp <= ?3
+        "#;
+        let video_chunks: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT file_path FROM video_chunks WHERE id IN (SELECT video_chunk_id FROM frames WHERE id IN ({})) ORDER BY id",
+            range
+        ))
+        .bind(host_name)
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_all(&mut *tx)
+        .await?;
+        for table in FRAME_TEXT_TABLES {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE frame_id IN ({})",
+                table, range
+            ))
+            .bind(host_name)
+            .bind(start_time)
+            .bind(end_time)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(&format!("DELETE FROM frames WHERE id IN ({})", range))
+            .bind(host_name)
+            .bind(start_time)
+            .bind(end_time)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(video_chunks)
+    }
+
+    /// Redactions logged after `redaction_id`, oldest first.
+    pub async fn get_redactions_after(
+        &self,
+        redaction_id: i64,
+        limit: u32,
+    ) -> Result<Vec<Redaction>, sqlx::Error> {
+        sqlx::query_as::<_, Redaction>(
+            "SELECT * FROM redactions WHERE id > ?1 ORDER BY id LIMIT ?2",
+        )
+        .bind(redaction_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Lists the redactions, most recent first.
+    pub async fn list_redactions(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Redaction>, sqlx::Error> {
+        sqlx::query_as::<_, Redaction>(
+            "SELECT * FROM redactions ORDER BY timestamp DESC, id DESC LIMIT ?1 OFFSET ?2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{DatabaseManager, DatabaseSchema, SchemaObject};
+
+impl DatabaseManager {
+    /// Tables, views, indexes and triggers of the database, with the columns of tables and
+    /// views, as created by the migrations applied so far.
+    pub async fn get_schema(&self) -> Result<DatabaseSchema, sqlx::Error> {
+        let migration: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+                .fetch_one(&self.pool)
+                .await?;
+
+        let mut objects: Vec<SchemaObject> = sqlx::query_as(
+            r#"
+            SELECT type AS kind, name, tbl_name AS table_name, sql
+            FROM sqlite_master
+            WHERE name NOT LIKE 'sqlite_%' AND name NOT LIKE '_sqlx%'
+            ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'view' THEN 1 WHEN 'index' THEN 2 ELSE 3 END, name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for object in objects
+            .iter_mut()
+            .filter(|object| object.kind == "table" || object.kind == "view")
+        {
+            object.columns = sqlx::query_as(
+                r#"SELECT name, type AS data_type, "notnull" = 1 AS not_null, dflt_value AS default_value, pk > 0 AS primary_key FROM pragma_table_info(?1) ORDER BY cid"#,
+            )
+            .bind(&object.name)
+            .fetch_all(&self.pool)
+            .await?;
+        }
+
+        Ok(DatabaseSchema { migration, objects })
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, RecordingSession};
+
+impl DatabaseManager {
+    pub async fn insert_recording_session(
+        &self,
+        name: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<RecordingSession, sqlx::Error> {
+        sqlx::query_as::<_, RecordingSession>(
+            "INSERT INTO recording_sessions (name, started_at) VALUES (?1, ?2) RETURNING *",
+        )
+        .bind(name)
+        .bind(started_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn end_recording_session(
+        &self,
+        id: i64,
+        ended_at: DateTime<Utc>,
+        end_reason: &str,
+    ) -> Result<RecordingSession, sqlx::Error> {
+        sqlx::query_as::<_, RecordingSession>(
+            "UPDATE recording_sessions SET ended_at = ?1, end_reason = ?2 WHERE id = ?3 RETURNING *",
+        )
+        .bind(ended_at)
+        .bind(end_reason)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn get_recording_session(&self, id: i64) -> Result<RecordingSession, sqlx::Error> {
+        sqlx::query_as::<_, RecordingSession>("SELECT * FROM recording_sessions WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Sets the name and notes of a session, leaving the ones that are `None` as they are.
+    pub async fn update_recording_session(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        notes: Option<&str>,
+    ) -> Result<RecordingSession, sqlx::Error> {
+        sqlx::query_as::<_, RecordingSession>(
+            r#"
+            UPDATE recording_sessions
+            SET name = COALESCE(?1, name), notes = COALESCE(?2, notes)
+            WHERE id = ?3
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(notes)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn set_recording_session_tag(
+        &self,
+        id: i64,
+        tag: Option<&str>,
+    ) -> Result<RecordingSession, sqlx::Error> {
+        sqlx::query_as::<_, RecordingSession>(
+            "UPDATE recording_sessions SET tag = ?1 WHERE id = ?2 RETURNING *",
+        )
+        .bind(tag)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Closes sessions left open by a previous run that didn't shut down cleanly, ending them
+    /// at their last recorded frame.
+    pub async fn end_open_recording_sessions(&self, end_reason: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE recording_sessions
+            SET ended_at = COALESCE(
+                    (SELECT MAX(timestamp) FROM frames WHERE timestamp >= recording_sessions.started_at),
+                    started_at
+                ),
+                end_reason = ?1
+            WHERE ended_at IS NULL
+            "#,
+        )
+        .bind(end_reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Lists sessions overlapping the given time range, most recent first, only those tagged
+    /// `tag` if given.
+    pub async fn list_recording_sessions(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        tag: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<RecordingSession>, sqlx::Error> {
+        sqlx::query_as::<_, RecordingSession>(
+            r#"
+            SELECT *
+            FROM recording_sessions
+            WHERE (?1 IS NULL OR ended_at IS NULL OR ended_at >= ?1)
+            AND (?2 IS NULL OR started_at <= ?2)
+            AND (?5 IS NULL OR tag = ?5)
+            ORDER BY started_at DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .bind(offset)
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Retrieves the video chunks holding frames recorded during the session, in order.
+    pub async fn get_recording_session_video_chunks(
+        &self,
+        id: i64,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT video_chunks.file_path
+            FROM video_chunks
+            JOIN frames ON frames.video_chunk_id = video_chunks.id
+            JOIN recording_sessions ON recording_sessions.id = ?1
+            WHERE frames.timestamp >= recording_sessions.started_at
+            AND (recording_sessions.ended_at IS NULL OR frames.timestamp <= recording_sessions.ended_at)
+            GROUP BY video_chunks.id
+            ORDER BY video_chunks.id ASC
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -47,6 +47,9 @@ pub struct OCRResultRaw {
     pub browser_url: Option<String>,
     pub focused: Option<bool>,
     pub device_name: String,
+    pub language: Option<String>,
+    pub host_name: Option<String>,
+    pub category: Option<String>,
 }
 
 #[derive(OaSchema, Debug, Serialize, Deserialize)]
@@ -65,6 +68,13 @@ pub struct OCRResult {
     pub browser_url: Option<String>,
     pub focused: Option<bool>,
     pub device_name: String,
+    /// ISO 639-1 code of the detected language, `und` if unknown.
+    pub language: Option<String>,
+    /// Machine the frame was recorded on when it was pushed to this hub, `None` if recorded
+    /// here.
+    pub host_name: Option<String>,
+    /// Category of the activity shown, from the rules of `categories.json`.
+    pub category: Option<String>,
 }
 
 #[derive(OaSchema, Debug, Deserialize, PartialEq, Default, Clone)]
@@ -171,6 +181,7 @@ pub struct OCREntry {
     pub window_name: String,
     pub device_name: String,
     pub video_file_path: String,
+    pub category: Option<String>,
 }
 
 #[derive(OaSchema, Debug, Clone)]
@@ -325,3 +336,340 @@ impl Default for CustomOcrConfig {
         }
     }
 }
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Bookmark {
+    pub id: i64,
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: Option<String>,
+    pub frame_id: Option<i64>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VideoChunkFrameText {
+    pub frame_id: i64,
+    pub offset_index: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub focused: Option<bool>,
+    pub text: String,
+    pub text_json: Option<String>,
+}
+
+/// A frame of a video chunk whose text wasn't read yet.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct UnreadFrame {
+    pub frame_id: i64,
+    pub offset_index: i64,
+    pub window_name: Option<String>,
+    pub focused: Option<bool>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FrameLocation {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub file_path: String,
+    pub offset_index: i64,
+}
+
+/// A frame and its text as a peer pushes them to a hub.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct RemoteFrame {
+    /// Id of the frame on the peer, the cursor of its pushes.
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    /// Chunk of the frame: its path on the peer when read, its key under the data directory
+    /// once pushed.
+    pub file_path: String,
+    pub offset_index: i64,
+    pub device_name: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+    /// Category of the frame by the rules of the machine it's indexed on, the hub's own rules
+    /// replacing the peer's once it's pushed.
+    #[serde(default)]
+    pub category: Option<String>,
+    pub text: Option<String>,
+    pub text_json: Option<String>,
+    pub ocr_engine: Option<String>,
+    pub language: Option<String>,
+}
+
+/// A frame of a recording made before it was indexed, at its place in its video.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillFrame {
+    pub offset_index: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub focused: bool,
+    pub category: Option<String>,
+}
+
+/// A video chunk with the capture times of its first and last frames.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VideoChunkSpan {
+    pub file_path: String,
+    pub first_frame_at: DateTime<Utc>,
+    pub last_frame_at: DateTime<Utc>,
+}
+
+/// Frames captured of one app window over a time range.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AppUsage {
+    pub app_name: String,
+    pub window_name: String,
+    pub frame_count: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// The window in front when a frame was captured.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AppActivity {
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    /// Category given to the frame by the rules of `categories.json`, if any matched.
+    pub category: Option<String>,
+}
+
+/// OCR text of a frame picked to represent a stretch of time in one window.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OcrTextSample {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub text: String,
+    pub language: Option<String>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RecordingSession {
+    pub id: i64,
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub end_reason: Option<String>,
+    pub notes: Option<String>,
+    /// What the session was recorded for, e.g. `meeting`, `None` for a session split by
+    /// idle gaps.
+    pub tag: Option<String>,
+}
+
+pub const JOB_PENDING: &str = "pending";
+pub const JOB_RUNNING: &str = "running";
+pub const JOB_DONE: &str = "done";
+pub const JOB_FAILED: &str = "failed";
+
+/// Language stored for OCR text whose language couldn't be told, so it isn't looked at again.
+pub const UNDETERMINED_LANGUAGE: &str = "und";
+
+/// The focused control changed.
+pub const UI_EVENT_FOCUS: &str = "focus";
+/// A window was focused; `controls` lists what it shows.
+pub const UI_EVENT_WINDOW: &str = "window";
+
+/// A named control of a window's accessibility tree.
+#[derive(OaSchema, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiControl {
+    /// Platform independent role, e.g. `button`, `menuitem` or `textfield`.
+    pub role: String,
+    pub name: String,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UiEvent {
+    pub id: i64,
+    /// The frame recorded just before, if there was one of the same app.
+    pub frame_id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+    /// One of `focus` or `window`.
+    pub event_type: String,
+    pub app_name: String,
+    pub window_name: String,
+    /// The focused control.
+    pub element_role: Option<String>,
+    pub element_name: Option<String>,
+    pub element_value: Option<String>,
+    /// JSON array of the window's `UiControl`s, for `window` events.
+    pub controls: Option<String>,
+}
+
+/// A UI event before it is stored.
+#[derive(Debug, Clone, Default)]
+pub struct NewUiEvent {
+    pub timestamp: DateTime<Utc>,
+    pub event_type: String,
+    pub app_name: String,
+    pub window_name: String,
+    pub element_role: Option<String>,
+    pub element_name: Option<String>,
+    pub element_value: Option<String>,
+    pub controls: Vec<UiControl>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    /// JSON arguments of the job, interpreted by the handler of its kind.
+    pub payload: String,
+    /// One of `pending`, `running`, `done` or `failed`.
+    pub status: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    /// Completion between 0 and 1, as last reported by the handler.
+    pub progress: f64,
+    pub error: Option<String>,
+    pub run_after: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct JobStatusCount {
+    pub status: String,
+    pub count: i64,
+}
+
+/// A read of recorded data through the api.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AccessLogEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    /// What was read, e.g. `search`, `frame` or `export`.
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    /// Fingerprint of the api key used, `anonymous` when none was, or `local` when the api is
+    /// not protected by a key.
+    pub client: String,
+    /// IP address the request came from.
+    pub remote_addr: Option<String>,
+    pub user_agent: Option<String>,
+    /// The time range read, when the request named one.
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// HTTP status of the response.
+    pub status: i64,
+}
+
+/// An access log entry before it is stored.
+#[derive(Debug, Clone, Default)]
+pub struct NewAccessLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    pub client: String,
+    pub remote_addr: Option<String>,
+    pub user_agent: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub status: u16,
+}
+
+/// The perceptual hash of a frame, for the dedup pass.
+#[derive(Debug, Clone, FromRow)]
+pub struct FrameHash {
+    pub id: i64,
+    pub device_name: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// The 64 bits of the hash, stored as a signed integer.
+    pub perceptual_hash: i64,
+    /// The OCR text of the frame, if it was read.
+    pub text: Option<String>,
+}
+
+/// The schema of the database, see [`DatabaseManager::get_schema`](crate::DatabaseManager::get_schema).
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSchema {
+    /// Version of the last migration applied.
+    pub migration: Option<i64>,
+    pub objects: Vec<SchemaObject>,
+}
+
+/// A table, view, index or trigger.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SchemaObject {
+    pub kind: String,
+    pub name: String,
+    /// The table an index or trigger belongs to, the object itself otherwise.
+    pub table_name: String,
+    /// The statement creating the object, missing for indexes SQLite creates itself.
+    pub sql: Option<String>,
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub columns: Vec<SchemaColumn>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SchemaColumn {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub default_value: Option<String>,
+    pub primary_key: bool,
+}
+
+/// Text copied to the clipboard, with its secrets redacted.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClipboardEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    /// The app and window focused when the text was copied.
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    /// Whether secrets were replaced in the text before it was stored.
+    pub redacted: bool,
+}
+
+/// A clipboard entry before it is stored.
+#[derive(Debug, Clone, Default)]
+pub struct NewClipboardEntry {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub redacted: bool,
+}
+
+/// A time range excised from the recordings: what was removed, not what it showed.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Redaction {
+    pub id: i64,
+    /// When the range was excised.
+    pub timestamp: DateTime<Utc>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// Rows removed from the index.
+    pub frames: i64,
+    pub audio_transcriptions: i64,
+    pub ui_events: i64,
+    pub clipboard_entries: i64,
+    pub reason: Option<String>,
+}
+
+/// An excised range and the recordings it went through, whose files are left to trim.
+#[derive(Debug, Clone)]
+pub struct Excision {
+    pub redaction: Redaction,
+    /// Video chunks that had frames in the range, and the latest chunk of each device, which
+    /// may have been recording it.
+    pub video_chunks: Vec<String>,
+    /// Audio chunks recorded in the range, removed from the index along with all their
+    /// transcriptions.
+    pub audio_chunks: Vec<String>,
+}
@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DatabaseManager, NewUiEvent, UiEvent};
+
+/// How far back a frame of the same app may be to be linked to a UI event.
+const FRAME_LINK_WINDOW_SECS: i64 = 10;
+
+impl DatabaseManager {
+    /// Stores the text of a window's accessibility tree, searched as UI content. The tree was
+    /// walked from the top at `timestamp`, so it's also its initial traversal.
+    pub async fn insert_ui_monitoring(
+        &self,
+        timestamp: DateTime<Utc>,
+        app_name: &str,
+        window_name: &str,
+        text: &str,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO ui_monitoring (text_output, timestamp, app, window, initial_traversal_at)
+            VALUES (?1, ?2, ?3, ?4, ?2)
+            RETURNING id
+            "#,
+        )
+        .bind(text)
+        .bind(timestamp)
+        .bind(app_name)
+        .bind(window_name)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Stores a UI event, linked to the last frame of its app recorded shortly before it.
+    pub async fn insert_ui_event(&self, event: &NewUiEvent) -> Result<i64, sqlx::Error> {
+        let controls = if event.controls.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&event.controls).ok()
+        };
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO ui_events (
+                frame_id, timestamp, event_type, app_name, window_name,
+                element_role, element_name, element_value, controls
+            )
+            VALUES (
+                (
+                    SELECT id FROM frames
+                    WHERE app_name = ?3 AND timestamp <= ?1 AND timestamp >= ?9
+                    ORDER BY timestamp DESC
+                    LIMIT 1
+                ),
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8
+            )
+            RETURNING id
+            "#,
+        )
+        .bind(event.timestamp)
+        .bind(&event.event_type)
+        .bind(&event.app_name)
+        .bind(&event.window_name)
+        .bind(&event.element_role)
+        .bind(&event.element_name)
+        .bind(&event.element_value)
+        .bind(controls)
+        .bind(event.timestamp - Duration::seconds(FRAME_LINK_WINDOW_SECS))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Searches control names and values, most recent first.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_ui_events(
+        &self,
+        query: &str,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<UiEvent>, sqlx::Error> {
+        // combine search aspects into single fts query
+        let mut fts_parts = Vec::new();
+        if !query.is_empty() {
+            fts_parts.push(query.to_owned());
+        }
+        if let Some(app) = app_name {
+            fts_parts.push(format!("app:{}", app));
+        }
+        if let Some(window) = window_name {
+            fts_parts.push(format!("window:{}", window));
+        }
+        let combined_query = fts_parts.join(" ");
+
+        let (base_sql, where_clause) = if combined_query.is_empty() {
+            ("ui_events", "WHERE ?1 = ?1")
+        } else {
+            (
+                "ui_events_fts JOIN ui_events ON ui_events_fts.ui_event_id = ui_events.id",
+                "WHERE ui_events_fts MATCH ?1",
+            )
+        };
+
+        let sql = format!(
+            r#"
+            SELECT ui_events.*
+            FROM {}
+            {}
+                AND (?2 IS NULL OR ui_events.timestamp >= ?2)
+                AND (?3 IS NULL OR ui_events.timestamp <= ?3)
+            ORDER BY ui_events.timestamp DESC
+            LIMIT ?4 OFFSET ?5
+            "#,
+            base_sql, where_clause
+        );
+
+        sqlx::query_as(&sql)
+            .bind(combined_query)
+            .bind(start_time)
+            .bind(end_time)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn get_ui_events_for_frame(
+        &self,
+        frame_id: i64,
+    ) -> Result<Vec<UiEvent>, sqlx::Error> {
+        sqlx::query_as::<_, UiEvent>(
+            "SELECT * FROM ui_events WHERE frame_id = ?1 ORDER BY timestamp",
+        )
+        .bind(frame_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
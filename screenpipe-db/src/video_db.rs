@@ -1,6 +1,11 @@
 use std::path::Path;
 
-use crate::DatabaseManager;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    AppActivity, AppUsage, BackfillFrame, DatabaseManager, FrameLocation, OcrTextSample,
+    RemoteFrame, UnreadFrame, VideoChunkFrameText, VideoChunkSpan,
+};
 
 impl DatabaseManager {
     pub async fn get_total_frames(&self, video_path: &Path) -> Result<i64, sqlx::Error> {
@@ -42,4 +47,604 @@ impl DatabaseManager {
         .fetch_optional(&self.pool)
         .await
     }
+
+    /// Retrieves the file path of the most recent video chunk recorded for `device_name`.
+    pub async fn get_latest_video_chunk_path(
+        &self,
+        device_name: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT file_path FROM video_chunks WHERE device_name = ?1 ORDER BY id DESC LIMIT 1",
+        )
+        .bind(device_name)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Retrieves the frame shown at `timestamp`: the last one captured at or before it, by
+    /// `device_name` when given, and no more than `max_gap_secs` earlier, past which nothing
+    /// was being recorded.
+    pub async fn get_frame_at(
+        &self,
+        timestamp: DateTime<Utc>,
+        device_name: Option<&str>,
+        max_gap_secs: i64,
+    ) -> Result<Option<FrameLocation>, sqlx::Error> {
+        sqlx::query_as::<_, FrameLocation>(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.timestamp,
+                video_chunks.file_path,
+                frames.offset_index
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE frames.timestamp <= ?1
+            AND frames.timestamp >= ?2
+            AND (?3 IS NULL OR frames.device_name = ?3)
+            ORDER BY frames.timestamp DESC, frames.id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(timestamp)
+        .bind(timestamp - chrono::Duration::seconds(max_gap_secs.max(0)))
+        .bind(device_name)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Retrieves the video chunks of a device with frames in `[start, end]`, in recording order.
+    /// The frame times cover the whole chunk, not only the part in range.
+    pub async fn get_video_chunk_spans(
+        &self,
+        device_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<VideoChunkSpan>, sqlx::Error> {
+        sqlx::query_as::<_, VideoChunkSpan>(
+            r#"
+            SELECT
+                video_chunks.file_path,
+                MIN(frames.timestamp) AS first_frame_at,
+                MAX(frames.timestamp) AS last_frame_at
+            FROM video_chunks
+            JOIN frames ON frames.video_chunk_id = video_chunks.id
+            WHERE video_chunks.device_name = ?1
+            GROUP BY video_chunks.id
+            HAVING MAX(frames.timestamp) >= ?2 AND MIN(frames.timestamp) <= ?3
+            ORDER BY video_chunks.id
+            "#,
+        )
+        .bind(device_name)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Retrieves the devices with frames in `[start, end)`, by name.
+    pub async fn get_recorded_devices(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT device_name
+            FROM frames
+            WHERE timestamp >= ?1 AND timestamp < ?2
+            ORDER BY device_name
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Retrieves the first frame of every minute in `[start, end)` that has any, in time order.
+    pub async fn get_first_frame_per_minute(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FrameLocation>, sqlx::Error> {
+        sqlx::query_as::<_, FrameLocation>(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.timestamp,
+                video_chunks.file_path,
+                frames.offset_index
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE frames.id IN (
+                SELECT MIN(id)
+                FROM frames
+                WHERE timestamp >= ?1 AND timestamp < ?2
+                GROUP BY strftime('%Y-%m-%d %H:%M', timestamp)
+            )
+            ORDER BY frames.timestamp
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Retrieves the OCR text of every frame stored in the given video chunk, in capture order.
+    pub async fn get_video_chunk_frame_texts(
+        &self,
+        video_path: &str,
+    ) -> Result<Vec<VideoChunkFrameText>, sqlx::Error> {
+        sqlx::query_as::<_, VideoChunkFrameText>(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.offset_index,
+                frames.timestamp,
+                frames.app_name,
+                frames.window_name,
+                frames.focused,
+                ocr_text.text,
+                ocr_text.text_json
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            JOIN ocr_text ON ocr_text.frame_id = frames.id
+            WHERE video_chunks.file_path = ?1
+            ORDER BY frames.offset_index ASC
+            "#,
+        )
+        .bind(video_path)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Retrieves every frame stored in the given video chunk, in capture order.
+    pub async fn get_video_chunk_frames(
+        &self,
+        video_path: &str,
+    ) -> Result<Vec<FrameLocation>, sqlx::Error> {
+        sqlx::query_as::<_, FrameLocation>(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.timestamp,
+                video_chunks.file_path,
+                frames.offset_index
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE video_chunks.file_path = ?1
+            ORDER BY frames.offset_index ASC
+            "#,
+        )
+        .bind(video_path)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// The offset and capture time of each frame encoded in the given video chunk, in order,
+    /// leaving out the duplicates of other frames.
+    pub async fn get_video_chunk_frame_times(
+        &self,
+        video_path: &str,
+    ) -> Result<Vec<(i64, DateTime<Utc>)>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT frames.offset_index, MIN(frames.timestamp)
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE video_chunks.file_path = ?1
+            AND frames.duplicate_of IS NULL
+            GROUP BY frames.offset_index
+            ORDER BY frames.offset_index ASC
+            "#,
+        )
+        .bind(video_path)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Frames of the given video chunk without text, not duplicates of others, in capture
+    /// order and the focused window of each first.
+    pub async fn get_video_chunk_unread_frames(
+        &self,
+        video_path: &str,
+    ) -> Result<Vec<UnreadFrame>, sqlx::Error> {
+        sqlx::query_as::<_, UnreadFrame>(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.offset_index,
+                frames.window_name,
+                frames.focused
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            LEFT JOIN ocr_text ON ocr_text.frame_id = frames.id
+            WHERE video_chunks.file_path = ?1
+                AND frames.duplicate_of IS NULL
+                AND ocr_text.frame_id IS NULL
+            ORDER BY frames.offset_index ASC, frames.focused DESC, frames.id ASC
+            "#,
+        )
+        .bind(video_path)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// The last video chunk of each device, when it has frames without text, not duplicates.
+    pub async fn get_last_video_chunks_with_unread_frames(
+        &self,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT video_chunks.file_path
+            FROM video_chunks
+            WHERE video_chunks.id IN (SELECT MAX(id) FROM video_chunks GROUP BY device_name)
+                AND EXISTS (
+                    SELECT 1
+                    FROM frames
+                    LEFT JOIN ocr_text ON ocr_text.frame_id = frames.id
+                    WHERE frames.video_chunk_id = video_chunks.id
+                        AND frames.duplicate_of IS NULL
+                        AND ocr_text.frame_id IS NULL
+                )
+            ORDER BY video_chunks.id ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Windows seen between `start` and `end`, most captured first.
+    pub async fn get_app_usage(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<AppUsage>, sqlx::Error> {
+        sqlx::query_as::<_, AppUsage>(
+            r#"
+            SELECT
+                frames.app_name,
+                COALESCE(frames.window_name, '') AS window_name,
+                COUNT(*) AS frame_count,
+                MIN(frames.timestamp) AS first_seen,
+                MAX(frames.timestamp) AS last_seen
+            FROM frames
+            WHERE frames.timestamp >= ?1 AND frames.timestamp < ?2
+            AND COALESCE(frames.app_name, '') != ''
+            GROUP BY frames.app_name, frames.window_name
+            ORDER BY frame_count DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// The app and window of each frame between `start` and `end` that shows the focused
+    /// window, in capture order. Frames of unfocused monitors would double count the time.
+    pub async fn get_app_activity(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AppActivity>, sqlx::Error> {
+        sqlx::query_as::<_, AppActivity>(
+            r#"
+            SELECT timestamp, app_name, COALESCE(window_name, '') AS window_name, category
+            FROM frames
+            WHERE timestamp >= ?1 AND timestamp < ?2
+            AND COALESCE(app_name, '') != ''
+            AND COALESCE(focused, 1) = 1
+            ORDER BY timestamp
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// One OCR text per window and `bucket_secs` between `start` and `end`, in capture order.
+    /// When a frame has text for several windows the longest is kept.
+    pub async fn get_ocr_text_samples(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        bucket_secs: i64,
+        limit: u32,
+    ) -> Result<Vec<OcrTextSample>, sqlx::Error> {
+        sqlx::query_as::<_, OcrTextSample>(
+            r#"
+            WITH samples AS (
+                SELECT MIN(frames.id) AS frame_id
+                FROM frames
+                JOIN ocr_text ON ocr_text.frame_id = frames.id
+                WHERE frames.timestamp >= ?1 AND frames.timestamp < ?2
+                AND LENGTH(TRIM(ocr_text.text)) > 0
+                GROUP BY
+                    CAST(strftime('%s', frames.timestamp) AS INTEGER) / ?3,
+                    frames.app_name,
+                    frames.window_name
+            )
+            SELECT
+                frames.id AS frame_id,
+                frames.timestamp,
+                COALESCE(frames.app_name, '') AS app_name,
+                COALESCE(frames.window_name, '') AS window_name,
+                ocr_text.text,
+                ocr_text.language,
+                MAX(LENGTH(ocr_text.text)) AS text_length
+            FROM samples
+            JOIN frames ON frames.id = samples.frame_id
+            JOIN ocr_text ON ocr_text.frame_id = frames.id
+            GROUP BY frames.id
+            ORDER BY frames.timestamp
+            LIMIT ?4
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(bucket_secs.max(1))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Indexes a frame found encoded in `file_path` at `offset_index` but missing from the
+    /// index. The chunk is indexed too if it is missing. Returns `None` when a frame of the
+    /// same window and time is already indexed in the chunk.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_recovered_frame(
+        &self,
+        file_path: &str,
+        offset_index: i64,
+        device_name: &str,
+        timestamp: DateTime<Utc>,
+        browser_url: Option<&str>,
+        app_name: &str,
+        window_name: &str,
+        focused: bool,
+        category: Option<&str>,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM video_chunks WHERE file_path = ?1")
+                .bind(file_path)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let video_chunk_id = match existing {
+            Some(id) => id,
+            None => {
+                sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
+                    .bind(file_path)
+                    .bind(device_name)
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_rowid()
+            }
+        };
+
+        // the indexer may have been killed halfway through the windows of the frame
+        let indexed: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM frames
+                WHERE video_chunk_id = ?1
+                    AND app_name = ?2
+                    AND window_name = ?3
+                    AND ABS(julianday(timestamp) - julianday(?4)) * 86400 < 1
+            )
+            "#,
+        )
+        .bind(video_chunk_id)
+        .bind(app_name)
+        .bind(window_name)
+        .bind(timestamp)
+        .fetch_one(&mut *tx)
+        .await?;
+        if indexed {
+            tx.commit().await?;
+            return Ok(None);
+        }
+
+        let id = sqlx::query(
+            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, device_name, category) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )
+        .bind(video_chunk_id)
+        .bind(offset_index)
+        .bind(timestamp)
+        .bind(file_path)
+        .bind(browser_url)
+        .bind(app_name)
+        .bind(window_name)
+        .bind(focused)
+        .bind(device_name)
+        .bind(category)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        tx.commit().await?;
+
+        Ok(Some(id))
+    }
+
+    /// Retrieves the frames recorded on this machine after `after_id`, with their text, to
+    /// push to a hub. Frames pushed to this machine by others are left out.
+    pub async fn get_local_frames_for_hub(
+        &self,
+        after_id: i64,
+        limit: u32,
+    ) -> Result<Vec<RemoteFrame>, sqlx::Error> {
+        sqlx::query_as::<_, RemoteFrame>(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.timestamp,
+                video_chunks.file_path,
+                frames.offset_index,
+                video_chunks.device_name,
+                frames.app_name,
+                frames.window_name,
+                frames.browser_url,
+                frames.focused,
+                frames.category,
+                ocr_text.text,
+                ocr_text.text_json,
+                ocr_text.ocr_engine,
+                ocr_text.language
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            LEFT JOIN ocr_text ON frames.id = ocr_text.frame_id
+            WHERE frames.id > ?1
+            AND video_chunks.host_name IS NULL
+            GROUP BY frames.id
+            ORDER BY frames.id
+            LIMIT ?2
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Indexes frames pushed by the peer `host_name`, under chunks tagged with it and devices
+    /// named `<host_name>/<device>` so they don't mix with this machine's. Frames already
+    /// indexed at their place in their chunk are skipped, a peer may push a batch again.
+    /// Returns how many were indexed.
+    pub async fn insert_remote_frames(
+        &self,
+        host_name: &str,
+        frames: &[RemoteFrame],
+    ) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for frame in frames {
+            let device_name = format!("{}/{}", host_name, frame.device_name);
+            let existing: Option<i64> = sqlx::query_scalar(
+                "SELECT id FROM video_chunks WHERE file_path = ?1 AND host_name = ?2",
+            )
+            .bind(&frame.file_path)
+            .bind(host_name)
+            .fetch_optional(&mut *tx)
+            .await?;
+            let video_chunk_id = match existing {
+                Some(id) => id,
+                None => sqlx::query(
+                    "INSERT INTO video_chunks (file_path, device_name, host_name) VALUES (?1, ?2, ?3)",
+                )
+                .bind(&frame.file_path)
+                .bind(&device_name)
+                .bind(host_name)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid(),
+            };
+
+            let indexed: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM frames WHERE video_chunk_id = ?1 AND offset_index = ?2)",
+            )
+            .bind(video_chunk_id)
+            .bind(frame.offset_index)
+            .fetch_one(&mut *tx)
+            .await?;
+            if indexed {
+                continue;
+            }
+
+            let id = sqlx::query(
+                "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, device_name, category) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )
+            .bind(video_chunk_id)
+            .bind(frame.offset_index)
+            .bind(frame.timestamp)
+            .bind(&frame.file_path)
+            .bind(&frame.browser_url)
+            .bind(&frame.app_name)
+            .bind(&frame.window_name)
+            .bind(frame.focused.unwrap_or_default())
+            .bind(&device_name)
+            .bind(&frame.category)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+
+            if let Some(text) = &frame.text {
+                sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length) VALUES (?1, ?2, ?3, ?4, ?5)")
+                    .bind(id)
+                    .bind(text)
+                    .bind(frame.text_json.as_deref().unwrap_or("[]"))
+                    .bind(frame.ocr_engine.as_deref().unwrap_or("unknown"))
+                    .bind(text.len() as i64)
+                    .execute(&mut *tx)
+                    .await?;
+                // set afterwards, as language detection does, for the CJK index to pick it up
+                if let Some(language) = &frame.language {
+                    sqlx::query("UPDATE ocr_text SET language = ?1 WHERE frame_id = ?2")
+                        .bind(language)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            inserted += 1;
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    /// Indexes the video at `file_path`, recorded before it was indexed, as a chunk of
+    /// `device_name` with `frames`, all at once. Returns the ids of the frames in their order,
+    /// `None` when the video was indexed already so that a backfill can run again.
+    pub async fn insert_backfilled_chunk(
+        &self,
+        file_path: &str,
+        device_name: &str,
+        frames: &[BackfillFrame],
+    ) -> Result<Option<Vec<i64>>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let indexed: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM video_chunks WHERE file_path = ?1)")
+                .bind(file_path)
+                .fetch_one(&mut *tx)
+                .await?;
+        if indexed {
+            return Ok(None);
+        }
+
+        let video_chunk_id =
+            sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
+                .bind(file_path)
+                .bind(device_name)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+        let mut ids = Vec::with_capacity(frames.len());
+        for frame in frames {
+            let id = sqlx::query(
+                "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, app_name, window_name, focused, device_name, category) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )
+            .bind(video_chunk_id)
+            .bind(frame.offset_index)
+            .bind(frame.timestamp)
+            .bind(file_path)
+            .bind(&frame.app_name)
+            .bind(&frame.window_name)
+            .bind(frame.focused)
+            .bind(device_name)
+            .bind(&frame.category)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+            ids.push(id);
+        }
+        tx.commit().await?;
+        Ok(Some(ids))
+    }
 }
@@ -4,7 +4,8 @@ mod tests {
 
     use chrono::Utc;
     use screenpipe_db::{
-        AudioDevice, ContentType, DatabaseManager, DeviceType, Frame, OcrEngine, SearchResult,
+        AudioDevice, BackfillFrame, ContentType, DatabaseManager, DeviceType, Frame,
+        NewClipboardEntry, OcrEngine, SearchResult, UnreadFrame,
     };
 
     async fn setup_test_db() -> DatabaseManager {
@@ -37,7 +38,15 @@ mod tests {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -65,6 +74,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -113,6 +124,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -134,6 +147,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -189,6 +204,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -210,6 +227,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -232,7 +251,15 @@ mod tests {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
 
@@ -297,6 +324,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -318,6 +347,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -348,7 +379,15 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -388,7 +427,15 @@ mod tests {
 
         // Insert remaining data
         let frame_id2 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -489,6 +536,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -513,6 +562,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -536,6 +587,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -563,6 +616,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -585,6 +640,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -603,7 +660,15 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -641,7 +706,15 @@ mod tests {
 
         // Insert remaining data
         let frame_id2 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -690,6 +763,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -716,6 +791,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1074,7 +1151,15 @@ mod tests {
 
         // Insert first frame with OCR
         let frame_id1 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -1088,7 +1173,15 @@ mod tests {
 
         // Insert second frame with OCR
         let frame_id2 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -1117,6 +1210,8 @@ mod tests {
                 Some("test_video"),
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1144,6 +1239,8 @@ mod tests {
                 Some("non_existent"),
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1171,6 +1268,8 @@ mod tests {
                 Some("test_video"),
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1196,6 +1295,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1245,6 +1346,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1274,6 +1377,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1296,6 +1401,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1318,6 +1425,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1334,7 +1443,15 @@ mod tests {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -1400,6 +1517,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1420,6 +1539,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1440,9 +1561,1821 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
         assert_eq!(count, 0, "Should count zero results for non-matching query");
     }
+
+    #[tokio::test]
+    async fn test_insert_and_list_bookmarks() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                Some("code"),
+                Some("main.rs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let bookmark = db
+            .insert_bookmark("bug repro here", Utc::now(), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(bookmark.name, "bug repro here");
+        assert_eq!(bookmark.frame_id, Some(frame_id));
+        assert_eq!(bookmark.device_name.as_deref(), Some("monitor_1"));
+
+        let _ = db
+            .insert_bookmark(
+                "standup",
+                Utc::now(),
+                Some("monitor_2"),
+                None,
+                Some("daily"),
+            )
+            .await
+            .unwrap();
+
+        let all = db
+            .list_bookmarks(None, None, None, None, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+
+        let matched = db
+            .list_bookmarks(Some("REPRO"), None, None, None, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].id, bookmark.id);
+
+        let by_note = db
+            .list_bookmarks(Some("daily"), None, None, Some("monitor_2"), 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(by_note.len(), 1);
+
+        let renamed = db
+            .update_bookmark(bookmark.id, Some("fixed"), None)
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, "fixed");
+
+        db.delete_bookmark(bookmark.id).await.unwrap();
+        assert!(db.delete_bookmark(bookmark.id).await.is_err());
+        let remaining = db
+            .list_bookmarks(None, None, None, None, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_video_chunk_frame_texts() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("chunk_a.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_a = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                Some("code"),
+                Some("main.rs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_a, "fn main", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+
+        let _ = db
+            .insert_video_chunk("chunk_b.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_b = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                Some("arc"),
+                Some("docs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_b,
+            "Getting Started",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.get_latest_video_chunk_path("monitor_1")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("chunk_b.mp4")
+        );
+        assert!(db
+            .get_latest_video_chunk_path("monitor_2")
+            .await
+            .unwrap()
+            .is_none());
+
+        let texts = db.get_video_chunk_frame_texts("chunk_a.mp4").await.unwrap();
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].frame_id, frame_a);
+        assert_eq!(texts[0].window_name.as_deref(), Some("main.rs"));
+        assert_eq!(texts[0].text, "fn main");
+    }
+
+    #[tokio::test]
+    async fn test_get_video_chunk_frames() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk_a.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            // frames without text are listed too
+            ids.push(
+                db.insert_frame("monitor_1", None, None, None, None, true, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+        db.insert_video_chunk("chunk_b.mp4", "monitor_1")
+            .await
+            .unwrap();
+        db.insert_frame("monitor_1", None, None, None, None, true, None)
+            .await
+            .unwrap();
+
+        let frames = db.get_video_chunk_frames("chunk_a.mp4").await.unwrap();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| frame.frame_id)
+                .collect::<Vec<_>>(),
+            ids
+        );
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| frame.offset_index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_video_chunk_unread_frames() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk_a.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let read = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                Some("code"),
+                Some("main.rs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(read, "fn main", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        let unread = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                Some("arc"),
+                Some("docs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        // duplicates are read with the frame they duplicate
+        db.insert_duplicate_frame(unread, None, None, Some("arc"), Some("docs"), true, 0, None)
+            .await
+            .unwrap();
+
+        let frames = db
+            .get_video_chunk_unread_frames("chunk_a.mp4")
+            .await
+            .unwrap();
+        assert_eq!(
+            frames,
+            vec![UnreadFrame {
+                frame_id: unread,
+                offset_index: 1,
+                window_name: Some("docs".to_string()),
+                focused: Some(true),
+            }]
+        );
+        assert!(db
+            .get_video_chunk_unread_frames("chunk_b.mp4")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recording_sessions() {
+        let db = setup_test_db().await;
+
+        let start = Utc::now() - chrono::Duration::seconds(10);
+        let session = db.insert_recording_session("morning", start).await.unwrap();
+        assert!(session.ended_at.is_none());
+
+        let _ = db
+            .insert_video_chunk("session_chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let _ = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                Some("code"),
+                Some("main.rs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.get_recording_session_video_chunks(session.id)
+                .await
+                .unwrap(),
+            vec!["session_chunk.mp4".to_string()]
+        );
+
+        let ended = db
+            .end_recording_session(session.id, Utc::now(), "idle")
+            .await
+            .unwrap();
+        assert_eq!(ended.end_reason.as_deref(), Some("idle"));
+
+        let renamed = db
+            .update_recording_session(session.id, Some("standup"), Some("sprint planning"))
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, "standup");
+        assert_eq!(renamed.notes.as_deref(), Some("sprint planning"));
+        let noted = db
+            .update_recording_session(session.id, None, Some(""))
+            .await
+            .unwrap();
+        assert_eq!(noted.name, "standup");
+        assert_eq!(noted.notes.as_deref(), Some(""));
+        assert_eq!(
+            db.get_recording_session(session.id).await.unwrap().notes,
+            noted.notes
+        );
+        assert!(matches!(
+            db.update_recording_session(-1, Some("missing"), None).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+
+        let open = db
+            .insert_recording_session("crashed", Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.end_open_recording_sessions("interrupted").await.unwrap(),
+            1
+        );
+
+        let sessions = db
+            .list_recording_sessions(None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, open.id);
+        assert_eq!(sessions[0].end_reason.as_deref(), Some("interrupted"));
+
+        let tagged = db
+            .set_recording_session_tag(session.id, Some("meeting"))
+            .await
+            .unwrap();
+        assert_eq!(tagged.tag.as_deref(), Some("meeting"));
+        let meetings = db
+            .list_recording_sessions(None, None, Some("meeting"), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(meetings.len(), 1);
+        assert_eq!(meetings[0].id, session.id);
+    }
+
+    #[tokio::test]
+    async fn test_job_queue_lifecycle() {
+        let db = setup_test_db().await;
+        let kinds = vec!["reencode_chunk".to_string()];
+
+        let job = db
+            .enqueue_job("reencode_chunk", r#"{"video_path":"a.mp4"}"#, 2)
+            .await
+            .unwrap();
+        db.enqueue_job("other", "{}", 1).await.unwrap();
+        assert_eq!(job.status, "pending");
+
+        let claimed = db
+            .claim_next_job(&kinds, Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.id, job.id);
+        assert_eq!(claimed.status, "running");
+        assert_eq!(claimed.attempts, 1);
+        assert!(db
+            .claim_next_job(&kinds, Utc::now())
+            .await
+            .unwrap()
+            .is_none());
+
+        // first failure reschedules the job, the second one exhausts its attempts
+        let retried = db
+            .fail_job(job.id, "ffmpeg crashed", chrono::Duration::seconds(60))
+            .await
+            .unwrap();
+        assert_eq!(retried.status, "pending");
+        assert!(db
+            .claim_next_job(&kinds, Utc::now())
+            .await
+            .unwrap()
+            .is_none());
+
+        let later = Utc::now() + chrono::Duration::seconds(120);
+        assert!(db.claim_next_job(&kinds, later).await.unwrap().is_some());
+        let failed = db
+            .fail_job(
+                job.id,
+                "ffmpeg crashed again",
+                chrono::Duration::seconds(60),
+            )
+            .await
+            .unwrap();
+        assert_eq!(failed.status, "failed");
+        assert_eq!(failed.error.as_deref(), Some("ffmpeg crashed again"));
+
+        let other = db
+            .claim_next_job(&["other".to_string()], Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        db.update_job_progress(other.id, 0.5).await.unwrap();
+        assert_eq!(db.requeue_running_jobs().await.unwrap(), 1);
+        let other = db
+            .claim_next_job(&["other".to_string()], Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(other.progress, 0.5);
+        assert_eq!(db.complete_job(other.id).await.unwrap().status, "done");
+
+        let counts = db.count_jobs_by_status().await.unwrap();
+        assert_eq!(
+            counts
+                .iter()
+                .map(|c| (c.status.as_str(), c.count))
+                .collect::<Vec<_>>(),
+            vec![("done", 1), ("failed", 1)]
+        );
+        assert_eq!(db.list_jobs(Some("failed"), 10, 0).await.unwrap().len(), 1);
+        assert_eq!(db.list_jobs(None, 10, 0).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_first_frame_per_minute() {
+        use chrono::TimeZone;
+
+        let db = setup_test_db().await;
+        db.insert_video_chunk("day.mp4", "monitor_1").await.unwrap();
+
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+        let mut ids = Vec::new();
+        for (minute, second) in [(0, 5), (0, 30), (2, 0), (2, 59), (10, 0)] {
+            let timestamp =
+                start + chrono::Duration::minutes(minute) + chrono::Duration::seconds(second);
+            ids.push(
+                db.insert_frame("monitor_1", Some(timestamp), None, None, None, true, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let frames = db
+            .get_first_frame_per_minute(start, start + chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| frame.frame_id)
+                .collect::<Vec<_>>(),
+            vec![ids[0], ids[2]]
+        );
+        assert_eq!(frames[1].timestamp, start + chrono::Duration::minutes(2));
+        assert_eq!(frames[0].file_path, "day.mp4");
+    }
+
+    #[tokio::test]
+    async fn test_get_frame_at() {
+        use chrono::TimeZone;
+
+        let db = setup_test_db().await;
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+        let at = |seconds| start + chrono::Duration::seconds(seconds);
+        db.insert_video_chunk("first.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for second in [0, 1, 2] {
+            ids.push(
+                db.insert_frame("monitor_1", Some(at(second)), None, None, None, true, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+        db.insert_video_chunk("other.mp4", "monitor_2")
+            .await
+            .unwrap();
+        let other = db
+            .insert_frame("monitor_2", Some(at(1)), None, None, None, true, None)
+            .await
+            .unwrap();
+
+        let frame = db
+            .get_frame_at(
+                at(1) + chrono::Duration::milliseconds(500),
+                Some("monitor_1"),
+                5,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.frame_id, ids[1]);
+        assert_eq!(frame.offset_index, 1);
+        assert_eq!(frame.file_path, "first.mp4");
+
+        let frame = db.get_frame_at(at(1), None, 5).await.unwrap().unwrap();
+        assert_eq!(frame.frame_id, other);
+
+        // before the first frame and long after the last, nothing is on screen
+        assert!(db
+            .get_frame_at(at(-1), Some("monitor_1"), 5)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get_frame_at(at(60), Some("monitor_1"), 5)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_video_chunk_spans() {
+        use chrono::TimeZone;
+
+        let db = setup_test_db().await;
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+        let at = |seconds| start + chrono::Duration::seconds(seconds);
+        for (chunk, seconds) in [
+            ("first.mp4", [0, 50]),
+            ("second.mp4", [60, 110]),
+            ("third.mp4", [120, 170]),
+        ] {
+            db.insert_video_chunk(chunk, "monitor_1").await.unwrap();
+            for second in seconds {
+                db.insert_frame("monitor_1", Some(at(second)), None, None, None, true, None)
+                    .await
+                    .unwrap();
+            }
+        }
+        db.insert_video_chunk("other.mp4", "monitor_2")
+            .await
+            .unwrap();
+        db.insert_frame("monitor_2", Some(at(70)), None, None, None, true, None)
+            .await
+            .unwrap();
+
+        let spans = db
+            .get_video_chunk_spans("monitor_1", at(30), at(100))
+            .await
+            .unwrap();
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| span.file_path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["first.mp4", "second.mp4"]
+        );
+        assert_eq!(spans[0].first_frame_at, at(0));
+        assert_eq!(spans[1].last_frame_at, at(110));
+
+        assert_eq!(
+            db.get_recorded_devices(at(0), at(180)).await.unwrap(),
+            vec!["monitor_1", "monitor_2"]
+        );
+        assert_eq!(
+            db.get_recorded_devices(at(100), at(180)).await.unwrap(),
+            vec!["monitor_1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_embed_and_search_transcriptions() {
+        let db = setup_test_db().await;
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "test".to_string(),
+            device_type: DeviceType::Output,
+        };
+        let pricing = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "the enterprise plan costs more",
+                0,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let lunch = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "let's get lunch",
+                1,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_audio_transcription(audio_chunk_id, "  ", 2, "", &device, None, None, None)
+            .await
+            .unwrap();
+
+        let pending = db.get_transcriptions_without_embeddings(10).await.unwrap();
+        assert_eq!(
+            pending.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![lunch, pricing]
+        );
+
+        db.insert_transcription_embedding(pricing, "[1.0, 0.0, 0.0]".to_string())
+            .await
+            .unwrap();
+        db.insert_transcription_embedding(lunch, "[0.0, 1.0, 0.0]".to_string())
+            .await
+            .unwrap();
+        assert!(db
+            .get_transcriptions_without_embeddings(10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let results = db
+            .search_similar_transcriptions(vec![0.9, 0.1, 0.0], 10, 0.5)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].transcription, "the enterprise plan costs more");
+        assert_eq!(results[0].file_path, "test_audio.mp4");
+    }
+
+    #[tokio::test]
+    async fn test_search_ocr_by_language() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let mut frame_ids = Vec::new();
+        for (text, language) in [
+            ("Compare our pricing plans", "en"),
+            ("料金プランを比較する", "ja"),
+            ("本日の料金プランのご案内", "ja"),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    Some("test"),
+                    Some(""),
+                    false,
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+            if language == "en" {
+                continue;
+            }
+            db.set_ocr_text_language(frame_id, language).await.unwrap();
+        }
+
+        // rows from before detection existed are picked up by the backfill
+        let pending = db.get_ocr_text_without_language(10).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1, "Compare our pricing plans");
+        db.update_ocr_text_languages(&[(pending[0].0, "en".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(db.count_ocr_text_without_language().await.unwrap(), 0);
+
+        let search = |query: &'static str, language: Option<&'static str>| {
+            let db = &db;
+            async move {
+                db.search(
+                    query,
+                    ContentType::All,
+                    100,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    language,
+                    None,
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|result| match result {
+                    SearchResult::OCR(ocr) => ocr.frame_id,
+                    _ => panic!("expected OCR result"),
+                })
+                .collect::<Vec<_>>()
+            }
+        };
+
+        let mut japanese = search("", Some("ja")).await;
+        japanese.sort();
+        assert_eq!(japanese, vec![frame_ids[1], frame_ids[2]]);
+        assert_eq!(search("", Some("en")).await, vec![frame_ids[0]]);
+
+        // a japanese sentence is a single unicode61 token, the trigram index finds words in it
+        let mut matches = search("料金プラン", None).await;
+        matches.sort();
+        assert_eq!(matches, vec![frame_ids[1], frame_ids[2]]);
+        assert_eq!(search("pricing", None).await, vec![frame_ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn test_search_cjk_text_in_frames_of_another_language() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "Pricing overview, see 料金プランのご案内 for details",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        db.set_ocr_text_language(frame_id, "en").await.unwrap();
+
+        let search = |query: &'static str| {
+            let db = &db;
+            async move {
+                db.search(
+                    query,
+                    ContentType::OCR,
+                    100,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap()
+                .len()
+            }
+        };
+        assert_eq!(search("料金プラン").await, 1);
+
+        // the trigram index follows edits of the text
+        sqlx::query("UPDATE ocr_text SET text = 'Pricing overview' WHERE frame_id = ?1")
+            .bind(frame_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(search("料金プラン").await, 0);
+        assert_eq!(search("overview").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_insert_ui_monitoring() {
+        let db = setup_test_db().await;
+
+        let id = db
+            .insert_ui_monitoring(
+                Utc::now(),
+                "Mail",
+                "Inbox",
+                "Inbox\nInvoice #4411 is due on Friday",
+            )
+            .await
+            .unwrap();
+
+        let results = db
+            .search(
+                "Invoice",
+                ContentType::UI,
+                100,
+                0,
+                None,
+                None,
+                Some("Mail"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        match &results[0] {
+            SearchResult::UI(ui) => {
+                assert_eq!(ui.id, id);
+                assert_eq!(ui.window_name, "Inbox");
+                assert!(ui.initial_traversal_at.is_some());
+            }
+            _ => panic!("expected UI result"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_clipboard() {
+        let db = setup_test_db().await;
+
+        for (text, app) in [
+            ("meeting moved to thursday 3pm", "Slack"),
+            ("password=[SECRET]", "Terminal"),
+        ] {
+            db.insert_clipboard_entry(&NewClipboardEntry {
+                timestamp: Utc::now(),
+                text: text.to_string(),
+                app_name: Some(app.to_string()),
+                window_name: None,
+                redacted: text.contains("[SECRET]"),
+            })
+            .await
+            .unwrap();
+        }
+
+        let results = db
+            .search_clipboard("thursday", None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].app_name.as_deref(), Some("Slack"));
+        assert!(!results[0].redacted);
+
+        let all = db.search_clipboard("", None, None, 10, 0).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().any(|entry| entry.redacted));
+
+        let later = db
+            .search_clipboard(
+                "",
+                Some(Utc::now() + chrono::Duration::hours(1)),
+                None,
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert!(later.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_ocr_by_block_language() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        // mostly english with a japanese sidebar
+        let mixed = db
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            mixed,
+            "Release notes for the new version ファイル",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        db.set_ocr_text_language(mixed, "en").await.unwrap();
+        db.set_ocr_block_languages(mixed, &[("en".to_string(), 3), ("ja".to_string(), 1)])
+            .await
+            .unwrap();
+
+        let english = db
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                Some("test"),
+                Some(""),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            english,
+            "Compare our pricing plans",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        db.set_ocr_text_language(english, "en").await.unwrap();
+
+        assert_eq!(
+            db.get_ocr_block_languages(mixed).await.unwrap(),
+            vec![("en".to_string(), 3), ("ja".to_string(), 1)]
+        );
+
+        let search = |language: &'static str| {
+            let db = &db;
+            async move {
+                db.search(
+                    "",
+                    ContentType::OCR,
+                    100,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(language),
+                    None,
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|result| match result {
+                    SearchResult::OCR(ocr) => ocr.frame_id,
+                    _ => panic!("expected OCR result"),
+                })
+                .collect::<Vec<_>>()
+            }
+        };
+
+        // found by the language of its blocks as well as by its main one
+        assert_eq!(search("ja").await, vec![mixed]);
+        let mut english_frames = search("en").await;
+        english_frames.sort();
+        assert_eq!(english_frames, vec![mixed, english]);
+        assert_eq!(
+            db.count_search_results(
+                "",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("ja"),
+                None,
+            )
+            .await
+            .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_app_usage_and_ocr_text_samples() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        // aligned to the sample buckets so the first two frames share one
+        let start = chrono::DateTime::from_timestamp(
+            (Utc::now() - chrono::Duration::hours(1)).timestamp() / 120 * 120,
+            0,
+        )
+        .unwrap();
+        for (offset, app, window, text) in [
+            (0, "Code", "main.rs", "fn main"),
+            (10, "Code", "main.rs", "fn main() {}"),
+            (20, "Firefox", "docs", "rust docs"),
+            (200, "Code", "main.rs", "fn main() { run() }"),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    Some(start + chrono::Duration::seconds(offset)),
+                    None,
+                    Some(app),
+                    Some(window),
+                    false,
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+        let end = start + chrono::Duration::minutes(10);
+
+        let usage = db.get_app_usage(start, end, 10).await.unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].app_name, "Code");
+        assert_eq!(usage[0].frame_count, 3);
+        assert_eq!(
+            usage[0].last_seen - usage[0].first_seen,
+            chrono::Duration::seconds(200)
+        );
+        assert_eq!(usage[1].app_name, "Firefox");
+
+        // 120s buckets keep the first frame of each window per bucket
+        let samples = db.get_ocr_text_samples(start, end, 120, 10).await.unwrap();
+        let texts: Vec<&str> = samples.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.contains(&"rust docs"));
+        assert!(texts.contains(&"fn main() { run() }"));
+        assert!(!texts.contains(&"fn main() {}"));
+        assert_eq!(samples.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_ui_events_link_frames_and_search() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let start = Utc::now() - chrono::Duration::minutes(5);
+        let frame_id = db
+            .insert_frame(
+                "test_device",
+                Some(start),
+                None,
+                Some("Mail"),
+                Some("Inbox"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let window_event = screenpipe_db::NewUiEvent {
+            timestamp: start + chrono::Duration::seconds(2),
+            event_type: screenpipe_db::UI_EVENT_WINDOW.to_string(),
+            app_name: "Mail".to_string(),
+            window_name: "Inbox".to_string(),
+            controls: vec![
+                screenpipe_db::UiControl {
+                    role: "button".to_string(),
+                    name: "Archive".to_string(),
+                },
+                screenpipe_db::UiControl {
+                    role: "button".to_string(),
+                    name: "Reply".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        db.insert_ui_event(&window_event).await.unwrap();
+        // too long after the frame to belong to it
+        db.insert_ui_event(&screenpipe_db::NewUiEvent {
+            timestamp: start + chrono::Duration::seconds(60),
+            event_type: screenpipe_db::UI_EVENT_FOCUS.to_string(),
+            app_name: "Mail".to_string(),
+            window_name: "Inbox".to_string(),
+            element_role: Some("textfield".to_string()),
+            element_name: Some("Search mailbox".to_string()),
+            element_value: Some("invoice".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let linked = db.get_ui_events_for_frame(frame_id).await.unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].event_type, "window");
+        let controls: Vec<screenpipe_db::UiControl> =
+            serde_json::from_str(linked[0].controls.as_deref().unwrap()).unwrap();
+        assert_eq!(controls, window_event.controls);
+
+        let found = db
+            .search_ui_events("archive", None, None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].frame_id, Some(frame_id));
+
+        let found = db
+            .search_ui_events("invoice", Some("Mail"), None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].frame_id, None);
+        assert_eq!(found[0].element_name.as_deref(), Some("Search mailbox"));
+
+        let all = db
+            .search_ui_events("", None, None, Some(start), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].event_type, "focus");
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_list_access_log() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+
+        for (seconds, action, client) in [(0, "search", "local"), (10, "export", "key:1a2b3c4d")] {
+            db.insert_access_log(&screenpipe_db::NewAccessLogEntry {
+                timestamp: now + chrono::Duration::seconds(seconds),
+                action: action.to_string(),
+                method: "GET".to_string(),
+                path: format!("/{}", action),
+                client: client.to_string(),
+                start_time: Some(now - chrono::Duration::hours(1)),
+                end_time: Some(now),
+                status: 200,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let all = db
+            .list_access_log(None, None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].action, "export");
+        assert_eq!(all[0].status, 200);
+        assert!(all[0].start_time.is_some());
+
+        let searches = db
+            .list_access_log(None, None, Some("search"), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(searches.len(), 1);
+        assert_eq!(searches[0].client, "local");
+
+        let later = db
+            .list_access_log(
+                Some(now + chrono::Duration::seconds(5)),
+                None,
+                None,
+                None,
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].client, "key:1a2b3c4d");
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_frames() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::minutes(10);
+        let at = |seconds| start + chrono::Duration::seconds(seconds);
+        let lock_screen = 0xF0F0_0F0F_AAAA_5555_u64;
+
+        db.insert_video_chunk("first.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let canonical_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(at(0)),
+                None,
+                Some("loginwindow"),
+                None,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        db.set_frame_perceptual_hash(canonical_id, lock_screen)
+            .await
+            .unwrap();
+
+        db.insert_video_chunk("second.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let desk_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(at(60)),
+                None,
+                Some("Finder"),
+                None,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        db.set_frame_perceptual_hash(desk_id, lock_screen ^ 0xFFFF)
+            .await
+            .unwrap();
+        let later_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(at(120)),
+                None,
+                Some("loginwindow"),
+                None,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        db.set_frame_perceptual_hash(later_id, lock_screen)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            later_id,
+            "Enter password",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+
+        // the screen came back, it reuses the image of the first frame
+        let duplicate_id = db
+            .insert_duplicate_frame(
+                canonical_id,
+                Some(at(180)),
+                None,
+                Some("loginwindow"),
+                None,
+                true,
+                lock_screen,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_frame(duplicate_id).await.unwrap(),
+            Some(("first.mp4".to_string(), 0))
+        );
+        // the next encoded frame of the chunk still gets the next offset
+        let next_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(at(190)),
+                None,
+                Some("Finder"),
+                None,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_frame(next_id).await.unwrap(),
+            Some(("second.mp4".to_string(), 2))
+        );
+        assert!(db
+            .insert_duplicate_frame(-1, None, None, None, None, true, lock_screen, None)
+            .await
+            .is_err());
+
+        let hashes = db.get_frame_hashes(at(0), at(200)).await.unwrap();
+        assert_eq!(
+            hashes.iter().map(|frame| frame.id).collect::<Vec<_>>(),
+            vec![canonical_id, desk_id, later_id]
+        );
+        assert_eq!(hashes[0].perceptual_hash as u64, lock_screen);
+        assert_eq!(hashes[2].text.as_deref(), Some("Enter password"));
+
+        let collapsed = db
+            .collapse_duplicate_frames(&[(later_id, canonical_id)])
+            .await
+            .unwrap();
+        assert_eq!(collapsed, 1);
+        assert_eq!(db.get_frame_hashes(at(0), at(200)).await.unwrap().len(), 2);
+        // still found at its own time
+        let texts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ocr_text WHERE frame_id = ?1")
+            .bind(later_id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(texts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_insert_image_frame() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+
+        // two windows of a capture share its image
+        let image = "frames/10/monitor_1_2025-03-01_10-00-00-000.webp";
+        let editor_id = db
+            .insert_image_frame(
+                "monitor_1",
+                image,
+                None,
+                None,
+                Some("Code"),
+                Some("main.rs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        let terminal_id = db
+            .insert_image_frame(
+                "monitor_1",
+                image,
+                None,
+                None,
+                Some("Terminal"),
+                Some("zsh"),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        let next_id = db
+            .insert_image_frame(
+                "monitor_1",
+                "frames/10/monitor_1_2025-03-01_10-00-01-000.webp",
+                None,
+                None,
+                Some("Code"),
+                Some("main.rs"),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        for frame_id in [editor_id, terminal_id] {
+            assert_eq!(
+                db.get_frame(frame_id).await.unwrap(),
+                Some((image.to_string(), 0))
+            );
+        }
+        assert_eq!(
+            db.get_frame(next_id).await.unwrap(),
+            Some((
+                "frames/10/monitor_1_2025-03-01_10-00-01-000.webp".to_string(),
+                0
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_count_unfinished_jobs_before() {
+        let db = setup_test_db().await;
+        let payload = r#"{"video_path":"a.mp4"}"#;
+
+        let reencode = db.enqueue_job("reencode_chunk", payload, 3).await.unwrap();
+        db.enqueue_job("build_pyramid", r#"{"video_path":"b.mp4"}"#, 3)
+            .await
+            .unwrap();
+        let upload = db.enqueue_job("upload_segment", payload, 3).await.unwrap();
+
+        assert_eq!(
+            db.count_unfinished_jobs_before(upload.id, payload)
+                .await
+                .unwrap(),
+            1
+        );
+        db.complete_job(reencode.id).await.unwrap();
+        assert_eq!(
+            db.count_unfinished_jobs_before(upload.id, payload)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_count_unfinished_jobs_of_kind_before() {
+        let db = setup_test_db().await;
+        let first = db
+            .enqueue_job("seal_segment", r#"{"video_path":"a.mp4"}"#, 3)
+            .await
+            .unwrap();
+        db.enqueue_job("upload_segment", r#"{"video_path":"a.mp4"}"#, 3)
+            .await
+            .unwrap();
+        let second = db
+            .enqueue_job("seal_segment", r#"{"video_path":"b.mp4"}"#, 3)
+            .await
+            .unwrap();
+
+        let unfinished = |id| db.count_unfinished_jobs_of_kind_before("seal_segment", id);
+        assert_eq!(unfinished(second.id).await.unwrap(), 1);
+        assert_eq!(unfinished(first.id).await.unwrap(), 0);
+        db.complete_job(first.id).await.unwrap();
+        assert_eq!(unfinished(second.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_schema() {
+        let db = setup_test_db().await;
+        let schema = db.get_schema().await.unwrap();
+        assert!(schema.migration.is_some());
+
+        let frames = schema
+            .objects
+            .iter()
+            .find(|object| object.kind == "table" && object.name == "frames")
+            .unwrap();
+        let id = frames
+            .columns
+            .iter()
+            .find(|column| column.name == "id")
+            .unwrap();
+        assert!(id.primary_key);
+        assert!(frames
+            .columns
+            .iter()
+            .any(|column| column.name == "timestamp"));
+
+        assert!(schema
+            .objects
+            .iter()
+            .any(|object| object.kind == "index" && object.table_name == "frames"));
+        // only tables and views have columns
+        assert!(schema
+            .objects
+            .iter()
+            .filter(|object| object.kind == "index")
+            .all(|object| object.columns.is_empty()));
+        assert!(!schema
+            .objects
+            .iter()
+            .any(|object| object.name.starts_with("_sqlx")));
+    }
+
+    #[tokio::test]
+    async fn test_get_app_activity() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::hours(1);
+        for (offset, app, window, focused) in [
+            (0, "Code", "main.rs", true),
+            // another window captured with the focused one
+            (0, "Slack", "general", false),
+            (5, "Firefox", "docs", true),
+            (10, "", "", true),
+        ] {
+            db.insert_frame(
+                "test_device",
+                Some(start + chrono::Duration::seconds(offset)),
+                None,
+                Some(app),
+                Some(window),
+                focused,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let activity = db
+            .get_app_activity(start, start + chrono::Duration::minutes(1))
+            .await
+            .unwrap();
+        let apps: Vec<&str> = activity.iter().map(|a| a.app_name.as_str()).collect();
+        assert_eq!(apps, vec!["Code", "Firefox"]);
+        assert_eq!(activity[1].window_name, "docs");
+        assert!(activity[0].timestamp < activity[1].timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_remote_frames_round_trip() {
+        let peer = setup_test_db().await;
+        peer.insert_video_chunk("/data/laptop/2025-03-01/monitor_1/a.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for text in ["quarterly report", "lunch menu"] {
+            let frame_id = peer
+                .insert_frame(
+                    "monitor_1",
+                    None,
+                    None,
+                    Some("Notes"),
+                    Some("doc"),
+                    true,
+                    None,
+                )
+                .await
+                .unwrap();
+            peer.insert_ocr_text(frame_id, text, "[]", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+
+        let mut frames = peer.get_local_frames_for_hub(0, 10).await.unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].text.as_deref(), Some("quarterly report"));
+        let cursor = frames[0].frame_id;
+        assert_eq!(
+            peer.get_local_frames_for_hub(cursor, 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        for frame in &mut frames {
+            frame.file_path = "laptop/2025-03-01/monitor_1/a.mp4".to_string();
+        }
+
+        let hub = setup_test_db().await;
+        assert_eq!(
+            hub.insert_remote_frames("laptop", &frames).await.unwrap(),
+            2
+        );
+        // pushed again after a lost response
+        assert_eq!(
+            hub.insert_remote_frames("laptop", &frames).await.unwrap(),
+            0
+        );
+        // frames pushed by peers aren't pushed on by the hub
+        assert!(hub
+            .get_local_frames_for_hub(0, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let results = hub
+            .search(
+                "quarterly",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let SearchResult::OCR(ocr) = &results[0] else {
+            panic!("Expected OCR result");
+        };
+        assert_eq!(ocr.host_name.as_deref(), Some("laptop"));
+        assert_eq!(ocr.device_name, "laptop/monitor_1");
+        assert_eq!(ocr.file_path, "laptop/2025-03-01/monitor_1/a.mp4");
+        assert_eq!(ocr.app_name, "Notes");
+    }
+
+    #[tokio::test]
+    async fn test_excise_time_range() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        db.insert_video_chunk("chunk_a.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let kept = db
+            .insert_frame(
+                "monitor_1",
+                Some(now - chrono::Duration::minutes(10)),
+                None,
+                Some("Notes"),
+                None,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        let mut excised = Vec::new();
+        for chunk in ["chunk_a.mp4", "chunk_b.mp4"] {
+            if chunk == "chunk_b.mp4" {
+                db.insert_video_chunk(chunk, "monitor_1").await.unwrap();
+            }
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(now - chrono::Duration::minutes(1)),
+                    None,
+                    Some("Bank"),
+                    None,
+                    true,
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, "account 1234", "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            excised.push(frame_id);
+        }
+        for minutes in [10, 1] {
+            db.insert_ui_monitoring(
+                now - chrono::Duration::minutes(minutes),
+                "Bank",
+                "Accounts",
+                &format!("balance {} minutes ago", minutes),
+            )
+            .await
+            .unwrap();
+            db.insert_clipboard_entry(&NewClipboardEntry {
+                timestamp: now - chrono::Duration::minutes(minutes),
+                text: format!("copied {} minutes ago", minutes),
+                app_name: None,
+                window_name: None,
+                redacted: false,
+            })
+            .await
+            .unwrap();
+        }
+
+        let excision = db
+            .excise_time_range(now - chrono::Duration::minutes(5), now, Some("hotkey"))
+            .await
+            .unwrap();
+        assert_eq!(excision.redaction.frames, 2);
+        assert_eq!(excision.redaction.clipboard_entries, 1);
+        assert_eq!(excision.video_chunks, vec!["chunk_a.mp4", "chunk_b.mp4"]);
+
+        let frames = db.get_video_chunk_frames("chunk_a.mp4").await.unwrap();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| frame.frame_id)
+                .collect::<Vec<_>>(),
+            vec![kept]
+        );
+        assert!(db
+            .get_video_chunk_frames("chunk_b.mp4")
+            .await
+            .unwrap()
+            .is_empty());
+        for frame_id in excised {
+            assert!(db.get_frame(frame_id).await.unwrap().is_none());
+        }
+        let clipboard = db.search_clipboard("", None, None, 10, 0).await.unwrap();
+        assert_eq!(clipboard.len(), 1);
+        assert_eq!(clipboard[0].text, "copied 10 minutes ago");
+        let ui_text: Vec<String> = sqlx::query_scalar("SELECT text_output FROM ui_monitoring")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(ui_text, vec!["balance 10 minutes ago"]);
+
+        let redactions = db.list_redactions(10, 0).await.unwrap();
+        assert_eq!(redactions.len(), 1);
+        assert_eq!(redactions[0].id, excision.redaction.id);
+        assert_eq!(redactions[0].reason.as_deref(), Some("hotkey"));
+    }
+
+    #[tokio::test]
+    async fn test_search_and_activity_by_category() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let mut frames = Vec::new();
+        for (app, category) in [
+            ("Code", Some("coding")),
+            ("Mail", Some("email")),
+            ("Finder", None),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    Some(app),
+                    Some(""),
+                    true,
+                    category,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                "quarterly report",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+            frames.push(frame_id);
+        }
+
+        let results = db
+            .search(
+                "",
+                ContentType::All,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("coding"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        match &results[0] {
+            SearchResult::OCR(ocr) => {
+                assert_eq!(ocr.frame_id, frames[0]);
+                assert_eq!(ocr.category.as_deref(), Some("coding"));
+            }
+            _ => panic!("expected OCR result"),
+        }
+        assert_eq!(
+            db.count_search_results(
+                "",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("email"),
+            )
+            .await
+            .unwrap(),
+            1
+        );
+
+        // recategorized after the rules changed
+        let now = Utc::now();
+        let start = now - chrono::Duration::minutes(5);
+        let to_categorize = db.get_frames_to_categorize(start, now).await.unwrap();
+        assert_eq!(to_categorize.len(), 3);
+        db.set_frame_categories(&[(frames[2], Some("files".to_string()))])
+            .await
+            .unwrap();
+
+        let activity = db.get_app_activity(start, now).await.unwrap();
+        assert_eq!(
+            activity
+                .iter()
+                .map(|frame| frame.category.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("coding"), Some("email"), Some("files")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_backfilled_chunk() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::days(60);
+        let frames: Vec<BackfillFrame> = (0..3)
+            .map(|i| BackfillFrame {
+                offset_index: i,
+                timestamp: start + chrono::Duration::seconds(i * 10),
+                app_name: Some("Code".to_string()),
+                window_name: Some(format!("file {}", i)),
+                focused: true,
+                category: None,
+            })
+            .collect();
+
+        let ids = db
+            .insert_backfilled_chunk("/recordings/monitor_1.mp4", "monitor_1", &frames)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        // indexed once, however many times the backfill runs
+        assert!(db
+            .insert_backfilled_chunk("/recordings/monitor_1.mp4", "monitor_1", &frames)
+            .await
+            .unwrap()
+            .is_none());
+
+        let frame = db
+            .get_frame_at(start + chrono::Duration::seconds(15), Some("monitor_1"), 60)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.frame_id, ids[1]);
+        assert_eq!(frame.offset_index, 1);
+        assert_eq!(frame.file_path, "/recordings/monitor_1.mp4");
+    }
 }
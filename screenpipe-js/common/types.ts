@@ -76,6 +76,8 @@ export interface OCRContent {
   browserUrl?: string;
   focused?: boolean;
   deviceName: string;
+  /** Machine the frame was recorded on, when searching a hub other machines push to. */
+  hostName?: string;
 }
 
 /**
@@ -8,4 +8,14 @@ fn main() {
     {
         link_onnx();
     }
+
+    // build without a system protoc
+    let protoc =
+        protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this platform");
+    std::env::set_var("PROTOC", protoc);
+    println!("cargo:rerun-if-changed=proto/screenpipe.proto");
+    tonic_build::configure()
+        .build_client(false)
+        .compile_protos(&["proto/screenpipe.proto"], &["proto"])
+        .expect("failed to compile proto/screenpipe.proto");
 }
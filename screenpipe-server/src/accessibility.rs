@@ -0,0 +1,227 @@
+use crate::ui_events::{
+    find_focused_window, is_secret, label, normalize_role, recordable_foreground_window,
+};
+use chrono::{DateTime, Utc};
+use screenpipe_core::{Desktop, UIElement};
+use screenpipe_db::DatabaseManager;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Bounds of the walk over a window's tree, which can be huge in browsers and IDEs.
+const MAX_VISITED: usize = 5000;
+const MAX_DEPTH: usize = 30;
+/// Characters kept of a window's text, a full document in an editor can be much longer.
+const MAX_TEXT_CHARS: usize = 20_000;
+
+/// The text of the focused window's accessibility tree at one snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeSnapshot {
+    pub app_name: String,
+    pub window_name: String,
+    pub text: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Joins the texts of a tree's elements, read top to bottom, into one text of a line each.
+/// Empty texts and texts already seen, like a label repeated as its button's description,
+/// are left out, and the text stops at [`MAX_TEXT_CHARS`].
+pub fn join_tree_text(texts: impl IntoIterator<Item = String>) -> String {
+    let mut seen = HashSet::new();
+    let mut out = String::new();
+    let mut chars = 0;
+    for text in texts {
+        let text = text.trim();
+        if text.is_empty() || !seen.insert(text.to_string()) {
+            continue;
+        }
+        let length = text.chars().count();
+        if chars + length > MAX_TEXT_CHARS {
+            break;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(text);
+        chars += length + 1;
+    }
+    out
+}
+
+/// The labels, descriptions and values of the elements under `window`, depth first so the text
+/// reads in the order it's laid out. Values of password fields are never read.
+fn tree_texts(window: &UIElement) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut stack = vec![(window.clone(), 0)];
+    let mut visited = 0;
+
+    while let Some((element, depth)) = stack.pop() {
+        visited += 1;
+        if visited > MAX_VISITED {
+            break;
+        }
+        let role = normalize_role(&element.role());
+        let name = label(&element);
+        if !is_secret(&role, &name) {
+            if let Some(value) = element.attributes().value {
+                texts.push(value);
+            }
+        }
+        texts.push(name);
+        if depth < MAX_DEPTH {
+            if let Ok(children) = element.children() {
+                // reversed so that the first child is walked first
+                stack.extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+            }
+        }
+    }
+    texts
+}
+
+/// Reads the text of the focused window's tree, `None` when nothing is focused or the window
+/// must not be recorded.
+pub fn read_tree_snapshot(desktop: &Desktop) -> Option<TreeSnapshot> {
+    let focused = find_focused_window(desktop)?;
+    let text = join_tree_text(tree_texts(&focused.window));
+    // the walk takes a while, the focus may have moved to a window not to record meanwhile
+    if recordable_foreground_window()? != (focused.app_name.clone(), focused.window_name.clone()) {
+        return None;
+    }
+    Some(TreeSnapshot {
+        text,
+        app_name: focused.app_name,
+        window_name: focused.window_name,
+        timestamp: Utc::now(),
+    })
+}
+
+/// Windows whose last stored text is remembered, for only what's new to be stored when one
+/// gets the focus again.
+const REMEMBERED_WINDOWS: usize = 16;
+/// How long the text of a window may keep changing, as while typing, before it's stored anyway.
+const MAX_UNSETTLED: Duration = Duration::from_secs(60);
+
+/// Turns the snapshots of the focused window into what's stored of it: once the text settled,
+/// being the same at two snapshots in a row, or the focus moved on, the lines the window didn't
+/// have when it was last stored.
+#[derive(Debug, Default)]
+pub struct TreeSnapshotTracker {
+    /// The last snapshot, whose text may still change, and since when it has been changing.
+    pending: Option<(TreeSnapshot, DateTime<Utc>)>,
+    /// The lines last stored of each window, the most recent last.
+    stored: VecDeque<((String, String), HashSet<String>)>,
+}
+
+impl TreeSnapshotTracker {
+    /// What to store after `snapshot`, `None` for nothing focused that may be recorded.
+    pub fn observe(&mut self, snapshot: Option<TreeSnapshot>) -> Option<TreeSnapshot> {
+        let Some(snapshot) = snapshot.filter(|snapshot| !snapshot.text.is_empty()) else {
+            return self.flush();
+        };
+        let Some((pending, since)) = self.pending.take() else {
+            self.pending = Some((snapshot.clone(), snapshot.timestamp));
+            return None;
+        };
+        if (&pending.app_name, &pending.window_name) != (&snapshot.app_name, &snapshot.window_name)
+        {
+            self.pending = Some((snapshot.clone(), snapshot.timestamp));
+            return self.new_lines(pending);
+        }
+        if pending.text == snapshot.text {
+            return self.new_lines(pending);
+        }
+        if (snapshot.timestamp - since).to_std().unwrap_or_default() >= MAX_UNSETTLED {
+            return self.new_lines(snapshot);
+        }
+        self.pending = Some((snapshot, since));
+        None
+    }
+
+    /// What's left to store of the last snapshot.
+    pub fn flush(&mut self) -> Option<TreeSnapshot> {
+        let (pending, _) = self.pending.take()?;
+        self.new_lines(pending)
+    }
+
+    /// The lines of `snapshot` its window didn't have when last stored, `None` when it has
+    /// none new.
+    fn new_lines(&mut self, snapshot: TreeSnapshot) -> Option<TreeSnapshot> {
+        let window = (snapshot.app_name.clone(), snapshot.window_name.clone());
+        let previous = self
+            .stored
+            .iter()
+            .position(|(stored, _)| *stored == window)
+            .and_then(|index| self.stored.remove(index))
+            .map(|(_, lines)| lines)
+            .unwrap_or_default();
+        let text = snapshot
+            .text
+            .lines()
+            .filter(|line| !previous.contains(*line))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.stored
+            .push_back((window, snapshot.text.lines().map(str::to_string).collect()));
+        if self.stored.len() > REMEMBERED_WINDOWS {
+            self.stored.pop_front();
+        }
+        (!text.is_empty()).then_some(TreeSnapshot { text, ..snapshot })
+    }
+}
+
+/// Snapshots the text of the focused window's accessibility tree every `interval` and stores
+/// what's new of it, searchable as UI content alongside the OCR text of the frames.
+pub async fn record_accessibility_text(db: Arc<DatabaseManager>, interval: Duration) {
+    let (sender, mut receiver) = mpsc::channel::<Option<TreeSnapshot>>(4);
+
+    // accessibility calls block, sometimes for long, so they get a thread of their own
+    std::thread::spawn(move || {
+        let desktop = match Desktop::new(false, false) {
+            Ok(desktop) => desktop,
+            Err(e) => {
+                warn!(
+                    "accessibility capture disabled, accessibility is not available: {}",
+                    e
+                );
+                return;
+            }
+        };
+        info!(
+            "capturing the text of the focused window's accessibility tree every {:?}",
+            interval
+        );
+        loop {
+            if sender.blocking_send(read_tree_snapshot(&desktop)).is_err() {
+                break;
+            }
+            std::thread::sleep(interval);
+        }
+    });
+
+    let mut tracker = TreeSnapshotTracker::default();
+    while let Some(snapshot) = receiver.recv().await {
+        let Some(snapshot) = tracker.observe(snapshot) else {
+            continue;
+        };
+        match db
+            .insert_ui_monitoring(
+                snapshot.timestamp,
+                &snapshot.app_name,
+                &snapshot.window_name,
+                &snapshot.text,
+            )
+            .await
+        {
+            Ok(id) => debug!(
+                "stored accessibility text {} of {} ({} chars)",
+                id,
+                snapshot.app_name,
+                snapshot.text.len()
+            ),
+            Err(e) => error!("failed to store accessibility text: {}", e),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::mosaic::local_day_bounds;
+use crate::time_tracking::{time_entries, TimeTrackingConfig};
+use crate::upload::{hex, hmac_sha256};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use oasgen::OaSchema;
+use rand::Rng;
+use screenpipe_db::AppActivity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Category of the time no rule of `time_tracking.json` maps to a project, or no rule of
+/// `categories.json` to a category.
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// What the export keeps of window titles.
+#[derive(OaSchema, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleMode {
+    /// Titles are left out, only the categories are exported.
+    #[default]
+    Strip,
+    /// Time per window is exported under a keyed hash of the title, which tells windows
+    /// apart without revealing them.
+    Hash,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatsOptions {
+    /// Privacy budget: Laplace noise scaled to what a single frame adds, divided by
+    /// `epsilon`, is added to every total. Exact totals when `None`.
+    pub epsilon: Option<f64>,
+    pub titles: TitleMode,
+    /// Key of the title hashes. A random one per export when not set, so that hashes of two
+    /// exports can't be matched.
+    pub salt: Option<String>,
+}
+
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeShare {
+    pub name: String,
+    pub secs: i64,
+}
+
+/// Time of a local day, in seconds.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayStats {
+    /// As `YYYY-MM-DD`.
+    pub date: String,
+    pub active_secs: i64,
+    /// Time of the day within the range nothing was recorded in, nights included.
+    pub idle_secs: i64,
+    /// Time per category, the projects of the time tracking rules, longest first.
+    pub categories: Vec<TimeShare>,
+    /// Time per category the frames were given by the rules of `categories.json`, longest
+    /// first.
+    #[serde(default)]
+    pub activity_categories: Vec<TimeShare>,
+    /// Time per hashed window title with [`TitleMode::Hash`], longest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<TimeShare>,
+}
+
+#[derive(Default)]
+struct DayTotals {
+    active_secs: i64,
+    idle_secs: i64,
+    categories: BTreeMap<String, i64>,
+    activity_categories: BTreeMap<String, i64>,
+    windows: BTreeMap<String, i64>,
+}
+
+fn longest_first(totals: BTreeMap<String, i64>) -> Vec<TimeShare> {
+    let mut shares: Vec<_> = totals
+        .into_iter()
+        .filter(|(_, secs)| *secs > 0)
+        .map(|(name, secs)| TimeShare { name, secs })
+        .collect();
+    shares.sort_by(|a, b| b.secs.cmp(&a.secs));
+    shares
+}
+
+/// Aggregate statistics of a time range, with nothing that reveals what was on screen.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityStats {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Privacy budget the totals were noised with, `None` when they're exact.
+    pub epsilon: Option<f64>,
+    pub days: Vec<DayStats>,
+}
+
+/// Laplace noise of scale `scale`.
+fn laplace(rng: &mut impl Rng, scale: f64) -> f64 {
+    let u = rng.gen::<f64>() - 0.5;
+    -scale * u.signum() * (1.0 - 2.0 * u.abs()).max(f64::MIN_POSITIVE).ln()
+}
+
+/// Splits `[start, end)` over the local days it spans.
+fn split_by_day(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(NaiveDate, i64)> {
+    let mut days = Vec::new();
+    let mut date = start.with_timezone(&Local).date_naive();
+    loop {
+        let (day_start, day_end) = local_day_bounds(date);
+        if day_start >= end {
+            return days;
+        }
+        let secs = (end.min(day_end) - start.max(day_start)).num_seconds();
+        if secs > 0 {
+            days.push((date, secs));
+        }
+        match date.succ_opt() {
+            Some(next) if day_end > day_start => date = next,
+            _ => return days,
+        }
+    }
+}
+
+/// Aggregates the frames of `activity`, captured between `start` and `end`, into the time of
+/// each day per category, mapped by the rules of `config`.
+pub fn activity_stats(
+    activity: &[AppActivity],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    config: &TimeTrackingConfig,
+    options: &StatsOptions,
+    rng: &mut impl Rng,
+) -> Result<ActivityStats> {
+    if end <= start {
+        return Err(anyhow!("end_time must be after start_time"));
+    }
+    if let Some(epsilon) = options
+        .epsilon
+        .filter(|epsilon| !epsilon.is_finite() || *epsilon <= 0.0)
+    {
+        return Err(anyhow!("epsilon must be positive, got {}", epsilon));
+    }
+
+    // every frame counts, not only the entries long enough for a timesheet
+    let config = TimeTrackingConfig {
+        default_project: Some(
+            config
+                .default_project
+                .clone()
+                .unwrap_or_else(|| UNCATEGORIZED.to_string()),
+        ),
+        min_entry_secs: 0,
+        ..config.clone()
+    };
+    let salt = options.salt.clone().unwrap_or_else(|| {
+        let key: [u8; 16] = rng.gen();
+        hex(&key)
+    });
+
+    let mut days: BTreeMap<NaiveDate, DayTotals> = split_by_day(start, end)
+        .into_iter()
+        .map(|(date, secs)| {
+            let day = DayTotals {
+                idle_secs: secs,
+                ..Default::default()
+            };
+            (date, day)
+        })
+        .collect();
+    for entry in time_entries(activity, &config)? {
+        let window = (options.titles == TitleMode::Hash).then(|| {
+            let title = format!("{}\n{}", entry.app_name, entry.window_name);
+            let mut hash = hex(&hmac_sha256(salt.as_bytes(), &title));
+            hash.truncate(16);
+            hash
+        });
+        for (date, secs) in split_by_day(entry.start.max(start), entry.end.min(end)) {
+            let Some(day) = days.get_mut(&date) else {
+                continue;
+            };
+            day.active_secs += secs;
+            day.idle_secs -= secs;
+            *day.categories.entry(entry.project.clone()).or_default() += secs;
+            let category = entry.category.as_deref().unwrap_or(UNCATEGORIZED);
+            *day.activity_categories
+                .entry(category.to_string())
+                .or_default() += secs;
+            if let Some(window) = &window {
+                *day.windows.entry(window.clone()).or_default() += secs;
+            }
+        }
+    }
+
+    if let Some(epsilon) = options.epsilon {
+        // a frame adds up to max_gap_secs to the active and idle time of its day, its
+        // categories and its window
+        let released = if options.titles == TitleMode::Hash {
+            5
+        } else {
+            4
+        };
+        let scale = (config.max_gap_secs * released) as f64 / epsilon;
+        let mut noised = |secs: &mut i64| {
+            *secs = (*secs as f64 + laplace(rng, scale)).round().max(0.0) as i64;
+        };
+        for day in days.values_mut() {
+            noised(&mut day.active_secs);
+            noised(&mut day.idle_secs);
+            day.categories.values_mut().for_each(&mut noised);
+            day.activity_categories.values_mut().for_each(&mut noised);
+            day.windows.values_mut().for_each(&mut noised);
+        }
+    }
+
+    Ok(ActivityStats {
+        start,
+        end,
+        epsilon: options.epsilon,
+        days: days
+            .into_iter()
+            .map(|(date, day)| DayStats {
+                date: date.format("%Y-%m-%d").to_string(),
+                active_secs: day.active_secs,
+                idle_secs: day.idle_secs,
+                categories: longest_first(day.categories),
+                activity_categories: longest_first(day.activity_categories),
+                windows: longest_first(day.windows),
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Result};
+use screenpipe_core::find_ffmpeg_path;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::process::Command;
+use tracing::debug;
+
+/// Longest clip exported as a GIF or APNG unless set otherwise, longer ones are better off
+/// as mp4: an animation keeps every frame whole and grows with each of them.
+pub const DEFAULT_MAX_ANIMATED_CLIP_SECS: u64 = 30;
+
+/// Width animations are scaled down to unless asked otherwise, what chat apps show inline.
+pub const DEFAULT_ANIMATED_CLIP_WIDTH: u32 = 960;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipFormat {
+    #[default]
+    Mp4,
+    Gif,
+    Apng,
+}
+
+impl ClipFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ClipFormat::Mp4 => "mp4",
+            ClipFormat::Gif => "gif",
+            ClipFormat::Apng => "png",
+        }
+    }
+
+    pub fn is_animation(&self) -> bool {
+        !matches!(self, ClipFormat::Mp4)
+    }
+}
+
+static MAX_ANIMATED_CLIP_SECS: AtomicU64 = AtomicU64::new(DEFAULT_MAX_ANIMATED_CLIP_SECS);
+
+pub fn set_max_animated_clip_secs(secs: u64) {
+    MAX_ANIMATED_CLIP_SECS.store(secs, Ordering::SeqCst);
+}
+
+pub fn max_animated_clip_secs() -> u64 {
+    MAX_ANIMATED_CLIP_SECS.load(Ordering::SeqCst)
+}
+
+/// How long `frame_count` frames play at `fps`.
+pub fn clip_duration_secs(frame_count: usize, fps: f64) -> f64 {
+    frame_count as f64 / fps
+}
+
+/// Refuses clips of `frame_count` frames at `fps` longer than the animation limit.
+pub fn check_animated_clip_length(frame_count: usize, fps: f64) -> Result<()> {
+    if !(fps > 0.0) {
+        return Err(anyhow!("fps must be above 0, got {}", fps));
+    }
+    let duration = clip_duration_secs(frame_count, fps);
+    let max = max_animated_clip_secs();
+    if duration > max as f64 {
+        return Err(anyhow!(
+            "the clip plays for {:.0}s, animations are limited to {}s, export it as mp4",
+            duration,
+            max
+        ));
+    }
+    Ok(())
+}
+
+/// Filter graph scaling frames to `width` and mapping them to a palette computed from the
+/// whole clip, rather than ffmpeg's fixed one, which bands and dithers screen content badly.
+/// Only the pixels that change from frame to frame are dithered, keeping static text crisp.
+pub fn palette_filter(fps: f64, width: u32) -> String {
+    format!(
+        "fps={fps},scale={width}:-2:flags=lanczos,split[frames][palette_input];\
+         [palette_input]palettegen=stats_mode=diff[palette];\
+         [frames][palette]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+    )
+}
+
+/// Encodes the images at `frame_paths`, in order, into a looping animation at `output`.
+/// The images are moved into a numbered sequence next to the first one.
+pub async fn encode_animated_clip(
+    frame_paths: &[String],
+    output: &Path,
+    format: ClipFormat,
+    fps: f64,
+    width: u32,
+) -> Result<()> {
+    let first = frame_paths
+        .first()
+        .ok_or_else(|| anyhow!("no frames to encode"))?;
+    let dir = Path::new(first)
+        .parent()
+        .ok_or_else(|| anyhow!("frame {} has no directory", first))?
+        .join("sequence");
+    tokio::fs::create_dir_all(&dir).await?;
+    for (index, path) in frame_paths.iter().enumerate() {
+        tokio::fs::rename(path, dir.join(format!("frame_{:05}.png", index))).await?;
+    }
+
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let mut command = Command::new(ffmpeg_path);
+    command
+        .args(["-y", "-loglevel", "error", "-framerate", &fps.to_string()])
+        .arg("-i")
+        .arg(dir.join("frame_%05d.png"))
+        .args(["-vf", &palette_filter(fps, width)]);
+    match format {
+        ClipFormat::Gif => command.args(["-loop", "0"]),
+        ClipFormat::Apng => command.args(["-f", "apng", "-plays", "0"]),
+        ClipFormat::Mp4 => return Err(anyhow!("mp4 clips are not animations")),
+    };
+    command.arg(output);
+    debug!("encoding animated clip: {:?}", command);
+
+    let result = command.output().await?;
+    if !result.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed to encode the {}: {}",
+            format.extension(),
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+/// Highest CRF x264 and x265 accept, the lowest quality.
+pub const MAX_CRF: u8 = 51;
+
+/// How the chunks recorded while an app is focused are encoded, in place of what the
+/// recorder picks: an IDE kept sharp at full resolution and a low CRF, a video player or a
+/// game scaled down heavily. OCR still reads the frames as captured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureQuality {
+    /// Share of the captured resolution the chunk is encoded at, in (0, 1].
+    pub scale: f64,
+    /// CRF of the chunk, the one of its content kind when not set.
+    pub crf: Option<u8>,
+}
+
+impl CaptureQuality {
+    /// Size frames of `size` are encoded at, at least 2x2.
+    pub fn frame_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let scaled = |length: u32| ((length as f64 * self.scale).round() as u32).max(2);
+        (scaled(width), scaled(height))
+    }
+}
+
+/// Quality overrides per app, by lowercased app name pattern, the first match wins.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppQualities {
+    apps: Vec<(String, CaptureQuality)>,
+}
+
+impl AppQualities {
+    /// Overrides the quality while an app whose name contains `pattern` is focused.
+    pub fn with_app(mut self, pattern: &str, quality: CaptureQuality) -> Self {
+        self.apps.push((pattern.to_lowercase(), quality));
+        self
+    }
+
+    /// The quality while `app_name` is focused, `None` when the recorder picks it.
+    pub fn for_app(&self, app_name: Option<&str>) -> Option<CaptureQuality> {
+        let app_name = app_name?.to_lowercase();
+        self.apps
+            .iter()
+            .find(|(pattern, _)| app_name.contains(pattern.as_str()))
+            .map(|(_, quality)| *quality)
+    }
+}
+
+/// Parses an `APP=SCALE` or `APP=SCALE:CRF` override, as given to `--app-quality`.
+pub fn parse_app_quality(value: &str) -> Result<(String, CaptureQuality), String> {
+    let (app, quality) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected APP=SCALE[:CRF], got '{}'", value))?;
+    let (scale, crf) = match quality.split_once(':') {
+        Some((scale, crf)) => (scale, Some(crf)),
+        None => (quality, None),
+    };
+    let scale = scale
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid scale in '{}': {}", value, e))?;
+    if !(scale > 0.0 && scale <= 1.0) {
+        return Err(format!(
+            "scale must be above 0 and at most 1, got {}",
+            scale
+        ));
+    }
+    let crf = crf
+        .map(|crf| {
+            crf.trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|crf| *crf <= MAX_CRF)
+                .ok_or_else(|| format!("crf must be between 0 and {}, got '{}'", MAX_CRF, crf))
+        })
+        .transpose()?;
+    let app = app.trim();
+    if app.is_empty() {
+        return Err(format!("missing app name in '{}'", value));
+    }
+    Ok((app.to_string(), CaptureQuality { scale, crf }))
+}
+
+static APP_QUALITIES: Lazy<RwLock<AppQualities>> = Lazy::new(Default::default);
+
+/// Sets the overrides every monitor's encoder uses from its next chunk on.
+pub fn set_app_qualities(qualities: AppQualities) {
+    *APP_QUALITIES.write().unwrap() = qualities;
+}
+
+/// The quality override that applies while `app_name` is focused.
+pub fn app_quality_for(app_name: Option<&str>) -> Option<CaptureQuality> {
+    APP_QUALITIES.read().unwrap().for_app(app_name)
+}
@@ -0,0 +1,63 @@
+use axum::{
+    extract::Query,
+    http::{Method, Uri},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Client recorded for requests to an api that is not protected by a key.
+pub const LOCAL_CLIENT: &str = "local";
+/// Client recorded for requests that didn't present the key the api is protected by.
+pub const ANONYMOUS_CLIENT: &str = "anonymous";
+
+/// What a request reads of the recorded data, e.g. `search` or `export`, or `None` for the
+/// requests that don't return any, which are not worth an audit entry.
+pub fn classify_access(method: &Method, path: &str) -> Option<&'static str> {
+    let path = path.trim_end_matches('/');
+    let action = match (method.as_str(), path) {
+        ("GET", "/search") | ("GET", "/search/keyword") | ("GET", "/semantic-search") => "search",
+        ("GET", "/ui-events") => "ui_events",
+        ("GET", "/clipboard") => "clipboard",
+        ("GET", "/sessions") => "sessions",
+        ("GET", "/stream/frames") => "stream",
+        ("GET", "/frames/export")
+        | ("GET", "/activity/export")
+        | ("POST", "/experimental/frames/merge") => "export",
+        ("GET", "/screenshot") => "screenshot",
+        ("POST", "/summarize") => "summarize",
+        ("POST", "/raw_sql") => "raw_sql",
+        ("GET", path) if path.starts_with("/stream/mjpeg/") => "live",
+        ("GET", path) if path.starts_with("/mosaic/") => "mosaic",
+        ("GET", "/thumbnails") => "thumbnails",
+        ("GET", path) if path.starts_with("/thumbnails/") => "thumbnails",
+        ("GET", path) if path.starts_with("/frames/") => "frame",
+        _ => return None,
+    };
+    Some(action)
+}
+
+#[derive(Deserialize, Default)]
+struct TimeRange {
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// The time range named by the `start_time` and `end_time` query parameters of a request.
+pub fn requested_time_range(uri: &Uri) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let range = Query::<TimeRange>::try_from_uri(uri)
+        .map(|Query(range)| range)
+        .unwrap_or_default();
+    (range.start_time, range.end_time)
+}
+
+/// Who made a request with `api_key`: a fingerprint of the key, so that entries of the same key
+/// can be told apart from others without storing the key itself.
+pub fn client_fingerprint(api_key: &str) -> String {
+    let digest = Sha256::digest(api_key.as_bytes());
+    let hex = digest[..4]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("key:{}", hex)
+}
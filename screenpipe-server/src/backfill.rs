@@ -0,0 +1,394 @@
+use crate::categorization::categorize;
+use crate::import::{ocr_frame, store_ocr_text, video_dimensions};
+use crate::video_utils::get_video_metadata;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDateTime, TimeZone, Utc};
+use image::{DynamicImage, RgbImage};
+use screenpipe_core::{find_ffmpeg_path, Language};
+use screenpipe_db::{BackfillFrame, DatabaseManager};
+use screenpipe_vision::OcrEngine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+/// Start time in the names of recordings, in UTC: `monitor_1_2024-11-28T01-00-00Z.mp4`.
+const NAME_FORMAT: &str = "%Y-%m-%dT%H-%M-%SZ";
+
+/// Start time in the names of the first recordings, in local time:
+/// `monitor_1_2024-11-28_10-00-00.mp4`.
+const LEGACY_NAME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    /// Whether the frames are OCR'd, for their text to be searched. Without it they're only
+    /// on the timeline, with their app and window.
+    pub ocr: bool,
+    pub ocr_engine: Arc<OcrEngine>,
+    pub languages: Vec<Language>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BackfillSummary {
+    pub recordings_found: usize,
+    pub recordings_indexed: usize,
+    /// Recordings indexed by an earlier backfill, left as they are.
+    pub recordings_already_indexed: usize,
+    /// Recordings without a `.frames` file, from before frame times were written, whose frame
+    /// times are spread over the recording.
+    pub recordings_with_estimated_times: usize,
+    /// Videos that aren't recordings, or failed to be indexed, with why.
+    pub skipped: Vec<String>,
+    pub frames_indexed: u64,
+    pub text_chars: usize,
+}
+
+/// A recording of a monitor found in an output directory, with the files next to its video.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OldRecording {
+    pub video: PathBuf,
+    pub monitor_id: u32,
+    pub started_at: DateTime<Utc>,
+    /// Capture time of each frame of the video, one RFC 3339 time per line.
+    pub frame_times: Option<PathBuf>,
+    /// Focused app and window over time, as JSONL blocks.
+    pub activity_log: Option<PathBuf>,
+}
+
+/// A block of an activity log, of the fields the index has a place for.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ActivityBlock {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    #[serde(default)]
+    pub app_name: String,
+    #[serde(default)]
+    pub window_title: String,
+    /// `false` for the blocks of blocked apps, pauses and sleeps, nothing captured in them.
+    #[serde(default)]
+    pub is_captured: bool,
+}
+
+/// The `.session.json` next to a video, written by the recorders that wrote frame times.
+#[derive(Deserialize)]
+struct SessionFile {
+    monitor_id: u32,
+    started_at: DateTime<Utc>,
+}
+
+/// Monitor id and start time of the recording named `file_name`,
+/// `monitor_{id}_{start}.{extension}` with the start in UTC, or in local time in the names
+/// of the first recordings. The hour repeated when DST ends is taken as its first occurrence.
+pub fn parse_recording_name(file_name: &str) -> Option<(u32, DateTime<Utc>)> {
+    let rest = file_name.strip_prefix("monitor_")?;
+    let (id, rest) = rest.split_once('_')?;
+    let (stamp, _) = rest.split_once('.')?;
+    let started_at = match NaiveDateTime::parse_from_str(stamp, NAME_FORMAT) {
+        Ok(utc) => utc.and_utc(),
+        Err(_) => {
+            let local = NaiveDateTime::parse_from_str(stamp, LEGACY_NAME_FORMAT).ok()?;
+            match Local.from_local_datetime(&local) {
+                LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => at.with_timezone(&Utc),
+                LocalResult::None => return None,
+            }
+        }
+    };
+    Some((id.parse().ok()?, started_at))
+}
+
+/// `video` with `.mp4` replaced by `.{extension}`, how the files of a recording are named.
+fn sidecar(video: &Path, extension: &str) -> PathBuf {
+    let name = video
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = name.strip_suffix(".mp4").unwrap_or(&name);
+    video.with_file_name(format!("{}.{}", stem, extension))
+}
+
+/// The recording of `video`, from its session file or, without one, its name. `None` when the
+/// video isn't a recording.
+pub fn read_recording(video: &Path) -> Option<OldRecording> {
+    let session = std::fs::read_to_string(sidecar(video, "session.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<SessionFile>(&content).ok());
+    let (monitor_id, started_at) = match session {
+        Some(session) => (session.monitor_id, session.started_at),
+        None => parse_recording_name(&video.file_name()?.to_string_lossy())?,
+    };
+    let existing = |path: PathBuf| path.exists().then_some(path);
+    Some(OldRecording {
+        video: video.to_path_buf(),
+        monitor_id,
+        started_at,
+        frame_times: existing(sidecar(video, "frames")),
+        activity_log: existing(sidecar(video, "jsonl")),
+    })
+}
+
+/// The blocks of the activity log at `path`, without the lines that aren't blocks.
+pub fn read_activity_log(path: &Path) -> Result<Vec<ActivityBlock>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut blocks = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(block) => blocks.push(block),
+            Err(e) => warn!("skipping line {} of {}: {}", number + 1, path.display(), e),
+        }
+    }
+    Ok(blocks)
+}
+
+/// The block captured at `at`, whose app and window the frame then showed.
+pub fn activity_at(blocks: &[ActivityBlock], at: DateTime<Utc>) -> Option<&ActivityBlock> {
+    blocks
+        .iter()
+        .find(|block| block.is_captured && block.start_time <= at && at < block.end_time)
+}
+
+/// Times of `count` frames spread evenly from `start` to `end`, the first at `start`. Frames
+/// of unchanged screens weren't kept, so these are only an estimate of when they were.
+pub fn spread_frame_times(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    count: usize,
+) -> Vec<DateTime<Utc>> {
+    let span = (end - start).num_milliseconds().max(0) as f64;
+    (0..count)
+        .map(|i| start + Duration::milliseconds((span * i as f64 / count as f64).round() as i64))
+        .collect()
+}
+
+fn read_frame_times(path: &Path) -> Result<Vec<DateTime<Utc>>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            DateTime::parse_from_rfc3339(line.trim())
+                .map(|time| time.with_timezone(&Utc))
+                .with_context(|| format!("invalid frame time in {}", path.display()))
+        })
+        .collect()
+}
+
+/// The recordings in `dir` and its subdirectories, oldest first, and the videos that aren't
+/// recordings.
+pub fn find_recordings(dir: &Path) -> (Vec<OldRecording>, Vec<PathBuf>) {
+    let mut recordings = Vec::new();
+    let mut others = Vec::new();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "mp4") {
+            continue;
+        }
+        match read_recording(path) {
+            Some(recording) => recordings.push(recording),
+            None => others.push(path.to_path_buf()),
+        }
+    }
+    recordings.sort_by_key(|recording| (recording.started_at, recording.monitor_id));
+    (recordings, others)
+}
+
+/// The frames of `recording`, at their capture times when written, spread over the time its
+/// activity log, or else its video, covers when they weren't. Returns whether the times are
+/// estimated.
+async fn recording_frames(recording: &OldRecording) -> Result<(Vec<BackfillFrame>, bool)> {
+    let metadata = get_video_metadata(&recording.video.to_string_lossy()).await?;
+    // every kept frame is a frame of the video, encoded at the recorder's fps
+    let frame_count = (metadata.duration * metadata.fps).round().max(0.0) as usize;
+    let blocks = match &recording.activity_log {
+        Some(path) => read_activity_log(path)?,
+        None => Vec::new(),
+    };
+
+    let (mut times, estimated) = match &recording.frame_times {
+        Some(path) => (read_frame_times(path)?, false),
+        None => {
+            let end = blocks
+                .iter()
+                .map(|block| block.end_time)
+                .max()
+                .filter(|end| *end > recording.started_at)
+                .unwrap_or_else(|| {
+                    recording.started_at
+                        + Duration::milliseconds((metadata.duration * 1000.0) as i64)
+                });
+            (
+                spread_frame_times(recording.started_at, end, frame_count.max(1)),
+                true,
+            )
+        }
+    };
+    // the last frames written before a crash may not have made it into the video
+    if frame_count > 0 && times.len() > frame_count + 1 {
+        times.truncate(frame_count);
+    }
+
+    let frames = times
+        .into_iter()
+        .enumerate()
+        .map(|(index, timestamp)| {
+            let block = activity_at(&blocks, timestamp);
+            BackfillFrame {
+                offset_index: index as i64,
+                timestamp,
+                app_name: block.map(|block| block.app_name.clone()),
+                window_name: block.map(|block| block.window_title.clone()),
+                focused: true,
+                category: block
+                    .and_then(|block| categorize(&block.app_name, &block.window_title, None)),
+            }
+        })
+        .collect();
+    Ok((frames, estimated))
+}
+
+/// OCRs the frames of `video`, decoded in order, storing the text of each under its id in
+/// `frame_ids`. Returns the length of the text read.
+async fn ocr_recording(
+    db: &DatabaseManager,
+    ffmpeg_path: &Path,
+    video: &Path,
+    frames: &[BackfillFrame],
+    frame_ids: &[i64],
+    options: &BackfillOptions,
+) -> Result<usize> {
+    let (width, height) = video_dimensions(video).await?;
+    let mut decoder = Command::new(ffmpeg_path)
+        .args(["-v", "error", "-noautorotate", "-i"])
+        .arg(video)
+        .args([
+            "-fps_mode",
+            "passthrough",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+            "-",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start ffmpeg")?;
+    let mut output = decoder.stdout.take().expect("ffmpeg stdout is piped");
+
+    let mut buffer = vec![0u8; width as usize * height as usize * 3];
+    let mut text_chars = 0;
+    for (index, (frame, frame_id)) in frames.iter().zip(frame_ids).enumerate() {
+        match output.read_exact(&mut buffer).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let image = DynamicImage::ImageRgb8(
+            RgbImage::from_raw(width, height, buffer.clone())
+                .ok_or_else(|| anyhow!("frame {} is truncated", index))?,
+        );
+        let window_name = frame.window_name.as_deref().unwrap_or_default();
+        if let Some(ocr) = ocr_frame(
+            image,
+            window_name,
+            index as u64,
+            &options.ocr_engine,
+            &options.languages,
+        )
+        .await?
+        {
+            text_chars += store_ocr_text(db, *frame_id, ocr, &options.ocr_engine).await?;
+        }
+    }
+    let _ = decoder.wait().await;
+    Ok(text_chars)
+}
+
+/// Indexes the recordings in the output directory `dir` of older recorders, the
+/// `monitor_{id}_{start}.mp4` videos and the activity logs and frame times next to them, so
+/// they're on the timeline and, OCR'd, searched like those recorded since. The videos are
+/// indexed where they are, under the devices `monitor_{id}`. Videos indexed already are
+/// skipped, so the backfill can run again as more are found.
+pub async fn backfill_output_dir(
+    db: &DatabaseManager,
+    dir: &Path,
+    options: &BackfillOptions,
+) -> Result<BackfillSummary> {
+    if !dir.is_dir() {
+        return Err(anyhow!("{} isn't a directory", dir.display()));
+    }
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let dir = dir.canonicalize()?;
+    let (recordings, others) = find_recordings(&dir);
+    let mut summary = BackfillSummary {
+        recordings_found: recordings.len(),
+        skipped: others
+            .iter()
+            .map(|path| format!("{}: not a recording", path.display()))
+            .collect(),
+        ..Default::default()
+    };
+    info!(
+        "backfilling {} recordings from {}",
+        recordings.len(),
+        dir.display()
+    );
+
+    for recording in &recordings {
+        let video = recording.video.to_string_lossy().to_string();
+        let (frames, estimated) = match recording_frames(recording).await {
+            Ok(frames) => frames,
+            Err(e) => {
+                warn!("skipping {}: {}", video, e);
+                summary.skipped.push(format!("{}: {}", video, e));
+                continue;
+            }
+        };
+        let device_name = format!("monitor_{}", recording.monitor_id);
+        let Some(frame_ids) = db
+            .insert_backfilled_chunk(&video, &device_name, &frames)
+            .await?
+        else {
+            debug!("{} is indexed already", video);
+            summary.recordings_already_indexed += 1;
+            continue;
+        };
+        summary.recordings_indexed += 1;
+        summary.frames_indexed += frame_ids.len() as u64;
+        if estimated {
+            summary.recordings_with_estimated_times += 1;
+        }
+
+        if options.ocr {
+            match ocr_recording(
+                db,
+                &ffmpeg_path,
+                &recording.video,
+                &frames,
+                &frame_ids,
+                options,
+            )
+            .await
+            {
+                Ok(text_chars) => summary.text_chars += text_chars,
+                // the frames stay on the timeline
+                Err(e) => warn!("failed to ocr {}: {}", video, e),
+            }
+        }
+        info!(
+            "backfilled {}: {} frames from {}",
+            video,
+            frame_ids.len(),
+            recording.started_at
+        );
+    }
+    Ok(summary)
+}
@@ -0,0 +1,54 @@
+use crossbeam::queue::ArrayQueue;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+/// What the recorder does with a captured frame when the queue of the encoder or the indexer
+/// is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    /// Drop the oldest queued frame to make room, capture keeps its pace and a slow encoder
+    /// loses frames.
+    #[default]
+    DropOldest,
+    /// Wait for room, no frame is lost and capture slows down to the pace of the slowest of
+    /// the encoder and the indexer.
+    Block,
+}
+
+static BACKPRESSURE_POLICY: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_backpressure_policy(policy: BackpressurePolicy) {
+    BACKPRESSURE_POLICY.store(policy as u8, Ordering::SeqCst);
+}
+
+pub fn backpressure_policy() -> BackpressurePolicy {
+    match BACKPRESSURE_POLICY.load(Ordering::SeqCst) {
+        1 => BackpressurePolicy::Block,
+        _ => BackpressurePolicy::DropOldest,
+    }
+}
+
+/// How often a blocked frame checks for room in its queue.
+const BLOCKED_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Pushes `item` to `queue` following `policy`, returns how many queued frames were dropped
+/// to make room for it.
+pub async fn enqueue<T>(queue: &ArrayQueue<T>, mut item: T, policy: BackpressurePolicy) -> u64 {
+    let mut dropped = 0;
+    loop {
+        match queue.push(item) {
+            Ok(()) => return dropped,
+            Err(rejected) => {
+                item = rejected;
+                match policy {
+                    BackpressurePolicy::DropOldest => {
+                        if queue.pop().is_some() {
+                            dropped += 1;
+                        }
+                    }
+                    BackpressurePolicy::Block => tokio::time::sleep(BLOCKED_POLL_INTERVAL).await,
+                }
+            }
+        }
+    }
+}
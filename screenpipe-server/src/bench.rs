@@ -0,0 +1,654 @@
+use crate::import::ocr_frame;
+use crate::video::{chunk_video_filter, MAX_FPS};
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, ImageFormat};
+use screenpipe_core::{find_ffmpeg_path, Language};
+use screenpipe_vision::capture_backend::{render_synthetic_frame, SyntheticFrame};
+use screenpipe_vision::capture_scope::{focused_window_only, set_focused_window_only};
+use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
+use screenpipe_vision::monitor::{list_monitors, SafeMonitor};
+use screenpipe_vision::utils::{
+    calculate_hash, capture_screenshot, compare_images_histogram, compare_images_ssim,
+};
+use screenpipe_vision::OcrEngine;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Codecs and presets the encode throughput is measured for, screenpipe's own first: x265
+/// ultrafast records, x264 ultrafast is what low power mode switches to.
+pub const ENCODE_SETTINGS: &[(&str, &str)] = &[
+    ("libx265", "ultrafast"),
+    ("libx264", "ultrafast"),
+    ("libx265", "veryfast"),
+    ("libx264", "veryfast"),
+    ("libx265", "medium"),
+    ("libx264", "medium"),
+];
+
+/// Share of the time between two frames the recommended fps leaves to recording, the rest to
+/// whatever the machine is used for.
+pub const RECORDING_TIME_SHARE: f64 = 0.5;
+
+/// Frame rates the recommendation is rounded down to.
+const FPS_STEPS: &[f64] = &[0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, MAX_FPS];
+
+/// Size of the synthetic frames measured when no monitor can be captured.
+const SYNTHETIC_SIZE: (u32, u32) = (1920, 1080);
+
+/// Capture p95 past which the whole monitor with its windows is deemed too slow to capture,
+/// and the focused window alone is recommended.
+const SLOW_CAPTURE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Captures and diffs measured per monitor, method and algorithm.
+    pub samples: usize,
+    /// Frames encoded per codec and preset.
+    pub encode_frames: usize,
+    pub skip_encode: bool,
+    /// The engine and languages frames are read with, as recording would.
+    pub ocr_engine: OcrEngine,
+    pub languages: Vec<Language>,
+}
+
+/// How long an operation took over its samples.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Timing {
+    pub samples: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Timing {
+    /// `None` without any sample.
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        ms.sort_by(f64::total_cmp);
+        // nearest rank
+        let percentile =
+            |p: f64| ms[((p * ms.len() as f64).ceil() as usize).clamp(1, ms.len()) - 1];
+        Some(Self {
+            samples: ms.len(),
+            mean_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: ms[ms.len() - 1],
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureMethod {
+    /// The monitor's image alone.
+    Monitor,
+    /// The monitor and its focused windows, what screenpipe records by default.
+    MonitorAndWindows,
+    /// The focused window alone, as with --focused-window-only.
+    FocusedWindow,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureBench {
+    pub monitor_id: u32,
+    pub width: u32,
+    pub height: u32,
+    pub method: CaptureMethod,
+    /// `None` when every capture failed.
+    pub timing: Option<Timing>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffAlgorithm {
+    /// Hash of the frame, telling identical frames apart.
+    Hash,
+    Histogram,
+    Ssim,
+    /// The mean of the histogram and SSIM differences, what screenpipe compares frames with.
+    HistogramSsim,
+}
+
+pub const DIFF_ALGORITHMS: [DiffAlgorithm; 4] = [
+    DiffAlgorithm::Hash,
+    DiffAlgorithm::Histogram,
+    DiffAlgorithm::Ssim,
+    DiffAlgorithm::HistogramSsim,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffBench {
+    pub algorithm: DiffAlgorithm,
+    pub width: u32,
+    pub height: u32,
+    pub timing: Timing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OcrBench {
+    pub engine: String,
+    pub width: u32,
+    pub height: u32,
+    /// `None` when every read failed.
+    pub timing: Option<Timing>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EncodeBench {
+    pub codec: String,
+    pub preset: String,
+    pub width: u32,
+    pub height: u32,
+    pub frames: usize,
+    /// Frames encoded per second, `None` when the encoder failed.
+    pub frames_per_second: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Settings the machine can record with, leaving it [`RECORDING_TIME_SHARE`] of the time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecommendedProfile {
+    pub fps: f64,
+    pub focused_window_only: bool,
+    /// Capturing, comparing, reading and encoding a frame of every monitor, in milliseconds.
+    pub frame_cost_ms: f64,
+    /// The flags to start screenpipe with.
+    pub command: String,
+    /// Why, one line each.
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchReport {
+    pub monitors: usize,
+    pub capture: Vec<CaptureBench>,
+    pub diff: Vec<DiffBench>,
+    pub ocr: OcrBench,
+    pub encode: Vec<EncodeBench>,
+    pub recommended: RecommendedProfile,
+}
+
+async fn capture_once(monitor: &SafeMonitor, method: CaptureMethod) -> Result<DynamicImage> {
+    let filters = WindowFilters::new(&[], &[]);
+    match method {
+        CaptureMethod::Monitor => monitor.capture_image().await,
+        CaptureMethod::MonitorAndWindows | CaptureMethod::FocusedWindow => {
+            let (image, ..) = capture_screenshot(monitor, &filters, false).await?;
+            Ok(image)
+        }
+    }
+}
+
+/// Times `samples` captures of `monitor` by `method`, keeping the images for the diff and
+/// encode benchmarks.
+async fn bench_capture(
+    monitor: &SafeMonitor,
+    method: CaptureMethod,
+    samples: usize,
+    images: &mut Vec<DynamicImage>,
+) -> CaptureBench {
+    let was_focused_only = focused_window_only();
+    set_focused_window_only(method == CaptureMethod::FocusedWindow);
+    let mut durations = Vec::new();
+    let mut error = None;
+    for _ in 0..samples {
+        let start = Instant::now();
+        match capture_once(monitor, method).await {
+            Ok(image) => {
+                durations.push(start.elapsed());
+                if method == CaptureMethod::Monitor {
+                    images.push(image);
+                }
+            }
+            Err(e) => error = Some(e.to_string()),
+        }
+    }
+    set_focused_window_only(was_focused_only);
+    CaptureBench {
+        monitor_id: monitor.id(),
+        width: monitor.width(),
+        height: monitor.height(),
+        method,
+        timing: Timing::from_samples(&durations),
+        error: if durations.is_empty() { error } else { None },
+    }
+}
+
+/// Times `algorithm` comparing `samples` consecutive pairs of `frames`, cycling through them.
+pub fn bench_diff(
+    frames: &[DynamicImage],
+    algorithm: DiffAlgorithm,
+    samples: usize,
+) -> Result<DiffBench> {
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("no frame to compare"))?;
+    let mut durations = Vec::with_capacity(samples);
+    for index in 0..samples {
+        let previous = &frames[index % frames.len()];
+        let current = &frames[(index + 1) % frames.len()];
+        let start = Instant::now();
+        match algorithm {
+            DiffAlgorithm::Hash => {
+                std::hint::black_box(calculate_hash(current));
+            }
+            DiffAlgorithm::Histogram => {
+                std::hint::black_box(compare_images_histogram(previous, current)?);
+            }
+            DiffAlgorithm::Ssim => {
+                std::hint::black_box(compare_images_ssim(previous, current));
+            }
+            DiffAlgorithm::HistogramSsim => {
+                let histogram = compare_images_histogram(previous, current)?;
+                let ssim = 1.0 - compare_images_ssim(previous, current);
+                std::hint::black_box((histogram + ssim) / 2.0);
+            }
+        }
+        durations.push(start.elapsed());
+    }
+    Ok(DiffBench {
+        algorithm,
+        width: first.width(),
+        height: first.height(),
+        timing: Timing::from_samples(&durations).ok_or_else(|| anyhow!("no sample"))?,
+    })
+}
+
+/// Times reading the text of `samples` of `frames`, cycling through them, the way capture
+/// reads a frame.
+async fn bench_ocr(
+    frames: &[DynamicImage],
+    ocr_engine: &OcrEngine,
+    languages: &[Language],
+    samples: usize,
+) -> Result<OcrBench> {
+    let first = frames.first().ok_or_else(|| anyhow!("no frame to read"))?;
+    let mut durations = Vec::with_capacity(samples);
+    let mut error = None;
+    for index in 0..samples {
+        let frame = frames[index % frames.len()].clone();
+        let start = Instant::now();
+        match ocr_frame(frame, "", index as u64, ocr_engine, languages).await {
+            Ok(_) => durations.push(start.elapsed()),
+            Err(e) => error = Some(e.to_string()),
+        }
+    }
+    Ok(OcrBench {
+        engine: format!("{:?}", ocr_engine),
+        width: first.width(),
+        height: first.height(),
+        timing: Timing::from_samples(&durations),
+        error: if durations.is_empty() { error } else { None },
+    })
+}
+
+/// Encodes `frames` with ffmpeg as the chunk encoder does, but with `codec` and `preset`, and
+/// returns the frames encoded per second, PNG decoding included.
+async fn encode_throughput(
+    frames: &[Vec<u8>],
+    size: (u32, u32),
+    codec: &str,
+    preset: &str,
+) -> Result<f64> {
+    let ffmpeg = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let dir = tempfile::tempdir()?;
+    let output = dir.path().join("bench.mp4");
+    let filter = chunk_video_filter(Some(size));
+
+    let start = Instant::now();
+    let mut child = Command::new(ffmpeg)
+        .args([
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-f",
+            "image2pipe",
+            "-vcodec",
+            "png",
+            "-r",
+            "1",
+            "-i",
+            "-",
+            "-vf",
+        ])
+        .arg(&filter)
+        .args([
+            "-vcodec", codec, "-preset", preset, "-crf", "23", "-pix_fmt", "yuv420p", "-y",
+        ])
+        .arg(&output)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("no ffmpeg stdin"))?;
+    for frame in frames {
+        stdin.write_all(frame).await?;
+    }
+    drop(stdin);
+    let result = child.wait_with_output().await?;
+    if !result.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(frames.len() as f64 / start.elapsed().as_secs_f64())
+}
+
+async fn bench_encode(frames: &[DynamicImage], count: usize) -> Result<Vec<EncodeBench>> {
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("no frame to encode"))?;
+    let size = (first.width(), first.height());
+    let mut encoded = Vec::with_capacity(count);
+    for index in 0..count {
+        let mut png = Vec::new();
+        frames[index % frames.len()].write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        encoded.push(png);
+    }
+
+    let mut benches = Vec::new();
+    for (codec, preset) in ENCODE_SETTINGS {
+        let result = encode_throughput(&encoded, size, codec, preset).await;
+        benches.push(EncodeBench {
+            codec: codec.to_string(),
+            preset: preset.to_string(),
+            width: size.0,
+            height: size.1,
+            frames: count,
+            frames_per_second: result.as_ref().ok().copied(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+    Ok(benches)
+}
+
+/// Frames that change from one to the next, for a machine whose monitors can't be captured.
+fn synthetic_frames(count: usize) -> Vec<DynamicImage> {
+    (0..count.max(2) as u64)
+        .filter_map(|index| {
+            render_synthetic_frame(
+                SyntheticFrame::Gradient {
+                    offset: index as u32 * 40,
+                },
+                index,
+                SYNTHETIC_SIZE.0,
+                SYNTHETIC_SIZE.1,
+            )
+        })
+        .collect()
+}
+
+fn worst_p95(capture: &[CaptureBench], method: CaptureMethod, monitor_id: u32) -> Option<f64> {
+    capture
+        .iter()
+        .filter(|bench| bench.method == method && bench.monitor_id == monitor_id)
+        .filter_map(|bench| bench.timing.as_ref().map(|timing| timing.p95_ms))
+        .reduce(f64::max)
+}
+
+/// The fps and capture mode the measurements allow: the p95 of capturing every monitor, of
+/// comparing and reading its frame and the time x265 ultrafast takes to encode it must fit in
+/// [`RECORDING_TIME_SHARE`] of the time between two frames.
+pub fn recommend_profile(
+    monitors: usize,
+    capture: &[CaptureBench],
+    diff: &[DiffBench],
+    ocr: &OcrBench,
+    encode: &[EncodeBench],
+) -> RecommendedProfile {
+    let mut notes = Vec::new();
+    let mut monitor_ids: Vec<u32> = capture.iter().map(|bench| bench.monitor_id).collect();
+    monitor_ids.dedup();
+
+    let mut focused_window_only = false;
+    let mut capture_ms = 0.0;
+    for monitor_id in &monitor_ids {
+        let full = worst_p95(capture, CaptureMethod::MonitorAndWindows, *monitor_id);
+        let focused = worst_p95(capture, CaptureMethod::FocusedWindow, *monitor_id);
+        match (full, focused) {
+            (Some(full), Some(focused))
+                if full > SLOW_CAPTURE.as_secs_f64() * 1000.0 && focused < full =>
+            {
+                notes.push(format!(
+                    "capturing monitor {} with its windows takes {:.0} ms, its focused window {:.0} ms",
+                    monitor_id, full, focused
+                ));
+                focused_window_only = true;
+            }
+            (None, Some(_)) => {
+                notes.push(format!(
+                    "monitor {} can only be captured by its focused window",
+                    monitor_id
+                ));
+                focused_window_only = true;
+            }
+            _ => {}
+        }
+    }
+    for monitor_id in &monitor_ids {
+        let method = if focused_window_only {
+            CaptureMethod::FocusedWindow
+        } else {
+            CaptureMethod::MonitorAndWindows
+        };
+        match worst_p95(capture, method, *monitor_id) {
+            Some(ms) => capture_ms += ms,
+            None => notes.push(format!("monitor {} couldn't be captured", monitor_id)),
+        }
+    }
+    if monitor_ids.is_empty() {
+        notes.push("no monitor could be captured, only diff and encode were measured".to_string());
+    }
+
+    let frames = monitors.max(1) as f64;
+    let diff_ms = diff
+        .iter()
+        .find(|bench| bench.algorithm == DiffAlgorithm::HistogramSsim)
+        .map_or(0.0, |bench| bench.timing.p95_ms)
+        * frames;
+    let ocr_ms = match &ocr.timing {
+        Some(timing) => timing.p95_ms * frames,
+        None => {
+            notes.push(format!(
+                "{} ocr failed, reading frames isn't accounted for",
+                ocr.engine
+            ));
+            0.0
+        }
+    };
+    let encode_fps = encode
+        .iter()
+        .find(|bench| bench.codec == ENCODE_SETTINGS[0].0 && bench.preset == ENCODE_SETTINGS[0].1)
+        .and_then(|bench| bench.frames_per_second);
+    let encode_ms = match encode_fps {
+        Some(fps) if fps > 0.0 => 1000.0 / fps * frames,
+        _ => {
+            if !encode.is_empty() {
+                notes.push("x265 ultrafast failed, encoding isn't accounted for".to_string());
+            }
+            0.0
+        }
+    };
+
+    let frame_cost_ms = capture_ms + diff_ms + ocr_ms + encode_ms;
+    let affordable = if frame_cost_ms > 0.0 {
+        RECORDING_TIME_SHARE * 1000.0 / frame_cost_ms
+    } else {
+        MAX_FPS
+    };
+    let fps = FPS_STEPS
+        .iter()
+        .copied()
+        .rev()
+        .find(|step| *step <= affordable)
+        .unwrap_or(FPS_STEPS[0]);
+    if affordable < FPS_STEPS[0] {
+        notes.push(format!(
+            "a frame costs {:.0} ms, even {} fps takes more than {:.0}% of the time",
+            frame_cost_ms,
+            FPS_STEPS[0],
+            RECORDING_TIME_SHARE * 100.0
+        ));
+    }
+    notes.push(format!(
+        "{:.0} ms capture, {:.0} ms diff, {:.0} ms ocr, {:.0} ms encode per frame of {} monitor(s)",
+        capture_ms, diff_ms, ocr_ms, encode_ms, monitors
+    ));
+
+    let mut command = format!("screenpipe --fps {}", fps);
+    if focused_window_only {
+        command.push_str(" --focused-window-only");
+    }
+    RecommendedProfile {
+        fps,
+        focused_window_only,
+        frame_cost_ms,
+        command,
+        notes,
+    }
+}
+
+/// Measures capture latency per monitor and method, diff cost per algorithm, OCR cost and
+/// encode throughput per codec and preset on this machine.
+pub async fn run_bench(options: &BenchOptions) -> Result<BenchReport> {
+    let samples = options.samples.max(1);
+    let monitors = list_monitors().await;
+
+    let mut capture = Vec::new();
+    let mut frames = Vec::new();
+    for monitor in &monitors {
+        let mut images = Vec::new();
+        for method in [
+            CaptureMethod::Monitor,
+            CaptureMethod::MonitorAndWindows,
+            CaptureMethod::FocusedWindow,
+        ] {
+            capture.push(bench_capture(monitor, method, samples, &mut images).await);
+        }
+        // the frames of the first monitor captured stand for the others
+        if frames.is_empty() {
+            frames = images;
+        }
+    }
+    if frames.len() < 2 {
+        frames = synthetic_frames(samples);
+    }
+
+    let diff = DIFF_ALGORITHMS
+        .iter()
+        .map(|algorithm| bench_diff(&frames, *algorithm, samples))
+        .collect::<Result<Vec<_>>>()?;
+    let ocr = bench_ocr(&frames, &options.ocr_engine, &options.languages, samples).await?;
+    let encode = if options.skip_encode {
+        Vec::new()
+    } else {
+        bench_encode(&frames, options.encode_frames.max(1)).await?
+    };
+
+    let recommended = recommend_profile(monitors.len(), &capture, &diff, &ocr, &encode);
+    Ok(BenchReport {
+        monitors: monitors.len(),
+        capture,
+        diff,
+        ocr,
+        encode,
+        recommended,
+    })
+}
+
+fn timing_row(timing: &Timing) -> String {
+    format!(
+        "p50 {:>7.1} ms   p95 {:>7.1} ms   max {:>7.1} ms",
+        timing.p50_ms, timing.p95_ms, timing.max_ms
+    )
+}
+
+/// The report as printed by `screenpipe bench`.
+pub fn format_report(report: &BenchReport) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "capture latency");
+    for bench in &report.capture {
+        let label = format!(
+            "  monitor {} {}x{} {:?}",
+            bench.monitor_id, bench.width, bench.height, bench.method
+        );
+        match (&bench.timing, &bench.error) {
+            (Some(timing), _) => {
+                let _ = writeln!(out, "{:<48}{}", label, timing_row(timing));
+            }
+            (None, error) => {
+                let _ = writeln!(
+                    out,
+                    "{:<48}failed: {}",
+                    label,
+                    error.as_deref().unwrap_or("unknown error")
+                );
+            }
+        }
+    }
+    let _ = writeln!(out, "\ndiff cost");
+    for bench in &report.diff {
+        let label = format!("  {:?} {}x{}", bench.algorithm, bench.width, bench.height);
+        let _ = writeln!(out, "{:<48}{}", label, timing_row(&bench.timing));
+    }
+    let _ = writeln!(out, "\nocr cost");
+    let ocr = &report.ocr;
+    let label = format!("  {} {}x{}", ocr.engine, ocr.width, ocr.height);
+    match (&ocr.timing, &ocr.error) {
+        (Some(timing), _) => {
+            let _ = writeln!(out, "{:<48}{}", label, timing_row(timing));
+        }
+        (None, error) => {
+            let _ = writeln!(
+                out,
+                "{:<48}failed: {}",
+                label,
+                error.as_deref().unwrap_or("unknown error")
+            );
+        }
+    }
+    if !report.encode.is_empty() {
+        let _ = writeln!(out, "\nencode throughput");
+        for bench in &report.encode {
+            let label = format!(
+                "  {} {} {}x{}",
+                bench.codec, bench.preset, bench.width, bench.height
+            );
+            match (bench.frames_per_second, &bench.error) {
+                (Some(fps), _) => {
+                    let _ = writeln!(out, "{:<48}{:>7.1} frames/s", label, fps);
+                }
+                (None, error) => {
+                    let _ = writeln!(
+                        out,
+                        "{:<48}failed: {}",
+                        label,
+                        error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+            }
+        }
+    }
+    let recommended = &report.recommended;
+    let _ = writeln!(out, "\nrecommended: {}", recommended.command);
+    for note in &recommended.notes {
+        let _ = writeln!(out, "  {}", note);
+    }
+    out
+}
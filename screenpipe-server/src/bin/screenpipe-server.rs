@@ -10,20 +10,75 @@ use screenpipe_audio::{
     core::device::{
         default_input_device, default_output_device, list_audio_devices, parse_audio_device,
     },
+    transcription::deferred::TRANSCRIBE_AUDIO_CHUNK_JOB,
 };
+use screenpipe_core::capture_policy::{set_capture_policy, CapturePolicy};
 use screenpipe_core::find_ffmpeg_path;
 use screenpipe_db::{
     create_migration_worker, DatabaseManager, MigrationCommand, MigrationConfig, MigrationStatus,
 };
 use screenpipe_server::{
+    accessibility::record_accessibility_text,
+    activity_stats::TitleMode,
+    animated_clip::set_max_animated_clip_secs,
+    backfill::{backfill_output_dir, BackfillOptions},
+    backpressure::set_backpressure_policy,
+    bench::{format_report, run_bench, BenchOptions},
+    categorization::{CategoriesConfig, CATEGORIES_CONFIG_FILE},
+    chunk_rotation::set_adaptive_chunks,
     cli::{
-        AudioCommand, Cli, CliAudioTranscriptionEngine, CliOcrEngine, Command, MigrationSubCommand,
-        OutputFormat, PipeCommand, VisionCommand, McpCommand,
+        AudioCommand, Cli, CliAudioTranscriptionEngine, CliBackpressurePolicy,
+        CliEmbeddingProvider, CliOcrEngine, Command, EvidenceCommand, McpCommand,
+        MigrationSubCommand, OutputFormat, PipeCommand, VisionCommand,
     },
+    clipboard::record_clipboard,
+    content_quality::set_dynamic_crf,
+    dedup::set_skip_duplicate_frames,
+    deferred_ocr::{queue_last_chunks, set_deferred_ocr, DeferredOcr},
+    evidence::{
+        encode_public_key, load_or_create_signing_key, set_evidence_config, verify_chain,
+        EvidenceConfig,
+    },
+    excluded_regions::{ExcludedRegionsConfig, EXCLUDED_REGIONS_CONFIG_FILE},
+    grpc::GrpcControl,
     handle_index_command,
+    hot_reload::{
+        watch_configs, RecordingConfig, RecordingSettings, WatchedConfig, RECORDING_CONFIG_FILE,
+    },
+    hub::{push_to_hub, set_hub, HubConfig, HUB_CURSOR_FILE},
+    import::{import_recording, ImportOptions},
+    jobs::{JobQueue, JobQueueConfig},
+    meeting_mode::{MeetingMode, MeetingProfile},
+    mosaic::schedule_nightly_mosaics,
+    ocr_language::{queue_ocr_language_backfill, set_ocr_languages},
     pipe_manager::PipeInfo,
-    start_continuous_recording, watch_pid, PipeManager, ResourceMonitor, SCServer,
+    plugins::PluginHost,
+    power::{monitor_power, PowerPolicy},
+    pre_roll::set_pre_roll,
+    recording_format::{recording_format, set_recording_format},
+    resource_guard::{guard_resources, ResourceLimits},
+    scheduled_export::{schedule_exports, ExportConfig, DEFAULT_EXPORT_CONFIG_FILE},
+    schema::archive_schema,
+    search_query::{format_search_result, parse_search_query},
+    secrets::{apply_stored_secrets, handle_secrets_command, resolve_secret, KeychainStore},
+    sensitive_content::{SensitiveContentConfig, SENSITIVE_CONTENT_CONFIG_FILE},
+    service::handle_service_command,
+    sessions::{SessionSplitConfig, SessionTracker},
+    start_continuous_recording,
+    subtitles::write_remaining_sidecars,
+    summarize::{parse_time_arg, set_llm_backend, LlmBackend, Summary},
+    tail::tail_events,
+    text_embeds::{schedule_embedding_jobs, set_embedding_provider, EmbeddingProvider},
+    time_tracking::TimeTrackingFormat,
+    timelapse::{parse_time_of_day, schedule_timelapses},
+    ui_events::record_ui_events,
+    upload::{set_sync_config, SyncConfig, SyncTarget, DEFAULT_SYNC_CONFIG_FILE},
+    video_utils::get_video_metadata,
+    watch_pid, ContentItem, PaginatedResponse, PipeManager, ResourceMonitor, SCServer, Storage,
 };
+use screenpipe_vision::capture_scope::set_focused_window_only;
+use screenpipe_vision::focus_trigger::set_focus_trigger;
+use screenpipe_vision::incremental_ocr::set_incremental_ocr;
 use screenpipe_vision::monitor::list_monitors;
 #[cfg(target_os = "macos")]
 use screenpipe_vision::run_ui;
@@ -171,7 +226,33 @@ fn setup_logging(local_data_dir: &PathBuf, cli: &Cli) -> anyhow::Result<WorkerGu
 #[tracing::instrument]
 async fn main() -> anyhow::Result<()> {
     debug!("starting screenpipe server");
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    // credentials not given as flags are read from the keychain, except to manage them
+    if !matches!(cli.command, Some(Command::Secrets { .. })) {
+        apply_stored_secrets(&mut cli, &KeychainStore);
+    }
+
+    if cli.headless {
+        if cli.api_key.is_none() {
+            eprintln!("--headless exposes your screen over the network, set --api-key (or SCREENPIPE_API_KEY) to use it");
+            return Err(anyhow::anyhow!("--headless requires --api-key"));
+        }
+        // on-demand screenshots only, nothing is recorded
+        cli.disable_vision = true;
+        cli.disable_audio = true;
+    }
+
+    // deleted when dropped at the end of main, along with everything recorded
+    let demo_dir = if cli.demo {
+        let dir = tempfile::Builder::new()
+            .prefix("screenpipe-demo-")
+            .tempdir()?;
+        cli.data_dir = Some(dir.path().to_string_lossy().into_owned());
+        cli.apply_demo_privacy();
+        Some(dir)
+    } else {
+        None
+    };
 
     // Initialize Sentry only if telemetry is enabled
     let _sentry_guard = if !cli.disable_telemetry {
@@ -225,6 +306,25 @@ async fn main() -> anyhow::Result<()> {
             output: OutputFormat::Text,
             ..
         }) => true,
+        Some(Command::Import {
+            output: OutputFormat::Json,
+            ..
+        }) => false,
+        Some(Command::Backfill {
+            output: OutputFormat::Json,
+            ..
+        }) => false,
+        // the terminal is for the events
+        Some(Command::Tail { .. }) => false,
+        Some(Command::Search { .. }) => false,
+        Some(Command::Service { .. }) => false,
+        Some(Command::Secrets { .. }) => false,
+        Some(Command::Evidence { .. }) => false,
+        Some(Command::Schema) => false,
+        // log lines would interleave with the report
+        Some(Command::Bench { .. }) => false,
+        Some(Command::ExportActivity { output: None, .. }) => false,
+        Some(Command::ExportStats { output: None, .. }) => false,
         _ => true,
     };
 
@@ -546,10 +646,427 @@ async fn main() -> anyhow::Result<()> {
                 .await?;
                 return Ok(());
             }
+            Command::Import {
+                path,
+                start,
+                data_dir,
+                fps,
+                diff_threshold,
+                device_name,
+                ocr_engine,
+                language,
+                output,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let start_time = match start {
+                    Some(start) => parse_time_arg(start, chrono::Local::now().date_naive())?,
+                    None => {
+                        get_video_metadata(&path.to_string_lossy())
+                            .await?
+                            .creation_time
+                    }
+                };
+                let languages = if language.is_empty() {
+                    cli.unique_languages().unwrap_or_default()
+                } else {
+                    language.clone()
+                };
+                set_ocr_languages(languages.clone());
+
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let options = ImportOptions {
+                    start_time,
+                    device_name: device_name.clone(),
+                    fps: *fps,
+                    diff_threshold: *diff_threshold,
+                    ocr_engine: Arc::new(
+                        ocr_engine.clone().unwrap_or(cli.ocr_engine.clone()).into(),
+                    ),
+                    languages,
+                };
+                let summary = import_recording(
+                    &db,
+                    &Storage::new(local_data_dir.join("data")),
+                    path,
+                    &options,
+                )
+                .await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+                    OutputFormat::Text => println!(
+                        "imported {} from {}: {} of {} frames indexed, {} characters of text",
+                        path.display(),
+                        start_time
+                            .with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M:%S"),
+                        summary.frames_indexed,
+                        summary.frames_decoded,
+                        summary.text_chars
+                    ),
+                }
+                return Ok(());
+            }
+            Command::Backfill {
+                path,
+                data_dir,
+                skip_ocr,
+                ocr_engine,
+                language,
+                output,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let languages = if language.is_empty() {
+                    cli.unique_languages().unwrap_or_default()
+                } else {
+                    language.clone()
+                };
+                set_ocr_languages(languages.clone());
+
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let options = BackfillOptions {
+                    ocr: !*skip_ocr,
+                    ocr_engine: Arc::new(
+                        ocr_engine.clone().unwrap_or(cli.ocr_engine.clone()).into(),
+                    ),
+                    languages,
+                };
+                let summary = backfill_output_dir(&db, path, &options).await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+                    OutputFormat::Text => {
+                        println!(
+                            "backfilled {} of {} recordings from {}: {} frames indexed, {} characters of text",
+                            summary.recordings_indexed,
+                            summary.recordings_found,
+                            path.display(),
+                            summary.frames_indexed,
+                            summary.text_chars
+                        );
+                        if summary.recordings_already_indexed > 0 {
+                            println!(
+                                "  {} were indexed already",
+                                summary.recordings_already_indexed
+                            );
+                        }
+                        if summary.recordings_with_estimated_times > 0 {
+                            println!(
+                                "  {} have no frame times, theirs are spread over the recording",
+                                summary.recordings_with_estimated_times
+                            );
+                        }
+                        for skipped in &summary.skipped {
+                            println!("  skipped {}", skipped);
+                        }
+                    }
+                }
+                return Ok(());
+            }
             Command::Mcp { subcommand } => {
                 handle_mcp_command(subcommand, &local_data_dir_clone).await?;
                 return Ok(());
             }
+            Command::Service { subcommand } => {
+                handle_service_command(subcommand, &local_data_dir)?;
+                return Ok(());
+            }
+            Command::Secrets { subcommand } => {
+                handle_secrets_command(subcommand, &KeychainStore)?;
+                return Ok(());
+            }
+            Command::Evidence { subcommand } => {
+                match subcommand {
+                    EvidenceCommand::Verify {
+                        paths,
+                        public_key,
+                        output,
+                    } => {
+                        let verified = verify_chain(paths, public_key.as_deref())?;
+                        match output {
+                            OutputFormat::Json => {
+                                println!("{}", serde_json::to_string_pretty(&verified)?)
+                            }
+                            OutputFormat::Text => {
+                                let (first, last) = (&verified[0], &verified[verified.len() - 1]);
+                                println!(
+                                    "verified {} segments, {} to {}, sealed {} to {}{}",
+                                    verified.len(),
+                                    first.sequence,
+                                    last.sequence,
+                                    first
+                                        .sealed_at
+                                        .with_timezone(&chrono::Local)
+                                        .format("%Y-%m-%d %H:%M:%S"),
+                                    last.sealed_at
+                                        .with_timezone(&chrono::Local)
+                                        .format("%Y-%m-%d %H:%M:%S"),
+                                    if verified.iter().all(|manifest| manifest.signature.is_some())
+                                    {
+                                        ", all signed"
+                                    } else {
+                                        ""
+                                    }
+                                );
+                                for manifest in verified
+                                    .iter()
+                                    .filter(|manifest| manifest.seal_error.is_some())
+                                {
+                                    println!(
+                                        "segment {} ({}) couldn't be sealed, its video isn't vouched for: {}",
+                                        manifest.sequence,
+                                        manifest.file_name,
+                                        manifest.seal_error.as_deref().unwrap_or_default()
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    EvidenceCommand::PublicKey { key } => {
+                        println!("{}", encode_public_key(&load_or_create_signing_key(key)?));
+                    }
+                }
+                return Ok(());
+            }
+            Command::Schema => {
+                // a fresh database has the schema of this version, whatever the user's is at
+                let scratch = tempfile::tempdir()?;
+                let db = DatabaseManager::new(&scratch.path().join("db.sqlite").to_string_lossy())
+                    .await?;
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&archive_schema(&db).await?)?
+                );
+                return Ok(());
+            }
+            Command::Bench {
+                samples,
+                encode_frames,
+                skip_encode,
+                output,
+            } => {
+                if matches!(output, OutputFormat::Text) {
+                    eprintln!("benchmarking, this takes a minute...");
+                }
+                let report = run_bench(&BenchOptions {
+                    samples: *samples,
+                    encode_frames: *encode_frames,
+                    skip_encode: *skip_encode,
+                    ocr_engine: cli.ocr_engine.clone().into(),
+                    languages: cli.language.clone(),
+                })
+                .await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                    OutputFormat::Text => print!("{}", format_report(&report)),
+                }
+                return Ok(());
+            }
+            Command::Tail { output, all, port } => {
+                tail_events(*port, cli.api_key.as_deref(), output, *all).await?;
+                return Ok(());
+            }
+            Command::Search {
+                query,
+                limit,
+                output,
+                port,
+            } => {
+                // the server searches a query it can't parse as text, a typo in a filter is
+                // pointed out here
+                if let Err(e) = parse_search_query(query) {
+                    eprintln!("{}, searching the query as text", e);
+                }
+
+                let mut request = Client::new()
+                    .get(format!("http://localhost:{}/search", port))
+                    .query(&json!({ "q": query, "limit": limit }));
+                if let Some(api_key) = &cli.api_key {
+                    request = request.bearer_auth(api_key);
+                }
+                let response = request.send().await.map_err(|e| {
+                    anyhow::anyhow!("screenpipe is not running on port {}: {}", port, e)
+                })?;
+                if !response.status().is_success() {
+                    let error: Value = response.json().await.unwrap_or_default();
+                    return Err(anyhow::anyhow!(
+                        "failed to search: {}",
+                        error["error"].as_str().unwrap_or("unknown error")
+                    ));
+                }
+
+                let results: PaginatedResponse<ContentItem> = response.json().await?;
+                match output {
+                    OutputFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&results.data)?)
+                    }
+                    OutputFormat::Text => {
+                        for item in &results.data {
+                            println!("{}", format_search_result(item));
+                        }
+                        println!(
+                            "\n{} of {} results",
+                            results.data.len(),
+                            results.pagination.total
+                        );
+                    }
+                }
+                return Ok(());
+            }
+            Command::ExportActivity {
+                from,
+                to,
+                format,
+                output,
+                port,
+            } => {
+                let today = chrono::Local::now().date_naive();
+                let start = parse_time_arg(from, today)?;
+                let end = match to {
+                    Some(to) => parse_time_arg(to, today)?,
+                    None => chrono::Utc::now(),
+                };
+                let format: TimeTrackingFormat = format.clone().into();
+
+                let mut request = Client::new()
+                    .get(format!("http://localhost:{}/activity/export", port))
+                    .query(&json!({ "start_time": start, "end_time": end, "format": format }));
+                if let Some(api_key) = &cli.api_key {
+                    request = request.bearer_auth(api_key);
+                }
+                let response = request.send().await.map_err(|e| {
+                    anyhow::anyhow!("screenpipe is not running on port {}: {}", port, e)
+                })?;
+                if !response.status().is_success() {
+                    let error: Value = response.json().await.unwrap_or_default();
+                    return Err(anyhow::anyhow!(
+                        "failed to export activity: {}",
+                        error["error"].as_str().unwrap_or("unknown error")
+                    ));
+                }
+
+                let content = response.text().await?;
+                match output {
+                    Some(path) => {
+                        std::fs::write(path, content)?;
+                        println!("exported activity to {}", path.display());
+                    }
+                    None => print!("{}", content),
+                }
+                return Ok(());
+            }
+            Command::ExportStats {
+                from,
+                to,
+                epsilon,
+                hash_titles,
+                salt,
+                output,
+                port,
+            } => {
+                let today = chrono::Local::now().date_naive();
+                let start = parse_time_arg(from, today)?;
+                let end = match to {
+                    Some(to) => parse_time_arg(to, today)?,
+                    None => chrono::Utc::now(),
+                };
+                let titles = if *hash_titles {
+                    TitleMode::Hash
+                } else {
+                    TitleMode::Strip
+                };
+
+                let mut request = Client::new()
+                    .get(format!("http://localhost:{}/activity/stats", port))
+                    .query(&json!({ "start_time": start, "end_time": end, "titles": titles }));
+                if let Some(epsilon) = epsilon {
+                    request = request.query(&[("epsilon", epsilon)]);
+                }
+                if let Some(salt) = salt {
+                    request = request.query(&[("salt", salt)]);
+                }
+                if let Some(api_key) = &cli.api_key {
+                    request = request.bearer_auth(api_key);
+                }
+                let response = request.send().await.map_err(|e| {
+                    anyhow::anyhow!("screenpipe is not running on port {}: {}", port, e)
+                })?;
+                if !response.status().is_success() {
+                    let error: Value = response.json().await.unwrap_or_default();
+                    return Err(anyhow::anyhow!(
+                        "failed to export stats: {}",
+                        error["error"].as_str().unwrap_or("unknown error")
+                    ));
+                }
+
+                let stats: Value = response.json().await?;
+                let content = serde_json::to_string_pretty(&stats)?;
+                match output {
+                    Some(path) => {
+                        std::fs::write(path, content)?;
+                        println!("exported stats to {}", path.display());
+                    }
+                    None => println!("{}", content),
+                }
+                return Ok(());
+            }
+            Command::Summarize {
+                from,
+                to,
+                output,
+                port,
+            } => {
+                let today = chrono::Local::now().date_naive();
+                let start = parse_time_arg(from, today)?;
+                let end = match to {
+                    Some(to) => parse_time_arg(to, today)?,
+                    None => chrono::Utc::now(),
+                };
+
+                let mut request = Client::new()
+                    .post(format!("http://localhost:{}/summarize", port))
+                    .json(&json!({ "start_time": start, "end_time": end }));
+                if let Some(api_key) = &cli.api_key {
+                    request = request.bearer_auth(api_key);
+                }
+                let response = request.send().await.map_err(|e| {
+                    anyhow::anyhow!("screenpipe is not running on port {}: {}", port, e)
+                })?;
+                if !response.status().is_success() {
+                    let error: Value = response.json().await.unwrap_or_default();
+                    return Err(anyhow::anyhow!(
+                        "failed to summarize: {}",
+                        error["error"].as_str().unwrap_or("unknown error")
+                    ));
+                }
+
+                let summary: Summary = response.json().await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+                    OutputFormat::Text => {
+                        println!(
+                            "{} – {} ({})\n",
+                            summary
+                                .start_time
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M"),
+                            summary
+                                .end_time
+                                .with_timezone(&chrono::Local)
+                                .format("%H:%M"),
+                            summary.model
+                        );
+                        println!("{}", summary.summary);
+                    }
+                }
+                return Ok(());
+            }
         }
     }
 
@@ -635,6 +1152,307 @@ async fn main() -> anyhow::Result<()> {
 
     let db_server = db.clone();
 
+    // only one recorder may write into this machine's output tree at a time
+    let storage = Storage::new(local_data_dir.join("data"));
+    let _storage_lock = if cli.disable_vision && cli.disable_audio {
+        None
+    } else {
+        match storage.lock() {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(e);
+            }
+        }
+    };
+
+    // index the frames the last run encoded and was killed before indexing
+    if !cli.disable_vision {
+        match storage.recover_frame_journals(&db).await {
+            Ok(0) => {}
+            Ok(recovered) => info!("recovered {} frames missing from the index", recovered),
+            Err(e) => warn!("failed to recover frames from the journals: {}", e),
+        }
+    }
+
+    let session_tracker = if cli.disable_vision {
+        None
+    } else {
+        Some(
+            SessionTracker::new(
+                db.clone(),
+                storage.clone(),
+                SessionSplitConfig {
+                    idle_gap: (cli.session_idle_minutes > 0)
+                        .then(|| Duration::from_secs(cli.session_idle_minutes * 60)),
+                    split_on_day_boundary: !cli.disable_day_session_split,
+                },
+            )
+            .await?,
+        )
+    };
+    let session_tracker_clone = session_tracker.clone();
+
+    set_embedding_provider(match cli.embedding_provider {
+        CliEmbeddingProvider::Local => EmbeddingProvider::Local,
+        CliEmbeddingProvider::Api => EmbeddingProvider::Api {
+            url: cli.embedding_api_url.clone(),
+            model: cli.embedding_model.clone(),
+            api_key: cli.embedding_api_key.clone(),
+        },
+    });
+
+    set_capture_policy(
+        CapturePolicy::new(&cli.ignored_windows, &cli.included_windows)
+            .mute_audio_on_ignored_windows(cli.mute_audio_on_ignored_windows)
+            .private_meetings(&cli.private_meetings),
+    );
+    let recording_config_path = local_data_dir.join(RECORDING_CONFIG_FILE);
+    let recording_settings = RecordingSettings {
+        ignored_windows: cli.ignored_windows.clone(),
+        included_windows: cli.included_windows.clone(),
+        fps: cli.fps,
+        diff_threshold: cli.diff_threshold,
+        app_diff_thresholds: cli.app_diff_threshold.clone(),
+        app_qualities: cli.app_quality.clone(),
+    };
+    recording_settings
+        .overridden_by(&RecordingConfig::load(&recording_config_path)?)
+        .apply();
+    set_skip_duplicate_frames(cli.skip_duplicate_frames);
+    set_recording_format(cli.recording_format.clone().into());
+    set_backpressure_policy(cli.backpressure.clone().into());
+    set_adaptive_chunks(!cli.fixed_chunk_duration);
+    set_dynamic_crf(!cli.fixed_crf);
+    set_ocr_languages(cli.unique_languages().unwrap_or_default());
+    set_focused_window_only(cli.focused_window_only);
+    set_incremental_ocr(cli.incremental_ocr);
+    set_focus_trigger(
+        cli.capture_on_focus_change
+            .then(|| Duration::from_millis(cli.focus_debounce_ms)),
+    );
+    if cli.focused_window_only && cli.capture_unfocused_windows {
+        warn!("--capture-unfocused-windows has no effect with --focused-window-only");
+    }
+    set_max_animated_clip_secs(cli.max_animated_clip_secs);
+    set_pre_roll(Duration::from_secs(cli.pre_roll_secs));
+    if cli.pre_roll_secs > 0 && cli.grpc_port.is_none() {
+        warn!("--pre-roll-secs has no effect without --grpc-port, recording is never paused");
+    }
+    let excluded_regions_path = local_data_dir.join(EXCLUDED_REGIONS_CONFIG_FILE);
+    let excluded_regions = ExcludedRegionsConfig::load(&excluded_regions_path)?;
+    excluded_regions.apply();
+    let sensitive_content_path = local_data_dir.join(SENSITIVE_CONTENT_CONFIG_FILE);
+    let sensitive_content = SensitiveContentConfig::load(&sensitive_content_path)?;
+    sensitive_content.apply();
+    let categories_path = local_data_dir.join(CATEGORIES_CONFIG_FILE);
+    let categories = CategoriesConfig::load(&categories_path)?;
+    categories.apply()?;
+
+    let sync_config_path = cli
+        .sync_config
+        .clone()
+        .unwrap_or_else(|| local_data_dir.join(DEFAULT_SYNC_CONFIG_FILE));
+    let sync_enabled = cli.sync_config.is_some() || sync_config_path.exists();
+    if sync_enabled {
+        let mut sync_config = SyncConfig::load(&sync_config_path)?;
+        sync_config.resolve_secrets(&KeychainStore)?;
+        if let SyncTarget::Hub { url, token, .. } = &sync_config.target {
+            tokio::spawn(push_to_hub(
+                db.clone(),
+                storage.root().to_path_buf(),
+                local_data_dir.join(HUB_CURSOR_FILE),
+                url.clone(),
+                token.clone(),
+            ));
+        }
+        if cli.job_workers == 0 && sync_config.uploads_segments() {
+            warn!("uploads are queued but --job-workers 0 leaves them unprocessed");
+        }
+        set_sync_config(sync_config);
+    }
+    if let Some(token) = &cli.hub_token {
+        set_hub(HubConfig {
+            token: resolve_secret(token, &KeychainStore)?,
+            root: storage.root().to_path_buf(),
+        });
+    }
+    if cli.seal_segments {
+        let signing_key = cli
+            .evidence_key
+            .as_deref()
+            .map(load_or_create_signing_key)
+            .transpose()?;
+        if let Some(key) = &signing_key {
+            info!(
+                "signing evidence manifests with public key {}",
+                encode_public_key(key)
+            );
+        }
+        if cli.job_workers == 0 {
+            warn!("segments are only sealed by job workers, --job-workers 0 leaves them unsealed");
+        }
+        set_evidence_config(EvidenceConfig { signing_key });
+    } else if cli.evidence_key.is_some() {
+        warn!("--evidence-key has no effect without --seal-segments");
+    }
+
+    let timelapse_at = cli
+        .timelapse_at
+        .as_deref()
+        .map(parse_time_of_day)
+        .transpose()?;
+
+    set_llm_backend(LlmBackend {
+        url: cli.llm_api_url.clone(),
+        model: cli.llm_model.clone(),
+        api_key: cli.llm_api_key.clone(),
+    });
+
+    if cli.enable_frame_pyramid && cli.job_workers == 0 {
+        warn!("--enable-frame-pyramid has no effect with --job-workers 0");
+    }
+    if cli.enable_thumbnail_strips && cli.job_workers == 0 {
+        warn!("--enable-thumbnail-strips has no effect with --job-workers 0");
+    }
+    if cli.enable_embeddings && cli.job_workers == 0 {
+        warn!("--enable-embeddings has no effect with --job-workers 0");
+    }
+    if cli.defer_processing && cli.job_workers == 0 {
+        warn!("--defer-processing has no effect with --job-workers 0");
+    }
+    let defer_processing = cli.defer_processing && cli.job_workers > 0;
+    let export_config_path = cli
+        .export_config
+        .clone()
+        .unwrap_or_else(|| local_data_dir.join(DEFAULT_EXPORT_CONFIG_FILE));
+    let export_schedule = std::sync::Mutex::new(None);
+    // started once the audio manager its transcription jobs go to is built
+    let job_queue = (cli.job_workers > 0).then(|| {
+        JobQueue::with_default_handlers(
+            db.clone(),
+            storage.clone(),
+            JobQueueConfig {
+                workers: cli.job_workers,
+                max_cpu_usage: cli.job_max_cpu_percent,
+                ..Default::default()
+            },
+        )
+    });
+    if cli.enable_ui_events {
+        tokio::spawn(record_ui_events(db.clone()));
+    }
+    if cli.enable_accessibility_capture {
+        tokio::spawn(record_accessibility_text(
+            db.clone(),
+            Duration::from_secs(cli.accessibility_interval_secs.max(1)),
+        ));
+    }
+    if cli.enable_clipboard_history {
+        tokio::spawn(record_clipboard(
+            db.clone(),
+            Duration::from_millis(cli.clipboard_interval_ms.max(100)),
+            cli.use_pii_removal,
+        ));
+    }
+    if cli.job_workers > 0 {
+        if !cli.disable_vision && !cli.disable_day_mosaic {
+            tokio::spawn(schedule_nightly_mosaics(db.clone()));
+        }
+        if let Some(at) = timelapse_at.filter(|_| !cli.disable_vision) {
+            tokio::spawn(schedule_timelapses(db.clone(), at, cli.timelapse_speed));
+        }
+        if cli.enable_embeddings {
+            tokio::spawn(schedule_embedding_jobs(
+                db.clone(),
+                Duration::from_secs(5 * 60),
+            ));
+        }
+        if let Err(e) = queue_ocr_language_backfill(&db).await {
+            warn!("failed to queue ocr language detection: {}", e);
+        }
+
+        if cli.export_config.is_some() || export_config_path.exists() {
+            let config = ExportConfig::load(&export_config_path)?;
+            if !config.exports.is_empty() {
+                *export_schedule.lock().unwrap() =
+                    Some(tokio::spawn(schedule_exports(db.clone(), config)));
+            }
+        }
+    } else if cli.export_config.is_some() {
+        warn!("--export-config has no effect with --job-workers 0");
+    }
+    if timelapse_at.is_some() && cli.job_workers == 0 {
+        warn!("--timelapse-at has no effect with --job-workers 0");
+    }
+
+    if !cli.disable_vision && !cli.disable_power_throttling {
+        tokio::spawn(monitor_power(PowerPolicy {
+            pause_below_percent: (cli.battery_pause_percent > 0)
+                .then_some(cli.battery_pause_percent as f32),
+            thermal_limit: (cli.thermal_limit_celsius > 0.0).then_some(cli.thermal_limit_celsius),
+            low_power_fps_factor: cli.low_power_fps_factor.clamp(0.05, 1.0),
+            ..Default::default()
+        }));
+    }
+    if !cli.disable_vision && !cli.disable_resource_guard {
+        let megabytes = |mb: u64| (mb > 0).then_some(mb * 1024 * 1024);
+        tokio::spawn(guard_resources(
+            ResourceLimits {
+                min_free_disk_bytes: megabytes(cli.min_free_disk_mb),
+                max_memory_bytes: megabytes(cli.max_memory_mb),
+                degraded_fps_factor: cli.low_power_fps_factor.clamp(0.05, 1.0),
+                ..Default::default()
+            },
+            local_data_dir_clone.clone(),
+        ));
+    }
+    if !cli.disable_config_reload {
+        let mut configs = vec![
+            WatchedConfig::new(recording_config_path, move |path| {
+                recording_settings
+                    .overridden_by(&RecordingConfig::load(path)?)
+                    .apply();
+                Ok(())
+            }),
+            WatchedConfig::new(excluded_regions_path, |path| {
+                ExcludedRegionsConfig::load(path)?.apply();
+                Ok(())
+            }),
+            WatchedConfig::new(sensitive_content_path, |path| {
+                SensitiveContentConfig::load(path)?.apply();
+                Ok(())
+            }),
+            WatchedConfig::new(categories_path, |path| {
+                CategoriesConfig::load(path)?.apply()
+            }),
+        ];
+        if cli.job_workers > 0 {
+            let db = db.clone();
+            configs.push(WatchedConfig::new(export_config_path, move |path| {
+                // a removed config stops the exports
+                let config = if path.exists() {
+                    ExportConfig::load(path)?
+                } else {
+                    ExportConfig::default()
+                };
+                let mut schedule = export_schedule.lock().unwrap();
+                if let Some(previous) = schedule.take() {
+                    previous.abort();
+                }
+                if !config.exports.is_empty() {
+                    *schedule = Some(tokio::spawn(schedule_exports(db.clone(), config)));
+                }
+                Ok(())
+            }));
+        }
+        tokio::spawn(async move {
+            if let Err(e) = watch_configs(configs).await {
+                warn!("config files are only read at start: {}", e);
+            }
+        });
+    }
+
     let warning_ocr_engine_clone = cli.ocr_engine.clone();
     let warning_audio_transcription_engine_clone = cli.audio_transcription_engine.clone();
     let monitor_ids = if cli.monitor_id.is_empty() {
@@ -646,6 +1464,21 @@ async fn main() -> anyhow::Result<()> {
     let languages = cli.unique_languages().unwrap();
     let languages_clone = languages.clone();
 
+    if defer_processing && !cli.disable_vision {
+        if recording_format().image_format().is_some() {
+            warn!("frames recorded as images are OCR'd as they're captured");
+        } else {
+            set_deferred_ocr(DeferredOcr {
+                ocr_engine: Arc::new(cli.ocr_engine.clone().into()),
+                languages: languages.clone(),
+                subtitle_sidecars: cli.enable_subtitle_sidecars,
+            });
+            if let Err(e) = queue_last_chunks(&db).await {
+                warn!("failed to queue ocr of the last chunks: {}", e);
+            }
+        }
+    }
+
     let ocr_engine_clone = cli.ocr_engine.clone();
     let vad_engine = cli.vad_engine.clone();
     let vad_engine_clone = vad_engine.clone();
@@ -664,6 +1497,8 @@ async fn main() -> anyhow::Result<()> {
     let monitor_ids_clone = monitor_ids.clone();
     let ignored_windows_clone = cli.ignored_windows.clone();
     let included_windows_clone = cli.included_windows.clone();
+    let stream_url_clone = cli.stream_url.clone();
+    let plugins = Arc::new(PluginHost::load_all(&cli.plugins)?);
     let realtime_audio_devices_clone = realtime_audio_devices.clone();
 
     let fps = if cli.fps.is_finite() && cli.fps > 0.0 {
@@ -684,6 +1519,7 @@ async fn main() -> anyhow::Result<()> {
         .realtime(cli.enable_realtime_audio_transcription)
         .enabled_devices(audio_devices)
         .deepgram_api_key(cli.deepgram_api_key.clone())
+        .defer_transcription(defer_processing)
         .output_path(PathBuf::from(output_path_clone.clone().to_string()));
 
     let audio_manager = match audio_manager_builder.build(db.clone()).await {
@@ -694,6 +1530,39 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    if let Some(job_queue) = job_queue {
+        let transcribing_manager = audio_manager.clone();
+        job_queue
+            .register(TRANSCRIBE_AUDIO_CHUNK_JOB, move |job, _progress| {
+                let audio_manager = transcribing_manager.clone();
+                Box::pin(async move { audio_manager.transcribe_deferred(&job.payload).await })
+            })
+            .start()
+            .await?;
+    }
+
+    if cli.enable_meeting_profile {
+        if cli.disable_vision {
+            warn!("meeting profile needs vision to detect meetings, it stays off");
+        } else {
+            if cli.disable_audio {
+                warn!("audio is disabled, meetings are recorded without it");
+            }
+            tokio::spawn(
+                MeetingMode::new(
+                    MeetingProfile {
+                        fps: Some(cli.meeting_fps),
+                        audio: !cli.disable_audio,
+                    },
+                    Some(audio_manager.clone()),
+                    session_tracker.clone(),
+                )
+                .follow_meetings(),
+            );
+        }
+    }
+
+    let subtitle_sidecars = cli.enable_subtitle_sidecars;
     let handle = {
         let runtime = &tokio::runtime::Handle::current();
         runtime.spawn(async move {
@@ -709,11 +1578,15 @@ async fn main() -> anyhow::Result<()> {
                     cli.use_pii_removal,
                     cli.disable_vision,
                     &vision_handle,
-                    &cli.ignored_windows,
-                    &cli.included_windows,
                     languages_clone.clone(),
                     cli.capture_unfocused_windows,
                     cli.enable_realtime_audio_transcription,
+                    cli.enable_subtitle_sidecars,
+                    stream_url_clone.clone(),
+                    session_tracker_clone.clone(),
+                    cli.enable_frame_pyramid,
+                    cli.enable_thumbnail_strips,
+                    plugins.clone(),
                 );
 
                 let result = tokio::select! {
@@ -748,6 +1621,13 @@ async fn main() -> anyhow::Result<()> {
     #[cfg(feature = "llm")]
     debug!("LLM initialized");
 
+    let grpc_control = cli.grpc_port.map(|port| {
+        (
+            port,
+            GrpcControl::new(db_server.clone(), audio_manager.clone(), cli.disable_audio),
+        )
+    });
+
     let server = SCServer::new(
         db_server,
         SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), cli.port),
@@ -758,6 +1638,10 @@ async fn main() -> anyhow::Result<()> {
         cli.enable_ui_monitoring,
         audio_manager.clone(),
         cli.enable_pipe_manager,
+        Arc::new(cli.ocr_engine.clone().into()),
+        cli.api_key.clone(),
+        cli.enable_access_log,
+        session_tracker.clone(),
     );
 
     // print screenpipe in gradient
@@ -783,15 +1667,55 @@ async fn main() -> anyhow::Result<()> {
     );
     println!(
         "│ video chunk duration   │ {:<34} │",
-        format!("{} seconds", cli.video_chunk_duration)
+        format!(
+            "{} seconds{}",
+            cli.video_chunk_duration,
+            if cli.fixed_chunk_duration {
+                ""
+            } else {
+                " (adaptive)"
+            }
+        )
+    );
+    println!(
+        "│ video quality          │ {:<34} │",
+        if cli.fixed_crf { "fixed" } else { "by content" }
+    );
+    println!(
+        "│ recording format       │ {:<34} │",
+        format!("{:?}", cli.recording_format).to_lowercase()
+    );
+    println!(
+        "│ full queues            │ {:<34} │",
+        match cli.backpressure {
+            CliBackpressurePolicy::DropOldest => "drop oldest frame",
+            CliBackpressurePolicy::Block => "slow capture down",
+        }
     );
     println!("│ port                   │ {:<34} │", cli.port);
+    println!(
+        "│ grpc port              │ {:<34} │",
+        cli.grpc_port
+            .map(|port| port.to_string())
+            .unwrap_or_else(|| "disabled".to_string())
+    );
     println!(
         "│ realtime audio enabled │ {:<34} │",
         cli.enable_realtime_audio_transcription
     );
     println!("│ audio disabled         │ {:<34} │", cli.disable_audio);
     println!("│ vision disabled        │ {:<34} │", cli.disable_vision);
+    println!("│ headless               │ {:<34} │", cli.headless);
+    println!("│ demo                   │ {:<34} │", cli.demo);
+    println!(
+        "│ api key                │ {:<34} │",
+        if cli.api_key.is_some() {
+            "set"
+        } else {
+            "not set"
+        }
+    );
+    println!("│ access log             │ {:<34} │", cli.enable_access_log);
     println!(
         "│ audio engine           │ {:<34} │",
         format!("{:?}", warning_audio_transcription_engine_clone)
@@ -828,10 +1752,73 @@ async fn main() -> anyhow::Result<()> {
         "│ included windows       │ {:<34} │",
         format_cell(&format!("{:?}", &included_windows_clone), VALUE_WIDTH)
     );
+    println!(
+        "│ audio muting           │ {:<34} │",
+        format_cell(
+            &match (
+                cli.mute_audio_on_ignored_windows,
+                cli.private_meetings.is_empty()
+            ) {
+                (false, true) => "disabled".to_string(),
+                (true, true) => "ignored windows".to_string(),
+                (false, false) => format!("private meetings {:?}", cli.private_meetings),
+                (true, false) => format!("ignored windows, meetings {:?}", cli.private_meetings),
+            },
+            VALUE_WIDTH
+        )
+    );
     println!(
         "│ ui monitoring          │ {:<34} │",
         cli.enable_ui_monitoring
     );
+    println!("│ ui events              │ {:<34} │", cli.enable_ui_events);
+    println!(
+        "│ accessibility capture  │ {:<34} │",
+        if cli.enable_accessibility_capture {
+            format!("every {}s", cli.accessibility_interval_secs.max(1))
+        } else {
+            "disabled".to_string()
+        }
+    );
+    println!(
+        "│ clipboard history      │ {:<34} │",
+        if cli.enable_clipboard_history {
+            format!("every {}ms", cli.clipboard_interval_ms.max(100))
+        } else {
+            "disabled".to_string()
+        }
+    );
+    println!(
+        "│ timelapse              │ {:<34} │",
+        match &cli.timelapse_at {
+            Some(at) => format!("{} ({}x)", at, cli.timelapse_speed),
+            None => "disabled".to_string(),
+        }
+    );
+    println!(
+        "│ sync                   │ {:<34} │",
+        if sync_enabled {
+            format_cell(&sync_config_path.to_string_lossy(), 34)
+        } else {
+            "disabled".to_string()
+        }
+    );
+    println!(
+        "│ hub                    │ {:<34} │",
+        if cli.hub_token.is_some() {
+            "accepting pushes"
+        } else {
+            "disabled"
+        }
+    );
+    println!(
+        "│ evidence               │ {:<34} │",
+        match (cli.seal_segments, cli.evidence_key.is_some()) {
+            (true, true) => "sealed, signed",
+            (true, false) => "sealed",
+            (false, _) => "disabled",
+        }
+    );
     println!(
         "│ frame cache            │ {:<34} │",
         cli.enable_frame_cache
@@ -840,6 +1827,134 @@ async fn main() -> anyhow::Result<()> {
         "│ capture unfocused wins │ {:<34} │",
         cli.capture_unfocused_windows
     );
+    println!(
+        "│ focused window only    │ {:<34} │",
+        cli.focused_window_only
+    );
+    println!("│ incremental ocr        │ {:<34} │", cli.incremental_ocr);
+    println!(
+        "│ focus change capture   │ {:<34} │",
+        if cli.capture_on_focus_change {
+            format!("{}ms debounce", cli.focus_debounce_ms)
+        } else {
+            "disabled".to_string()
+        }
+    );
+    println!(
+        "│ excluded regions       │ {:<34} │",
+        excluded_regions.regions.len()
+    );
+    println!(
+        "│ sensitive content      │ {:<34} │",
+        format!("{:?}", sensitive_content.mode)
+    );
+    println!(
+        "│ category rules         │ {:<34} │",
+        categories.rules.len()
+    );
+    println!(
+        "│ skip duplicate frames  │ {:<34} │",
+        cli.skip_duplicate_frames
+    );
+    println!(
+        "│ pre-roll               │ {:<34} │",
+        format!("{}s", cli.pre_roll_secs)
+    );
+    println!(
+        "│ diff threshold         │ {:<34} │",
+        format_cell(
+            &std::iter::once(cli.diff_threshold.to_string())
+                .chain(
+                    cli.app_diff_threshold
+                        .iter()
+                        .map(|(app, threshold)| format!("{}={}", app, threshold)),
+                )
+                .collect::<Vec<_>>()
+                .join(", "),
+            VALUE_WIDTH
+        )
+    );
+    println!(
+        "│ app quality            │ {:<34} │",
+        if cli.app_quality.is_empty() {
+            "auto".to_string()
+        } else {
+            format_cell(
+                &cli.app_quality
+                    .iter()
+                    .map(|(app, quality)| match quality.crf {
+                        Some(crf) => format!("{}={}:{}", app, quality.scale, crf),
+                        None => format!("{}={}", app, quality.scale),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                VALUE_WIDTH,
+            )
+        }
+    );
+    println!(
+        "│ meeting profile        │ {:<34} │",
+        if cli.enable_meeting_profile && !cli.disable_vision {
+            format!("{} fps", cli.meeting_fps)
+        } else {
+            "off".to_string()
+        }
+    );
+    println!(
+        "│ subtitle sidecars      │ {:<34} │",
+        cli.enable_subtitle_sidecars
+    );
+    println!(
+        "│ frame pyramid          │ {:<34} │",
+        cli.enable_frame_pyramid
+    );
+    println!(
+        "│ thumbnail strips       │ {:<34} │",
+        cli.enable_thumbnail_strips
+    );
+    println!(
+        "│ live stream url        │ {:<34} │",
+        cli.stream_url.as_deref().unwrap_or("disabled")
+    );
+    println!(
+        "│ power throttling       │ {:<34} │",
+        if cli.disable_power_throttling {
+            "disabled".to_string()
+        } else {
+            format!(
+                "pause <{}%, {}°C, x{}",
+                cli.battery_pause_percent, cli.thermal_limit_celsius, cli.low_power_fps_factor
+            )
+        }
+    );
+    println!(
+        "│ resource guard         │ {:<34} │",
+        if cli.disable_resource_guard {
+            "disabled".to_string()
+        } else {
+            format!(
+                "disk <{} MB, memory >{} MB",
+                cli.min_free_disk_mb, cli.max_memory_mb
+            )
+        }
+    );
+    println!(
+        "│ config reload          │ {:<34} │",
+        !cli.disable_config_reload
+    );
+    println!("│ job workers            │ {:<34} │", cli.job_workers);
+    println!(
+        "│ embeddings             │ {:<34} │",
+        if !cli.enable_embeddings {
+            "disabled".to_string()
+        } else if cli.embedding_provider == CliEmbeddingProvider::Local {
+            "local model".to_string()
+        } else {
+            cli.embedding_model.clone()
+        }
+    );
+    println!("│ summaries model        │ {:<34} │", cli.llm_model);
+    println!("│ plugins                │ {:<34} │", cli.plugins.len());
     println!(
         "│ auto-destruct pid      │ {:<34} │",
         cli.auto_destruct_pid.unwrap_or(0)
@@ -1073,6 +2188,16 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    if let Some((port, grpc_control)) = grpc_control {
+        let api_key = cli.api_key.clone();
+        tokio::spawn(async move {
+            let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+            if let Err(e) = grpc_control.serve(addr, api_key).await {
+                error!("gRPC control interface stopped: {}", e);
+            }
+        });
+    }
+
     let server_future = server.start(cli.enable_frame_cache);
     pin_mut!(server_future);
 
@@ -1156,8 +2281,15 @@ async fn main() -> anyhow::Result<()> {
         }
         _ = ctrl_c_future => {
             info!("received ctrl+c, initiating shutdown");
+            if let Some(session_tracker) = &session_tracker {
+                session_tracker.finish("shutdown").await;
+            }
             audio_manager.shutdown().await?;
             let _ = shutdown_tx.send(());
+            // the chunks being recorded are final, and so is the OCR of their frames
+            if subtitle_sidecars {
+                write_remaining_sidecars(&db, &monitor_ids).await;
+            }
         }
     }
 
@@ -1167,6 +2299,18 @@ async fn main() -> anyhow::Result<()> {
         drop(audio_manager);
     });
 
+    if let Some(dir) = demo_dir {
+        let path = dir.path().to_path_buf();
+        match dir.close() {
+            Ok(()) => info!("deleted demo recordings in {}", path.display()),
+            Err(e) => warn!(
+                "failed to delete demo recordings in {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
     info!("shutdown complete");
 
     Ok(())
@@ -0,0 +1,213 @@
+use crate::time_tracking::ActivityPatterns;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use once_cell::sync::Lazy;
+use screenpipe_db::DatabaseManager;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Rules mapping activities to categories, in the data dir.
+pub const CATEGORIES_CONFIG_FILE: &str = "categories.json";
+
+/// Colors for the categories without one in `categories.json`, picked by their name so a
+/// category keeps its color across restarts.
+const PALETTE: [&str; 10] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+    "#9c755f", "#bab0ac",
+];
+
+/// A rule of `categories.json`, e.g.
+/// `{"category": "client x", "title": "ACME|client-x", "priority": 10}`. Each pattern set is a
+/// case-insensitive regex that must match somewhere in the app name, window title or browser
+/// url; a rule without patterns matches every activity, as a fallback.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryRule {
+    pub category: String,
+    #[serde(default)]
+    pub app: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Never matches activities without a url, like those of apps other than browsers.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Rules with a higher priority are tried first, those with the same in the file's order.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// `categories.json`: the rules, and the colors of the categories on the timeline as
+/// `{"coding": "#59a14f"}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoriesConfig {
+    #[serde(default)]
+    pub rules: Vec<CategoryRule>,
+    #[serde(default)]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl CategoriesConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(content)?;
+        // invalid patterns are reported when the file is loaded, not when a frame is recorded
+        config.categorizer()?;
+        Ok(config)
+    }
+
+    /// The config at `path`, one without rules when there's no file.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read categories config {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    pub fn categorizer(&self) -> Result<Categorizer> {
+        let mut rules = self
+            .rules
+            .iter()
+            .map(CompiledRule::new)
+            .collect::<Result<Vec<_>>>()?;
+        // stable, so rules of the same priority keep their order
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        for (category, color) in &self.colors {
+            if !is_hex_color(color) {
+                return Err(anyhow!(
+                    "invalid color {:?} for category {:?}, expected one like #4e79a7",
+                    color,
+                    category
+                ));
+            }
+        }
+        Ok(Categorizer {
+            rules,
+            colors: self.colors.clone(),
+        })
+    }
+
+    /// Makes the recorders categorize their frames with these rules from their next frame on.
+    pub fn apply(&self) -> Result<()> {
+        *CATEGORIZER.write().unwrap() = self.categorizer()?;
+        Ok(())
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    category: String,
+    patterns: ActivityPatterns,
+    priority: i32,
+}
+
+impl CompiledRule {
+    fn new(rule: &CategoryRule) -> Result<Self> {
+        if rule.category.trim().is_empty() {
+            return Err(anyhow!("a category rule has an empty category"));
+        }
+        let patterns = ActivityPatterns::compile(
+            rule.app.as_deref(),
+            rule.title.as_deref(),
+            rule.url.as_deref(),
+        )
+        .with_context(|| format!("in the rule of category {:?}", rule.category))?;
+        Ok(Self {
+            category: rule.category.clone(),
+            patterns,
+            priority: rule.priority,
+        })
+    }
+}
+
+/// The compiled rules of a [`CategoriesConfig`], by priority.
+#[derive(Debug, Clone, Default)]
+pub struct Categorizer {
+    rules: Vec<CompiledRule>,
+    colors: BTreeMap<String, String>,
+}
+
+impl Categorizer {
+    /// The category of the first rule by priority matching the activity, `None` when none does.
+    pub fn categorize(
+        &self,
+        app_name: &str,
+        window_name: &str,
+        browser_url: Option<&str>,
+    ) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| rule.patterns.matches(app_name, window_name, browser_url))
+            .map(|rule| rule.category.as_str())
+    }
+
+    /// The configured color of `category`, or one of the palette.
+    pub fn color(&self, category: &str) -> String {
+        if let Some(color) = self.colors.get(category) {
+            return color.clone();
+        }
+        // FNV-1a, unlike the std hasher it doesn't change between releases
+        let hash = category.bytes().fold(0x811c9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+        PALETTE[hash as usize % PALETTE.len()].to_string()
+    }
+}
+
+static CATEGORIZER: Lazy<RwLock<Categorizer>> = Lazy::new(Default::default);
+
+/// The category of an activity by the rules applied last.
+pub fn categorize(app_name: &str, window_name: &str, browser_url: Option<&str>) -> Option<String> {
+    CATEGORIZER
+        .read()
+        .unwrap()
+        .categorize(app_name, window_name, browser_url)
+        .map(str::to_string)
+}
+
+pub fn category_color(category: &str) -> String {
+    CATEGORIZER.read().unwrap().color(category)
+}
+
+/// What [`recategorize_frames`] did.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recategorization {
+    pub frames: usize,
+    /// Frames a rule matched, the others have no category anymore.
+    pub categorized: usize,
+}
+
+/// Categorizes the frames recorded between `start` and `end` again with the rules applied
+/// last, for rules added or edited after they were recorded.
+pub async fn recategorize_frames(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Recategorization> {
+    let frames = db.get_frames_to_categorize(start, end).await?;
+    let categories: Vec<_> = frames
+        .iter()
+        .map(|(frame_id, app_name, window_name, browser_url)| {
+            (
+                *frame_id,
+                categorize(app_name, window_name, browser_url.as_deref()),
+            )
+        })
+        .collect();
+    db.set_frame_categories(&categories).await?;
+    Ok(Recategorization {
+        frames: categories.len(),
+        categorized: categories
+            .iter()
+            .filter(|(_, category)| category.is_some())
+            .count(),
+    })
+}
@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Shortest pause in captured frames taken for a lull: the screen stopped changing, so no
+/// frame passed the diff threshold.
+const MIN_LULL: Duration = Duration::from_secs(10);
+
+/// When a video chunk ends. An adaptive rotation ends chunks at an app switch or a lull once
+/// they get near the target length, so that chunks follow what was being done and exports of
+/// one activity rarely span a cut. Without a boundary a chunk ends a bit after the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRotation {
+    min_frames: usize,
+    max_frames: usize,
+    lull: Duration,
+}
+
+impl ChunkRotation {
+    /// Chunks of exactly `target_frames` frames.
+    pub fn fixed(target_frames: usize) -> Self {
+        ChunkRotation {
+            min_frames: target_frames,
+            max_frames: target_frames,
+            lull: Duration::MAX,
+        }
+    }
+
+    /// Chunks of a quarter of `target_frames` less to a quarter more, at a capture rate of
+    /// `fps`.
+    pub fn adaptive(target_frames: usize, fps: f64) -> Self {
+        let frame_interval = Duration::from_secs_f64(1.0 / fps.max(0.01));
+        ChunkRotation {
+            min_frames: (target_frames * 3 / 4).max(1),
+            max_frames: (target_frames * 5 / 4).max(1),
+            lull: MIN_LULL.max(frame_interval * 3),
+        }
+    }
+
+    /// Whether a chunk of `frames` frames is as long as chunks get.
+    pub fn is_full(&self, frames: usize) -> bool {
+        frames >= self.max_frames
+    }
+
+    /// Whether a chunk of `frames` frames, last focused on `previous_app`, ends before a frame
+    /// focused on `app`.
+    pub fn ends_at_app_switch(
+        &self,
+        frames: usize,
+        previous_app: Option<&str>,
+        app: Option<&str>,
+    ) -> bool {
+        match (previous_app, app) {
+            (Some(previous), Some(app)) => frames >= self.min_frames && previous != app,
+            _ => false,
+        }
+    }
+
+    /// Whether a chunk of `frames` frames ends before a frame focused on an app encoded at
+    /// another quality. A shorter chunk goes on at its quality, the app's applying from the
+    /// next chunk.
+    pub fn ends_at_quality_change(&self, frames: usize) -> bool {
+        frames >= self.min_frames
+    }
+
+    /// Whether a chunk of `frames` frames ends after no frame was captured for `idle`.
+    pub fn ends_at_lull(&self, frames: usize, idle: Duration) -> bool {
+        frames >= self.min_frames && idle >= self.lull
+    }
+}
+
+static ADAPTIVE_CHUNKS: AtomicBool = AtomicBool::new(true);
+
+/// Whether chunks end at activity boundaries near the target duration rather than exactly at it.
+pub fn set_adaptive_chunks(enabled: bool) {
+    ADAPTIVE_CHUNKS.store(enabled, Ordering::SeqCst);
+}
+
+pub fn adaptive_chunks() -> bool {
+    ADAPTIVE_CHUNKS.load(Ordering::SeqCst)
+}
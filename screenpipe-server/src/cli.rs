@@ -5,10 +5,17 @@ use clap_complete::{generate, Shell};
 use clap::CommandFactory;
 use screenpipe_audio::{vad::{VadSensitivity, VadEngineEnum}, core::engine::AudioTranscriptionEngine as CoreAudioTranscriptionEngine};
 use screenpipe_vision::{custom_ocr::CustomOcrConfig, utils::OcrEngine as CoreOcrEngine};
+use screenpipe_vision::diff_threshold::{parse_app_threshold, DEFAULT_DIFF_THRESHOLD};
+use screenpipe_vision::focus_trigger::DEFAULT_FOCUS_DEBOUNCE;
 use clap::ValueEnum;
 use screenpipe_core::Language;
 use screenpipe_db::OcrEngine as DBOcrEngine;
 use screenpipe_db::CustomOcrConfig as DBCustomOcrConfig;
+use crate::animated_clip::DEFAULT_MAX_ANIMATED_CLIP_SECS;
+use crate::app_quality::{parse_app_quality, CaptureQuality};
+use crate::backpressure::BackpressurePolicy;
+use crate::recording_format::RecordingFormat;
+use crate::time_tracking::TimeTrackingFormat;
 #[derive(Clone, Debug, ValueEnum, PartialEq)]
 pub enum CliAudioTranscriptionEngine {
     #[clap(name = "deepgram")]
@@ -116,6 +123,14 @@ impl From<CliVadEngine> for VadEngineEnum {
     }
 }
 
+#[derive(Clone, Debug, ValueEnum, PartialEq)]
+pub enum CliEmbeddingProvider {
+    /// The bundled model, run in-process
+    Local,
+    /// An OpenAI-compatible embeddings api such as ollama
+    Api,
+}
+
 #[derive(Clone, Debug, ValueEnum, PartialEq)]
 pub enum CliVadSensitivity {
     Low,
@@ -133,6 +148,63 @@ impl From<CliVadSensitivity> for VadSensitivity {
     }
 }
 
+#[derive(Clone, Debug, ValueEnum, PartialEq)]
+pub enum CliRecordingFormat {
+    /// Chunks of mp4 video
+    Video,
+    /// One lossless WebP image per frame
+    Webp,
+    /// One PNG image per frame
+    Png,
+}
+
+impl From<CliRecordingFormat> for RecordingFormat {
+    fn from(cli_format: CliRecordingFormat) -> Self {
+        match cli_format {
+            CliRecordingFormat::Video => RecordingFormat::Video,
+            CliRecordingFormat::Webp => RecordingFormat::Webp,
+            CliRecordingFormat::Png => RecordingFormat::Png,
+        }
+    }
+}
+
+#[derive(Clone, Debug, ValueEnum, PartialEq)]
+pub enum CliBackpressurePolicy {
+    /// Drop the oldest queued frame, capture keeps its pace
+    DropOldest,
+    /// Wait for room, capture slows down to the pace of the encoder and the indexer
+    Block,
+}
+
+impl From<CliBackpressurePolicy> for BackpressurePolicy {
+    fn from(cli_policy: CliBackpressurePolicy) -> Self {
+        match cli_policy {
+            CliBackpressurePolicy::DropOldest => BackpressurePolicy::DropOldest,
+            CliBackpressurePolicy::Block => BackpressurePolicy::Block,
+        }
+    }
+}
+
+#[derive(Clone, Debug, ValueEnum, PartialEq)]
+pub enum CliTimeTrackingFormat {
+    /// CSV for Toggl Track's import
+    TogglCsv,
+    /// JSON for `timew import`
+    Timewarrior,
+    /// iCalendar events
+    Ical,
+}
+
+impl From<CliTimeTrackingFormat> for TimeTrackingFormat {
+    fn from(cli_format: CliTimeTrackingFormat) -> Self {
+        match cli_format {
+            CliTimeTrackingFormat::TogglCsv => TimeTrackingFormat::TogglCsv,
+            CliTimeTrackingFormat::Timewarrior => TimeTrackingFormat::Timewarrior,
+            CliTimeTrackingFormat::Ical => TimeTrackingFormat::Ical,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(
     author, 
@@ -159,6 +231,10 @@ pub struct Cli {
     #[arg(short = 'p', long, default_value_t = 3030)]
     pub port: u16,
 
+    /// Also serve the gRPC control interface on this localhost port (requires the api key if one is set)
+    #[arg(long)]
+    pub grpc_port: Option<u16>,
+
     /// Disable audio recording
     #[arg(long, default_value_t = false)]
     pub disable_audio: bool,
@@ -245,10 +321,34 @@ pub struct Cli {
     #[arg(long)]
     pub included_windows: Vec<String>,
 
+    /// Also stop recording audio while a window ignored by the screen recording is focused, e.g. --ignored-windows "1Password" mutes the microphone while 1Password is in front
+    #[arg(long, default_value_t = false)]
+    pub mute_audio_on_ignored_windows: bool,
+
+    /// Don't record audio during meetings whose app or window title contains this, e.g. --private-meeting "1:1" --private-meeting "interview"
+    #[arg(long = "private-meeting")]
+    pub private_meetings: Vec<String>,
+
     /// Video chunk duration in seconds
     #[arg(long, default_value_t = 60)]
     pub video_chunk_duration: u64,
 
+    /// End video chunks exactly at the chunk duration, instead of at an app switch or a pause in activity near it
+    #[arg(long, default_value_t = false)]
+    pub fixed_chunk_duration: bool,
+
+    /// Encode every video chunk at the same quality, instead of sharper for text and more compressed for video and photos
+    #[arg(long, default_value_t = false)]
+    pub fixed_crf: bool,
+
+    /// How frames of the screen are stored: video chunks, or one image per frame in a directory per hour (indexed the same way)
+    #[arg(long, value_enum, default_value_t = CliRecordingFormat::Video)]
+    pub recording_format: CliRecordingFormat,
+
+    /// What to do with a captured frame when the encoder or the indexer falls behind and its queue is full: drop the oldest queued frame, or slow capture down until there's room
+    #[arg(long, value_enum, default_value_t = CliBackpressurePolicy::DropOldest)]
+    pub backpressure: CliBackpressurePolicy,
+
     /// Deepgram API Key for audio transcription
     #[arg(long = "deepgram-api-key")]
     pub deepgram_api_key: Option<String>,
@@ -272,6 +372,26 @@ pub struct Cli {
     /// Enable UI monitoring (macOS only)
     #[arg(long, default_value_t = false)]
     pub enable_ui_monitoring: bool,
+
+    /// Record the focused control and the buttons, tabs and fields of the focused window from the accessibility tree, searchable at /ui-events
+    #[arg(long, default_value_t = false)]
+    pub enable_ui_events: bool,
+
+    /// Snapshot the text of the focused window's accessibility tree alongside the pixels and store it in the index, searchable as ui content. Gives clean text without OCR noise, including tiny and truncated labels
+    #[arg(long, default_value_t = false)]
+    pub enable_accessibility_capture: bool,
+
+    /// Seconds between two snapshots of the accessibility tree, with --enable-accessibility-capture
+    #[arg(long, default_value_t = 5)]
+    pub accessibility_interval_secs: u64,
+
+    /// Record the text copied to the clipboard, searchable at /clipboard. Nothing copied in an ignored window is kept, and passwords, api keys, tokens and private keys are replaced with [SECRET]
+    #[arg(long, default_value_t = false)]
+    pub enable_clipboard_history: bool,
+
+    /// Milliseconds between two reads of the clipboard, with --enable-clipboard-history
+    #[arg(long, default_value_t = 1000)]
+    pub clipboard_interval_ms: u64,
     
     /// Enable experimental video frame cache (may increase CPU usage) - makes timeline UI available, frame streaming, etc.
     #[arg(long, default_value_t = true)]
@@ -281,10 +401,213 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub capture_unfocused_windows: bool,
 
+    /// Record only the focused window, leaving the rest of its monitor black, instead of whole monitors. Windows in the background are never captured (default: false)
+    #[arg(long, default_value_t = false)]
+    pub focused_window_only: bool,
+
+    /// Also capture a frame as soon as the focused window changes, once the new window has kept the focus for --focus-debounce-ms, so quick switches aren't missed at a low --fps (default: false)
+    #[arg(long, default_value_t = false)]
+    pub capture_on_focus_change: bool,
+
+    /// How long a newly focused window must keep the focus before --capture-on-focus-change captures it, in milliseconds
+    #[arg(long, default_value_t = DEFAULT_FOCUS_DEBOUNCE.as_millis() as u64)]
+    pub focus_debounce_ms: u64,
+
+    /// Average difference to the previous frame below which a frame is dropped as unchanged (default: 0.006)
+    #[arg(long, default_value_t = DEFAULT_DIFF_THRESHOLD)]
+    pub diff_threshold: f64,
+
+    /// OCR windows in bands, column by column for windows with sidebars or columns, and read again only the bands that changed since the window was last read, keeping the text of the others.
+    /// Cuts OCR cost on mostly static screens, the first frame of each window costs one OCR call per band (default: false)
+    #[arg(long, default_value_t = false)]
+    pub incremental_ocr: bool,
+
+    /// Threshold while an app is focused, as APP=THRESHOLD matching the app name case-insensitively,
+    /// e.g. --app-diff-threshold Terminal=0.05 for blinking cursors, --app-diff-threshold Grafana=0.002 for dashboards that barely change
+    #[arg(long, value_parser = parse_app_threshold)]
+    pub app_diff_threshold: Vec<(String, f64)>,
+
+    /// Resolution and quality of the video while an app is focused, as APP=SCALE or APP=SCALE:CRF matching the app name case-insensitively, SCALE being the share of the captured resolution kept,
+    /// e.g. --app-quality Code=1:16 to keep code sharp, --app-quality VLC=0.25 for videos. OCR still reads the frames at full resolution
+    #[arg(long, value_parser = parse_app_quality)]
+    pub app_quality: Vec<(String, CaptureQuality)>,
+
+    /// Write a WebVTT sidecar (<chunk>.vtt) with window titles and headings next to each video chunk (default: false)
+    #[arg(long, default_value_t = false)]
+    pub enable_subtitle_sidecars: bool,
+
+    /// Store the keyframes of each chunk at 1/4 and 1/16 width next to it for fast timeline zooming, built by the job workers (default: false)
+    #[arg(long, default_value_t = false)]
+    pub enable_frame_pyramid: bool,
+
+    /// Render a strip of thumbnails of each chunk, one a minute, next to it for scrubbable previews in the timeline and at /thumbnails, built by the job workers (default: false)
+    #[arg(long, default_value_t = false)]
+    pub enable_thumbnail_strips: bool,
+
+    /// Also push the capture live to an RTMP/RTSP/SRT url, e.g. rtmp://host/live/{monitor_id}. A live MJPEG view is always available at /stream/mjpeg/<monitor_id>
+    #[arg(long)]
+    pub stream_url: Option<String>,
+
     /// Enable pipe functionality (default: false)
     #[arg(long, default_value_t = false)]
     pub enable_pipe_manager: bool,
 
+    /// Require this key on every API request (`Authorization: Bearer <key>` or `?api_key=`), except /health
+    #[arg(long, env = "SCREENPIPE_API_KEY")]
+    pub api_key: Option<String>,
+
+    /// Record which client read or exported which time ranges through the API, viewable at /access-log
+    #[arg(long, default_value_t = false)]
+    pub enable_access_log: bool,
+
+    /// JSON file defining scheduled exports to a folder (default: exports.json in the data dir, if present)
+    #[arg(long)]
+    pub export_config: Option<PathBuf>,
+
+    /// JSON file defining where finalized chunks and their subtitles are uploaded: an S3-compatible bucket, a WebDAV folder, an rclone remote or another screenpipe started with --hub-token (default: sync.json in the data dir, if present)
+    #[arg(long)]
+    pub sync_config: Option<PathBuf>,
+
+    /// Make this instance a hub: accept the index, and segments if they send them, of other machines whose sync config targets it with this token, removing the ranges they excise, tagging their frames with the machine's hostname in search and timeline. Can be keychain:<name> to read it from the keychain
+    #[arg(long, env = "SCREENPIPE_HUB_TOKEN")]
+    pub hub_token: Option<String>,
+
+    /// Seal every finalized chunk into a tamper-evident chain: its hash, linked to the hash of the chunk sealed before, goes into a manifest next to it and the link into the video's metadata, so exported recordings can be shown unmodified with `evidence verify`
+    #[arg(long, default_value_t = false)]
+    pub seal_segments: bool,
+
+    /// Sign the evidence manifests with this Ed25519 key (PKCS#8 file), generated if it doesn't exist
+    #[arg(long)]
+    pub evidence_key: Option<PathBuf>,
+
+    /// Don't encode frames showing a screen recorded shortly before again (e.g. a lock screen coming back), index them as duplicates of it
+    #[arg(long, default_value_t = false)]
+    pub skip_duplicate_frames: bool,
+
+    /// While recording is stopped or paused through the gRPC control interface, keep the last this many seconds of frames in memory and start the recording with them when it's started again, to catch what happened just before (default: 0, off)
+    #[arg(long, default_value_t = 0)]
+    pub pre_roll_secs: u64,
+
+    /// Longest clip, in seconds, the frame export encodes as a GIF or APNG rather than refusing and asking for an mp4
+    #[arg(long, default_value_t = DEFAULT_MAX_ANIMATED_CLIP_SECS)]
+    pub max_animated_clip_secs: u64,
+
+    /// Start a new recording session after this many minutes without screen activity (0 to disable)
+    #[arg(long, default_value_t = 30)]
+    pub session_idle_minutes: u64,
+
+    /// Don't start a new recording session when the day changes (default: false)
+    #[arg(long, default_value_t = false)]
+    pub disable_day_session_split: bool,
+
+    /// While a meeting app (Zoom, Google Meet, Teams...) holds a meeting, capture at --meeting-fps and record audio, then go back to the usual settings, recording the meeting as a session tagged "meeting". Needs vision, and audio devices for the audio (default: false)
+    #[arg(long, default_value_t = false)]
+    pub enable_meeting_profile: bool,
+
+    /// Frames per second captured at least during a meeting with --enable-meeting-profile
+    #[arg(long, default_value_t = 2.0)]
+    pub meeting_fps: f64,
+
+    /// Don't lower fps, switch to a cheaper encoder or pause capture based on battery and temperature (default: false)
+    #[arg(long, default_value_t = false)]
+    pub disable_power_throttling: bool,
+
+    /// On battery, pause capture below this charge percentage (0 to never pause)
+    #[arg(long, default_value_t = 20)]
+    pub battery_pause_percent: u8,
+
+    /// Switch to low-power capture above this CPU temperature in °C (0 to ignore temperature)
+    #[arg(long, default_value_t = 90.0)]
+    pub thermal_limit_celsius: f32,
+
+    /// Fraction of the fps kept on battery, under thermal pressure or while disk or memory runs low
+    #[arg(long, default_value_t = 0.5)]
+    pub low_power_fps_factor: f64,
+
+    /// Don't lower fps and quality and skip OCR while disk space or memory runs low (default: false)
+    #[arg(long, default_value_t = false)]
+    pub disable_resource_guard: bool,
+
+    /// Switch to the degraded profile below this much free space on the disk of the data directory, in MB (0 to ignore disk space)
+    #[arg(long, default_value_t = 2048)]
+    pub min_free_disk_mb: u64,
+
+    /// Switch to the degraded profile above this much memory used by screenpipe and ffmpeg, in MB (0 to ignore memory)
+    #[arg(long, default_value_t = 4096)]
+    pub max_memory_mb: u64,
+
+    /// Don't apply changes to recording.json, excluded_regions.json and the export config while recording, only at start (default: false)
+    #[arg(long, default_value_t = false)]
+    pub disable_config_reload: bool,
+
+    /// Don't render the day strip shown at the top of the timeline each night (default: false)
+    #[arg(long, default_value_t = false)]
+    pub disable_day_mosaic: bool,
+
+    /// Local time (HH:MM) at which each monitor's recording of the day is rendered into a sped-up timelapse with the time burned in, under timelapses/ (default: disabled)
+    #[arg(long)]
+    pub timelapse_at: Option<String>,
+
+    /// How many times faster than the recording timelapses play
+    #[arg(long, default_value_t = 60)]
+    pub timelapse_speed: u32,
+
+    /// Number of background workers draining deferred jobs such as re-encoding (0 to disable)
+    #[arg(long, default_value_t = 1)]
+    pub job_workers: usize,
+
+    /// On battery, only run deferred jobs while overall cpu usage is below this percentage
+    #[arg(long, default_value_t = 20.0)]
+    pub job_max_cpu_percent: f32,
+
+    /// Leave OCR and audio transcription to the job workers, which run them on the recorded chunks when the machine is on AC power or idle, instead of during capture (default: false)
+    #[arg(long, default_value_t = false)]
+    pub defer_processing: bool,
+
+    /// Embed OCR text and transcripts in the background so /semantic-search can find them without exact keywords (default: false)
+    #[arg(long, default_value_t = false)]
+    pub enable_embeddings: bool,
+
+    /// Where embeddings come from
+    #[arg(long, value_enum, default_value_t = CliEmbeddingProvider::Api)]
+    pub embedding_provider: CliEmbeddingProvider,
+
+    /// Base url of the OpenAI-compatible embeddings api
+    #[arg(long, default_value = "http://localhost:11434/v1")]
+    pub embedding_api_url: String,
+
+    /// Model requested from the embeddings api
+    #[arg(long, default_value = "nomic-embed-text")]
+    pub embedding_model: String,
+
+    /// Key sent to the embeddings api, if it needs one
+    #[arg(long, env = "SCREENPIPE_EMBEDDING_API_KEY")]
+    pub embedding_api_key: Option<String>,
+
+    /// Base url of the OpenAI-compatible chat api that writes summaries (local ollama by default)
+    #[arg(long, default_value = "http://localhost:11434/v1")]
+    pub llm_api_url: String,
+
+    /// Model requested from the chat api for summaries
+    #[arg(long, default_value = "llama3.2")]
+    pub llm_model: String,
+
+    /// Key sent to the chat api, if it needs one
+    #[arg(long, env = "SCREENPIPE_LLM_API_KEY")]
+    pub llm_api_key: Option<String>,
+
+    /// Load a frame plugin from a dynamic library (.so, .dylib, .dll) or a .wasm module. Can be repeated. Plugins run with the same rights as screenpipe, only load ones you trust
+    #[arg(long = "plugin", value_hint = ValueHint::FilePath)]
+    pub plugins: Vec<PathBuf>,
+
+    /// Don't record anything, only serve on-demand screenshots and OCR of the current screen at /screenshot. Requires --api-key
+    #[arg(long, default_value_t = false)]
+    pub headless: bool,
+
+    /// Try screenpipe without touching your archive: record into a temporary directory deleted on exit, with every privacy setting on and nothing sent off the machine
+    #[arg(long, default_value_t = false, conflicts_with = "data_dir")]
+    pub demo: bool,
+
     #[command(subcommand)]
     pub command: Option<Command>,
 
@@ -302,6 +625,43 @@ impl Cli {
         }
         Ok(unique_langs.into_iter().collect())
     }
+    /// Turns on every privacy setting for `--demo`, and off everything that sends recordings off the machine or keeps them past the process.
+    pub fn apply_demo_privacy(&mut self) {
+        self.use_pii_removal = true;
+        self.disable_telemetry = true;
+        self.mute_audio_on_ignored_windows = true;
+        self.capture_unfocused_windows = false;
+        self.enable_access_log = true;
+        self.stream_url = None;
+        self.sync_config = None;
+        self.export_config = None;
+        self.hub_token = None;
+        self.enable_pipe_manager = false;
+        self.plugins.clear();
+        // summaries and embeddings come from the local ollama instead, if it runs
+        self.llm_api_url = "http://localhost:11434/v1".to_string();
+        self.llm_api_key = None;
+        self.embedding_api_url = "http://localhost:11434/v1".to_string();
+        self.embedding_api_key = None;
+        self.deepgram_api_key = None;
+        if self.audio_transcription_engine == CliAudioTranscriptionEngine::Deepgram {
+            self.audio_transcription_engine = CliAudioTranscriptionEngine::WhisperTinyQuantized;
+        }
+        if self.ocr_engine == CliOcrEngine::Unstructured {
+            #[cfg(target_os = "macos")]
+            {
+                self.ocr_engine = CliOcrEngine::AppleNative;
+            }
+            #[cfg(target_os = "windows")]
+            {
+                self.ocr_engine = CliOcrEngine::WindowsNative;
+            }
+            #[cfg(target_os = "linux")]
+            {
+                self.ocr_engine = CliOcrEngine::Tesseract;
+            }
+        }
+    }
     pub fn handle_completions(&self, shell: Shell) -> anyhow::Result<()> {
         let mut cmd = Self::command();
         generate(shell, &mut cmd, "screenpipe", &mut std::io::stdout());
@@ -360,6 +720,57 @@ pub enum Command {
         #[arg(long, default_value_t = false)]
         use_embedding: bool,
     },
+    /// Import a screen recording made elsewhere (mp4, mov...) into the index so it can be searched, e.g. `import meeting.mov --start "2024-11-20 14:00"`
+    Import {
+        /// Path to the video file
+        #[arg(value_hint = ValueHint::FilePath)]
+        path: PathBuf,
+        /// When the recording started: RFC 3339, "YYYY-MM-DD HH:MM" or a time today such as 14:00 or 2pm. Defaults to the creation time stored in the file
+        #[arg(long)]
+        start: Option<String>,
+        /// Data directory. Default to $HOME/.screenpipe
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        data_dir: Option<String>,
+        /// Frames per second decoded from the recording
+        #[arg(long, default_value_t = 1.0)]
+        fps: f64,
+        /// Average difference to the previous kept frame below which a frame is skipped as unchanged
+        #[arg(long, default_value_t = DEFAULT_DIFF_THRESHOLD)]
+        diff_threshold: f64,
+        /// Device the recording is indexed under, to search or delete it apart from the screens
+        #[arg(long, default_value = "imported")]
+        device_name: String,
+        /// OCR engine to use. Defaults to the one screenpipe records with
+        #[arg(long, value_enum)]
+        ocr_engine: Option<CliOcrEngine>,
+        /// Languages of the text in the recording. Defaults to the ones screenpipe records with
+        #[arg(short = 'l', long, value_enum)]
+        language: Vec<Language>,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Index the recordings of an output directory of an older version, with their activity logs, so they can be searched and are on the timeline, e.g. `backfill ~/.screenpipe/recordings`
+    Backfill {
+        /// Output directory holding the `monitor_*_*.mp4` videos and their `.jsonl` logs
+        #[arg(value_hint = ValueHint::DirPath)]
+        path: PathBuf,
+        /// Data directory. Default to $HOME/.screenpipe
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        data_dir: Option<String>,
+        /// Only index the frames, with their apps and windows, without reading their text
+        #[arg(long, default_value_t = false)]
+        skip_ocr: bool,
+        /// OCR engine to use. Defaults to the one screenpipe records with
+        #[arg(long, value_enum)]
+        ocr_engine: Option<CliOcrEngine>,
+        /// Languages of the text in the recordings. Defaults to the ones screenpipe records with
+        #[arg(short = 'l', long, value_enum)]
+        language: Vec<Language>,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
     /// Run data migrations in the background
     Migrate {
         /// The name of the migration to run
@@ -384,6 +795,121 @@ pub enum Command {
         #[arg(long, default_value_t = true)]
         continue_on_error: bool,
     },
+    /// Summarize what happened in a time range with the configured LLM, e.g. `summarize --from 14:00 --to 16:00`
+    Summarize {
+        /// Start of the range: RFC 3339, "YYYY-MM-DD HH:MM" or a time today such as 14:00 or 2pm
+        #[arg(long)]
+        from: String,
+        /// End of the range, in the same formats. Defaults to now
+        #[arg(long)]
+        to: Option<String>,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Server port
+        #[arg(short = 'p', long, default_value_t = 3030)]
+        port: u16,
+    },
+    /// Search the recordings from the command line, with the filters of the search api, e.g. `search 'app:chrome title:"jira" after:2024-05-01 text:"retry policy"'`
+    Search {
+        /// Text to search and `key:value` filters: app, title, url, type, after, before, lang, text and is:focused
+        query: String,
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Server port
+        #[arg(short = 'p', long, default_value_t = 3030)]
+        port: u16,
+    },
+    /// Export the apps used in a time range as time entries, with projects mapped by the rules in time_tracking.json in the data directory, e.g. `export-activity --from "2025-03-03 00:00" --format toggl-csv`
+    ExportActivity {
+        /// Start of the range: RFC 3339, "YYYY-MM-DD HH:MM" or a time today such as 14:00 or 2pm
+        #[arg(long)]
+        from: String,
+        /// End of the range, in the same formats. Defaults to now
+        #[arg(long)]
+        to: Option<String>,
+        /// Time tracking format
+        #[arg(short, long, value_enum)]
+        format: CliTimeTrackingFormat,
+        /// File to write, printed when not given
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+        /// Server port
+        #[arg(short = 'p', long, default_value_t = 3030)]
+        port: u16,
+    },
+    /// Export only aggregate statistics of a time range, time per category (the projects of time_tracking.json) and active vs idle time per day as JSON, without window titles or frames, e.g. to share with a manager or a study
+    ExportStats {
+        /// Start of the range: RFC 3339, "YYYY-MM-DD HH:MM" or a time today such as 14:00 or 2pm
+        #[arg(long)]
+        from: String,
+        /// End of the range, in the same formats. Defaults to now
+        #[arg(long)]
+        to: Option<String>,
+        /// Add Laplace noise with this privacy budget to every total, lower is more private, e.g. 1.0. Exact totals when not given
+        #[arg(long)]
+        epsilon: Option<f64>,
+        /// Also export the time per window, under a keyed hash of its title
+        #[arg(long, default_value_t = false)]
+        hash_titles: bool,
+        /// Key of the title hashes, the same one gives the same hashes across exports. Random when not given
+        #[arg(long)]
+        salt: Option<String>,
+        /// File to write, printed when not given
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+        /// Server port
+        #[arg(short = 'p', long, default_value_t = 3030)]
+        port: u16,
+    },
+    /// Watch what the running screenpipe records: focus changes, frames written, sessions and meetings
+    Tail {
+        /// Output format, json prints one event per line
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Show every event, not only recording activity
+        #[arg(long, default_value_t = false)]
+        all: bool,
+        /// Server port
+        #[arg(short = 'p', long, default_value_t = 3030)]
+        port: u16,
+    },
+    /// Measure what this machine can record: capture latency per monitor and method, diff cost per algorithm, OCR cost with --ocr-engine and encode throughput per codec and preset, with a recommended --fps
+    Bench {
+        /// Captures, diffs and OCR reads measured per monitor, method and algorithm
+        #[arg(long, default_value_t = 10)]
+        samples: usize,
+        /// Frames encoded per codec and preset
+        #[arg(long, default_value_t = 30)]
+        encode_frames: usize,
+        /// Skip the encode benchmark, the slowest part
+        #[arg(long, default_value_t = false)]
+        skip_encode: bool,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Print the layout of the data directory, the database schema and the manifest formats as JSON, for tools reading recordings directly
+    Schema,
+    /// Run screenpipe in the background from login: a systemd user service, launchd agent or scheduled task
+    Service {
+        #[command(subcommand)]
+        subcommand: ServiceCommand,
+    },
+    /// Store api keys and passwords in the OS keychain rather than in flags and configs, e.g. `secrets set llm-api-key`
+    Secrets {
+        #[command(subcommand)]
+        subcommand: SecretsCommand,
+    },
+    /// Check recordings sealed with --seal-segments: that no video was modified, removed or reordered since it was recorded
+    Evidence {
+        #[command(subcommand)]
+        subcommand: EvidenceCommand,
+    },
     /// Generate shell completions
     Completions {
         /// The shell to generate completions for
@@ -539,6 +1065,69 @@ pub enum McpCommand {
     },
 }
 
+#[derive(Subcommand)]
+pub enum SecretsCommand {
+    /// Store a secret, its value is read from stdin. Known names are used when their flag isn't given, other names are read where a config has "keychain:<name>"
+    Set {
+        /// Name of the secret, e.g. llm-api-key or s3-secret-access-key
+        name: String,
+    },
+    /// Remove a secret from the keychain
+    Delete {
+        /// Name of the secret
+        name: String,
+    },
+    /// Show which of the secrets screenpipe reads by name are stored
+    List {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EvidenceCommand {
+    /// Verify the manifests given, or found in the folders given, against their videos and each other, e.g. `evidence verify ~/.screenpipe/data/laptop/2025-03-01`
+    Verify {
+        /// Evidence manifests, or folders holding them
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Public key the manifests must be signed with, as printed by `evidence public-key`
+        #[arg(long)]
+        public_key: Option<String>,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Print the public key of an --evidence-key, generating the key if there is none yet, to hand to whoever verifies the recordings
+    PublicKey {
+        /// The key file
+        key: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ServiceCommand {
+    /// Start screenpipe at every login, and now. Recording flags go after `--`, e.g. `screenpipe service install -- --fps 0.5`
+    Install {
+        /// Flags the service runs screenpipe with
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Stop screenpipe and no longer start it at login
+    Uninstall,
+    /// Start the installed service
+    Start,
+    /// Stop the service until the next login or `service start`
+    Stop,
+    /// Show whether the service is installed and running
+    Status {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+}
+
 #[derive(Clone, Debug, ValueEnum, PartialEq)]
 pub enum OutputFormat {
     Text,
@@ -0,0 +1,217 @@
+use crate::ui_events::recordable_foreground_window;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_core::pii_removal::remove_pii;
+use screenpipe_db::{DatabaseManager, NewClipboardEntry};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Replaces the secrets found in copied text.
+pub const SECRET_PLACEHOLDER: &str = "[SECRET]";
+/// Characters kept of a copied text, the rest of a copied file or log is dropped.
+const MAX_TEXT_CHARS: usize = 10_000;
+
+/// Tokens and keys whose format gives them away.
+static SECRET_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // private keys, whole blocks
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+        // aws access keys
+        r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+        // github tokens
+        r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+        r"\bgithub_pat_[A-Za-z0-9_]{22,}\b",
+        // slack tokens
+        r"\bxox[abprs]-[A-Za-z0-9-]{10,}\b",
+        // openai, anthropic and stripe style api keys
+        r"\b(?:sk|pk|rk)[-_](?:live_|test_|proj-|ant-)?[A-Za-z0-9_-]{20,}\b",
+        // json web tokens
+        r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}\b",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+/// `password = hunter2` and the like, the name is kept and the value replaced.
+static SECRET_ASSIGNMENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)\b((?:password|passwd|pwd|secret|token|api[_-]?key|access[_-]?key|client[_-]?secret)["']?\s*[:=]\s*)["']?[^\s"',;]+["']?"#,
+    )
+    .unwrap()
+});
+
+/// Bits of Shannon entropy per character of `text`, how varied its characters are.
+fn entropy_per_char(text: &str) -> f64 {
+    let mut counts = HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+    let length = text.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Whether a copied text is a single word that looks like a password or a generated key: 8 to
+/// 128 characters, that isn't a link, a path or an email address, mixing letters with digits
+/// or symbols, and whose characters are as varied as a random string's, at least 3 bits of
+/// entropy per character. Words, identifiers and dates repeat too many characters to be.
+pub fn looks_like_password(text: &str) -> bool {
+    let length = text.chars().count();
+    if !(8..=128).contains(&length) || text.chars().any(char::is_whitespace) {
+        return false;
+    }
+    if text.contains("://") || text.starts_with('/') || text.starts_with('~') {
+        return false;
+    }
+    if text
+        .split_once('@')
+        .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+    {
+        return false;
+    }
+    let letters = text.chars().any(char::is_alphabetic);
+    let others = text.chars().any(|c| !c.is_alphabetic());
+    letters && others && entropy_per_char(text) >= 3.0
+}
+
+/// Replaces the secrets in a copied text with [`SECRET_PLACEHOLDER`], returns the text and
+/// whether anything was replaced.
+pub fn redact_secrets(text: &str) -> (String, bool) {
+    if looks_like_password(text.trim()) {
+        return (SECRET_PLACEHOLDER.to_string(), true);
+    }
+    let mut redacted = text.to_string();
+    for pattern in SECRET_PATTERNS.iter() {
+        redacted = pattern
+            .replace_all(&redacted, SECRET_PLACEHOLDER)
+            .into_owned();
+    }
+    redacted = SECRET_ASSIGNMENT
+        .replace_all(&redacted, format!("${{1}}{}", SECRET_PLACEHOLDER))
+        .into_owned();
+    let changed = redacted != text;
+    (redacted, changed)
+}
+
+/// Prepares a copied text for storage: cut to [`MAX_TEXT_CHARS`], its secrets redacted and,
+/// with `use_pii_removal`, its emails, phone and card numbers too.
+pub fn sanitize_clipboard_text(text: &str, use_pii_removal: bool) -> (String, bool) {
+    let text: String = text.chars().take(MAX_TEXT_CHARS).collect();
+    let (mut sanitized, mut redacted) = redact_secrets(&text);
+    if use_pii_removal {
+        let without_pii = remove_pii(&sanitized);
+        redacted |= without_pii != sanitized;
+        sanitized = without_pii;
+    }
+    (sanitized, redacted)
+}
+
+/// A text copied to the clipboard and the window in the foreground when it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardCopy {
+    pub text: String,
+    pub app_name: String,
+    pub window_name: String,
+}
+
+/// Decides which clipboard reads are new copies worth recording.
+#[derive(Debug, Default)]
+pub struct ClipboardTracker {
+    last: Option<String>,
+}
+
+impl ClipboardTracker {
+    /// Whether `text` is a copy not observed yet.
+    pub fn is_new(&self, text: &str) -> bool {
+        !text.trim().is_empty() && self.last.as_deref() != Some(text)
+    }
+
+    /// Returns the copy to record, if `text` is a new copy. `foreground` is the window in the
+    /// foreground, `None` when it's unknown, ignored by the capture policy or capture is
+    /// paused: the copy is never recorded then, not even once the user switches to another
+    /// window.
+    pub fn observe(
+        &mut self,
+        text: &str,
+        foreground: Option<(String, String)>,
+    ) -> Option<ClipboardCopy> {
+        if !self.is_new(text) {
+            return None;
+        }
+        self.last = Some(text.to_string());
+        let (app_name, window_name) = foreground?;
+        Some(ClipboardCopy {
+            text: text.to_string(),
+            app_name,
+            window_name,
+        })
+    }
+}
+
+/// Polls the clipboard every `interval` and stores the texts copied outside of the ignored
+/// windows while capture isn't paused, with their secrets redacted, searchable at `/clipboard`.
+pub async fn record_clipboard(db: Arc<DatabaseManager>, interval: Duration, use_pii_removal: bool) {
+    let (sender, mut receiver) = mpsc::channel::<ClipboardCopy>(16);
+
+    // the clipboard handle isn't Send on every platform, so it lives on a thread of its own
+    std::thread::spawn(move || {
+        let mut clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                warn!(
+                    "clipboard history disabled, the clipboard is not available: {}",
+                    e
+                );
+                return;
+            }
+        };
+        info!("recording clipboard history every {:?}", interval);
+        let mut tracker = ClipboardTracker::default();
+        loop {
+            // errors are expected while the clipboard holds an image or nothing at all
+            if let Ok(text) = clipboard.get_text() {
+                // the foreground window is only looked up for new copies
+                let copy = tracker
+                    .is_new(&text)
+                    .then(|| tracker.observe(&text, recordable_foreground_window()))
+                    .flatten();
+                if let Some(copy) = copy {
+                    if sender.blocking_send(copy).is_err() {
+                        break;
+                    }
+                }
+            }
+            std::thread::sleep(interval);
+        }
+    });
+
+    while let Some(copy) = receiver.recv().await {
+        let (text, redacted) = sanitize_clipboard_text(&copy.text, use_pii_removal);
+        let entry = NewClipboardEntry {
+            timestamp: Utc::now(),
+            text,
+            app_name: Some(copy.app_name),
+            window_name: Some(copy.window_name),
+            redacted,
+        };
+        match db.insert_clipboard_entry(&entry).await {
+            Ok(id) => debug!(
+                "stored clipboard entry {} ({} chars, redacted: {})",
+                id,
+                entry.text.len(),
+                redacted
+            ),
+            Err(e) => error!("failed to store clipboard entry: {}", e),
+        }
+    }
+}
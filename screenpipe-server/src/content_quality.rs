@@ -0,0 +1,105 @@
+use image::DynamicImage;
+use screenpipe_vision::CaptureResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Frames are measured on a copy this large at most, small enough to classify quickly and
+/// large enough to resolve text edges.
+const SAMPLE_WIDTH: u32 = 320;
+const SAMPLE_HEIGHT: u32 = 200;
+/// Gradient below which a pixel is part of a flat area, like a window background.
+const FLAT_GRADIENT: u16 = 4;
+/// Gradient above which a pixel is on a sharp edge, like the stroke of a glyph.
+const EDGE_GRADIENT: u16 = 48;
+/// OCR text longer than this makes a frame text-heavy whatever its edges.
+const TEXT_HEAVY_CHARS: usize = 400;
+
+/// What a frame shows, as far as the encoder is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    /// Documents, code, chats: sharp glyphs on flat backgrounds that blur first.
+    Text,
+    Mixed,
+    /// Video, photos, games: smooth gradients where compression artifacts don't show.
+    Photographic,
+}
+
+/// Share of sharp edge pixels and of flat pixels in a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeStats {
+    pub edge_ratio: f64,
+    pub flat_ratio: f64,
+}
+
+pub fn edge_stats(image: &DynamicImage) -> EdgeStats {
+    let gray = image.thumbnail(SAMPLE_WIDTH, SAMPLE_HEIGHT).to_luma8();
+    let (width, height) = gray.dimensions();
+    if width < 2 || height < 2 {
+        return EdgeStats {
+            edge_ratio: 0.0,
+            flat_ratio: 1.0,
+        };
+    }
+
+    let (mut edges, mut flat) = (0u32, 0u32);
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let pixel = gray.get_pixel(x, y)[0] as i16;
+            let right = gray.get_pixel(x + 1, y)[0] as i16;
+            let below = gray.get_pixel(x, y + 1)[0] as i16;
+            let gradient = (pixel - right).unsigned_abs() + (pixel - below).unsigned_abs();
+            if gradient < FLAT_GRADIENT {
+                flat += 1;
+            } else if gradient > EDGE_GRADIENT {
+                edges += 1;
+            }
+        }
+    }
+    let total = ((width - 1) * (height - 1)) as f64;
+    EdgeStats {
+        edge_ratio: edges as f64 / total,
+        flat_ratio: flat as f64 / total,
+    }
+}
+
+/// Classifies a frame from its edges and the length of the text OCR found in it. Screens of
+/// text are mostly flat with many sharp edges, photographic content has few flat areas.
+pub fn classify_content(stats: EdgeStats, ocr_chars: usize) -> ContentKind {
+    if stats.flat_ratio < 0.4 {
+        ContentKind::Photographic
+    } else if ocr_chars >= TEXT_HEAVY_CHARS || (stats.edge_ratio > 0.04 && stats.flat_ratio > 0.6) {
+        ContentKind::Text
+    } else {
+        ContentKind::Mixed
+    }
+}
+
+pub fn classify_frame(frame: &CaptureResult) -> ContentKind {
+    let ocr_chars = frame
+        .window_ocr_results
+        .iter()
+        .map(|window| window.text.len())
+        .sum();
+    classify_content(edge_stats(&frame.image), ocr_chars)
+}
+
+/// CRF of a chunk showing `content`, lower is sharper. The base values are those used for
+/// every chunk without dynamic CRF, for x265 and for the x264 of low power mode.
+pub fn chunk_crf(content: ContentKind, low_power: bool) -> u8 {
+    let base = if low_power { 28 } else { 23 };
+    match content {
+        ContentKind::Text => base - 4,
+        ContentKind::Mixed => base,
+        ContentKind::Photographic => base + 5,
+    }
+}
+
+static DYNAMIC_CRF: AtomicBool = AtomicBool::new(true);
+
+/// Whether each chunk's CRF follows what its first frame shows rather than being fixed.
+pub fn set_dynamic_crf(enabled: bool) {
+    DYNAMIC_CRF.store(enabled, Ordering::SeqCst);
+}
+
+pub fn dynamic_crf() -> bool {
+    DYNAMIC_CRF.load(Ordering::SeqCst)
+}
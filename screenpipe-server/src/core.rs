@@ -1,17 +1,57 @@
+use crate::categorization::categorize;
+use crate::dedup::RecentScreens;
+use crate::deferred_ocr::{deferred_ocr, OCR_CHUNK_JOB, OCR_CHUNK_MAX_ATTEMPTS};
+use crate::evidence::{evidence_config, SEAL_MAX_ATTEMPTS, SEAL_SEGMENT_JOB};
+use crate::jobs::{enqueue_job, ChunkJobPayload, REENCODE_CHUNK_JOB};
+use crate::live_events::{BlockedWindowTracker, SegmentRotated};
+use crate::ocr_language::{ocr_language_code, tag_block_languages};
+use crate::permissions::{wait_for_permission, Capability, PERMISSION_CHECK_INTERVAL};
+use crate::plugins::{ActivityChange, FinalizedSegment, PluginHost};
+use crate::power::current_throttle;
+use crate::pre_roll::captured_at;
+use crate::presentation;
+use crate::pyramid::BUILD_PYRAMID_JOB;
+use crate::recording_format::{recording_format, write_frame_image};
+use crate::sessions::SessionTracker;
+use crate::storage::{JournalRecord, Storage};
+use crate::streaming::{stream_monitor_to_url, stream_url_for_monitor};
+use crate::subtitles::{queue_chunk_sidecar, write_ready_sidecars};
+use crate::thumbnails::{BUILD_THUMBNAIL_STRIP_JOB, THUMBNAIL_MAX_ATTEMPTS};
+use crate::upload::{sync_config, SyncConfig, UPLOAD_MAX_ATTEMPTS, UPLOAD_SEGMENT_JOB};
 use crate::VideoCapture;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use futures::future::join_all;
+use futures::StreamExt;
+use screenpipe_core::capture_policy::{
+    capture_policy, report_meeting_ended, report_meeting_started,
+};
 use screenpipe_core::pii_removal::remove_pii;
 use screenpipe_core::Language;
 use screenpipe_db::{DatabaseManager, Speaker};
-use screenpipe_events::{poll_meetings_events, send_event};
-use screenpipe_vision::core::WindowOcr;
+use screenpipe_events::{poll_meetings_events, send_event, subscribe_to_all_events};
+use screenpipe_vision::core::{WindowOcr, WindowOcrResult};
 use screenpipe_vision::OcrEngine;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Handle;
 use tracing::{debug, error, info, warn};
 
+/// Sent as `frame_written` once a window of a frame and its text are stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameWritten {
+    pub frame_id: i64,
+    pub monitor_id: u32,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub focused: bool,
+    pub text_length: usize,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn start_continuous_recording(
     db: Arc<DatabaseManager>,
@@ -23,13 +63,20 @@ pub async fn start_continuous_recording(
     use_pii_removal: bool,
     vision_disabled: bool,
     vision_handle: &Handle,
-    ignored_windows: &[String],
-    include_windows: &[String],
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
     realtime_vision: bool,
+    subtitle_sidecars: bool,
+    stream_url: Option<String>,
+    session_tracker: Option<Arc<SessionTracker>>,
+    frame_pyramid: bool,
+    thumbnail_strips: bool,
+    plugins: Arc<PluginHost>,
 ) -> Result<()> {
     info!("Starting video recording for monitors {:?}", monitor_ids);
+    if !vision_disabled {
+        wait_for_permission(Capability::ScreenRecording, PERMISSION_CHECK_INTERVAL).await;
+    }
     let video_tasks = if !vision_disabled {
         monitor_ids
             .iter()
@@ -37,10 +84,18 @@ pub async fn start_continuous_recording(
                 let db_manager_video = Arc::clone(&db);
                 let output_path_video = Arc::clone(&output_path);
                 let ocr_engine = Arc::clone(&ocr_engine);
-                let ignored_windows_video = ignored_windows.to_vec();
-                let include_windows_video = include_windows.to_vec();
 
                 let languages = languages.clone();
+                let session_tracker = session_tracker.clone();
+                let plugins = plugins.clone();
+
+                if let Some(stream_url) = &stream_url {
+                    vision_handle.spawn(stream_monitor_to_url(
+                        monitor_id,
+                        stream_url_for_monitor(stream_url, monitor_id),
+                        fps,
+                    ));
+                }
 
                 info!("Starting video recording for monitor {}", monitor_id);
                 vision_handle.spawn(async move {
@@ -54,12 +109,15 @@ pub async fn start_continuous_recording(
                             ocr_engine.clone(),
                             monitor_id,
                             use_pii_removal,
-                            &ignored_windows_video,
-                            &include_windows_video,
                             video_chunk_duration,
                             languages.clone(),
                             capture_unfocused_windows,
                             realtime_vision,
+                            subtitle_sidecars,
+                            session_tracker.clone(),
+                            frame_pyramid,
+                            thumbnail_strips,
+                            plugins.clone(),
                         )
                         .await
                         {
@@ -86,6 +144,7 @@ pub async fn start_continuous_recording(
     };
 
     if !vision_disabled {
+        vision_handle.spawn(follow_meetings());
         vision_handle.spawn(async move {
             info!("Starting meeting events polling");
             match poll_meetings_events().await {
@@ -110,6 +169,20 @@ pub async fn start_continuous_recording(
     Ok(())
 }
 
+/// Keeps the capture policy informed of meetings, so that audio of private ones is muted.
+async fn follow_meetings() {
+    let mut events = subscribe_to_all_events();
+    while let Some(event) = events.next().await {
+        match event.name.as_str() {
+            "meeting_started" => {
+                report_meeting_started(event.data["app"].as_str().unwrap_or_default())
+            }
+            "meeting_ended" => report_meeting_ended(),
+            _ => {}
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn record_video(
     db: Arc<DatabaseManager>,
@@ -118,12 +191,15 @@ async fn record_video(
     ocr_engine: Arc<OcrEngine>,
     monitor_id: u32,
     use_pii_removal: bool,
-    ignored_windows: &[String],
-    include_windows: &[String],
     video_chunk_duration: Duration,
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
     realtime_vision: bool,
+    subtitle_sidecars: bool,
+    session_tracker: Option<Arc<SessionTracker>>,
+    frame_pyramid: bool,
+    thumbnail_strips: bool,
+    plugins: Arc<PluginHost>,
 ) -> Result<()> {
     info!("record_video: Starting for monitor {}", monitor_id);
     let device_name = Arc::new(format!("monitor_{}", monitor_id));
@@ -141,18 +217,92 @@ async fn record_video(
     let new_chunk_callback = {
         let db_clone = Arc::clone(&db);
         let device_name_clone = Arc::clone(&device_name);
+        let plugins = Arc::clone(&plugins);
+        // whether the chunk being replaced was encoded in low-power mode
+        let previous_low_power = AtomicBool::new(false);
         move |file_path: &str| {
             let file_path = file_path.to_string();
             let db = Arc::clone(&db_clone);
             let device_name = Arc::clone(&device_name_clone);
+            let plugins = Arc::clone(&plugins);
+            let reencode_previous =
+                previous_low_power.swap(current_throttle().is_low_power(), Ordering::SeqCst);
 
             // Just spawn the task directly
             tokio::spawn(async move {
+                // the chunk being replaced is complete: queue its subtitles sidecar and its
+                // post-processing
+                let upload = sync_config().is_some_and(SyncConfig::uploads_segments);
+                let seal = evidence_config().is_some();
+                let ocr = deferred_ocr().is_some();
+                if subtitle_sidecars
+                    || reencode_previous
+                    || ocr
+                    || frame_pyramid
+                    || thumbnail_strips
+                    || seal
+                    || upload
+                    || !plugins.is_empty()
+                {
+                    match db.get_latest_video_chunk_path(&device_name).await {
+                        Ok(Some(previous_chunk)) => {
+                            plugins.on_segment_finalized(&FinalizedSegment {
+                                monitor_id,
+                                device_name: device_name.to_string(),
+                                video_path: previous_chunk.clone(),
+                                finalized_at: Utc::now(),
+                            });
+                            // the OCR of its last frames is still queued, the sidecar is
+                            // written once they're indexed, or by the job OCRing the chunk
+                            if subtitle_sidecars && !ocr {
+                                queue_chunk_sidecar(&previous_chunk, Utc::now());
+                            }
+                            let payload = ChunkJobPayload {
+                                video_path: previous_chunk.clone(),
+                            };
+                            // jobs are claimed in order, so the re-encode is picked up first,
+                            // the seal once the video is final and the upload last
+                            let jobs = [
+                                (reencode_previous, REENCODE_CHUNK_JOB, 3),
+                                (ocr, OCR_CHUNK_JOB, OCR_CHUNK_MAX_ATTEMPTS),
+                                (frame_pyramid, BUILD_PYRAMID_JOB, 3),
+                                (
+                                    thumbnail_strips,
+                                    BUILD_THUMBNAIL_STRIP_JOB,
+                                    THUMBNAIL_MAX_ATTEMPTS,
+                                ),
+                                (seal, SEAL_SEGMENT_JOB, SEAL_MAX_ATTEMPTS),
+                                (upload, UPLOAD_SEGMENT_JOB, UPLOAD_MAX_ATTEMPTS),
+                            ];
+                            for (_, kind, max_attempts) in
+                                jobs.iter().filter(|(enabled, _, _)| *enabled)
+                            {
+                                if let Err(e) =
+                                    enqueue_job(&db, kind, &payload, *max_attempts).await
+                                {
+                                    warn!("Failed to queue {} of {}: {}", kind, previous_chunk, e);
+                                }
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to get previous video chunk: {}", e),
+                    }
+                }
+
                 debug!("Inserting new video chunk: {}", file_path);
                 if let Err(e) = db.insert_video_chunk(&file_path, &device_name).await {
                     error!("Failed to insert new video chunk: {}", e);
                 } else {
                     debug!("Successfully inserted video chunk: {}", file_path);
+                    let _ = send_event(
+                        "segment_rotated",
+                        SegmentRotated {
+                            monitor_id,
+                            device_name: device_name.to_string(),
+                            video_path: file_path,
+                            timestamp: Utc::now(),
+                        },
+                    );
                 }
             });
         }
@@ -166,10 +316,11 @@ async fn record_video(
         new_chunk_callback,
         Arc::clone(&ocr_engine),
         monitor_id,
-        ignored_windows,
-        include_windows,
         languages,
         capture_unfocused_windows,
+        session_tracker,
+        Arc::clone(&plugins),
+        use_pii_removal,
     );
 
     info!(
@@ -178,6 +329,28 @@ async fn record_video(
     );
     let mut last_frame_time = std::time::Instant::now();
     let mut frames_processed = 0;
+    // (app, window) last focused, to tell plugins and event subscribers when it changes
+    let mut focused_window: Option<(String, String)> = None;
+    let mut blocked_window = BlockedWindowTracker::default();
+    // (app, window, frame id) of the screens sent to the video, for the duplicates coming back
+    let mut encoded_screens = RecentScreens::<(u64, Vec<(String, String, i64)>)>::default();
+    // set when frames are kept as images rather than video
+    let image_format = recording_format().image_format();
+    let storage = Storage::new(output_path.as_str());
+    // frames of the video are marked indexed in the journal the encoder wrote them to
+    let journal = if image_format.is_none() {
+        storage
+            .frame_journal(monitor_id)
+            .map_err(|e| {
+                warn!(
+                    "failed to open the frame journal of monitor {}: {}",
+                    monitor_id, e
+                )
+            })
+            .ok()
+    } else {
+        None
+    };
 
     // Keep count of consecutive errors to detect unhealthy state
     let mut consecutive_db_errors = 0;
@@ -223,7 +396,8 @@ async fn record_video(
             }
         }
 
-        if let Some(frame) = video_capture.ocr_frame_queue.pop() {
+        if let Some(queued) = video_capture.ocr_frame_queue.pop() {
+            let frame = queued.result;
             let time_since_last_frame = last_frame_time.elapsed();
             last_frame_time = std::time::Instant::now();
             frames_processed += 1;
@@ -235,18 +409,183 @@ async fn record_video(
                 time_since_last_frame.as_millis()
             );
 
+            let focused_app = frame
+                .window_ocr_results
+                .iter()
+                .find(|window_result| window_result.focused)
+                .map(|window_result| window_result.app_name.as_str());
+            presentation::observe_frame(monitor_id, focused_app, &frame);
+            // the windows the policy ignores aren't in the frame, only reported as focused by
+            // the capture of the monitor they're on
+            if let Some(blocked) = blocked_window.observe(
+                monitor_id,
+                screenpipe_core::capture_policy::focused_window_on(monitor_id),
+                &capture_policy(),
+            ) {
+                let _ = send_event("app_blocked", blocked);
+            }
+
+            // a frame kept out of the video is indexed as a duplicate of the frames of its screen
+            let canonical_frames = if queued.encoded {
+                None
+            } else {
+                let found = encoded_screens
+                    .find_where(queued.perceptual_hash, |(text, _)| {
+                        *text == queued.text_fingerprint
+                    })
+                    .map(|(_, frames)| frames.clone());
+                if found.is_none() {
+                    warn!(
+                        "no frame of the screen of monitor {} was indexed, neither is its duplicate unless kept as an image",
+                        monitor_id
+                    );
+                }
+                found
+            };
+            let mut indexed_frames = Vec::new();
+
+            // frames of the pre-roll are indexed well after they were captured
+            let captured_at = captured_at(frame.timestamp);
+            let image_path = match image_format {
+                // duplicates point at the image of their screen, unless it wasn't indexed
+                Some(format)
+                    if canonical_frames.is_none() && !frame.window_ocr_results.is_empty() =>
+                {
+                    let storage = storage.clone();
+                    let image_frame = frame.clone();
+                    match tokio::task::spawn_blocking(move || {
+                        write_frame_image(
+                            &storage,
+                            monitor_id,
+                            captured_at,
+                            &image_frame.image,
+                            format,
+                        )
+                    })
+                    .await
+                    {
+                        Ok(Ok(path)) => Some(path.to_string_lossy().into_owned()),
+                        Ok(Err(e)) => {
+                            error!(
+                                "Failed to write frame image of monitor {}: {}",
+                                monitor_id, e
+                            );
+                            None
+                        }
+                        Err(e) => {
+                            error!("Frame image task of monitor {} failed: {}", monitor_id, e);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+
             for window_result in &frame.window_ocr_results {
+                if window_result.focused {
+                    let current = (
+                        window_result.app_name.clone(),
+                        window_result.window_name.clone(),
+                    );
+                    if focused_window.as_ref() != Some(&current) {
+                        let previous = focused_window.replace(current);
+                        let change = ActivityChange {
+                            monitor_id,
+                            timestamp: Utc::now(),
+                            app_name: window_result.app_name.clone(),
+                            window_name: window_result.window_name.clone(),
+                            browser_url: window_result.browser_url.clone(),
+                            previous_app_name: previous.as_ref().map(|(app, _)| app.clone()),
+                            previous_window_name: previous.map(|(_, window)| window),
+                        };
+                        plugins.on_activity_change(&change);
+                        let _ = send_event("activity_changed", change);
+                    }
+                }
+
+                let category = categorize(
+                    &window_result.app_name,
+                    &window_result.window_name,
+                    window_result.browser_url.as_deref(),
+                );
+                if let Some(canonical_frames) = &canonical_frames {
+                    let canonical_id = canonical_frames
+                        .iter()
+                        .find(|(app_name, window_name, _)| {
+                            *app_name == window_result.app_name
+                                && *window_name == window_result.window_name
+                        })
+                        .or_else(|| canonical_frames.first())
+                        .map(|(_, _, frame_id)| *frame_id);
+                    if let Some(canonical_id) = canonical_id {
+                        match db
+                            .insert_duplicate_frame(
+                                canonical_id,
+                                None,
+                                window_result.browser_url.as_deref(),
+                                Some(window_result.app_name.as_str()),
+                                Some(window_result.window_name.as_str()),
+                                window_result.focused,
+                                queued.perceptual_hash,
+                                category.as_deref(),
+                            )
+                            .await
+                        {
+                            Ok(frame_id) => {
+                                debug!("Frame {} is a duplicate of {}", frame_id, canonical_id);
+                                consecutive_db_errors = 0;
+                            }
+                            Err(e) => {
+                                warn!("Failed to insert duplicate frame: {}", e);
+                                consecutive_db_errors += 1;
+                            }
+                        }
+                        continue;
+                    }
+                    // not in the video, an offset of its own would point at another frame
+                    if image_format.is_none() {
+                        continue;
+                    }
+                } else if !queued.encoded && image_format.is_none() {
+                    continue;
+                }
+
+                let text = if use_pii_removal {
+                    remove_pii(&window_result.text)
+                } else {
+                    window_result.text.clone()
+                };
+
                 let insert_frame_start = std::time::Instant::now();
-                let result = db
-                    .insert_frame(
-                        &device_name,
-                        None,
-                        window_result.browser_url.as_deref(),
-                        Some(window_result.app_name.as_str()),
-                        Some(window_result.window_name.as_str()),
-                        window_result.focused,
-                    )
-                    .await;
+                let result = match (image_format, &image_path) {
+                    (None, _) => {
+                        db.insert_frame(
+                            &device_name,
+                            Some(captured_at),
+                            window_result.browser_url.as_deref(),
+                            Some(window_result.app_name.as_str()),
+                            Some(window_result.window_name.as_str()),
+                            window_result.focused,
+                            category.as_deref(),
+                        )
+                        .await
+                    }
+                    (Some(_), Some(image_path)) => {
+                        db.insert_image_frame(
+                            &device_name,
+                            image_path,
+                            Some(captured_at),
+                            window_result.browser_url.as_deref(),
+                            Some(window_result.app_name.as_str()),
+                            Some(window_result.window_name.as_str()),
+                            window_result.focused,
+                            category.as_deref(),
+                        )
+                        .await
+                    }
+                    // the image couldn't be written, there is nothing to point the frame at
+                    (Some(_), None) => continue,
+                };
 
                 let insert_duration = insert_frame_start.elapsed();
                 if insert_duration.as_millis() > 100 {
@@ -263,14 +602,20 @@ async fn record_video(
                             frame_id,
                             insert_duration.as_millis()
                         );
-                        let text_json =
-                            serde_json::to_string(&window_result.text_json).unwrap_or_default();
-
-                        let text = if use_pii_removal {
-                            &remove_pii(&window_result.text)
-                        } else {
-                            &window_result.text
-                        };
+                        indexed_frames.push((
+                            window_result.app_name.clone(),
+                            window_result.window_name.clone(),
+                            frame_id,
+                        ));
+                        if let Err(e) = db
+                            .set_frame_perceptual_hash(frame_id, queued.perceptual_hash)
+                            .await
+                        {
+                            warn!("failed to store hash of frame {}: {}", frame_id, e);
+                        }
+                        let mut text_blocks = window_result.text_json.clone();
+                        let block_languages = tag_block_languages(&mut text_blocks);
+                        let text_json = serde_json::to_string(&text_blocks).unwrap_or_default();
 
                         if realtime_vision {
                             let send_event_start = std::time::Instant::now();
@@ -279,7 +624,7 @@ async fn record_video(
                                 WindowOcr {
                                     image: Some(frame.image.clone()),
                                     text: text.clone(),
-                                    text_json: window_result.text_json.clone(),
+                                    text_json: text_blocks.clone(),
                                     app_name: window_result.app_name.clone(),
                                     window_name: window_result.window_name.clone(),
                                     focused: window_result.focused,
@@ -301,36 +646,27 @@ async fn record_video(
                             }
                         }
 
-                        let insert_ocr_start = std::time::Instant::now();
-                        if let Err(e) = db
-                            .insert_ocr_text(
-                                frame_id,
-                                text,
-                                &text_json,
-                                Arc::new((*ocr_engine).clone().into()),
-                            )
-                            .await
+                        match store_window_text(
+                            &db,
+                            frame_id,
+                            monitor_id,
+                            window_result,
+                            &text,
+                            &text_json,
+                            &block_languages,
+                            &ocr_engine,
+                        )
+                        .await
                         {
-                            error!(
-                                "Failed to insert OCR text: {}, skipping window {} of frame {}",
-                                e, window_result.window_name, frame_id
-                            );
-                            consecutive_db_errors += 1;
-                            continue;
-                        } else {
-                            let ocr_insert_duration = insert_ocr_start.elapsed();
-                            if ocr_insert_duration.as_millis() > 100 {
-                                warn!(
-                                    "Slow DB insert_ocr_text operation: {}ms",
-                                    ocr_insert_duration.as_millis()
+                            Ok(()) => consecutive_db_errors = 0,
+                            Err(e) => {
+                                error!(
+                                    "Failed to insert OCR text: {}, skipping window {} of frame {}",
+                                    e, window_result.window_name, frame_id
                                 );
+                                consecutive_db_errors += 1;
+                                continue;
                             }
-                            consecutive_db_errors = 0; // Reset on success
-                            debug!(
-                                "OCR text inserted for frame {} in {}ms",
-                                frame_id,
-                                ocr_insert_duration.as_millis()
-                            );
                         }
                     }
                     Err(e) => {
@@ -341,6 +677,26 @@ async fn record_video(
                     }
                 }
             }
+            if queued.encoded {
+                encoded_screens.insert(
+                    queued.perceptual_hash,
+                    (queued.text_fingerprint, indexed_frames),
+                );
+                if let Some(journal) = &journal {
+                    let record = JournalRecord::Indexed {
+                        frame_number: frame.frame_number,
+                    };
+                    if let Err(e) = journal.append(&record) {
+                        warn!(
+                            "failed to journal frame {} as indexed: {}",
+                            frame.frame_number, e
+                        );
+                    }
+                }
+            }
+            if subtitle_sidecars {
+                write_ready_sidecars(&db, &device_name, captured_at).await;
+            }
         } else {
             // Log when frame queue is empty
             if heartbeat_counter % 10 == 0 {
@@ -366,6 +722,74 @@ async fn record_video(
     }
 }
 
+/// Stores the text of a window of a frame and sends `frame_written`. While OCR is deferred
+/// the text is read by the job OCRing the chunk once it's finalized, so the event is sent
+/// as soon as the frame is stored.
+#[allow(clippy::too_many_arguments)]
+pub async fn store_window_text(
+    db: &DatabaseManager,
+    frame_id: i64,
+    monitor_id: u32,
+    window: &WindowOcrResult,
+    text: &str,
+    text_json: &str,
+    block_languages: &[(String, u32)],
+    ocr_engine: &OcrEngine,
+) -> Result<(), sqlx::Error> {
+    if deferred_ocr().is_none() {
+        let insert_ocr_start = std::time::Instant::now();
+        db.insert_ocr_text(
+            frame_id,
+            text,
+            text_json,
+            Arc::new(ocr_engine.clone().into()),
+        )
+        .await?;
+        let ocr_insert_duration = insert_ocr_start.elapsed();
+        if ocr_insert_duration.as_millis() > 100 {
+            warn!(
+                "Slow DB insert_ocr_text operation: {}ms",
+                ocr_insert_duration.as_millis()
+            );
+        }
+        debug!(
+            "OCR text inserted for frame {} in {}ms",
+            frame_id,
+            ocr_insert_duration.as_millis()
+        );
+
+        if let Err(e) = db
+            .set_ocr_text_language(frame_id, ocr_language_code(text))
+            .await
+        {
+            warn!("failed to store language of frame {}: {}", frame_id, e);
+        }
+        if !block_languages.is_empty() {
+            if let Err(e) = db.set_ocr_block_languages(frame_id, block_languages).await {
+                warn!(
+                    "failed to store block languages of frame {}: {}",
+                    frame_id, e
+                );
+            }
+        }
+    }
+
+    let _ = send_event(
+        "frame_written",
+        FrameWritten {
+            frame_id,
+            monitor_id,
+            timestamp: Utc::now(),
+            app_name: window.app_name.clone(),
+            window_name: window.window_name.clone(),
+            browser_url: window.browser_url.clone(),
+            focused: window.focused,
+            text_length: text.chars().count(),
+        },
+    );
+    Ok(())
+}
+
 pub async fn merge_speakers(
     db: &DatabaseManager,
     speaker_to_keep_id: i64,
@@ -0,0 +1,167 @@
+use crate::jobs::JobProgress;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use image::DynamicImage;
+use screenpipe_db::{DatabaseManager, FrameHash, Job};
+use screenpipe_vision::CaptureResult;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::info;
+
+/// Collapses frames showing a screen already recorded earlier into the first one.
+pub const DEDUP_FRAMES_JOB: &str = "dedup_frames";
+
+/// Bits two hashes may differ by and still show the same screen: enough for a clock ticking
+/// on a lock screen, not for a new line of text.
+pub const MAX_HASH_DISTANCE: u32 = 3;
+
+/// Distinct screens remembered per monitor to recognize one coming back.
+const RECENT_SCREENS: usize = 32;
+
+/// A 64 bit difference hash: the image shrunk to 9x8 gray pixels, one bit per pair of
+/// neighbours telling whether brightness goes up. Small changes flip few bits.
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    let small = image.thumbnail_exact(9, 8).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x + 1, y)[0] > small.get_pixel(x, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Identifies the text read in the windows of a capture, for a screen that looks the same to
+/// [`perceptual_hash`] but reads differently, like a new line in a terminal, not to be taken
+/// for a duplicate. Only compared within the running process.
+pub fn text_fingerprint(result: &CaptureResult) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for window in &result.window_ocr_results {
+        window.text.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// The last distinct screens of a monitor, most recent first, with what is known about each
+/// (e.g. the frame that recorded it).
+#[derive(Debug, Clone)]
+pub struct RecentScreens<T> {
+    screens: VecDeque<(u64, T)>,
+}
+
+impl<T> Default for RecentScreens<T> {
+    fn default() -> Self {
+        RecentScreens {
+            screens: VecDeque::with_capacity(RECENT_SCREENS),
+        }
+    }
+}
+
+impl<T> RecentScreens<T> {
+    /// The remembered screen closest to `hash`, if one is close enough to be the same.
+    pub fn find(&self, hash: u64) -> Option<&T> {
+        self.find_where(hash, |_| true)
+    }
+
+    /// Like [`RecentScreens::find`], among the screens whose value `matches`.
+    pub fn find_where(&self, hash: u64, matches: impl Fn(&T) -> bool) -> Option<&T> {
+        self.screens
+            .iter()
+            .filter(|(_, value)| matches(value))
+            .map(|(screen, value)| (hash_distance(*screen, hash), value))
+            .filter(|(distance, _)| *distance <= MAX_HASH_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, value)| value)
+    }
+
+    pub fn insert(&mut self, hash: u64, value: T) {
+        if self.screens.len() == RECENT_SCREENS {
+            self.screens.pop_back();
+        }
+        self.screens.push_front((hash, value));
+    }
+}
+
+/// The `(frame_id, canonical_id)` pairs of frames showing a screen recorded earlier in the same
+/// window of the same monitor, with the same text, given frames ordered by monitor and time.
+pub fn find_duplicates(frames: &[FrameHash]) -> Vec<(i64, i64)> {
+    type Window<'a> = (&'a str, Option<&'a str>, Option<&'a str>);
+    let mut screens: HashMap<Window, RecentScreens<(i64, Option<&str>)>> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for frame in frames {
+        let hash = frame.perceptual_hash as u64;
+        // every window of a capture has a frame of the same screen, they aren't duplicates
+        let window = (
+            frame.device_name.as_str(),
+            frame.app_name.as_deref(),
+            frame.window_name.as_deref(),
+        );
+        let text = frame.text.as_deref();
+        let recent = screens.entry(window).or_default();
+        match recent.find_where(hash, |(_, screen_text)| *screen_text == text) {
+            Some((canonical_id, _)) => duplicates.push((frame.id, *canonical_id)),
+            None => recent.insert(hash, (frame.id, text)),
+        }
+    }
+    duplicates
+}
+
+static SKIP_DUPLICATE_FRAMES: AtomicBool = AtomicBool::new(false);
+
+/// Whether frames showing a screen recorded shortly before are kept out of the video, and
+/// indexed as duplicates of the earlier frame instead.
+pub fn set_skip_duplicate_frames(enabled: bool) {
+    SKIP_DUPLICATE_FRAMES.store(enabled, Ordering::SeqCst);
+}
+
+pub fn skip_duplicate_frames() -> bool {
+    SKIP_DUPLICATE_FRAMES.load(Ordering::SeqCst)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DedupFramesPayload {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// Collapses the duplicate frames recorded between `start_time` and `end_time`, returning how
+/// many were collapsed.
+pub async fn dedup_frames(
+    db: &DatabaseManager,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<u64> {
+    let frames = db.get_frame_hashes(start_time, end_time).await?;
+    let duplicates = find_duplicates(&frames);
+    let collapsed = db.collapse_duplicate_frames(&duplicates).await?;
+    info!(
+        "collapsed {} of {} frames between {} and {}",
+        collapsed,
+        frames.len(),
+        start_time,
+        end_time
+    );
+    Ok(collapsed)
+}
+
+pub(crate) async fn dedup_frames_job(
+    db: Arc<DatabaseManager>,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: DedupFramesPayload = serde_json::from_str(&job.payload)?;
+    dedup_frames(&db, payload.start_time, payload.end_time).await?;
+    progress.report(1.0).await;
+    Ok(())
+}
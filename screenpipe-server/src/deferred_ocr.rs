@@ -0,0 +1,163 @@
+use crate::import::{ocr_frame, store_ocr_text, video_dimensions};
+use crate::jobs::{enqueue_job, ChunkJobPayload, JobProgress};
+use crate::subtitles::write_chunk_sidecar;
+use anyhow::{anyhow, Context, Result};
+use image::{DynamicImage, RgbImage};
+use once_cell::sync::OnceCell;
+use screenpipe_core::{find_ffmpeg_path, Language};
+use screenpipe_db::{DatabaseManager, Job};
+use screenpipe_vision::core::{set_defer_ocr, skip_ocr};
+use screenpipe_vision::OcrEngine;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// OCRs the frames of a finalized chunk recorded while OCR is deferred.
+pub const OCR_CHUNK_JOB: &str = "ocr_chunk";
+
+/// Attempts at OCRing a chunk, held back while resources run low.
+pub const OCR_CHUNK_MAX_ATTEMPTS: i64 = 10;
+
+/// How the frames of chunks are read when OCR is left to the job workers.
+#[derive(Debug, Clone)]
+pub struct DeferredOcr {
+    pub ocr_engine: Arc<OcrEngine>,
+    pub languages: Vec<Language>,
+    /// Whether the subtitles sidecar of each chunk is written once it's OCR'd.
+    pub subtitle_sidecars: bool,
+}
+
+static DEFERRED_OCR: OnceCell<DeferredOcr> = OnceCell::new();
+
+/// Leaves OCR out of capture, each chunk being OCR'd by an [`OCR_CHUNK_JOB`] once finalized.
+/// Only the first call has an effect.
+pub fn set_deferred_ocr(config: DeferredOcr) {
+    if DEFERRED_OCR.set(config).is_err() {
+        warn!("deferred ocr already set");
+        return;
+    }
+    set_defer_ocr(true);
+}
+
+pub fn deferred_ocr() -> Option<&'static DeferredOcr> {
+    DEFERRED_OCR.get()
+}
+
+/// Queues the last chunk of each monitor when it has frames left to OCR, recording having
+/// stopped before it was finalized. To be called before recording starts.
+pub async fn queue_last_chunks(db: &DatabaseManager) -> Result<()> {
+    let chunks = db.get_last_video_chunks_with_unread_frames().await?;
+    for video_path in &chunks {
+        let payload = ChunkJobPayload {
+            video_path: video_path.clone(),
+        };
+        enqueue_job(db, OCR_CHUNK_JOB, &payload, OCR_CHUNK_MAX_ATTEMPTS).await?;
+    }
+    if !chunks.is_empty() {
+        info!("queued ocr of {} chunks left unread", chunks.len());
+    }
+    Ok(())
+}
+
+/// Decodes the chunk of the job in order and OCRs its frames that have no text yet, the way
+/// the recorder would have as they were captured. The text of the whole screen goes to the
+/// focused window of each frame, the other windows of the frame are stored without text.
+pub async fn ocr_chunk_job(
+    db: Arc<DatabaseManager>,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let config = deferred_ocr().ok_or_else(|| anyhow!("ocr isn't deferred"))?;
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    let frames = db
+        .get_video_chunk_unread_frames(&payload.video_path)
+        .await?;
+    let Some(last_offset) = frames.last().map(|frame| frame.offset_index) else {
+        return Ok(());
+    };
+    let video = Path::new(&payload.video_path);
+    if !video.exists() {
+        return Err(anyhow!("video chunk {} no longer exists", video.display()));
+    }
+
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let (width, height) = video_dimensions(video).await?;
+    let mut decoder = Command::new(&ffmpeg_path)
+        .args(["-v", "error", "-noautorotate", "-i"])
+        .arg(video)
+        .args([
+            "-fps_mode",
+            "passthrough",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+            "-",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start ffmpeg")?;
+    let mut output = decoder.stdout.take().expect("ffmpeg stdout is piped");
+
+    let mut buffer = vec![0u8; width as usize * height as usize * 3];
+    let mut frames = frames.iter().peekable();
+    for offset in 0..=last_offset {
+        match output.read_exact(&mut buffer).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let mut windows = Vec::new();
+        while let Some(frame) = frames.next_if(|frame| frame.offset_index == offset) {
+            windows.push(frame);
+        }
+        let Some((focused, others)) = windows.split_first() else {
+            continue;
+        };
+        // nothing is read while resources run low, the job is retried later
+        if skip_ocr() {
+            return Err(anyhow!("resources are low, ocr is held back"));
+        }
+
+        let image = DynamicImage::ImageRgb8(
+            RgbImage::from_raw(width, height, buffer.clone())
+                .ok_or_else(|| anyhow!("frame {} is truncated", offset))?,
+        );
+        let window_name = focused.window_name.as_deref().unwrap_or_default();
+        if let Some(ocr) = ocr_frame(
+            image,
+            window_name,
+            offset as u64,
+            &config.ocr_engine,
+            &config.languages,
+        )
+        .await?
+        {
+            store_ocr_text(&db, focused.frame_id, ocr, &config.ocr_engine).await?;
+        }
+        for window in others {
+            db.insert_ocr_text(
+                window.frame_id,
+                "",
+                "[]",
+                Arc::new((*config.ocr_engine).clone().into()),
+            )
+            .await?;
+        }
+        progress
+            .report((offset + 1) as f64 / (last_offset + 1) as f64)
+            .await;
+    }
+    let _ = decoder.wait().await;
+
+    if config.subtitle_sidecars {
+        write_chunk_sidecar(&db, &payload.video_path).await?;
+    }
+    Ok(())
+}
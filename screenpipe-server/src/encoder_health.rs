@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use once_cell::sync::Lazy;
+use screenpipe_events::send_event;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tracing::{debug, error, warn};
+
+/// Errors kept per encoder, for the health endpoint.
+const MAX_RECENT_ERRORS: usize = 5;
+/// Longest wait before starting a new encoder after unhealthy ones.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfmpegLogLevel {
+    Warning,
+    Error,
+}
+
+/// Level of an ffmpeg stderr line. The encoders run with `-loglevel level+warning`, which
+/// prefixes every line with its level; lines without one are only errors when they say so.
+pub fn classify_ffmpeg_line(line: &str) -> Option<FfmpegLogLevel> {
+    if ["[panic]", "[fatal]", "[error]"]
+        .iter()
+        .any(|level| line.contains(level))
+    {
+        return Some(FfmpegLogLevel::Error);
+    }
+    if line.contains("[warning]") {
+        return Some(FfmpegLogLevel::Warning);
+    }
+    let lowercase = line.to_lowercase();
+    if lowercase.starts_with("error")
+        || lowercase.contains("conversion failed")
+        || lowercase.contains("error while")
+    {
+        return Some(FfmpegLogLevel::Error);
+    }
+    None
+}
+
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderState {
+    Ok,
+    /// ffmpeg warned, the chunk is most likely still fine.
+    Degraded,
+    /// ffmpeg reported an error, the chunk may be broken.
+    Unhealthy,
+}
+
+/// What the stderr of the ffmpeg encoding the current chunk of a monitor said so far.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct EncoderHealth {
+    pub monitor_id: u32,
+    /// The chunk being encoded, by file name.
+    pub segment: String,
+    pub state: EncoderState,
+    pub warnings: u32,
+    pub errors: u32,
+    pub recent_errors: Vec<String>,
+    /// Chunks in a row before this one whose encoder turned unhealthy.
+    pub consecutive_failures: u32,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EncoderHealth {
+    pub fn new(monitor_id: u32, segment: &str) -> Self {
+        EncoderHealth {
+            monitor_id,
+            segment: segment.to_string(),
+            state: EncoderState::Ok,
+            warnings: 0,
+            errors: 0,
+            recent_errors: Vec::new(),
+            consecutive_failures: 0,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// The health of the encoder of the next chunk, which starts clean but for the failures
+    /// in a row, so that an encoder failing again and again is restarted less and less often.
+    pub fn next_chunk(&self, segment: &str) -> Self {
+        EncoderHealth {
+            consecutive_failures: if self.state == EncoderState::Unhealthy {
+                self.consecutive_failures + 1
+            } else {
+                0
+            },
+            ..EncoderHealth::new(self.monitor_id, segment)
+        }
+    }
+
+    /// How long to wait before starting the encoder of the next chunk: none while this one
+    /// is healthy, then doubling from a second with each chunk failing in a row.
+    pub fn restart_backoff(&self) -> Duration {
+        if self.state != EncoderState::Unhealthy {
+            return Duration::ZERO;
+        }
+        Duration::from_secs(1 << self.consecutive_failures.min(6)).min(MAX_RESTART_BACKOFF)
+    }
+
+    /// Accounts for a classified stderr line, returning whether the state changed.
+    pub fn record(&mut self, level: FfmpegLogLevel, line: &str) -> bool {
+        let previous = self.state;
+        match level {
+            FfmpegLogLevel::Warning => {
+                self.warnings += 1;
+                if self.state == EncoderState::Ok {
+                    self.state = EncoderState::Degraded;
+                }
+            }
+            FfmpegLogLevel::Error => {
+                self.errors += 1;
+                self.state = EncoderState::Unhealthy;
+                if self.recent_errors.len() == MAX_RECENT_ERRORS {
+                    self.recent_errors.remove(0);
+                }
+                self.recent_errors.push(line.to_string());
+            }
+        }
+        self.updated_at = Utc::now();
+        self.state != previous
+    }
+}
+
+static ENCODERS: Lazy<Mutex<HashMap<u32, EncoderHealth>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Health of the encoder of every monitor, by monitor id.
+pub fn encoder_health() -> Vec<EncoderHealth> {
+    let mut encoders = ENCODERS
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    encoders.sort_by_key(|encoder| encoder.monitor_id);
+    encoders
+}
+
+/// See [`EncoderHealth::restart_backoff`].
+pub fn encoder_restart_backoff(monitor_id: u32) -> Duration {
+    ENCODERS
+        .lock()
+        .unwrap()
+        .get(&monitor_id)
+        .map_or(Duration::ZERO, EncoderHealth::restart_backoff)
+}
+
+pub fn encoder_state(monitor_id: u32) -> Option<EncoderState> {
+    ENCODERS
+        .lock()
+        .unwrap()
+        .get(&monitor_id)
+        .map(|encoder| encoder.state)
+}
+
+/// Watches the stderr of the ffmpeg encoding `segment` until it exits, logging its warnings
+/// and errors and announcing `encoder_health` when the state of the encoder changes.
+pub fn watch_ffmpeg_stderr(
+    stderr: impl AsyncRead + Unpin + Send + 'static,
+    monitor_id: u32,
+    segment: String,
+) {
+    // a new chunk starts with a new ffmpeg, and a clean slate but for the failures in a row
+    {
+        let mut encoders = ENCODERS.lock().unwrap();
+        let health = match encoders.get(&monitor_id) {
+            Some(previous) => previous.next_chunk(&segment),
+            None => EncoderHealth::new(monitor_id, &segment),
+        };
+        encoders.insert(monitor_id, health);
+    }
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Some(level) = classify_ffmpeg_line(&line) else {
+                debug!("FFmpeg stderr ({}): {}", segment, line);
+                continue;
+            };
+            match level {
+                FfmpegLogLevel::Warning => warn!("ffmpeg warning on {}: {}", segment, line),
+                FfmpegLogLevel::Error => error!("ffmpeg error on {}: {}", segment, line),
+            }
+
+            let changed = {
+                let mut encoders = ENCODERS.lock().unwrap();
+                match encoders.get_mut(&monitor_id) {
+                    // a newer chunk already replaced this one
+                    Some(encoder) if encoder.segment == segment => {
+                        encoder.record(level, &line).then(|| encoder.clone())
+                    }
+                    _ => None,
+                }
+            };
+            if let Some(encoder) = changed {
+                let _ = send_event("encoder_health", encoder);
+            }
+        }
+    });
+}
@@ -0,0 +1,589 @@
+use crate::jobs::{run_ffmpeg_with_progress, ChunkJobPayload, JobProgress};
+use crate::storage::Storage;
+use crate::upload::hex;
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use once_cell::sync::{Lazy, OnceCell};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use screenpipe_db::{DatabaseManager, Job};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+use walkdir::WalkDir;
+
+/// Hashes a finalized chunk into the evidence chain of the machine.
+pub const SEAL_SEGMENT_JOB: &str = "seal_segment";
+
+/// Attempts of a seal, generous since it waits for the chunk's other jobs and the seals
+/// of the chunks before it. A segment still not sealed after the last one is recorded in the
+/// chain as a failed link.
+pub const SEAL_MAX_ATTEMPTS: i64 = 10;
+
+pub const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+const CHAIN_HEAD_FILE: &str = "evidence_chain.json";
+
+/// `previous_hash` of the first segment of a chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Manifest of a sealed segment, next to its video. Its `chain_hash` covers the previous
+/// segment's, so removing, reordering or editing a segment breaks the chain from there on.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentManifest {
+    pub format_version: u32,
+    /// Position of the segment in the chain of its machine, from 1.
+    pub sequence: u64,
+    /// Name of the video, in the manifest's directory.
+    pub file_name: String,
+    /// SHA-256 of the video as sealed, its metadata comment carrying `sequence` and
+    /// `previous_hash`.
+    pub file_sha256: String,
+    pub file_size: u64,
+    /// `chain_hash` of the segment sealed before, [`GENESIS_HASH`] for the first one.
+    pub previous_hash: String,
+    pub sealed_at: DateTime<Utc>,
+    /// SHA-256 of the fields above.
+    pub chain_hash: String,
+    /// Base64 Ed25519 signature of `chain_hash`, when sealed with a signing key.
+    pub signature: Option<String>,
+    /// Base64 Ed25519 public key of `signature`.
+    pub public_key: Option<String>,
+    /// Why the segment couldn't be sealed, the link only keeping its place in the chain: its
+    /// video, as it was then, isn't vouched for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seal_error: Option<String>,
+}
+
+/// Last link of a machine's chain, kept in its directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChainHead {
+    sequence: u64,
+    chain_hash: String,
+}
+
+pub struct EvidenceConfig {
+    pub signing_key: Option<Ed25519KeyPair>,
+}
+
+static EVIDENCE_CONFIG: OnceCell<EvidenceConfig> = OnceCell::new();
+
+/// Held while a link is added to the chain, a failed link not waiting for the seals before it.
+static CHAIN_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Enables sealing of finalized segments. Only the first call has an effect.
+pub fn set_evidence_config(config: EvidenceConfig) {
+    if EVIDENCE_CONFIG.set(config).is_err() {
+        warn!("evidence config already set");
+    }
+}
+
+pub fn evidence_config() -> Option<&'static EvidenceConfig> {
+    EVIDENCE_CONFIG.get()
+}
+
+/// Reads the PKCS#8 Ed25519 key at `path`, generating it first if there's none.
+pub fn load_or_create_signing_key(path: &Path) -> Result<Ed25519KeyPair> {
+    if !path.exists() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| anyhow!("failed to generate a signing key"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, pkcs8.as_ref())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        info!("generated evidence signing key {}", path.display());
+    }
+    let pkcs8 = std::fs::read(path)
+        .with_context(|| format!("failed to read signing key {}", path.display()))?;
+    Ed25519KeyPair::from_pkcs8(&pkcs8)
+        .map_err(|e| anyhow!("invalid signing key {}: {}", path.display(), e))
+}
+
+pub fn encode_public_key(key: &Ed25519KeyPair) -> String {
+    general_purpose::STANDARD.encode(key.public_key().as_ref())
+}
+
+/// Manifest of the segment `video_path`: `<video>.evidence.json`.
+pub fn manifest_path(video_path: &Path) -> PathBuf {
+    video_path.with_extension("evidence.json")
+}
+
+pub fn chain_head_path(storage: &Storage) -> PathBuf {
+    storage.host_dir().join(CHAIN_HEAD_FILE)
+}
+
+/// Metadata comment of a sealed video, linking it to the segment before.
+pub fn evidence_comment(sequence: u64, previous_hash: &str) -> String {
+    format!(
+        "screenpipe-evidence sequence={} previous={}",
+        sequence, previous_hash
+    )
+}
+
+/// Hash of a link of the chain, covering the `seal_error` of failed links.
+pub fn chain_hash(
+    sequence: u64,
+    file_name: &str,
+    file_sha256: &str,
+    file_size: u64,
+    previous_hash: &str,
+    sealed_at: DateTime<Utc>,
+    seal_error: Option<&str>,
+) -> String {
+    let mut content = format!(
+        "screenpipe-evidence/{}\n{}\n{}\n{}\n{}\n{}\n{}",
+        MANIFEST_FORMAT_VERSION,
+        sequence,
+        file_name,
+        file_sha256,
+        file_size,
+        previous_hash,
+        sealed_at.to_rfc3339()
+    );
+    // sealed links hash as they did before failed ones were recorded
+    if let Some(seal_error) = seal_error {
+        content.push_str(&format!("\nunsealed {}", seal_error));
+    }
+    hex(&Sha256::digest(content.as_bytes()))
+}
+
+/// SHA-256 and size of `path`.
+pub fn file_digest(path: &Path) -> Result<(String, u64)> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((hex(&hasher.finalize()), size))
+}
+
+/// Builds the manifest of `video_path` once sealed, as the link after `previous_hash`.
+pub fn build_manifest(
+    video_path: &Path,
+    sequence: u64,
+    previous_hash: &str,
+    sealed_at: DateTime<Utc>,
+    signing_key: Option<&Ed25519KeyPair>,
+) -> Result<SegmentManifest> {
+    let (file_sha256, file_size) = file_digest(video_path)?;
+    link_manifest(
+        video_path,
+        (file_sha256, file_size),
+        sequence,
+        previous_hash,
+        sealed_at,
+        None,
+        signing_key,
+    )
+}
+
+/// Builds the manifest of `video_path` that couldn't be sealed because of `seal_error`, as
+/// the link after `previous_hash`. Its digest is the one of the video as it is, if readable.
+pub fn build_failed_manifest(
+    video_path: &Path,
+    sequence: u64,
+    previous_hash: &str,
+    sealed_at: DateTime<Utc>,
+    seal_error: &str,
+    signing_key: Option<&Ed25519KeyPair>,
+) -> Result<SegmentManifest> {
+    link_manifest(
+        video_path,
+        file_digest(video_path).unwrap_or_default(),
+        sequence,
+        previous_hash,
+        sealed_at,
+        Some(seal_error),
+        signing_key,
+    )
+}
+
+fn link_manifest(
+    video_path: &Path,
+    (file_sha256, file_size): (String, u64),
+    sequence: u64,
+    previous_hash: &str,
+    sealed_at: DateTime<Utc>,
+    seal_error: Option<&str>,
+    signing_key: Option<&Ed25519KeyPair>,
+) -> Result<SegmentManifest> {
+    let file_name = video_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow!("{} has no file name", video_path.display()))?;
+    let chain_hash = chain_hash(
+        sequence,
+        &file_name,
+        &file_sha256,
+        file_size,
+        previous_hash,
+        sealed_at,
+        seal_error,
+    );
+    let (signature, public_key) = match signing_key {
+        Some(key) => (
+            Some(general_purpose::STANDARD.encode(key.sign(chain_hash.as_bytes()).as_ref())),
+            Some(encode_public_key(key)),
+        ),
+        None => (None, None),
+    };
+    Ok(SegmentManifest {
+        format_version: MANIFEST_FORMAT_VERSION,
+        sequence,
+        file_name,
+        file_sha256,
+        file_size,
+        previous_hash: previous_hash.to_string(),
+        sealed_at,
+        chain_hash,
+        signature,
+        public_key,
+        seal_error: seal_error.map(str::to_string),
+    })
+}
+
+/// Writes `content` to `path` whole or not at all, through a file renamed over it.
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let partial = PathBuf::from(format!("{}.part", path.display()));
+    std::fs::write(&partial, content)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Writes the manifest of a new link of the chain and moves the chain head to it.
+fn append_link(head_path: &Path, manifest_path: &Path, manifest: &SegmentManifest) -> Result<()> {
+    write_atomically(manifest_path, &serde_json::to_string_pretty(manifest)?)?;
+    write_atomically(
+        head_path,
+        &serde_json::to_string(&ChainHead {
+            sequence: manifest.sequence,
+            chain_hash: manifest.chain_hash.clone(),
+        })?,
+    )
+}
+
+fn read_chain_head(path: &Path) -> Result<ChainHead> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)
+            .with_context(|| format!("corrupt evidence chain head {}", path.display()))?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ChainHead {
+            sequence: 0,
+            chain_hash: GENESIS_HASH.to_string(),
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Seals `video_path` as the next segment of the chain of `storage`: embeds its link in the
+/// video's metadata, then writes its manifest and moves the chain head to it.
+pub async fn seal_segment(
+    storage: &Storage,
+    video_path: &Path,
+    signing_key: Option<&Ed25519KeyPair>,
+    progress: &JobProgress,
+) -> Result<SegmentManifest> {
+    let _chain = CHAIN_LOCK.lock().await;
+    let head_path = chain_head_path(storage);
+    let head = read_chain_head(&head_path)?;
+    let manifest_path = manifest_path(video_path);
+    // sealed already, by an attempt stopped before it was marked done
+    if let Ok(content) = std::fs::read_to_string(&manifest_path) {
+        if let Ok(manifest) = serde_json::from_str::<SegmentManifest>(&content) {
+            if manifest.chain_hash == head.chain_hash {
+                return Ok(manifest);
+            }
+        }
+    }
+
+    let sequence = head.sequence + 1;
+    let sealed = video_path.with_extension("sealed.mp4");
+    let sealed_str = sealed.to_string_lossy();
+    let comment = format!("comment={}", evidence_comment(sequence, &head.chain_hash));
+    let input = video_path.to_string_lossy();
+    if let Err(e) = run_ffmpeg_with_progress(
+        &input,
+        &[
+            "-map",
+            "0",
+            "-c",
+            "copy",
+            "-metadata",
+            &comment,
+            &*sealed_str,
+        ],
+        progress,
+    )
+    .await
+    {
+        let _ = tokio::fs::remove_file(&sealed).await;
+        return Err(e);
+    }
+    // in place, so frames keep pointing at the same file
+    tokio::fs::rename(&sealed, video_path).await?;
+
+    let manifest = build_manifest(
+        video_path,
+        sequence,
+        &head.chain_hash,
+        Utc::now(),
+        signing_key,
+    )?;
+    append_link(&head_path, &manifest_path, &manifest)?;
+    Ok(manifest)
+}
+
+/// Records `video_path` in the chain of `storage` as a segment that couldn't be sealed, so
+/// the chain shows where it is rather than going on as if it never existed.
+pub async fn record_failed_seal(
+    storage: &Storage,
+    video_path: &Path,
+    seal_error: &str,
+    signing_key: Option<&Ed25519KeyPair>,
+) -> Result<SegmentManifest> {
+    let _chain = CHAIN_LOCK.lock().await;
+    let head_path = chain_head_path(storage);
+    let head = read_chain_head(&head_path)?;
+    let manifest = build_failed_manifest(
+        video_path,
+        head.sequence + 1,
+        &head.chain_hash,
+        Utc::now(),
+        seal_error,
+        signing_key,
+    )?;
+    append_link(&head_path, &manifest_path(video_path), &manifest)?;
+    Ok(manifest)
+}
+
+async fn seal_chunk(
+    db: Arc<DatabaseManager>,
+    storage: &Storage,
+    job: &Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    // the chunk may still be re-encoded, which would change its hash
+    if db
+        .count_unfinished_jobs_before(job.id, &job.payload)
+        .await?
+        > 0
+    {
+        return Err(anyhow!(
+            "{} still has jobs to run before its seal",
+            payload.video_path
+        ));
+    }
+    // the chain follows the order the chunks were finalized in
+    if db
+        .count_unfinished_jobs_of_kind_before(SEAL_SEGMENT_JOB, job.id)
+        .await?
+        > 0
+    {
+        return Err(anyhow!(
+            "chunks finalized before {} are still to be sealed",
+            payload.video_path
+        ));
+    }
+    let config = evidence_config().ok_or_else(|| anyhow!("sealing is not enabled"))?;
+    let manifest = seal_segment(
+        storage,
+        Path::new(&payload.video_path),
+        config.signing_key.as_ref(),
+        &progress,
+    )
+    .await?;
+    info!(
+        "sealed {} as segment {} of the evidence chain",
+        payload.video_path, manifest.sequence
+    );
+    Ok(())
+}
+
+/// Seals the chunk of the job, recording it as a failed link once its last attempt fails,
+/// whatever it waited for.
+pub(crate) async fn seal_segment_job(
+    db: Arc<DatabaseManager>,
+    storage: Storage,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let Err(e) = seal_chunk(db, &storage, &job, progress).await else {
+        return Ok(());
+    };
+    if job.attempts < job.max_attempts {
+        return Err(e);
+    }
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    let signing_key = evidence_config().and_then(|config| config.signing_key.as_ref());
+    match record_failed_seal(
+        &storage,
+        Path::new(&payload.video_path),
+        &e.to_string(),
+        signing_key,
+    )
+    .await
+    {
+        Ok(manifest) => error!(
+            "{} couldn't be sealed, recorded as failed segment {} of the evidence chain: {}",
+            payload.video_path, manifest.sequence, e
+        ),
+        Err(record_error) => error!(
+            "{} couldn't be sealed nor recorded as failed, the evidence chain has a gap: {}",
+            payload.video_path, record_error
+        ),
+    }
+    Err(e)
+}
+
+pub fn read_manifest(path: &Path) -> Result<SegmentManifest> {
+    serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?,
+    )
+    .with_context(|| format!("invalid manifest {}", path.display()))
+}
+
+/// Checks the manifest at `path` against its video and its signature, and that it follows
+/// `previous` when given. With `public_key`, the manifest must be signed by it.
+pub fn verify_manifest(
+    path: &Path,
+    previous: Option<&SegmentManifest>,
+    public_key: Option<&str>,
+) -> Result<SegmentManifest> {
+    let manifest = read_manifest(path)?;
+    let expected = chain_hash(
+        manifest.sequence,
+        &manifest.file_name,
+        &manifest.file_sha256,
+        manifest.file_size,
+        &manifest.previous_hash,
+        manifest.sealed_at,
+        manifest.seal_error.as_deref(),
+    );
+    if expected != manifest.chain_hash {
+        return Err(anyhow!(
+            "{} was edited, its chain hash doesn't match",
+            path.display()
+        ));
+    }
+
+    // a failed link vouches for nothing but its place in the chain
+    if manifest.seal_error.is_none() {
+        let video = path.with_file_name(&manifest.file_name);
+        let (file_sha256, file_size) = file_digest(&video)?;
+        if file_sha256 != manifest.file_sha256 || file_size != manifest.file_size {
+            return Err(anyhow!(
+                "{} was modified since it was sealed",
+                video.display()
+            ));
+        }
+    }
+
+    if let Some(previous) = previous {
+        if manifest.sequence <= previous.sequence {
+            return Err(anyhow!(
+                "{} is segment {} again, or of another chain",
+                path.display(),
+                manifest.sequence
+            ));
+        }
+        if manifest.sequence != previous.sequence + 1 {
+            return Err(anyhow!(
+                "segments {} to {} are missing before {}",
+                previous.sequence + 1,
+                manifest.sequence - 1,
+                path.display()
+            ));
+        }
+        if manifest.previous_hash != previous.chain_hash {
+            return Err(anyhow!(
+                "{} doesn't follow segment {}",
+                path.display(),
+                previous.sequence
+            ));
+        }
+    } else if manifest.sequence == 1 && manifest.previous_hash != GENESIS_HASH {
+        return Err(anyhow!(
+            "{} starts a chain without the genesis hash",
+            path.display()
+        ));
+    }
+
+    match (&manifest.signature, &manifest.public_key) {
+        (Some(signature), Some(key)) => {
+            if public_key.is_some_and(|expected| expected != key) {
+                return Err(anyhow!("{} is signed by another key", path.display()));
+            }
+            let signature = general_purpose::STANDARD.decode(signature)?;
+            let key = general_purpose::STANDARD.decode(key)?;
+            UnparsedPublicKey::new(&ED25519, key)
+                .verify(manifest.chain_hash.as_bytes(), &signature)
+                .map_err(|_| anyhow!("{} has an invalid signature", path.display()))?;
+        }
+        _ if public_key.is_some() => {
+            return Err(anyhow!("{} isn't signed", path.display()));
+        }
+        _ => {}
+    }
+    Ok(manifest)
+}
+
+/// Manifests in `paths`, given directly or found in directories.
+pub fn find_manifests(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut manifests = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            manifests.extend(
+                WalkDir::new(path)
+                    .into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.into_path())
+                    .filter(|path| path.to_string_lossy().ends_with(".evidence.json")),
+            );
+        } else {
+            manifests.push(path.clone());
+        }
+    }
+    manifests
+}
+
+/// Verifies a run of consecutive segments of one chain, in sequence order. The run may start
+/// anywhere in the chain, as exported evidence usually covers only part of it. Segments that
+/// couldn't be sealed are returned with their `seal_error`, for the caller to report.
+pub fn verify_chain(paths: &[PathBuf], public_key: Option<&str>) -> Result<Vec<SegmentManifest>> {
+    let mut manifests = Vec::new();
+    for path in find_manifests(paths) {
+        manifests.push((read_manifest(&path)?.sequence, path));
+    }
+    if manifests.is_empty() {
+        return Err(anyhow!("no evidence manifest found"));
+    }
+    manifests.sort();
+
+    let mut verified: Vec<SegmentManifest> = Vec::new();
+    for (_, path) in manifests {
+        let manifest = verify_manifest(&path, verified.last(), public_key)?;
+        debug!("verified segment {}", manifest.sequence);
+        verified.push(manifest);
+    }
+    Ok(verified)
+}
@@ -0,0 +1,303 @@
+use crate::jobs::{enqueue_job, ChunkJobPayload, JobProgress};
+use crate::mosaic::{local_day_bounds, mosaic_path, DayMosaicPayload, RENDER_DAY_MOSAIC_JOB};
+use crate::presentation::excise_presentations;
+use crate::pyramid::{pyramid_dir, PYRAMID_LEVELS};
+use crate::storage::Storage;
+use crate::subtitles::{sidecar_path, write_chunk_sidecar};
+use crate::thumbnails::{build_thumbnail_strip, thumbnail_index_path};
+use crate::upload::{
+    segment_files, sync_config, SyncConfig, UPLOAD_MAX_ATTEMPTS, UPLOAD_SEGMENT_JOB,
+};
+use crate::video::{chunk_encoder_args, chunk_encoding};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::{DatabaseManager, FrameLocation, Job, Redaction};
+use screenpipe_events::send_event;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+/// Trims the tail excised from a video chunk, or removes the chunk when nothing is left.
+pub const EXCISE_SEGMENT_JOB: &str = "excise_segment";
+
+/// Attempts of an excision, which waits for the chunk's encoder to finish by failing.
+pub const EXCISE_MAX_ATTEMPTS: i64 = 10;
+
+/// Longest range excised at once, past which the timeline should be deleted another way.
+pub const MAX_EXCISE_MINUTES: u32 = 24 * 60;
+
+static CHUNK_CUTS: AtomicU64 = AtomicU64::new(0);
+
+/// Ends the video chunks being recorded, so the frames captured next land in new chunks and
+/// the excised ones can be trimmed.
+pub fn request_chunk_cut() {
+    CHUNK_CUTS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Cuts requested so far, a chunk started before the count changed must end.
+pub fn chunk_cuts() -> u64 {
+    CHUNK_CUTS.load(Ordering::SeqCst)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExciseSegmentPayload {
+    pub video_path: String,
+    /// Start of the excised range, frames of the chunk from then on are removed.
+    pub start_time: DateTime<Utc>,
+}
+
+/// Frames the trimmed chunk keeps: up to the last one still indexed.
+pub fn trimmed_frame_count(frames: &[FrameLocation]) -> i64 {
+    frames
+        .iter()
+        .map(|frame| frame.offset_index + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Removes what was made of the range outside of the index and its chunks: the frames of the
+/// journals, the slides of presentations, the timelapses of its days and their mosaics, which
+/// are rendered again.
+async fn excise_outputs(
+    db: &DatabaseManager,
+    storage: &Storage,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<()> {
+    storage.excise_frame_journals(start_time, end_time)?;
+    excise_presentations(db, storage, start_time, end_time).await?;
+
+    let mut date = start_time.with_timezone(&Local).date_naive();
+    let last = end_time.with_timezone(&Local).date_naive();
+    while date <= last {
+        let mosaic = mosaic_path(storage, date);
+        if mosaic.exists() {
+            tokio::fs::remove_file(&mosaic).await?;
+            // today's is rendered at midnight
+            if local_day_bounds(date).1 <= Utc::now() {
+                enqueue_job(db, RENDER_DAY_MOSAIC_JOB, &DayMosaicPayload { date }, 3).await?;
+            }
+        }
+        let prefix = format!("{}_", date.format("%Y-%m-%d"));
+        if let Ok(mut entries) = tokio::fs::read_dir(storage.host_dir().join("timelapses")).await {
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    tokio::fs::remove_file(entry.path()).await?;
+                    info!("removed excised timelapse {}", entry.path().display());
+                }
+            }
+        }
+        match date.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+/// Excises the last `minutes` of the recordings: removes the range from the index and from
+/// what was made of it, deletes the audio recorded in it, ends the chunks being recorded and
+/// queues the trimming of the video chunks it went through. The range is logged as a
+/// redaction.
+pub async fn excise_last_minutes(
+    db: &DatabaseManager,
+    storage: &Storage,
+    minutes: u32,
+    reason: Option<&str>,
+) -> Result<Redaction> {
+    if minutes == 0 || minutes > MAX_EXCISE_MINUTES {
+        return Err(anyhow!(
+            "minutes must be between 1 and {}",
+            MAX_EXCISE_MINUTES
+        ));
+    }
+    let end_time = Utc::now();
+    let start_time = end_time - chrono::Duration::minutes(minutes as i64);
+
+    let excision = db.excise_time_range(start_time, end_time, reason).await?;
+    // after listing the chunks, so the ones started by the cut aren't trimmed
+    request_chunk_cut();
+    for audio_chunk in &excision.audio_chunks {
+        if let Err(e) = tokio::fs::remove_file(audio_chunk).await {
+            warn!("failed to remove excised audio {}: {}", audio_chunk, e);
+        }
+    }
+    for video_path in &excision.video_chunks {
+        // the latest chunk of a device idle over the whole range wasn't written in it
+        let written_in_range = std::fs::metadata(video_path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| DateTime::<Utc>::from(modified) >= start_time);
+        if !written_in_range {
+            continue;
+        }
+        let payload = ExciseSegmentPayload {
+            video_path: video_path.clone(),
+            start_time,
+        };
+        enqueue_job(db, EXCISE_SEGMENT_JOB, &payload, EXCISE_MAX_ATTEMPTS).await?;
+    }
+    excise_outputs(db, storage, start_time, end_time).await?;
+
+    let redaction = excision.redaction;
+    info!(
+        "redaction {}: excised {} to {} ({} frames, {} transcriptions, {} ui events, {} clipboard entries)",
+        redaction.id,
+        redaction.start_time,
+        redaction.end_time,
+        redaction.frames,
+        redaction.audio_transcriptions,
+        redaction.ui_events,
+        redaction.clipboard_entries
+    );
+    let _ = send_event("redaction", redaction.clone());
+    Ok(redaction)
+}
+
+/// Removes a chunk left without frames, with its pyramid and the files next to it.
+async fn remove_segment(video_path: &str) -> Result<()> {
+    for file in segment_files(Path::new(video_path)) {
+        tokio::fs::remove_file(&file).await?;
+    }
+    let pyramid = pyramid_dir(video_path);
+    if pyramid.is_dir() {
+        tokio::fs::remove_dir_all(pyramid).await?;
+    }
+    info!("removed excised chunk {}", video_path);
+    Ok(())
+}
+
+/// Re-encodes the first `frames` frames of a chunk in place of it, with the codec and crf it
+/// was recorded with.
+async fn trim_video(video_path: &str, frames: i64) -> Result<()> {
+    let input = Path::new(video_path);
+    let output = input.with_extension("excise.mp4");
+    let frames = frames.to_string();
+    let (low_power, crf) = chunk_encoding(video_path).await?;
+    let status = Command::new(find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?)
+        .args(["-y", "-i", video_path, "-frames:v", &frames])
+        .args(["-fps_mode", "passthrough"])
+        .args(chunk_encoder_args(low_power, crf))
+        .args(["-pix_fmt", "yuv420p"])
+        .arg(&output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+    if !status.success() {
+        let _ = tokio::fs::remove_file(&output).await;
+        // also the case of a chunk whose encoder hasn't finished writing it
+        return Err(anyhow!(
+            "ffmpeg exited with {} trimming {}",
+            status,
+            video_path
+        ));
+    }
+    tokio::fs::rename(&output, input).await?;
+    Ok(())
+}
+
+/// Removes the pyramid images of the frames from `frames` on.
+async fn trim_pyramid(video_path: &str, frames: i64) -> Result<()> {
+    for level in 0..PYRAMID_LEVELS.len() {
+        let dir = pyramid_dir(video_path).join(level.to_string());
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let offset = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<i64>().ok());
+            if offset.is_some_and(|offset| offset >= frames) {
+                tokio::fs::remove_file(&path).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes the excised tail of a chunk from its video and the files derived from it, or the
+/// whole chunk when none of its frames are left. Returns the frames the chunk keeps.
+pub async fn excise_segment(
+    db: &DatabaseManager,
+    video_path: &str,
+    start_time: DateTime<Utc>,
+    progress: Option<&JobProgress>,
+) -> Result<i64> {
+    // frames of the range indexed after it was excised
+    let late = db.excise_video_chunk_tail(video_path, start_time).await?;
+    if late > 0 {
+        debug!("excised {} frames indexed late in {}", late, video_path);
+    }
+    let frames = db.get_video_chunk_frames(video_path).await?;
+    let kept = trimmed_frame_count(&frames);
+    if kept == 0 {
+        remove_segment(video_path).await?;
+        return Ok(0);
+    }
+    // a frame image still shown by frames before the range
+    if image::ImageFormat::from_path(video_path).is_ok() {
+        return Ok(kept);
+    }
+
+    trim_video(video_path, kept).await?;
+    trim_pyramid(video_path, kept).await?;
+    if sidecar_path(Path::new(video_path)).exists() {
+        write_chunk_sidecar(db, video_path).await?;
+    }
+    if thumbnail_index_path(video_path).exists() {
+        build_thumbnail_strip(db, video_path, None).await?;
+    }
+    if let Some(progress) = progress {
+        progress.report(1.0).await;
+    }
+    info!("trimmed {} to {} frames", video_path, kept);
+    Ok(kept)
+}
+
+pub(crate) async fn excise_segment_job(
+    db: Arc<DatabaseManager>,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: ExciseSegmentPayload = serde_json::from_str(&job.payload)?;
+    if !Path::new(&payload.video_path).exists() {
+        return Ok(());
+    }
+    // a re-encode of the chunk queued before would write the excised frames back
+    let chunk_payload = serde_json::to_string(&ChunkJobPayload {
+        video_path: payload.video_path.clone(),
+    })?;
+    if db
+        .count_unfinished_jobs_before(job.id, &chunk_payload)
+        .await?
+        > 0
+    {
+        return Err(anyhow!(
+            "{} still has jobs to run before its excision",
+            payload.video_path
+        ));
+    }
+    let kept = excise_segment(
+        &db,
+        &payload.video_path,
+        payload.start_time,
+        Some(&progress),
+    )
+    .await?;
+    // the copy uploaded before the excision still shows the excised frames
+    if kept > 0 && sync_config().is_some_and(SyncConfig::uploads_segments) {
+        let payload = ChunkJobPayload {
+            video_path: payload.video_path,
+        };
+        enqueue_job(&db, UPLOAD_SEGMENT_JOB, &payload, UPLOAD_MAX_ATTEMPTS).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Context, Result};
+use oasgen::OaSchema;
+use screenpipe_vision::excluded_regions::{set_excluded_regions, ExcludedRegion};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Read from the data directory at startup and written by the UI's region picker.
+pub const EXCLUDED_REGIONS_CONFIG_FILE: &str = "excluded_regions.json";
+
+/// A rectangle blacked out of every frame before OCR and encoding, in points from the top
+/// left corner of its monitor.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionConfig {
+    /// The monitor the region is on, every monitor when not set.
+    #[serde(default)]
+    pub monitor_id: Option<u32>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// What the region hides, shown in the UI.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(OaSchema, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExcludedRegionsConfig {
+    #[serde(default)]
+    pub regions: Vec<RegionConfig>,
+}
+
+impl ExcludedRegionsConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: ExcludedRegionsConfig = serde_json::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The config at `path`, one without regions when there's no file.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read excluded regions {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.validate()?;
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write excluded regions {}", path.display()))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(region) = self
+            .regions
+            .iter()
+            .find(|region| region.width == 0 || region.height == 0)
+        {
+            return Err(anyhow!(
+                "excluded region at {},{} is empty",
+                region.x,
+                region.y
+            ));
+        }
+        Ok(())
+    }
+
+    /// Makes the capture loops black out these regions from their next frame on.
+    pub fn apply(&self) {
+        set_excluded_regions(
+            self.regions
+                .iter()
+                .map(|region| ExcludedRegion {
+                    monitor_id: region.monitor_id,
+                    x: region.x,
+                    y: region.y,
+                    width: region.width,
+                    height: region.height,
+                })
+                .collect(),
+        );
+    }
+}
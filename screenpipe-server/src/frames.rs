@@ -0,0 +1,101 @@
+use crate::video_utils::get_video_fps;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, ImageFormat};
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::{DatabaseManager, FrameLocation};
+use std::io::Cursor;
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::debug;
+
+/// How long a frame stays on screen at most when no later one was captured, the capture
+/// interval is at most a few seconds while recording.
+pub const DEFAULT_MAX_FRAME_GAP_SECS: i64 = 10;
+
+/// A decoded frame and where it was read from.
+pub struct SeekedFrame {
+    pub location: FrameLocation,
+    pub image: DynamicImage,
+}
+
+/// Where to seek `offset_index` in a video of `fps`: half a frame before its presentation
+/// time, so that rounding can't land on the next frame, as ffmpeg decodes the first frame at
+/// or after the seek point.
+pub fn seek_position(offset_index: i64, fps: f64) -> f64 {
+    ((offset_index as f64 - 0.5) / fps).max(0.0)
+}
+
+/// Decodes frame `offset_index` of `file_path`. Seeks to it when the frame rate is known,
+/// otherwise decodes from the start and selects it by number.
+pub async fn decode_frame(file_path: &str, offset_index: i64) -> Result<DynamicImage> {
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+
+    let mut command = Command::new(&ffmpeg_path);
+    command.args(["-loglevel", "error"]);
+    match get_video_fps(&ffmpeg_path, file_path).await {
+        Ok(fps) if fps > 0.0 => {
+            command.args([
+                "-ss",
+                &format!("{:.6}", seek_position(offset_index, fps)),
+                "-i",
+                file_path,
+            ]);
+        }
+        _ => {
+            // chunks still being written may not report their frame rate yet
+            command.args([
+                "-i",
+                file_path,
+                "-vf",
+                &format!("select=eq(n\\,{})", offset_index),
+            ]);
+        }
+    }
+    command
+        .args(["-frames:v", "1", "-f", "image2pipe", "-c:v", "png", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    debug!("ffmpeg command: {:?}", command);
+
+    let output = command.output().await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed to decode frame {} of {}: {}",
+            offset_index,
+            file_path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    if output.stdout.is_empty() {
+        return Err(anyhow!("{} has no frame {}", file_path, offset_index));
+    }
+    Ok(image::load_from_memory_with_format(
+        &output.stdout,
+        ImageFormat::Png,
+    )?)
+}
+
+/// Decodes the frame on screen at `timestamp`, on `device_name` when given. `None` when
+/// nothing was recorded in the `max_gap_secs` before it.
+pub async fn frame_at(
+    db: &DatabaseManager,
+    timestamp: DateTime<Utc>,
+    device_name: Option<&str>,
+    max_gap_secs: i64,
+) -> Result<Option<SeekedFrame>> {
+    let Some(location) = db
+        .get_frame_at(timestamp, device_name, max_gap_secs)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let image = decode_frame(&location.file_path, location.offset_index).await?;
+    Ok(Some(SeekedFrame { location, image }))
+}
+
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
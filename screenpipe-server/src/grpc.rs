@@ -0,0 +1,370 @@
+use crate::core::FrameWritten;
+use crate::power::{current_throttle, set_capture_paused, subscribe_throttle, ThrottleMode};
+use crate::server::constant_time_eq;
+use chrono::{DateTime, Utc};
+use futures::{future, Stream, StreamExt};
+use proto::screenpipe_server::{Screenpipe, ScreenpipeServer};
+use proto::RecordingState;
+use screenpipe_audio::audio_manager::{AudioManager, AudioManagerStatus};
+use screenpipe_db::{ContentType, DatabaseManager, DeviceType, SearchResult};
+use screenpipe_events::subscribe_to_event;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+pub mod proto {
+    tonic::include_proto!("screenpipe.v1");
+}
+
+/// How often `WatchStatus` looks for changes the recorder doesn't announce, like new frames.
+const STATUS_REFRESH: Duration = Duration::from_secs(10);
+
+/// What the last control command asked for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Control {
+    Recording,
+    Paused { until: DateTime<Utc> },
+    Stopped,
+}
+
+/// The gRPC control interface, for local services that want typed calls and pushed updates
+/// rather than polling the REST api.
+#[derive(Clone)]
+pub struct GrpcControl {
+    db: Arc<DatabaseManager>,
+    audio_manager: Arc<AudioManager>,
+    audio_disabled: bool,
+    started_at: DateTime<Utc>,
+    control: Arc<watch::Sender<Control>>,
+}
+
+pub fn timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+pub fn datetime(timestamp: prost_types::Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp.seconds, timestamp.nanos.try_into().ok()?)
+}
+
+fn internal(e: impl std::fmt::Display) -> Status {
+    Status::internal(e.to_string())
+}
+
+/// Rejects calls without `authorization: Bearer <api_key>` when an api key is set.
+fn authorize(
+    api_key: Option<String>,
+) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |request: Request<()>| {
+        let Some(expected) = api_key.as_deref() else {
+            return Ok(request);
+        };
+        let provided = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match provided {
+            Some(provided) if constant_time_eq(provided.as_bytes(), expected.as_bytes()) => {
+                Ok(request)
+            }
+            _ => Err(Status::unauthenticated("missing or invalid api key")),
+        }
+    }
+}
+
+impl GrpcControl {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        audio_manager: Arc<AudioManager>,
+        audio_disabled: bool,
+    ) -> Self {
+        GrpcControl {
+            db,
+            audio_manager,
+            audio_disabled,
+            started_at: Utc::now(),
+            control: Arc::new(watch::channel(Control::Recording).0),
+        }
+    }
+
+    pub async fn serve(
+        self,
+        addr: SocketAddr,
+        api_key: Option<String>,
+    ) -> Result<(), tonic::transport::Error> {
+        info!("gRPC control interface listening on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(ScreenpipeServer::with_interceptor(self, authorize(api_key)))
+            .serve(addr)
+            .await
+    }
+
+    async fn status(&self) -> proto::Status {
+        let control = *self.control.borrow();
+        let throttle = current_throttle();
+        let state = match control {
+            Control::Stopped => RecordingState::Stopped,
+            Control::Paused { .. } => RecordingState::Paused,
+            Control::Recording if throttle == ThrottleMode::Paused => RecordingState::PowerSaving,
+            Control::Recording => RecordingState::Recording,
+        };
+        let (last_frame_at, last_audio_at, _) = match self.db.get_latest_timestamps().await {
+            Ok(timestamps) => timestamps,
+            Err(e) => {
+                warn!("failed to get latest timestamps: {}", e);
+                (None, None, None)
+            }
+        };
+
+        proto::Status {
+            state: state.into(),
+            paused_until: match control {
+                Control::Paused { until } => Some(timestamp(until)),
+                _ => None,
+            },
+            low_power: throttle.is_low_power(),
+            audio_running: self.audio_manager.status().await == AudioManagerStatus::Running,
+            audio_devices: self
+                .audio_manager
+                .current_devices()
+                .iter()
+                .map(|device| device.to_string())
+                .collect(),
+            last_frame_at: last_frame_at.map(timestamp),
+            last_audio_at: last_audio_at.map(timestamp),
+            started_at: Some(timestamp(self.started_at)),
+        }
+    }
+
+    async fn resume(&self) -> Result<(), Status> {
+        set_capture_paused(false);
+        if !self.audio_disabled {
+            self.audio_manager.start().await.map_err(internal)?;
+        }
+        self.control.send_replace(Control::Recording);
+        Ok(())
+    }
+
+    async fn halt(&self, control: Control) -> Result<(), Status> {
+        set_capture_paused(true);
+        if !self.audio_disabled {
+            self.audio_manager.stop().await.map_err(internal)?;
+        }
+        self.control.send_replace(control);
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl Screenpipe for GrpcControl {
+    type WatchStatusStream = Pin<Box<dyn Stream<Item = Result<proto::Status, Status>> + Send>>;
+    type WatchFramesStream = Pin<Box<dyn Stream<Item = Result<proto::FrameEvent, Status>> + Send>>;
+
+    async fn get_status(
+        &self,
+        _request: Request<proto::StatusRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn watch_status(
+        &self,
+        _request: Request<proto::StatusRequest>,
+    ) -> Result<Response<Self::WatchStatusStream>, Status> {
+        let watcher = (
+            self.clone(),
+            self.control.subscribe(),
+            subscribe_throttle(),
+            None,
+        );
+        let stream = futures::stream::unfold(
+            watcher,
+            |(service, mut control, mut throttle, last_sent)| async move {
+                loop {
+                    if last_sent.is_some() {
+                        tokio::select! {
+                            _ = control.changed() => {}
+                            _ = throttle.changed() => {}
+                            _ = tokio::time::sleep(STATUS_REFRESH) => {}
+                        }
+                    }
+                    let status = service.status().await;
+                    if last_sent.as_ref() != Some(&status) {
+                        let sent = Some(status.clone());
+                        return Some((Ok(status), (service, control, throttle, sent)));
+                    }
+                }
+            },
+        );
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn watch_frames(
+        &self,
+        request: Request<proto::WatchFramesRequest>,
+    ) -> Result<Response<Self::WatchFramesStream>, Status> {
+        let monitor_id = request.into_inner().monitor_id;
+        let stream = subscribe_to_event::<FrameWritten>("frame_written")
+            .filter(move |event| {
+                future::ready(monitor_id.map_or(true, |id| id == event.data.monitor_id))
+            })
+            .map(|event| Ok(event.data.into()));
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn start(
+        &self,
+        _request: Request<proto::StartRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        self.resume().await?;
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn stop(
+        &self,
+        _request: Request<proto::StopRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        self.halt(Control::Stopped).await?;
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn pause(
+        &self,
+        request: Request<proto::PauseRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        let duration_secs = request.into_inner().duration_secs;
+        if duration_secs == 0 {
+            return Err(Status::invalid_argument(
+                "duration_secs must be positive, use Stop to pause until Start",
+            ));
+        }
+        let duration = Duration::from_secs(duration_secs);
+        let until = Utc::now()
+            + chrono::Duration::from_std(duration)
+                .map_err(|_| Status::invalid_argument("duration_secs is too large"))?;
+        self.halt(Control::Paused { until }).await?;
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+            // unless a later command replaced this pause
+            if *service.control.borrow() == (Control::Paused { until }) {
+                if let Err(e) = service.resume().await {
+                    warn!("failed to resume recording after pause: {}", e);
+                }
+            }
+        });
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let request = request.into_inner();
+        let content_type = match request.content_type() {
+            proto::ContentType::All => ContentType::All,
+            proto::ContentType::Ocr => ContentType::OCR,
+            proto::ContentType::Audio => ContentType::Audio,
+            proto::ContentType::Ui => ContentType::UI,
+        };
+        let limit = if request.limit == 0 {
+            20
+        } else {
+            request.limit
+        };
+
+        let results = self
+            .db
+            .search(
+                &request.query,
+                content_type,
+                limit,
+                request.offset,
+                request.start_time.and_then(datetime),
+                request.end_time.and_then(datetime),
+                request.app_name.as_deref(),
+                request.window_name.as_deref(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .map_err(internal)?;
+
+        Ok(Response::new(proto::SearchResponse {
+            hits: results.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+impl From<FrameWritten> for proto::FrameEvent {
+    fn from(frame: FrameWritten) -> Self {
+        proto::FrameEvent {
+            frame_id: frame.frame_id,
+            monitor_id: frame.monitor_id,
+            timestamp: Some(timestamp(frame.timestamp)),
+            app_name: frame.app_name,
+            window_name: frame.window_name,
+            browser_url: frame.browser_url,
+            focused: frame.focused,
+            text_length: frame.text_length as u64,
+        }
+    }
+}
+
+impl From<SearchResult> for proto::SearchHit {
+    fn from(result: SearchResult) -> Self {
+        use proto::search_hit::Content;
+
+        let content = match result {
+            SearchResult::OCR(ocr) => Content::Ocr(proto::OcrHit {
+                frame_id: ocr.frame_id,
+                text: ocr.ocr_text,
+                timestamp: Some(timestamp(ocr.timestamp)),
+                app_name: ocr.app_name,
+                window_name: ocr.window_name,
+                browser_url: ocr.browser_url,
+                file_path: ocr.file_path,
+                offset_index: ocr.offset_index,
+                device_name: ocr.device_name,
+            }),
+            SearchResult::Audio(audio) => Content::Audio(proto::AudioHit {
+                audio_chunk_id: audio.audio_chunk_id,
+                transcription: audio.transcription,
+                timestamp: Some(timestamp(audio.timestamp)),
+                device_name: audio.device_name,
+                device_type: match audio.device_type {
+                    DeviceType::Input => "input",
+                    DeviceType::Output => "output",
+                }
+                .to_string(),
+                speaker_id: audio.speaker.map(|speaker| speaker.id),
+                file_path: audio.file_path,
+            }),
+            SearchResult::UI(ui) => Content::Ui(proto::UiHit {
+                id: ui.id,
+                text: ui.text,
+                timestamp: Some(timestamp(ui.timestamp)),
+                app_name: ui.app_name,
+                window_name: ui.window_name,
+                browser_url: ui.browser_url,
+            }),
+        };
+        proto::SearchHit {
+            content: Some(content),
+        }
+    }
+}
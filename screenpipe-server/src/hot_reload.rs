@@ -0,0 +1,283 @@
+use crate::app_quality::{parse_app_quality, set_app_qualities, AppQualities, CaptureQuality};
+use anyhow::{anyhow, Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use screenpipe_core::capture_policy::{capture_policy, set_capture_policy};
+use screenpipe_events::send_event;
+use screenpipe_vision::diff_threshold::{parse_app_threshold, set_diff_thresholds, DiffThresholds};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+/// Recording settings changed while recording, in the data dir.
+pub const RECORDING_CONFIG_FILE: &str = "recording.json";
+
+/// Most frames per second a reloaded fps may ask for, the recorder's own limit.
+const MAX_RELOADED_FPS: f64 = 30.0;
+
+/// The part of `recording.json` that overrides the command line, e.g.
+/// `{"ignored_windows": ["1Password"], "fps": 0.5, "app_diff_thresholds": ["Terminal=0.05"]}`.
+/// A setting left out keeps its command line value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordingConfig {
+    pub ignored_windows: Option<Vec<String>>,
+    pub included_windows: Option<Vec<String>>,
+    pub fps: Option<f64>,
+    pub diff_threshold: Option<f64>,
+    /// `APP=THRESHOLD`, like `--app-diff-threshold`.
+    pub app_diff_thresholds: Option<Vec<String>>,
+    /// `APP=SCALE[:CRF]`, like `--app-quality`.
+    pub app_qualities: Option<Vec<String>>,
+}
+
+impl RecordingConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: RecordingConfig = serde_json::from_str(content)?;
+        if let Some(fps) = config.fps {
+            if !(fps > 0.0 && fps <= MAX_RELOADED_FPS) {
+                return Err(anyhow!(
+                    "fps must be above 0 and at most {}, got {}",
+                    MAX_RELOADED_FPS,
+                    fps
+                ));
+            }
+        }
+        if let Some(threshold) = config.diff_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(anyhow!(
+                    "diff_threshold must be between 0 and 1, got {}",
+                    threshold
+                ));
+            }
+        }
+        for value in config.app_diff_thresholds.iter().flatten() {
+            parse_app_threshold(value).map_err(|e| anyhow!(e))?;
+        }
+        for value in config.app_qualities.iter().flatten() {
+            parse_app_quality(value).map_err(|e| anyhow!(e))?;
+        }
+        Ok(config)
+    }
+
+    /// The config at `path`, one overriding nothing when there's no file.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read recording config {}", path.display()))?;
+        Self::parse(&content)
+    }
+}
+
+/// The recording settings in effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingSettings {
+    pub ignored_windows: Vec<String>,
+    pub included_windows: Vec<String>,
+    pub fps: f64,
+    pub diff_threshold: f64,
+    pub app_diff_thresholds: Vec<(String, f64)>,
+    pub app_qualities: Vec<(String, CaptureQuality)>,
+}
+
+impl RecordingSettings {
+    /// These settings with the ones `config` sets.
+    pub fn overridden_by(&self, config: &RecordingConfig) -> RecordingSettings {
+        RecordingSettings {
+            ignored_windows: config
+                .ignored_windows
+                .clone()
+                .unwrap_or_else(|| self.ignored_windows.clone()),
+            included_windows: config
+                .included_windows
+                .clone()
+                .unwrap_or_else(|| self.included_windows.clone()),
+            fps: config.fps.unwrap_or(self.fps),
+            diff_threshold: config.diff_threshold.unwrap_or(self.diff_threshold),
+            app_diff_thresholds: match &config.app_diff_thresholds {
+                // checked when the config was parsed
+                Some(values) => values
+                    .iter()
+                    .filter_map(|value| parse_app_threshold(value).ok())
+                    .collect(),
+                None => self.app_diff_thresholds.clone(),
+            },
+            app_qualities: match &config.app_qualities {
+                Some(values) => values
+                    .iter()
+                    .filter_map(|value| parse_app_quality(value).ok())
+                    .collect(),
+                None => self.app_qualities.clone(),
+            },
+        }
+    }
+
+    /// Makes the running recorders follow these settings: blocklists and thresholds from
+    /// their next frame on, app qualities from their next chunk, the fps from the next capture
+    /// restart. Videos keep the fps they were started with, as in low-power mode.
+    pub fn apply(&self) {
+        set_capture_policy(
+            capture_policy()
+                .as_ref()
+                .clone()
+                .with_windows(&self.ignored_windows, &self.included_windows),
+        );
+        set_diff_thresholds(self.app_diff_thresholds.iter().fold(
+            DiffThresholds::new(self.diff_threshold),
+            |thresholds, (app, threshold)| thresholds.with_app(app, *threshold),
+        ));
+        set_app_qualities(
+            self.app_qualities
+                .iter()
+                .fold(AppQualities::default(), |qualities, (app, quality)| {
+                    qualities.with_app(app, *quality)
+                }),
+        );
+        update_capture_fps(|fps| fps.settings = Some(self.fps));
+    }
+}
+
+#[derive(Default)]
+struct CaptureFps {
+    settings: Option<f64>,
+    boost: Option<f64>,
+}
+
+impl CaptureFps {
+    fn effective(&self) -> Option<f64> {
+        match (self.settings, self.boost) {
+            (Some(settings), Some(boost)) => Some(settings.max(boost)),
+            (settings, boost) => settings.or(boost),
+        }
+    }
+}
+
+static CAPTURE_FPS_SOURCES: Lazy<Mutex<CaptureFps>> = Lazy::new(Default::default);
+static CAPTURE_FPS: Lazy<watch::Sender<Option<f64>>> = Lazy::new(|| watch::channel(None).0);
+
+fn update_capture_fps(change: impl FnOnce(&mut CaptureFps)) {
+    let mut sources = CAPTURE_FPS_SOURCES.lock().unwrap();
+    change(&mut sources);
+    let effective = sources.effective();
+    CAPTURE_FPS.send_if_modified(|fps| std::mem::replace(fps, effective) != effective);
+}
+
+/// Captures at `fps` at least, whatever the settings, until it's set back to `None`, e.g.
+/// for the length of a meeting.
+pub fn boost_capture_fps(fps: Option<f64>) {
+    update_capture_fps(|sources| sources.boost = fps);
+}
+
+/// The fps the recorders capture at, `None` until settings are applied.
+pub fn subscribe_capture_fps() -> watch::Receiver<Option<f64>> {
+    CAPTURE_FPS.subscribe()
+}
+
+type ApplyConfig = Box<dyn Fn(&Path) -> Result<()> + Send + Sync>;
+
+/// A config file applied again each time it changes.
+pub struct WatchedConfig {
+    path: PathBuf,
+    apply: ApplyConfig,
+}
+
+impl WatchedConfig {
+    /// `apply` reads the file at the path it's given, which may no longer exist.
+    pub fn new(path: PathBuf, apply: impl Fn(&Path) -> Result<()> + Send + Sync + 'static) -> Self {
+        WatchedConfig {
+            path,
+            apply: Box::new(apply),
+        }
+    }
+}
+
+/// How long changes to a file are let settle before it's read, editors write a file in
+/// several steps.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// The path of `path` with its directory resolved, to match the paths of file events.
+fn resolved(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    match path.file_name() {
+        Some(name) => dir.join(name),
+        None => dir,
+    }
+}
+
+fn reload(config: &WatchedConfig) {
+    let file = config.path.display().to_string();
+    match (config.apply)(&config.path) {
+        Ok(()) => {
+            info!("applied the changes to {}", file);
+            let _ = send_event("config_reloaded", serde_json::json!({ "file": file }));
+        }
+        Err(e) => {
+            warn!(
+                "failed to apply {}, keeping the previous settings: {}",
+                file, e
+            );
+            let _ = send_event(
+                "config_reload_failed",
+                serde_json::json!({ "file": file, "error": e.to_string() }),
+            );
+        }
+    }
+}
+
+/// Watches the directories of `configs` and applies a config again once its file is
+/// created, changed or removed.
+pub async fn watch_configs(configs: Vec<WatchedConfig>) -> Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+        }
+    })?;
+
+    let paths: Vec<PathBuf> = configs
+        .iter()
+        .map(|config| resolved(&config.path))
+        .collect();
+    let dirs: BTreeSet<&Path> = paths.iter().filter_map(|path| path.parent()).collect();
+    for dir in dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch {}", dir.display()))?;
+    }
+    info!(
+        "applying changes to {} while recording",
+        configs
+            .iter()
+            .map(|config| config.path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    while let Some(path) = receiver.recv().await {
+        tokio::time::sleep(SETTLE_TIME).await;
+        let mut changed = BTreeSet::from([path]);
+        while let Ok(path) = receiver.try_recv() {
+            changed.insert(path);
+        }
+        for (config, path) in configs.iter().zip(&paths) {
+            if changed.iter().any(|changed| resolved(changed) == *path) {
+                reload(config);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,492 @@
+use crate::categorization::categorize;
+use crate::excise::{ExciseSegmentPayload, EXCISE_MAX_ATTEMPTS, EXCISE_SEGMENT_JOB};
+use crate::jobs::enqueue_job;
+use crate::server::constant_time_eq;
+use crate::storage::{local_hostname, sanitize_component};
+use crate::upload::{hex, hmac_sha256, remote_key};
+use anyhow::{anyhow, Context, Result};
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use screenpipe_db::{DatabaseManager, RemoteFrame};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+pub const HUB_SIGNATURE_HEADER: &str = "x-screenpipe-signature";
+pub const HUB_TIMESTAMP_HEADER: &str = "x-screenpipe-timestamp";
+pub const HUB_HOST_HEADER: &str = "x-screenpipe-host";
+pub const HUB_NONCE_HEADER: &str = "x-screenpipe-nonce";
+/// SHA-256 of a pushed segment, sent ahead of its streamed body and checked once received.
+pub const HUB_CONTENT_SHA256_HEADER: &str = "x-screenpipe-content-sha256";
+
+/// Requests signed longer ago than this, or this far in the future, are refused, so a
+/// captured request can't be replayed later.
+pub const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// Frames pushed per request, kept small as every frame carries its OCR boxes.
+pub const HUB_PUSH_BATCH: u32 = 100;
+
+/// Excised ranges pushed per request.
+const HUB_EXCISION_BATCH: u32 = 100;
+
+/// Largest body of a push of frames the hub accepts.
+pub const HUB_MAX_PUSH_BYTES: usize = 64 * 1024 * 1024;
+
+/// Frames are only pushed once this old, for their text to be indexed first.
+const HUB_PUSH_DELAY_SECS: i64 = 60;
+
+const HUB_PUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where a peer keeps the ids of the last frame the hub indexed and of the last redaction it
+/// applied, in the data directory.
+pub const HUB_CURSOR_FILE: &str = "hub_cursor.json";
+
+/// Frames a peer pushes to `POST /hub/frames`, their chunks given by key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HubPush {
+    pub frames: Vec<RemoteFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HubPushResponse {
+    pub inserted: usize,
+}
+
+/// A range a peer excised, pushed to `POST /hub/excisions` for the hub to remove it too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HubExcision {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HubExcisions {
+    pub excisions: Vec<HubExcision>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HubCursor {
+    last_frame_id: i64,
+    #[serde(default)]
+    last_redaction_id: i64,
+}
+
+/// How this instance takes the pushes of its peers.
+#[derive(Debug, Clone)]
+pub struct HubConfig {
+    /// The token pushes are signed with.
+    pub token: String,
+    /// Where the files of the peers are kept, each in a directory named after it: the root
+    /// of this machine's own storage.
+    pub root: PathBuf,
+}
+
+static HUB: OnceCell<HubConfig> = OnceCell::new();
+
+/// Makes this instance a hub accepting pushes signed with the token of `config`. Only the
+/// first call has an effect.
+pub fn set_hub(config: HubConfig) {
+    if HUB.set(config).is_err() {
+        warn!("hub already set");
+    }
+}
+
+pub fn hub() -> Option<&'static HubConfig> {
+    HUB.get()
+}
+
+/// Nonces of the requests the hub accepted, kept as long as their timestamps are, so a
+/// request can't be replayed within [`MAX_CLOCK_SKEW_SECS`].
+#[derive(Debug, Default)]
+pub struct HubNonces {
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl HubNonces {
+    /// Records `nonce`, sent at `timestamp`, failing when it was already used.
+    pub fn check(&self, nonce: &str, timestamp: i64, now: i64) -> Result<()> {
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, sent_at| (now - *sent_at).abs() <= MAX_CLOCK_SKEW_SECS);
+        if seen.contains_key(nonce) {
+            return Err(anyhow!("nonce {} already used", nonce));
+        }
+        seen.insert(nonce.to_string(), timestamp);
+        Ok(())
+    }
+}
+
+/// Nonces of the pushes this hub accepted.
+pub static HUB_NONCES: Lazy<HubNonces> = Lazy::new(HubNonces::default);
+
+/// Signature of a request to a hub: an HMAC-SHA256 with the hub token of the request line,
+/// the peer's hostname, the time, a nonce unique to the request and the SHA-256 of the body.
+pub fn hub_signature(
+    token: &str,
+    method: &str,
+    path: &str,
+    host: &str,
+    timestamp: i64,
+    nonce: &str,
+    body_digest: &str,
+) -> String {
+    hex(&hmac_sha256(
+        token.as_bytes(),
+        &format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, host, timestamp, nonce, body_digest
+        ),
+    ))
+}
+
+pub fn body_digest(body: &[u8]) -> String {
+    hex(&Sha256::digest(body))
+}
+
+/// SHA-256 of the file at `path`, read in chunks.
+pub async fn file_digest(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Headers signing a request to a hub from this machine.
+pub fn hub_request_headers(
+    token: &str,
+    method: &str,
+    path: &str,
+    body_digest: &str,
+) -> Vec<(&'static str, String)> {
+    let host = local_hostname();
+    let timestamp = Utc::now().timestamp();
+    let nonce = Uuid::new_v4().to_string();
+    vec![
+        (
+            HUB_SIGNATURE_HEADER,
+            hub_signature(token, method, path, &host, timestamp, &nonce, body_digest),
+        ),
+        (HUB_TIMESTAMP_HEADER, timestamp.to_string()),
+        (HUB_NONCE_HEADER, nonce),
+        (HUB_HOST_HEADER, host),
+    ]
+}
+
+/// Checks the signature of a request to the hub and returns the hostname of the peer that
+/// sent it. The nonce of the request is recorded in `nonces`, a request sent again is refused.
+pub fn verify_hub_request(
+    token: &str,
+    method: &str,
+    path: &str,
+    headers: &HeaderMap,
+    body_digest: &str,
+    nonces: &HubNonces,
+    now: i64,
+) -> Result<String> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| anyhow!("missing {} header", name))
+    };
+    let host = header(HUB_HOST_HEADER)?;
+    if host.chars().all(|c| c == '.') || sanitize_component(host) != host {
+        return Err(anyhow!("invalid host name {}", host));
+    }
+    let timestamp: i64 = header(HUB_TIMESTAMP_HEADER)?
+        .parse()
+        .context("invalid timestamp")?;
+    if (now - timestamp).abs() > MAX_CLOCK_SKEW_SECS {
+        return Err(anyhow!(
+            "request signed at {}, too far from {}",
+            timestamp,
+            now
+        ));
+    }
+    let nonce = header(HUB_NONCE_HEADER)?;
+    let expected = hub_signature(token, method, path, host, timestamp, nonce, body_digest);
+    if !constant_time_eq(
+        header(HUB_SIGNATURE_HEADER)?.as_bytes(),
+        expected.as_bytes(),
+    ) {
+        return Err(anyhow!("invalid signature"));
+    }
+    // only once the signature holds, so forged requests can't fill the cache
+    nonces.check(nonce, timestamp, now)?;
+    Ok(host.to_string())
+}
+
+/// Key of a file of `host` on the hub: its key under the data directory, which starts with
+/// the hostname for the files recorded there, or its name in the host's directory otherwise.
+pub fn hub_key(host: &str, key: &str) -> String {
+    if key.split('/').next() == Some(host) {
+        key.to_string()
+    } else {
+        format!("{}/{}", host, key)
+    }
+}
+
+/// Where the hub keeps the file `key` pushed by `host`, which must be in the host's
+/// directory under `root`.
+pub fn hub_file_path(root: &Path, host: &str, key: &str) -> Result<PathBuf> {
+    let relative = Path::new(key);
+    let mut components = relative.components();
+    if components.next() != Some(Component::Normal(host.as_ref())) {
+        return Err(anyhow!("{} isn't in the directory of {}", key, host));
+    }
+    if !components.all(|component| matches!(component, Component::Normal(_))) {
+        return Err(anyhow!("invalid key {}", key));
+    }
+    if relative.components().count() < 2 {
+        return Err(anyhow!("{} isn't a file", key));
+    }
+    Ok(root.join(relative))
+}
+
+/// Indexes frames pushed by `host`, pointing them to where their chunks are kept on the hub
+/// and categorizing them by the hub's rules.
+pub async fn index_pushed_frames(
+    db: &DatabaseManager,
+    root: &Path,
+    host: &str,
+    mut frames: Vec<RemoteFrame>,
+) -> Result<usize> {
+    for frame in &mut frames {
+        frame.file_path = hub_file_path(root, host, &frame.file_path)?
+            .to_string_lossy()
+            .into_owned();
+        frame.category = categorize(
+            frame.app_name.as_deref().unwrap_or_default(),
+            frame.window_name.as_deref().unwrap_or_default(),
+            frame.browser_url.as_deref(),
+        );
+    }
+    Ok(db.insert_remote_frames(host, &frames).await?)
+}
+
+/// Removes from the hub what `host` excised: its frames in the range, and the excised tail
+/// of their chunks, trimmed as the peer trims its own. Returns the chunks queued for trimming.
+pub async fn excise_pushed_range(
+    db: &DatabaseManager,
+    host: &str,
+    excision: &HubExcision,
+) -> Result<usize> {
+    let video_chunks = db
+        .excise_remote_time_range(host, excision.start_time, excision.end_time)
+        .await?;
+    for video_path in &video_chunks {
+        let payload = ExciseSegmentPayload {
+            video_path: video_path.clone(),
+            start_time: excision.start_time,
+        };
+        enqueue_job(db, EXCISE_SEGMENT_JOB, &payload, EXCISE_MAX_ATTEMPTS).await?;
+    }
+    Ok(video_chunks.len())
+}
+
+/// Writes a segment pushed by `host` to the hub's data directory, replacing it whole once
+/// received, so a push cut short doesn't leave half a video. The segment is only kept when
+/// its SHA-256 is `digest`, the one the peer signed.
+pub async fn store_segment<S, B, E>(
+    root: &Path,
+    host: &str,
+    key: &str,
+    digest: &str,
+    body: S,
+) -> Result<u64>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let path = hub_file_path(root, host, key)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = PathBuf::from(format!("{}.part", path.display()));
+    let mut file = tokio::fs::File::create(&partial).await?;
+    let mut body = body;
+    let mut hasher = Sha256::new();
+    let mut written = 0;
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                drop(file);
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e.into());
+            }
+        };
+        hasher.update(chunk.as_ref());
+        file.write_all(chunk.as_ref()).await?;
+        written += chunk.as_ref().len() as u64;
+    }
+    if hex(&hasher.finalize()) != digest {
+        drop(file);
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(anyhow!(
+            "{} doesn't match the digest it was signed with",
+            key
+        ));
+    }
+    file.sync_all().await?;
+    tokio::fs::rename(&partial, &path).await?;
+    debug!(
+        "stored {} ({} bytes) from {}",
+        path.display(),
+        written,
+        host
+    );
+    Ok(written)
+}
+
+fn read_cursor(path: &Path) -> HubCursor {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_cursor(path: &Path, cursor: &HubCursor) -> Result<()> {
+    let partial = path.with_extension("json.part");
+    std::fs::write(&partial, serde_json::to_string(cursor)?)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Posts `body` as JSON to `path` on the hub, signed.
+async fn post_to_hub(
+    client: &Client,
+    url: &str,
+    token: &str,
+    path: &str,
+    body: Vec<u8>,
+) -> Result<reqwest::Response> {
+    let mut request = client
+        .post(format!("{}{}", url.trim_end_matches('/'), path))
+        .header(CONTENT_TYPE, "application/json");
+    for (name, value) in hub_request_headers(token, "POST", path, &body_digest(&body)) {
+        request = request.header(name, value);
+    }
+    let response = request.body(body).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("hub refused {} with {}: {}", path, status, body));
+    }
+    Ok(response)
+}
+
+/// Pushes the next batch of frames indexed here to the hub. Returns how many were pushed.
+pub async fn push_frames(
+    client: &Client,
+    db: &DatabaseManager,
+    root: &Path,
+    cursor_path: &Path,
+    url: &str,
+    token: &str,
+) -> Result<usize> {
+    let host = local_hostname();
+    let cutoff = Utc::now() - chrono::Duration::seconds(HUB_PUSH_DELAY_SECS);
+    let mut cursor = read_cursor(cursor_path);
+    let mut frames: Vec<RemoteFrame> = db
+        .get_local_frames_for_hub(cursor.last_frame_id, HUB_PUSH_BATCH)
+        .await?
+        .into_iter()
+        // stops at the first recent frame, the cursor mustn't skip over it
+        .take_while(|frame| frame.timestamp <= cutoff)
+        .collect();
+    let Some(last_frame_id) = frames.last().map(|frame| frame.frame_id) else {
+        return Ok(0);
+    };
+    for frame in &mut frames {
+        frame.file_path = hub_key(&host, &remote_key(root, Path::new(&frame.file_path)));
+    }
+
+    let count = frames.len();
+    let body = serde_json::to_vec(&HubPush { frames })?;
+    let response = post_to_hub(client, url, token, "/hub/frames", body).await?;
+    let pushed: HubPushResponse = response.json().await?;
+    cursor.last_frame_id = last_frame_id;
+    write_cursor(cursor_path, &cursor)?;
+    debug!(
+        "pushed frames up to {} to the hub, {} new",
+        last_frame_id, pushed.inserted
+    );
+    Ok(count)
+}
+
+/// Pushes the ranges excised here since the last push to the hub, for it to remove them from
+/// its copy. Returns how many were pushed.
+pub async fn push_excisions(
+    client: &Client,
+    db: &DatabaseManager,
+    cursor_path: &Path,
+    url: &str,
+    token: &str,
+) -> Result<usize> {
+    let mut cursor = read_cursor(cursor_path);
+    let redactions = db
+        .get_redactions_after(cursor.last_redaction_id, HUB_EXCISION_BATCH)
+        .await?;
+    let Some(last_redaction_id) = redactions.last().map(|redaction| redaction.id) else {
+        return Ok(0);
+    };
+    let excisions: Vec<HubExcision> = redactions
+        .iter()
+        .map(|redaction| HubExcision {
+            start_time: redaction.start_time,
+            end_time: redaction.end_time,
+        })
+        .collect();
+    let count = excisions.len();
+    let body = serde_json::to_vec(&HubExcisions { excisions })?;
+    post_to_hub(client, url, token, "/hub/excisions", body).await?;
+    cursor.last_redaction_id = last_redaction_id;
+    write_cursor(cursor_path, &cursor)?;
+    debug!("pushed redactions up to {} to the hub", last_redaction_id);
+    Ok(count)
+}
+
+/// Keeps the hub's index up to date with this machine's, catching up batch by batch. Ranges
+/// excised here are pushed before the frames, so none of them is pushed after its excision.
+pub async fn push_to_hub(
+    db: Arc<DatabaseManager>,
+    root: PathBuf,
+    cursor_path: PathBuf,
+    url: String,
+    token: String,
+) {
+    info!("pushing the index to the hub at {}", url);
+    let client = Client::new();
+    loop {
+        if let Err(e) = push_excisions(&client, &db, &cursor_path, &url, &token).await {
+            warn!("failed to push excisions to the hub: {}", e);
+            tokio::time::sleep(HUB_PUSH_INTERVAL).await;
+            continue;
+        }
+        match push_frames(&client, &db, &root, &cursor_path, &url, &token).await {
+            // a full batch, there may be more to catch up on
+            Ok(pushed) if pushed == HUB_PUSH_BATCH as usize => continue,
+            Ok(_) => {}
+            Err(e) => warn!("failed to push frames to the hub: {}", e),
+        }
+        tokio::time::sleep(HUB_PUSH_INTERVAL).await;
+    }
+}
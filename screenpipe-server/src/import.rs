@@ -0,0 +1,311 @@
+use crate::categorization::categorize;
+use crate::ocr_language::{ocr_language_code, tag_block_languages};
+use crate::storage::Storage;
+use crate::video::{finish_ffmpeg_process, start_ffmpeg_process, write_frame_to_ffmpeg};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use screenpipe_core::{find_ffmpeg_path, find_ffprobe_path, Language};
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_screenshot_by_window::CapturedWindow;
+use screenpipe_vision::core::{process_ocr_task, OcrTaskData};
+use screenpipe_vision::utils::compare_with_previous_image;
+use screenpipe_vision::OcrEngine;
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Device imported recordings are indexed under, unless another one is given.
+pub const IMPORTED_DEVICE_NAME: &str = "imported";
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// When the first frame of the recording was shown.
+    pub start_time: DateTime<Utc>,
+    pub device_name: String,
+    /// Frames decoded per second of the recording, like the `--fps` of the recorder.
+    pub fps: f64,
+    /// Frames that differ less than this from the last kept one are skipped, like
+    /// `--diff-threshold`.
+    pub diff_threshold: f64,
+    pub ocr_engine: Arc<OcrEngine>,
+    pub languages: Vec<Language>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    /// The video the kept frames were encoded to, in the data dir.
+    pub video_path: PathBuf,
+    pub frames_decoded: u64,
+    pub frames_indexed: u64,
+    pub text_chars: usize,
+}
+
+/// When the `index`th decoded frame was shown, decoding `fps` frames per second from
+/// `start_time`.
+pub fn frame_timestamp(start_time: DateTime<Utc>, index: u64, fps: f64) -> DateTime<Utc> {
+    start_time + Duration::milliseconds((index as f64 * 1000.0 / fps).round() as i64)
+}
+
+/// Width and height of the first video stream of `path`, as stored, without rotation.
+pub(crate) async fn video_dimensions(path: &Path) -> Result<(u32, u32)> {
+    let ffprobe_path = find_ffprobe_path().ok_or_else(|| anyhow!("ffprobe not found"))?;
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height",
+            "-of",
+            "csv=p=0:s=x",
+        ])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("failed to run ffprobe")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffprobe failed on {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (width, height) = stdout
+        .trim()
+        .split_once('x')
+        .ok_or_else(|| anyhow!("{} has no video stream", path.display()))?;
+    Ok((width.trim().parse()?, height.trim().parse()?))
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    image.write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)?;
+    Ok(buffer)
+}
+
+/// OCRs `image` the way the recorder OCRs a captured window.
+pub(crate) async fn ocr_frame(
+    image: DynamicImage,
+    window_name: &str,
+    frame_number: u64,
+    ocr_engine: &OcrEngine,
+    languages: &[Language],
+) -> Result<Option<screenpipe_vision::core::WindowOcrResult>> {
+    let (result_tx, mut result_rx) = mpsc::channel(1);
+    let window = CapturedWindow {
+        image: image.clone(),
+        app_name: String::new(),
+        window_name: window_name.to_string(),
+        process_id: 0,
+        is_focused: true,
+        bounds: None,
+    };
+    process_ocr_task(
+        OcrTaskData {
+            image,
+            window_images: vec![window],
+            frame_number,
+            timestamp: Instant::now(),
+            result_tx,
+        },
+        ocr_engine,
+        languages.to_vec(),
+    )
+    .await
+    .map_err(|e| anyhow!("ocr failed on frame {}: {}", frame_number, e))?;
+    Ok(result_rx
+        .recv()
+        .await
+        .and_then(|result| result.window_ocr_results.into_iter().next()))
+}
+
+/// Stores the text `ocr` read on the frame `frame_id`, with its languages, as the recorder
+/// does. Returns its length.
+pub(crate) async fn store_ocr_text(
+    db: &DatabaseManager,
+    frame_id: i64,
+    ocr: screenpipe_vision::core::WindowOcrResult,
+    ocr_engine: &OcrEngine,
+) -> Result<usize> {
+    let mut text_blocks = ocr.text_json;
+    let block_languages = tag_block_languages(&mut text_blocks);
+    db.insert_ocr_text(
+        frame_id,
+        &ocr.text,
+        &serde_json::to_string(&text_blocks).unwrap_or_default(),
+        Arc::new(ocr_engine.clone().into()),
+    )
+    .await?;
+    if let Err(e) = db
+        .set_ocr_text_language(frame_id, ocr_language_code(&ocr.text))
+        .await
+    {
+        warn!("failed to store language of frame {}: {}", frame_id, e);
+    }
+    if !block_languages.is_empty() {
+        if let Err(e) = db.set_ocr_block_languages(frame_id, &block_languages).await {
+            warn!(
+                "failed to store block languages of frame {}: {}",
+                frame_id, e
+            );
+        }
+    }
+    Ok(ocr.text.len())
+}
+
+/// Decodes the screen recording at `path`, keeps the frames that changed, OCRs them and adds
+/// them to the index as if they had been recorded from `options.start_time` on. The kept
+/// frames are encoded into a new video in the data dir, so the recording is played back and
+/// its frames served like screenpipe's own, the file itself is left as it is.
+pub async fn import_recording(
+    db: &DatabaseManager,
+    storage: &Storage,
+    path: &Path,
+    options: &ImportOptions,
+) -> Result<ImportSummary> {
+    if !(options.fps > 0.0) {
+        return Err(anyhow!("fps must be above 0, got {}", options.fps));
+    }
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let (width, height) = video_dimensions(path).await?;
+    let frame_len = width as usize * height as usize * 3;
+    let window_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut decoder = Command::new(&ffmpeg_path)
+        .args(["-v", "error", "-noautorotate", "-i"])
+        .arg(path)
+        .args([
+            "-vf",
+            &format!("fps={}", options.fps),
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+            "-",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start ffmpeg")?;
+    let mut frames = decoder.stdout.take().expect("ffmpeg stdout is piped");
+    // read as it comes, ffmpeg would block on a full pipe while frames are read
+    let mut stderr = decoder.stderr.take().expect("ffmpeg stderr is piped");
+    let stderr = tokio::spawn(async move {
+        let mut output = Vec::new();
+        let _ = stderr.read_to_end(&mut output).await;
+        output
+    });
+
+    let video_path = storage.imported_video_path(options.start_time)?;
+    let video_file = video_path.to_string_lossy().to_string();
+    let mut encoder = start_ffmpeg_process(&video_file, options.fps).await?;
+    let mut encoder_stdin = encoder.stdin.take().expect("ffmpeg stdin is piped");
+    db.insert_video_chunk(&video_file, &options.device_name)
+        .await?;
+    info!(
+        "importing {} ({}x{}) from {} into {}",
+        path.display(),
+        width,
+        height,
+        options.start_time,
+        video_path.display()
+    );
+
+    let mut summary = ImportSummary {
+        video_path: video_path.clone(),
+        ..Default::default()
+    };
+    let mut previous: Option<DynamicImage> = None;
+    let mut buffer = vec![0u8; frame_len];
+    loop {
+        match frames.read_exact(&mut buffer).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let index = summary.frames_decoded;
+        summary.frames_decoded += 1;
+        let image = DynamicImage::ImageRgb8(
+            RgbImage::from_raw(width, height, buffer.clone())
+                .ok_or_else(|| anyhow!("frame {} is truncated", index))?,
+        );
+
+        let average =
+            compare_with_previous_image(previous.as_ref(), &image, &mut None, index, &mut 0.0)
+                .await?;
+        if previous.is_some() && average < options.diff_threshold {
+            debug!(
+                "skipping frame {}, difference {:.3} below threshold",
+                index, average
+            );
+            continue;
+        }
+        previous = Some(image.clone());
+
+        write_frame_to_ffmpeg(&mut encoder_stdin, &encode_png(&image)?).await?;
+        let timestamp = frame_timestamp(options.start_time, index, options.fps);
+        let frame_id = db
+            .insert_frame(
+                &options.device_name,
+                Some(timestamp),
+                None,
+                None,
+                Some(window_name.as_str()),
+                true,
+                categorize("", &window_name, None).as_deref(),
+            )
+            .await?;
+        summary.frames_indexed += 1;
+
+        let Some(ocr) = ocr_frame(
+            image,
+            &window_name,
+            index,
+            &options.ocr_engine,
+            &options.languages,
+        )
+        .await?
+        else {
+            continue;
+        };
+        summary.text_chars += store_ocr_text(db, frame_id, ocr, &options.ocr_engine).await?;
+    }
+
+    finish_ffmpeg_process(encoder, Some(encoder_stdin)).await;
+    let status = decoder.wait().await?;
+    if !status.success() {
+        let stderr = stderr.await.unwrap_or_default();
+        return Err(anyhow!(
+            "ffmpeg failed to decode {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&stderr).trim()
+        ));
+    }
+    if summary.frames_decoded == 0 {
+        return Err(anyhow!("{} has no frames", path.display()));
+    }
+    info!(
+        "imported {}: indexed {} of {} frames, {} characters of text",
+        path.display(),
+        summary.frames_indexed,
+        summary.frames_decoded,
+        summary.text_chars
+    );
+    Ok(summary)
+}
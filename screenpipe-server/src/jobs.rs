@@ -0,0 +1,412 @@
+use crate::dedup::{dedup_frames_job, DEDUP_FRAMES_JOB};
+use crate::deferred_ocr::{ocr_chunk_job, OCR_CHUNK_JOB};
+use crate::evidence::{seal_segment_job, SEAL_SEGMENT_JOB};
+use crate::excise::{excise_segment_job, EXCISE_SEGMENT_JOB};
+use crate::mosaic::{render_day_mosaic_job, RENDER_DAY_MOSAIC_JOB};
+use crate::ocr_language::{detect_ocr_language_job, DETECT_OCR_LANGUAGE_JOB};
+use crate::power::{current_throttle, read_power_status, ThrottleMode};
+use crate::presentation::{export_presentation_job, EXPORT_PRESENTATION_JOB};
+use crate::pyramid::{build_pyramid_job, BUILD_PYRAMID_JOB};
+use crate::scheduled_export::{scheduled_export_job, SCHEDULED_EXPORT_JOB};
+use crate::storage::Storage;
+use crate::text_embeds::{embed_text_job, EMBED_TEXT_JOB};
+use crate::thumbnails::{build_thumbnail_strip_job, BUILD_THUMBNAIL_STRIP_JOB};
+use crate::timelapse::{render_timelapse_job, RENDER_TIMELAPSE_JOB};
+use crate::upload::{upload_segment_job, UPLOAD_SEGMENT_JOB};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::{DatabaseManager, Job};
+use screenpipe_events::send_event;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use sysinfo::{CpuExt, System, SystemExt};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Re-encodes a chunk recorded with the low-power encoder to the regular x265 settings.
+pub const REENCODE_CHUNK_JOB: &str = "reencode_chunk";
+
+pub type JobHandler = Arc<dyn Fn(Job, JobProgress) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Lets a running job report how far along it is.
+#[derive(Clone)]
+pub struct JobProgress {
+    db: Arc<DatabaseManager>,
+    job_id: i64,
+    kind: String,
+}
+
+impl JobProgress {
+    pub async fn report(&self, progress: f64) {
+        if let Err(e) = self.db.update_job_progress(self.job_id, progress).await {
+            warn!("failed to update progress of job {}: {}", self.job_id, e);
+        }
+        let _ = send_event(
+            "job_progress",
+            serde_json::json!({ "id": self.job_id, "kind": self.kind, "progress": progress }),
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobQueueConfig {
+    pub workers: usize,
+    /// Only run jobs on battery while overall cpu usage stays below this percentage.
+    pub max_cpu_usage: f32,
+    pub max_attempts: i64,
+    pub retry_delay: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for JobQueueConfig {
+    fn default() -> Self {
+        JobQueueConfig {
+            workers: 1,
+            max_cpu_usage: 20.0,
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Persistent queue of deferred heavy processing, drained by a pool of background workers
+/// while the machine is on AC power or idle.
+pub struct JobQueue {
+    db: Arc<DatabaseManager>,
+    config: JobQueueConfig,
+    handlers: HashMap<String, JobHandler>,
+}
+
+impl JobQueue {
+    pub fn new(db: Arc<DatabaseManager>, config: JobQueueConfig) -> Self {
+        JobQueue {
+            db,
+            config,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Queue with the built-in handlers registered.
+    pub fn with_default_handlers(
+        db: Arc<DatabaseManager>,
+        storage: Storage,
+        config: JobQueueConfig,
+    ) -> Self {
+        let mosaic_db = db.clone();
+        let embedding_db = db.clone();
+        let language_db = db.clone();
+        let presentation_db = db.clone();
+        let dedup_db = db.clone();
+        let export_db = db.clone();
+        let upload_db = db.clone();
+        let upload_root = storage.root().to_path_buf();
+        let thumbnail_db = db.clone();
+        let seal_db = db.clone();
+        let seal_storage = storage.clone();
+        let timelapse_db = db.clone();
+        let timelapse_storage = storage.clone();
+        let excise_db = db.clone();
+        let ocr_db = db.clone();
+        Self::new(db, config)
+            .register(REENCODE_CHUNK_JOB, |job, progress| {
+                Box::pin(reencode_chunk(job, progress))
+            })
+            .register(BUILD_PYRAMID_JOB, |job, progress| {
+                Box::pin(build_pyramid_job(job, progress))
+            })
+            .register(RENDER_DAY_MOSAIC_JOB, move |job, progress| {
+                Box::pin(render_day_mosaic_job(
+                    mosaic_db.clone(),
+                    storage.clone(),
+                    job,
+                    progress,
+                ))
+            })
+            .register(EMBED_TEXT_JOB, move |job, progress| {
+                Box::pin(embed_text_job(embedding_db.clone(), job, progress))
+            })
+            .register(DETECT_OCR_LANGUAGE_JOB, move |job, progress| {
+                Box::pin(detect_ocr_language_job(language_db.clone(), job, progress))
+            })
+            .register(EXPORT_PRESENTATION_JOB, move |job, progress| {
+                Box::pin(export_presentation_job(
+                    presentation_db.clone(),
+                    job,
+                    progress,
+                ))
+            })
+            .register(DEDUP_FRAMES_JOB, move |job, progress| {
+                Box::pin(dedup_frames_job(dedup_db.clone(), job, progress))
+            })
+            .register(SCHEDULED_EXPORT_JOB, move |job, progress| {
+                Box::pin(scheduled_export_job(export_db.clone(), job, progress))
+            })
+            .register(BUILD_THUMBNAIL_STRIP_JOB, move |job, progress| {
+                Box::pin(build_thumbnail_strip_job(
+                    thumbnail_db.clone(),
+                    job,
+                    progress,
+                ))
+            })
+            .register(SEAL_SEGMENT_JOB, move |job, progress| {
+                Box::pin(seal_segment_job(
+                    seal_db.clone(),
+                    seal_storage.clone(),
+                    job,
+                    progress,
+                ))
+            })
+            .register(UPLOAD_SEGMENT_JOB, move |job, progress| {
+                Box::pin(upload_segment_job(
+                    upload_db.clone(),
+                    upload_root.clone(),
+                    job,
+                    progress,
+                ))
+            })
+            .register(RENDER_TIMELAPSE_JOB, move |job, progress| {
+                Box::pin(render_timelapse_job(
+                    timelapse_db.clone(),
+                    timelapse_storage.clone(),
+                    job,
+                    progress,
+                ))
+            })
+            .register(EXCISE_SEGMENT_JOB, move |job, progress| {
+                Box::pin(excise_segment_job(excise_db.clone(), job, progress))
+            })
+            .register(OCR_CHUNK_JOB, move |job, progress| {
+                Box::pin(ocr_chunk_job(ocr_db.clone(), job, progress))
+            })
+    }
+
+    pub fn register<F>(mut self, kind: &str, handler: F) -> Self
+    where
+        F: Fn(Job, JobProgress) -> BoxFuture<'static, Result<()>> + Send + Sync + 'static,
+    {
+        self.handlers.insert(kind.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Requeues jobs interrupted by a previous shutdown and spawns the workers.
+    pub async fn start(self) -> Result<Vec<JoinHandle<()>>> {
+        let requeued = self.db.requeue_running_jobs().await?;
+        if requeued > 0 {
+            info!("requeued {} interrupted jobs", requeued);
+        }
+
+        let queue = Arc::new(self);
+        Ok((0..queue.config.workers)
+            .map(|worker| {
+                let queue = queue.clone();
+                tokio::spawn(async move { queue.run_worker(worker).await })
+            })
+            .collect())
+    }
+
+    async fn run_worker(&self, worker: usize) {
+        let kinds: Vec<String> = self.handlers.keys().cloned().collect();
+        info!("job worker {} started for {:?}", worker, kinds);
+
+        loop {
+            if !system_can_run_jobs(self.config.max_cpu_usage).await {
+                tokio::time::sleep(self.config.poll_interval).await;
+                continue;
+            }
+
+            let job = match self.db.claim_next_job(&kinds, Utc::now()).await {
+                Ok(Some(job)) => job,
+                Ok(None) => {
+                    tokio::time::sleep(self.config.poll_interval).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!("job worker {} failed to claim a job: {}", worker, e);
+                    tokio::time::sleep(self.config.poll_interval).await;
+                    continue;
+                }
+            };
+            self.run_job(worker, job).await;
+        }
+    }
+
+    async fn run_job(&self, worker: usize, job: Job) {
+        let Some(handler) = self.handlers.get(&job.kind) else {
+            return;
+        };
+        info!(
+            "job worker {} running {} job {} (attempt {}/{})",
+            worker, job.kind, job.id, job.attempts, job.max_attempts
+        );
+
+        let progress = JobProgress {
+            db: self.db.clone(),
+            job_id: job.id,
+            kind: job.kind.clone(),
+        };
+        let (id, kind) = (job.id, job.kind.clone());
+        let result = handler(job, progress).await;
+
+        let update = match result {
+            Ok(()) => {
+                info!("{} job {} done", kind, id);
+                self.db.complete_job(id).await
+            }
+            Err(e) => {
+                warn!("{} job {} failed: {}", kind, id, e);
+                let retry_delay = chrono::Duration::from_std(self.config.retry_delay)
+                    .unwrap_or_else(|_| chrono::Duration::seconds(60));
+                self.db.fail_job(id, &e.to_string(), retry_delay).await
+            }
+        };
+        match update {
+            Ok(job) => {
+                let _ = send_event("job_finished", job);
+            }
+            Err(e) => error!("failed to record outcome of job {}: {}", id, e),
+        }
+    }
+}
+
+/// Adds a job to the queue; the payload is stored as JSON.
+pub async fn enqueue_job(
+    db: &DatabaseManager,
+    kind: &str,
+    payload: &impl Serialize,
+    max_attempts: i64,
+) -> Result<Job> {
+    let payload = serde_json::to_string(payload)?;
+    Ok(db.enqueue_job(kind, &payload, max_attempts).await?)
+}
+
+/// Heavy work runs on AC power, or on battery while the machine is otherwise idle, and never
+/// while capture itself is paused to save the battery.
+async fn system_can_run_jobs(max_cpu_usage: f32) -> bool {
+    if current_throttle() == ThrottleMode::Paused {
+        return false;
+    }
+    tokio::task::spawn_blocking(move || {
+        if !read_power_status().on_battery {
+            return true;
+        }
+        let usage = global_cpu_usage();
+        debug!("on battery, cpu usage {:.1}%", usage);
+        usage < max_cpu_usage
+    })
+    .await
+    .unwrap_or(false)
+}
+
+fn global_cpu_usage() -> f32 {
+    let mut sys = System::new();
+    sys.refresh_cpu();
+    std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_cpu();
+    sys.global_cpu_info().cpu_usage()
+}
+
+/// Payload of jobs working on a single video chunk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkJobPayload {
+    pub video_path: String,
+}
+
+/// Runs ffmpeg on `input` with `args`, reporting progress through the encoded duration.
+pub async fn run_ffmpeg_with_progress(
+    input: &str,
+    args: &[&str],
+    progress: &JobProgress,
+) -> Result<()> {
+    run_ffmpeg_on(input, &[], args, progress).await
+}
+
+/// [`run_ffmpeg_with_progress`] with `input_args`, options of how `input` is read.
+pub async fn run_ffmpeg_on(
+    input: &str,
+    input_args: &[&str],
+    args: &[&str],
+    progress: &JobProgress,
+) -> Result<()> {
+    let duration = crate::video_utils::get_video_metadata(input)
+        .await
+        .map(|metadata| metadata.duration)
+        .unwrap_or(0.0);
+
+    let mut child = Command::new(find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?)
+        .arg("-y")
+        .args(input_args)
+        .args(["-i", input])
+        .args(args)
+        .args(["-progress", "pipe:1", "-nostats"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Some(out_time_us) = line.strip_prefix("out_time_us=") else {
+                continue;
+            };
+            if let Ok(out_time_us) = out_time_us.parse::<f64>() {
+                if duration > 0.0 {
+                    progress.report(out_time_us / 1_000_000.0 / duration).await;
+                }
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(anyhow!("ffmpeg exited with {}", status));
+    }
+    Ok(())
+}
+
+async fn reencode_chunk(job: Job, progress: JobProgress) -> Result<()> {
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    let input = Path::new(&payload.video_path);
+    if !input.exists() {
+        return Err(anyhow!("video chunk {} no longer exists", input.display()));
+    }
+    let output = input.with_extension("reencode.mp4");
+    let output_str = output.to_string_lossy();
+
+    if let Err(e) = run_ffmpeg_with_progress(
+        &payload.video_path,
+        &[
+            "-fps_mode",
+            "passthrough",
+            "-vcodec",
+            "libx265",
+            "-tag:v",
+            "hvc1",
+            "-preset",
+            "medium",
+            "-crf",
+            "23",
+            "-pix_fmt",
+            "yuv420p",
+            &*output_str,
+        ],
+        &progress,
+    )
+    .await
+    {
+        let _ = tokio::fs::remove_file(&output).await;
+        return Err(e);
+    }
+    // replace the chunk in place so frames keep pointing at the same file
+    tokio::fs::rename(&output, input).await?;
+    Ok(())
+}
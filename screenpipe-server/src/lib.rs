@@ -1,13 +1,67 @@
+pub mod accessibility;
+pub mod activity_stats;
 mod add;
+pub mod animated_clip;
+pub mod app_quality;
+pub mod audit;
 mod auto_destruct;
+pub mod backfill;
+pub mod backpressure;
+pub mod bench;
+pub mod categorization;
+pub mod chunk_rotation;
 pub mod chunking;
 pub mod cli;
+pub mod clipboard;
+pub mod content_quality;
 pub mod core;
+pub mod dedup;
+pub mod deferred_ocr;
+pub mod encoder_health;
+pub mod evidence;
+pub mod excise;
+pub mod excluded_regions;
 pub mod filtering;
+pub mod frames;
+pub mod grpc;
+pub mod hot_reload;
+pub mod hub;
+pub mod import;
+pub mod jobs;
+pub mod live_events;
+pub mod meeting_mode;
+pub mod metrics;
+pub mod mosaic;
+pub mod ocr_language;
+pub mod permissions;
 pub mod pipe_manager;
+pub mod plugins;
+pub mod power;
+pub mod pre_roll;
+pub mod presentation;
+pub mod pyramid;
+pub mod recording_format;
+pub mod resource_guard;
 mod resource_monitor;
+pub mod scheduled_export;
+pub mod schema;
+pub mod search_query;
+pub mod secrets;
+pub mod sensitive_content;
 mod server;
+pub mod service;
+pub mod sessions;
+pub mod storage;
+pub mod streaming;
+pub mod subtitles;
+pub mod summarize;
+pub mod tail;
 pub mod text_embeds;
+pub mod thumbnails;
+pub mod time_tracking;
+pub mod timelapse;
+pub mod ui_events;
+pub mod upload;
 mod video;
 pub mod video_cache;
 pub mod video_utils;
@@ -26,5 +80,6 @@ pub use server::HealthCheckResponse;
 pub use server::PaginatedResponse;
 pub use server::SCServer;
 pub use server::{api_list_monitors, MonitorInfo};
+pub use storage::Storage;
 pub use video::VideoCapture;
 pub mod embedding;
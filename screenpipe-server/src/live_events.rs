@@ -0,0 +1,115 @@
+//! The recording events streamed at `/ws/live`, for dashboards and scripts to react to what is
+//! recorded as it happens. Each message is an [`Event`] as JSON, `{"name": ..., "data": ...}`.
+
+use chrono::{DateTime, Utc};
+use futures::{future, Stream, StreamExt};
+use screenpipe_core::capture_policy::CapturePolicy;
+use screenpipe_events::{subscribe_to_all_events, Event};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Events of the recording, the ones streamed unless the client picks some of them:
+/// [`crate::core::FrameWritten`], [`crate::plugins::ActivityChange`], [`AppBlocked`] and
+/// [`SegmentRotated`].
+pub const LIVE_EVENTS: &[&str] = &[
+    "frame_written",
+    "activity_changed",
+    "app_blocked",
+    "segment_rotated",
+];
+
+/// Sent as `app_blocked` when the focused window of a monitor turns to one the capture policy
+/// ignores, nothing of it being recorded until the focus moves on. Only a marker: which
+/// window it is isn't told, the user chose not to record it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppBlocked {
+    pub monitor_id: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Sent as `segment_rotated` when the recording of a monitor goes on in a new video chunk,
+/// the previous one, of the previous `segment_rotated`, being final.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentRotated {
+    pub monitor_id: u32,
+    pub device_name: String,
+    pub video_path: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The events a client of `/ws/live` gets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveEventFilter {
+    events: Vec<String>,
+    monitor_id: Option<u32>,
+}
+
+impl LiveEventFilter {
+    /// `events` is a comma separated list of [`LIVE_EVENTS`], all of them when `None` or
+    /// empty. Only the events of `monitor_id` are kept when given.
+    pub fn new(events: Option<&str>, monitor_id: Option<u32>) -> Result<Self, String> {
+        let events = events
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                if LIVE_EVENTS.contains(&name) {
+                    Ok(name.to_string())
+                } else {
+                    Err(format!(
+                        "unknown event '{}', expected one of {}",
+                        name,
+                        LIVE_EVENTS.join(", ")
+                    ))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { events, monitor_id })
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let wanted = if self.events.is_empty() {
+            LIVE_EVENTS.contains(&event.name.as_str())
+        } else {
+            self.events.contains(&event.name)
+        };
+        wanted
+            && self.monitor_id.map_or(true, |id| {
+                event.data["monitor_id"].as_u64() == Some(u64::from(id))
+            })
+    }
+}
+
+/// The events sent from now on that `filter` matches.
+pub fn subscribe_to_live_events(filter: LiveEventFilter) -> impl Stream<Item = Event<Value>> {
+    subscribe_to_all_events().filter(move |event| future::ready(filter.matches(event)))
+}
+
+/// Follows the focused window of a monitor, frame after frame, to tell when a blocked one
+/// on it gets the focus.
+#[derive(Debug, Default)]
+pub struct BlockedWindowTracker {
+    blocked: Option<(String, String)>,
+}
+
+impl BlockedWindowTracker {
+    /// The [`AppBlocked`] to send when `focused`, the app and title of the focused window if
+    /// it's on the monitor, is blocked by `policy` and wasn't already at the previous frame.
+    pub fn observe(
+        &mut self,
+        monitor_id: u32,
+        focused: Option<(String, String)>,
+        policy: &CapturePolicy,
+    ) -> Option<AppBlocked> {
+        let blocked = focused.filter(|(app_name, title)| !policy.allows_window(app_name, title));
+        if blocked == self.blocked {
+            return None;
+        }
+        self.blocked = blocked;
+        self.blocked.as_ref().map(|_| AppBlocked {
+            monitor_id,
+            timestamp: Utc::now(),
+        })
+    }
+}
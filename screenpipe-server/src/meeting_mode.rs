@@ -0,0 +1,269 @@
+use crate::hot_reload::boost_capture_fps;
+use crate::sessions::SessionTracker;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_audio::audio_manager::{AudioManager, AudioManagerStatus};
+use screenpipe_events::{send_event, subscribe_to_all_events};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tracing::{error, info, warn};
+
+/// Tag of the sessions recorded for a meeting.
+pub const MEETING_SESSION_TAG: &str = "meeting";
+
+/// How long a meeting goes on once its window is no longer captured, for a window hidden for a
+/// moment not to end it.
+pub const MEETING_WINDOW_GRACE: Duration = Duration::from_secs(30);
+
+/// Windows of a meeting in progress, as the app and a part of the title, lowercase.
+const MEETING_WINDOWS: &[(&str, &str)] = &[
+    ("zoom", "zoom meeting"),
+    ("zoom", "zoom webinar"),
+    ("teams", "meeting"),
+    ("teams", "call"),
+    ("webex", "meeting"),
+    ("slack", "huddle"),
+];
+
+/// A Google Meet call in a browser, titled after the code of the meeting.
+static MEET_TITLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\bmeet - [a-z]{3}-[a-z]{4}-[a-z]{3}\b").unwrap());
+
+/// Whether a window of `app_name` titled `window_name` shows a meeting in progress.
+pub fn is_meeting_window(app_name: &str, window_name: &str) -> bool {
+    let app_name = app_name.to_lowercase();
+    let window_name = window_name.to_lowercase();
+    MEETING_WINDOWS
+        .iter()
+        .any(|(app, title)| app_name.contains(app) && window_name.contains(title))
+        || MEET_TITLE.is_match(&window_name)
+}
+
+/// The app of the meeting window captured on each monitor.
+static MEETING_WINDOWS_SHOWN: Lazy<watch::Sender<HashMap<u32, String>>> =
+    Lazy::new(|| watch::channel(HashMap::new()).0);
+
+/// Reports the windows, as app and title, of a frame captured on `monitor_id`, for the
+/// meetings held in them to be followed.
+pub fn report_captured_windows<'a>(
+    monitor_id: u32,
+    windows: impl IntoIterator<Item = (&'a str, &'a str)>,
+) {
+    let meeting = windows
+        .into_iter()
+        .find(|(app_name, window_name)| is_meeting_window(app_name, window_name))
+        .map(|(app_name, _)| app_name.to_string());
+    MEETING_WINDOWS_SHOWN.send_if_modified(|shown| match meeting {
+        Some(app) => shown.insert(monitor_id, app.clone()) != Some(app),
+        None => shown.remove(&monitor_id).is_some(),
+    });
+}
+
+/// What changed of the meeting windows captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MeetingWindowChange {
+    /// A meeting window of this app is captured.
+    Started(String),
+    /// The meeting window is gone since [`MEETING_WINDOW_GRACE`].
+    Ended,
+}
+
+/// Tells when meetings start and end from the meeting windows captured on the monitors.
+#[derive(Debug, Default)]
+pub struct MeetingWindowTracker {
+    /// The app of the meeting in progress, and since when its window is gone.
+    meeting: Option<(String, Option<DateTime<Utc>>)>,
+}
+
+impl MeetingWindowTracker {
+    /// What changed at `now`, `shown` holding the app of the meeting window of each monitor.
+    pub fn observe(
+        &mut self,
+        shown: &HashMap<u32, String>,
+        now: DateTime<Utc>,
+    ) -> Option<MeetingWindowChange> {
+        let Some(app) = shown.values().min() else {
+            let (_, gone_since) = self.meeting.as_mut()?;
+            let gone_since = *gone_since.get_or_insert(now);
+            if (now - gone_since).to_std().unwrap_or_default() < MEETING_WINDOW_GRACE {
+                return None;
+            }
+            self.meeting = None;
+            return Some(MeetingWindowChange::Ended);
+        };
+        match &mut self.meeting {
+            Some((_, gone_since)) => {
+                *gone_since = None;
+                None
+            }
+            None => {
+                self.meeting = Some((app.clone(), None));
+                Some(MeetingWindowChange::Started(app.clone()))
+            }
+        }
+    }
+}
+
+/// How recording changes while a meeting app (Zoom, Meet, Teams...) holds a meeting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeetingProfile {
+    /// Frames per second captured at least, shared screens and slides change fast.
+    pub fps: Option<f64>,
+    /// Whether audio is recorded, and so transcribed, even when it was stopped.
+    pub audio: bool,
+}
+
+struct ActiveMeeting {
+    app: String,
+    session_id: Option<i64>,
+    /// Whether the meeting started the audio, which is stopped again when it ends.
+    started_audio: bool,
+}
+
+/// Switches recording to the meeting profile while a meeting is detected and back once it
+/// ends, recording the meeting as a session tagged [`MEETING_SESSION_TAG`].
+pub struct MeetingMode {
+    profile: MeetingProfile,
+    audio: Option<Arc<AudioManager>>,
+    sessions: Option<Arc<SessionTracker>>,
+    active: Mutex<Option<ActiveMeeting>>,
+}
+
+impl MeetingMode {
+    pub fn new(
+        profile: MeetingProfile,
+        audio: Option<Arc<AudioManager>>,
+        sessions: Option<Arc<SessionTracker>>,
+    ) -> Arc<Self> {
+        Arc::new(MeetingMode {
+            profile,
+            audio,
+            sessions,
+            active: Mutex::new(None),
+        })
+    }
+
+    /// Applies the profile for a meeting held in `app` from `now` on. A meeting detected while
+    /// another is in progress is part of it.
+    pub async fn meeting_started(&self, app: &str, now: DateTime<Utc>) {
+        let mut active = self.active.lock().await;
+        if active.is_some() {
+            return;
+        }
+
+        boost_capture_fps(self.profile.fps);
+        let mut started_audio = false;
+        if let Some(audio) = self.audio.as_ref().filter(|_| self.profile.audio) {
+            if audio.status().await != AudioManagerStatus::Running {
+                match audio.start().await {
+                    Ok(()) => started_audio = true,
+                    Err(e) => warn!("failed to start audio for the meeting: {}", e),
+                }
+            }
+        }
+        let session_id = match &self.sessions {
+            Some(sessions) => match sessions
+                .start_tagged_session(&format!("meeting in {}", app), MEETING_SESSION_TAG, now)
+                .await
+            {
+                Ok(session) => Some(session.id),
+                Err(e) => {
+                    error!("failed to start the meeting session: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        info!(
+            "meeting in {} started, recording with the meeting profile",
+            app
+        );
+        let _ = send_event(
+            "meeting_profile_started",
+            serde_json::json!({ "app": app, "session_id": session_id }),
+        );
+        *active = Some(ActiveMeeting {
+            app: app.to_string(),
+            session_id,
+            started_audio,
+        });
+    }
+
+    /// Reverts what [`Self::meeting_started`] changed.
+    pub async fn meeting_ended(&self) {
+        let Some(meeting) = self.active.lock().await.take() else {
+            return;
+        };
+
+        boost_capture_fps(None);
+        if meeting.started_audio {
+            if let Some(audio) = &self.audio {
+                if let Err(e) = audio.stop().await {
+                    warn!("failed to stop the audio started for the meeting: {}", e);
+                }
+            }
+        }
+        if let (Some(sessions), Some(id)) = (&self.sessions, meeting.session_id) {
+            sessions.end_session(id, "meeting_ended").await;
+        }
+
+        info!(
+            "meeting in {} ended, back to the recording settings",
+            meeting.app
+        );
+        let _ = send_event(
+            "meeting_profile_ended",
+            serde_json::json!({ "app": meeting.app, "session_id": meeting.session_id }),
+        );
+    }
+
+    /// Follows the meetings the events report, and the ones held in the windows captured,
+    /// calls in a browser included, which end once their window is gone.
+    pub async fn follow_meetings(self: Arc<Self>) {
+        let mut events = subscribe_to_all_events();
+        let mut shown = MEETING_WINDOWS_SHOWN.subscribe();
+        let mut windows = MeetingWindowTracker::default();
+        // ends the meeting when the screen stopped changing after its window was closed
+        let mut tick = tokio::time::interval(MEETING_WINDOW_GRACE / 3);
+        loop {
+            tokio::select! {
+                event = events.next() => {
+                    let Some(event) = event else {
+                        break;
+                    };
+                    match event.name.as_str() {
+                        "meeting_started" => {
+                            self.meeting_started(
+                                event.data["app"].as_str().unwrap_or_default(),
+                                Utc::now(),
+                            )
+                            .await
+                        }
+                        "meeting_ended" => self.meeting_ended().await,
+                        _ => {}
+                    }
+                    continue;
+                }
+                changed = shown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                _ = tick.tick() => {}
+            }
+            let change = windows.observe(&shown.borrow_and_update(), Utc::now());
+            match change {
+                Some(MeetingWindowChange::Started(app)) => {
+                    self.meeting_started(&app, Utc::now()).await
+                }
+                Some(MeetingWindowChange::Ended) => self.meeting_ended().await,
+                None => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use once_cell::sync::Lazy;
+use screenpipe_vision::capture_stats::{capture_stats, CaptureStats};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Content type of the Prometheus text exposition format served at `/metrics`.
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// What a monitor's recorder did with the frames it captured since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecorderStats {
+    /// Frames written to the ffmpeg encoder of a chunk.
+    pub frames_written: u64,
+    /// Chunks restarted because their encoder failed to start, to take a frame or reported
+    /// errors.
+    pub ffmpeg_restarts: u64,
+    /// Frames waiting to be encoded, as of the last frame queued.
+    pub video_queue_depth: usize,
+    /// Frames waiting to be indexed, as of the last frame queued.
+    pub ocr_queue_depth: usize,
+    /// Queued frames dropped to make room in a full queue, see
+    /// [`BackpressurePolicy::DropOldest`](crate::backpressure::BackpressurePolicy::DropOldest).
+    pub video_frames_dropped: u64,
+    pub ocr_frames_dropped: u64,
+}
+
+static RECORDER_STATS: Lazy<Mutex<HashMap<u32, RecorderStats>>> = Lazy::new(Default::default);
+
+fn update(monitor_id: u32, f: impl FnOnce(&mut RecorderStats)) {
+    f(RECORDER_STATS
+        .lock()
+        .unwrap()
+        .entry(monitor_id)
+        .or_default());
+}
+
+pub fn record_frame_written(monitor_id: u32) {
+    update(monitor_id, |stats| stats.frames_written += 1);
+}
+
+pub fn record_ffmpeg_restart(monitor_id: u32) {
+    update(monitor_id, |stats| stats.ffmpeg_restarts += 1);
+}
+
+pub fn record_queue_depth(monitor_id: u32, video: usize, ocr: usize) {
+    update(monitor_id, |stats| {
+        stats.video_queue_depth = video;
+        stats.ocr_queue_depth = ocr;
+    });
+}
+
+pub fn record_frames_dropped(monitor_id: u32, video: u64, ocr: u64) {
+    update(monitor_id, |stats| {
+        stats.video_frames_dropped += video;
+        stats.ocr_frames_dropped += ocr;
+    });
+}
+
+/// The stats of every monitor recorded so far, by monitor id.
+pub fn recorder_stats() -> HashMap<u32, RecorderStats> {
+    RECORDER_STATS.lock().unwrap().clone()
+}
+
+/// The recording health of every monitor in the Prometheus text format.
+pub fn prometheus_metrics() -> String {
+    render_metrics(&capture_stats(), &recorder_stats())
+}
+
+/// Renders the stats in the Prometheus text format, one series per monitor, labeled with
+/// its id.
+pub fn render_metrics(
+    capture: &HashMap<u32, CaptureStats>,
+    recorder: &HashMap<u32, RecorderStats>,
+) -> String {
+    let monitors: BTreeSet<u32> = capture.keys().chain(recorder.keys()).copied().collect();
+    let capture_of = |id: u32| capture.get(&id).copied().unwrap_or_default();
+    let recorder_of = |id: u32| recorder.get(&id).copied().unwrap_or_default();
+    let mut out = String::new();
+
+    let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (series, value) in samples {
+            let _ = writeln!(out, "{} {}", series, value);
+        }
+    };
+
+    metric(
+        "screenpipe_frames_captured_total",
+        "counter",
+        "Frames captured from the monitor.",
+        per_monitor(&monitors, "screenpipe_frames_captured_total", |id| {
+            capture_of(id).frames_captured.to_string()
+        }),
+    );
+    metric(
+        "screenpipe_frames_skipped_total",
+        "counter",
+        "Frames dropped because they barely differed from the previous one.",
+        per_monitor(&monitors, "screenpipe_frames_skipped_total", |id| {
+            capture_of(id).frames_skipped.to_string()
+        }),
+    );
+    metric(
+        "screenpipe_frames_written_total",
+        "counter",
+        "Frames written to the video encoder.",
+        per_monitor(&monitors, "screenpipe_frames_written_total", |id| {
+            recorder_of(id).frames_written.to_string()
+        }),
+    );
+    let mut latency = per_monitor(&monitors, "screenpipe_capture_duration_seconds_sum", |id| {
+        capture_of(id).capture_seconds.to_string()
+    });
+    latency.extend(per_monitor(
+        &monitors,
+        "screenpipe_capture_duration_seconds_count",
+        |id| capture_of(id).frames_captured.to_string(),
+    ));
+    metric(
+        "screenpipe_capture_duration_seconds",
+        "summary",
+        "Time taken to capture a frame.",
+        latency,
+    );
+    metric(
+        "screenpipe_ffmpeg_restarts_total",
+        "counter",
+        "Video chunks restarted because their encoder failed.",
+        per_monitor(&monitors, "screenpipe_ffmpeg_restarts_total", |id| {
+            recorder_of(id).ffmpeg_restarts.to_string()
+        }),
+    );
+    metric(
+        "screenpipe_queue_depth",
+        "gauge",
+        "Frames waiting in the recorder's queues.",
+        monitors
+            .iter()
+            .flat_map(|&id| {
+                let stats = recorder_of(id);
+                [
+                    ("video", stats.video_queue_depth),
+                    ("ocr", stats.ocr_queue_depth),
+                ]
+                .map(|(queue, depth)| {
+                    (
+                        format!(
+                            "screenpipe_queue_depth{{monitor=\"{}\",queue=\"{}\"}}",
+                            id, queue
+                        ),
+                        depth.to_string(),
+                    )
+                })
+            })
+            .collect(),
+    );
+    metric(
+        "screenpipe_queue_dropped_frames_total",
+        "counter",
+        "Queued frames dropped to make room in a full queue.",
+        monitors
+            .iter()
+            .flat_map(|&id| {
+                let stats = recorder_of(id);
+                [
+                    ("video", stats.video_frames_dropped),
+                    ("ocr", stats.ocr_frames_dropped),
+                ]
+                .map(|(queue, dropped)| {
+                    (
+                        format!(
+                            "screenpipe_queue_dropped_frames_total{{monitor=\"{}\",queue=\"{}\"}}",
+                            id, queue
+                        ),
+                        dropped.to_string(),
+                    )
+                })
+            })
+            .collect(),
+    );
+    out
+}
+
+fn per_monitor(
+    monitors: &BTreeSet<u32>,
+    name: &str,
+    value: impl Fn(u32) -> String,
+) -> Vec<(String, String)> {
+    monitors
+        .iter()
+        .map(|&id| (format!("{}{{monitor=\"{}\"}}", name, id), value(id)))
+        .collect()
+}
@@ -0,0 +1,159 @@
+use crate::jobs::{enqueue_job, JobProgress};
+use crate::pyramid::pyramid_keyframe_path;
+use crate::storage::Storage;
+use crate::video_utils::extract_frame_from_video;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Timelike, Utc};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use screenpipe_db::{DatabaseManager, Job};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Renders the day strip of a local date.
+pub const RENDER_DAY_MOSAIC_JOB: &str = "render_day_mosaic";
+
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+/// Width of the slice drawn for each minute.
+pub const SLICE_WIDTH: u32 = 2;
+pub const STRIP_HEIGHT: u32 = 48;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayMosaicPayload {
+    pub date: NaiveDate,
+}
+
+/// Where the strip of `date` is stored: `<root>/<hostname>/mosaics/<date>.jpg`.
+pub fn mosaic_path(storage: &Storage, date: NaiveDate) -> PathBuf {
+    storage
+        .host_dir()
+        .join("mosaics")
+        .join(format!("{}.jpg", date.format("%Y-%m-%d")))
+}
+
+/// UTC bounds of a local calendar day.
+pub fn local_day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let bound = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .unwrap_or_else(|| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    };
+    (bound(date), bound(date.succ_opt().unwrap_or(date)))
+}
+
+/// Minute of the local day a frame falls in, used as its horizontal position in the strip.
+pub fn minute_of_day(timestamp: DateTime<Utc>) -> u32 {
+    let local = timestamp.with_timezone(&Local);
+    local.hour() * 60 + local.minute()
+}
+
+/// Draws one squeezed slice of each frame at its minute. Minutes without frames stay dark.
+pub fn render_day_strip(slices: &[(u32, DynamicImage)]) -> RgbImage {
+    let mut strip = RgbImage::from_pixel(
+        MINUTES_PER_DAY * SLICE_WIDTH,
+        STRIP_HEIGHT,
+        Rgb([16, 16, 16]),
+    );
+    for (minute, image) in slices {
+        if *minute >= MINUTES_PER_DAY {
+            continue;
+        }
+        let slice = image
+            .resize_exact(SLICE_WIDTH, STRIP_HEIGHT, FilterType::Triangle)
+            .to_rgb8();
+        image::imageops::replace(&mut strip, &slice, (*minute * SLICE_WIDTH) as i64, 0);
+    }
+    strip
+}
+
+async fn load_frame(file_path: &str, offset_index: i64) -> Result<DynamicImage> {
+    // the smallest pyramid level is plenty for a 2px slice and avoids decoding the video, its
+    // closest keyframe stands for the frame
+    if let Some(pyramid_path) = pyramid_keyframe_path(file_path, 2, offset_index) {
+        return Ok(image::open(pyramid_path)?);
+    }
+    let frame_path = extract_frame_from_video(file_path, offset_index).await?;
+    let image = image::open(&frame_path);
+    let _ = tokio::fs::remove_file(&frame_path).await;
+    Ok(image?)
+}
+
+/// Renders and stores the strip of `date`, returning its path.
+pub async fn render_day_mosaic(
+    db: &DatabaseManager,
+    storage: &Storage,
+    date: NaiveDate,
+    progress: Option<&JobProgress>,
+) -> Result<PathBuf> {
+    let (start, end) = local_day_bounds(date);
+    let frames = db.get_first_frame_per_minute(start, end).await?;
+    if frames.is_empty() {
+        return Err(anyhow!("no frames recorded on {}", date));
+    }
+
+    let mut slices = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        match load_frame(&frame.file_path, frame.offset_index).await {
+            Ok(image) => slices.push((minute_of_day(frame.timestamp), image)),
+            Err(e) => debug!("skipping frame {} in day mosaic: {}", frame.frame_id, e),
+        }
+        if let Some(progress) = progress {
+            if i % 60 == 0 {
+                progress.report(i as f64 / frames.len() as f64).await;
+            }
+        }
+    }
+
+    let path = mosaic_path(storage, date);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let strip = render_day_strip(&slices);
+    let output = path.clone();
+    tokio::task::spawn_blocking(move || strip.save_with_format(output, ImageFormat::Jpeg))
+        .await??;
+    info!(
+        "rendered day mosaic for {} from {} frames",
+        date,
+        slices.len()
+    );
+    Ok(path)
+}
+
+pub(crate) async fn render_day_mosaic_job(
+    db: Arc<DatabaseManager>,
+    storage: Storage,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: DayMosaicPayload = serde_json::from_str(&job.payload)?;
+    render_day_mosaic(&db, &storage, payload.date, Some(&progress)).await?;
+    Ok(())
+}
+
+/// Queues the strip of the previous day shortly after every local midnight.
+pub async fn schedule_nightly_mosaics(db: Arc<DatabaseManager>) {
+    loop {
+        let now = Local::now();
+        let tomorrow = now.date_naive().succ_opt().unwrap_or(now.date_naive());
+        let (next_midnight, _) = local_day_bounds(tomorrow);
+        // leave time for the last chunk of the day to be finalized
+        let wait = (next_midnight - now.with_timezone(&Utc))
+            .to_std()
+            .unwrap_or_default()
+            + Duration::from_secs(5 * 60);
+        tokio::time::sleep(wait).await;
+
+        let payload = DayMosaicPayload {
+            date: now.date_naive(),
+        };
+        if let Err(e) = enqueue_job(&db, RENDER_DAY_MOSAIC_JOB, &payload, 3).await {
+            warn!("failed to queue day mosaic for {}: {}", payload.date, e);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::jobs::{enqueue_job, JobProgress};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use screenpipe_core::{detect_language_among, Language};
+use screenpipe_db::{DatabaseManager, Job, UNDETERMINED_LANGUAGE};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+/// Detects the language of OCR text recorded before detection happened at capture time.
+pub const DETECT_OCR_LANGUAGE_JOB: &str = "detect_ocr_language";
+
+const BACKFILL_BATCH_SIZE: u32 = 500;
+
+/// Key of the language of a text block in the OCR text json.
+pub const BLOCK_LANGUAGE_KEY: &str = "language";
+
+static OCR_LANGUAGES: Lazy<RwLock<Vec<Language>>> = Lazy::new(Default::default);
+
+/// The languages OCR is configured for, which detection picks the language of OCR text from.
+/// Detection picks from every language it knows when none is set.
+pub fn set_ocr_languages(languages: Vec<Language>) {
+    *OCR_LANGUAGES.write().unwrap() = languages;
+}
+
+pub fn ocr_languages() -> Vec<Language> {
+    OCR_LANGUAGES.read().unwrap().clone()
+}
+
+/// ISO 639-1 code stored for `text`, `und` when the language can't be told.
+pub fn ocr_language_code(text: &str) -> &'static str {
+    detect_language_among(text, &OCR_LANGUAGES.read().unwrap())
+        .map(|language| language.as_lang_code())
+        .unwrap_or(UNDETERMINED_LANGUAGE)
+}
+
+/// Tags every block of the OCR text json `blocks` with the language of its text, returning
+/// how many blocks are in each language as `(language, blocks)`, most blocks first. Blocks
+/// whose language can't be told are tagged `und` but not counted.
+pub fn tag_block_languages(blocks: &mut [HashMap<String, String>]) -> Vec<(String, u32)> {
+    let mut counts = BTreeMap::<&'static str, u32>::new();
+    for block in blocks.iter_mut() {
+        let language = block
+            .get("text")
+            .map_or(UNDETERMINED_LANGUAGE, |text| ocr_language_code(text));
+        block.insert(BLOCK_LANGUAGE_KEY.to_string(), language.to_string());
+        if language != UNDETERMINED_LANGUAGE {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<(String, u32)> = counts
+        .into_iter()
+        .map(|(language, blocks)| (language.to_string(), blocks))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    counts
+}
+
+/// Detects and stores the language of all OCR text that doesn't have one yet, returning how
+/// many rows were tagged.
+pub async fn backfill_ocr_languages(
+    db: &DatabaseManager,
+    progress: Option<&JobProgress>,
+) -> Result<u64> {
+    let total = db.count_ocr_text_without_language().await?.max(1) as f64;
+    let mut tagged = 0u64;
+
+    loop {
+        let rows = db
+            .get_ocr_text_without_language(BACKFILL_BATCH_SIZE)
+            .await?;
+        if rows.is_empty() {
+            break;
+        }
+        let languages: Vec<(i64, String)> = rows
+            .into_iter()
+            .map(|(rowid, text)| (rowid, ocr_language_code(&text).to_string()))
+            .collect();
+        db.update_ocr_text_languages(&languages).await?;
+
+        tagged += languages.len() as u64;
+        if let Some(progress) = progress {
+            progress.report((tagged as f64 / total).min(1.0)).await;
+        }
+    }
+
+    Ok(tagged)
+}
+
+pub(crate) async fn detect_ocr_language_job(
+    db: Arc<DatabaseManager>,
+    _job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let tagged = backfill_ocr_languages(&db, Some(&progress)).await?;
+    info!("detected the language of {} ocr texts", tagged);
+    Ok(())
+}
+
+/// Queues the backfill when OCR text without a language is left, e.g. after upgrading.
+pub async fn queue_ocr_language_backfill(db: &DatabaseManager) -> Result<()> {
+    if db.count_ocr_text_without_language().await? == 0
+        || db.count_unfinished_jobs(DETECT_OCR_LANGUAGE_JOB).await? > 0
+    {
+        return Ok(());
+    }
+    enqueue_job(db, DETECT_OCR_LANGUAGE_JOB, &serde_json::json!({}), 3).await?;
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use oasgen::OaSchema;
+use screenpipe_events::send_event;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use tracing::{info, warn};
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+}
+
+/// How often a missing permission is checked again while the capture waits for it.
+pub const PERMISSION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What the OS has to allow for screenpipe to record.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Capturing the screens, without it macOS hands out black frames.
+    ScreenRecording,
+    /// Reading the UI of other apps, for UI monitoring and the accessibility text.
+    Accessibility,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 2] = [Capability::ScreenRecording, Capability::Accessibility];
+
+    /// Url opening the settings pane where the capability is granted, if the OS has one.
+    pub fn settings_url(&self) -> Option<&'static str> {
+        if !cfg!(target_os = "macos") {
+            return None;
+        }
+        Some(match self {
+            Capability::ScreenRecording => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"
+            }
+            Capability::Accessibility => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"
+            }
+        })
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::ScreenRecording => "screen recording",
+            Capability::Accessibility => "accessibility",
+        })
+    }
+}
+
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStatus {
+    Granted,
+    /// Not granted, or granted but screenpipe wasn't restarted since, as macOS requires.
+    Denied,
+    /// The OS doesn't ask for it.
+    NotNeeded,
+}
+
+impl PermissionStatus {
+    pub fn permitted(&self) -> bool {
+        matches!(
+            self,
+            PermissionStatus::Granted | PermissionStatus::NotNeeded
+        )
+    }
+
+    #[cfg(target_os = "macos")]
+    fn granted_if(granted: bool) -> Self {
+        if granted {
+            PermissionStatus::Granted
+        } else {
+            PermissionStatus::Denied
+        }
+    }
+}
+
+/// Status of a capability, as reported by `/health`.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PermissionCheck {
+    pub capability: Capability,
+    pub status: PermissionStatus,
+    /// Settings pane to send the user to, set while the capability is missing.
+    pub settings_url: Option<String>,
+}
+
+impl PermissionCheck {
+    pub fn new(capability: Capability, status: PermissionStatus) -> Self {
+        PermissionCheck {
+            capability,
+            status,
+            settings_url: capability
+                .settings_url()
+                .filter(|_| !status.permitted())
+                .map(str::to_string),
+        }
+    }
+}
+
+/// Asks the OS whether `capability` is granted, without prompting the user.
+pub fn check_permission(capability: Capability) -> PermissionStatus {
+    match capability {
+        Capability::ScreenRecording => screen_recording_status(),
+        Capability::Accessibility => accessibility_status(),
+    }
+}
+
+/// Checks every capability.
+pub fn check_permissions() -> Vec<PermissionCheck> {
+    Capability::ALL
+        .iter()
+        .map(|capability| PermissionCheck::new(*capability, check_permission(*capability)))
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn screen_recording_status() -> PermissionStatus {
+    PermissionStatus::granted_if(unsafe { CGPreflightScreenCaptureAccess() })
+}
+
+/// Windows lets a process capture the desktop its session shows, which a service or a process
+/// of a disconnected session doesn't have, and neither the secure desktop of UAC prompts.
+#[cfg(target_os = "windows")]
+fn screen_recording_status() -> PermissionStatus {
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_READOBJECTS,
+    };
+
+    match unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS) } {
+        Ok(desktop) => {
+            let _ = unsafe { CloseDesktop(desktop) };
+            PermissionStatus::Granted
+        }
+        Err(_) => PermissionStatus::Denied,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn screen_recording_status() -> PermissionStatus {
+    PermissionStatus::NotNeeded
+}
+
+#[cfg(target_os = "macos")]
+fn accessibility_status() -> PermissionStatus {
+    PermissionStatus::granted_if(unsafe { AXIsProcessTrusted() })
+}
+
+#[cfg(not(target_os = "macos"))]
+fn accessibility_status() -> PermissionStatus {
+    PermissionStatus::NotNeeded
+}
+
+/// Returns once `capability` is granted, checking again every `interval`, so that capturing
+/// without it doesn't record black frames. Sends a `permission_missing` event when it isn't.
+pub async fn wait_for_permission(capability: Capability, interval: Duration) {
+    let status = check_permission(capability);
+    if status.permitted() {
+        return;
+    }
+
+    let check = PermissionCheck::new(capability, status);
+    match &check.settings_url {
+        Some(url) => warn!(
+            "{} permission is missing, waiting for it to be granted in {}",
+            capability, url
+        ),
+        None => warn!(
+            "{} permission is missing, waiting for it to be granted",
+            capability
+        ),
+    }
+    let _ = send_event("permission_missing", check);
+
+    while !check_permission(capability).permitted() {
+        tokio::time::sleep(interval).await;
+    }
+    info!("{} permission granted", capability);
+}
@@ -0,0 +1,269 @@
+//! Third-party processing of what the recorder captures, without patching the recorder loop.
+//!
+//! Plugins implement [`FramePlugin`] and are either registered in-process by embedders of this
+//! crate, or loaded at startup from a dynamic library (`--plugin ./libredact.so`) or, with the
+//! `wasm-plugins` feature, a WASM module (`--plugin ./counter.wasm`).
+//!
+//! Loaded plugins exchange JSON with the host, one [`PluginEvent`] per call:
+//!
+//! - dynamic libraries export `screenpipe_plugin_abi_version() -> u32` returning
+//!   [`PLUGIN_ABI_VERSION`], `screenpipe_plugin_handle(ptr: *const u8, len: usize,
+//!   out_len: *mut usize) -> *mut u8` and `screenpipe_plugin_free(ptr: *mut u8, len: usize)` for
+//!   the buffers they return.
+//! - WASM modules export `memory`, `screenpipe_alloc(len: u32) -> u32`,
+//!   `screenpipe_handle(ptr: u32, len: u32) -> u64` returning `ptr << 32 | len` and
+//!   `screenpipe_free(ptr: u32, len: u32)`. They get no imports.
+//!
+//! A null pointer or zero length response leaves the frame as it is. Otherwise frames expect a
+//! [`FrameResponse`], other events' responses are ignored.
+
+mod native;
+#[cfg(feature = "wasm-plugins")]
+mod wasm;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Version of the JSON protocol spoken with loaded plugins.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// A window captured in a frame, before the frame is encoded and stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginFrame {
+    pub monitor_id: u32,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub focused: bool,
+    /// Stored as the window's OCR text, so plugins can redact it. Empty while OCR is skipped
+    /// or left to the job workers, plugins can read [`PluginFrame::image`] instead.
+    pub text: String,
+    /// The window as captured. Only in-process plugins get it, loaded plugins get the JSON of
+    /// the other fields.
+    #[serde(skip)]
+    pub image: Arc<DynamicImage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameVerdict {
+    /// Store the window, with any changes made to its text.
+    Keep,
+    /// Drop the frame showing the window, which is neither encoded nor indexed.
+    Skip,
+}
+
+/// The focused window of a monitor changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityChange {
+    pub monitor_id: u32,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub previous_app_name: Option<String>,
+    pub previous_window_name: Option<String>,
+}
+
+/// A video chunk was closed and won't get any more frames.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalizedSegment {
+    pub monitor_id: u32,
+    pub device_name: String,
+    pub video_path: String,
+    pub finalized_at: DateTime<Utc>,
+}
+
+/// Hooks called from the recorder loop. They run inline with capture, so anything slow should
+/// be handed off to a thread of the plugin's own.
+pub trait FramePlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn on_frame(&self, _frame: &mut PluginFrame) -> Result<FrameVerdict> {
+        Ok(FrameVerdict::Keep)
+    }
+
+    fn on_activity_change(&self, _change: &ActivityChange) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_segment_finalized(&self, _segment: &FinalizedSegment) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The JSON sent to loaded plugins.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginEvent<'a> {
+    Frame(&'a PluginFrame),
+    ActivityChange(&'a ActivityChange),
+    SegmentFinalized(&'a FinalizedSegment),
+}
+
+/// What a loaded plugin answers to a frame. Fields left out keep the frame as it is.
+#[derive(Debug, Default, Deserialize)]
+pub struct FrameResponse {
+    pub text: Option<String>,
+    pub verdict: Option<FrameVerdict>,
+}
+
+/// Adapts a plugin speaking the JSON protocol to [`FramePlugin`].
+pub(crate) trait JsonPlugin: Send + Sync {
+    fn name(&self) -> &str;
+    fn handle(&self, event: &[u8]) -> Result<Option<Vec<u8>>>;
+}
+
+struct LoadedPlugin<P>(P);
+
+impl<P: JsonPlugin> LoadedPlugin<P> {
+    fn send(&self, event: PluginEvent) -> Result<Option<Vec<u8>>> {
+        self.0.handle(&serde_json::to_vec(&event)?)
+    }
+}
+
+impl<P: JsonPlugin> FramePlugin for LoadedPlugin<P> {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn on_frame(&self, frame: &mut PluginFrame) -> Result<FrameVerdict> {
+        let response: FrameResponse = match self.send(PluginEvent::Frame(frame))? {
+            Some(response) if !response.is_empty() => serde_json::from_slice(&response)?,
+            _ => FrameResponse::default(),
+        };
+        if let Some(text) = response.text {
+            frame.text = text;
+        }
+        Ok(response.verdict.unwrap_or(FrameVerdict::Keep))
+    }
+
+    fn on_activity_change(&self, change: &ActivityChange) -> Result<()> {
+        self.send(PluginEvent::ActivityChange(change)).map(|_| ())
+    }
+
+    fn on_segment_finalized(&self, segment: &FinalizedSegment) -> Result<()> {
+        self.send(PluginEvent::SegmentFinalized(segment))
+            .map(|_| ())
+    }
+}
+
+/// The plugins of the recorder, called in registration order.
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Arc<dyn FramePlugin>>,
+}
+
+impl PluginHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every plugin in `paths`, failing on the first that can't be loaded.
+    pub fn load_all(paths: &[PathBuf]) -> Result<Self> {
+        let mut host = Self::new();
+        for path in paths {
+            host.load(path)?;
+        }
+        Ok(host)
+    }
+
+    pub fn register(&mut self, plugin: Arc<dyn FramePlugin>) {
+        info!("registered plugin {}", plugin.name());
+        self.plugins.push(plugin);
+    }
+
+    /// Loads a dynamic library or, with the `wasm-plugins` feature, a `.wasm` module.
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let plugin: Arc<dyn FramePlugin> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("so" | "dylib" | "dll") => {
+                Arc::new(LoadedPlugin(native::NativePlugin::load(path)?))
+            }
+            #[cfg(feature = "wasm-plugins")]
+            Some("wasm") => Arc::new(LoadedPlugin(wasm::WasmPlugin::load(path)?)),
+            #[cfg(not(feature = "wasm-plugins"))]
+            Some("wasm") => {
+                return Err(anyhow!(
+                "{} is a wasm plugin, which needs screenpipe built with the wasm-plugins feature",
+                path.display()
+            ))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "{} is not a plugin, expected a .so, .dylib, .dll or .wasm file",
+                    path.display()
+                ))
+            }
+        };
+        self.register(plugin);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    /// Runs `frame` through every plugin until one skips it. A failing plugin is logged and
+    /// passed over, keeping whatever the plugins before it did.
+    pub fn on_frame(&self, frame: &mut PluginFrame) -> FrameVerdict {
+        for plugin in &self.plugins {
+            let mut edited = frame.clone();
+            match guarded(plugin.as_ref(), "frame", || plugin.on_frame(&mut edited)) {
+                Some(FrameVerdict::Keep) => *frame = edited,
+                Some(FrameVerdict::Skip) => return FrameVerdict::Skip,
+                None => {}
+            }
+        }
+        FrameVerdict::Keep
+    }
+
+    pub fn on_activity_change(&self, change: &ActivityChange) {
+        for plugin in &self.plugins {
+            guarded(plugin.as_ref(), "activity change", || {
+                plugin.on_activity_change(change)
+            });
+        }
+    }
+
+    pub fn on_segment_finalized(&self, segment: &FinalizedSegment) {
+        for plugin in &self.plugins {
+            guarded(plugin.as_ref(), "finalized segment", || {
+                plugin.on_segment_finalized(segment)
+            });
+        }
+    }
+}
+
+/// Calls a hook, turning errors and panics into a warning so that a broken plugin can't stop
+/// the recording.
+fn guarded<T>(plugin: &dyn FramePlugin, hook: &str, call: impl FnOnce() -> Result<T>) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            warn!("plugin {} failed on {}: {}", plugin.name(), hook, e);
+            None
+        }
+        Err(_) => {
+            warn!("plugin {} panicked on {}", plugin.name(), hook);
+            None
+        }
+    }
+}
+
+/// Name a loaded plugin is logged with, its file name without extension.
+pub(crate) fn plugin_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().trim_start_matches("lib").to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
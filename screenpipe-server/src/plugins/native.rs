@@ -0,0 +1,85 @@
+use super::{plugin_name, JsonPlugin, PLUGIN_ABI_VERSION};
+use anyhow::{anyhow, Result};
+use libloading::Library;
+use std::path::Path;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type HandleFn = unsafe extern "C" fn(*const u8, usize, *mut usize) -> *mut u8;
+type FreeFn = unsafe extern "C" fn(*mut u8, usize);
+
+/// A plugin in a dynamic library exporting the C functions described in [`super`].
+pub(crate) struct NativePlugin {
+    name: String,
+    handle: HandleFn,
+    free: FreeFn,
+    // the functions above point into the library, which must stay loaded as long as they
+    _library: Library,
+}
+
+impl NativePlugin {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let name = plugin_name(path);
+        // SAFETY: loading runs the library's initializers; plugins are trusted like the binary
+        let library = unsafe { Library::new(path) }
+            .map_err(|e| anyhow!("failed to load plugin {}: {}", path.display(), e))?;
+
+        let (abi_version, handle, free) = unsafe {
+            let symbol = |name: &[u8]| {
+                anyhow!(
+                    "plugin {} doesn't export {}",
+                    path.display(),
+                    String::from_utf8_lossy(&name[..name.len() - 1])
+                )
+            };
+            (
+                *library
+                    .get::<AbiVersionFn>(b"screenpipe_plugin_abi_version\0")
+                    .map_err(|_| symbol(b"screenpipe_plugin_abi_version\0"))?,
+                *library
+                    .get::<HandleFn>(b"screenpipe_plugin_handle\0")
+                    .map_err(|_| symbol(b"screenpipe_plugin_handle\0"))?,
+                *library
+                    .get::<FreeFn>(b"screenpipe_plugin_free\0")
+                    .map_err(|_| symbol(b"screenpipe_plugin_free\0"))?,
+            )
+        };
+
+        let version = unsafe { abi_version() };
+        if version != PLUGIN_ABI_VERSION {
+            return Err(anyhow!(
+                "plugin {} was built for plugin abi {}, screenpipe speaks {}",
+                path.display(),
+                version,
+                PLUGIN_ABI_VERSION
+            ));
+        }
+
+        Ok(NativePlugin {
+            name,
+            handle,
+            free,
+            _library: library,
+        })
+    }
+}
+
+impl JsonPlugin for NativePlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handle(&self, event: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut len = 0usize;
+        // SAFETY: the plugin reads `event` only during the call and hands back a buffer of
+        // `len` bytes that stays valid until it is freed
+        unsafe {
+            let response = (self.handle)(event.as_ptr(), event.len(), &mut len);
+            if response.is_null() {
+                return Ok(None);
+            }
+            let bytes = std::slice::from_raw_parts(response, len).to_vec();
+            (self.free)(response, len);
+            Ok(Some(bytes))
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use super::{plugin_name, JsonPlugin};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::sync::Mutex;
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+struct Exports {
+    memory: Memory,
+    alloc: TypedFunc<u32, u32>,
+    handle: TypedFunc<(u32, u32), u64>,
+    free: TypedFunc<(u32, u32), ()>,
+}
+
+/// A plugin in a WASM module exporting the functions described in [`super`]. Modules get no
+/// imports, so they can't touch the filesystem or network.
+pub(crate) struct WasmPlugin {
+    name: String,
+    // calls need the store mutably, and frames of several monitors come in concurrently
+    instance: Mutex<(Store<()>, Exports)>,
+}
+
+impl WasmPlugin {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path)
+            .map_err(|e| anyhow!("failed to load plugin {}: {}", path.display(), e))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+
+        let exports = Exports {
+            memory: instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| anyhow!("plugin {} doesn't export memory", path.display()))?,
+            alloc: instance.get_typed_func(&mut store, "screenpipe_alloc")?,
+            handle: instance.get_typed_func(&mut store, "screenpipe_handle")?,
+            free: instance.get_typed_func(&mut store, "screenpipe_free")?,
+        };
+
+        Ok(WasmPlugin {
+            name: plugin_name(path),
+            instance: Mutex::new((store, exports)),
+        })
+    }
+}
+
+impl JsonPlugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handle(&self, event: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut instance = self
+            .instance
+            .lock()
+            .map_err(|_| anyhow!("plugin {} is poisoned", self.name))?;
+        let (store, exports) = &mut *instance;
+
+        let len = event.len() as u32;
+        let ptr = exports.alloc.call(&mut *store, len)?;
+        exports.memory.write(&mut *store, ptr as usize, event)?;
+        let packed = exports.handle.call(&mut *store, (ptr, len))?;
+        exports.free.call(&mut *store, (ptr, len))?;
+
+        let (response_ptr, response_len) = ((packed >> 32) as u32, packed as u32);
+        if response_len == 0 {
+            return Ok(None);
+        }
+        let mut response = vec![0; response_len as usize];
+        exports
+            .memory
+            .read(&*store, response_ptr as usize, &mut response)?;
+        exports
+            .free
+            .call(&mut *store, (response_ptr, response_len))?;
+        Ok(Some(response))
+    }
+}
@@ -0,0 +1,282 @@
+use once_cell::sync::Lazy;
+use screenpipe_events::send_event;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{ComponentExt, System, SystemExt};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+/// How hard the recorder may work given the current power and thermal state.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ThrottleMode {
+    Normal,
+    /// Capture at `fps_factor` times the configured fps and encode with a cheaper codec.
+    LowPower {
+        fps_factor: f64,
+    },
+    /// Stop capturing until the machine is plugged in or charged, or capture is resumed.
+    Paused,
+}
+
+impl ThrottleMode {
+    /// Capture interval to use in this mode, or `None` when capture is paused.
+    pub fn capture_interval(&self, interval: Duration) -> Option<Duration> {
+        match self {
+            ThrottleMode::Normal => Some(interval),
+            ThrottleMode::LowPower { fps_factor } if *fps_factor > 0.0 && *fps_factor < 1.0 => {
+                Some(interval.div_f64(*fps_factor))
+            }
+            ThrottleMode::LowPower { .. } => Some(interval),
+            ThrottleMode::Paused => None,
+        }
+    }
+
+    pub fn is_low_power(&self) -> bool {
+        matches!(self, ThrottleMode::LowPower { .. })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    /// Remaining charge in percent, if a battery was found.
+    pub battery_percent: Option<f32>,
+    /// Hottest temperature reported by any sensor, in °C.
+    pub max_temperature: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerPolicy {
+    /// Pause capture when on battery below this charge, in percent. `None` never pauses.
+    pub pause_below_percent: Option<f32>,
+    /// Switch to low-power capture when a sensor goes above this temperature, in °C.
+    pub thermal_limit: Option<f32>,
+    /// Factor applied to the fps on battery or under thermal pressure.
+    pub low_power_fps_factor: f64,
+    pub check_interval: Duration,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        PowerPolicy {
+            pause_below_percent: Some(20.0),
+            thermal_limit: Some(90.0),
+            low_power_fps_factor: 0.5,
+            check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl PowerPolicy {
+    pub fn decide(&self, status: &PowerStatus) -> ThrottleMode {
+        if status.on_battery {
+            if let (Some(threshold), Some(percent)) =
+                (self.pause_below_percent, status.battery_percent)
+            {
+                if percent < threshold {
+                    return ThrottleMode::Paused;
+                }
+            }
+        }
+
+        let too_hot = matches!(
+            (self.thermal_limit, status.max_temperature),
+            (Some(limit), Some(temperature)) if temperature > limit
+        );
+        if status.on_battery || too_hot {
+            return ThrottleMode::LowPower {
+                fps_factor: self.low_power_fps_factor,
+            };
+        }
+
+        ThrottleMode::Normal
+    }
+}
+
+static THROTTLE: Lazy<watch::Sender<ThrottleMode>> =
+    Lazy::new(|| watch::channel(ThrottleMode::Normal).0);
+/// What the power policy picked, before a manual pause is applied.
+static POWER_MODE: Lazy<Mutex<ThrottleMode>> = Lazy::new(|| Mutex::new(ThrottleMode::Normal));
+static CAPTURE_PAUSED: AtomicBool = AtomicBool::new(false);
+/// Fps factor the resource guard asks for while disk or memory runs low.
+static RESOURCE_FPS_FACTOR: Lazy<Mutex<Option<f64>>> = Lazy::new(Default::default);
+
+/// Current throttle mode; `Normal` unless [`monitor_power`] is running or capture is paused.
+pub fn current_throttle() -> ThrottleMode {
+    *THROTTLE.borrow()
+}
+
+/// What the power policy picked, whether or not capture is paused by hand.
+pub fn power_mode() -> ThrottleMode {
+    *POWER_MODE.lock().unwrap()
+}
+
+/// `mode` lowered to low-power capture at `fps_factor` at most, when resources run low.
+pub fn under_resource_pressure(mode: ThrottleMode, fps_factor: Option<f64>) -> ThrottleMode {
+    match (mode, fps_factor) {
+        (ThrottleMode::Normal, Some(factor)) => ThrottleMode::LowPower { fps_factor: factor },
+        (ThrottleMode::LowPower { fps_factor }, Some(factor)) => ThrottleMode::LowPower {
+            fps_factor: fps_factor.min(factor),
+        },
+        (mode, _) => mode,
+    }
+}
+
+fn publish_throttle() {
+    let mode = if capture_paused() {
+        ThrottleMode::Paused
+    } else {
+        under_resource_pressure(power_mode(), *RESOURCE_FPS_FACTOR.lock().unwrap())
+    };
+    THROTTLE.send_if_modified(|current| std::mem::replace(current, mode) != mode);
+}
+
+/// Pauses or resumes screen capture regardless of the power state.
+pub fn set_capture_paused(paused: bool) {
+    if CAPTURE_PAUSED.swap(paused, Ordering::SeqCst) != paused {
+        info!(
+            "screen capture {}",
+            if paused { "paused" } else { "resumed" }
+        );
+    }
+    publish_throttle();
+}
+
+/// Switches capture to low-power at `fps_factor` while disk or memory runs low, whatever
+/// the power state, or back with `None`.
+pub fn set_resource_pressure(fps_factor: Option<f64>) {
+    *RESOURCE_FPS_FACTOR.lock().unwrap() = fps_factor;
+    publish_throttle();
+}
+
+pub fn capture_paused() -> bool {
+    CAPTURE_PAUSED.load(Ordering::SeqCst)
+}
+
+pub fn subscribe_throttle() -> watch::Receiver<ThrottleMode> {
+    THROTTLE.subscribe()
+}
+
+/// Polls the power and thermal state and publishes the throttle mode the policy picks.
+pub async fn monitor_power(policy: PowerPolicy) {
+    info!("power-aware throttling enabled: {:?}", policy);
+    let mut interval = tokio::time::interval(policy.check_interval);
+
+    loop {
+        interval.tick().await;
+
+        let status = match tokio::task::spawn_blocking(read_power_status).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("failed to read power status: {}", e);
+                continue;
+            }
+        };
+        debug!("power status: {:?}", status);
+
+        let mode = policy.decide(&status);
+        if mode != power_mode() {
+            info!("switching to {:?} capture ({:?})", mode, status);
+            *POWER_MODE.lock().unwrap() = mode;
+            publish_throttle();
+            let _ = send_event(
+                "power_throttle_changed",
+                serde_json::json!({ "throttle": mode, "status": status }),
+            );
+        }
+    }
+}
+
+pub fn read_power_status() -> PowerStatus {
+    let (on_battery, battery_percent) = read_battery();
+    PowerStatus {
+        on_battery,
+        battery_percent,
+        max_temperature: read_max_temperature(),
+    }
+}
+
+fn read_max_temperature() -> Option<f32> {
+    let mut sys = System::new();
+    sys.refresh_components_list();
+    sys.components()
+        .iter()
+        .map(|component| component.temperature())
+        .filter(|temperature| temperature.is_finite() && *temperature > 0.0)
+        .reduce(f32::max)
+}
+
+#[cfg(target_os = "linux")]
+fn read_battery() -> (bool, Option<f32>) {
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return (false, None);
+    };
+
+    let mut ac_online = None;
+    let mut discharging = false;
+    let mut percent = None;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let read = |name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            "Mains" => ac_online = Some(ac_online.unwrap_or(false) || read("online") == "1"),
+            "Battery" => {
+                discharging |= read("status") == "Discharging";
+                percent = read("capacity").parse::<f32>().ok().or(percent);
+            }
+            _ => {}
+        }
+    }
+
+    let on_battery = match ac_online {
+        Some(online) => !online && percent.is_some(),
+        None => discharging,
+    };
+    (on_battery, percent)
+}
+
+#[cfg(target_os = "macos")]
+fn read_battery() -> (bool, Option<f32>) {
+    let Ok(output) = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+    else {
+        return (false, None);
+    };
+    parse_pmset_batt(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(target_os = "windows")]
+fn read_battery() -> (bool, Option<f32>) {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    if unsafe { GetSystemPowerStatus(&mut status) }.is_err() {
+        return (false, None);
+    }
+    // 255 means unknown for both fields
+    let percent = (status.BatteryLifePercent != 255).then_some(status.BatteryLifePercent as f32);
+    (status.ACLineStatus == 0, percent)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_battery() -> (bool, Option<f32>) {
+    (false, None)
+}
+
+/// Parses `pmset -g batt`, e.g. "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1)\t84%; discharging; ...".
+pub fn parse_pmset_batt(output: &str) -> (bool, Option<f32>) {
+    let on_battery = output.contains("'Battery Power'");
+    let percent = output
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .find_map(|word| word.strip_suffix('%'))
+        .and_then(|value| value.parse::<f32>().ok());
+    (on_battery, percent)
+}
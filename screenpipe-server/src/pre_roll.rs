@@ -0,0 +1,71 @@
+use crate::power::capture_paused;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static PRE_ROLL_MILLIS: AtomicU64 = AtomicU64::new(0);
+
+/// How much of the screen to keep while recording is paused, so that resuming starts the
+/// recording with what happened just before. Zero turns the pre-roll off and capture stops
+/// altogether while paused.
+pub fn set_pre_roll(duration: Duration) {
+    PRE_ROLL_MILLIS.store(duration.as_millis() as u64, Ordering::SeqCst);
+}
+
+pub fn pre_roll() -> Option<Duration> {
+    match PRE_ROLL_MILLIS.load(Ordering::SeqCst) {
+        0 => None,
+        millis => Some(Duration::from_millis(millis)),
+    }
+}
+
+/// Whether frames captured now are kept for the pre-roll rather than recorded: recording is
+/// paused by hand and a pre-roll is set. A pause of the power policy stops capture instead.
+pub fn buffering_pre_roll() -> bool {
+    pre_roll().is_some() && capture_paused()
+}
+
+/// When a frame captured at `instant` was captured.
+pub fn captured_at(instant: Instant) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::from_std(instant.elapsed()).unwrap_or_default()
+}
+
+/// The frames of the last `duration`, oldest first. Frames are held in memory: a pre-roll of
+/// 30 seconds at 1 fps keeps 30 captures of each monitor.
+pub struct PreRollBuffer<T> {
+    duration: Duration,
+    frames: VecDeque<(Instant, T)>,
+}
+
+impl<T> PreRollBuffer<T> {
+    pub fn new(duration: Duration) -> Self {
+        PreRollBuffer {
+            duration,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Adds a frame captured at `captured_at`, dropping the frames that became too old.
+    pub fn push(&mut self, captured_at: Instant, frame: T) {
+        self.frames.push_back((captured_at, frame));
+        while self.frames.front().is_some_and(|(oldest, _)| {
+            captured_at.saturating_duration_since(*oldest) > self.duration
+        }) {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Takes the frames out, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.frames.drain(..).map(|(_, frame)| frame)
+    }
+}
@@ -0,0 +1,707 @@
+use crate::jobs::{enqueue_job, JobProgress};
+use crate::storage::Storage;
+use crate::video_utils::get_video_metadata;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use image::{DynamicImage, ImageFormat};
+use oasgen::OaSchema;
+use once_cell::sync::Lazy;
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::{DatabaseManager, Job};
+use screenpipe_vision::CaptureResult;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tokio::process::Command;
+use tracing::{error, info, warn};
+
+/// Builds the PDF and chaptered video of a stopped presentation.
+pub const EXPORT_PRESENTATION_JOB: &str = "export_presentation";
+
+pub const MANIFEST_FILE: &str = "presentation.json";
+pub const PDF_FILE: &str = "slides.pdf";
+pub const VIDEO_FILE: &str = "presentation.mp4";
+
+/// Frames are compared on a small grayscale copy, which ignores noise like a blinking cursor.
+const THUMBNAIL_WIDTH: u32 = 64;
+const THUMBNAIL_HEIGHT: u32 = 36;
+/// How much two thumbnail pixels may differ and still count as the same.
+const PIXEL_TOLERANCE: u8 = 24;
+/// Width of the PDF pages in points, whatever the resolution of the screen.
+const PAGE_WIDTH: f64 = 960.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail(Vec<u8>);
+
+impl Thumbnail {
+    pub fn new(image: &DynamicImage) -> Self {
+        Thumbnail(
+            image
+                .thumbnail_exact(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)
+                .to_luma8()
+                .into_raw(),
+        )
+    }
+
+    /// Share of the pixels that differ between the two thumbnails, between 0 and 1.
+    pub fn difference(&self, other: &Thumbnail) -> f32 {
+        if self.0.len() != other.0.len() || self.0.is_empty() {
+            return 1.0;
+        }
+        let changed = self
+            .0
+            .iter()
+            .zip(&other.0)
+            .filter(|(a, b)| a.abs_diff(**b) > PIXEL_TOLERANCE)
+            .count();
+        changed as f32 / self.0.len() as f32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SlideDetectorConfig {
+    /// Share of the screen that must change from the previous slide to make a new one.
+    pub change_threshold: f32,
+    /// Share of the screen that may change within a slide, like a moving pointer.
+    pub stable_threshold: f32,
+    /// How long a frame must stay on screen to be a slide, so transitions are skipped.
+    pub min_display: chrono::Duration,
+}
+
+impl Default for SlideDetectorConfig {
+    fn default() -> Self {
+        SlideDetectorConfig {
+            change_threshold: 0.2,
+            stable_threshold: 0.02,
+            min_display: chrono::Duration::seconds(2),
+        }
+    }
+}
+
+/// Tells slide changes apart from the rest of a talk: a large part of the screen changes
+/// within the app showing the slides, then stays. The app of the first slide is the
+/// presenting app; frames of other apps, like a demo, are never taken as slides.
+///
+/// Capture only delivers frames when the screen changes, so a frame is judged when the next
+/// one arrives, or when the presentation ends.
+#[derive(Debug, Default)]
+pub struct SlideDetector {
+    config: SlideDetectorConfig,
+    app_name: Option<String>,
+    last_slide: Option<Thumbnail>,
+    /// The frame on screen, its app and since when.
+    pending: Option<(Thumbnail, String, DateTime<Utc>)>,
+}
+
+impl SlideDetector {
+    pub fn new(config: SlideDetectorConfig) -> Self {
+        SlideDetector {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn app_name(&self) -> Option<&str> {
+        self.app_name.as_deref()
+    }
+
+    /// Feeds the next frame. Returns when the previous frame appeared if it was a slide; it
+    /// is not when this frame only slightly differs from it, which then stays on screen.
+    pub fn observe(
+        &mut self,
+        thumbnail: Thumbnail,
+        app_name: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        if let Some((pending, pending_app, _)) = self.pending.as_mut() {
+            if pending_app == app_name
+                && pending.difference(&thumbnail) <= self.config.stable_threshold
+            {
+                *pending = thumbnail;
+                return None;
+            }
+        }
+        let slide = self.finish(timestamp);
+        self.pending = Some((thumbnail, app_name.to_string(), timestamp));
+        slide
+    }
+
+    /// Judges the frame on screen at `timestamp`, when no other frame follows it.
+    pub fn finish(&mut self, timestamp: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let (thumbnail, app_name, since) = self.pending.take()?;
+        if timestamp - since < self.config.min_display
+            || self.app_name.as_ref().is_some_and(|app| *app != app_name)
+        {
+            return None;
+        }
+        if let Some(slide) = &self.last_slide {
+            if slide.difference(&thumbnail) < self.config.change_threshold {
+                return None;
+            }
+        }
+        self.last_slide = Some(thumbnail);
+        self.app_name.get_or_insert(app_name);
+        Some(since)
+    }
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct Slide {
+    pub index: u32,
+    /// When the slide first appeared on screen.
+    pub timestamp: DateTime<Utc>,
+    /// File name of the keyframe, in the directory of the presentation.
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A recorded presentation, as stored in its `presentation.json`.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct Presentation {
+    pub name: String,
+    pub dir: String,
+    /// The monitor the slides are taken from, the one focused first unless given.
+    pub monitor_id: Option<u32>,
+    pub app_name: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub slides: Vec<Slide>,
+    pub pdf: Option<String>,
+    pub video: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportPresentationPayload {
+    pub dir: PathBuf,
+}
+
+struct Recording {
+    presentation: Presentation,
+    detector: SlideDetector,
+    /// The latest frame, kept until the detector tells whether it is a slide.
+    pending_frame: Option<Arc<CaptureResult>>,
+    /// The threads saving keyframes, waited for when the presentation stops.
+    writers: Vec<JoinHandle<()>>,
+}
+
+static RECORDING: Lazy<Mutex<Option<Recording>>> = Lazy::new(|| Mutex::new(None));
+
+/// Where presentations are stored: `<root>/<hostname>/presentations/<start time>/`.
+pub fn presentations_dir(storage: &Storage) -> PathBuf {
+    storage.host_dir().join("presentations")
+}
+
+fn write_manifest(presentation: &Presentation) -> Result<()> {
+    let path = Path::new(&presentation.dir).join(MANIFEST_FILE);
+    std::fs::write(path, serde_json::to_vec_pretty(presentation)?)?;
+    Ok(())
+}
+
+pub fn read_manifest(dir: &Path) -> Result<Presentation> {
+    Ok(serde_json::from_slice(&std::fs::read(
+        dir.join(MANIFEST_FILE),
+    )?)?)
+}
+
+pub fn current_presentation() -> Option<Presentation> {
+    RECORDING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|recording| recording.presentation.clone())
+}
+
+/// Starts taking slides from the captured frames. Only one presentation records at a time.
+pub fn start_presentation(
+    storage: &Storage,
+    name: Option<&str>,
+    monitor_id: Option<u32>,
+) -> Result<Presentation> {
+    let mut recording = RECORDING.lock().unwrap();
+    if let Some(current) = recording.as_ref() {
+        return Err(anyhow!(
+            "presentation {} is already recording",
+            current.presentation.name
+        ));
+    }
+
+    let started_at = Utc::now();
+    let local = started_at.with_timezone(&Local);
+    let dir = presentations_dir(storage).join(local.format("%Y-%m-%d_%H-%M-%S").to_string());
+    std::fs::create_dir_all(&dir)?;
+    let presentation = Presentation {
+        name: name
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Presentation {}", local.format("%Y-%m-%d %H:%M"))),
+        dir: dir.to_string_lossy().to_string(),
+        monitor_id,
+        app_name: None,
+        started_at,
+        ended_at: None,
+        slides: Vec::new(),
+        pdf: None,
+        video: None,
+    };
+    write_manifest(&presentation)?;
+    info!("presentation {} started", presentation.name);
+
+    *recording = Some(Recording {
+        presentation: presentation.clone(),
+        detector: SlideDetector::default(),
+        pending_frame: None,
+        writers: Vec::new(),
+    });
+    Ok(presentation)
+}
+
+/// Stops taking slides. The caller queues the export.
+pub fn stop_presentation() -> Result<Presentation> {
+    let mut recording = RECORDING
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| anyhow!("no presentation is recording"))?;
+    let ended_at = Utc::now();
+    if let Some(started_at) = recording.detector.finish(ended_at) {
+        if let Some(frame) = recording.pending_frame.take() {
+            add_slide(&mut recording, started_at, frame)();
+        }
+    }
+    // the export reads the keyframes
+    for writer in recording.writers.drain(..) {
+        if writer.join().is_err() {
+            error!(
+                "a slide of presentation {} failed to save",
+                recording.presentation.name
+            );
+        }
+    }
+    let mut presentation = recording.presentation;
+    presentation.ended_at = Some(ended_at);
+    write_manifest(&presentation)?;
+    info!(
+        "presentation {} stopped with {} slides",
+        presentation.name,
+        presentation.slides.len()
+    );
+    Ok(presentation)
+}
+
+/// Every stored presentation, the most recent first. The recording one is included with the
+/// slides taken so far, which are only written to its manifest when it stops.
+pub fn list_presentations(storage: &Storage) -> Result<Vec<Presentation>> {
+    let dir = presentations_dir(storage);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let current = current_presentation();
+    let mut presentations = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if let Some(current) = current
+            .as_ref()
+            .filter(|current| Path::new(&current.dir) == path)
+        {
+            presentations.push(current.clone());
+            continue;
+        }
+        match read_manifest(&path) {
+            Ok(presentation) => presentations.push(presentation),
+            Err(e) => warn!("skipping presentation {}: {}", path.display(), e),
+        }
+    }
+    presentations.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Ok(presentations)
+}
+
+/// Removes the slides shown between `start_time` and `end_time` from the presentations, with
+/// their keyframes. The exports of a stopped presentation are deleted and queued again, one
+/// left without slides is removed.
+pub async fn excise_presentations(
+    db: &DatabaseManager,
+    storage: &Storage,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<()> {
+    let in_range = |slide: &Slide| slide.timestamp >= start_time && slide.timestamp <= end_time;
+    let recording_dir = {
+        let mut guard = RECORDING.lock().unwrap();
+        guard.as_mut().map(|recording| {
+            let presentation = &recording.presentation;
+            for slide in presentation.slides.iter().filter(|slide| in_range(*slide)) {
+                let _ = std::fs::remove_file(Path::new(&presentation.dir).join(&slide.image));
+            }
+            recording
+                .presentation
+                .slides
+                .retain(|slide| !in_range(slide));
+            // the frame on screen now, a slide once the next one differs
+            recording.pending_frame = None;
+            PathBuf::from(&recording.presentation.dir)
+        })
+    };
+
+    for mut presentation in list_presentations(storage)? {
+        let dir = PathBuf::from(&presentation.dir);
+        if recording_dir.as_ref() == Some(&dir) || !presentation.slides.iter().any(in_range) {
+            continue;
+        }
+        if presentation.slides.iter().all(in_range) {
+            tokio::fs::remove_dir_all(&dir).await?;
+            info!("removed excised presentation {}", presentation.name);
+            continue;
+        }
+        for slide in presentation.slides.iter().filter(|slide| in_range(*slide)) {
+            let _ = tokio::fs::remove_file(dir.join(&slide.image)).await;
+        }
+        presentation.slides.retain(|slide| !in_range(slide));
+        let exported = presentation.pdf.is_some() || presentation.video.is_some();
+        for export in [presentation.pdf.take(), presentation.video.take()]
+            .into_iter()
+            .flatten()
+        {
+            let _ = tokio::fs::remove_file(export).await;
+        }
+        write_manifest(&presentation)?;
+        if exported {
+            enqueue_job(
+                db,
+                EXPORT_PRESENTATION_JOB,
+                &ExportPresentationPayload { dir },
+                10,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Feeds a captured frame of `monitor_id` to the recording presentation, if any.
+/// `app_name` is the app of the focused window, when it is on this monitor.
+pub fn observe_frame(monitor_id: u32, app_name: Option<&str>, frame: &Arc<CaptureResult>) {
+    match RECORDING.lock().unwrap().as_ref() {
+        Some(recording)
+            if recording
+                .presentation
+                .monitor_id
+                .map_or(true, |id| id == monitor_id) => {}
+        _ => return,
+    }
+    // outside of the lock, which the capture of every monitor takes
+    let thumbnail = Thumbnail::new(&frame.image);
+
+    let mut guard = RECORDING.lock().unwrap();
+    let Some(recording) = guard.as_mut() else {
+        return;
+    };
+    match recording.presentation.monitor_id {
+        Some(id) if id != monitor_id => return,
+        Some(_) => {}
+        None if app_name.is_some() => recording.presentation.monitor_id = Some(monitor_id),
+        None => return,
+    }
+
+    let slide = recording
+        .detector
+        .observe(thumbnail, app_name.unwrap_or_default(), Utc::now());
+    let previous = recording.pending_frame.replace(frame.clone());
+    if let (Some(started_at), Some(previous)) = (slide, previous) {
+        let save = add_slide(recording, started_at, previous);
+        recording.writers.retain(|writer| !writer.is_finished());
+        recording.writers.push(std::thread::spawn(save));
+    }
+}
+
+/// Records a slide, returning the work of saving its keyframe.
+fn add_slide(
+    recording: &mut Recording,
+    started_at: DateTime<Utc>,
+    frame: Arc<CaptureResult>,
+) -> impl FnOnce() + Send + 'static {
+    let presentation = &mut recording.presentation;
+    // after the last one, slides may have been excised
+    let index = presentation
+        .slides
+        .last()
+        .map_or(1, |slide| slide.index + 1);
+    let slide = Slide {
+        index,
+        timestamp: started_at,
+        image: format!("slide_{:03}.jpg", index),
+        width: frame.image.width(),
+        height: frame.image.height(),
+    };
+    let path = Path::new(&presentation.dir).join(&slide.image);
+    presentation.slides.push(slide);
+    presentation.app_name = recording.detector.app_name().map(str::to_string);
+
+    move || {
+        if let Err(e) = frame
+            .image
+            .to_rgb8()
+            .save_with_format(&path, ImageFormat::Jpeg)
+        {
+            error!("failed to save slide {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// A JPEG shown full page in the PDF.
+pub struct PdfPage {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A PDF with one page per image, the JPEG data embedded as is.
+pub fn slides_pdf(pages: &[PdfPage]) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    let mut object = |pdf: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(pdf.len());
+        let _ = writeln!(pdf, "{} 0 obj", offsets.len());
+        pdf.extend_from_slice(body);
+        pdf.extend_from_slice(b"\nendobj\n");
+    };
+
+    // 1 is the catalog, 2 the page tree, then a page, its content and its image per slide
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", 3 + 3 * i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    object(&mut pdf, b"<< /Type /Catalog /Pages 2 0 R >>");
+    object(
+        &mut pdf,
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).as_bytes(),
+    );
+    for (i, page) in pages.iter().enumerate() {
+        let height = PAGE_WIDTH * page.height as f64 / page.width.max(1) as f64;
+        object(
+            &mut pdf,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                height,
+                5 + 3 * i,
+                4 + 3 * i
+            )
+            .as_bytes(),
+        );
+        let content = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q", PAGE_WIDTH, height);
+        object(
+            &mut pdf,
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            )
+            .as_bytes(),
+        );
+        let mut image = format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            page.width,
+            page.height,
+            page.jpeg.len()
+        )
+        .into_bytes();
+        image.extend_from_slice(&page.jpeg);
+        image.extend_from_slice(b"\nendstream");
+        object(&mut pdf, &image);
+    }
+
+    let xref = pdf.len();
+    let _ = writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", offsets.len() + 1);
+    for offset in &offsets {
+        let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+    }
+    let _ = writeln!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF",
+        offsets.len() + 1,
+        xref
+    );
+    pdf
+}
+
+/// Seconds into the concatenation of `chunks` at which `time` was recorded. Each chunk is its
+/// first frame time and duration; times between chunks map to the start of the next one.
+pub fn video_offset(chunks: &[(DateTime<Utc>, f64)], time: DateTime<Utc>) -> f64 {
+    let mut elapsed = 0.0;
+    for (first_frame_at, duration) in chunks {
+        let into = (time - *first_frame_at).num_milliseconds() as f64 / 1000.0;
+        if into < 0.0 {
+            return elapsed;
+        }
+        if into < *duration {
+            return elapsed + into;
+        }
+        elapsed += duration;
+    }
+    elapsed
+}
+
+fn escape_metadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// FFMETADATA with a chapter per slide. `starts` are the slide offsets into a video of
+/// `duration` seconds.
+pub fn chapters_metadata(title: &str, starts: &[f64], duration: f64) -> String {
+    let millis = |seconds: f64| (seconds.clamp(0.0, duration) * 1000.0).round() as i64;
+    let mut metadata = format!(";FFMETADATA1\ntitle={}\n", escape_metadata(title));
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(duration);
+        metadata.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle=Slide {}\n",
+            millis(*start),
+            millis(end),
+            i + 1
+        ));
+    }
+    metadata
+}
+
+/// Cuts the recording of the presentation out of its monitor's chunks, with a chapter per
+/// slide. Fails while the last chunk is still being written, so the job is retried later.
+async fn export_video(
+    db: &DatabaseManager,
+    presentation: &Presentation,
+    monitor_id: u32,
+) -> Result<Option<PathBuf>> {
+    let device_name = format!("monitor_{}", monitor_id);
+    let ended_at = presentation.ended_at.unwrap_or_else(Utc::now);
+    let spans = db
+        .get_video_chunk_spans(&device_name, presentation.started_at, ended_at)
+        .await?;
+    let Some(last) = spans.last() else {
+        return Ok(None);
+    };
+    if db
+        .get_latest_video_chunk_path(&device_name)
+        .await?
+        .as_deref()
+        == Some(&last.file_path)
+    {
+        return Err(anyhow!("{} is still being recorded", last.file_path));
+    }
+
+    let mut chunks = Vec::with_capacity(spans.len());
+    for span in &spans {
+        let duration = get_video_metadata(&span.file_path).await?.duration;
+        chunks.push((span.first_frame_at, duration));
+    }
+    let start = video_offset(&chunks, presentation.started_at);
+    let duration = video_offset(&chunks, ended_at) - start;
+    let starts = presentation
+        .slides
+        .iter()
+        .map(|slide| video_offset(&chunks, slide.timestamp) - start)
+        .collect::<Vec<_>>();
+
+    let dir = Path::new(&presentation.dir);
+    let list_path = dir.join("chunks.txt");
+    let chapters_path = dir.join("chapters.txt");
+    let list = spans
+        .iter()
+        .map(|span| format!("file '{}'\n", span.file_path.replace('\'', r"'\''")))
+        .collect::<String>();
+    tokio::fs::write(&list_path, list).await?;
+    tokio::fs::write(
+        &chapters_path,
+        chapters_metadata(&presentation.name, &starts, duration),
+    )
+    .await?;
+
+    let output = dir.join(VIDEO_FILE);
+    let status = Command::new(find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?)
+        .args(["-y", "-f", "concat", "-safe", "0"])
+        .args([
+            "-ss",
+            &format!("{:.3}", start),
+            "-t",
+            &format!("{:.3}", duration),
+        ])
+        .arg("-i")
+        .arg(&list_path)
+        .arg("-i")
+        .arg(&chapters_path)
+        .args(["-map", "0:v", "-map_metadata", "1", "-map_chapters", "1"])
+        .args(["-c", "copy"])
+        .arg(&output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+    let _ = tokio::fs::remove_file(&list_path).await;
+    let _ = tokio::fs::remove_file(&chapters_path).await;
+    if !status.success() {
+        return Err(anyhow!("ffmpeg exited with {}", status));
+    }
+    Ok(Some(output))
+}
+
+/// Writes the slides of a stopped presentation to a PDF, then cuts its chaptered video.
+pub async fn export_presentation(
+    db: &DatabaseManager,
+    dir: &Path,
+    progress: Option<&JobProgress>,
+) -> Result<Presentation> {
+    let mut presentation = read_manifest(dir)?;
+    if presentation.slides.is_empty() {
+        info!("presentation {} has no slides to export", presentation.name);
+        return Ok(presentation);
+    }
+
+    let mut pages = Vec::with_capacity(presentation.slides.len());
+    for slide in &presentation.slides {
+        pages.push(PdfPage {
+            jpeg: tokio::fs::read(dir.join(&slide.image)).await?,
+            width: slide.width,
+            height: slide.height,
+        });
+    }
+    let pdf_path = dir.join(PDF_FILE);
+    tokio::fs::write(&pdf_path, slides_pdf(&pages)).await?;
+    presentation.pdf = Some(pdf_path.to_string_lossy().to_string());
+    write_manifest(&presentation)?;
+    if let Some(progress) = progress {
+        progress.report(0.2).await;
+    }
+
+    if let Some(monitor_id) = presentation.monitor_id {
+        if let Some(video) = export_video(db, &presentation, monitor_id).await? {
+            presentation.video = Some(video.to_string_lossy().to_string());
+            write_manifest(&presentation)?;
+        }
+    }
+    info!(
+        "exported presentation {} with {} slides",
+        presentation.name,
+        presentation.slides.len()
+    );
+    Ok(presentation)
+}
+
+pub(crate) async fn export_presentation_job(
+    db: Arc<DatabaseManager>,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: ExportPresentationPayload = serde_json::from_str(&job.payload)?;
+    export_presentation(&db, &payload.dir, Some(&progress)).await?;
+    Ok(())
+}
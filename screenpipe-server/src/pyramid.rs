@@ -0,0 +1,173 @@
+use crate::jobs::{run_ffmpeg_on, ChunkJobPayload, JobProgress};
+use crate::video_utils::get_video_fps;
+use anyhow::{anyhow, Result};
+use screenpipe_core::{find_ffmpeg_path, find_ffprobe_path};
+use screenpipe_db::Job;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Extracts the keyframes of a finished chunk at each pyramid level.
+pub const BUILD_PYRAMID_JOB: &str = "build_pyramid";
+
+/// Width divisor of each level: full resolution, 1/4 and 1/16. Level 2 is small enough to tile
+/// a whole day in a mosaic. Level 0 is the video itself and isn't extracted.
+pub const PYRAMID_LEVELS: [u32; 3] = [1, 4, 16];
+
+/// Directory holding the pyramid of a chunk, next to it: `monitor_1_..._pyramid/`.
+pub fn pyramid_dir(video_path: &str) -> PathBuf {
+    let path = Path::new(video_path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}_pyramid", stem))
+}
+
+/// Image of the keyframe at `offset_index` in `video_path` at `level`, e.g. `.../1/000042.jpg`.
+pub fn pyramid_frame_path(video_path: &str, level: usize, offset_index: i64) -> PathBuf {
+    pyramid_dir(video_path)
+        .join(level.to_string())
+        .join(format!("{:06}.jpg", offset_index))
+}
+
+/// Image at `level` of the last keyframe at or before `offset_index`, the closest the pyramid
+/// has of that frame. `None` when the pyramid isn't built.
+pub fn pyramid_keyframe_path(video_path: &str, level: usize, offset_index: i64) -> Option<PathBuf> {
+    let exact = pyramid_frame_path(video_path, level, offset_index);
+    if exact.exists() {
+        return Some(exact);
+    }
+    std::fs::read_dir(pyramid_dir(video_path).join(level.to_string()))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let offset = entry.path().file_stem()?.to_str()?.parse::<i64>().ok()?;
+            Some((offset, entry.path()))
+        })
+        .filter(|(offset, _)| *offset <= offset_index)
+        .max_by_key(|(offset, _)| *offset)
+        .map(|(_, path)| path)
+}
+
+/// ffmpeg arguments writing all levels in a single decode pass, to run on the chunk decoded
+/// with [`KEYFRAMES_ONLY`]. Its keyframes are numbered in order, see [`name_by_offset`].
+pub fn pyramid_ffmpeg_args(video_path: &str) -> Vec<String> {
+    let dir = pyramid_dir(video_path);
+    let levels = 1..PYRAMID_LEVELS.len();
+    let mut filter = format!("[0:v]split={}", levels.len());
+    for level in levels.clone() {
+        filter.push_str(&format!("[in{}]", level));
+    }
+    for level in levels.clone() {
+        let divisor = PYRAMID_LEVELS[level];
+        filter.push_str(&format!(";[in{level}]scale=iw/{divisor}:-1[out{level}]"));
+    }
+
+    let mut args = vec!["-filter_complex".to_string(), filter];
+    for level in levels {
+        args.extend([
+            "-map".to_string(),
+            format!("[out{}]", level),
+            "-fps_mode".to_string(),
+            "passthrough".to_string(),
+            "-start_number".to_string(),
+            "0".to_string(),
+            "-q:v".to_string(),
+            "3".to_string(),
+            dir.join(level.to_string())
+                .join("%06d.jpg")
+                .to_string_lossy()
+                .into_owned(),
+        ]);
+    }
+    args
+}
+
+/// Decoder options skipping every frame but the keyframes, which are decoded on their own.
+pub const KEYFRAMES_ONLY: [&str; 2] = ["-skip_frame", "nokey"];
+
+/// Offsets in `video_path` of its keyframes, in order.
+async fn keyframe_offsets(video_path: &str) -> Result<Vec<i64>> {
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let fps = get_video_fps(&ffmpeg_path, video_path).await?;
+    let ffprobe_path = find_ffprobe_path().ok_or_else(|| anyhow!("ffprobe not found"))?;
+    let output = Command::new(ffprobe_path)
+        .args(["-v", "error"])
+        .args(KEYFRAMES_ONLY)
+        .args([
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "frame=best_effort_timestamp_time",
+            "-of",
+            "csv=p=0",
+            video_path,
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffprobe failed on {}: {}",
+            video_path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(keyframe_offsets_from_times(
+        &String::from_utf8_lossy(&output.stdout),
+        fps,
+    ))
+}
+
+/// Offsets of the frames at the times, in seconds one per line, of a video at `fps`.
+pub fn keyframe_offsets_from_times(times: &str, fps: f64) -> Vec<i64> {
+    times
+        .lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+        .map(|time| (time * fps).round() as i64)
+        .collect()
+}
+
+/// Renames the images of a level, numbered in the order of the keyframes, after the offsets
+/// of their keyframes.
+fn name_by_offset(level_dir: &Path, offsets: &[i64]) -> Result<()> {
+    // offsets are at least the numbers, renaming from the last can't overwrite an image
+    for (number, offset) in offsets.iter().enumerate().rev() {
+        let from = level_dir.join(format!("{:06}.jpg", number));
+        if *offset != number as i64 && from.exists() {
+            std::fs::rename(&from, level_dir.join(format!("{:06}.jpg", offset)))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn build_pyramid_job(job: Job, progress: JobProgress) -> Result<()> {
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    if !Path::new(&payload.video_path).exists() {
+        return Err(anyhow!(
+            "video chunk {} no longer exists",
+            payload.video_path
+        ));
+    }
+
+    let dir = pyramid_dir(&payload.video_path);
+    let result = async {
+        let offsets = keyframe_offsets(&payload.video_path).await?;
+        for level in 1..PYRAMID_LEVELS.len() {
+            tokio::fs::create_dir_all(dir.join(level.to_string())).await?;
+        }
+        let args = pyramid_ffmpeg_args(&payload.video_path);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_ffmpeg_on(&payload.video_path, &KEYFRAMES_ONLY, &args, &progress).await?;
+        for level in 1..PYRAMID_LEVELS.len() {
+            name_by_offset(&dir.join(level.to_string()), &offsets)?;
+        }
+        Ok(())
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+    result
+}
@@ -0,0 +1,66 @@
+use crate::storage::Storage;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, ImageFormat};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How the kept frames of the screen are stored. Frames are indexed the same way in every
+/// format: an image is a chunk of one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordingFormat {
+    /// Chunks of mp4 video, the most compact.
+    #[default]
+    Video,
+    /// One lossless WebP image per frame.
+    Webp,
+    /// One PNG image per frame.
+    Png,
+}
+
+impl RecordingFormat {
+    /// Format of the frame images, or `None` when frames go to video chunks.
+    pub fn image_format(self) -> Option<ImageFormat> {
+        match self {
+            RecordingFormat::Video => None,
+            RecordingFormat::Webp => Some(ImageFormat::WebP),
+            RecordingFormat::Png => Some(ImageFormat::Png),
+        }
+    }
+}
+
+static RECORDING_FORMAT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_recording_format(format: RecordingFormat) {
+    RECORDING_FORMAT.store(format as u8, Ordering::SeqCst);
+}
+
+pub fn recording_format() -> RecordingFormat {
+    match RECORDING_FORMAT.load(Ordering::SeqCst) {
+        1 => RecordingFormat::Webp,
+        2 => RecordingFormat::Png,
+        _ => RecordingFormat::Video,
+    }
+}
+
+/// Writes the frame of `monitor_id` captured at `time` as an image, returning its path.
+pub fn write_frame_image(
+    storage: &Storage,
+    monitor_id: u32,
+    time: DateTime<Utc>,
+    image: &DynamicImage,
+    format: ImageFormat,
+) -> Result<PathBuf> {
+    let path = storage.frame_image_path(monitor_id, time, format.extensions_str()[0])?;
+    // the WebP encoder takes no 16 bit or float pixels, captures are 8 bit anyway
+    let converted;
+    let image = match image {
+        DynamicImage::ImageRgba8(_) | DynamicImage::ImageRgb8(_) => image,
+        _ => {
+            converted = DynamicImage::ImageRgba8(image.to_rgba8());
+            &converted
+        }
+    };
+    image.save_with_format(&path, format)?;
+    Ok(path)
+}
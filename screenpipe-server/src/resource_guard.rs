@@ -0,0 +1,144 @@
+use crate::power::set_resource_pressure;
+use screenpipe_events::send_event;
+use screenpipe_vision::core::set_skip_ocr;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sysinfo::{DiskExt, Pid, PidExt, ProcessExt, System, SystemExt};
+use tracing::{debug, info, warn};
+
+/// How far past a limit the guard waits before leaving the degraded profile, so that it
+/// doesn't flip back and forth around the limit.
+const RECOVERY_MARGIN: f64 = 0.1;
+
+/// When the recorder switches to its degraded profile: lower fps, higher CRF and no OCR.
+#[derive(Debug, Clone)]
+pub struct ResourceLimits {
+    /// Free space of the disk the recordings are written to. `None` never checks it.
+    pub min_free_disk_bytes: Option<u64>,
+    /// Resident memory of screenpipe and its child processes. `None` never checks it.
+    pub max_memory_bytes: Option<u64>,
+    /// Factor applied to the fps in the degraded profile.
+    pub degraded_fps_factor: f64,
+    pub check_interval: Duration,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            min_free_disk_bytes: Some(2 * 1024 * 1024 * 1024),
+            max_memory_bytes: Some(4 * 1024 * 1024 * 1024),
+            degraded_fps_factor: 0.5,
+            check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ResourceStatus {
+    pub free_disk_bytes: Option<u64>,
+    pub memory_bytes: Option<u64>,
+}
+
+/// A limit the recorder is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourcePressure {
+    LowDisk,
+    HighMemory,
+}
+
+impl ResourceLimits {
+    /// The limits `status` is over. In the degraded profile a limit stays exceeded until
+    /// there's a margin of [`RECOVERY_MARGIN`] past it.
+    pub fn pressure(&self, status: &ResourceStatus, degraded: bool) -> Vec<ResourcePressure> {
+        let margin = if degraded { 1.0 + RECOVERY_MARGIN } else { 1.0 };
+        let mut pressure = Vec::new();
+        if let (Some(min), Some(free)) = (self.min_free_disk_bytes, status.free_disk_bytes) {
+            if (free as f64) < min as f64 * margin {
+                pressure.push(ResourcePressure::LowDisk);
+            }
+        }
+        if let (Some(max), Some(memory)) = (self.max_memory_bytes, status.memory_bytes) {
+            if memory as f64 > max as f64 / margin {
+                pressure.push(ResourcePressure::HighMemory);
+            }
+        }
+        pressure
+    }
+}
+
+/// Free space of the disk holding `path`: the one mounted at the longest prefix of it.
+fn free_disk_space(sys: &System, path: &Path) -> Option<u64> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    sys.disks()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// Resident memory of this process and of its children, like ffmpeg.
+fn memory_usage(sys: &System) -> Option<u64> {
+    let pid = Pid::from_u32(std::process::id());
+    let main = sys.process(pid)?.memory();
+    let children: u64 = sys
+        .processes()
+        .values()
+        .filter(|process| process.parent() == Some(pid))
+        .map(|process| process.memory())
+        .sum();
+    Some(main + children)
+}
+
+pub fn read_resource_status(data_dir: &Path) -> ResourceStatus {
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    sys.refresh_processes();
+    ResourceStatus {
+        free_disk_bytes: free_disk_space(&sys, data_dir),
+        memory_bytes: memory_usage(&sys),
+    }
+}
+
+/// Checks the free space of the disk under `data_dir` and the memory of the recorder, and
+/// switches to the degraded profile while one of them is over its limit.
+pub async fn guard_resources(limits: ResourceLimits, data_dir: PathBuf) {
+    info!("resource guard enabled: {:?}", limits);
+    let mut interval = tokio::time::interval(limits.check_interval);
+    let mut degraded = false;
+
+    loop {
+        interval.tick().await;
+
+        let dir = data_dir.clone();
+        let status = match tokio::task::spawn_blocking(move || read_resource_status(&dir)).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("failed to read resource usage: {}", e);
+                continue;
+            }
+        };
+        debug!("resource status: {:?}", status);
+
+        let pressure = limits.pressure(&status, degraded);
+        if pressure.is_empty() == !degraded {
+            continue;
+        }
+        degraded = !pressure.is_empty();
+        if degraded {
+            warn!(
+                "running low on resources ({:?}, {:?}), lowering fps and quality and skipping OCR",
+                pressure, status
+            );
+        } else {
+            info!("resources recovered ({:?}), back to normal capture", status);
+        }
+        set_resource_pressure(degraded.then_some(limits.degraded_fps_factor));
+        set_skip_ocr(degraded);
+        let _ = send_event(
+            "resource_pressure_changed",
+            serde_json::json!({ "degraded": degraded, "pressure": pressure, "status": status }),
+        );
+    }
+}
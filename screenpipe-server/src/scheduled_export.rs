@@ -0,0 +1,360 @@
+use crate::jobs::{enqueue_job, JobProgress};
+use crate::storage::sanitize_component;
+use crate::summarize::{llm_backend, summarize_range};
+use crate::video_utils::extract_frame_from_video;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use cron::Schedule;
+use oasgen::OaSchema;
+use screenpipe_db::{Bookmark, DatabaseManager, Job};
+use screenpipe_events::send_event;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Runs one occurrence of an export schedule.
+pub const SCHEDULED_EXPORT_JOB: &str = "scheduled_export";
+
+/// Read from the data directory when `--export-config` is not given.
+pub const DEFAULT_EXPORT_CONFIG_FILE: &str = "exports.json";
+
+pub const MANIFEST_FILE: &str = "export.json";
+pub const BOOKMARKS_FILE: &str = "bookmarks.json";
+pub const REPORT_FILE: &str = "report.md";
+
+/// Where the desktop app listens for notifications to show.
+const NOTIFY_URL: &str = "http://localhost:11435/notify";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportContent {
+    /// The bookmarks of the period, with a still of the frame each one points at.
+    Bookmarks,
+    /// A summary of the period written by the configured llm, with the apps used.
+    Report,
+}
+
+/// An export run on a schedule, as defined in the export config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportSchedule {
+    pub name: String,
+    /// Cron expression in local time, seconds first: `0 0 17 * * Fri` is Fridays at 5pm.
+    pub schedule: String,
+    /// Folder the exports are written to, e.g. a shared team drive or a synced bucket.
+    pub destination: PathBuf,
+    /// How far back each export goes.
+    #[serde(default = "default_lookback_days")]
+    pub lookback_days: u32,
+    #[serde(default = "default_contents")]
+    pub contents: Vec<ExportContent>,
+}
+
+fn default_lookback_days() -> u32 {
+    7
+}
+
+fn default_contents() -> Vec<ExportContent> {
+    vec![ExportContent::Bookmarks, ExportContent::Report]
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExportConfig {
+    #[serde(default)]
+    pub exports: Vec<ExportSchedule>,
+}
+
+impl ExportConfig {
+    /// Parses the config, rejecting schedules that can't be run.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: ExportConfig = serde_json::from_str(content)?;
+        for export in &config.exports {
+            if export.name.trim().is_empty() {
+                return Err(anyhow!("an export has no name"));
+            }
+            Schedule::from_str(&export.schedule)
+                .map_err(|e| anyhow!("invalid schedule of export '{}': {}", export.name, e))?;
+            if export.lookback_days == 0 {
+                return Err(anyhow!("lookback_days of export '{}' is 0", export.name));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read export config {}", path.display()))?;
+        Self::parse(&content)
+    }
+}
+
+impl ExportSchedule {
+    /// When the export runs next after `after`.
+    pub fn next_run(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        Schedule::from_str(&self.schedule)
+            .ok()?
+            .after(&after)
+            .next()
+    }
+
+    /// Folder of the occurrence ending at `end`: `<destination>/<name>/<local date>`.
+    pub fn export_dir(&self, end: DateTime<Utc>) -> PathBuf {
+        self.destination
+            .join(sanitize_component(&self.name))
+            .join(end.with_timezone(&Local).format("%Y-%m-%d").to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledExportPayload {
+    pub export: ExportSchedule,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// What an export wrote, saved as its manifest.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub name: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub exported_at: DateTime<Utc>,
+    /// Paths relative to the export folder.
+    pub files: Vec<String>,
+    /// Why the contents left out of the export couldn't be written.
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// Writes the contents of `payload.export` for its period, returning the manifest.
+pub async fn run_scheduled_export(
+    db: &DatabaseManager,
+    payload: &ScheduledExportPayload,
+    progress: Option<&JobProgress>,
+) -> Result<ExportManifest> {
+    let export = &payload.export;
+    let dir = export.export_dir(payload.end_time);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for (i, content) in export.contents.iter().enumerate() {
+        match content {
+            ExportContent::Bookmarks => {
+                files.extend(
+                    export_bookmarks(db, &dir, payload.start_time, payload.end_time).await?,
+                );
+            }
+            // the llm may well be unreachable when the export runs, the rest is still written
+            ExportContent::Report => match write_report(db, payload, &dir).await {
+                Ok(()) => files.push(REPORT_FILE.to_string()),
+                Err(e) => {
+                    error!("export '{}' left out its report: {}", export.name, e);
+                    errors.push(format!("report: {}", e));
+                }
+            },
+        }
+        if let Some(progress) = progress {
+            progress
+                .report((i + 1) as f64 / export.contents.len() as f64)
+                .await;
+        }
+    }
+
+    let manifest = ExportManifest {
+        name: export.name.clone(),
+        start_time: payload.start_time,
+        end_time: payload.end_time,
+        exported_at: Utc::now(),
+        files,
+        errors,
+    };
+    tokio::fs::write(
+        dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .await?;
+    Ok(manifest)
+}
+
+/// Writes the summary of the period and the apps used in it to [`REPORT_FILE`].
+async fn write_report(
+    db: &DatabaseManager,
+    payload: &ScheduledExportPayload,
+    dir: &Path,
+) -> Result<()> {
+    let export = &payload.export;
+    let summary = summarize_range(db, llm_backend(), payload.start_time, payload.end_time).await?;
+    let apps = db
+        .get_app_usage(payload.start_time, payload.end_time, 50)
+        .await?;
+    let mut report = format!(
+        "# {}\n\n{} to {}\n\n{}\n",
+        export.name,
+        payload
+            .start_time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        payload
+            .end_time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        summary.summary.trim()
+    );
+    if !apps.is_empty() {
+        report.push_str("\n## Apps\n\n| app | window | frames |\n| --- | --- | --- |\n");
+        for app in &apps {
+            let _ = writeln!(
+                report,
+                "| {} | {} | {} |",
+                app.app_name.replace('|', "\\|"),
+                app.window_name.replace('|', "\\|"),
+                app.frame_count
+            );
+        }
+    }
+    tokio::fs::write(dir.join(REPORT_FILE), report).await?;
+    Ok(())
+}
+
+/// Writes the bookmarks of the period and a still of each bookmarked frame, returning the
+/// files written. A still that can't be extracted is left out, the bookmark is still listed.
+async fn export_bookmarks(
+    db: &DatabaseManager,
+    dir: &Path,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<Vec<String>> {
+    let bookmarks: Vec<Bookmark> = db
+        .list_bookmarks(None, Some(start_time), Some(end_time), None, u32::MAX, 0)
+        .await?;
+    let mut files = vec![BOOKMARKS_FILE.to_string()];
+    tokio::fs::write(
+        dir.join(BOOKMARKS_FILE),
+        serde_json::to_string_pretty(&bookmarks)?,
+    )
+    .await?;
+
+    for bookmark in &bookmarks {
+        let Some(frame_id) = bookmark.frame_id else {
+            continue;
+        };
+        let still = match db.get_frame(frame_id).await? {
+            Some((file_path, offset_index)) => {
+                extract_frame_from_video(&file_path, offset_index).await
+            }
+            None => continue,
+        };
+        let file = format!(
+            "bookmarks/{}_{}.jpg",
+            bookmark.id,
+            sanitize_component(&bookmark.name)
+        );
+        match still {
+            Ok(still) => {
+                tokio::fs::create_dir_all(dir.join("bookmarks")).await?;
+                tokio::fs::copy(&still, dir.join(&file)).await?;
+                files.push(file);
+            }
+            Err(e) => warn!(
+                "failed to extract the frame of bookmark {}: {}",
+                bookmark.id, e
+            ),
+        }
+    }
+    Ok(files)
+}
+
+#[derive(Debug, Serialize)]
+struct ScheduledExportEvent<'a> {
+    name: &'a str,
+    success: bool,
+    dir: String,
+    error: Option<String>,
+}
+
+/// Tells event subscribers and the desktop app how an export went.
+async fn notify(export: &ExportSchedule, dir: &Path, error: Option<String>) {
+    let (title, body) = match &error {
+        None => (
+            format!("export '{}' done", export.name),
+            format!("written to {}", dir.display()),
+        ),
+        Some(error) => (format!("export '{}' failed", export.name), error.clone()),
+    };
+    let _ = send_event(
+        "scheduled_export",
+        ScheduledExportEvent {
+            name: &export.name,
+            success: error.is_none(),
+            dir: dir.to_string_lossy().to_string(),
+            error,
+        },
+    );
+    // the app may not be running, the event is enough then
+    let _ = reqwest::Client::new()
+        .post(NOTIFY_URL)
+        .json(&serde_json::json!({ "title": title, "body": body }))
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await;
+}
+
+pub(crate) async fn scheduled_export_job(
+    db: Arc<DatabaseManager>,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: ScheduledExportPayload = serde_json::from_str(&job.payload)?;
+    let dir = payload.export.export_dir(payload.end_time);
+    match run_scheduled_export(&db, &payload, Some(&progress)).await {
+        Ok(manifest) => {
+            info!(
+                "export '{}' wrote {} files to {}",
+                manifest.name,
+                manifest.files.len(),
+                dir.display()
+            );
+            notify(&payload.export, &dir, None).await;
+            Ok(())
+        }
+        Err(e) => {
+            // only the last attempt is worth a notification
+            if job.attempts >= job.max_attempts {
+                notify(&payload.export, &dir, Some(e.to_string())).await;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Queues each export at the times of its schedule, for as long as the recorder runs.
+/// The schedules run in this future, dropping it stops them all.
+pub async fn schedule_exports(db: Arc<DatabaseManager>, config: ExportConfig) {
+    let tasks = config.exports.into_iter().map(|export| {
+        let db = db.clone();
+        async move {
+            info!("scheduling export '{}' ({})", export.name, export.schedule);
+            while let Some(next) = export.next_run(Local::now()) {
+                let wait = (next - Local::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(wait).await;
+
+                let end_time = Utc::now();
+                let payload = ScheduledExportPayload {
+                    start_time: end_time - Duration::days(export.lookback_days as i64),
+                    end_time,
+                    export: export.clone(),
+                };
+                if let Err(e) = enqueue_job(&db, SCHEDULED_EXPORT_JOB, &payload, 3).await {
+                    error!("failed to queue export '{}': {}", export.name, e);
+                }
+            }
+            warn!("export '{}' has no upcoming run", export.name);
+        }
+    });
+    futures::future::join_all(tasks).await;
+}
@@ -0,0 +1,320 @@
+use crate::evidence::{chain_head_path, manifest_path, SegmentManifest};
+use crate::mosaic::mosaic_path;
+use crate::presentation::{presentations_dir, Presentation, MANIFEST_FILE, PDF_FILE, VIDEO_FILE};
+use crate::pyramid::pyramid_frame_path;
+use crate::scheduled_export::{self, ExportManifest};
+use crate::sessions::{session_manifest_path, session_manifest_schema};
+use crate::storage::Storage;
+use crate::subtitles::sidecar_path;
+use crate::thumbnails::{thumbnail_index_path, thumbnail_sprite_path, ThumbnailStrip};
+use crate::timelapse::timelapse_path;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, DatabaseSchema, RecordingSession};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Version of the archive format, bumped on changes that break readers.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const SAMPLE_MONITOR_ID: u32 = 7;
+const SAMPLE_SESSION_ID: i64 = 42;
+
+/// Self-description of the data directory, for tools reading recordings without screenpipe.
+#[derive(Debug, Serialize)]
+pub struct ArchiveSchema {
+    pub format_version: u32,
+    pub screenpipe_version: &'static str,
+    /// What the placeholders in the layout paths stand for.
+    pub placeholders: &'static [Placeholder],
+    pub layout: Vec<LayoutEntry>,
+    pub database: DatabaseSchema,
+    pub manifests: Vec<ManifestFormat>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Placeholder {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// A kind of file, with its path relative to the data directory.
+#[derive(Debug, Serialize)]
+pub struct LayoutEntry {
+    pub path: String,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestFormat {
+    pub path: String,
+    /// JSON schema of the manifest.
+    pub schema: Value,
+}
+
+const PLACEHOLDERS: &[Placeholder] = &[
+    Placeholder {
+        name: "{hostname}",
+        description: "the machine that recorded, several can share a data directory",
+    },
+    Placeholder {
+        name: "{date}",
+        description: "YYYY-MM-DD, in UTC for recordings and local time for everything else",
+    },
+    Placeholder {
+        name: "{time}",
+        description: "HH-MM-SS, with the same time zone as {date}",
+    },
+    Placeholder {
+        name: "{millis}",
+        description: "milliseconds of {time}, three digits",
+    },
+    Placeholder {
+        name: "{hour}",
+        description: "HH of the capture time, in UTC",
+    },
+    Placeholder {
+        name: "{monitor_id}",
+        description: "the id of the monitor, as in the device_name of its frames",
+    },
+    Placeholder {
+        name: "{device}",
+        description: "the name of the audio device, with path separators replaced by _",
+    },
+    Placeholder {
+        name: "{level}",
+        description: "zoom level, 1 for 1/4 of the width and 2 for 1/16, 0 being the video itself",
+    },
+    Placeholder {
+        name: "{offset_index}",
+        description: "offset_index of the frame in its chunk, six digits",
+    },
+    Placeholder {
+        name: "{extension}",
+        description: "webp or png",
+    },
+    Placeholder {
+        name: "{session_id}",
+        description: "id of the recording_sessions row",
+    },
+    Placeholder {
+        name: "{export_dir}",
+        description: "the destination of a scheduled export, outside of the data directory",
+    },
+    Placeholder {
+        name: "{export_name}",
+        description: "the name of the scheduled export",
+    },
+];
+
+/// Replaces the parts of paths built for `sample` and the sample ids with placeholders.
+struct Templater {
+    substrings: Vec<(String, &'static str)>,
+    hour: String,
+}
+
+impl Templater {
+    fn new(sample: DateTime<Utc>) -> Self {
+        let local = sample.with_timezone(&Local);
+        Templater {
+            substrings: vec![
+                (
+                    sample.format("%Y-%m-%d_%H-%M-%S-%3f").to_string(),
+                    "{date}_{time}-{millis}",
+                ),
+                (
+                    sample.format("%Y-%m-%d_%H-%M-%S").to_string(),
+                    "{date}_{time}",
+                ),
+                (
+                    local.format("%Y-%m-%d_%H-%M-%S").to_string(),
+                    "{date}_{time}",
+                ),
+                (sample.format("%Y-%m-%d").to_string(), "{date}"),
+                (local.format("%Y-%m-%d").to_string(), "{date}"),
+                (
+                    format!("monitor_{}", SAMPLE_MONITOR_ID),
+                    "monitor_{monitor_id}",
+                ),
+                (format!("_{}.json", SAMPLE_SESSION_ID), "_{session_id}.json"),
+                ("000000.jpg".to_string(), "{offset_index}.jpg"),
+            ],
+            hour: sample.format("%H").to_string(),
+        }
+    }
+
+    /// `path` relative to `base`, with `/` separators.
+    fn template(&self, base: &Path, path: &Path) -> String {
+        path.strip_prefix(base)
+            .unwrap_or(path)
+            .components()
+            .map(|component| {
+                let component = component.as_os_str().to_string_lossy();
+                if component == self.hour {
+                    return "{hour}".to_string();
+                }
+                if component == "1" {
+                    return "{level}".to_string();
+                }
+                self.substrings.iter().fold(
+                    component.into_owned(),
+                    |component, (sample, placeholder)| {
+                        component.replace(sample.as_str(), placeholder)
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+fn schema_of<T: OaSchema>() -> Value {
+    serde_json::to_value(T::schema()).unwrap_or_default()
+}
+
+/// Describes the layout of the data directory and the manifests from the paths the recorder
+/// builds, and the database from the schema the migrations create in `db`.
+pub async fn archive_schema(db: &DatabaseManager) -> Result<ArchiveSchema> {
+    // some paths are only built along with their directory
+    let scratch = tempfile::tempdir()?;
+    let base = scratch.path();
+    let storage = Storage::with_hostname(base.join("data"), "{hostname}");
+    let sample =
+        Utc.with_ymd_and_hms(2001, 2, 3, 12, 34, 56).unwrap() + Duration::milliseconds(789);
+    let templater = Templater::new(sample);
+    let local_date = sample.with_timezone(&Local).date_naive();
+
+    let video = storage.video_chunk_path(SAMPLE_MONITOR_ID, sample)?;
+    let session = RecordingSession {
+        id: SAMPLE_SESSION_ID,
+        name: String::new(),
+        started_at: sample,
+        ended_at: None,
+        end_reason: None,
+        notes: None,
+        tag: None,
+    };
+    let presentation_dir = presentations_dir(&storage).join(
+        sample
+            .with_timezone(&Local)
+            .format("%Y-%m-%d_%H-%M-%S")
+            .to_string(),
+    );
+    let monitor = format!("monitor_{}", SAMPLE_MONITOR_ID);
+
+    let layout = [
+        (
+            base.join("db.sqlite"),
+            "SQLite database indexing everything recorded, see database",
+        ),
+        (
+            storage.lock_path(),
+            "held by the process recording into the tree: pid, session_id and started_at",
+        ),
+        (
+            storage.journal_path(SAMPLE_MONITOR_ID),
+            "write-ahead journal of a monitor: a JSON record per frame encoded and per frame indexed, replayed into the database on start",
+        ),
+        (
+            video.clone(),
+            "video of one monitor, its frames are the frames rows with this file_path in video_chunks, by offset_index",
+        ),
+        (
+            sidecar_path(&video),
+            "WebVTT subtitles of a video: the window title and headings on screen at each frame",
+        ),
+        (
+            manifest_path(&video),
+            "evidence manifest of a sealed video, chained to the one sealed before, see manifests",
+        ),
+        (
+            chain_head_path(&storage),
+            "last link of the evidence chain of the machine: sequence and chain_hash",
+        ),
+        (
+            pyramid_frame_path(&video.to_string_lossy(), 1, 0),
+            "JPEG of a keyframe of a video, 1/4 wide at level 1 and 1/16 at level 2, built in the background",
+        ),
+        (
+            thumbnail_sprite_path(&video.to_string_lossy()),
+            "WebP sprite of thumbnails of a video, one a minute, built in the background",
+        ),
+        (
+            thumbnail_index_path(&video.to_string_lossy()),
+            "where each thumbnail of the sprite is and the frame it shows, see manifests",
+        ),
+        (
+            storage.frame_image_path(SAMPLE_MONITOR_ID, sample, "{extension}")?,
+            "frame recorded as an image rather than into a video, its video_chunks row has this file_path",
+        ),
+        (
+            base.join("data").join("{device}_{date}_{time}.mp4"),
+            "audio of one device, see audio_chunks",
+        ),
+        (
+            session_manifest_path(&storage, &session),
+            "manifest of a recording session, see manifests",
+        ),
+        (
+            mosaic_path(&storage, local_date),
+            "strip of the day's frames shown above the timeline",
+        ),
+        (
+            timelapse_path(&storage, local_date, &monitor),
+            "sped-up video of a monitor's day with the time burned in",
+        ),
+        (
+            presentation_dir.join(MANIFEST_FILE),
+            "manifest of a recorded presentation, its slides are images next to it",
+        ),
+        (presentation_dir.join(PDF_FILE), "slides of a presentation"),
+        (
+            presentation_dir.join(VIDEO_FILE),
+            "video of a presentation, one frame per slide",
+        ),
+    ]
+    .into_iter()
+    .map(|(path, description)| LayoutEntry {
+        path: templater.template(base, &path),
+        description,
+    })
+    .collect();
+
+    let export_manifest = format!(
+        "{{export_dir}}/{{export_name}}/{{date}}/{}",
+        scheduled_export::MANIFEST_FILE
+    );
+    let manifests = vec![
+        ManifestFormat {
+            path: templater.template(base, &session_manifest_path(&storage, &session)),
+            schema: session_manifest_schema(),
+        },
+        ManifestFormat {
+            path: templater.template(base, &presentation_dir.join(MANIFEST_FILE)),
+            schema: schema_of::<Presentation>(),
+        },
+        ManifestFormat {
+            path: export_manifest,
+            schema: schema_of::<ExportManifest>(),
+        },
+        ManifestFormat {
+            path: templater.template(base, &manifest_path(&video)),
+            schema: schema_of::<SegmentManifest>(),
+        },
+        ManifestFormat {
+            path: templater.template(base, &thumbnail_index_path(&video.to_string_lossy())),
+            schema: schema_of::<ThumbnailStrip>(),
+        },
+    ];
+
+    Ok(ArchiveSchema {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        screenpipe_version: env!("CARGO_PKG_VERSION"),
+        placeholders: PLACEHOLDERS,
+        layout,
+        database: db.get_schema().await?,
+        manifests,
+    })
+}
@@ -0,0 +1,198 @@
+use crate::summarize::parse_time_arg;
+use crate::ContentItem;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use screenpipe_db::ContentType;
+use tracing::debug;
+
+/// Filters typed into a search query as `key:value` terms, e.g.
+/// `app:chrome title:"jira" after:2024-05-01 before:2024-05-02 text:"retry policy"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilters {
+    /// The terms left for full text search, quoted phrases kept quoted.
+    pub text: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub content_type: Option<ContentType>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub language: Option<String>,
+    pub focused: Option<bool>,
+    /// A category of `categories.json`, as written there.
+    pub category: Option<String>,
+}
+
+/// The keys understood in a query, anything else before a colon, like `https:` or `12:`, is
+/// searched as text.
+pub const SEARCH_FILTER_KEYS: &[&str] = &[
+    "app", "title", "window", "url", "type", "after", "before", "lang", "text", "is", "category",
+];
+
+/// Splits a query on whitespace outside of double quotes, keeping the quotes.
+fn tokenize(query: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow!("unterminated quote in '{}'", query));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn unquote(value: &str) -> String {
+    value.replace('"', "")
+}
+
+/// A date is the local midnight starting it, so `after:2024-05-01 before:2024-05-02` is that
+/// day. Anything else is read like the times given on the command line.
+fn parse_time_filter(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("'{}' doesn't exist in the local timezone", value));
+    }
+    parse_time_arg(value, Local::now().date_naive())
+}
+
+fn parse_content_type(value: &str) -> Result<ContentType> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase())).map_err(|_| {
+        anyhow!(
+            "unknown content type '{}', use all, ocr, audio, ui, audio+ui, ocr+ui or audio+ocr",
+            value
+        )
+    })
+}
+
+/// Parses the `key:value` filters out of a search query, the rest is left as text. Values
+/// with spaces are quoted, `title:"pull request"`, and a filter given twice keeps the last.
+pub fn parse_search_query(query: &str) -> Result<SearchFilters> {
+    let mut filters = SearchFilters::default();
+    let mut text = Vec::new();
+
+    for token in tokenize(query)? {
+        let filter = token
+            .split_once(':')
+            .map(|(key, value)| (key.to_lowercase(), value))
+            .filter(|(key, _)| SEARCH_FILTER_KEYS.contains(&key.as_str()));
+        let Some((key, raw_value)) = filter else {
+            text.push(token);
+            continue;
+        };
+        let value = unquote(raw_value);
+        if value.trim().is_empty() {
+            return Err(anyhow!("{}: needs a value", key));
+        }
+
+        match key.as_str() {
+            "app" => filters.app_name = Some(value),
+            "title" | "window" => filters.window_name = Some(value),
+            "url" => filters.browser_url = Some(value),
+            "type" => filters.content_type = Some(parse_content_type(&value)?),
+            "after" => filters.start_time = Some(parse_time_filter(&value)?),
+            "before" => filters.end_time = Some(parse_time_filter(&value)?),
+            "lang" => filters.language = Some(value.to_lowercase()),
+            "category" => filters.category = Some(value),
+            "text" => text.push(format!("\"{}\"", value)),
+            "is" => match value.to_lowercase().as_str() {
+                "focused" => filters.focused = Some(true),
+                "unfocused" => filters.focused = Some(false),
+                _ => {
+                    return Err(anyhow!(
+                        "unknown is:{}, use is:focused or is:unfocused",
+                        value
+                    ))
+                }
+            },
+            _ => unreachable!("keys are checked against SEARCH_FILTER_KEYS"),
+        }
+    }
+
+    if let (Some(start), Some(end)) = (filters.start_time, filters.end_time) {
+        if start > end {
+            return Err(anyhow!("after: is later than before:"));
+        }
+    }
+    filters.text = text.join(" ");
+    Ok(filters)
+}
+
+/// The filters of a query like [`parse_search_query`], or when it can't be parsed, an
+/// unterminated quote or a filter with a bad value, the whole query searched as text. Its
+/// quotes are dropped when unterminated, for the text to still be a valid search.
+pub fn search_filters_or_text(query: &str) -> SearchFilters {
+    match parse_search_query(query) {
+        Ok(filters) => filters,
+        Err(e) => {
+            debug!("searching '{}' as text: {}", query, e);
+            let text = if query.matches('"').count() % 2 == 0 {
+                query.to_string()
+            } else {
+                unquote(query)
+            };
+            SearchFilters {
+                text,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Characters of a result's text shown by `screenpipe search`.
+const PREVIEW_CHARS: usize = 120;
+
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > PREVIEW_CHARS {
+        format!("{}…", text.chars().take(PREVIEW_CHARS).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// One line of `screenpipe search` output: when and where the result was recorded and the
+/// start of its text.
+pub fn format_search_result(item: &ContentItem) -> String {
+    let (timestamp, source, text) = match item {
+        ContentItem::OCR(ocr) => (
+            ocr.timestamp,
+            format!("{} — {}", ocr.app_name, ocr.window_name),
+            &ocr.text,
+        ),
+        ContentItem::Audio(audio) => (
+            audio.timestamp,
+            audio.device_name.clone(),
+            &audio.transcription,
+        ),
+        ContentItem::UI(ui) => (
+            ui.timestamp,
+            format!("{} — {}", ui.app_name, ui.window_name),
+            &ui.text,
+        ),
+    };
+    format!(
+        "{}  {}: {}",
+        timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        source,
+        preview(text)
+    )
+}
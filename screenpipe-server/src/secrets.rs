@@ -0,0 +1,224 @@
+use crate::cli::{Cli, OutputFormat, SecretsCommand};
+use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+/// Service the secrets are stored under in the keychain.
+pub const KEYCHAIN_SERVICE: &str = "screenpipe";
+
+/// Prefix of a config value read from the keychain, e.g.
+/// `"secret_access_key": "keychain:s3-secret-access-key"`.
+pub const KEYCHAIN_REFERENCE_PREFIX: &str = "keychain:";
+
+pub const API_KEY: &str = "api-key";
+pub const LLM_API_KEY: &str = "llm-api-key";
+pub const EMBEDDING_API_KEY: &str = "embedding-api-key";
+pub const DEEPGRAM_API_KEY: &str = "deepgram-api-key";
+
+/// The secrets screenpipe reads by name when their flag isn't given, and what they are.
+pub const KNOWN_SECRETS: &[(&str, &str)] = &[
+    (API_KEY, "key the api is protected by, --api-key"),
+    (LLM_API_KEY, "key of the chat api, --llm-api-key"),
+    (
+        EMBEDDING_API_KEY,
+        "key of the embeddings api, --embedding-api-key",
+    ),
+    (DEEPGRAM_API_KEY, "key of deepgram, --deepgram-api-key"),
+];
+
+/// Where secrets are kept, the OS keychain outside of tests.
+pub trait SecretStore {
+    fn get(&self, name: &str) -> Result<Option<String>>;
+    fn set(&self, name: &str, value: &str) -> Result<()>;
+    /// Returns whether there was a secret to delete.
+    fn delete(&self, name: &str) -> Result<bool>;
+}
+
+/// The macOS Keychain, Windows Credential Manager or Secret Service on Linux.
+pub struct KeychainStore;
+
+fn keychain_entry(name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name)
+        .with_context(|| format!("failed to open keychain entry {}", name))
+}
+
+impl SecretStore for KeychainStore {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        match keychain_entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(anyhow!("failed to read {} from the keychain: {}", name, e)),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        keychain_entry(name)?
+            .set_password(value)
+            .map_err(|e| anyhow!("failed to store {} in the keychain: {}", name, e))
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        match keychain_entry(name)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(anyhow!(
+                "failed to delete {} from the keychain: {}",
+                name,
+                e
+            )),
+        }
+    }
+}
+
+/// Secrets held in memory, for tests and machines without a keychain.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl SecretStore for MemoryStore {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.secrets.lock().unwrap().get(name).cloned())
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        Ok(self.secrets.lock().unwrap().remove(name).is_some())
+    }
+}
+
+/// Names are lowercase letters, digits, `-`, `_` and `.`, like `s3-secret-access-key`.
+pub fn validate_secret_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_' || c == '.'
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "invalid secret name '{}', use lowercase letters, digits, '-', '_' and '.'",
+            name
+        ))
+    }
+}
+
+/// A config value, read from the store when it is a `keychain:<name>` reference.
+pub fn resolve_secret(value: &str, store: &dyn SecretStore) -> Result<String> {
+    let Some(name) = value.strip_prefix(KEYCHAIN_REFERENCE_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    validate_secret_name(name)?;
+    store.get(name)?.ok_or_else(|| {
+        anyhow!(
+            "secret {} is not in the keychain, set it with `screenpipe secrets set {}`",
+            name,
+            name
+        )
+    })
+}
+
+/// Fills the credentials not given on the command line or in the environment with the ones
+/// stored under their [`KNOWN_SECRETS`] name. A keychain that can't be read, as on a Linux
+/// box without a Secret Service, leaves them unset.
+pub fn apply_stored_secrets(cli: &mut Cli, store: &dyn SecretStore) {
+    for (name, value) in [
+        (API_KEY, &mut cli.api_key),
+        (LLM_API_KEY, &mut cli.llm_api_key),
+        (EMBEDDING_API_KEY, &mut cli.embedding_api_key),
+        (DEEPGRAM_API_KEY, &mut cli.deepgram_api_key),
+    ] {
+        if value.is_some() {
+            continue;
+        }
+        match store.get(name) {
+            Ok(Some(secret)) => {
+                debug!("using {} from the keychain", name);
+                *value = Some(secret);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("{}", e),
+        }
+    }
+}
+
+/// Reads a secret's value from stdin, so it stays out of the shell history.
+fn read_secret_value(name: &str) -> Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("value of {} (input is shown, end with enter):", name);
+    }
+    let mut value = String::new();
+    stdin.lock().read_line(&mut value)?;
+    let value = value.trim_end_matches(['\r', '\n']).to_string();
+    if value.is_empty() {
+        return Err(anyhow!("the value of {} is empty", name));
+    }
+    Ok(value)
+}
+
+pub fn handle_secrets_command(command: &SecretsCommand, store: &dyn SecretStore) -> Result<()> {
+    match command {
+        SecretsCommand::Set { name } => {
+            validate_secret_name(name)?;
+            let value = read_secret_value(name)?;
+            store.set(name, &value)?;
+            println!("stored {} in the keychain", name);
+        }
+        SecretsCommand::Delete { name } => {
+            validate_secret_name(name)?;
+            if store.delete(name)? {
+                println!("deleted {} from the keychain", name);
+            } else {
+                println!("{} is not in the keychain", name);
+            }
+        }
+        SecretsCommand::List { output } => {
+            let secrets = KNOWN_SECRETS
+                .iter()
+                .map(|(name, description)| Ok((*name, *description, store.get(name)?.is_some())))
+                .collect::<Result<Vec<_>>>()?;
+            match output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json!({
+                        "data": secrets
+                            .iter()
+                            .map(|(name, description, stored)| json!({
+                                "name": name,
+                                "description": description,
+                                "stored": stored,
+                            }))
+                            .collect::<Vec<_>>(),
+                        "success": true
+                    }))?
+                ),
+                OutputFormat::Text => {
+                    for (name, description, stored) in secrets {
+                        println!(
+                            "{:<20} {:<10} {}",
+                            name,
+                            if stored { "stored" } else { "-" },
+                            description
+                        );
+                    }
+                    println!(
+                        "\nother secrets are read where a config has \"{}<name>\"",
+                        KEYCHAIN_REFERENCE_PREFIX
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
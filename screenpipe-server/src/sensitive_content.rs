@@ -0,0 +1,550 @@
+use crate::import::ocr_frame;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use image::imageops::{self, FilterType};
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_core::Language;
+use screenpipe_events::send_event;
+use screenpipe_vision::capture_scope::Bounds;
+use screenpipe_vision::core::{defer_ocr, skip_ocr};
+use screenpipe_vision::monitor::get_monitor_by_id;
+use screenpipe_vision::{CaptureResult, OcrEngine};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Read from the data directory at startup and whenever it changes.
+pub const SENSITIVE_CONTENT_CONFIG_FILE: &str = "sensitive_content.json";
+
+/// Pixels around a detected line also blurred, OCR boxes fit the glyphs tightly.
+const REDACTION_MARGIN: u32 = 4;
+
+/// How much a blurred region is scaled down.
+const BLUR_FACTOR: u32 = 16;
+
+/// What the recorder does with a frame whose OCR text shows sensitive content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveContentMode {
+    /// The detectors don't run.
+    #[default]
+    Off,
+    /// Blur where the content is in the frame and replace its text in the index.
+    Redact,
+    /// Neither encode nor index the frame.
+    DropFrame,
+    /// Keep the frame as it is, only log the detections.
+    LogOnly,
+}
+
+/// A kind of sensitive content the detectors look for in OCR text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveKind {
+    Email,
+    /// Card numbers passing the Luhn check.
+    CreditCard,
+    /// Keys of well known APIs, recognized by their prefix.
+    ApiKey,
+    /// A filled password field, read as a run of bullets.
+    PasswordField,
+}
+
+pub const ALL_SENSITIVE_KINDS: [SensitiveKind; 4] = [
+    SensitiveKind::Email,
+    SensitiveKind::CreditCard,
+    SensitiveKind::ApiKey,
+    SensitiveKind::PasswordField,
+];
+
+impl SensitiveKind {
+    /// What the content is replaced with in the index.
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            SensitiveKind::Email => "[EMAIL]",
+            SensitiveKind::CreditCard => "[CREDIT_CARD]",
+            SensitiveKind::ApiKey => "[API_KEY]",
+            SensitiveKind::PasswordField => "[PASSWORD]",
+        }
+    }
+
+    fn pattern(self) -> &'static Regex {
+        match self {
+            SensitiveKind::Email => &EMAIL,
+            SensitiveKind::CreditCard => &CREDIT_CARD,
+            SensitiveKind::ApiKey => &API_KEY,
+            SensitiveKind::PasswordField => &PASSWORD_FIELD,
+        }
+    }
+}
+
+static EMAIL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b").unwrap());
+static CREDIT_CARD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").unwrap());
+static API_KEY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\b(?:sk-(?:proj-|ant-)?[A-Za-z0-9_-]{20,}",
+        r"|AKIA[0-9A-Z]{16}",
+        r"|gh[pousr]_[A-Za-z0-9]{36}",
+        r"|github_pat_[A-Za-z0-9_]{22,}",
+        r"|xox[abprs]-[A-Za-z0-9-]{10,}",
+        r"|AIza[0-9A-Za-z_-]{35})"
+    ))
+    .unwrap()
+});
+static PASSWORD_FIELD: Lazy<Regex> = Lazy::new(|| Regex::new(r"[•●∙⬤]{4,}").unwrap());
+
+/// `sensitive_content.json`, e.g. `{"mode": "redact", "detectors": ["email", "api_key"]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensitiveContentConfig {
+    #[serde(default)]
+    pub mode: SensitiveContentMode,
+    /// The kinds looked for, every kind when not set.
+    #[serde(default)]
+    pub detectors: Option<Vec<SensitiveKind>>,
+}
+
+impl SensitiveContentConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    /// The config at `path`, one with the detectors off when there's no file.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path).with_context(|| {
+            format!("failed to read sensitive content config {}", path.display())
+        })?;
+        Self::parse(&content)
+    }
+
+    pub fn kinds(&self) -> &[SensitiveKind] {
+        self.detectors.as_deref().unwrap_or(&ALL_SENSITIVE_KINDS)
+    }
+
+    /// Makes the recorders screen their frames with this config from their next frame on.
+    pub fn apply(&self) {
+        *SENSITIVE_CONTENT_CONFIG.write().unwrap() = self.clone();
+    }
+}
+
+static SENSITIVE_CONTENT_CONFIG: Lazy<RwLock<SensitiveContentConfig>> = Lazy::new(Default::default);
+
+pub fn sensitive_content_config() -> SensitiveContentConfig {
+    SENSITIVE_CONTENT_CONFIG.read().unwrap().clone()
+}
+
+/// A rectangle of an image, in its pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Sensitive content found in a window of a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub kind: SensitiveKind,
+    /// Index of the window in the frame's `window_ocr_results`.
+    pub window: usize,
+    /// Where the content is in the window's image, the whole window when the OCR engine
+    /// doesn't report where its text is.
+    pub region: Option<PixelRegion>,
+}
+
+/// A detection as logged and sent as a `sensitive_content` event, without the content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionEvent {
+    pub monitor_id: u32,
+    pub timestamp: DateTime<Utc>,
+    pub kind: SensitiveKind,
+    pub app_name: String,
+    pub window_name: String,
+    pub mode: SensitiveContentMode,
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let digits: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => *digit,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// Where `text` shows content of `kind`, as byte ranges.
+fn find_kind(text: &str, kind: SensitiveKind) -> Vec<std::ops::Range<usize>> {
+    kind.pattern()
+        .find_iter(text)
+        .filter(|found| kind != SensitiveKind::CreditCard || luhn_valid(found.as_str()))
+        .map(|found| found.range())
+        .collect()
+}
+
+/// The kinds of sensitive content `text` shows, in the order of `kinds`.
+pub fn detect_in_text(text: &str, kinds: &[SensitiveKind]) -> Vec<SensitiveKind> {
+    kinds
+        .iter()
+        .copied()
+        .filter(|kind| !find_kind(text, *kind).is_empty())
+        .collect()
+}
+
+/// `text` with the content of `kinds` replaced by their placeholders.
+pub fn redact_text(text: &str, kinds: &[SensitiveKind]) -> String {
+    let mut text = text.to_string();
+    for kind in kinds {
+        // replaced from the end, the ranges before stay valid
+        for range in find_kind(&text, *kind).into_iter().rev() {
+            text.replace_range(range, kind.placeholder());
+        }
+    }
+    text
+}
+
+/// Where an OCR block is in an image of `width` by `height`. Apple's Vision reports boxes as
+/// fractions of the image from its bottom left corner, the other engines in pixels from its
+/// top left one, when they report them at all.
+pub fn block_region(
+    block: &std::collections::HashMap<String, String>,
+    width: u32,
+    height: u32,
+) -> Option<PixelRegion> {
+    let value = |key: &str| block.get(key)?.parse::<f64>().ok();
+    let (left, top, block_width, block_height) = (
+        value("left")?,
+        value("top")?,
+        value("width")?,
+        value("height")?,
+    );
+    let normalized = [left, top, block_width, block_height]
+        .iter()
+        .all(|value| (0.0..=1.0).contains(value));
+    let (left, top, right, bottom) = if normalized {
+        (
+            left * width as f64,
+            (1.0 - top - block_height) * height as f64,
+            (left + block_width) * width as f64,
+            (1.0 - top) * height as f64,
+        )
+    } else {
+        (left, top, left + block_width, top + block_height)
+    };
+    let clamp = |value: f64, limit: u32| value.clamp(0.0, limit as f64) as u32;
+    let x = clamp(left.floor() - REDACTION_MARGIN as f64, width);
+    let y = clamp(top.floor() - REDACTION_MARGIN as f64, height);
+    let region = PixelRegion {
+        x,
+        y,
+        width: clamp(right.ceil() + REDACTION_MARGIN as f64, width).saturating_sub(x),
+        height: clamp(bottom.ceil() + REDACTION_MARGIN as f64, height).saturating_sub(y),
+    };
+    (region.width > 0 && region.height > 0).then_some(region)
+}
+
+/// Runs the detectors of `kinds` on the OCR text of every window of `frame`. Content a
+/// window's text shows but none of its blocks does, split over lines, is reported for the
+/// whole window.
+pub fn detect_sensitive_content(frame: &CaptureResult, kinds: &[SensitiveKind]) -> Vec<Detection> {
+    let mut detections = Vec::new();
+    for (index, window) in frame.window_ocr_results.iter().enumerate() {
+        let mut found_in_blocks = Vec::new();
+        for block in &window.text_json {
+            let Some(text) = block.get("text") else {
+                continue;
+            };
+            for kind in detect_in_text(text, kinds) {
+                found_in_blocks.push(kind);
+                detections.push(Detection {
+                    kind,
+                    window: index,
+                    region: block_region(block, window.image.width(), window.image.height()),
+                });
+            }
+        }
+        for kind in detect_in_text(&window.text, kinds) {
+            if !found_in_blocks.contains(&kind) {
+                detections.push(Detection {
+                    kind,
+                    window: index,
+                    region: None,
+                });
+            }
+        }
+    }
+    detections
+}
+
+fn blur_region(image: &mut DynamicImage, region: PixelRegion) {
+    let region = PixelRegion {
+        width: region.width.min(image.width().saturating_sub(region.x)),
+        height: region.height.min(image.height().saturating_sub(region.y)),
+        ..region
+    };
+    if region.width == 0 || region.height == 0 {
+        return;
+    }
+    // scaled down and back up, cheap even over a whole frame and no glyph can be made out
+    let blurred = image
+        .crop_imm(region.x, region.y, region.width, region.height)
+        .resize_exact(
+            (region.width / BLUR_FACTOR).max(1),
+            (region.height / BLUR_FACTOR).max(1),
+            FilterType::Triangle,
+        )
+        .resize_exact(region.width, region.height, FilterType::Triangle);
+    imageops::replace(image, &blurred, region.x as i64, region.y as i64);
+}
+
+/// Maps `region` of a window's image at `window` on the desktop to the image of the monitor
+/// at `monitor`, whose pixels may be at another scale.
+fn region_on_monitor(
+    region: PixelRegion,
+    window_image: (u32, u32),
+    window: Bounds,
+    monitor_image: (u32, u32),
+    monitor: Bounds,
+) -> Option<PixelRegion> {
+    if window_image.0 == 0 || window_image.1 == 0 || monitor.width == 0 || monitor.height == 0 {
+        return None;
+    }
+    let to_desktop_x =
+        |x: u32| window.x as f64 + x as f64 * window.width as f64 / window_image.0 as f64;
+    let to_desktop_y =
+        |y: u32| window.y as f64 + y as f64 * window.height as f64 / window_image.1 as f64;
+    let to_monitor_x = |x: f64| {
+        ((x - monitor.x as f64) * monitor_image.0 as f64 / monitor.width as f64)
+            .clamp(0.0, monitor_image.0 as f64)
+    };
+    let to_monitor_y = |y: f64| {
+        ((y - monitor.y as f64) * monitor_image.1 as f64 / monitor.height as f64)
+            .clamp(0.0, monitor_image.1 as f64)
+    };
+
+    let left = to_monitor_x(to_desktop_x(region.x)).floor() as u32;
+    let top = to_monitor_y(to_desktop_y(region.y)).floor() as u32;
+    let right = to_monitor_x(to_desktop_x(region.x + region.width)).ceil() as u32;
+    let bottom = to_monitor_y(to_desktop_y(region.y + region.height)).ceil() as u32;
+    (right > left && bottom > top).then_some(PixelRegion {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+/// Blurs the detections out of the frame's image and its windows' images, and replaces their
+/// content in the windows' text. Where a window is on `monitor` isn't known without its
+/// bounds, the whole frame is blurred then.
+pub fn redact_detections(
+    frame: &mut CaptureResult,
+    detections: &[Detection],
+    monitor: Option<Bounds>,
+) {
+    let monitor_image = (frame.image.width(), frame.image.height());
+    let whole_frame = PixelRegion {
+        x: 0,
+        y: 0,
+        width: monitor_image.0,
+        height: monitor_image.1,
+    };
+    for detection in detections {
+        let Some(window) = frame.window_ocr_results.get_mut(detection.window) else {
+            continue;
+        };
+        let window_image = (window.image.width(), window.image.height());
+        let region = detection.region.unwrap_or(PixelRegion {
+            x: 0,
+            y: 0,
+            width: window_image.0,
+            height: window_image.1,
+        });
+        blur_region(&mut window.image, region);
+
+        let on_monitor = match (window.bounds, monitor) {
+            (Some(bounds), Some(monitor)) => {
+                region_on_monitor(region, window_image, bounds, monitor_image, monitor)
+            }
+            _ => Some(whole_frame),
+        };
+        if let Some(on_monitor) = on_monitor {
+            blur_region(&mut frame.image, on_monitor);
+        }
+    }
+
+    let kinds: Vec<SensitiveKind> = detections.iter().map(|detection| detection.kind).collect();
+    for window in &mut frame.window_ocr_results {
+        window.text = redact_text(&window.text, &kinds);
+        for block in &mut window.text_json {
+            if let Some(text) = block.get_mut("text") {
+                *text = redact_text(text, &kinds);
+            }
+        }
+    }
+}
+
+/// Screens a frame of `monitor_id` for sensitive content before it's encoded and indexed,
+/// following the config in effect. Returns whether the frame is kept.
+///
+/// The text of a frame captured while OCR is deferred is read with `ocr_engine` for the
+/// detectors, then left out again for the frame to be read from the video later. While OCR is
+/// skipped to spare resources there's nothing to screen with, so the frame is taken as
+/// showing sensitive content: blurred whole when redacting, dropped when dropping frames.
+pub async fn screen_frame(
+    monitor_id: u32,
+    frame: &mut CaptureResult,
+    ocr_engine: &OcrEngine,
+    languages: &[Language],
+) -> bool {
+    let config = sensitive_content_config();
+    if config.mode == SensitiveContentMode::Off {
+        return true;
+    }
+    let unread = (skip_ocr() || defer_ocr())
+        && frame
+            .window_ocr_results
+            .iter()
+            .all(|window| window.text.is_empty());
+    if !unread {
+        return screen_text(monitor_id, frame, &config).await;
+    }
+    if skip_ocr() {
+        return withhold_unread(monitor_id, frame, config.mode);
+    }
+    if let Err(e) = read_for_screening(frame, ocr_engine, languages).await {
+        warn!(
+            "failed to read frame {} for screening: {}",
+            frame.frame_number, e
+        );
+        return withhold_unread(monitor_id, frame, config.mode);
+    }
+    let kept = screen_text(monitor_id, frame, &config).await;
+    for window in &mut frame.window_ocr_results {
+        window.text.clear();
+        window.text_json.clear();
+    }
+    kept
+}
+
+/// Reads the text of the frame's windows the way capture would have.
+async fn read_for_screening(
+    frame: &mut CaptureResult,
+    ocr_engine: &OcrEngine,
+    languages: &[Language],
+) -> Result<()> {
+    for window in &mut frame.window_ocr_results {
+        if let Some(ocr) = ocr_frame(
+            window.image.clone(),
+            &window.window_name,
+            frame.frame_number,
+            ocr_engine,
+            languages,
+        )
+        .await?
+        {
+            window.text = ocr.text;
+            window.text_json = ocr.text_json;
+        }
+    }
+    Ok(())
+}
+
+/// What becomes of a frame that couldn't be screened under `mode`, returns whether it's kept.
+fn withhold_unread(monitor_id: u32, frame: &mut CaptureResult, mode: SensitiveContentMode) -> bool {
+    match mode {
+        SensitiveContentMode::Redact => {
+            let whole = |image: &DynamicImage| PixelRegion {
+                x: 0,
+                y: 0,
+                width: image.width(),
+                height: image.height(),
+            };
+            let region = whole(&frame.image);
+            blur_region(&mut frame.image, region);
+            for window in &mut frame.window_ocr_results {
+                let region = whole(&window.image);
+                blur_region(&mut window.image, region);
+            }
+            debug!(
+                "blurred unscreened frame {} of monitor {}",
+                frame.frame_number, monitor_id
+            );
+            true
+        }
+        SensitiveContentMode::DropFrame => {
+            debug!(
+                "dropped unscreened frame {} of monitor {}",
+                frame.frame_number, monitor_id
+            );
+            false
+        }
+        SensitiveContentMode::LogOnly | SensitiveContentMode::Off => true,
+    }
+}
+
+async fn screen_text(
+    monitor_id: u32,
+    frame: &mut CaptureResult,
+    config: &SensitiveContentConfig,
+) -> bool {
+    let detections = detect_sensitive_content(frame, config.kinds());
+    if detections.is_empty() {
+        return true;
+    }
+
+    for detection in &detections {
+        let Some(window) = frame.window_ocr_results.get(detection.window) else {
+            continue;
+        };
+        warn!(
+            "{:?} shown in {} ({}) on monitor {}, {:?}",
+            detection.kind, window.app_name, window.window_name, monitor_id, config.mode
+        );
+        let _ = send_event(
+            "sensitive_content",
+            DetectionEvent {
+                monitor_id,
+                timestamp: Utc::now(),
+                kind: detection.kind,
+                app_name: window.app_name.clone(),
+                window_name: window.window_name.clone(),
+                mode: config.mode,
+            },
+        );
+    }
+
+    match config.mode {
+        SensitiveContentMode::Redact => {
+            let monitor = get_monitor_by_id(monitor_id)
+                .await
+                .map(|monitor| monitor.bounds());
+            redact_detections(frame, &detections, monitor);
+            true
+        }
+        SensitiveContentMode::DropFrame => {
+            debug!(
+                "dropped frame {} of monitor {} for sensitive content",
+                frame.frame_number, monitor_id
+            );
+            false
+        }
+        SensitiveContentMode::LogOnly | SensitiveContentMode::Off => true,
+    }
+}
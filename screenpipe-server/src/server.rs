@@ -1,12 +1,16 @@
 use axum::{
-    body::Body,
+    body::{Body, Bytes},
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Json, Path, Query, State,
+        ConnectInfo, DefaultBodyLimit, Json, Path, Query, State,
     },
-    http::StatusCode,
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+        HeaderMap, Request, StatusCode, Uri,
+    },
+    middleware::{self, Next},
     response::{IntoResponse, Json as JsonResponse, Response},
-    routing::get,
+    routing::{get, post, put},
     serve, Router,
 };
 use oasgen::{oasgen, OaSchema, Server};
@@ -15,10 +19,12 @@ use screenpipe_core::Desktop;
 
 use chrono::TimeZone;
 use screenpipe_db::{
-    ContentType, DatabaseManager, FrameData, Order, SearchMatch, SearchResult, Speaker,
-    TagContentType,
+    AccessLogEntry, Bookmark, ClipboardEntry, ContentType, DatabaseManager, FrameData, Job,
+    JobStatusCount, NewAccessLogEntry, Order, RecordingSession, Redaction, SearchMatch,
+    SearchResult, Speaker, TagContentType, UiEvent,
 };
 
+use base64::{engine::general_purpose, Engine as _};
 use tokio_util::io::ReaderStream;
 
 use tokio::fs::File;
@@ -31,7 +37,45 @@ use image::ImageFormat::{self};
 use screenpipe_events::{send_event, subscribe_to_all_events, Event as ScreenpipeEvent};
 
 use crate::{
+    activity_stats::{activity_stats, ActivityStats, StatsOptions, TitleMode},
+    animated_clip::{
+        check_animated_clip_length, encode_animated_clip, ClipFormat, DEFAULT_ANIMATED_CLIP_WIDTH,
+    },
+    audit::{
+        classify_access, client_fingerprint, requested_time_range, ANONYMOUS_CLIENT, LOCAL_CLIENT,
+    },
+    categorization::{categorize, category_color, recategorize_frames, Recategorization},
+    dedup::{DedupFramesPayload, DEDUP_FRAMES_JOB},
     embedding::embedding_endpoint::create_embeddings,
+    encoder_health::{encoder_health, EncoderHealth, EncoderState},
+    excise::{excise_last_minutes, MAX_EXCISE_MINUTES},
+    excluded_regions::{ExcludedRegionsConfig, EXCLUDED_REGIONS_CONFIG_FILE},
+    frames::{encode_png, frame_at, DEFAULT_MAX_FRAME_GAP_SECS},
+    hub::{
+        body_digest, excise_pushed_range, hub, index_pushed_frames, store_segment,
+        verify_hub_request, HubConfig, HubExcisions, HubPush, HubPushResponse,
+        HUB_CONTENT_SHA256_HEADER, HUB_MAX_PUSH_BYTES, HUB_NONCES,
+    },
+    jobs::enqueue_job,
+    live_events::{subscribe_to_live_events, LiveEventFilter},
+    metrics::{prometheus_metrics, METRICS_CONTENT_TYPE},
+    mosaic::{mosaic_path, DayMosaicPayload, RENDER_DAY_MOSAIC_JOB},
+    permissions::{check_permissions, Capability, PermissionCheck},
+    presentation::{
+        list_presentations, start_presentation, stop_presentation, ExportPresentationPayload,
+        Presentation, EXPORT_PRESENTATION_JOB,
+    },
+    pyramid::{pyramid_keyframe_path, PYRAMID_LEVELS},
+    search_query::search_filters_or_text,
+    sessions::{session_details, SessionDetails, SessionTracker},
+    storage::{local_hostname, Storage},
+    streaming::subscribe_live_frames,
+    summarize::{llm_backend, summarize_range, Summary},
+    thumbnails::{read_thumbnail_strip, thumbnail_sprite_path, ThumbnailStrip},
+    time_tracking::{
+        render_entries, time_entries, TimeTrackingConfig, TimeTrackingFormat,
+        TIME_TRACKING_CONFIG_FILE,
+    },
     video::{finish_ffmpeg_process, start_ffmpeg_process, write_frame_to_ffmpeg, MAX_FPS},
     video_cache::{AudioEntry, DeviceFrame, FrameCache, FrameMetadata, TimeSeriesFrame},
     video_utils::{
@@ -40,7 +84,7 @@ use crate::{
     },
     PipeManager,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use screenpipe_audio::{
     audio_manager::AudioManager,
     core::device::{
@@ -49,6 +93,9 @@ use screenpipe_audio::{
 };
 use tracing::{debug, error, info};
 
+use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
+use screenpipe_vision::core::capture_frame_with_ocr;
+use screenpipe_vision::excluded_regions::{black_out, excluded_regions_on};
 use screenpipe_vision::monitor::{get_monitor_by_id, list_monitors};
 use screenpipe_vision::OcrEngine;
 use serde::{Deserialize, Deserializer, Serialize};
@@ -96,6 +143,10 @@ pub struct AppState {
     pub frame_cache: Option<Arc<FrameCache>>,
     pub frame_image_cache: Option<Arc<Mutex<FrameImageCache>>>,
     pub element_cache: Arc<Mutex<Option<(Vec<UIElement>, Instant, String)>>>,
+    pub ocr_engine: Arc<OcrEngine>,
+    pub api_key: Option<String>,
+    pub access_log_enabled: bool,
+    pub session_tracker: Option<Arc<SessionTracker>>,
 }
 
 // Update the SearchQuery struct
@@ -131,6 +182,12 @@ pub(crate) struct SearchQuery {
     focused: Option<bool>,
     #[serde(default)]
     browser_url: Option<String>,
+    /// Only OCR text in this language, as an ISO 639-1 code such as `ja`
+    #[serde(default)]
+    language: Option<String>,
+    /// Only frames given this category by the rules of `categories.json`
+    #[serde(default)]
+    category: Option<String>,
 }
 
 #[derive(OaSchema, Deserialize)]
@@ -195,6 +252,56 @@ pub enum ContentItem {
     UI(UiContent),
 }
 
+impl From<&SearchResult> for ContentItem {
+    fn from(result: &SearchResult) -> Self {
+        match result {
+            SearchResult::OCR(ocr) => ContentItem::OCR(OCRContent {
+                frame_id: ocr.frame_id,
+                text: ocr.ocr_text.clone(),
+                timestamp: ocr.timestamp,
+                file_path: ocr.file_path.clone(),
+                offset_index: ocr.offset_index,
+                app_name: ocr.app_name.clone(),
+                window_name: ocr.window_name.clone(),
+                tags: ocr.tags.clone(),
+                frame: None,
+                frame_name: Some(ocr.frame_name.clone()),
+                browser_url: ocr.browser_url.clone(),
+                focused: ocr.focused,
+                device_name: ocr.device_name.clone(),
+                language: ocr.language.clone(),
+                host_name: ocr.host_name.clone(),
+                category: ocr.category.clone(),
+            }),
+            SearchResult::Audio(audio) => ContentItem::Audio(AudioContent {
+                chunk_id: audio.audio_chunk_id,
+                transcription: audio.transcription.clone(),
+                timestamp: audio.timestamp,
+                file_path: audio.file_path.clone(),
+                offset_index: audio.offset_index,
+                tags: audio.tags.clone(),
+                device_name: audio.device_name.clone(),
+                device_type: audio.device_type.clone().into(),
+                speaker: audio.speaker.clone(),
+                start_time: audio.start_time,
+                end_time: audio.end_time,
+            }),
+            SearchResult::UI(ui) => ContentItem::UI(UiContent {
+                id: ui.id,
+                text: ui.text.clone(),
+                timestamp: ui.timestamp,
+                app_name: ui.app_name.clone(),
+                window_name: ui.window_name.clone(),
+                initial_traversal_at: ui.initial_traversal_at,
+                file_path: ui.file_path.clone(),
+                offset_index: ui.offset_index,
+                frame_name: ui.frame_name.clone(),
+                browser_url: ui.browser_url.clone(),
+            }),
+        }
+    }
+}
+
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
 pub struct OCRContent {
     pub frame_id: i64,
@@ -210,6 +317,10 @@ pub struct OCRContent {
     pub browser_url: Option<String>,
     pub focused: Option<bool>,
     pub device_name: String,
+    pub language: Option<String>,
+    /// Machine the frame was recorded on when pushed to this hub.
+    pub host_name: Option<String>,
+    pub category: Option<String>,
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -291,6 +402,12 @@ pub struct HealthCheckResponse {
     pub frame_status: String,
     pub audio_status: String,
     pub ui_status: String,
+    /// `ok`, `degraded` when an ffmpeg encoder warned or `unhealthy` when one failed.
+    pub encoder_status: String,
+    pub encoders: Vec<EncoderHealth>,
+    /// What the OS allows screenpipe to capture, screen recording is `denied` in
+    /// `frame_status` too.
+    pub permissions: Vec<PermissionCheck>,
     pub message: String,
     pub verbose_instructions: Option<String>,
     pub device_status_details: Option<String>,
@@ -308,6 +425,22 @@ pub(crate) async fn search(
     Query(query): Query<SearchQuery>,
     State(state): State<Arc<AppState>>,
 ) -> Result<JsonResponse<SearchResponse>, (StatusCode, JsonResponse<serde_json::Value>)> {
+    let mut query = query;
+    // filters typed into the query, like app:chrome or after:2024-05-01, win over the params
+    if let Some(q) = &query.q {
+        let filters = search_filters_or_text(q);
+        query.q = Some(filters.text);
+        query.app_name = filters.app_name.or(query.app_name);
+        query.window_name = filters.window_name.or(query.window_name);
+        query.browser_url = filters.browser_url.or(query.browser_url);
+        query.content_type = filters.content_type.unwrap_or(query.content_type);
+        query.start_time = filters.start_time.or(query.start_time);
+        query.end_time = filters.end_time.or(query.end_time);
+        query.language = filters.language.or(query.language);
+        query.focused = filters.focused.or(query.focused);
+        query.category = filters.category.or(query.category);
+    }
+
     info!(
         "received search request: query='{}', content_type={:?}, limit={}, offset={}, start_time={:?}, end_time={:?}, app_name={:?}, window_name={:?}, min_length={:?}, max_length={:?}, speaker_ids={:?}, frame_name={:?}, browser_url={:?}, focused={:?}",
         query.q.as_deref().unwrap_or(""),
@@ -346,6 +479,8 @@ pub(crate) async fn search(
             query.frame_name.as_deref(),
             query.browser_url.as_deref(),
             query.focused,
+            query.language.as_deref(),
+            query.category.as_deref(),
         ),
         state.db.count_search_results(
             query_str,
@@ -360,6 +495,8 @@ pub(crate) async fn search(
             query.frame_name.as_deref(),
             query.browser_url.as_deref(),
             query.focused,
+            query.language.as_deref(),
+            query.category.as_deref(),
         ),
     )
     .await
@@ -371,51 +508,7 @@ pub(crate) async fn search(
         )
     })?;
 
-    let mut content_items: Vec<ContentItem> = results
-        .iter()
-        .map(|result| match result {
-            SearchResult::OCR(ocr) => ContentItem::OCR(OCRContent {
-                frame_id: ocr.frame_id,
-                text: ocr.ocr_text.clone(),
-                timestamp: ocr.timestamp,
-                file_path: ocr.file_path.clone(),
-                offset_index: ocr.offset_index,
-                app_name: ocr.app_name.clone(),
-                window_name: ocr.window_name.clone(),
-                tags: ocr.tags.clone(),
-                frame: None,
-                frame_name: Some(ocr.frame_name.clone()),
-                browser_url: ocr.browser_url.clone(),
-                focused: ocr.focused,
-                device_name: ocr.device_name.clone(),
-            }),
-            SearchResult::Audio(audio) => ContentItem::Audio(AudioContent {
-                chunk_id: audio.audio_chunk_id,
-                transcription: audio.transcription.clone(),
-                timestamp: audio.timestamp,
-                file_path: audio.file_path.clone(),
-                offset_index: audio.offset_index,
-                tags: audio.tags.clone(),
-                device_name: audio.device_name.clone(),
-                device_type: audio.device_type.clone().into(),
-                speaker: audio.speaker.clone(),
-                start_time: audio.start_time,
-                end_time: audio.end_time,
-            }),
-            SearchResult::UI(ui) => ContentItem::UI(UiContent {
-                id: ui.id,
-                text: ui.text.clone(),
-                timestamp: ui.timestamp,
-                app_name: ui.app_name.clone(),
-                window_name: ui.window_name.clone(),
-                initial_traversal_at: ui.initial_traversal_at,
-                file_path: ui.file_path.clone(),
-                offset_index: ui.offset_index,
-                frame_name: ui.frame_name.clone(),
-                browser_url: ui.browser_url.clone(),
-            }),
-        })
-        .collect();
+    let mut content_items: Vec<ContentItem> = results.iter().map(ContentItem::from).collect();
 
     if query.include_frames {
         debug!("extracting frames for ocr content");
@@ -508,7 +601,7 @@ pub async fn api_list_monitors(
         match get_monitor_by_id(monitor_id).await {
             Some(monitor) => MonitorInfo {
                 id: monitor.id(),
-                name: monitor.name().to_string(),
+                name: monitor.name(),
                 width: monitor.width(),
                 height: monitor.height(),
                 is_default: monitor.is_primary(),
@@ -535,26 +628,841 @@ pub async fn api_list_monitors(
 }
 
 #[oasgen]
-pub(crate) async fn add_tags(
+pub(crate) async fn add_tags(
+    State(state): State<Arc<AppState>>,
+    Path((content_type, id)): Path<(String, i64)>,
+    JsonResponse(payload): JsonResponse<AddTagsRequest>,
+) -> Result<Json<AddTagsResponse>, (StatusCode, JsonResponse<Value>)> {
+    let content_type = match content_type.as_str() {
+        "vision" => TagContentType::Vision,
+        "audio" => TagContentType::Audio,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "Invalid content type"})),
+            ))
+        }
+    };
+
+    match state.db.add_tags(id, content_type, payload.tags).await {
+        Ok(_) => Ok(JsonResponse(AddTagsResponse { success: true })),
+        Err(e) => {
+            error!("Failed to add tags: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+pub(crate) async fn remove_tags(
+    State(state): State<Arc<AppState>>,
+    Path((content_type, id)): Path<(String, i64)>,
+    JsonResponse(payload): JsonResponse<RemoveTagsRequest>,
+) -> Result<Json<RemoveTagsResponse>, (StatusCode, JsonResponse<Value>)> {
+    let content_type = match content_type.as_str() {
+        "vision" => TagContentType::Vision,
+        "audio" => TagContentType::Audio,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "Invalid content type"})),
+            ))
+        }
+    };
+
+    match state.db.remove_tags(id, content_type, payload.tags).await {
+        Ok(_) => Ok(JsonResponse(RemoveTagsResponse { success: true })),
+        Err(e) => {
+            error!("Failed to remove tag: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct CreateBookmarkRequest {
+    name: String,
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    device_name: Option<String>,
+    #[serde(default)]
+    frame_id: Option<i64>,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct UpdateBookmarkRequest {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct BookmarksQuery {
+    q: Option<String>,
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    device_name: Option<String>,
+}
+
+#[oasgen]
+pub(crate) async fn create_bookmark(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<CreateBookmarkRequest>,
+) -> Result<JsonResponse<Bookmark>, (StatusCode, JsonResponse<Value>)> {
+    if payload.name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "bookmark name cannot be empty"})),
+        ));
+    }
+
+    match state
+        .db
+        .insert_bookmark(
+            payload.name.trim(),
+            payload.timestamp.unwrap_or_else(Utc::now),
+            payload.device_name.as_deref(),
+            payload.frame_id,
+            payload.note.as_deref(),
+        )
+        .await
+    {
+        Ok(bookmark) => {
+            let _ = send_event("bookmark_created", bookmark.clone());
+            Ok(JsonResponse(bookmark))
+        }
+        Err(e) => {
+            error!("Failed to create bookmark: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+pub(crate) async fn list_bookmarks(
+    Query(query): Query<BookmarksQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<Bookmark>>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .db
+        .list_bookmarks(
+            query.q.as_deref(),
+            query.start_time,
+            query.end_time,
+            query.device_name.as_deref(),
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+    {
+        Ok(bookmarks) => Ok(JsonResponse(bookmarks)),
+        Err(e) => {
+            error!("Failed to list bookmarks: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+pub(crate) async fn update_bookmark(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    JsonResponse(payload): JsonResponse<UpdateBookmarkRequest>,
+) -> Result<JsonResponse<Bookmark>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .db
+        .update_bookmark(id, payload.name.as_deref(), payload.note.as_deref())
+        .await
+    {
+        Ok(bookmark) => Ok(JsonResponse(bookmark)),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "bookmark not found"})),
+        )),
+        Err(e) => {
+            error!("Failed to update bookmark: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+pub(crate) async fn delete_bookmark(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_bookmark(id).await {
+        Ok(_) => Ok(JsonResponse(json!({"success": true}))),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "bookmark not found"})),
+        )),
+        Err(e) => {
+            error!("Failed to delete bookmark: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct SessionsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    /// Only the sessions with this tag, e.g. `meeting`.
+    #[serde(default)]
+    tag: Option<String>,
+}
+
+#[oasgen]
+pub(crate) async fn list_sessions(
+    Query(query): Query<SessionsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<RecordingSession>>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .db
+        .list_recording_sessions(
+            query.start_time,
+            query.end_time,
+            query.tag.as_deref(),
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+    {
+        Ok(sessions) => Ok(JsonResponse(sessions)),
+        Err(e) => {
+            error!("Failed to list recording sessions: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+pub(crate) async fn get_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<SessionDetails>, (StatusCode, JsonResponse<Value>)> {
+    match session_details(&state.db, id, Utc::now()).await {
+        Ok(details) => Ok(JsonResponse(details)),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "session not found"})),
+        )),
+        Err(e) => {
+            error!("Failed to get recording session: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct UpdateSessionRequest {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+#[oasgen]
+pub(crate) async fn update_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    JsonResponse(payload): JsonResponse<UpdateSessionRequest>,
+) -> Result<JsonResponse<RecordingSession>, (StatusCode, JsonResponse<Value>)> {
+    if payload
+        .name
+        .as_deref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "session name is empty"})),
+        ));
+    }
+    match state
+        .db
+        .update_recording_session(
+            id,
+            payload.name.as_deref().map(str::trim),
+            payload.notes.as_deref(),
+        )
+        .await
+    {
+        Ok(session) => Ok(JsonResponse(session)),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "session not found"})),
+        )),
+        Err(e) => {
+            error!("Failed to update recording session: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+fn session_tracker(state: &AppState) -> Result<&SessionTracker, (StatusCode, JsonResponse<Value>)> {
+    state.session_tracker.as_deref().ok_or_else(|| {
+        (
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": "sessions are only tracked while recording the screen"})),
+        )
+    })
+}
+
+/// Ends the current session and starts a new one, named and noted as given.
+#[oasgen]
+pub(crate) async fn start_session_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<UpdateSessionRequest>,
+) -> Result<JsonResponse<RecordingSession>, (StatusCode, JsonResponse<Value>)> {
+    session_tracker(&state)?
+        .start_session(
+            payload
+                .name
+                .as_deref()
+                .map(str::trim)
+                .filter(|name| !name.is_empty()),
+            payload.notes.as_deref(),
+            Utc::now(),
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to start recording session: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Ends the current session, the next captured frame starts another one.
+#[oasgen]
+pub(crate) async fn stop_session_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<RecordingSession>, (StatusCode, JsonResponse<Value>)> {
+    session_tracker(&state)?
+        .stop_session()
+        .await
+        .map(JsonResponse)
+        .ok_or_else(|| {
+            (
+                StatusCode::CONFLICT,
+                JsonResponse(json!({"error": "no session is being recorded"})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct UiEventsQuery {
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    app_name: Option<String>,
+    #[serde(default)]
+    window_name: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    /// Only the events linked to this frame; the other filters are ignored.
+    #[serde(default)]
+    frame_id: Option<i64>,
+}
+
+#[oasgen]
+pub(crate) async fn list_ui_events(
+    Query(query): Query<UiEventsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<UiEvent>>, (StatusCode, JsonResponse<Value>)> {
+    let result = match query.frame_id {
+        Some(frame_id) => state.db.get_ui_events_for_frame(frame_id).await,
+        None => {
+            state
+                .db
+                .search_ui_events(
+                    query.q.as_deref().unwrap_or_default(),
+                    query.app_name.as_deref(),
+                    query.window_name.as_deref(),
+                    query.start_time,
+                    query.end_time,
+                    query.pagination.limit,
+                    query.pagination.offset,
+                )
+                .await
+        }
+    };
+    match result {
+        Ok(events) => Ok(JsonResponse(events)),
+        Err(e) => {
+            error!("Failed to search ui events: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ClipboardQuery {
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+#[oasgen]
+pub(crate) async fn list_clipboard(
+    Query(query): Query<ClipboardQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<ClipboardEntry>>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .db
+        .search_clipboard(
+            query.q.as_deref().unwrap_or_default(),
+            query.start_time,
+            query.end_time,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+    {
+        Ok(entries) => Ok(JsonResponse(entries)),
+        Err(e) => {
+            error!("Failed to search clipboard history: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct AccessLogQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    client: Option<String>,
+}
+
+#[oasgen]
+pub(crate) async fn list_access_log(
+    Query(query): Query<AccessLogQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<AccessLogEntry>>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .db
+        .list_access_log(
+            query.start_time,
+            query.end_time,
+            query.action.as_deref(),
+            query.client.as_deref(),
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+    {
+        Ok(entries) => Ok(JsonResponse(entries)),
+        Err(e) => {
+            error!("Failed to list access log: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ExciseRequest {
+    /// How far back from now to delete.
+    minutes: u32,
+    /// Why, kept with the redaction, e.g. `hotkey`.
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Deletes the last minutes of the recordings: their index rows, OCR text, transcriptions, UI
+/// events and clipboard entries right away, the video trimmed by a background job. Returns the
+/// redaction logged for it.
+#[oasgen]
+pub(crate) async fn excise_recording(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<ExciseRequest>,
+) -> Result<JsonResponse<Redaction>, (StatusCode, JsonResponse<Value>)> {
+    if payload.minutes == 0 || payload.minutes > MAX_EXCISE_MINUTES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!("minutes must be between 1 and {}", MAX_EXCISE_MINUTES)
+            })),
+        ));
+    }
+
+    match excise_last_minutes(
+        &state.db,
+        &Storage::new(state.screenpipe_dir.join("data")),
+        payload.minutes,
+        payload.reason.as_deref(),
+    )
+    .await
+    {
+        Ok(redaction) => Ok(JsonResponse(redaction)),
+        Err(e) => {
+            error!(
+                "Failed to excise the last {} minutes: {}",
+                payload.minutes, e
+            );
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RecategorizeRequest {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+/// Categorizes the frames of a time range again with the current rules of `categories.json`.
+#[oasgen]
+pub(crate) async fn recategorize_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<RecategorizeRequest>,
+) -> Result<JsonResponse<Recategorization>, (StatusCode, JsonResponse<Value>)> {
+    if payload.end_time <= payload.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+
+    match recategorize_frames(&state.db, payload.start_time, payload.end_time).await {
+        Ok(recategorization) => Ok(JsonResponse(recategorization)),
+        Err(e) => {
+            error!("Failed to recategorize frames: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RedactionsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+}
+
+#[oasgen]
+pub(crate) async fn list_redactions(
+    Query(query): Query<RedactionsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<Redaction>>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .db
+        .list_redactions(query.pagination.limit, query.pagination.offset)
+        .await
+    {
+        Ok(redactions) => Ok(JsonResponse(redactions)),
+        Err(e) => {
+            error!("Failed to list redactions: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct JobsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+#[derive(OaSchema, Serialize)]
+pub(crate) struct JobsResponse {
+    counts: Vec<JobStatusCount>,
+    jobs: Vec<Job>,
+}
+
+#[oasgen]
+pub(crate) async fn list_jobs(
+    Query(query): Query<JobsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<JobsResponse>, (StatusCode, JsonResponse<Value>)> {
+    let result = async {
+        let counts = state.db.count_jobs_by_status().await?;
+        let jobs = state
+            .db
+            .list_jobs(
+                query.status.as_deref(),
+                query.pagination.limit,
+                query.pagination.offset,
+            )
+            .await?;
+        Ok::<_, sqlx::Error>(JobsResponse { counts, jobs })
+    }
+    .await;
+
+    match result {
+        Ok(response) => Ok(JsonResponse(response)),
+        Err(e) => {
+            error!("Failed to list jobs: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+fn parse_mosaic_date(date: &str) -> Result<NaiveDate, (StatusCode, JsonResponse<Value>)> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("invalid date {}: {}", date, e)})),
+        )
+    })
+}
+
+#[oasgen]
+pub(crate) async fn get_day_mosaic(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<String>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let date = parse_mosaic_date(&date)?;
+    let path = mosaic_path(&Storage::new(state.screenpipe_dir.join("data")), date);
+    if !path.exists() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("no day mosaic for {}", date)})),
+        ));
+    }
+    serve_file(&path.to_string_lossy()).await
+}
+
+/// Queues a (re)render of the day strip, e.g. for today before the nightly run.
+#[oasgen]
+pub(crate) async fn render_day_mosaic_handler(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<String>,
+) -> Result<JsonResponse<Job>, (StatusCode, JsonResponse<Value>)> {
+    let date = parse_mosaic_date(&date)?;
+    match enqueue_job(
+        &state.db,
+        RENDER_DAY_MOSAIC_JOB,
+        &DayMosaicPayload { date },
+        3,
+    )
+    .await
+    {
+        Ok(job) => Ok(JsonResponse(job)),
+        Err(e) => {
+            error!("Failed to queue day mosaic: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ThumbnailsQuery {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    /// e.g. `monitor_1`, all devices when omitted
+    device_name: Option<String>,
+}
+
+#[derive(OaSchema, Serialize)]
+pub(crate) struct ChunkThumbnails {
+    device_name: String,
+    first_frame_at: DateTime<Utc>,
+    last_frame_at: DateTime<Utc>,
+    /// Where to fetch the sprite the thumbnails are cut from.
+    sprite_url: String,
+    strip: ThumbnailStrip,
+}
+
+/// Thumbnail strips of the chunks recorded between the times, to scrub previews without
+/// decoding the videos. Chunks whose strip isn't built yet are left out.
+#[oasgen]
+pub(crate) async fn list_thumbnails(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ThumbnailsQuery>,
+) -> Result<JsonResponse<Vec<ChunkThumbnails>>, (StatusCode, JsonResponse<Value>)> {
+    let internal_error = |e: String| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e})),
+        )
+    };
+    let devices = match query.device_name {
+        Some(device_name) => vec![device_name],
+        None => state
+            .db
+            .get_recorded_devices(query.start_time, query.end_time)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?,
+    };
+
+    let mut chunks = Vec::new();
+    for device_name in devices {
+        let spans = state
+            .db
+            .get_video_chunk_spans(&device_name, query.start_time, query.end_time)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        for span in spans {
+            let Some(strip) = read_thumbnail_strip(&span.file_path)
+                .await
+                .map_err(|e| internal_error(e.to_string()))?
+            else {
+                continue;
+            };
+            let Some(first) = strip.thumbnails.first() else {
+                continue;
+            };
+            chunks.push(ChunkThumbnails {
+                device_name: device_name.clone(),
+                first_frame_at: span.first_frame_at,
+                last_frame_at: span.last_frame_at,
+                sprite_url: format!("/thumbnails/{}/sprite", first.frame_id),
+                strip,
+            });
+        }
+    }
+    Ok(JsonResponse(chunks))
+}
+
+/// WebP sprite of the thumbnails of the chunk holding the frame.
+#[oasgen]
+pub(crate) async fn get_thumbnail_sprite(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("no thumbnails for frame {}", frame_id)})),
+        )
+    };
+    let (file_path, _) = state
+        .db
+        .get_frame(frame_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?
+        .ok_or_else(not_found)?;
+    let sprite = tokio::fs::read(thumbnail_sprite_path(&file_path))
+        .await
+        .map_err(|_| not_found())?;
+    Response::builder()
+        .header(CONTENT_TYPE, "image/webp")
+        .header("cache-control", "public, max-age=604800")
+        .body(Body::from(sprite))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create response: {}", e)})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct DedupFramesRequest {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+/// Queues the collapse of frames showing a screen recorded earlier, e.g. a lock screen coming
+/// back all day, into the first frame of it.
+#[oasgen]
+pub(crate) async fn dedup_frames_handler(
     State(state): State<Arc<AppState>>,
-    Path((content_type, id)): Path<(String, i64)>,
-    JsonResponse(payload): JsonResponse<AddTagsRequest>,
-) -> Result<Json<AddTagsResponse>, (StatusCode, JsonResponse<Value>)> {
-    let content_type = match content_type.as_str() {
-        "vision" => TagContentType::Vision,
-        "audio" => TagContentType::Audio,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                JsonResponse(json!({"error": "Invalid content type"})),
-            ))
-        }
+    JsonResponse(request): JsonResponse<DedupFramesRequest>,
+) -> Result<JsonResponse<Job>, (StatusCode, JsonResponse<Value>)> {
+    if request.start_time >= request.end_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "start_time must be before end_time"})),
+        ));
+    }
+    let payload = DedupFramesPayload {
+        start_time: request.start_time,
+        end_time: request.end_time,
     };
-
-    match state.db.add_tags(id, content_type, payload.tags).await {
-        Ok(_) => Ok(JsonResponse(AddTagsResponse { success: true })),
+    match enqueue_job(&state.db, DEDUP_FRAMES_JOB, &payload, 3).await {
+        Ok(job) => Ok(JsonResponse(job)),
         Err(e) => {
-            error!("Failed to add tags: {}", e);
+            error!("Failed to queue frame dedup: {}", e);
             Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({"error": e.to_string()})),
@@ -563,33 +1471,186 @@ pub(crate) async fn add_tags(
     }
 }
 
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct StartPresentationRequest {
+    #[serde(default)]
+    name: Option<String>,
+    /// Monitor showing the slides, by default the one focused first.
+    #[serde(default)]
+    monitor_id: Option<u32>,
+}
+
 #[oasgen]
-pub(crate) async fn remove_tags(
+pub(crate) async fn list_presentations_handler(
     State(state): State<Arc<AppState>>,
-    Path((content_type, id)): Path<(String, i64)>,
-    JsonResponse(payload): JsonResponse<RemoveTagsRequest>,
-) -> Result<Json<RemoveTagsResponse>, (StatusCode, JsonResponse<Value>)> {
-    let content_type = match content_type.as_str() {
-        "vision" => TagContentType::Vision,
-        "audio" => TagContentType::Audio,
-        _ => {
+) -> Result<JsonResponse<Vec<Presentation>>, (StatusCode, JsonResponse<Value>)> {
+    list_presentations(&Storage::new(state.screenpipe_dir.join("data")))
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list presentations: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Starts taking a slide at every slide change of the presentation on screen.
+#[oasgen]
+pub(crate) async fn start_presentation_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<StartPresentationRequest>,
+) -> Result<JsonResponse<Presentation>, (StatusCode, JsonResponse<Value>)> {
+    start_presentation(
+        &Storage::new(state.screenpipe_dir.join("data")),
+        payload
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty()),
+        payload.monitor_id,
+    )
+    .map(JsonResponse)
+    .map_err(|e| {
+        (
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })
+}
+
+/// Stops the presentation and queues the export of its PDF and chaptered video.
+#[oasgen]
+pub(crate) async fn stop_presentation_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Presentation>, (StatusCode, JsonResponse<Value>)> {
+    let presentation = match tokio::task::spawn_blocking(stop_presentation).await {
+        Ok(Ok(presentation)) => presentation,
+        Ok(Err(e)) => {
             return Err((
-                StatusCode::BAD_REQUEST,
-                JsonResponse(json!({"error": "Invalid content type"})),
+                StatusCode::CONFLICT,
+                JsonResponse(json!({"error": e.to_string()})),
             ))
         }
-    };
-
-    match state.db.remove_tags(id, content_type, payload.tags).await {
-        Ok(_) => Ok(JsonResponse(RemoveTagsResponse { success: true })),
         Err(e) => {
-            error!("Failed to remove tag: {}", e);
-            Err((
+            return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({"error": e.to_string()})),
             ))
         }
+    };
+
+    let payload = ExportPresentationPayload {
+        dir: presentation.dir.clone().into(),
+    };
+    // the last video chunk is usually still open, the job waits for it
+    if let Err(e) = enqueue_job(&state.db, EXPORT_PRESENTATION_JOB, &payload, 10).await {
+        error!("Failed to queue presentation export: {}", e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        ));
+    }
+    Ok(JsonResponse(presentation))
+}
+
+/// Rejects requests without the configured api key, passed as `Authorization: Bearer <key>`
+/// or as an `api_key` query parameter (for `<img>`/websocket clients that can't set headers).
+/// `/health` stays public so uptime checks keep working, and `/hub/` pushes are signed with
+/// the hub token instead.
+async fn require_api_key(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(expected) = state.api_key.as_deref() else {
+        return next.run(request).await;
+    };
+    if request.uri().path() == "/health" || request.uri().path().starts_with("/hub/") {
+        return next.run(request).await;
+    }
+
+    match provided_api_key(&request) {
+        Some(provided) if constant_time_eq(provided.as_bytes(), expected.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            JsonResponse(json!({"error": "missing or invalid api key"})),
+        )
+            .into_response(),
+    }
+}
+
+fn provided_api_key(request: &Request<Body>) -> Option<&str> {
+    let from_header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let from_query = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("api_key="))
+    });
+    from_header.or(from_query)
+}
+
+/// Stores an access log entry for every request reading recorded data, rejected ones included,
+/// when the access log is enabled. Entries are written after the response, off the request path.
+async fn record_access(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !state.access_log_enabled {
+        return next.run(request).await;
     }
+    let Some(action) = classify_access(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let (start_time, end_time) = requested_time_range(request.uri());
+    let client = match (state.api_key.as_deref(), provided_api_key(&request)) {
+        (None, _) => LOCAL_CLIENT.to_string(),
+        (Some(_), Some(provided)) => client_fingerprint(provided),
+        (Some(_), None) => ANONYMOUS_CLIENT.to_string(),
+    };
+    let remote_addr = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let user_agent = request
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let mut entry = NewAccessLogEntry {
+        timestamp: Utc::now(),
+        action: action.to_string(),
+        method: request.method().to_string(),
+        path: request.uri().path().to_string(),
+        client,
+        remote_addr,
+        user_agent,
+        start_time,
+        end_time,
+        status: 0,
+    };
+
+    let response = next.run(request).await;
+    entry.status = response.status().as_u16();
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = db.insert_access_log(&entry).await {
+            error!("Failed to record access to {}: {}", entry.path, e);
+        }
+    });
+    response
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 #[oasgen]
@@ -652,8 +1713,15 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
     let now = Utc::now();
     let threshold = Duration::from_secs(1800); // 30 minutes
 
+    let permissions = check_permissions();
+    let screen_recording_denied = permissions
+        .iter()
+        .any(|check| check.capability == Capability::ScreenRecording && !check.status.permitted());
+
     let frame_status = if state.vision_disabled {
         "disabled"
+    } else if screen_recording_denied {
+        "permission_denied"
     } else {
         match last_frame {
             Some(timestamp)
@@ -719,10 +1787,28 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         }
     };
 
+    let encoders = encoder_health();
+    let encoder_status = if state.vision_disabled {
+        "disabled"
+    } else if encoders
+        .iter()
+        .any(|encoder| encoder.state == EncoderState::Unhealthy)
+    {
+        "unhealthy"
+    } else if encoders
+        .iter()
+        .any(|encoder| encoder.state == EncoderState::Degraded)
+    {
+        "degraded"
+    } else {
+        "ok"
+    };
+
     let (overall_status, message, verbose_instructions, status_code) = if (frame_status == "ok"
         || frame_status == "disabled")
         && (audio_status == "ok" || audio_status == "disabled")
         && (ui_status == "ok" || ui_status == "disabled")
+        && encoder_status != "unhealthy"
     {
         (
             "healthy",
@@ -741,12 +1827,15 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         if ui_status != "ok" && ui_status != "disabled" {
             unhealthy_systems.push("ui");
         }
+        if encoder_status == "unhealthy" {
+            unhealthy_systems.push("encoder");
+        }
 
         let systems_str = unhealthy_systems.join(", ");
         (
             "degraded",
             format!("some systems are not healthy: {}", systems_str),
-            Some(get_verbose_instructions(&unhealthy_systems)),
+            Some(get_verbose_instructions(&unhealthy_systems, &permissions)),
             503,
         )
     };
@@ -767,19 +1856,38 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         frame_status: frame_status.to_string(),
         audio_status,
         ui_status: ui_status.to_string(),
+        encoder_status: encoder_status.to_string(),
+        encoders,
+        permissions,
         message,
         verbose_instructions,
         device_status_details,
     })
 }
 
-fn get_verbose_instructions(unhealthy_systems: &[&str]) -> String {
+fn get_verbose_instructions(unhealthy_systems: &[&str], permissions: &[PermissionCheck]) -> String {
     let mut instructions = String::new();
 
+    for check in permissions.iter().filter(|check| !check.status.permitted()) {
+        match &check.settings_url {
+            Some(url) => instructions.push_str(&format!(
+                "{} permission is missing, grant it in {} and restart screenpipe.\n",
+                check.capability, url
+            )),
+            None => {
+                instructions.push_str(&format!("{} permission is missing.\n", check.capability))
+            }
+        }
+    }
+
     if unhealthy_systems.contains(&"vision") {
         instructions.push_str("Vision system is not working properly. Check if screen recording permissions are enabled.\n");
     }
 
+    if unhealthy_systems.contains(&"encoder") {
+        instructions.push_str("Video encoding reported errors, recent chunks may be broken. Check the ffmpeg errors in the logs.\n");
+    }
+
     if unhealthy_systems.contains(&"audio") {
         instructions.push_str("Audio system is not working properly. Check if microphone permissions are enabled and devices are connected.\n");
     }
@@ -1118,6 +2226,10 @@ pub struct SCServer {
     audio_disabled: bool,
     ui_monitoring_enabled: bool,
     enable_pipe: bool,
+    ocr_engine: Arc<OcrEngine>,
+    api_key: Option<String>,
+    access_log_enabled: bool,
+    session_tracker: Option<Arc<SessionTracker>>,
 }
 
 impl SCServer {
@@ -1132,6 +2244,10 @@ impl SCServer {
         ui_monitoring_enabled: bool,
         audio_manager: Arc<AudioManager>,
         enable_pipe: bool,
+        ocr_engine: Arc<OcrEngine>,
+        api_key: Option<String>,
+        access_log_enabled: bool,
+        session_tracker: Option<Arc<SessionTracker>>,
     ) -> Self {
         SCServer {
             db,
@@ -1143,6 +2259,10 @@ impl SCServer {
             ui_monitoring_enabled,
             audio_manager,
             enable_pipe,
+            ocr_engine,
+            api_key,
+            access_log_enabled,
+            session_tracker,
         }
     }
 
@@ -1193,6 +2313,10 @@ impl SCServer {
                 None
             },
             element_cache: Arc::new(Mutex::new(None)),
+            ocr_engine: self.ocr_engine.clone(),
+            api_key: self.api_key.clone(),
+            access_log_enabled: self.access_log_enabled,
+            session_tracker: self.session_tracker.clone(),
         });
 
         let cors = CorsLayer::new()
@@ -1209,6 +2333,29 @@ impl SCServer {
             .get("/vision/list", api_list_monitors)
             .post("/tags/:content_type/:id", add_tags)
             .delete("/tags/:content_type/:id", remove_tags)
+            .get("/bookmarks", list_bookmarks)
+            .post("/bookmarks", create_bookmark)
+            .post("/bookmarks/:id", update_bookmark)
+            .delete("/bookmarks/:id", delete_bookmark)
+            .get("/sessions", list_sessions)
+            .post("/sessions/start", start_session_handler)
+            .post("/sessions/stop", stop_session_handler)
+            .get("/sessions/:id", get_session)
+            .post("/sessions/:id", update_session)
+            .get("/ui-events", list_ui_events)
+            .get("/clipboard", list_clipboard)
+            .get("/jobs", list_jobs)
+            .get("/access-log", list_access_log)
+            .post("/excise", excise_recording)
+            .get("/redactions", list_redactions)
+            .post("/categories/recategorize", recategorize_handler)
+            .get("/mosaic/:date", get_day_mosaic)
+            .post("/mosaic/:date", render_day_mosaic_handler)
+            .get("/thumbnails", list_thumbnails)
+            .get("/thumbnails/:frame_id/sprite", get_thumbnail_sprite)
+            .get("/presentations", list_presentations_handler)
+            .post("/presentations/start", start_presentation_handler)
+            .post("/presentations/stop", stop_presentation_handler)
             .get("/pipes/info/:pipe_id", get_pipe_info_handler)
             .get("/pipes/list", list_pipes_handler)
             .post("/pipes/download", download_pipe_handler)
@@ -1219,7 +2366,9 @@ impl SCServer {
             .post("/pipes/update-version", update_pipe_version_handler)
             .post("/pipes/delete", delete_pipe_handler)
             .post("/pipes/purge", purge_pipe_handler)
+            .get("/frames", get_frame_at_handler)
             .get("/frames/:frame_id", get_frame_data)
+            .post("/frames/dedup", dedup_frames_handler)
             .get("/health", health_check)
             .post("/raw_sql", execute_raw_sql)
             .post("/add", add_to_database)
@@ -1263,11 +2412,17 @@ impl SCServer {
             .post("/audio/start", start_audio)
             .post("/audio/stop", stop_audio)
             .get("/semantic-search", semantic_search_handler)
+            .post("/summarize", summarize_handler)
+            .get("/activity/export", export_activity_handler)
+            .get("/activity/stats", activity_stats_handler)
+            .get("/excluded-regions", get_excluded_regions)
+            .post("/excluded-regions", set_excluded_regions_handler)
             .get("/pipes/build-status/:pipe_id", get_pipe_build_status)
             .get("/search/keyword", keyword_search_handler)
             .post("/v1/embeddings", create_embeddings)
             .post("/audio/device/start", start_audio_device)
             .post("/audio/device/stop", stop_audio_device)
+            .get("/screenshot", screenshot_handler)
             .route_yaml_spec("/openapi.yaml")
             .route_json_spec("/openapi.json")
             .freeze();
@@ -1277,9 +2432,28 @@ impl SCServer {
             .merge(server.into_router())
             // NOTE: websockerts and sse is not supported by openapi so we move it down here
             .route("/stream/frames", get(stream_frames_handler))
+            .route("/stream/mjpeg/:monitor_id", get(mjpeg_stream_handler))
             .route("/ws/events", get(ws_events_handler))
+            .route("/ws/live", get(ws_live_handler))
             .route("/ws/health", get(ws_health_handler))
+            // plain text in the Prometheus format, which openapi can't describe either
+            .route("/metrics", get(metrics_handler))
             .route("/frames/export", get(handle_video_export_ws))
+            // signed over their raw body, which openapi can't describe
+            .route(
+                "/hub/frames",
+                post(hub_frames_handler).layer(DefaultBodyLimit::max(HUB_MAX_PUSH_BYTES)),
+            )
+            .route("/hub/excisions", post(hub_excisions_handler))
+            .route("/hub/segments/*key", put(hub_segment_handler))
+            .layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_api_key,
+            ))
+            .layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                record_access,
+            ))
             .with_state(app_state)
             .layer(cors)
             .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::default()))
@@ -1405,6 +2579,12 @@ async fn add_frame_to_db(
             frame.app_name.as_deref(),
             frame.window_name.as_deref(),
             false,
+            categorize(
+                frame.app_name.as_deref().unwrap_or_default(),
+                frame.window_name.as_deref().unwrap_or_default(),
+                None,
+            )
+            .as_deref(),
         )
         .await?;
 
@@ -1695,6 +2875,9 @@ pub struct DeviceMetadata {
     pub app_name: String,
     pub window_name: String,
     pub ocr_text: String,
+    pub category: Option<String>,
+    /// Color to draw the frame's category with on the timeline.
+    pub color: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1724,6 +2907,12 @@ impl From<TimeSeriesFrame> for StreamTimeSeriesResponse {
                             app_name: device_frame.metadata.app_name,
                             window_name: device_frame.metadata.window_name,
                             ocr_text: device_frame.metadata.ocr_text,
+                            color: device_frame
+                                .metadata
+                                .category
+                                .as_deref()
+                                .map(category_color),
+                            category: device_frame.metadata.category,
                         },
                         audio: device_frame
                             .audio_entries
@@ -2068,18 +3257,28 @@ struct EventsQuery {
     images: Option<bool>,
 }
 
+#[derive(Deserialize)]
+struct LiveEventsQuery {
+    /// Comma separated names of the events to stream, all the recording events by default
+    events: Option<String>,
+    monitor_id: Option<u32>,
+}
+
 #[derive(Debug, OaSchema, Deserialize)]
 struct SemanticSearchQuery {
     text: String,
     limit: Option<u32>,
     threshold: Option<f32>,
+    /// ocr, audio or all (default); up to `limit` results of each are returned
+    #[serde(default)]
+    content_type: ContentType,
 }
 
 #[oasgen]
 async fn semantic_search_handler(
     Query(query): Query<SemanticSearchQuery>,
     State(state): State<Arc<AppState>>,
-) -> Result<JsonResponse<Vec<screenpipe_db::OCRResult>>, (StatusCode, JsonResponse<Value>)> {
+) -> Result<JsonResponse<Vec<ContentItem>>, (StatusCode, JsonResponse<Value>)> {
     let limit = query.limit.unwrap_or(10);
     let threshold = query.threshold.unwrap_or(0.3);
 
@@ -2088,36 +3287,267 @@ async fn semantic_search_handler(
         query.text, limit, threshold
     );
 
-    // Generate embedding for search text
-    let embedding = match generate_embedding(&query.text, 0).await {
-        Ok(emb) => emb,
-        Err(e) => {
-            error!("failed to generate embedding: {}", e);
-            return Err((
+    // Generate embedding for search text
+    let embedding = match generate_embedding(&query.text, 0).await {
+        Ok(emb) => emb,
+        Err(e) => {
+            error!("failed to generate embedding: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to generate embedding: {}", e)})),
+            ));
+        }
+    };
+
+    let search_ocr = matches!(
+        query.content_type,
+        ContentType::All | ContentType::OCR | ContentType::OcrAndUi | ContentType::AudioAndOcr
+    );
+    let search_audio = matches!(
+        query.content_type,
+        ContentType::All | ContentType::Audio | ContentType::AudioAndUi | ContentType::AudioAndOcr
+    );
+
+    // Search database for similar embeddings
+    let search_error = |e: sqlx::Error| {
+        error!("failed to search embeddings: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("failed to search embeddings: {}", e)})),
+        )
+    };
+    let mut results = Vec::new();
+    if search_ocr {
+        results.extend(
+            state
+                .db
+                .search_similar_embeddings(embedding.clone(), limit, threshold)
+                .await
+                .map_err(search_error)?
+                .into_iter()
+                .map(SearchResult::OCR),
+        );
+    }
+    if search_audio {
+        results.extend(
+            state
+                .db
+                .search_similar_transcriptions(embedding, limit, threshold)
+                .await
+                .map_err(search_error)?
+                .into_iter()
+                .map(SearchResult::Audio),
+        );
+    }
+
+    debug!("found {} similar results", results.len());
+    Ok(JsonResponse(
+        results.iter().map(ContentItem::from).collect(),
+    ))
+}
+
+#[derive(Debug, OaSchema, Deserialize)]
+struct SummarizeRequest {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+#[oasgen]
+async fn summarize_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<SummarizeRequest>,
+) -> Result<JsonResponse<Summary>, (StatusCode, JsonResponse<Value>)> {
+    if request.end_time <= request.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+
+    match summarize_range(
+        &state.db,
+        llm_backend(),
+        request.start_time,
+        request.end_time,
+    )
+    .await
+    {
+        Ok(summary) => Ok(JsonResponse(summary)),
+        Err(e) => {
+            error!("failed to summarize time range: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to summarize: {}", e)})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ActivityExportQuery {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    format: TimeTrackingFormat,
+}
+
+/// The activity of a time range as time entries for Toggl, Timewarrior or a calendar, with
+/// the projects mapped from apps by the rules in `time_tracking.json`.
+#[oasgen]
+pub(crate) async fn export_activity_handler(
+    Query(query): Query<ActivityExportQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    if query.end_time <= query.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+    let config = TimeTrackingConfig::load(&state.screenpipe_dir.join(TIME_TRACKING_CONFIG_FILE))
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": format!("{:#}", e)})),
+            )
+        })?;
+    let activity = state
+        .db
+        .get_app_activity(query.start_time, query.end_time)
+        .await
+        .map_err(|e| {
+            error!("Failed to get app activity: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    let content = time_entries(&activity, &config)
+        .and_then(|entries| render_entries(&entries, query.format, &config))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+
+    let file_name = format!(
+        "screenpipe_{}_{}.{}",
+        query.start_time.format("%Y-%m-%d"),
+        query.end_time.format("%Y-%m-%d"),
+        query.format.extension()
+    );
+    Response::builder()
+        .header("content-type", query.format.content_type())
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{}\"", file_name),
+        )
+        .body(Body::from(content))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create response: {}", e)})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ActivityStatsQuery {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    /// Privacy budget of the Laplace noise added to the totals, exact totals when not set.
+    epsilon: Option<f64>,
+    #[serde(default)]
+    titles: TitleMode,
+    /// Key of the title hashes, to compare exports of the same person. Random when not set.
+    salt: Option<String>,
+}
+
+/// Aggregate statistics of a time range, time per category and active vs idle time per day,
+/// without window titles or frames, to share outside of screenpipe.
+#[oasgen]
+pub(crate) async fn activity_stats_handler(
+    Query(query): Query<ActivityStatsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<ActivityStats>, (StatusCode, JsonResponse<Value>)> {
+    let config = TimeTrackingConfig::load(&state.screenpipe_dir.join(TIME_TRACKING_CONFIG_FILE))
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": format!("{:#}", e)})),
+            )
+        })?;
+    let activity = state
+        .db
+        .get_app_activity(query.start_time, query.end_time)
+        .await
+        .map_err(|e| {
+            error!("Failed to get app activity: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    let options = StatsOptions {
+        epsilon: query.epsilon,
+        titles: query.titles,
+        salt: query.salt,
+    };
+    activity_stats(
+        &activity,
+        query.start_time,
+        query.end_time,
+        &config,
+        &options,
+        &mut rand::thread_rng(),
+    )
+    .map(JsonResponse)
+    .map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })
+}
+
+/// The regions blacked out of every frame.
+#[oasgen]
+pub(crate) async fn get_excluded_regions(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<ExcludedRegionsConfig>, (StatusCode, JsonResponse<Value>)> {
+    ExcludedRegionsConfig::load(&state.screenpipe_dir.join(EXCLUDED_REGIONS_CONFIG_FILE))
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": format!("failed to generate embedding: {}", e)})),
-            ));
-        }
-    };
+                JsonResponse(json!({"error": format!("{:#}", e)})),
+            )
+        })
+}
 
-    // Search database for similar embeddings
-    match state
-        .db
-        .search_similar_embeddings(embedding, limit, threshold)
-        .await
-    {
-        Ok(results) => {
-            debug!("found {} similar results", results.len());
-            Ok(JsonResponse(results))
-        }
-        Err(e) => {
-            error!("failed to search embeddings: {}", e);
-            Err((
+/// Replaces the regions blacked out of every frame, from the next frame on.
+#[oasgen]
+pub(crate) async fn set_excluded_regions_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(config): JsonResponse<ExcludedRegionsConfig>,
+) -> Result<JsonResponse<ExcludedRegionsConfig>, (StatusCode, JsonResponse<Value>)> {
+    config.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?;
+    config
+        .save(&state.screenpipe_dir.join(EXCLUDED_REGIONS_CONFIG_FILE))
+        .map_err(|e| {
+            error!("failed to save excluded regions: {:#}", e);
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": format!("failed to search embeddings: {}", e)})),
-            ))
-        }
-    }
+                JsonResponse(json!({"error": format!("{:#}", e)})),
+            )
+        })?;
+    config.apply();
+    Ok(JsonResponse(config))
 }
 
 #[derive(Serialize, OaSchema, Deserialize)]
@@ -2262,6 +3692,182 @@ async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>) {
     debug!("WebSocket connection closed");
 }
 
+/// Recording health of every monitor, for Prometheus to scrape.
+async fn metrics_handler() -> impl IntoResponse {
+    ([(CONTENT_TYPE, METRICS_CONTENT_TYPE)], prometheus_metrics())
+}
+
+/// Checks a push to this hub, returning the hub and the peer it's from.
+fn authorize_hub_push(
+    method: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+    body_digest: &str,
+) -> Result<(&'static HubConfig, String), (StatusCode, JsonResponse<Value>)> {
+    let Some(hub) = hub() else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "hub mode is disabled, start with --hub-token"})),
+        ));
+    };
+    let host = verify_hub_request(
+        &hub.token,
+        method,
+        uri.path(),
+        headers,
+        body_digest,
+        &HUB_NONCES,
+        Utc::now().timestamp(),
+    )
+    .map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?;
+    // its files would land in this machine's own directory
+    if host == local_hostname() {
+        return Err((
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": format!("{} is the hub's own hostname", host)})),
+        ));
+    }
+    Ok((hub, host))
+}
+
+async fn hub_frames_handler(
+    State(state): State<Arc<AppState>>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<JsonResponse<HubPushResponse>, (StatusCode, JsonResponse<Value>)> {
+    let (hub, host) = authorize_hub_push("POST", &uri, &headers, &body_digest(&body))?;
+    let push: HubPush = serde_json::from_slice(&body).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("invalid push: {}", e)})),
+        )
+    })?;
+    match index_pushed_frames(&state.db, &hub.root, &host, push.frames).await {
+        Ok(inserted) => {
+            debug!("indexed {} frames pushed by {}", inserted, host);
+            Ok(JsonResponse(HubPushResponse { inserted }))
+        }
+        Err(e) => {
+            error!("Failed to index frames pushed by {}: {}", host, e);
+            Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+/// Removes the ranges a peer excised from what it pushed here.
+async fn hub_excisions_handler(
+    State(state): State<Arc<AppState>>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let (_, host) = authorize_hub_push("POST", &uri, &headers, &body_digest(&body))?;
+    let push: HubExcisions = serde_json::from_slice(&body).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("invalid push: {}", e)})),
+        )
+    })?;
+    let mut trimmed = 0;
+    for excision in &push.excisions {
+        match excise_pushed_range(&state.db, &host, excision).await {
+            Ok(chunks) => trimmed += chunks,
+            Err(e) => {
+                error!("Failed to excise the range pushed by {}: {}", host, e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": e.to_string()})),
+                ));
+            }
+        }
+    }
+    info!(
+        "excised {} ranges pushed by {}, {} chunks to trim",
+        push.excisions.len(),
+        host,
+        trimmed
+    );
+    Ok(JsonResponse(json!({"trimmed_chunks": trimmed})))
+}
+
+async fn hub_segment_handler(
+    Path(key): Path<String>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let Some(digest) = headers
+        .get(HUB_CONTENT_SHA256_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            JsonResponse(json!({"error": format!("missing {} header", HUB_CONTENT_SHA256_HEADER)})),
+        ));
+    };
+    let (hub, host) = authorize_hub_push("PUT", &uri, &headers, &digest)?;
+    match store_segment(&hub.root, &host, &key, &digest, body.into_data_stream()).await {
+        Ok(bytes) => Ok(JsonResponse(json!({"key": key, "bytes": bytes}))),
+        Err(e) => {
+            error!("Failed to store segment {} pushed by {}: {}", key, host, e);
+            Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+/// Streams the recording events, frames written, activity changes, blocked apps and rotated
+/// segments, as they happen.
+async fn ws_live_handler(ws: WebSocketUpgrade, Query(query): Query<LiveEventsQuery>) -> Response {
+    match LiveEventFilter::new(query.events.as_deref(), query.monitor_id) {
+        Ok(filter) => ws.on_upgrade(move |socket| handle_live_socket(socket, filter)),
+        Err(e) => (StatusCode::BAD_REQUEST, JsonResponse(json!({ "error": e }))).into_response(),
+    }
+}
+
+async fn handle_live_socket(mut socket: WebSocket, filter: LiveEventFilter) {
+    let mut events = Box::pin(subscribe_to_live_events(filter));
+    let mut ping = tokio::time::interval(Duration::from_secs(30));
+
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else { break };
+                let message = serde_json::to_string(&event).unwrap_or_default();
+                if let Err(e) = socket.send(Message::Text(message)).await {
+                    debug!("Failed to send live event: {}", e);
+                    break;
+                }
+            }
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(vec![])).await.is_err() {
+                    break;
+                }
+            }
+            // clients only listen, anything but a close is ignored
+            message = socket.recv() => {
+                if matches!(message, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    break;
+                }
+            }
+        }
+    }
+
+    debug!("Live events WebSocket closed");
+}
+
 async fn ws_health_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
     ws.on_upgrade(move |socket| handle_health_socket(socket, state))
 }
@@ -2296,6 +3902,11 @@ pub struct VideoExportRequest {
     #[serde(deserialize_with = "deserialize_frame_ids")]
     frame_ids: Vec<i64>,
     fps: f64,
+    /// `mp4`, or `gif` or `apng` for clips up to `--max-animated-clip-secs`.
+    #[serde(default)]
+    format: ClipFormat,
+    /// Width animations are scaled to, 960 by default.
+    width: Option<u32>,
 }
 
 #[derive(OaSchema, Debug, Deserialize)]
@@ -2432,6 +4043,23 @@ async fn handle_video_export(
     state: Arc<AppState>,
     payload: VideoExportRequest,
 ) {
+    if payload.format.is_animation() {
+        if let Err(e) = check_animated_clip_length(payload.frame_ids.len(), payload.fps) {
+            let _ = socket
+                .send(Message::Text(
+                    serde_json::to_string(&ExportProgress {
+                        status: "error".to_string(),
+                        progress: 0.0,
+                        video_data: None,
+                        error: Some(e.to_string()),
+                    })
+                    .unwrap(),
+                ))
+                .await;
+            return;
+        }
+    }
+
     let temp_dir = match tempfile::tempdir() {
         Ok(dir) => dir,
         Err(e) => {
@@ -2467,8 +4095,9 @@ async fn handle_video_export(
     }
 
     let output_filename = format!(
-        "screenpipe_export_{}.mp4",
-        chrono::Utc::now().format("%Y%m%d_%H%M%S")
+        "screenpipe_export_{}.{}",
+        chrono::Utc::now().format("%Y%m%d_%H%M%S"),
+        payload.format.extension()
     );
     let output_path = temp_dir.path().join(&output_filename);
 
@@ -2562,7 +4191,20 @@ async fn handle_video_export(
         .await;
 
     // Create video
-    match write_frames_to_video(&frames, output_path.to_str().unwrap(), payload.fps).await {
+    let encoded = if payload.format.is_animation() {
+        let frame_paths: Vec<String> = frames.iter().map(|frame| frame.file_path.clone()).collect();
+        encode_animated_clip(
+            &frame_paths,
+            &output_path,
+            payload.format,
+            payload.fps,
+            payload.width.unwrap_or(DEFAULT_ANIMATED_CLIP_WIDTH),
+        )
+        .await
+    } else {
+        write_frames_to_video(&frames, output_path.to_str().unwrap(), payload.fps).await
+    };
+    match encoded {
         Ok(_) => match tokio::fs::read(&output_path).await {
             Ok(video_data) => {
                 let _ = socket
@@ -2785,13 +4427,34 @@ pub struct KeywordSearchRequest {
     app_names: Option<Vec<String>>,
 }
 
+#[derive(OaSchema, Deserialize)]
+pub struct FrameQuery {
+    /// Pyramid level (0 = full, 1 = 1/4, 2 = 1/16 width), served when the pyramid is built.
+    /// Levels 1 and 2 show the closest keyframe at or before the frame.
+    #[serde(default)]
+    level: Option<usize>,
+}
+
 #[oasgen]
 pub async fn get_frame_data(
     State(state): State<Arc<AppState>>,
     Path(frame_id): Path<i64>,
+    Query(query): Query<FrameQuery>,
 ) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
     let start_time = Instant::now();
 
+    // level 0 is the video itself
+    if let Some(level) = query
+        .level
+        .filter(|level| (1..PYRAMID_LEVELS.len()).contains(level))
+    {
+        if let Ok(Some((file_path, offset_index))) = state.db.get_frame(frame_id).await {
+            if let Some(pyramid_path) = pyramid_keyframe_path(&file_path, level, offset_index) {
+                return serve_file(&pyramid_path.to_string_lossy()).await;
+            }
+        }
+    }
+
     match timeout(Duration::from_secs(5), async {
         // Try to get frame from cache if enabled
         if let Some(cache) = &state.frame_image_cache {
@@ -2876,6 +4539,82 @@ pub async fn get_frame_data(
     }
 }
 
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct FrameAtQuery {
+    /// Wall-clock time to show the screen at.
+    ts: DateTime<Utc>,
+    /// Monitor to show, any of them when not set.
+    #[serde(default)]
+    device_name: Option<String>,
+    /// How far before `ts` the last captured frame may be, past which nothing was recorded.
+    #[serde(default)]
+    max_gap_secs: Option<i64>,
+}
+
+/// The frame on screen at a time, decoded from its video chunk as PNG, with its id and
+/// capture time in the `x-frame-id` and `x-frame-timestamp` headers.
+#[oasgen]
+pub(crate) async fn get_frame_at_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FrameAtQuery>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let max_gap_secs = query.max_gap_secs.unwrap_or(DEFAULT_MAX_FRAME_GAP_SECS);
+    let frame = match timeout(
+        Duration::from_secs(10),
+        frame_at(
+            &state.db,
+            query.ts,
+            query.device_name.as_deref(),
+            max_gap_secs,
+        ),
+    )
+    .await
+    {
+        Ok(Ok(Some(frame))) => frame,
+        Ok(Ok(None)) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({
+                    "error": "no frame recorded at this time",
+                    "ts": query.ts
+                })),
+            ))
+        }
+        Ok(Err(e)) => {
+            error!("Failed to seek frame at {}: {}", query.ts, e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to seek frame: {}", e)})),
+            ));
+        }
+        Err(_) => {
+            return Err((
+                StatusCode::REQUEST_TIMEOUT,
+                JsonResponse(json!({"error": "Request timed out", "ts": query.ts})),
+            ))
+        }
+    };
+
+    let png = encode_png(&frame.image).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to encode frame: {}", e)})),
+        )
+    })?;
+    Response::builder()
+        .header(CONTENT_TYPE, "image/png")
+        .header("cache-control", "public, max-age=604800")
+        .header("x-frame-id", frame.location.frame_id)
+        .header("x-frame-timestamp", frame.location.timestamp.to_rfc3339())
+        .body(Body::from(png))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create response: {}", e)})),
+            )
+        })
+}
+
 async fn serve_file(path: &str) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
     match File::open(path).await {
         Ok(file) => {
@@ -2950,6 +4689,7 @@ fn create_time_series_frame(chunk: FrameData) -> TimeSeriesFrame {
                         .collect::<Vec<_>>()
                         .join(" "),
                     ocr_text: device_data.text,
+                    category: device_data.category,
                 },
                 audio_entries: chunk
                     .audio_entries
@@ -3088,6 +4828,153 @@ async fn stream_frames_handler(
     ws.on_upgrade(move |socket| handle_stream_frames_socket(socket, state))
 }
 
+#[derive(OaSchema, Deserialize)]
+pub struct ScreenshotQuery {
+    monitor_id: Option<u32>,
+    #[serde(default)]
+    skip_ocr: bool,
+}
+
+#[derive(OaSchema, Serialize)]
+pub struct ScreenshotWindow {
+    app_name: String,
+    window_name: String,
+    focused: bool,
+    browser_url: Option<String>,
+    text: String,
+}
+
+#[derive(OaSchema, Serialize)]
+pub struct ScreenshotResponse {
+    monitor_id: u32,
+    timestamp: DateTime<Utc>,
+    /// base64-encoded jpeg of the whole monitor
+    image: String,
+    windows: Vec<ScreenshotWindow>,
+}
+
+/// Captures the current screen on demand, without touching the recording or the database.
+#[oasgen]
+async fn screenshot_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScreenshotQuery>,
+) -> Result<JsonResponse<ScreenshotResponse>, (StatusCode, JsonResponse<Value>)> {
+    let monitor_id = match query.monitor_id {
+        Some(id) => id,
+        None => screenpipe_vision::monitor::get_default_monitor().await.id(),
+    };
+
+    let (image, windows) = if !query.skip_ocr {
+        capture_frame_with_ocr(
+            monitor_id,
+            // the ignored and included windows of the recording, as reloaded
+            &WindowFilters::shared(),
+            &state.ocr_engine,
+            Vec::new(),
+            false,
+        )
+        .await
+        .map(|(image, results)| {
+            let windows = results
+                .into_iter()
+                .map(|result| ScreenshotWindow {
+                    app_name: result.app_name,
+                    window_name: result.window_name,
+                    focused: result.focused,
+                    browser_url: result.browser_url,
+                    text: result.text,
+                })
+                .collect();
+            (image, windows)
+        })
+        .map_err(|e| e.to_string())
+    } else {
+        match get_monitor_by_id(monitor_id).await {
+            Some(monitor) => monitor
+                .capture_image()
+                .await
+                .map(|mut image| {
+                    let bounds = monitor.bounds();
+                    black_out(
+                        &mut image,
+                        bounds,
+                        &excluded_regions_on(monitor.id(), bounds),
+                    );
+                    (image, Vec::new())
+                })
+                .map_err(|e| e.to_string()),
+            None => Err(format!("monitor {} not found", monitor_id)),
+        }
+    }
+    .map_err(|e| {
+        error!("failed to capture screenshot: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e})),
+        )
+    })?;
+
+    let mut buffer = Vec::new();
+    image
+        .to_rgb8()
+        .write_to(&mut std::io::Cursor::new(&mut buffer), ImageFormat::Jpeg)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to encode screenshot: {}", e)})),
+            )
+        })?;
+
+    Ok(JsonResponse(ScreenshotResponse {
+        monitor_id,
+        timestamp: Utc::now(),
+        image: general_purpose::STANDARD.encode(buffer),
+        windows,
+    }))
+}
+
+/// Live MJPEG stream of a monitor (multipart/x-mixed-replace), viewable in any browser or in
+/// `ffplay`/VLC from another machine.
+async fn mjpeg_stream_handler(
+    Path(monitor_id): Path<u32>,
+) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
+    if get_monitor_by_id(monitor_id).await.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("monitor {} not found", monitor_id)})),
+        ));
+    }
+
+    let receiver = subscribe_live_frames(monitor_id);
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            receiver.changed().await.ok()?;
+            let frame = receiver.borrow_and_update().clone();
+            if let Some(frame) = frame {
+                let mut part = format!(
+                    "--frame\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                    frame.len()
+                )
+                .into_bytes();
+                part.extend_from_slice(&frame);
+                part.extend_from_slice(b"\r\n");
+                return Some((Ok::<_, std::io::Error>(part), receiver));
+            }
+        }
+    });
+
+    Response::builder()
+        .header("content-type", "multipart/x-mixed-replace; boundary=frame")
+        .header("cache-control", "no-cache")
+        .body(Body::from_stream(stream))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create response: {}", e)})),
+            )
+        })
+}
+
 #[oasgen]
 pub async fn delete_pipe_handler(
     State(state): State<Arc<AppState>>,
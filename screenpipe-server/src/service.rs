@@ -0,0 +1,424 @@
+use crate::cli::{OutputFormat, ServiceCommand};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the systemd unit and the Windows scheduled task.
+pub const SERVICE_NAME: &str = "screenpipe";
+/// Label of the launchd agent.
+pub const LAUNCHD_LABEL: &str = "pe.screenpi.screenpipe";
+
+/// How the background service is started: the recorder binary, the flags it was installed with
+/// and where the service manager writes its output.
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    pub exe: PathBuf,
+    pub args: Vec<String>,
+    pub log_path: PathBuf,
+    /// `PATH` of the installing shell, so the service finds ffmpeg and friends like it did.
+    pub path_env: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    /// systemd, launchd or task scheduler.
+    pub manager: &'static str,
+    /// The unit file or launchd plist, if the manager uses one.
+    pub definition: Option<PathBuf>,
+    pub installed: bool,
+    pub running: bool,
+}
+
+/// Quotes an argument for `ExecStart=`, where `%` and `$` would otherwise be expanded.
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+pub fn systemd_unit(spec: &ServiceSpec) -> String {
+    let command = std::iter::once(spec.exe.to_string_lossy().to_string())
+        .chain(spec.args.iter().cloned())
+        .map(|arg| systemd_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let environment = spec
+        .path_env
+        .as_ref()
+        .map(|path| format!("Environment={}\n", systemd_quote(&format!("PATH={}", path))))
+        .unwrap_or_default();
+
+    format!(
+        "[Unit]\n\
+         Description=screenpipe screen and audio recorder\n\
+         After=graphical-session.target\n\
+         PartOf=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         {}Restart=on-failure\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n",
+        command, environment
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn launchd_plist(spec: &ServiceSpec) -> String {
+    let arguments = std::iter::once(spec.exe.to_string_lossy().to_string())
+        .chain(spec.args.iter().cloned())
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect::<String>();
+    let environment = spec
+        .path_env
+        .as_ref()
+        .map(|path| {
+            format!(
+                "    <key>EnvironmentVariables</key>\n    <dict>\n        <key>PATH</key>\n        <string>{}</string>\n    </dict>\n",
+                xml_escape(path)
+            )
+        })
+        .unwrap_or_default();
+    let log_path = xml_escape(&spec.log_path.to_string_lossy());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+{environment}    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ProcessType</key>
+    <string>Interactive</string>
+    <key>StandardOutPath</key>
+    <string>{log_path}</string>
+    <key>StandardErrorPath</key>
+    <string>{log_path}</string>
+</dict>
+</plist>
+"#,
+        label = LAUNCHD_LABEL,
+    )
+}
+
+/// Quotes an argument the way the Windows C runtime splits command lines.
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            c => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// The command line the scheduled task runs.
+pub fn windows_task_command(spec: &ServiceSpec) -> String {
+    std::iter::once(spec.exe.to_string_lossy().to_string())
+        .chain(spec.args.iter().cloned())
+        .map(|arg| windows_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Runs a service manager command, failing with its stderr when it fails.
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run {}", program))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Variables the recorder needs to reach the screen, which not every desktop hands over to
+/// the systemd user manager.
+const SESSION_ENVIRONMENT: &[&str] = &["DISPLAY", "WAYLAND_DISPLAY", "XAUTHORITY"];
+
+/// Hands the display of the current session over to the systemd user manager, for the service
+/// started from it.
+fn import_session_environment() -> Result<()> {
+    let mut args = vec!["--user", "import-environment"];
+    args.extend(
+        SESSION_ENVIRONMENT
+            .iter()
+            .filter(|name| std::env::var_os(name).is_some()),
+    );
+    if args.len() > 2 {
+        run("systemctl", &args)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn definition_path() -> Result<Option<PathBuf>> {
+    let config_dir = dirs::config_dir().ok_or_else(|| anyhow!("no config directory"))?;
+    Ok(Some(
+        config_dir
+            .join("systemd/user")
+            .join(format!("{}.service", SERVICE_NAME)),
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn definition_path() -> Result<Option<PathBuf>> {
+    let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("no home directory"))?;
+    Ok(Some(
+        home_dir
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCHD_LABEL)),
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn definition_path() -> Result<Option<PathBuf>> {
+    Ok(None)
+}
+
+fn manager() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "launchd"
+    } else if cfg!(target_os = "windows") {
+        "task scheduler"
+    } else {
+        "systemd"
+    }
+}
+
+/// Registers screenpipe to start at login and starts it now.
+pub fn install(spec: &ServiceSpec) -> Result<ServiceStatus> {
+    if let Some(path) = definition_path()? {
+        let definition = if cfg!(target_os = "macos") {
+            launchd_plist(spec)
+        } else {
+            systemd_unit(spec)
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // replace an older install instead of failing on it
+        if path.exists() {
+            let _ = stop();
+        }
+        std::fs::write(&path, definition)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    if cfg!(target_os = "macos") {
+        let path = definition_path()?.unwrap_or_default();
+        run("launchctl", &["load", "-w", &path.to_string_lossy()])?;
+    } else if cfg!(target_os = "windows") {
+        let command = windows_task_command(spec);
+        run(
+            "schtasks",
+            &[
+                "/Create",
+                "/TN",
+                SERVICE_NAME,
+                "/TR",
+                &command,
+                "/SC",
+                "ONLOGON",
+                "/RL",
+                "LIMITED",
+                "/F",
+            ],
+        )?;
+        run("schtasks", &["/Run", "/TN", SERVICE_NAME])?;
+    } else {
+        let unit = format!("{}.service", SERVICE_NAME);
+        import_session_environment()?;
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", "--now", &unit])?;
+    }
+    status()
+}
+
+/// Stops screenpipe and removes it from the login items.
+pub fn uninstall() -> Result<()> {
+    if cfg!(target_os = "windows") {
+        let _ = stop();
+        run("schtasks", &["/Delete", "/TN", SERVICE_NAME, "/F"])?;
+        return Ok(());
+    }
+
+    let Some(path) = definition_path()?.filter(|path| path.exists()) else {
+        return Err(anyhow!("screenpipe is not installed as a service"));
+    };
+    if cfg!(target_os = "macos") {
+        let _ = run("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+    } else {
+        let unit = format!("{}.service", SERVICE_NAME);
+        let _ = run("systemctl", &["--user", "disable", "--now", &unit]);
+    }
+    std::fs::remove_file(&path)?;
+    if cfg!(target_os = "linux") {
+        run("systemctl", &["--user", "daemon-reload"])?;
+    }
+    Ok(())
+}
+
+pub fn start() -> Result<()> {
+    if cfg!(target_os = "macos") {
+        let path = definition_path()?.unwrap_or_default();
+        run("launchctl", &["load", &path.to_string_lossy()])?;
+    } else if cfg!(target_os = "windows") {
+        run("schtasks", &["/Run", "/TN", SERVICE_NAME])?;
+    } else {
+        import_session_environment()?;
+        run(
+            "systemctl",
+            &["--user", "start", &format!("{}.service", SERVICE_NAME)],
+        )?;
+    }
+    Ok(())
+}
+
+/// Stops the service until the next login, or until [`start`].
+pub fn stop() -> Result<()> {
+    if cfg!(target_os = "macos") {
+        // unloaded without -w, so it still starts at the next login
+        let path = definition_path()?.unwrap_or_default();
+        run("launchctl", &["unload", &path.to_string_lossy()])?;
+    } else if cfg!(target_os = "windows") {
+        run("schtasks", &["/End", "/TN", SERVICE_NAME])?;
+    } else {
+        run(
+            "systemctl",
+            &["--user", "stop", &format!("{}.service", SERVICE_NAME)],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn status() -> Result<ServiceStatus> {
+    let definition = definition_path()?;
+    let (installed, running) = if cfg!(target_os = "macos") {
+        let installed = definition.as_ref().is_some_and(|path| path.exists());
+        // loaded jobs are listed, running ones with their pid
+        let running = run("launchctl", &["list", LAUNCHD_LABEL])
+            .map(|listing| listing.contains("\"PID\""))
+            .unwrap_or(false);
+        (installed, running)
+    } else if cfg!(target_os = "windows") {
+        match run(
+            "schtasks",
+            &["/Query", "/TN", SERVICE_NAME, "/FO", "CSV", "/NH"],
+        ) {
+            Ok(listing) => (true, listing.contains("\"Running\"")),
+            Err(_) => (false, false),
+        }
+    } else {
+        let installed = definition.as_ref().is_some_and(|path| path.exists());
+        let unit = format!("{}.service", SERVICE_NAME);
+        let running = run("systemctl", &["--user", "is-active", &unit])
+            .map(|state| state.trim() == "active")
+            .unwrap_or(false);
+        (installed, running)
+    };
+
+    Ok(ServiceStatus {
+        manager: manager(),
+        definition,
+        installed,
+        running,
+    })
+}
+
+pub fn handle_service_command(command: &ServiceCommand, local_data_dir: &Path) -> Result<()> {
+    match command {
+        ServiceCommand::Install { args } => {
+            let spec = ServiceSpec {
+                exe: std::env::current_exe()?,
+                args: args.clone(),
+                log_path: local_data_dir.join("screenpipe-service.log"),
+                path_env: std::env::var("PATH").ok(),
+            };
+            let status = install(&spec)?;
+            println!(
+                "screenpipe installed with {}, it starts at login{}",
+                status.manager,
+                if status.running {
+                    " and is running"
+                } else {
+                    ""
+                }
+            );
+        }
+        ServiceCommand::Uninstall => {
+            uninstall()?;
+            println!("screenpipe service removed");
+        }
+        ServiceCommand::Start => {
+            start()?;
+            println!("screenpipe service started");
+        }
+        ServiceCommand::Stop => {
+            stop()?;
+            println!(
+                "screenpipe service stopped until the next login or `screenpipe service start`"
+            );
+        }
+        ServiceCommand::Status { output } => {
+            let status = status()?;
+            match output {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&status)?),
+                OutputFormat::Text => {
+                    println!("manager:   {}", status.manager);
+                    if let Some(definition) = &status.definition {
+                        println!("file:      {}", definition.display());
+                    }
+                    println!("installed: {}", status.installed);
+                    println!("running:   {}", status.running);
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,374 @@
+use crate::storage::Storage;
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{AppActivity, DatabaseManager, RecordingSession};
+use screenpipe_events::send_event;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+#[derive(Debug, Clone)]
+pub struct SessionSplitConfig {
+    /// Start a new session after this long without any new frame. `None` disables idle splits.
+    pub idle_gap: Option<Duration>,
+    /// Start a new session when the local date changes.
+    pub split_on_day_boundary: bool,
+}
+
+impl Default for SessionSplitConfig {
+    fn default() -> Self {
+        SessionSplitConfig {
+            idle_gap: Some(Duration::from_secs(30 * 60)),
+            split_on_day_boundary: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitReason {
+    Idle,
+    DayBoundary,
+}
+
+impl SplitReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitReason::Idle => "idle",
+            SplitReason::DayBoundary => "day_boundary",
+        }
+    }
+}
+
+/// Decides whether activity at `now` belongs to a new session, given when the current one
+/// started and when its last frame was seen.
+pub fn split_reason(
+    config: &SessionSplitConfig,
+    session_started_at: DateTime<Utc>,
+    last_activity: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Option<SplitReason> {
+    if let Some(idle_gap) = config.idle_gap {
+        if (now - last_activity).to_std().unwrap_or_default() >= idle_gap {
+            return Some(SplitReason::Idle);
+        }
+    }
+    if config.split_on_day_boundary
+        && session_started_at.with_timezone(&Local).date_naive()
+            != now.with_timezone(&Local).date_naive()
+    {
+        return Some(SplitReason::DayBoundary);
+    }
+    None
+}
+
+#[derive(Serialize)]
+struct SessionManifest<'a> {
+    #[serde(flatten)]
+    session: &'a RecordingSession,
+    video_chunks: Vec<String>,
+}
+
+/// Where the manifest of `session` is written:
+/// `<root>/<hostname>/sessions/<local start date>_<id>.json`.
+pub fn session_manifest_path(storage: &Storage, session: &RecordingSession) -> PathBuf {
+    storage.host_dir().join("sessions").join(format!(
+        "{}_{}.json",
+        session.started_at.with_timezone(&Local).format("%Y-%m-%d"),
+        session.id
+    ))
+}
+
+/// JSON schema of the session manifests: the fields of the session and its video chunks.
+pub fn session_manifest_schema() -> Value {
+    let mut schema = serde_json::to_value(RecordingSession::schema()).unwrap_or_default();
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert(
+            "video_chunks".to_string(),
+            json!({"type": "array", "items": {"type": "string"}}),
+        );
+    }
+    if let Some(required) = schema.get_mut("required").and_then(Value::as_array_mut) {
+        required.push(json!("video_chunks"));
+    }
+    schema
+}
+
+/// How long a frame counts for at most in the time per app, so that time away from the
+/// computer isn't given to the app left in front.
+const MAX_FRAME_SECS: i64 = 120;
+
+/// Time spent in one app during a session.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize)]
+pub struct SessionAppTime {
+    pub app_name: String,
+    pub seconds: i64,
+    pub frame_count: i64,
+}
+
+/// Time per app from the focused windows of a session's frames, in capture order, the app
+/// used most first. Each frame counts until the next one, the last one until `end`.
+pub fn app_breakdown(activity: &[AppActivity], end: DateTime<Utc>) -> Vec<SessionAppTime> {
+    let mut apps: Vec<SessionAppTime> = Vec::new();
+    for (i, frame) in activity.iter().enumerate() {
+        let until = activity.get(i + 1).map_or(end, |next| next.timestamp);
+        let seconds = (until - frame.timestamp)
+            .num_seconds()
+            .clamp(0, MAX_FRAME_SECS);
+        match apps.iter_mut().find(|app| app.app_name == frame.app_name) {
+            Some(app) => {
+                app.seconds += seconds;
+                app.frame_count += 1;
+            }
+            None => apps.push(SessionAppTime {
+                app_name: frame.app_name.clone(),
+                seconds,
+                frame_count: 1,
+            }),
+        }
+    }
+    apps.sort_by(|a, b| b.seconds.cmp(&a.seconds));
+    apps
+}
+
+/// A session with what was recorded during it.
+#[derive(OaSchema, Debug, Clone, Serialize)]
+pub struct SessionDetails {
+    pub session: RecordingSession,
+    /// Until now for the session being recorded.
+    pub duration_secs: i64,
+    pub apps: Vec<SessionAppTime>,
+    pub video_chunks: Vec<String>,
+}
+
+pub async fn session_details(
+    db: &DatabaseManager,
+    id: i64,
+    now: DateTime<Utc>,
+) -> Result<SessionDetails, sqlx::Error> {
+    let session = db.get_recording_session(id).await?;
+    let end = session.ended_at.unwrap_or(now);
+    // a closed session ends at its last frame, which is part of it
+    let activity = db
+        .get_app_activity(session.started_at, end + chrono::Duration::milliseconds(1))
+        .await?;
+    Ok(SessionDetails {
+        duration_secs: (end - session.started_at).num_seconds().max(0),
+        apps: app_breakdown(&activity, end),
+        video_chunks: db.get_recording_session_video_chunks(id).await?,
+        session,
+    })
+}
+
+struct CurrentSession {
+    session: RecordingSession,
+    last_activity: DateTime<Utc>,
+}
+
+/// Tracks the recording session shared by all monitors, splitting it on idle gaps and day
+/// boundaries. Each split closes the session in the database and writes its manifest to
+/// `<root>/<hostname>/sessions/`.
+pub struct SessionTracker {
+    db: Arc<DatabaseManager>,
+    storage: Storage,
+    config: SessionSplitConfig,
+    current: Mutex<Option<CurrentSession>>,
+}
+
+impl SessionTracker {
+    pub async fn new(
+        db: Arc<DatabaseManager>,
+        storage: Storage,
+        config: SessionSplitConfig,
+    ) -> Result<Arc<Self>> {
+        let closed = db.end_open_recording_sessions("interrupted").await?;
+        if closed > 0 {
+            info!("closed {} sessions left open by a previous run", closed);
+        }
+
+        Ok(Arc::new(SessionTracker {
+            db,
+            storage,
+            config,
+            current: Mutex::new(None),
+        }))
+    }
+
+    pub fn idle_gap(&self) -> Option<Duration> {
+        self.config.idle_gap
+    }
+
+    /// Records a captured frame at `now` and returns the id of the session it belongs to,
+    /// splitting the current session first if needed. Callers finalize their current video
+    /// chunk when the returned id changes.
+    pub async fn record_activity(&self, now: DateTime<Utc>) -> Result<i64> {
+        let mut current = self.current.lock().await;
+
+        if let Some(active) = current.as_mut() {
+            match split_reason(
+                &self.config,
+                active.session.started_at,
+                active.last_activity,
+                now,
+            ) {
+                None => {
+                    active.last_activity = now;
+                    return Ok(active.session.id);
+                }
+                Some(reason) => {
+                    let ended_at = active.last_activity;
+                    let session_id = active.session.id;
+                    *current = None;
+                    self.close_session(session_id, ended_at, reason.as_str())
+                        .await;
+                }
+            }
+        }
+
+        let session = self.open_session(None, now).await?;
+        let id = session.id;
+        *current = Some(CurrentSession {
+            session,
+            last_activity: now,
+        });
+        Ok(id)
+    }
+
+    /// Ends the current session and starts one right away, for a sitting started by hand.
+    /// Without a name the session is named after its start time like the automatic ones.
+    pub async fn start_session(
+        &self,
+        name: Option<&str>,
+        notes: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<RecordingSession> {
+        let mut current = self.current.lock().await;
+        if let Some(active) = current.take() {
+            self.close_session(active.session.id, active.last_activity, "manual")
+                .await;
+        }
+
+        let mut session = self.open_session(name, now).await?;
+        if notes.is_some() {
+            session = self
+                .db
+                .update_recording_session(session.id, None, notes)
+                .await?;
+        }
+        *current = Some(CurrentSession {
+            session: session.clone(),
+            last_activity: now,
+        });
+        Ok(session)
+    }
+
+    /// Ends the current session by hand and returns it. Recording goes on, the next frame
+    /// starts a new session.
+    pub async fn stop_session(&self) -> Option<RecordingSession> {
+        let active = self.current.lock().await.take()?;
+        self.close_session(active.session.id, active.last_activity, "stopped")
+            .await
+    }
+
+    /// Starts a session tagged `tag`, closing the current one as [`Self::start_session`] does.
+    pub async fn start_tagged_session(
+        &self,
+        name: &str,
+        tag: &str,
+        now: DateTime<Utc>,
+    ) -> Result<RecordingSession> {
+        let session = self.start_session(Some(name), None, now).await?;
+        let session = self
+            .db
+            .set_recording_session_tag(session.id, Some(tag))
+            .await?;
+        if let Some(active) = self.current.lock().await.as_mut() {
+            if active.session.id == session.id {
+                active.session = session.clone();
+            }
+        }
+        Ok(session)
+    }
+
+    /// Ends the session `id` with `reason` if it's still the current one, the next frame
+    /// starts a new session. Returns the ended session.
+    pub async fn end_session(&self, id: i64, reason: &str) -> Option<RecordingSession> {
+        let mut current = self.current.lock().await;
+        if current.as_ref()?.session.id != id {
+            return None;
+        }
+        let active = current.take()?;
+        self.close_session(active.session.id, active.last_activity, reason)
+            .await
+    }
+
+    /// Ends the current session, e.g. on shutdown.
+    pub async fn finish(&self, reason: &str) {
+        if let Some(active) = self.current.lock().await.take() {
+            self.close_session(active.session.id, active.last_activity, reason)
+                .await;
+        }
+    }
+
+    async fn open_session(
+        &self,
+        name: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<RecordingSession> {
+        let name = name.map(str::to_string).unwrap_or_else(|| {
+            format!(
+                "session {}",
+                now.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            )
+        });
+        let session = self.db.insert_recording_session(&name, now).await?;
+        info!(
+            "started recording session {} ({})",
+            session.id, session.name
+        );
+        let _ = send_event("session_started", session.clone());
+        Ok(session)
+    }
+
+    async fn close_session(
+        &self,
+        id: i64,
+        ended_at: DateTime<Utc>,
+        reason: &str,
+    ) -> Option<RecordingSession> {
+        let session = match self.db.end_recording_session(id, ended_at, reason).await {
+            Ok(session) => session,
+            Err(e) => {
+                error!("failed to end recording session {}: {}", id, e);
+                return None;
+            }
+        };
+        info!("ended recording session {} ({})", session.id, reason);
+
+        if let Err(e) = self.write_manifest(&session).await {
+            error!("failed to write manifest for session {}: {}", session.id, e);
+        }
+        let _ = send_event("session_ended", session.clone());
+        Some(session)
+    }
+
+    async fn write_manifest(&self, session: &RecordingSession) -> Result<()> {
+        let manifest = SessionManifest {
+            session,
+            video_chunks: self
+                .db
+                .get_recording_session_video_chunks(session.id)
+                .await?,
+        };
+        let path = session_manifest_path(&self.storage, session);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&manifest)?).await?;
+        Ok(())
+    }
+}
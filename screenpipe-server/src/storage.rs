@@ -0,0 +1,555 @@
+use crate::categorization::categorize;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use screenpipe_db::DatabaseManager;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+const LOCK_FILE_NAME: &str = "screenpipe.lock";
+const JOURNAL_DIR_NAME: &str = "journal";
+/// Records a journal grows to before it is rewritten with only the frames not indexed yet.
+const MAX_JOURNAL_RECORDS: usize = 10_000;
+
+/// Output layout of recorded media: `<root>/<hostname>/<date>/monitor_<id>/<file>`.
+///
+/// Namespacing by hostname lets several machines share one synced folder, and the per-host
+/// lock file keeps two recorders (e.g. the app and the CLI) from writing into the same tree.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    root: PathBuf,
+    hostname: String,
+}
+
+/// Content of the lock file, identifying the recording session that owns the tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLockInfo {
+    pub pid: u32,
+    pub session_id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Held for as long as a session records into a [`Storage`]; the lock file is removed on drop.
+#[derive(Debug)]
+pub struct StorageLock {
+    path: PathBuf,
+    pub info: SessionLockInfo,
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("failed to remove lock file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Name of this machine as it appears in paths, `localhost` when it has none.
+pub fn local_hostname() -> String {
+    System::new()
+        .host_name()
+        .map(|name| sanitize_component(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+impl Storage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::with_hostname(root, local_hostname())
+    }
+
+    pub fn with_hostname(root: impl Into<PathBuf>, hostname: impl Into<String>) -> Self {
+        Storage {
+            root: root.into(),
+            hostname: hostname.into(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding everything recorded on this machine.
+    pub fn host_dir(&self) -> PathBuf {
+        self.root.join(&self.hostname)
+    }
+
+    pub fn day_dir(&self, time: DateTime<Utc>) -> PathBuf {
+        self.host_dir().join(time.format("%Y-%m-%d").to_string())
+    }
+
+    pub fn monitor_dir(&self, monitor_id: u32, time: DateTime<Utc>) -> PathBuf {
+        self.day_dir(time).join(format!("monitor_{}", monitor_id))
+    }
+
+    /// Path of a new video chunk for `monitor_id`, creating its directory if needed.
+    pub fn video_chunk_path(&self, monitor_id: u32, time: DateTime<Utc>) -> Result<PathBuf> {
+        let dir = self.monitor_dir(monitor_id, time);
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!(
+            "monitor_{}_{}.mp4",
+            monitor_id,
+            time.format("%Y-%m-%d_%H-%M-%S")
+        )))
+    }
+
+    /// Path of the video an imported recording that started at `time` is encoded to:
+    /// `<date>/imported/imported_<time>.mp4`. Creates its directory.
+    pub fn imported_video_path(&self, time: DateTime<Utc>) -> Result<PathBuf> {
+        let dir = self.day_dir(time).join("imported");
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!("imported_{}.mp4", time.format("%Y-%m-%d_%H-%M-%S"))))
+    }
+
+    /// Path of a frame kept as an image, sharded by hour under the monitor's directory:
+    /// `monitor_<id>/frames/<hour>/monitor_<id>_<time>.<extension>`. Creates its directory.
+    pub fn frame_image_path(
+        &self,
+        monitor_id: u32,
+        time: DateTime<Utc>,
+        extension: &str,
+    ) -> Result<PathBuf> {
+        let dir = self
+            .monitor_dir(monitor_id, time)
+            .join("frames")
+            .join(time.format("%H").to_string());
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!(
+            "monitor_{}_{}.{}",
+            monitor_id,
+            time.format("%Y-%m-%d_%H-%M-%S-%3f"),
+            extension
+        )))
+    }
+
+    /// Write-ahead journal of the frames encoded into the videos of `monitor_id`.
+    pub fn journal_path(&self, monitor_id: u32) -> PathBuf {
+        self.host_dir()
+            .join(JOURNAL_DIR_NAME)
+            .join(format!("monitor_{}.jsonl", monitor_id))
+    }
+
+    /// The journal of `monitor_id`, shared by the encoder that appends the frames it wrote and
+    /// the indexer that appends the frames it indexed.
+    pub fn frame_journal(&self, monitor_id: u32) -> Result<Arc<FrameJournal>> {
+        shared_journal(self.journal_path(monitor_id))
+    }
+
+    /// Drops the frames captured between `start_time` and `end_time` from the journals, so
+    /// the windows, titles and URLs of an excised range aren't left on disk.
+    pub fn excise_frame_journals(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<usize> {
+        let dir = self.host_dir().join(JOURNAL_DIR_NAME);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut excised = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "jsonl")
+            {
+                continue;
+            }
+            excised += shared_journal(path)?.excise(start_time, end_time)?;
+        }
+        Ok(excised)
+    }
+
+    /// Indexes the frames the journals say were encoded but never indexed, as when the last
+    /// run was killed with frames still queued for OCR, then clears the journals. The frames
+    /// are indexed with their windows but without text. Must run before recording starts.
+    ///
+    /// Returns the number of frames recovered.
+    pub async fn recover_frame_journals(&self, db: &DatabaseManager) -> Result<usize> {
+        let dir = self.host_dir().join(JOURNAL_DIR_NAME);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut recovered = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "jsonl")
+            {
+                continue;
+            }
+            for frame in pending_frames(read_journal(&path)?) {
+                // the chunk never made it to disk, there is nothing to point the rows at
+                if !Path::new(&frame.chunk_path).exists() {
+                    debug!(
+                        "dropping journaled frame {} of missing chunk {}",
+                        frame.frame_number, frame.chunk_path
+                    );
+                    continue;
+                }
+                let mut inserted = false;
+                for window in &frame.windows {
+                    inserted |= db
+                        .insert_recovered_frame(
+                            &frame.chunk_path,
+                            frame.offset_index,
+                            &frame.device_name,
+                            frame.timestamp,
+                            window.browser_url.as_deref(),
+                            &window.app_name,
+                            &window.window_name,
+                            window.focused,
+                            categorize(
+                                &window.app_name,
+                                &window.window_name,
+                                window.browser_url.as_deref(),
+                            )
+                            .as_deref(),
+                        )
+                        .await?
+                        .is_some();
+                }
+                if inserted {
+                    recovered += 1;
+                }
+            }
+            fs::remove_file(&path)?;
+        }
+        Ok(recovered)
+    }
+
+    pub fn lock_path(&self) -> PathBuf {
+        self.host_dir().join(LOCK_FILE_NAME)
+    }
+
+    /// Takes the session lock for this machine's output tree.
+    ///
+    /// Fails if another live process holds it. A lock left behind by a crashed process is
+    /// detected through its pid, and the start time of the process holding that pid now, and
+    /// taken over.
+    pub fn lock(&self) -> Result<StorageLock> {
+        fs::create_dir_all(self.host_dir())?;
+        let path = self.lock_path();
+        let info = SessionLockInfo {
+            pid: std::process::id(),
+            session_id: Uuid::new_v4().to_string(),
+            started_at: Utc::now(),
+        };
+
+        // written whole aside and linked into place, so that the lock is never seen half
+        // written and only one process can link it
+        let pending = path.with_extension(format!("{}.tmp", info.session_id));
+        fs::write(&pending, serde_json::to_string(&info)?)?;
+        let result = self.link_lock(&path, &pending, &info);
+        let _ = fs::remove_file(&pending);
+        result
+    }
+
+    fn link_lock(
+        &self,
+        path: &Path,
+        pending: &Path,
+        info: &SessionLockInfo,
+    ) -> Result<StorageLock> {
+        for _ in 0..3 {
+            match fs::hard_link(pending, path).or_else(|e| match e.kind() {
+                // file systems without hard links get the lock created in place
+                ErrorKind::Unsupported => OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)?
+                    .write_all(serde_json::to_string(info)?.as_bytes()),
+                _ => Err(e),
+            }) {
+                Ok(()) => {
+                    info!(
+                        "acquired storage lock {} (session {})",
+                        path.display(),
+                        info.session_id
+                    );
+                    return Ok(StorageLock {
+                        path: path.to_path_buf(),
+                        info: info.clone(),
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let owner = read_lock_info(path);
+                    if let Some(owner) = owner
+                        .as_ref()
+                        .filter(|owner| owner.pid != info.pid && is_lock_owner_alive(owner))
+                    {
+                        return Err(anyhow!(
+                            "another screenpipe instance (pid {}, session {}) is already recording into {}",
+                            owner.pid,
+                            owner.session_id,
+                            self.host_dir().display()
+                        ));
+                    }
+                    warn!("removing stale lock file {}", path.display());
+                    remove_stale_lock(path, owner.as_ref(), &info.session_id)?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(anyhow!("failed to acquire storage lock {}", path.display()))
+    }
+}
+
+fn read_lock_info(path: &Path) -> Option<SessionLockInfo> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// Removes the lock at `path` read as `stale`. It's moved aside first and put back when it
+/// turns out to be another, taken meanwhile by a process that found the same stale lock.
+fn remove_stale_lock(path: &Path, stale: Option<&SessionLockInfo>, session_id: &str) -> Result<()> {
+    let aside = path.with_extension(format!("{}.stale", session_id));
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    }
+    let moved = read_lock_info(&aside).map(|owner| owner.session_id);
+    if moved.is_some() && moved != stale.map(|owner| owner.session_id.clone()) {
+        let _ = fs::hard_link(&aside, path);
+    }
+    fs::remove_file(&aside)?;
+    Ok(())
+}
+
+/// Whether the process that took `owner` still runs: a process has its pid and started before
+/// the lock was taken, a later one reuses the pid of the process that crashed.
+fn is_lock_owner_alive(owner: &SessionLockInfo) -> bool {
+    let pid = sysinfo::Pid::from_u32(owner.pid);
+    let mut sys = System::new();
+    if !sys.refresh_process(pid) {
+        return false;
+    }
+    sys.process(pid).map_or(false, |process| {
+        // start times are in whole seconds
+        process.start_time() as i64 <= owner.started_at.timestamp() + 1
+    })
+}
+
+/// Keeps a hostname usable as a single path component on every platform.
+pub(crate) fn sanitize_component(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+static JOURNALS: Lazy<Mutex<HashMap<PathBuf, Arc<FrameJournal>>>> = Lazy::new(Default::default);
+
+fn shared_journal(path: PathBuf) -> Result<Arc<FrameJournal>> {
+    let mut journals = JOURNALS.lock().unwrap();
+    if let Some(journal) = journals.get(&path) {
+        return Ok(Arc::clone(journal));
+    }
+    let journal = Arc::new(FrameJournal::open(&path)?);
+    journals.insert(path, Arc::clone(&journal));
+    Ok(journal)
+}
+
+/// A window of a journaled frame, as it is indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalWindow {
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub focused: bool,
+}
+
+/// A frame written to the encoder of a video chunk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodedFrame {
+    /// Number of the capture, shared by the encoder and the indexer.
+    pub frame_number: u64,
+    pub chunk_path: String,
+    /// Index of the frame in the chunk.
+    pub offset_index: i64,
+    pub device_name: String,
+    pub timestamp: DateTime<Utc>,
+    pub windows: Vec<JournalWindow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalRecord {
+    Encoded(EncodedFrame),
+    /// The rows of the frame were written to the index.
+    Indexed {
+        frame_number: u64,
+    },
+}
+
+/// Append-only journal of a monitor's frames, one JSON record a line, written before the
+/// index is so the index can be completed after a crash.
+///
+/// Records are written straight to the file without buffering, so they survive the process
+/// being killed.
+#[derive(Debug)]
+pub struct FrameJournal {
+    path: PathBuf,
+    state: Mutex<JournalFile>,
+}
+
+#[derive(Debug)]
+struct JournalFile {
+    file: File,
+    records: usize,
+    /// Records at which the journal is compacted next, raised while the indexer lags far
+    /// behind so that it isn't rewritten on every frame.
+    compact_at: usize,
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+impl FrameJournal {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let records = if path.exists() {
+            read_journal(path)?.len()
+        } else {
+            0
+        };
+        Ok(FrameJournal {
+            path: path.to_path_buf(),
+            state: Mutex::new(JournalFile {
+                file: open_append(path)?,
+                records,
+                compact_at: MAX_JOURNAL_RECORDS,
+            }),
+        })
+    }
+
+    pub fn append(&self, record: &JournalRecord) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.records >= state.compact_at {
+            state.records = self.compact()?;
+            state.compact_at = MAX_JOURNAL_RECORDS.max(state.records * 2);
+            state.file = open_append(&self.path)?;
+        }
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        // a single write, so a record is never interleaved with another
+        state.file.write_all(line.as_bytes())?;
+        state.records += 1;
+        Ok(())
+    }
+
+    /// Rewrites the journal without the frames captured between `start_time` and `end_time`,
+    /// compacting it. Returns how many of its frames were not indexed yet and are dropped.
+    pub fn excise(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        let pending = pending_frames(read_journal(&self.path)?);
+        let total = pending.len();
+        let kept: Vec<_> = pending
+            .into_iter()
+            .filter(|frame| frame.timestamp < start_time || frame.timestamp > end_time)
+            .collect();
+        state.records = self.rewrite(&kept)?;
+        state.file = open_append(&self.path)?;
+        Ok(total - kept.len())
+    }
+
+    /// Rewrites the journal with only the frames not indexed yet, returns how many there are.
+    fn compact(&self) -> Result<usize> {
+        self.rewrite(&pending_frames(read_journal(&self.path)?))
+    }
+
+    fn rewrite(&self, pending: &[EncodedFrame]) -> Result<usize> {
+        let tmp = self.path.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp)?;
+        for frame in pending {
+            let mut line = serde_json::to_string(&JournalRecord::Encoded(frame.clone()))?;
+            line.push('\n');
+            file.write_all(line.as_bytes())?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(pending.len())
+    }
+}
+
+/// Reads the records of a journal. A last line cut short by a crash is skipped.
+pub fn read_journal(path: &Path) -> Result<Vec<JournalRecord>> {
+    let mut records = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => warn!("skipping unreadable record of {}: {}", path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+/// The frames encoded and not indexed, in the order they were encoded. A frame can be
+/// indexed before the encoder gets to it, and frame numbers start over when the capture of a
+/// monitor restarts, so an indexed record matches the oldest unmatched frame of its number,
+/// or the next one encoded.
+pub fn pending_frames(records: impl IntoIterator<Item = JournalRecord>) -> Vec<EncodedFrame> {
+    let mut frames: Vec<(EncodedFrame, bool)> = Vec::new();
+    let mut unmatched_frames: HashMap<u64, VecDeque<usize>> = HashMap::new();
+    let mut unmatched_indexed: HashMap<u64, usize> = HashMap::new();
+    for record in records {
+        match record {
+            JournalRecord::Encoded(frame) => {
+                let early = unmatched_indexed.get_mut(&frame.frame_number);
+                if let Some(count) = early.filter(|count| **count > 0) {
+                    *count -= 1;
+                    frames.push((frame, true));
+                    continue;
+                }
+                unmatched_frames
+                    .entry(frame.frame_number)
+                    .or_default()
+                    .push_back(frames.len());
+                frames.push((frame, false));
+            }
+            JournalRecord::Indexed { frame_number } => {
+                match unmatched_frames
+                    .get_mut(&frame_number)
+                    .and_then(VecDeque::pop_front)
+                {
+                    Some(position) => frames[position].1 = true,
+                    None => *unmatched_indexed.entry(frame_number).or_default() += 1,
+                }
+            }
+        }
+    }
+    frames
+        .into_iter()
+        .filter(|(_, indexed)| !indexed)
+        .map(|(frame, _)| frame)
+        .collect()
+}
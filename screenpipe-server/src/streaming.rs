@@ -0,0 +1,152 @@
+use crate::video::MAX_FPS;
+use image::{DynamicImage, ImageFormat};
+use once_cell::sync::Lazy;
+use screenpipe_core::find_ffmpeg_path;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+/// Latest JPEG-encoded frame of a monitor, shared by every live sink (RTMP push, MJPEG endpoint).
+pub type LiveFrame = Option<Arc<Vec<u8>>>;
+
+static LIVE_FRAMES: Lazy<Mutex<HashMap<u32, watch::Sender<LiveFrame>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn live_sender(monitor_id: u32) -> watch::Sender<LiveFrame> {
+    LIVE_FRAMES
+        .lock()
+        .unwrap()
+        .entry(monitor_id)
+        .or_insert_with(|| watch::channel(None).0)
+        .clone()
+}
+
+/// Subscribes to the live frames of a monitor. Frames are only encoded while at least one
+/// receiver is alive, so an idle stream costs nothing.
+pub fn subscribe_live_frames(monitor_id: u32) -> watch::Receiver<LiveFrame> {
+    live_sender(monitor_id).subscribe()
+}
+
+/// Publishes a captured frame to the live sinks of `monitor_id`, if anyone is watching.
+pub fn publish_live_frame(monitor_id: u32, image: &DynamicImage) {
+    let sender = live_sender(monitor_id);
+    if sender.receiver_count() == 0 {
+        return;
+    }
+
+    let mut buffer = Vec::new();
+    match image
+        .to_rgb8()
+        .write_to(&mut std::io::Cursor::new(&mut buffer), ImageFormat::Jpeg)
+    {
+        Ok(_) => {
+            sender.send_replace(Some(Arc::new(buffer)));
+        }
+        Err(e) => error!(
+            "Failed to encode live frame for monitor {}: {}",
+            monitor_id, e
+        ),
+    }
+}
+
+/// Expands the `{monitor_id}` placeholder so each monitor pushes to its own stream key.
+pub fn stream_url_for_monitor(stream_url: &str, monitor_id: u32) -> String {
+    stream_url.replace("{monitor_id}", &monitor_id.to_string())
+}
+
+fn stream_output_format(stream_url: &str) -> &'static str {
+    if stream_url.starts_with("rtmp://") || stream_url.starts_with("rtmps://") {
+        "flv"
+    } else if stream_url.starts_with("rtsp://") {
+        "rtsp"
+    } else {
+        "mpegts"
+    }
+}
+
+/// Spawns an ffmpeg process reading JPEG frames from stdin and pushing H.264 to `stream_url`.
+pub async fn start_stream_process(stream_url: &str, fps: f64) -> Result<Child, anyhow::Error> {
+    let fps = fps.min(MAX_FPS);
+    let fps_str = fps.to_string();
+    // keyframe every two seconds so viewers can join quickly
+    let gop_str = ((fps * 2.0).ceil() as u32).max(1).to_string();
+
+    info!("Starting FFmpeg live stream to: {}", stream_url);
+    let mut command = Command::new(find_ffmpeg_path().unwrap());
+    command
+        .args([
+            "-f",
+            "image2pipe",
+            "-vcodec",
+            "mjpeg",
+            "-r",
+            fps_str.as_str(),
+            "-i",
+            "-",
+            "-vf",
+            "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2",
+            "-vcodec",
+            "libx264",
+            "-preset",
+            "veryfast",
+            "-tune",
+            "zerolatency",
+            "-g",
+            gop_str.as_str(),
+            "-pix_fmt",
+            "yuv420p",
+            "-f",
+            stream_output_format(stream_url),
+            stream_url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    debug!("FFmpeg stream command: {:?}", command);
+
+    Ok(command.spawn()?)
+}
+
+/// Pushes the live frames of `monitor_id` to `stream_url` until the process is stopped,
+/// restarting ffmpeg when the remote end drops the connection.
+pub async fn stream_monitor_to_url(monitor_id: u32, stream_url: String, fps: f64) {
+    let mut receiver = subscribe_live_frames(monitor_id);
+
+    loop {
+        let mut child = match start_stream_process(&stream_url, fps).await {
+            Ok(child) => child,
+            Err(e) => {
+                error!(
+                    "Failed to start live stream for monitor {}: {}",
+                    monitor_id, e
+                );
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let mut stdin = child.stdin.take().expect("Failed to open stdin");
+
+        while receiver.changed().await.is_ok() {
+            let frame = receiver.borrow_and_update().clone();
+            if let Some(frame) = frame {
+                if let Err(e) = stdin.write_all(&frame).await {
+                    warn!(
+                        "Live stream for monitor {} interrupted: {}, restarting",
+                        monitor_id, e
+                    );
+                    break;
+                }
+            }
+        }
+
+        drop(stdin);
+        let _ = child.kill().await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
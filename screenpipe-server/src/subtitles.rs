@@ -0,0 +1,291 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use screenpipe_db::{DatabaseManager, VideoChunkFrameText};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Maximum number of heading-like OCR lines shown per cue, next to the window title.
+const MAX_HEADINGS_PER_CUE: usize = 2;
+/// Lines longer than this are body text rather than headings.
+const MAX_HEADING_CHARS: usize = 80;
+/// How long the last cue of a chunk stays visible after its frame.
+const LAST_CUE_DURATION: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+/// Returns the path of the WebVTT sidecar for a video chunk (`<chunk>.vtt`).
+pub fn sidecar_path(video_path: &Path) -> PathBuf {
+    video_path.with_extension("vtt")
+}
+
+/// Formats a cue offset as a WebVTT timestamp (`HH:MM:SS.mmm`).
+pub fn format_timestamp(offset: Duration) -> String {
+    let millis = offset.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}
+
+/// Picks the lines of a frame's OCR output that look like headings.
+///
+/// When the OCR engine reports line heights in `text_json` (apple native OCR), lines noticeably
+/// taller than the median are kept. Otherwise short, capitalized lines without trailing
+/// punctuation are used as a best-effort fallback.
+pub fn notable_lines(text: &str, text_json: Option<&str>, max: usize) -> Vec<String> {
+    let mut sized: Vec<(f64, String)> = text_json
+        .and_then(|json| serde_json::from_str::<Vec<serde_json::Value>>(json).ok())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            let line = entry.get("text")?.as_str()?.trim();
+            let height = entry.get("height")?.as_str()?.parse::<f64>().ok()?;
+            is_heading_candidate(line).then(|| (height, line.to_string()))
+        })
+        .collect();
+
+    if sized.len() >= 2 {
+        let mut heights: Vec<f64> = sized.iter().map(|(h, _)| *h).collect();
+        heights.sort_by(|a, b| a.total_cmp(b));
+        let median = heights[heights.len() / 2];
+        sized.sort_by(|a, b| b.0.total_cmp(&a.0));
+        return dedup_lines(
+            sized
+                .into_iter()
+                .filter(|(height, _)| *height >= median * 1.4)
+                .map(|(_, line)| line),
+            max,
+        );
+    }
+
+    dedup_lines(
+        text.lines()
+            .map(str::trim)
+            .filter(|line| {
+                is_heading_candidate(line)
+                    && line.chars().next().is_some_and(char::is_uppercase)
+                    && !line.ends_with(['.', ',', ';', ':'])
+            })
+            .map(String::from),
+        max,
+    )
+}
+
+fn is_heading_candidate(line: &str) -> bool {
+    let len = line.chars().count();
+    (3..=MAX_HEADING_CHARS).contains(&len) && line.chars().any(char::is_alphabetic)
+}
+
+fn dedup_lines(lines: impl Iterator<Item = String>, max: usize) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in lines {
+        if out.len() >= max {
+            break;
+        }
+        if !out.contains(&line) {
+            out.push(line);
+        }
+    }
+    out
+}
+
+fn cue_text(frame: &VideoChunkFrameText) -> String {
+    let app_name = frame.app_name.as_deref().unwrap_or_default();
+    let window_name = frame.window_name.as_deref().unwrap_or_default();
+    let title = match (app_name.is_empty(), window_name.is_empty()) {
+        (false, false) => format!("{} - {}", app_name, window_name),
+        (false, true) => app_name.to_string(),
+        (true, false) => window_name.to_string(),
+        (true, true) => String::new(),
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    if !title.is_empty() {
+        lines.push(title);
+    }
+    lines.extend(
+        notable_lines(
+            &frame.text,
+            frame.text_json.as_deref(),
+            MAX_HEADINGS_PER_CUE,
+        )
+        .into_iter()
+        .filter(|line| line != window_name),
+    );
+    lines.join("\n")
+}
+
+/// Builds cues from the frames of a single chunk, timed from `chunk_start`, the capture time of
+/// its first frame. Only focused windows are used when the chunk has any, and consecutive
+/// frames showing the same content are merged into one cue.
+pub fn build_cues(frames: &[VideoChunkFrameText], chunk_start: DateTime<Utc>) -> Vec<SubtitleCue> {
+    let has_focused = frames.iter().any(|f| f.focused.unwrap_or(false));
+    let frames: Vec<&VideoChunkFrameText> = frames
+        .iter()
+        .filter(|f| !has_focused || f.focused.unwrap_or(false))
+        .collect();
+
+    let mut cues: Vec<SubtitleCue> = Vec::new();
+    let mut previous_text: Option<String> = None;
+    for frame in frames {
+        let offset = (frame.timestamp - chunk_start).to_std().unwrap_or_default();
+        let text = cue_text(frame);
+
+        let showing_cue = previous_text.as_deref().is_some_and(|t| !t.is_empty());
+        if previous_text.as_deref() == Some(text.as_str()) {
+            if let Some(last) = cues.last_mut().filter(|_| showing_cue) {
+                last.end = offset + LAST_CUE_DURATION;
+            }
+            continue;
+        }
+        // content changed, close the cue that was on screen
+        if let Some(last) = cues.last_mut().filter(|_| showing_cue) {
+            last.end = offset.max(last.start);
+        }
+        previous_text = Some(text.clone());
+        if !text.is_empty() {
+            cues.push(SubtitleCue {
+                start: offset,
+                end: offset + LAST_CUE_DURATION,
+                text,
+            });
+        }
+    }
+    cues
+}
+
+fn escape_cue_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Renders cues as a WebVTT document.
+pub fn render_webvtt(cues: &[SubtitleCue]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "\n{}\n{} --> {}\n{}\n",
+            i + 1,
+            format_timestamp(cue.start),
+            format_timestamp(cue.end),
+            escape_cue_text(&cue.text)
+        ));
+    }
+    out
+}
+
+/// Writes the WebVTT sidecar next to `video_path` from the OCR text stored for that chunk.
+/// Returns `None` when the chunk has no OCR data yet.
+pub async fn write_chunk_sidecar(
+    db: &DatabaseManager,
+    video_path: &str,
+) -> Result<Option<PathBuf>> {
+    // the first frame of the chunk may have no text, or not be focused, it still starts the video
+    let Some(chunk_start) = db
+        .get_video_chunk_frames(video_path)
+        .await?
+        .first()
+        .map(|frame| frame.timestamp)
+    else {
+        debug!("no frames in chunk {}, skipping sidecar", video_path);
+        return Ok(None);
+    };
+    let frames = db.get_video_chunk_frame_texts(video_path).await?;
+    let cues = build_cues(&frames, chunk_start);
+    if cues.is_empty() {
+        debug!("no OCR cues for chunk {}, skipping sidecar", video_path);
+        return Ok(None);
+    }
+
+    let path = sidecar_path(Path::new(video_path));
+    tokio::fs::write(&path, render_webvtt(&cues)).await?;
+    debug!("wrote {} subtitle cues to {}", cues.len(), path.display());
+    Ok(Some(path))
+}
+
+/// Chunks finalized while the OCR of their last frames was still queued, with when they were.
+static PENDING_SIDECARS: Lazy<Mutex<Vec<(String, DateTime<Utc>)>>> = Lazy::new(Default::default);
+
+/// Queues the sidecar of a chunk finalized at `finalized_at`, written by
+/// [`write_ready_sidecars`] once the frames captured before then are indexed.
+pub fn queue_chunk_sidecar(video_path: &str, finalized_at: DateTime<Utc>) {
+    PENDING_SIDECARS
+        .lock()
+        .unwrap()
+        .push((video_path.to_string(), finalized_at));
+}
+
+/// Writes the queued sidecars of the chunks of `device_name` finalized before `indexed_until`,
+/// the capture time of the frame just indexed: the frames of these chunks were queued for OCR
+/// before it, so their text is all stored.
+pub async fn write_ready_sidecars(
+    db: &DatabaseManager,
+    device_name: &str,
+    indexed_until: DateTime<Utc>,
+) {
+    let ready: Vec<String> = {
+        let mut pending = PENDING_SIDECARS.lock().unwrap();
+        let (ready, waiting) = pending.drain(..).partition(|(video_path, finalized_at)| {
+            *finalized_at <= indexed_until && chunk_of_device(video_path, device_name)
+        });
+        *pending = waiting;
+        ready
+            .into_iter()
+            .map(|(video_path, _)| video_path)
+            .collect()
+    };
+    for video_path in ready {
+        if let Err(e) = write_chunk_sidecar(db, &video_path).await {
+            warn!("Failed to write subtitles for {}: {}", video_path, e);
+        }
+    }
+}
+
+/// Writes the sidecars still queued and those of the chunks being recorded, when recording
+/// stops and no more OCR results are coming.
+pub async fn write_remaining_sidecars(db: &DatabaseManager, monitor_ids: &[u32]) {
+    let mut video_paths: Vec<String> = PENDING_SIDECARS
+        .lock()
+        .unwrap()
+        .drain(..)
+        .map(|(video_path, _)| video_path)
+        .collect();
+    for monitor_id in monitor_ids {
+        match db
+            .get_latest_video_chunk_path(&format!("monitor_{}", monitor_id))
+            .await
+        {
+            Ok(Some(video_path)) if !video_paths.contains(&video_path) => {
+                video_paths.push(video_path)
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to get video chunk of monitor {}: {}", monitor_id, e),
+        }
+    }
+    for video_path in video_paths {
+        if let Err(e) = write_chunk_sidecar(db, &video_path).await {
+            warn!("Failed to write subtitles for {}: {}", video_path, e);
+        }
+    }
+}
+
+/// Whether `video_path` is a chunk of `device_name`, `monitor_1` recording `monitor_1_*.mp4`.
+fn chunk_of_device(video_path: &str, device_name: &str) -> bool {
+    Path::new(video_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(device_name))
+        .is_some_and(|rest| rest.starts_with('_'))
+}
@@ -0,0 +1,381 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use oasgen::OaSchema;
+use once_cell::sync::OnceCell;
+use reqwest::Client;
+use screenpipe_db::{
+    AppUsage, ContentType, DatabaseManager, OcrTextSample, SearchResult, UNDETERMINED_LANGUAGE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::Write;
+use tracing::{debug, warn};
+
+/// Prompts are cut to this many characters, which fits the context of small local models.
+pub const MAX_PROMPT_CHARS: usize = 24_000;
+/// Characters kept of each OCR sample and transcript.
+const MAX_ENTRY_CHARS: usize = 400;
+
+/// OpenAI-compatible chat completions endpoint used to write summaries, e.g. a local ollama or
+/// a hosted api with a key.
+#[derive(Debug, Clone)]
+pub struct LlmBackend {
+    pub url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl Default for LlmBackend {
+    fn default() -> Self {
+        LlmBackend {
+            url: "http://localhost:11434/v1".to_string(),
+            model: "llama3.2".to_string(),
+            api_key: None,
+        }
+    }
+}
+
+static LLM_BACKEND: OnceCell<LlmBackend> = OnceCell::new();
+
+/// Sets the backend used for summaries. Only the first call has an effect.
+pub fn set_llm_backend(backend: LlmBackend) {
+    if LLM_BACKEND.set(backend).is_err() {
+        warn!("llm backend already set");
+    }
+}
+
+pub fn llm_backend() -> &'static LlmBackend {
+    LLM_BACKEND.get_or_init(LlmBackend::default)
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+impl LlmBackend {
+    pub async fn complete(&self, system: &str, prompt: &str) -> Result<String> {
+        let mut request = Client::new()
+            .post(format!(
+                "{}/chat/completions",
+                self.url.trim_end_matches('/')
+            ))
+            .json(&json!({
+                "model": self.model,
+                "stream": false,
+                "messages": [
+                    { "role": "system", "content": system },
+                    { "role": "user", "content": prompt },
+                ],
+            }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("llm api at {} not reachable: {}", self.url, e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("llm api returned {}: {}", status, body));
+        }
+
+        response
+            .json::<ChatCompletionResponse>()
+            .await?
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content.trim().to_string())
+            .ok_or_else(|| anyhow!("llm api returned no choices"))
+    }
+}
+
+/// A transcript line of the summarized range.
+#[derive(Debug, Clone)]
+pub struct TranscriptEntry {
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub text: String,
+}
+
+/// Everything recorded in a time range that goes into a summary.
+#[derive(Debug, Default)]
+pub struct ActivityDigest {
+    pub apps: Vec<AppUsage>,
+    pub screen_text: Vec<OcrTextSample>,
+    pub transcripts: Vec<TranscriptEntry>,
+}
+
+impl ActivityDigest {
+    pub fn is_empty(&self) -> bool {
+        self.apps.is_empty() && self.screen_text.is_empty() && self.transcripts.is_empty()
+    }
+
+    /// Most frequent detected language of the screen text, if any was detected.
+    pub fn dominant_language(&self) -> Option<&str> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for sample in &self.screen_text {
+            if let Some(language) = sample
+                .language
+                .as_deref()
+                .filter(|l| *l != UNDETERMINED_LANGUAGE)
+            {
+                *counts.entry(language).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(language, _)| language)
+    }
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub summary: String,
+    pub model: String,
+    pub apps: usize,
+    pub screen_samples: usize,
+    pub transcripts: usize,
+}
+
+/// Collects app usage, sampled screen text and transcripts between `start` and `end`.
+pub async fn gather_activity(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<ActivityDigest> {
+    // about 60 screen samples per range, whatever its length
+    let bucket_secs = ((end - start).num_seconds() / 60).max(30);
+    let apps = db.get_app_usage(start, end, 30).await?;
+    let screen_text = db
+        .get_ocr_text_samples(start, end, bucket_secs, 120)
+        .await?;
+
+    let mut transcripts: Vec<TranscriptEntry> = db
+        .search(
+            "",
+            ContentType::Audio,
+            500,
+            0,
+            Some(start),
+            Some(end),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .into_iter()
+        .filter_map(|result| match result {
+            SearchResult::Audio(audio) => Some(TranscriptEntry {
+                timestamp: audio.timestamp,
+                device_name: audio.device_name,
+                text: audio.transcription,
+            }),
+            _ => None,
+        })
+        .filter(|entry| !entry.text.trim().is_empty())
+        .collect();
+    transcripts.sort_by_key(|entry| entry.timestamp);
+
+    Ok(ActivityDigest {
+        apps,
+        screen_text,
+        transcripts,
+    })
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+pub const SUMMARY_SYSTEM_PROMPT: &str = "You summarize what a person did on their computer from \
+    what was recorded on their screen and microphone. Write a short overview followed by a few \
+    bullet points in chronological order, naming the apps, documents, websites, people and topics \
+    involved. Only state what the data supports, and don't repeat the raw text.";
+
+/// User prompt describing `digest`, cut to `max_chars`. Times are written in local time, since
+/// that is how the person will ask about them.
+pub fn build_summary_prompt(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    digest: &ActivityDigest,
+    max_chars: usize,
+) -> String {
+    let local = |time: DateTime<Utc>| time.with_timezone(&Local).format("%H:%M").to_string();
+    let mut prompt = format!(
+        "Summarize my activity between {} and {} on {}.\n",
+        local(start),
+        local(end),
+        start.with_timezone(&Local).format("%A %Y-%m-%d")
+    );
+    if let Some(language) = digest.dominant_language() {
+        let _ = writeln!(
+            prompt,
+            "Most of the text on screen is in language '{}'; translate what you quote, and \
+             answer in English unless I wrote in another language.",
+            language
+        );
+    }
+
+    if !digest.apps.is_empty() {
+        prompt.push_str("\nApps and windows, most used first:\n");
+        for app in &digest.apps {
+            let _ = writeln!(
+                prompt,
+                "- {} — {} ({}–{}, {} frames)",
+                app.app_name,
+                truncate(&app.window_name, 120),
+                local(app.first_seen),
+                local(app.last_seen),
+                app.frame_count
+            );
+        }
+    }
+
+    let mut entries: Vec<(DateTime<Utc>, String)> = digest
+        .screen_text
+        .iter()
+        .map(|sample| {
+            (
+                sample.timestamp,
+                format!(
+                    "[{}] screen, {} — {}: {}",
+                    local(sample.timestamp),
+                    sample.app_name,
+                    truncate(&sample.window_name, 80),
+                    truncate(&sample.text, MAX_ENTRY_CHARS)
+                ),
+            )
+        })
+        .chain(digest.transcripts.iter().map(|entry| {
+            (
+                entry.timestamp,
+                format!(
+                    "[{}] audio, {}: {}",
+                    local(entry.timestamp),
+                    entry.device_name,
+                    truncate(&entry.text, MAX_ENTRY_CHARS)
+                ),
+            )
+        }))
+        .collect();
+    entries.sort_by_key(|(timestamp, _)| *timestamp);
+
+    if !entries.is_empty() {
+        prompt.push_str("\nTimeline of screen text and speech:\n");
+        for (_, entry) in entries {
+            if prompt.chars().count() + entry.chars().count() + 1 > max_chars {
+                prompt.push_str("[later entries cut]\n");
+                break;
+            }
+            prompt.push_str(&entry);
+            prompt.push('\n');
+        }
+    }
+    prompt
+}
+
+/// Summarizes everything recorded between `start` and `end` with `backend`.
+pub async fn summarize_range(
+    db: &DatabaseManager,
+    backend: &LlmBackend,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Summary> {
+    if end <= start {
+        return Err(anyhow!("end of the range must be after its start"));
+    }
+    let digest = gather_activity(db, start, end).await?;
+    let summary = if digest.is_empty() {
+        "Nothing was recorded in this time range.".to_string()
+    } else {
+        let prompt = build_summary_prompt(start, end, &digest, MAX_PROMPT_CHARS);
+        debug!("summarizing {} chars of activity", prompt.len());
+        backend.complete(SUMMARY_SYSTEM_PROMPT, &prompt).await?
+    };
+
+    Ok(Summary {
+        start_time: start,
+        end_time: end,
+        summary,
+        model: backend.model.clone(),
+        apps: digest.apps.len(),
+        screen_samples: digest.screen_text.len(),
+        transcripts: digest.transcripts.len(),
+    })
+}
+
+/// Parses a time given on the command line: RFC 3339, `YYYY-MM-DD HH:MM`, or a time of the day
+/// `today` such as `14:00`, `2pm` or `2:30pm`.
+pub fn parse_time_arg(value: &str, today: NaiveDate) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+        .ok()
+        .or_else(|| parse_time_of_day(value).map(|time| today.and_time(time)))
+        .ok_or_else(|| {
+            anyhow!(
+                "can't parse time '{}', use e.g. 14:00, 2pm or an RFC 3339 timestamp",
+                value
+            )
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("'{}' doesn't exist in the local timezone", value))
+}
+
+fn parse_time_of_day(value: &str) -> Option<NaiveTime> {
+    let lower = value.to_lowercase();
+    let (clock, offset) = if let Some(clock) = lower.strip_suffix("am") {
+        (clock.trim(), Some(0))
+    } else if let Some(clock) = lower.strip_suffix("pm") {
+        (clock.trim(), Some(12))
+    } else {
+        (lower.as_str(), None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if offset.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match offset {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(offset) => hour % 12 + offset,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
@@ -0,0 +1,130 @@
+use crate::cli::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use futures::StreamExt;
+use screenpipe_events::Event;
+use serde_json::Value;
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// Events shown unless `--all` is given: what is being recorded, not every internal event.
+pub const TAIL_EVENTS: &[&str] = &[
+    "activity_changed",
+    "frame_written",
+    "app_blocked",
+    "session_started",
+    "session_ended",
+    "meeting_started",
+    "meeting_ended",
+];
+
+fn local_time(event: &Event) -> String {
+    event.data["timestamp"]
+        .as_str()
+        .and_then(|timestamp| timestamp.parse::<DateTime<Utc>>().ok())
+        .map(|timestamp| timestamp.with_timezone(&Local))
+        .unwrap_or_else(Local::now)
+        .format("%H:%M:%S")
+        .to_string()
+}
+
+fn window(data: &Value) -> String {
+    let app_name = data["app_name"].as_str().unwrap_or_default();
+    match data["window_name"].as_str().unwrap_or_default() {
+        "" => app_name.to_string(),
+        window_name => format!("{} — {}", app_name, window_name),
+    }
+}
+
+/// One line describing `event` for the terminal, `None` for events that aren't shown.
+pub fn format_event(event: &Event, all: bool) -> Option<String> {
+    let data = &event.data;
+    let line = match event.name.as_str() {
+        "activity_changed" => format!("▸ {} (monitor {})", window(data), data["monitor_id"]),
+        "frame_written" => format!(
+            "  frame {} on monitor {}: {} ({} chars)",
+            data["frame_id"],
+            data["monitor_id"],
+            window(data),
+            data["text_length"]
+        ),
+        "app_blocked" => format!(
+            "▪ a blocked window is focused, not recorded (monitor {})",
+            data["monitor_id"]
+        ),
+        "session_started" => format!(
+            "── session {} started: {}",
+            data["id"],
+            data["name"].as_str().unwrap_or_default()
+        ),
+        "session_ended" => format!(
+            "── session {} ended ({})",
+            data["id"],
+            data["end_reason"].as_str().unwrap_or("stopped")
+        ),
+        "meeting_started" => format!(
+            "── meeting started in {}",
+            data["app"].as_str().unwrap_or_default()
+        ),
+        "meeting_ended" => format!(
+            "── meeting ended in {}",
+            data["app"].as_str().unwrap_or_default()
+        ),
+        name if all => format!("{}: {}", name, data),
+        _ => return None,
+    };
+    Some(format!("{}  {}", local_time(event), line))
+}
+
+/// Prints the live events of the screenpipe running on `port` until interrupted, reconnecting
+/// when it restarts.
+pub async fn tail_events(
+    port: u16,
+    api_key: Option<&str>,
+    output: &OutputFormat,
+    all: bool,
+) -> Result<()> {
+    let mut url = format!("ws://localhost:{}/ws/events?images=false", port);
+    if let Some(api_key) = api_key {
+        url.push_str(&format!("&api_key={}", api_key));
+    }
+
+    let mut connected_once = false;
+    loop {
+        let mut socket = match connect_async(&url).await {
+            Ok((socket, _)) => socket,
+            Err(e) if !connected_once => {
+                return Err(anyhow!("screenpipe is not running on port {}: {}", port, e));
+            }
+            Err(_) => {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+        if connected_once {
+            eprintln!("reconnected to screenpipe");
+        }
+        connected_once = true;
+
+        while let Some(message) = socket.next().await {
+            let Ok(Message::Text(text)) = message else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_str::<Event>(&text) else {
+                continue;
+            };
+            if !all && !TAIL_EVENTS.contains(&event.name.as_str()) {
+                continue;
+            }
+            match output {
+                OutputFormat::Json => println!("{}", text),
+                OutputFormat::Text => {
+                    if let Some(line) = format_event(&event, all) {
+                        println!("{}", line);
+                    }
+                }
+            }
+        }
+        eprintln!("lost connection to screenpipe, retrying...");
+    }
+}
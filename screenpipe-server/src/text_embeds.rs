@@ -1,49 +1,218 @@
-use anyhow::Result;
+use crate::embedding::embedding_endpoint::get_or_initialize_model;
+use crate::jobs::{enqueue_job, JobProgress};
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
 use reqwest::Client;
+use screenpipe_db::{DatabaseManager, Job};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Embeds OCR text and transcripts recorded since the last run.
+pub const EMBED_TEXT_JOB: &str = "embed_text";
+
+/// Texts sent to the model at once.
+pub const EMBEDDING_BATCH_SIZE: u32 = 32;
+/// Texts embedded per job, so a large backlog doesn't hold a worker for hours.
+pub const MAX_TEXTS_PER_JOB: u32 = 1024;
+
+/// Where embeddings come from. Vectors of different models can't be compared, so switching
+/// provider only makes sense on a fresh database.
+#[derive(Debug, Clone)]
+pub enum EmbeddingProvider {
+    /// The bundled model, run in-process.
+    Local,
+    /// An OpenAI-compatible `/embeddings` endpoint, e.g. ollama, openai or a llama.cpp server.
+    Api {
+        url: String,
+        model: String,
+        api_key: Option<String>,
+    },
+}
+
+impl Default for EmbeddingProvider {
+    fn default() -> Self {
+        EmbeddingProvider::Api {
+            url: "http://localhost:11434/v1".to_string(),
+            model: "nomic-embed-text".to_string(),
+            api_key: None,
+        }
+    }
+}
+
+static EMBEDDING_PROVIDER: OnceCell<EmbeddingProvider> = OnceCell::new();
+
+/// Sets the provider used for indexing and semantic search. Only the first call has an effect.
+pub fn set_embedding_provider(provider: EmbeddingProvider) {
+    if EMBEDDING_PROVIDER.set(provider).is_err() {
+        warn!("embedding provider already set");
+    }
+}
+
+pub fn embedding_provider() -> &'static EmbeddingProvider {
+    EMBEDDING_PROVIDER.get_or_init(EmbeddingProvider::default)
+}
 
 #[derive(Debug, Serialize)]
-struct OllamaRequest {
-    model: String,
-    prompt: String,
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingsData>,
 }
 
 #[derive(Debug, Deserialize)]
-struct OllamaResponse {
+struct EmbeddingsData {
     embedding: Vec<f32>,
+    index: usize,
 }
 
-/// Generates embeddings for text using Ollama's nomic-embed-text model
-pub async fn generate_embedding(text: &str, frame_id: i64) -> Result<Vec<f32>> {
-    let client = Client::new();
-    
-    debug!("generating embedding for frame_id: {}, text: {}", frame_id, text);
-
-    // Check if Ollama server is running
-    if let Err(e) = client.get("http://localhost:11434/api/version").send().await {
-        error!("ollama server not running: {}", e);
-        return Err(anyhow::anyhow!("ollama server not running"));
+impl EmbeddingProvider {
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self {
+            EmbeddingProvider::Local => {
+                let model = get_or_initialize_model().await?;
+                let model = model.lock().await;
+                model.generate_batch_embeddings(texts)
+            }
+            EmbeddingProvider::Api {
+                url,
+                model,
+                api_key,
+            } => {
+                let mut request = Client::new()
+                    .post(format!("{}/embeddings", url.trim_end_matches('/')))
+                    .json(&EmbeddingsRequest {
+                        model,
+                        input: texts,
+                    });
+                if let Some(api_key) = api_key {
+                    request = request.bearer_auth(api_key);
+                }
+
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|e| anyhow!("embedding api at {} not reachable: {}", url, e))?;
+                if !response.status().is_success() {
+                    return Err(anyhow!(
+                        "failed to generate embedding: {}",
+                        response.status()
+                    ));
+                }
+
+                let mut data = response.json::<EmbeddingsResponse>().await?.data;
+                if data.len() != texts.len() {
+                    return Err(anyhow!(
+                        "embedding api returned {} vectors for {} texts",
+                        data.len(),
+                        texts.len()
+                    ));
+                }
+                data.sort_by_key(|data| data.index);
+                Ok(data.into_iter().map(|data| data.embedding).collect())
+            }
+        }
     }
+}
+
+/// Generates the embedding of a single text with the configured provider
+pub async fn generate_embedding(text: &str, frame_id: i64) -> Result<Vec<f32>> {
+    debug!("generating embedding for frame_id: {}", frame_id);
+    embedding_provider()
+        .embed(&[text.to_string()])
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow!("no embedding returned"))
+}
+
+/// Embeds up to `limit` OCR texts and transcripts that don't have an embedding yet, returning
+/// how many were stored.
+pub async fn embed_pending_texts(
+    db: &DatabaseManager,
+    limit: u32,
+    progress: Option<&JobProgress>,
+) -> Result<u32> {
+    let provider = embedding_provider();
+    let mut embedded = 0;
 
-    let request = OllamaRequest {
-        model: "nomic-embed-text".to_string(),
-        prompt: text.to_string(),
-    };
+    while embedded < limit {
+        let batch = EMBEDDING_BATCH_SIZE.min(limit - embedded);
+        let frames = db.get_ocr_text_without_embeddings(batch).await?;
+        let transcriptions = db.get_transcriptions_without_embeddings(batch).await?;
+        if frames.is_empty() && transcriptions.is_empty() {
+            break;
+        }
 
-    let response = client
-        .post("http://localhost:11434/api/embeddings")
-        .json(&request)
-        .send()
-        .await?;
+        let texts: Vec<String> = frames.iter().map(|(_, text)| text.clone()).collect();
+        for ((frame_id, _), embedding) in frames.iter().zip(provider.embed(&texts).await?) {
+            db.insert_embeddings(*frame_id, serde_json::to_string(&embedding)?)
+                .await?;
+        }
+        let texts: Vec<String> = transcriptions
+            .iter()
+            .map(|(_, text)| text.clone())
+            .collect();
+        for ((id, _), embedding) in transcriptions.iter().zip(provider.embed(&texts).await?) {
+            db.insert_transcription_embedding(*id, serde_json::to_string(&embedding)?)
+                .await?;
+        }
 
-    if !response.status().is_success() {
-        error!("failed to generate embedding: {}", response.status());
-        return Err(anyhow::anyhow!("failed to generate embedding"));
+        embedded += (frames.len() + transcriptions.len()) as u32;
+        if let Some(progress) = progress {
+            progress
+                .report((embedded as f64 / limit as f64).min(1.0))
+                .await;
+        }
     }
 
-    let embedding = response.json::<OllamaResponse>().await?;
-    info!("generated embedding for frame_id: {}", frame_id);
-    
-    Ok(embedding.embedding)
+    Ok(embedded)
+}
+
+pub(crate) async fn embed_text_job(
+    db: Arc<DatabaseManager>,
+    _job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let embedded = embed_pending_texts(&db, MAX_TEXTS_PER_JOB, Some(&progress)).await?;
+    info!("embedded {} texts for semantic search", embedded);
+    Ok(())
+}
+
+/// Queues an embedding job every `interval` while there is text left to embed.
+pub async fn schedule_embedding_jobs(db: Arc<DatabaseManager>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let has_pending_text = match (
+            db.get_ocr_text_without_embeddings(1).await,
+            db.get_transcriptions_without_embeddings(1).await,
+        ) {
+            (Ok(frames), Ok(transcriptions)) => !frames.is_empty() || !transcriptions.is_empty(),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("failed to look for text to embed: {}", e);
+                continue;
+            }
+        };
+        if !has_pending_text {
+            continue;
+        }
+
+        match db.count_unfinished_jobs(EMBED_TEXT_JOB).await {
+            Ok(0) => {
+                if let Err(e) = enqueue_job(&db, EMBED_TEXT_JOB, &serde_json::json!({}), 3).await {
+                    warn!("failed to queue embedding job: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("failed to count embedding jobs: {}", e),
+        }
+    }
 }
@@ -0,0 +1,238 @@
+use crate::jobs::{ChunkJobPayload, JobProgress};
+use crate::pyramid::pyramid_keyframe_path;
+use crate::video_utils::extract_frame_from_video;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, FrameLocation, Job};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Renders the thumbnail strip of a finished chunk.
+pub const BUILD_THUMBNAIL_STRIP_JOB: &str = "build_thumbnail_strip";
+
+/// Attempts of a strip, which waits for the re-encode and pyramid of its chunk by failing.
+pub const THUMBNAIL_MAX_ATTEMPTS: i64 = 10;
+
+/// Time between two thumbnails of a chunk.
+pub const THUMBNAIL_INTERVAL_SECS: i64 = 60;
+
+/// Size of a thumbnail cell, frames are fitted in it and letterboxed.
+pub const THUMBNAIL_WIDTH: u32 = 160;
+pub const THUMBNAIL_HEIGHT: u32 = 90;
+
+/// Thumbnails per row of the sprite, so long chunks stay within WebP's dimensions.
+pub const THUMBNAIL_COLUMNS: u32 = 10;
+
+/// One thumbnail of a strip, at `(x, y)` in its sprite.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Thumbnail {
+    pub frame_id: i64,
+    pub offset_index: i64,
+    pub timestamp: DateTime<Utc>,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Index of the sprite of a chunk, stored as `<chunk>_thumbs.json` next to it.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailStrip {
+    /// File name of the WebP sprite, in the same directory.
+    pub sprite: String,
+    pub thumbnail_width: u32,
+    pub thumbnail_height: u32,
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+fn strip_path(video_path: &str, extension: &str) -> PathBuf {
+    let path = Path::new(video_path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}_thumbs.{}", stem, extension))
+}
+
+/// Sprite of the thumbnails of a chunk, next to it: `monitor_1_..._thumbs.webp`.
+pub fn thumbnail_sprite_path(video_path: &str) -> PathBuf {
+    strip_path(video_path, "webp")
+}
+
+/// Index of the sprite of a chunk, next to it: `monitor_1_..._thumbs.json`.
+pub fn thumbnail_index_path(video_path: &str) -> PathBuf {
+    strip_path(video_path, "json")
+}
+
+/// The first frame of each interval from the chunk's first frame, frames in capture order.
+pub fn select_thumbnail_frames(
+    frames: &[FrameLocation],
+    interval: chrono::Duration,
+) -> Vec<&FrameLocation> {
+    let mut selected: Vec<&FrameLocation> = Vec::new();
+    let mut next_at = None;
+    for frame in frames {
+        if next_at.is_none_or(|next_at| frame.timestamp >= next_at) {
+            // gaps skip ahead to the frame rather than leaving empty intervals
+            next_at = Some(frame.timestamp + interval);
+            selected.push(frame);
+        }
+    }
+    selected
+}
+
+/// Top left corner of the thumbnail at `index` in the sprite.
+pub fn thumbnail_position(index: usize) -> (u32, u32) {
+    let index = index as u32;
+    (
+        (index % THUMBNAIL_COLUMNS) * THUMBNAIL_WIDTH,
+        (index / THUMBNAIL_COLUMNS) * THUMBNAIL_HEIGHT,
+    )
+}
+
+/// Draws the images into a sprite, in rows of [`THUMBNAIL_COLUMNS`], each fitted in its cell.
+pub fn render_thumbnail_sprite(images: &[DynamicImage]) -> RgbImage {
+    let count = images.len().max(1) as u32;
+    let columns = count.min(THUMBNAIL_COLUMNS);
+    let rows = count.div_ceil(THUMBNAIL_COLUMNS);
+    let mut sprite = RgbImage::from_pixel(
+        columns * THUMBNAIL_WIDTH,
+        rows * THUMBNAIL_HEIGHT,
+        Rgb([16, 16, 16]),
+    );
+    for (index, image) in images.iter().enumerate() {
+        let thumbnail = image
+            .resize(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Triangle)
+            .to_rgb8();
+        let (x, y) = thumbnail_position(index);
+        image::imageops::replace(
+            &mut sprite,
+            &thumbnail,
+            (x + (THUMBNAIL_WIDTH - thumbnail.width()) / 2) as i64,
+            (y + (THUMBNAIL_HEIGHT - thumbnail.height()) / 2) as i64,
+        );
+    }
+    sprite
+}
+
+async fn load_frame(file_path: &str, offset_index: i64) -> Result<DynamicImage> {
+    // the 1/16 pyramid level is about the thumbnail size and avoids decoding the video, its
+    // closest keyframe stands for the frame
+    if let Some(pyramid_path) = pyramid_keyframe_path(file_path, 2, offset_index) {
+        return Ok(image::open(pyramid_path)?);
+    }
+    let frame_path = extract_frame_from_video(file_path, offset_index).await?;
+    let image = image::open(&frame_path);
+    let _ = tokio::fs::remove_file(&frame_path).await;
+    Ok(image?)
+}
+
+/// Renders and stores the thumbnail strip of a chunk, returning its index.
+pub async fn build_thumbnail_strip(
+    db: &DatabaseManager,
+    video_path: &str,
+    progress: Option<&JobProgress>,
+) -> Result<ThumbnailStrip> {
+    let frames = db.get_video_chunk_frames(video_path).await?;
+    let selected =
+        select_thumbnail_frames(&frames, chrono::Duration::seconds(THUMBNAIL_INTERVAL_SECS));
+    if selected.is_empty() {
+        return Err(anyhow!("no frames indexed in {}", video_path));
+    }
+
+    let mut images = Vec::with_capacity(selected.len());
+    let mut thumbnails = Vec::with_capacity(selected.len());
+    for (i, frame) in selected.iter().enumerate() {
+        match load_frame(&frame.file_path, frame.offset_index).await {
+            Ok(image) => {
+                let (x, y) = thumbnail_position(images.len());
+                thumbnails.push(Thumbnail {
+                    frame_id: frame.frame_id,
+                    offset_index: frame.offset_index,
+                    timestamp: frame.timestamp,
+                    x,
+                    y,
+                });
+                images.push(image);
+            }
+            Err(e) => debug!("skipping frame {} in thumbnails: {}", frame.frame_id, e),
+        }
+        if let Some(progress) = progress {
+            progress
+                .report((i + 1) as f64 / selected.len() as f64)
+                .await;
+        }
+    }
+    if images.is_empty() {
+        return Err(anyhow!("no frame of {} could be decoded", video_path));
+    }
+
+    let sprite_path = thumbnail_sprite_path(video_path);
+    let sprite = render_thumbnail_sprite(&images);
+    let output = sprite_path.clone();
+    tokio::task::spawn_blocking(move || {
+        DynamicImage::ImageRgb8(sprite).save_with_format(output, ImageFormat::WebP)
+    })
+    .await??;
+
+    let strip = ThumbnailStrip {
+        sprite: sprite_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        thumbnail_width: THUMBNAIL_WIDTH,
+        thumbnail_height: THUMBNAIL_HEIGHT,
+        thumbnails,
+    };
+    // the index is written last, a strip is only listed once its sprite is complete
+    tokio::fs::write(
+        thumbnail_index_path(video_path),
+        serde_json::to_string(&strip)?,
+    )
+    .await?;
+    info!(
+        "rendered {} thumbnails of {}",
+        strip.thumbnails.len(),
+        video_path
+    );
+    Ok(strip)
+}
+
+/// The strip of a chunk, `None` when it wasn't built.
+pub async fn read_thumbnail_strip(video_path: &str) -> Result<Option<ThumbnailStrip>> {
+    match tokio::fs::read_to_string(thumbnail_index_path(video_path)).await {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub(crate) async fn build_thumbnail_strip_job(
+    db: Arc<DatabaseManager>,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    if !Path::new(&payload.video_path).exists() {
+        return Err(anyhow!(
+            "video chunk {} no longer exists",
+            payload.video_path
+        ));
+    }
+    // the chunk may still be re-encoded, or its pyramid, read instead of the video, built
+    if db
+        .count_unfinished_jobs_before(job.id, &job.payload)
+        .await?
+        > 0
+    {
+        return Err(anyhow!(
+            "{} still has jobs to run before its thumbnails",
+            payload.video_path
+        ));
+    }
+    build_thumbnail_strip(&db, &payload.video_path, Some(&progress)).await?;
+    Ok(())
+}
@@ -0,0 +1,496 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use oasgen::OaSchema;
+use regex::{Regex, RegexBuilder};
+use screenpipe_db::AppActivity;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Read from the data directory on each export, so rule edits apply right away.
+pub const TIME_TRACKING_CONFIG_FILE: &str = "time_tracking.json";
+
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeTrackingFormat {
+    /// CSV in the columns of Toggl Track's import.
+    TogglCsv,
+    /// JSON for `timew import`.
+    Timewarrior,
+    /// An iCalendar file with an event per entry.
+    Ical,
+}
+
+impl TimeTrackingFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TimeTrackingFormat::TogglCsv => "text/csv; charset=utf-8",
+            TimeTrackingFormat::Timewarrior => "application/json",
+            TimeTrackingFormat::Ical => "text/calendar; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TimeTrackingFormat::TogglCsv => "csv",
+            TimeTrackingFormat::Timewarrior => "json",
+            TimeTrackingFormat::Ical => "ics",
+        }
+    }
+}
+
+/// Assigns the time spent in matching windows to a project. `app` and `window` are
+/// case-insensitive regexes, a missing one matches anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRule {
+    #[serde(default)]
+    pub app: Option<String>,
+    #[serde(default)]
+    pub window: Option<String>,
+    pub project: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeTrackingConfig {
+    /// Tried in order, the first match wins.
+    #[serde(default)]
+    pub rules: Vec<ProjectRule>,
+    /// Project of the time no rule matches, which is left out when not set.
+    #[serde(default)]
+    pub default_project: Option<String>,
+    /// Longest pause between frames still counted as time in the same window.
+    #[serde(default = "default_max_gap_secs")]
+    pub max_gap_secs: i64,
+    /// Entries shorter than this are dropped, e.g. a glance at a chat while switching apps.
+    #[serde(default = "default_min_entry_secs")]
+    pub min_entry_secs: i64,
+    /// Email of the Toggl workspace member the entries are imported for.
+    #[serde(default)]
+    pub toggl_email: Option<String>,
+}
+
+fn default_max_gap_secs() -> i64 {
+    120
+}
+
+fn default_min_entry_secs() -> i64 {
+    60
+}
+
+impl Default for TimeTrackingConfig {
+    fn default() -> Self {
+        TimeTrackingConfig {
+            rules: Vec::new(),
+            default_project: None,
+            max_gap_secs: default_max_gap_secs(),
+            min_entry_secs: default_min_entry_secs(),
+            toggl_email: None,
+        }
+    }
+}
+
+impl TimeTrackingConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: TimeTrackingConfig = serde_json::from_str(content)?;
+        config.matchers()?;
+        if config.max_gap_secs <= 0 {
+            return Err(anyhow!("max_gap_secs must be positive"));
+        }
+        Ok(config)
+    }
+
+    /// The config at `path`, the default one when there's no file.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read time tracking config {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    fn matchers(&self) -> Result<Vec<RuleMatcher<'_>>> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                Ok(RuleMatcher {
+                    patterns: ActivityPatterns::compile(
+                        rule.app.as_deref(),
+                        rule.window.as_deref(),
+                        None,
+                    )?,
+                    rule,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The patterns of a rule matching activities, here and in `categories.json`: case-insensitive
+/// regexes that must match somewhere in the app name, window title or browser url, a missing
+/// one matching anything.
+#[derive(Debug, Clone)]
+pub(crate) struct ActivityPatterns {
+    app: Option<Regex>,
+    window: Option<Regex>,
+    url: Option<Regex>,
+}
+
+impl ActivityPatterns {
+    pub(crate) fn compile(
+        app: Option<&str>,
+        window: Option<&str>,
+        url: Option<&str>,
+    ) -> Result<Self> {
+        let compile = |pattern: Option<&str>| {
+            pattern
+                .map(|pattern| {
+                    RegexBuilder::new(pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|e| anyhow!("invalid pattern {:?}: {}", pattern, e))
+                })
+                .transpose()
+        };
+        Ok(Self {
+            app: compile(app)?,
+            window: compile(window)?,
+            url: compile(url)?,
+        })
+    }
+
+    /// A url pattern never matches activities without a url, like those of apps other than
+    /// browsers.
+    pub(crate) fn matches(
+        &self,
+        app_name: &str,
+        window_name: &str,
+        browser_url: Option<&str>,
+    ) -> bool {
+        self.app.as_ref().map_or(true, |app| app.is_match(app_name))
+            && self
+                .window
+                .as_ref()
+                .map_or(true, |window| window.is_match(window_name))
+            && self.url.as_ref().map_or(true, |url| {
+                browser_url.is_some_and(|browser_url| url.is_match(browser_url))
+            })
+    }
+}
+
+struct RuleMatcher<'a> {
+    patterns: ActivityPatterns,
+    rule: &'a ProjectRule,
+}
+
+impl RuleMatcher<'_> {
+    fn matches(&self, activity: &AppActivity) -> bool {
+        self.patterns
+            .matches(&activity.app_name, &activity.window_name, None)
+    }
+}
+
+/// A stretch of time spent on one project in one app.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimeEntry {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub project: String,
+    pub app_name: String,
+    /// The window most of the time went to.
+    pub window_name: String,
+    pub tags: Vec<String>,
+    /// Category of the frames by the rules of `categories.json`, an entry has only one.
+    pub category: Option<String>,
+}
+
+impl TimeEntry {
+    pub fn duration_secs(&self) -> i64 {
+        (self.end - self.start).num_seconds()
+    }
+
+    /// The tags exported with the entry: its rule's, then its category.
+    pub fn export_tags(&self) -> Vec<String> {
+        let mut tags = self.tags.clone();
+        if let Some(category) = &self.category {
+            if !tags.contains(category) {
+                tags.push(category.clone());
+            }
+        }
+        tags
+    }
+}
+
+struct OpenEntry {
+    entry: TimeEntry,
+    windows: Vec<(String, i64)>,
+}
+
+impl OpenEntry {
+    fn add_window(&mut self, window_name: &str, secs: i64) {
+        match self
+            .windows
+            .iter_mut()
+            .find(|(name, _)| name == window_name)
+        {
+            Some((_, total)) => *total += secs,
+            None => self.windows.push((window_name.to_string(), secs)),
+        }
+    }
+
+    /// Whether `next` picks up where this entry ends, in the same project and app, so that
+    /// only a change of category parts them.
+    fn continues_into(&self, next: &OpenEntry) -> bool {
+        self.entry.project == next.entry.project
+            && self.entry.tags == next.entry.tags
+            && self.entry.app_name == next.entry.app_name
+            && self.entry.end >= next.entry.start
+    }
+
+    /// Extends this entry over `other`, which continues it or is continued by it, keeping this
+    /// entry's category.
+    fn absorb(&mut self, other: OpenEntry) {
+        self.entry.start = self.entry.start.min(other.entry.start);
+        self.entry.end = self.entry.end.max(other.entry.end);
+        for (window_name, secs) in other.windows {
+            self.add_window(&window_name, secs);
+        }
+    }
+
+    fn close(mut self) -> TimeEntry {
+        if let Some((window_name, _)) = self.windows.iter().max_by_key(|(_, secs)| *secs) {
+            self.entry.window_name = window_name.clone();
+        }
+        self.entry
+    }
+}
+
+/// Turns the windows of the frames, in capture order, into time entries. Each frame counts
+/// until the next one, or for `max_gap_secs` at most when nothing follows.
+pub fn time_entries(
+    activity: &[AppActivity],
+    config: &TimeTrackingConfig,
+) -> Result<Vec<TimeEntry>> {
+    let matchers = config.matchers()?;
+    let max_gap = chrono::Duration::seconds(config.max_gap_secs);
+
+    let mut entries = Vec::new();
+    let mut open: Option<OpenEntry> = None;
+    for (i, frame) in activity.iter().enumerate() {
+        let until = activity
+            .get(i + 1)
+            .map(|next| next.timestamp.min(frame.timestamp + max_gap))
+            .unwrap_or(frame.timestamp + max_gap);
+
+        let (project, tags) = match matchers.iter().find(|matcher| matcher.matches(frame)) {
+            Some(matcher) => (matcher.rule.project.clone(), matcher.rule.tags.clone()),
+            None => match &config.default_project {
+                Some(project) => (project.clone(), Vec::new()),
+                None => {
+                    entries.extend(open.take());
+                    continue;
+                }
+            },
+        };
+
+        if let Some(current) = open.as_mut() {
+            let continues = current.entry.project == project
+                && current.entry.app_name == frame.app_name
+                && current.entry.category == frame.category
+                && current.entry.end >= frame.timestamp;
+            if continues {
+                current.entry.end = until;
+                current.add_window(&frame.window_name, (until - frame.timestamp).num_seconds());
+                continue;
+            }
+        }
+        entries.extend(open.take());
+        let mut entry = OpenEntry {
+            entry: TimeEntry {
+                start: frame.timestamp,
+                end: until,
+                project,
+                app_name: frame.app_name.clone(),
+                window_name: frame.window_name.clone(),
+                tags,
+                category: frame.category.clone(),
+            },
+            windows: Vec::new(),
+        };
+        entry.add_window(&frame.window_name, (until - frame.timestamp).num_seconds());
+        open = Some(entry);
+    }
+    entries.extend(open);
+
+    Ok(merge_short_entries(entries, config.min_entry_secs)
+        .into_iter()
+        .map(OpenEntry::close)
+        .filter(|entry| entry.duration_secs() >= config.min_entry_secs)
+        .collect())
+}
+
+/// Folds the entries shorter than `min_entry_secs` into the entry of the same project and app
+/// they continue, or that continues them, so that time parted from it by a change of category
+/// is still counted, only under the category of the longer entry.
+fn merge_short_entries(entries: Vec<OpenEntry>, min_entry_secs: i64) -> Vec<OpenEntry> {
+    let is_short = |entry: &OpenEntry| entry.entry.duration_secs() < min_entry_secs;
+    let mut merged: Vec<OpenEntry> = Vec::with_capacity(entries.len());
+    for mut entry in entries {
+        if let Some(previous) = merged.last_mut() {
+            if previous.continues_into(&entry) {
+                if is_short(&entry) {
+                    previous.absorb(entry);
+                    continue;
+                }
+                if is_short(previous) {
+                    let previous = merged.pop().expect("there is a previous entry");
+                    entry.absorb(previous);
+                }
+            }
+        }
+        merged.push(entry);
+    }
+    merged
+}
+
+fn description(entry: &TimeEntry) -> String {
+    if entry.window_name.is_empty() {
+        entry.app_name.clone()
+    } else {
+        format!("{} – {}", entry.app_name, entry.window_name)
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Renders `entries` in `format`.
+pub fn render_entries(
+    entries: &[TimeEntry],
+    format: TimeTrackingFormat,
+    config: &TimeTrackingConfig,
+) -> Result<String> {
+    match format {
+        TimeTrackingFormat::TogglCsv => Ok(to_toggl_csv(entries, config)),
+        TimeTrackingFormat::Timewarrior => to_timewarrior(entries),
+        TimeTrackingFormat::Ical => Ok(to_ical(entries, Utc::now())),
+    }
+}
+
+pub fn to_toggl_csv(entries: &[TimeEntry], config: &TimeTrackingConfig) -> String {
+    let email = config.toggl_email.as_deref().unwrap_or_default();
+    let mut csv = String::from("Email,Project,Description,Start date,Start time,Duration,Tags\n");
+    for entry in entries {
+        let start = entry.start.with_timezone(&Local);
+        let secs = entry.duration_secs();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{:02}:{:02}:{:02},{}",
+            csv_field(email),
+            csv_field(&entry.project),
+            csv_field(&description(entry)),
+            start.format("%Y-%m-%d"),
+            start.format("%H:%M:%S"),
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60,
+            csv_field(&entry.export_tags().join(", "))
+        );
+    }
+    csv
+}
+
+pub fn to_timewarrior(entries: &[TimeEntry]) -> Result<String> {
+    let intervals: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let mut tags = vec![entry.project.clone(), entry.app_name.clone()];
+            tags.extend(entry.export_tags());
+            json!({
+                "start": entry.start.format("%Y%m%dT%H%M%SZ").to_string(),
+                "end": entry.end.format("%Y%m%dT%H%M%SZ").to_string(),
+                "tags": tags,
+                "annotation": entry.window_name,
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&intervals)?)
+}
+
+fn ical_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Folds a content line at 75 octets, as iCalendar requires.
+fn fold_ical_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+pub fn to_ical(entries: &[TimeEntry], now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut ical = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//screenpipe//time tracking//EN",
+        "CALSCALE:GREGORIAN",
+    ] {
+        fold_ical_line(&mut ical, line);
+    }
+    for entry in entries {
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!(
+                "UID:{}-{}@screenpipe",
+                entry.start.timestamp(),
+                ical_text(&entry.app_name).replace(' ', "-")
+            ),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", entry.start.format("%Y%m%dT%H%M%SZ")),
+            format!("DTEND:{}", entry.end.format("%Y%m%dT%H%M%SZ")),
+            format!(
+                "SUMMARY:{}",
+                ical_text(&format!("{}: {}", entry.project, entry.app_name))
+            ),
+            format!("DESCRIPTION:{}", ical_text(&entry.window_name)),
+        ];
+        let tags = entry.export_tags();
+        if !tags.is_empty() {
+            lines.push(format!(
+                "CATEGORIES:{}",
+                tags.iter()
+                    .map(|tag| ical_text(tag))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+        }
+        lines.push("END:VEVENT".to_string());
+        for line in &lines {
+            fold_ical_line(&mut ical, line);
+        }
+    }
+    fold_ical_line(&mut ical, "END:VCALENDAR");
+    ical
+}
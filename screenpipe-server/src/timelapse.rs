@@ -0,0 +1,359 @@
+use crate::import::video_dimensions;
+use crate::jobs::{enqueue_job, JobProgress};
+use crate::mosaic::local_day_bounds;
+use crate::storage::Storage;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::{DatabaseManager, Job};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+/// Renders the sped-up recording of a local date, one video per monitor.
+pub const RENDER_TIMELAPSE_JOB: &str = "render_timelapse";
+
+/// Frame rate of the rendered timelapses, the sped-up frames beyond it are dropped.
+const TIMELAPSE_FPS: u32 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelapsePayload {
+    pub date: NaiveDate,
+    /// How many times faster than the recording the timelapse plays.
+    pub speed: u32,
+}
+
+/// Where the timelapse of a monitor is stored:
+/// `<root>/<hostname>/timelapses/<date>_<device name>.mp4`.
+pub fn timelapse_path(storage: &Storage, date: NaiveDate, device_name: &str) -> PathBuf {
+    storage.host_dir().join("timelapses").join(format!(
+        "{}_{}.mp4",
+        date.format("%Y-%m-%d"),
+        device_name
+    ))
+}
+
+/// The offsets of the frames making up the timelapse of a chunk, which has a frame at each
+/// of its `frame_times`, the offset and capture time of its frames in order. A frame stays on
+/// screen until the next one was captured, however long capture paused or skipped frames in
+/// between, and frames shorter than a frame of the timelapse are left out.
+pub fn timelapse_frames(frame_times: &[(i64, DateTime<Utc>)], speed: u32) -> Vec<i64> {
+    let (Some((_, first)), Some((_, last))) = (frame_times.first(), frame_times.last()) else {
+        return Vec::new();
+    };
+    let mut shown = Vec::new();
+    let mut frame = 0;
+    for slot in 0.. {
+        // the recording time the frame of the timelapse stands for
+        let at = *first
+            + chrono::Duration::milliseconds(
+                slot * speed.max(1) as i64 * 1000 / TIMELAPSE_FPS as i64,
+            );
+        if at > *last {
+            break;
+        }
+        while frame_times
+            .get(frame + 1)
+            .is_some_and(|(_, time)| *time <= at)
+        {
+            frame += 1;
+        }
+        shown.push(frame_times[frame].0);
+    }
+    shown
+}
+
+/// ffmpeg arguments rendering to `output` the frames of [`timelapse_frames`], read from stdin
+/// as `rgb24` raw video of `size`, `speed` times faster with the local time of each frame
+/// burned in. The first frame was captured at `first_frame_at`.
+pub fn timelapse_part_args(
+    size: (u32, u32),
+    output: &Path,
+    first_frame_at: DateTime<Utc>,
+    speed: u32,
+) -> Vec<String> {
+    // the clock is drawn before speeding up, while timestamps are still those of the recording
+    let filter = format!(
+        "drawtext=text='%{{pts\\:localtime\\:{}\\:%T}}':x=w-tw-24:y=h-th-24:fontsize=h/20:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=8,setpts=PTS/{}",
+        first_frame_at.timestamp(),
+        speed.max(1)
+    );
+    vec![
+        "-y".to_string(),
+        "-v".to_string(),
+        "error".to_string(),
+        "-f".to_string(),
+        "rawvideo".to_string(),
+        "-pix_fmt".to_string(),
+        "rgb24".to_string(),
+        "-s".to_string(),
+        format!("{}x{}", size.0, size.1),
+        // each frame stands for as much of the recording as a frame of the timelapse shows
+        "-framerate".to_string(),
+        format!("{}/{}", TIMELAPSE_FPS, speed.max(1)),
+        "-i".to_string(),
+        "-".to_string(),
+        "-vf".to_string(),
+        filter,
+        "-r".to_string(),
+        TIMELAPSE_FPS.to_string(),
+        "-an".to_string(),
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "veryfast".to_string(),
+        "-crf".to_string(),
+        "28".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        output.to_string_lossy().to_string(),
+    ]
+}
+
+async fn run_ffmpeg(args: &[String]) -> Result<()> {
+    let output = Command::new(find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "ffmpeg exited with {}: {}",
+            output.status,
+            stderr.lines().last().unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Renders the part of the timelapse of the chunk `input`, at the capture times of its frames.
+async fn render_timelapse_part(
+    db: &DatabaseManager,
+    input: &str,
+    output: &Path,
+    speed: u32,
+) -> Result<()> {
+    let frame_times = db.get_video_chunk_frame_times(input).await?;
+    let Some((_, first_frame_at)) = frame_times.first() else {
+        return Err(anyhow!("no frame of {} is indexed", input));
+    };
+    let shown = timelapse_frames(&frame_times, speed);
+
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let (width, height) = video_dimensions(Path::new(input)).await?;
+    let mut decoder = Command::new(&ffmpeg_path)
+        .args(["-v", "error", "-noautorotate", "-i", input])
+        .args([
+            "-fps_mode",
+            "passthrough",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+            "-",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start ffmpeg")?;
+    let mut frames = decoder.stdout.take().expect("ffmpeg stdout is piped");
+    let mut encoder = Command::new(&ffmpeg_path)
+        .args(timelapse_part_args(
+            (width, height),
+            output,
+            *first_frame_at,
+            speed,
+        ))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start ffmpeg")?;
+    let mut stdin = encoder.stdin.take().expect("ffmpeg stdin is piped");
+
+    let mut buffer = vec![0u8; width as usize * height as usize * 3];
+    // offset of the frame in the buffer
+    let mut decoded = -1;
+    for offset in shown {
+        while decoded < offset {
+            match frames.read_exact(&mut buffer).await {
+                Ok(_) => decoded += 1,
+                // the chunk was cut short, what it has is rendered
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if decoded < offset {
+            break;
+        }
+        stdin.write_all(&buffer).await?;
+    }
+    drop(stdin);
+    let _ = decoder.wait().await;
+
+    let output = encoder.wait_with_output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "ffmpeg exited with {}: {}",
+            output.status,
+            stderr.lines().last().unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Renders the timelapse of one device on `date` from its finalized chunks, returning its
+/// path, or `None` when nothing was recorded.
+async fn render_device_timelapse(
+    db: &DatabaseManager,
+    storage: &Storage,
+    date: NaiveDate,
+    device_name: &str,
+    speed: u32,
+) -> Result<Option<PathBuf>> {
+    let (start, end) = local_day_bounds(date);
+    let recording = db.get_latest_video_chunk_path(device_name).await?;
+    let spans = db.get_video_chunk_spans(device_name, start, end).await?;
+
+    let output = timelapse_path(storage, date, device_name);
+    let parts_dir = output.with_extension("parts");
+    tokio::fs::create_dir_all(&parts_dir).await?;
+
+    let mut parts = Vec::new();
+    for span in &spans {
+        // frames kept as images have no video to speed up
+        if !span.file_path.ends_with(".mp4") {
+            continue;
+        }
+        if recording.as_deref() == Some(&span.file_path) {
+            debug!("{} is still being recorded, leaving it out", span.file_path);
+            continue;
+        }
+        let part = parts_dir.join(format!("{:05}.mp4", parts.len()));
+        match render_timelapse_part(db, &span.file_path, &part, speed).await {
+            Ok(()) => parts.push(part),
+            Err(e) => warn!("leaving {} out of the timelapse: {}", span.file_path, e),
+        }
+    }
+
+    let result = if parts.is_empty() {
+        Ok(None)
+    } else {
+        let list_path = parts_dir.join("parts.txt");
+        let list = parts
+            .iter()
+            .map(|part| format!("file '{}'\n", part.to_string_lossy().replace('\'', r"'\''")))
+            .collect::<String>();
+        tokio::fs::write(&list_path, list).await?;
+        let args = [
+            "-y",
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+            &list_path.to_string_lossy(),
+            "-c",
+            "copy",
+            "-movflags",
+            "+faststart",
+            &output.to_string_lossy(),
+        ]
+        .map(String::from);
+        run_ffmpeg(&args).await.map(|()| Some(output))
+    };
+    let _ = tokio::fs::remove_dir_all(&parts_dir).await;
+    result
+}
+
+/// Renders the timelapses of `date`, returning their paths.
+pub async fn render_timelapses(
+    db: &DatabaseManager,
+    storage: &Storage,
+    date: NaiveDate,
+    speed: u32,
+    progress: Option<&JobProgress>,
+) -> Result<Vec<PathBuf>> {
+    let (start, end) = local_day_bounds(date);
+    let monitors: Vec<String> = db
+        .get_recorded_devices(start, end)
+        .await?
+        .into_iter()
+        .filter(|name| name.starts_with("monitor_"))
+        .collect();
+    let mut rendered = Vec::new();
+    for (i, device_name) in monitors.iter().enumerate() {
+        if let Some(path) = render_device_timelapse(db, storage, date, device_name, speed).await? {
+            info!("rendered timelapse of {} on {}", device_name, date);
+            rendered.push(path);
+        }
+        if let Some(progress) = progress {
+            progress
+                .report((i + 1) as f64 / monitors.len() as f64)
+                .await;
+        }
+    }
+    if rendered.is_empty() {
+        return Err(anyhow!("no finalized recordings on {}", date));
+    }
+    Ok(rendered)
+}
+
+pub(crate) async fn render_timelapse_job(
+    db: Arc<DatabaseManager>,
+    storage: Storage,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: TimelapsePayload = serde_json::from_str(&job.payload)?;
+    render_timelapses(&db, &storage, payload.date, payload.speed, Some(&progress)).await?;
+    Ok(())
+}
+
+/// Parses a time of day written `HH:MM`.
+pub fn parse_time_of_day(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| anyhow!("invalid time of day {:?}, expected HH:MM", value))
+}
+
+/// Next local time after `now` the clock shows `at`.
+pub fn next_occurrence(now: DateTime<Local>, at: NaiveTime) -> DateTime<Local> {
+    let mut date = now.date_naive();
+    loop {
+        // skipped when daylight saving time jumps over it
+        if let Some(time) = Local.from_local_datetime(&date.and_time(at)).earliest() {
+            if time > now {
+                return time;
+            }
+        }
+        date = date.succ_opt().unwrap_or(date);
+    }
+}
+
+/// Queues the timelapses of the current day every day at `at`, local time.
+pub async fn schedule_timelapses(db: Arc<DatabaseManager>, at: NaiveTime, speed: u32) {
+    loop {
+        let next = next_occurrence(Local::now(), at);
+        let wait = (next - Local::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        let payload = TimelapsePayload {
+            date: next.date_naive(),
+            speed,
+        };
+        if let Err(e) = enqueue_job(&db, RENDER_TIMELAPSE_JOB, &payload, 3).await {
+            warn!("failed to queue timelapse of {}: {}", payload.date, e);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::power::capture_paused;
+use chrono::{DateTime, Utc};
+use screenpipe_core::capture_policy::capture_policy;
+use screenpipe_core::{Desktop, UIElement};
+use screenpipe_db::{DatabaseManager, NewUiEvent, UiControl, UI_EVENT_FOCUS, UI_EVENT_WINDOW};
+use screenpipe_vision::capture_screenshot_by_window::foreground_window;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Bounds of the walk over a window's tree, which can be huge in browsers and IDEs.
+const MAX_VISITED: usize = 2000;
+const MAX_DEPTH: usize = 12;
+const MAX_CONTROLS: usize = 100;
+const MAX_ANCESTORS: usize = 30;
+
+/// Roles worth remembering: what can be clicked, typed into or switched to.
+const CONTROL_ROLES: &[&str] = &[
+    "button",
+    "menuitem",
+    "menubaritem",
+    "tab",
+    "link",
+    "hyperlink",
+    "checkbox",
+    "radiobutton",
+    "popupbutton",
+    "combobox",
+    "textfield",
+    "textarea",
+    "searchfield",
+    "edit",
+    "slider",
+    "switch",
+];
+
+/// Platform independent role: "AXButton" on macOS and "Button" on Windows are both "button".
+pub fn normalize_role(role: &str) -> String {
+    let role = role.strip_prefix("AX").unwrap_or(role);
+    role.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Never keep what is typed into password fields.
+pub(crate) fn is_secret(role: &str, name: &str) -> bool {
+    role.contains("secure") || role.contains("password") || name.to_lowercase().contains("password")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedControl {
+    pub role: String,
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// What the accessibility tree of the focused window showed at one sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiSnapshot {
+    pub app_name: String,
+    pub window_name: String,
+    pub focused: Option<FocusedControl>,
+    pub controls: Vec<UiControl>,
+}
+
+/// Turns consecutive snapshots into events: one when a window gets focus, with its controls,
+/// and one whenever the focused control changes within it.
+#[derive(Debug, Default)]
+pub struct UiEventTracker {
+    window: Option<(String, String)>,
+    focused: Option<FocusedControl>,
+}
+
+impl UiEventTracker {
+    pub fn observe(&mut self, snapshot: UiSnapshot, timestamp: DateTime<Utc>) -> Vec<NewUiEvent> {
+        let window = (snapshot.app_name.clone(), snapshot.window_name.clone());
+        let event = |event_type: &str, focused: Option<&FocusedControl>, controls| NewUiEvent {
+            timestamp,
+            event_type: event_type.to_string(),
+            app_name: snapshot.app_name.clone(),
+            window_name: snapshot.window_name.clone(),
+            element_role: focused.map(|control| control.role.clone()),
+            element_name: focused.map(|control| control.name.clone()),
+            element_value: focused.and_then(|control| control.value.clone()),
+            controls,
+        };
+
+        let mut events = Vec::new();
+        if self.window.as_ref() != Some(&window) {
+            events.push(event(
+                UI_EVENT_WINDOW,
+                snapshot.focused.as_ref(),
+                snapshot.controls.clone(),
+            ));
+        } else if snapshot.focused.is_some() && self.focused != snapshot.focused {
+            events.push(event(UI_EVENT_FOCUS, snapshot.focused.as_ref(), Vec::new()));
+        }
+        self.window = Some(window);
+        self.focused = snapshot.focused;
+        events
+    }
+}
+
+pub(crate) fn label(element: &UIElement) -> String {
+    let attributes = element.attributes();
+    attributes
+        .label
+        .or(attributes.description)
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// The named controls under `window`, breadth first so the visible chrome comes first.
+fn collect_controls(window: &UIElement) -> Vec<UiControl> {
+    let mut controls = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([(window.clone(), 0)]);
+    let mut visited = 0;
+
+    while let Some((element, depth)) = queue.pop_front() {
+        visited += 1;
+        if visited > MAX_VISITED || controls.len() >= MAX_CONTROLS {
+            break;
+        }
+        let role = normalize_role(&element.role());
+        if CONTROL_ROLES.contains(&role.as_str()) {
+            let name = label(&element);
+            if !name.is_empty() && seen.insert((role.clone(), name.clone())) {
+                controls.push(UiControl { role, name });
+            }
+        }
+        if depth < MAX_DEPTH {
+            if let Ok(children) = element.children() {
+                queue.extend(children.into_iter().map(|child| (child, depth + 1)));
+            }
+        }
+    }
+    controls
+}
+
+/// The focused element and the window it's in.
+pub(crate) struct FocusedWindow {
+    pub element: UIElement,
+    pub window: UIElement,
+    pub app_name: String,
+    pub window_name: String,
+}
+
+/// The `(app_name, window_name)` of the window in the foreground, as screen capture names it,
+/// when what it shows may be recorded. `None` while capture is paused, when the foreground
+/// window can't be told or the capture policy ignores it, for nothing to be recorded then.
+pub(crate) fn recordable_foreground_window() -> Option<(String, String)> {
+    if capture_paused() {
+        return None;
+    }
+    let Some(window) = foreground_window() else {
+        debug!("foreground window unknown, recording nothing of it");
+        return None;
+    };
+    capture_policy()
+        .allows_window(&window.app_name, &window.window_name)
+        .then_some((window.app_name, window.window_name))
+}
+
+/// The window holding the focused element, `None` when nothing is focused or the window must
+/// not be recorded.
+pub(crate) fn find_focused_window(desktop: &Desktop) -> Option<FocusedWindow> {
+    // the names screen capture uses, so events link to its frames
+    let (app_name, window_name) = recordable_foreground_window()?;
+    let focused = desktop.focused_element().ok()?;
+
+    let mut window = None;
+    let mut current = Some(focused.clone());
+    for _ in 0..MAX_ANCESTORS {
+        let Some(element) = current else {
+            break;
+        };
+        match normalize_role(&element.role()).as_str() {
+            "window" => {
+                window = Some(element);
+                break;
+            }
+            "application" => break,
+            _ => {}
+        }
+        current = element.parent().ok().flatten();
+    }
+    let window = window?;
+
+    Some(FocusedWindow {
+        element: focused,
+        window,
+        app_name,
+        window_name,
+    })
+}
+
+/// Reads the focused control, and the controls of its window unless it is `previous_window`.
+/// `None` when nothing is focused or the window must not be recorded.
+pub fn read_snapshot(
+    desktop: &Desktop,
+    previous_window: Option<&(String, String)>,
+) -> Option<UiSnapshot> {
+    let FocusedWindow {
+        element: focused,
+        window,
+        app_name,
+        window_name,
+    } = find_focused_window(desktop)?;
+
+    let role = normalize_role(&focused.role());
+    let name = label(&focused);
+    let value = if is_secret(&role, &name) {
+        None
+    } else {
+        focused.attributes().value.filter(|value| !value.is_empty())
+    };
+
+    // the controls are only stored when the window changes, and walking them is the slow part
+    let same_window =
+        previous_window.is_some_and(|(app, title)| *app == app_name && *title == window_name);
+    Some(UiSnapshot {
+        controls: if same_window {
+            Vec::new()
+        } else {
+            collect_controls(&window)
+        },
+        app_name,
+        window_name,
+        focused: (!name.is_empty() || value.is_some()).then_some(FocusedControl {
+            role,
+            name,
+            value,
+        }),
+    })
+}
+
+/// Samples the focused window every [`SAMPLE_INTERVAL`] and stores the UI events it produces.
+pub async fn record_ui_events(db: Arc<DatabaseManager>) {
+    let (sender, mut receiver) = mpsc::channel::<UiSnapshot>(16);
+
+    // accessibility calls block, sometimes for long, so they get a thread of their own
+    std::thread::spawn(move || {
+        let desktop = match Desktop::new(false, false) {
+            Ok(desktop) => desktop,
+            Err(e) => {
+                warn!("ui events disabled, accessibility is not available: {}", e);
+                return;
+            }
+        };
+        info!("recording ui events from the accessibility tree");
+        let mut previous_window = None;
+        loop {
+            if let Some(snapshot) = read_snapshot(&desktop, previous_window.as_ref()) {
+                previous_window = Some((snapshot.app_name.clone(), snapshot.window_name.clone()));
+                if sender.blocking_send(snapshot).is_err() {
+                    break;
+                }
+            }
+            std::thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+
+    let mut tracker = UiEventTracker::default();
+    while let Some(snapshot) = receiver.recv().await {
+        for event in tracker.observe(snapshot, Utc::now()) {
+            match db.insert_ui_event(&event).await {
+                Ok(id) => debug!("stored ui event {} ({})", id, event.event_type),
+                Err(e) => error!("failed to store ui event: {}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use crate::evidence::manifest_path;
+use crate::hub::{file_digest, hub_key, hub_request_headers, HUB_CONTENT_SHA256_HEADER};
+use crate::jobs::{ChunkJobPayload, JobProgress};
+use crate::secrets::{resolve_secret, SecretStore};
+use crate::storage::local_hostname;
+use crate::subtitles::sidecar_path;
+use crate::thumbnails::{thumbnail_index_path, thumbnail_sprite_path};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use once_cell::sync::OnceCell;
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH};
+use reqwest::{Body, Client, Method, StatusCode};
+use screenpipe_db::{DatabaseManager, Job};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
+
+/// Uploads a finalized chunk and its subtitles to the sync target.
+pub const UPLOAD_SEGMENT_JOB: &str = "upload_segment";
+
+/// Read from the data directory when `--sync-config` is not given.
+pub const DEFAULT_SYNC_CONFIG_FILE: &str = "sync.json";
+
+/// Attempts of an upload, generous since it also waits for the chunk's other jobs.
+pub const UPLOAD_MAX_ATTEMPTS: i64 = 10;
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Where finalized segments are pushed. Files keep their path under the data directory, so
+/// recordings of several machines don't collide.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncTarget {
+    /// An S3-compatible bucket (AWS, R2, B2, MinIO...), addressed path-style.
+    S3 {
+        /// e.g. `https://s3.eu-west-1.amazonaws.com`
+        endpoint: String,
+        bucket: String,
+        #[serde(default = "default_region")]
+        region: String,
+        /// The credentials can be `keychain:<name>` to read them from the keychain.
+        access_key_id: String,
+        secret_access_key: String,
+        /// Prepended to the keys of the uploaded files.
+        #[serde(default)]
+        prefix: String,
+    },
+    /// A WebDAV folder (Nextcloud, ownCloud, a NAS...).
+    Webdav {
+        url: String,
+        username: Option<String>,
+        /// Or `keychain:<name>` to read it from the keychain.
+        password: Option<String>,
+    },
+    /// Any remote configured in rclone, e.g. `dropbox:screenpipe` or an iCloud Drive folder.
+    Rclone {
+        remote: String,
+        #[serde(default = "default_rclone")]
+        binary: String,
+    },
+    /// Another screenpipe started with `--hub-token`, merging the index of its peers into its
+    /// own. Frames and their text are pushed as they are indexed, segments only with
+    /// `segments`, so the hub can show their images.
+    Hub {
+        /// e.g. `http://desktop.local:3030`
+        url: String,
+        /// The hub's `--hub-token`, or `keychain:<name>` to read it from the keychain.
+        token: String,
+        #[serde(default)]
+        segments: bool,
+    },
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_rclone() -> String {
+    "rclone".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncConfig {
+    pub target: SyncTarget,
+    /// Upload speed limit in kilobytes per second.
+    #[serde(default)]
+    pub bandwidth_limit_kbps: Option<u64>,
+    /// Removes the video and subtitles of a chunk once uploaded, for machines short on disk.
+    /// Its frames and text stay searchable, their images are gone from this machine.
+    #[serde(default)]
+    pub delete_after_upload: bool,
+}
+
+impl SyncConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: SyncConfig = serde_json::from_str(content)?;
+        match &config.target {
+            SyncTarget::S3 {
+                endpoint, bucket, ..
+            } => {
+                reqwest::Url::parse(endpoint)
+                    .with_context(|| format!("invalid s3 endpoint {}", endpoint))?;
+                if bucket.is_empty() {
+                    return Err(anyhow!("the s3 bucket is empty"));
+                }
+            }
+            SyncTarget::Webdav { url, .. } => {
+                reqwest::Url::parse(url).with_context(|| format!("invalid webdav url {}", url))?;
+            }
+            SyncTarget::Rclone { remote, .. } => {
+                if !remote.contains(':') {
+                    return Err(anyhow!("rclone remote {} has no 'name:' prefix", remote));
+                }
+            }
+            SyncTarget::Hub { url, token, .. } => {
+                reqwest::Url::parse(url).with_context(|| format!("invalid hub url {}", url))?;
+                if token.is_empty() {
+                    return Err(anyhow!("the hub token is empty"));
+                }
+            }
+        }
+        if config.bandwidth_limit_kbps == Some(0) {
+            return Err(anyhow!("bandwidth_limit_kbps is 0"));
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read sync config {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    /// Reads the credentials given as `keychain:<name>` from the keychain, so they needn't
+    /// be written in the config.
+    pub fn resolve_secrets(&mut self, store: &dyn SecretStore) -> Result<()> {
+        match &mut self.target {
+            SyncTarget::S3 {
+                access_key_id,
+                secret_access_key,
+                ..
+            } => {
+                *access_key_id = resolve_secret(access_key_id, store)?;
+                *secret_access_key = resolve_secret(secret_access_key, store)?;
+            }
+            SyncTarget::Webdav { password, .. } => {
+                if let Some(password) = password {
+                    *password = resolve_secret(password, store)?;
+                }
+            }
+            SyncTarget::Hub { token, .. } => {
+                *token = resolve_secret(token, store)?;
+            }
+            SyncTarget::Rclone { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Whether finalized segments are uploaded, which a hub only takes when asked for.
+    pub fn uploads_segments(&self) -> bool {
+        match &self.target {
+            SyncTarget::Hub { segments, .. } => *segments,
+            _ => true,
+        }
+    }
+}
+
+static SYNC_CONFIG: OnceCell<SyncConfig> = OnceCell::new();
+
+/// Enables uploads of finalized segments. Only the first call has an effect.
+pub fn set_sync_config(config: SyncConfig) {
+    if SYNC_CONFIG.set(config).is_err() {
+        warn!("sync config already set");
+    }
+}
+
+pub fn sync_config() -> Option<&'static SyncConfig> {
+    SYNC_CONFIG.get()
+}
+
+/// Files of a finalized segment: the chunk, its subtitles, the activity of the chunk, its
+/// thumbnail strip and its evidence manifest when sealed.
+pub fn segment_files(video_path: &Path) -> Vec<PathBuf> {
+    let video = video_path.to_string_lossy();
+    [
+        video_path.to_path_buf(),
+        sidecar_path(video_path),
+        thumbnail_sprite_path(&video),
+        thumbnail_index_path(&video),
+        manifest_path(video_path),
+    ]
+    .into_iter()
+    .filter(|path| path.is_file())
+    .collect()
+}
+
+/// Key of `file` on the remote: its path under `root` with `/` separators, or its name when
+/// it's elsewhere.
+pub fn remote_key(root: &Path, file: &Path) -> String {
+    match file.strip_prefix(root) {
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    }
+}
+
+/// Percent-encodes `value` as S3 expects in a canonical uri, keeping `/` between segments.
+pub(crate) fn uri_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac takes keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `Authorization` header of an S3 `PUT` of `canonical_uri` on `host`, signed with AWS
+/// signature version 4 over an unsigned payload.
+pub fn s3_authorization(
+    access_key_id: &str,
+    secret_access_key: &str,
+    region: &str,
+    host: &str,
+    canonical_uri: &str,
+    time: DateTime<Utc>,
+) -> String {
+    let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = time.format("%Y%m%d").to_string();
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        canonical_uri, host, UNSIGNED_PAYLOAD, amz_date, signed_headers, UNSIGNED_PAYLOAD
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let mut key = hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), &date);
+    for part in [region, "s3", "aws4_request"] {
+        key = hmac_sha256(&key, part);
+    }
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key_id,
+        scope,
+        signed_headers,
+        hex(&hmac_sha256(&key, &string_to_sign))
+    )
+}
+
+/// Body streaming `file`, no faster than `limit` bytes per second when set.
+async fn file_body(file: &Path, limit: Option<u64>) -> Result<(Body, u64)> {
+    let length = tokio::fs::metadata(file).await?.len();
+    let stream = ReaderStream::with_capacity(tokio::fs::File::open(file).await?, 64 * 1024);
+    let body = match limit {
+        None => Body::wrap_stream(stream),
+        Some(limit) => {
+            let started = tokio::time::Instant::now();
+            let mut sent = 0u64;
+            Body::wrap_stream(stream.then(move |chunk| {
+                if let Ok(bytes) = &chunk {
+                    sent += bytes.len() as u64;
+                }
+                let due = started + Duration::from_secs_f64(sent as f64 / limit as f64);
+                async move {
+                    tokio::time::sleep_until(due).await;
+                    chunk
+                }
+            }))
+        }
+    };
+    Ok((body, length))
+}
+
+async fn check_response(response: reqwest::Response, key: &str) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow!(
+        "upload of {} failed with {}: {}",
+        key,
+        status,
+        body
+    ))
+}
+
+/// Pushes `file` to `key` on the target of `config`.
+pub async fn upload_file(config: &SyncConfig, file: &Path, key: &str) -> Result<()> {
+    let limit = config.bandwidth_limit_kbps.map(|kbps| kbps * 1024);
+    let client = Client::new();
+    match &config.target {
+        SyncTarget::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+            prefix,
+        } => {
+            let key = match prefix.trim_matches('/') {
+                "" => key.to_string(),
+                prefix => format!("{}/{}", prefix, key),
+            };
+            let canonical_uri = uri_encode(&format!("/{}/{}", bucket, key));
+            let url = reqwest::Url::parse(&format!(
+                "{}{}",
+                endpoint.trim_end_matches('/'),
+                canonical_uri
+            ))?;
+            let host = match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => return Err(anyhow!("s3 endpoint {} has no host", endpoint)),
+            };
+            let now = Utc::now();
+            let (body, length) = file_body(file, limit).await?;
+            let response = client
+                .put(url)
+                .header(
+                    AUTHORIZATION,
+                    s3_authorization(
+                        access_key_id,
+                        secret_access_key,
+                        region,
+                        &host,
+                        &canonical_uri,
+                        now,
+                    ),
+                )
+                .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+                .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+                .header(CONTENT_LENGTH, length)
+                .body(body)
+                .send()
+                .await?;
+            check_response(response, &key).await
+        }
+        SyncTarget::Webdav {
+            url,
+            username,
+            password,
+        } => {
+            let base = url.trim_end_matches('/');
+            let authorize = |request: reqwest::RequestBuilder| match username {
+                Some(username) => request.basic_auth(username, password.as_deref()),
+                None => request,
+            };
+            // collections must exist before a file is put in them
+            let segments: Vec<&str> = key.split('/').collect();
+            for depth in 1..segments.len() {
+                let collection = format!("{}/{}", base, uri_encode(&segments[..depth].join("/")));
+                let response =
+                    authorize(client.request(Method::from_bytes(b"MKCOL")?, &collection))
+                        .send()
+                        .await?;
+                // 405 when it already exists
+                if !response.status().is_success()
+                    && response.status() != StatusCode::METHOD_NOT_ALLOWED
+                {
+                    return check_response(response, &segments[..depth].join("/")).await;
+                }
+            }
+            let (body, length) = file_body(file, limit).await?;
+            let response = authorize(client.put(format!("{}/{}", base, uri_encode(key))))
+                .header(CONTENT_LENGTH, length)
+                .body(body)
+                .send()
+                .await?;
+            check_response(response, key).await
+        }
+        SyncTarget::Rclone { remote, binary } => {
+            let destination = format!("{}/{}", remote.trim_end_matches('/'), key);
+            let mut command = Command::new(binary);
+            command.arg("copyto").arg(file).arg(&destination);
+            if let Some(kbps) = config.bandwidth_limit_kbps {
+                command.arg("--bwlimit").arg(format!("{}k", kbps));
+            }
+            let output = command
+                .output()
+                .await
+                .with_context(|| format!("failed to run {}", binary))?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "rclone failed to upload {}: {}",
+                    key,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(())
+        }
+        SyncTarget::Hub { url, token, .. } => {
+            let key = hub_key(&local_hostname(), key);
+            let path = format!("/hub/segments/{}", uri_encode(&key));
+            // hashed ahead of streaming it, the hub checks the body against the signed digest
+            let digest = file_digest(file).await?;
+            let (body, length) = file_body(file, limit).await?;
+            let mut request = client.put(format!("{}{}", url.trim_end_matches('/'), path));
+            for (name, value) in hub_request_headers(token, "PUT", &path, &digest) {
+                request = request.header(name, value);
+            }
+            let response = request
+                .header(HUB_CONTENT_SHA256_HEADER, &digest)
+                .header(CONTENT_LENGTH, length)
+                .body(body)
+                .send()
+                .await?;
+            check_response(response, &key).await
+        }
+    }
+}
+
+/// Uploads the files of the segment, removing them afterwards when configured to.
+pub async fn upload_segment(
+    config: &SyncConfig,
+    root: &Path,
+    video_path: &Path,
+    progress: Option<&JobProgress>,
+) -> Result<Vec<String>> {
+    let files = segment_files(video_path);
+    let mut keys = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let key = remote_key(root, file);
+        upload_file(config, file, &key).await?;
+        keys.push(key);
+        if let Some(progress) = progress {
+            progress.report((i + 1) as f64 / files.len() as f64).await;
+        }
+    }
+    if config.delete_after_upload {
+        for file in &files {
+            if let Err(e) = tokio::fs::remove_file(file).await {
+                warn!("failed to remove uploaded {}: {}", file.display(), e);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+pub(crate) async fn upload_segment_job(
+    db: Arc<DatabaseManager>,
+    root: PathBuf,
+    job: Job,
+    progress: JobProgress,
+) -> Result<()> {
+    let payload: ChunkJobPayload = serde_json::from_str(&job.payload)?;
+    // the chunk may still be re-encoded, or read for its pyramid
+    if db
+        .count_unfinished_jobs_before(job.id, &job.payload)
+        .await?
+        > 0
+    {
+        return Err(anyhow!(
+            "{} still has jobs to run before its upload",
+            payload.video_path
+        ));
+    }
+    let config = sync_config().ok_or_else(|| anyhow!("no sync target is configured"))?;
+    let keys = upload_segment(
+        config,
+        &root,
+        Path::new(&payload.video_path),
+        Some(&progress),
+    )
+    .await?;
+    info!("uploaded {}", keys.join(", "));
+    Ok(())
+}
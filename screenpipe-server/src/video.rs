@@ -1,13 +1,35 @@
+use crate::app_quality::{app_quality_for, CaptureQuality};
+use crate::backpressure::{backpressure_policy, enqueue, BackpressurePolicy};
+use crate::chunk_rotation::{adaptive_chunks, ChunkRotation};
+use crate::content_quality::{chunk_crf, classify_frame, dynamic_crf, ContentKind};
+use crate::dedup::{perceptual_hash, skip_duplicate_frames, text_fingerprint, RecentScreens};
+use crate::encoder_health::{
+    encoder_restart_backoff, encoder_state, watch_ffmpeg_stderr, EncoderState,
+};
+use crate::excise::chunk_cuts;
+use crate::hot_reload::subscribe_capture_fps;
+use crate::meeting_mode::report_captured_windows;
+use crate::metrics::{
+    record_ffmpeg_restart, record_frame_written, record_frames_dropped, record_queue_depth,
+};
+use crate::plugins::{FrameVerdict, PluginFrame, PluginHost};
+use crate::power::{current_throttle, power_mode, subscribe_throttle, ThrottleMode};
+use crate::pre_roll::{buffering_pre_roll, captured_at, pre_roll, PreRollBuffer};
+use crate::recording_format::recording_format;
+use crate::sensitive_content::screen_frame;
+use crate::sessions::SessionTracker;
+use crate::storage::{EncodedFrame, FrameJournal, JournalRecord, JournalWindow, Storage};
+use crate::streaming::publish_live_frame;
 use chrono::Utc;
 use crossbeam::queue::ArrayQueue;
 use image::ImageFormat::{self};
+use screenpipe_core::pii_removal::remove_pii;
 use screenpipe_core::{find_ffmpeg_path, Language};
 use screenpipe_vision::monitor::get_monitor_by_id;
 use screenpipe_vision::{
     capture_screenshot_by_window::WindowFilters, continuous_capture, CaptureResult, OcrEngine,
 };
 use std::borrow::Cow;
-use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -23,10 +45,64 @@ use tracing::{debug, error, info, warn};
 pub(crate) const MAX_FPS: f64 = 30.0; // Adjust based on your needs
 const MAX_QUEUE_SIZE: usize = 30; // Increased from 10 for more buffer room
 
+/// Runs the windows of a frame through the plugins, before it's encoded or indexed. The text
+/// the plugins edit is the one stored for the windows. False when a plugin skipped one of
+/// them, the frame showing it being dropped altogether.
+fn run_plugins(
+    plugins: &PluginHost,
+    monitor_id: u32,
+    result: &mut CaptureResult,
+    use_pii_removal: bool,
+) -> bool {
+    let timestamp = captured_at(result.timestamp);
+    for window in &mut result.window_ocr_results {
+        let text = if use_pii_removal {
+            remove_pii(&window.text)
+        } else {
+            window.text.clone()
+        };
+        let mut frame = PluginFrame {
+            monitor_id,
+            timestamp,
+            app_name: window.app_name.clone(),
+            window_name: window.window_name.clone(),
+            browser_url: window.browser_url.clone(),
+            focused: window.focused,
+            text: text.clone(),
+            image: Arc::new(window.image.clone()),
+        };
+        if plugins.on_frame(&mut frame) == FrameVerdict::Skip {
+            debug!(
+                "plugin skipped window {} of monitor {}, dropping the frame",
+                window.window_name, monitor_id
+            );
+            return false;
+        }
+        if frame.text != text {
+            // the positions of the words read no longer match the text
+            window.text = frame.text;
+            window.text_json.clear();
+        }
+    }
+    true
+}
+
+/// A captured frame on its way to the index.
+#[derive(Clone)]
+pub struct QueuedFrame {
+    pub result: Arc<CaptureResult>,
+    pub perceptual_hash: u64,
+    /// See [`text_fingerprint`].
+    pub text_fingerprint: u64,
+    /// Whether the frame was sent to the video, it otherwise shows a screen encoded shortly
+    /// before and is indexed as a duplicate of it.
+    pub encoded: bool,
+}
+
 pub struct VideoCapture {
     #[allow(unused)]
     video_frame_queue: Arc<ArrayQueue<Arc<CaptureResult>>>,
-    pub ocr_frame_queue: Arc<ArrayQueue<Arc<CaptureResult>>>,
+    pub ocr_frame_queue: Arc<ArrayQueue<QueuedFrame>>,
     // Add handles to tasks so we can monitor their status
     capture_thread_handle: tokio::task::JoinHandle<()>,
     queue_thread_handle: tokio::task::JoinHandle<()>,
@@ -45,10 +121,11 @@ impl VideoCapture {
         new_chunk_callback: impl Fn(&str) + Send + Sync + 'static,
         ocr_engine: Arc<OcrEngine>,
         monitor_id: u32,
-        ignore_list: &[String],
-        include_list: &[String],
         languages: Vec<Language>,
         capture_unfocused_windows: bool,
+        session_tracker: Option<Arc<SessionTracker>>,
+        plugins: Arc<PluginHost>,
+        use_pii_removal: bool,
     ) -> Self {
         let fps = if fps.is_finite() && fps > 0.0 {
             fps
@@ -69,10 +146,14 @@ impl VideoCapture {
             monitor_id, MAX_QUEUE_SIZE, fps
         );
 
+        // frames kept as images are written when indexed, nothing goes to a video
+        let frames_as_images = recording_format().image_format().is_some();
+
         let capture_video_frame_queue = video_frame_queue.clone();
         let capture_ocr_frame_queue = ocr_frame_queue.clone();
         let (result_sender, mut result_receiver) = channel(512);
-        let window_filters = Arc::new(WindowFilters::new(ignore_list, include_list));
+        // follows the capture policy, so that blocklists reloaded while recording apply
+        let window_filters = Arc::new(WindowFilters::shared());
 
         // Add parameters for monitoring restart
         let capture_ocr_engine = ocr_engine.clone();
@@ -82,6 +163,8 @@ impl VideoCapture {
         let capture_result_sender = result_sender.clone();
         let capture_interval = interval;
         let capture_unfocused = capture_unfocused_windows;
+        let mut capture_throttle = subscribe_throttle();
+        let mut capture_fps = subscribe_capture_fps();
 
         // Store task handles for health monitoring
         let capture_thread = tokio::spawn(async move {
@@ -101,27 +184,61 @@ impl VideoCapture {
                     continue;
                 }
 
-                info!("Starting continuous_capture for monitor {}", monitor_id);
+                let mut throttle = *capture_throttle.borrow_and_update();
+                // paused by hand, the capture goes on into the pre-roll
+                if throttle == ThrottleMode::Paused && buffering_pre_roll() {
+                    throttle = power_mode();
+                }
+                // a reloaded fps only changes how often frames are captured, the videos being
+                // encoded keep theirs like in low-power mode
+                let base_interval = capture_fps
+                    .borrow_and_update()
+                    .filter(|fps| *fps > 0.0)
+                    .map_or(capture_interval, |fps| Duration::from_secs_f64(1.0 / fps));
+                let Some(interval) = throttle.capture_interval(base_interval) else {
+                    info!("Capture paused for monitor {}", monitor_id);
+                    let _ = capture_throttle.changed().await;
+                    continue;
+                };
 
-                match continuous_capture(
-                    capture_result_sender.clone(),
-                    capture_interval,
-                    (*capture_ocr_engine).clone(),
-                    monitor_id,
-                    capture_window_filters.clone(),
-                    capture_languages.clone(),
-                    capture_unfocused,
-                )
-                .await
-                {
-                    Ok(_) => warn!(
-                        "continuous_capture task for monitor {} completed unexpectedly",
-                        monitor_id
-                    ),
-                    Err(e) => error!(
-                        "continuous_capture task for monitor {} failed with error: {}",
-                        monitor_id, e
-                    ),
+                info!(
+                    "Starting continuous_capture for monitor {} ({:?})",
+                    monitor_id, throttle
+                );
+
+                tokio::select! {
+                    result = continuous_capture(
+                        capture_result_sender.clone(),
+                        interval,
+                        (*capture_ocr_engine).clone(),
+                        monitor_id,
+                        capture_window_filters.clone(),
+                        capture_languages.clone(),
+                        capture_unfocused,
+                    ) => match result {
+                        Ok(_) => warn!(
+                            "continuous_capture task for monitor {} completed unexpectedly",
+                            monitor_id
+                        ),
+                        Err(e) => error!(
+                            "continuous_capture task for monitor {} failed with error: {}",
+                            monitor_id, e
+                        ),
+                    },
+                    _ = capture_throttle.changed() => {
+                        info!(
+                            "Power mode changed, restarting capture for monitor {}",
+                            monitor_id
+                        );
+                        continue;
+                    }
+                    _ = capture_fps.changed() => {
+                        info!(
+                            "Capture fps changed, restarting capture for monitor {}",
+                            monitor_id
+                        );
+                        continue;
+                    }
                 }
 
                 // If we get here, either the task completed or failed
@@ -138,88 +255,132 @@ impl VideoCapture {
             let mut last_log_time = start_time;
             let log_interval = Duration::from_secs(30); // Log stats every 30 seconds
 
-            // Helper function to push to queue and handle errors
-            fn push_to_queue(
-                queue: &ArrayQueue<Arc<CaptureResult>>,
-                result: &Arc<CaptureResult>,
-                queue_name: &str,
-            ) -> bool {
-                if queue.push(Arc::clone(result)).is_err() {
-                    if queue.len() >= queue.capacity() {
-                        error!(
-                            "{} queue is full ({}/{})",
-                            queue_name,
-                            queue.len(),
-                            queue.capacity()
-                        );
-                    }
+            // screens sent to the video and their text, to keep the ones coming back out of it
+            let mut encoded_screens = RecentScreens::<u64>::default();
+            let mut pre_roll_frames = PreRollBuffer::new(pre_roll().unwrap_or_default());
 
-                    if queue.pop().is_none() {
-                        error!("{} queue is in an inconsistent state", queue_name);
-                        return false;
-                    }
-                    if queue.push(Arc::clone(result)).is_err() {
-                        error!(
-                            "Failed to push to {} queue after removing oldest frame",
-                            queue_name
-                        );
-                        return false;
-                    }
-                    debug!(
-                        "{} queue was full, dropped oldest frame, new size: {}/{}",
-                        queue_name,
-                        queue.len(),
-                        queue.capacity()
+            while let Some(mut result) = result_receiver.recv().await {
+                report_captured_windows(
+                    monitor_id,
+                    result
+                        .window_ocr_results
+                        .iter()
+                        .map(|window| (window.app_name.as_str(), window.window_name.as_str())),
+                );
+                // before the pre-roll, which is encoded and indexed as well
+                if !screen_frame(monitor_id, &mut result, &ocr_engine, &languages).await {
+                    continue;
+                }
+                if !plugins.is_empty()
+                    && !run_plugins(&plugins, monitor_id, &mut result, use_pii_removal)
+                {
+                    continue;
+                }
+                if buffering_pre_roll() {
+                    pre_roll_frames.push(result.timestamp, result);
+                    continue;
+                }
+                // the pre-roll starts the recording, it's queued without dropping frames
+                let flushing = pre_roll_frames.len();
+                if flushing > 0 {
+                    info!(
+                        "Recording resumed, queueing {} pre-roll frames of monitor {}",
+                        flushing, monitor_id
                     );
                 }
-                true
-            }
-
-            while let Some(result) = result_receiver.recv().await {
-                let frame_number = result.frame_number;
-                processed_count += 1;
+                let frames: Vec<CaptureResult> = pre_roll_frames.drain().collect();
+                for (i, result) in frames
+                    .into_iter()
+                    .chain(std::iter::once(result))
+                    .enumerate()
+                {
+                    let frame_number = result.frame_number;
+                    processed_count += 1;
+
+                    // Periodically log stats
+                    let now = std::time::Instant::now();
+                    if now.duration_since(last_log_time) >= log_interval {
+                        let elapsed_secs = now.duration_since(start_time).as_secs_f64();
+                        let rate = if elapsed_secs > 0.0 {
+                            processed_count as f64 / elapsed_secs
+                        } else {
+                            0.0
+                        };
+                        info!(
+                            "Queue stats for monitor {}: processed {} frames in {:.1}s ({:.2} fps), queue sizes: video={}/{}, ocr={}/{}",
+                            monitor_id, processed_count, elapsed_secs, rate,
+                            capture_video_frame_queue.len(), capture_video_frame_queue.capacity(),
+                            capture_ocr_frame_queue.len(), capture_ocr_frame_queue.capacity()
+                        );
+                        last_log_time = now;
+                    }
 
-                // Periodically log stats
-                let now = std::time::Instant::now();
-                if now.duration_since(last_log_time) >= log_interval {
-                    let elapsed_secs = now.duration_since(start_time).as_secs_f64();
-                    let rate = if elapsed_secs > 0.0 {
-                        processed_count as f64 / elapsed_secs
+                    debug!("Received frame {} for queueing", frame_number);
+
+                    let result = Arc::new(result);
+                    publish_live_frame(monitor_id, &result.image);
+
+                    let perceptual_hash = perceptual_hash(&result.image);
+                    let text_fingerprint = text_fingerprint(&result);
+                    let encoded = !skip_duplicate_frames()
+                        || encoded_screens
+                            .find_where(perceptual_hash, |text| *text == text_fingerprint)
+                            .is_none();
+                    // kept as images, the frames are only written once indexed, a frame
+                    // dropped from the queue would be lost from the recording
+                    let policy = if i < flushing || frames_as_images {
+                        BackpressurePolicy::Block
                     } else {
-                        0.0
+                        backpressure_policy()
                     };
-                    info!(
-                        "Queue stats for monitor {}: processed {} frames in {:.1}s ({:.2} fps), queue sizes: video={}/{}, ocr={}/{}",
-                        monitor_id, processed_count, elapsed_secs, rate,
-                        capture_video_frame_queue.len(), capture_video_frame_queue.capacity(),
-                        capture_ocr_frame_queue.len(), capture_ocr_frame_queue.capacity()
+                    if encoded {
+                        encoded_screens.insert(perceptual_hash, text_fingerprint);
+                        if !frames_as_images {
+                            let dropped =
+                                enqueue(&capture_video_frame_queue, result.clone(), policy).await;
+                            if dropped > 0 {
+                                warn!(
+                                    "Video queue of monitor {} was full, dropped its oldest frame",
+                                    monitor_id
+                                );
+                                record_frames_dropped(monitor_id, dropped, 0);
+                            }
+                        }
+                    } else {
+                        debug!(
+                            "Frame {} shows a screen encoded shortly before, not encoding it again",
+                            frame_number
+                        );
+                    }
+                    let queued = QueuedFrame {
+                        result: result.clone(),
+                        perceptual_hash,
+                        text_fingerprint,
+                        encoded,
+                    };
+                    let dropped = enqueue(&capture_ocr_frame_queue, queued, policy).await;
+                    if dropped > 0 {
+                        warn!(
+                            "OCR queue of monitor {} was full, dropped its oldest frame",
+                            monitor_id
+                        );
+                        record_frames_dropped(monitor_id, 0, dropped);
+                    }
+                    record_queue_depth(
+                        monitor_id,
+                        capture_video_frame_queue.len(),
+                        capture_ocr_frame_queue.len(),
                     );
-                    last_log_time = now;
-                }
-
-                debug!("Received frame {} for queueing", frame_number);
-
-                let result = Arc::new(result);
 
-                let video_pushed = push_to_queue(&capture_video_frame_queue, &result, "Video");
-                let ocr_pushed = push_to_queue(&capture_ocr_frame_queue, &result, "OCR");
-
-                if !video_pushed || !ocr_pushed {
-                    error!(
-                        "Failed to push frame {} to one or more queues",
-                        frame_number
+                    debug!(
+                        "Frame {} pushed to queues. Queue lengths: video={}/{}, ocr={}/{}",
+                        frame_number,
+                        capture_video_frame_queue.len(),
+                        capture_video_frame_queue.capacity(),
+                        capture_ocr_frame_queue.len(),
+                        capture_ocr_frame_queue.capacity()
                     );
-                    continue; // Skip to next iteration instead of crashing
                 }
-
-                debug!(
-                    "Frame {} pushed to queues. Queue lengths: video={}/{}, ocr={}/{}",
-                    frame_number,
-                    capture_video_frame_queue.len(),
-                    capture_video_frame_queue.capacity(),
-                    capture_ocr_frame_queue.len(),
-                    capture_ocr_frame_queue.capacity()
-                );
             }
 
             warn!(
@@ -230,36 +391,42 @@ impl VideoCapture {
 
         let video_frame_queue_clone = video_frame_queue.clone();
 
-        let output_path = output_path.to_string();
-        let video_thread = tokio::spawn(async move {
-            info!(
-                "Starting save_frames_as_video task for monitor {}",
-                monitor_id
-            );
-            match save_frames_as_video(
-                &video_frame_queue_clone,
-                &output_path,
-                fps,
-                new_chunk_callback_clone,
-                monitor_id,
-                video_chunk_duration,
-            )
-            .await
-            {
-                Ok(_) => warn!(
-                    "save_frames_as_video task completed unexpectedly for monitor {}",
+        let storage = Storage::new(output_path);
+        let video_thread = if frames_as_images {
+            info!("Recording monitor {} as images", monitor_id);
+            tokio::spawn(std::future::pending::<()>())
+        } else {
+            tokio::spawn(async move {
+                info!(
+                    "Starting save_frames_as_video task for monitor {}",
                     monitor_id
-                ),
-                Err(e) => error!(
-                    "save_frames_as_video task failed for monitor {}: {}",
-                    monitor_id, e
-                ),
-            }
-            warn!(
-                "save_frames_as_video task terminated for monitor {}",
-                monitor_id
-            );
-        });
+                );
+                match save_frames_as_video(
+                    &video_frame_queue_clone,
+                    &storage,
+                    fps,
+                    new_chunk_callback_clone,
+                    monitor_id,
+                    video_chunk_duration,
+                    session_tracker,
+                )
+                .await
+                {
+                    Ok(_) => warn!(
+                        "save_frames_as_video task completed unexpectedly for monitor {}",
+                        monitor_id
+                    ),
+                    Err(e) => error!(
+                        "save_frames_as_video task failed for monitor {}: {}",
+                        monitor_id, e
+                    ),
+                }
+                warn!(
+                    "save_frames_as_video task terminated for monitor {}",
+                    monitor_id
+                );
+            })
+        };
 
         // Add monitor availability check task
         let monitor_check_handle = tokio::spawn(async move {
@@ -311,6 +478,7 @@ impl VideoCapture {
         let video_ok = !self.video_thread_handle.is_finished();
         let monitor_check_ok = !self.monitor_check_handle.is_finished();
         let monitor_available = self.monitor_available.load(Ordering::SeqCst);
+        let encoder_ok = encoder_state(self.monitor_id) != Some(EncoderState::Unhealthy);
 
         if !capture_ok {
             error!("continuous_capture task has terminated unexpectedly");
@@ -327,8 +495,14 @@ impl VideoCapture {
         if !monitor_available {
             warn!("monitor {} is currently unavailable", self.monitor_id);
         }
+        if !encoder_ok {
+            error!(
+                "ffmpeg encoder of monitor {} reported errors, see the log of its chunk",
+                self.monitor_id
+            );
+        }
 
-        capture_ok && queue_ok && video_ok && monitor_check_ok
+        capture_ok && queue_ok && video_ok && monitor_check_ok && encoder_ok
     }
 
     // Add method to check if monitor is available
@@ -338,6 +512,99 @@ impl VideoCapture {
 }
 
 pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child, anyhow::Error> {
+    start_ffmpeg_encoder(output_file, fps, false, ContentKind::Mixed, None, None).await
+}
+
+/// Video filter of a chunk whose frames are `frame_size`, the size of its first frame. The
+/// size is rounded up to even for yuv420p, and a frame of another size, captured as the
+/// monitor turns before the chunk is ended, is fitted into it rather than breaking the
+/// encoder. Without a size, each frame is only padded to even.
+pub fn chunk_video_filter(frame_size: Option<(u32, u32)>) -> String {
+    match frame_size {
+        Some((width, height)) => {
+            let (width, height) = (width.div_ceil(2) * 2, height.div_ceil(2) * 2);
+            format!(
+                "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+                w = width,
+                h = height
+            )
+        }
+        None => "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2".to_string(),
+    }
+}
+
+/// Encoder arguments of a chunk: x264 in low power mode, x265 otherwise, at `crf`. The crf is
+/// kept in the comment tag of the chunk, for it to be re-encoded the way it was recorded.
+pub fn chunk_encoder_args(low_power: bool, crf: u8) -> Vec<String> {
+    let codec: &[&str] = if low_power {
+        &["-vcodec", "libx264", "-preset", "ultrafast"]
+    } else {
+        &[
+            "-vcodec",
+            "libx265",
+            "-tag:v",
+            "hvc1",
+            "-preset",
+            "ultrafast",
+        ]
+    };
+    let mut args: Vec<String> = codec.iter().map(|arg| arg.to_string()).collect();
+    args.extend([
+        "-crf".to_string(),
+        crf.to_string(),
+        "-metadata".to_string(),
+        format!("comment=crf={}", crf),
+    ]);
+    args
+}
+
+/// How a recorded chunk was encoded, as given to [`chunk_encoder_args`]: whether it is x264,
+/// and its crf. A chunk recorded before its crf was tagged gets the base crf of its codec.
+pub async fn chunk_encoding(video_path: &str) -> Result<(bool, u8), anyhow::Error> {
+    let ffprobe_path = find_ffmpeg_path()
+        .ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?
+        .with_file_name("ffprobe");
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name:format_tags=comment",
+            "-of",
+            "json",
+            video_path,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffprobe exited with {} reading {}",
+            output.status,
+            video_path
+        ));
+    }
+    let probe: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let low_power = probe["streams"][0]["codec_name"].as_str() == Some("h264");
+    let crf = probe["format"]["tags"]["comment"]
+        .as_str()
+        .and_then(|comment| comment.strip_prefix("crf="))
+        .and_then(|crf| crf.parse().ok())
+        .unwrap_or_else(|| chunk_crf(ContentKind::Mixed, low_power));
+    Ok((low_power, crf))
+}
+
+/// Spawns the chunk encoder. `low_power` trades file size for CPU by using x264 instead of x265,
+/// `content` sets the quality the chunk is encoded at, unless the `quality` of its app does.
+async fn start_ffmpeg_encoder(
+    output_file: &str,
+    fps: f64,
+    low_power: bool,
+    content: ContentKind,
+    frame_size: Option<(u32, u32)>,
+    quality: Option<CaptureQuality>,
+) -> Result<Child, anyhow::Error> {
     // Overriding fps with max fps if over the max and warning user
     let fps = if fps > MAX_FPS {
         warn!("Overriding FPS from {} to {}", fps, MAX_FPS);
@@ -346,10 +613,23 @@ pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child,
         fps
     };
 
-    info!("Starting FFmpeg process for file: {}", output_file);
+    let crf = quality
+        .and_then(|quality| quality.crf)
+        .unwrap_or_else(|| chunk_crf(content, low_power));
+    let frame_size =
+        frame_size.map(|size| quality.map_or(size, |quality| quality.frame_size(size)));
+    info!(
+        "Starting FFmpeg process for file: {} ({:?} content, crf {}, size {:?})",
+        output_file, content, crf, frame_size
+    );
     let fps_str = fps.to_string();
+    let filter = chunk_video_filter(frame_size);
     let mut command = Command::new(find_ffmpeg_path().unwrap());
-    let mut args = vec![
+    // only warnings and errors, prefixed with their level for the stderr watcher
+    let mut args: Vec<String> = [
+        "-hide_banner",
+        "-loglevel",
+        "level+warning",
         "-f",
         "image2pipe",
         "-vcodec",
@@ -359,21 +639,12 @@ pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child,
         "-i",
         "-",
         "-vf",
-        "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2",
-    ];
-
-    args.extend_from_slice(&[
-        "-vcodec",
-        "libx265",
-        "-tag:v",
-        "hvc1",
-        "-preset",
-        "ultrafast",
-        "-crf",
-        "23",
-    ]);
-
-    args.extend_from_slice(&["-pix_fmt", "yuv420p", output_file]);
+        &filter,
+    ]
+    .map(String::from)
+    .to_vec();
+    args.extend(chunk_encoder_args(low_power, crf));
+    args.extend(["-pix_fmt", "yuv420p", output_file].map(String::from));
 
     command
         .args(&args)
@@ -407,20 +678,48 @@ async fn log_ffmpeg_output(stream: impl AsyncBufReadExt + Unpin, stream_name: &s
 
 async fn save_frames_as_video(
     frame_queue: &Arc<ArrayQueue<Arc<CaptureResult>>>,
-    output_path: &str,
+    storage: &Storage,
     fps: f64,
     new_chunk_callback: Arc<dyn Fn(&str) + Send + Sync>,
     monitor_id: u32,
     video_chunk_duration: Duration,
+    session_tracker: Option<Arc<SessionTracker>>,
 ) -> Result<(), anyhow::Error> {
     info!(
         "Starting save_frames_as_video function for monitor {}",
         monitor_id
     );
     let frames_per_video = (fps * video_chunk_duration.as_secs_f64()).ceil() as usize;
+    let rotation = if adaptive_chunks() {
+        ChunkRotation::adaptive(frames_per_video, fps)
+    } else {
+        ChunkRotation::fixed(frames_per_video)
+    };
     let mut frame_count = 0;
     let mut current_ffmpeg: Option<Child> = None;
     let mut current_stdin: Option<ChildStdin> = None;
+    // frame that opened a new session and must start the next chunk
+    let mut pending_frame: Option<Arc<CaptureResult>> = None;
+    let mut chunk_session_id: Option<i64> = None;
+    // app focused in the last frame of the chunk, to end chunks where the app changes
+    let mut chunk_app: Option<String> = None;
+    // size of the chunk's frames, which changes when the monitor is rotated
+    let mut chunk_size = (0, 0);
+    let mut chunk_quality = None;
+    let mut chunk_path = String::new();
+    // cuts requested when the chunk started, a new one ends it
+    let mut chunk_cut = chunk_cuts();
+    // frames are journaled as they're encoded so that a crash can't lose their index rows
+    let journal = match storage.frame_journal(monitor_id) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            warn!(
+                "failed to open the frame journal of monitor {}, frames queued for indexing are lost on a crash: {}",
+                monitor_id, e
+            );
+            None
+        }
+    };
 
     // Track health metrics
     let start_time = std::time::Instant::now();
@@ -430,7 +729,21 @@ async fn save_frames_as_video(
     let stats_interval = Duration::from_secs(60);
 
     loop {
-        if frame_count >= frames_per_video || current_ffmpeg.is_none() {
+        // a chunk whose encoder reported errors may be broken, start a fresh one
+        let encoder_unhealthy =
+            current_ffmpeg.is_some() && encoder_state(monitor_id) == Some(EncoderState::Unhealthy);
+        if encoder_unhealthy {
+            warn!(
+                "ffmpeg encoder of monitor {} is unhealthy, starting a new chunk",
+                monitor_id
+            );
+            record_ffmpeg_restart(monitor_id);
+        }
+        if rotation.is_full(frame_count)
+            || current_ffmpeg.is_none()
+            || pending_frame.is_some()
+            || encoder_unhealthy
+        {
             if let Some(child) = current_ffmpeg.take() {
                 info!(
                     "Finishing FFmpeg process for monitor {} after {} frames",
@@ -439,24 +752,76 @@ async fn save_frames_as_video(
                 finish_ffmpeg_process(child, current_stdin.take()).await;
                 chunks_total += 1;
             }
+            if encoder_unhealthy {
+                let backoff = encoder_restart_backoff(monitor_id);
+                warn!(
+                    "waiting {:?} before restarting the ffmpeg encoder of monitor {}",
+                    backoff, monitor_id
+                );
+                sleep(backoff).await;
+            }
 
             frame_count = 0;
             debug!("Waiting for first frame for monitor {}", monitor_id);
-            let first_frame = wait_for_first_frame(frame_queue).await;
+            let first_frame = match pending_frame.take() {
+                Some(frame) => frame,
+                None => wait_for_first_frame(frame_queue).await,
+            };
+            if let Some(tracker) = &session_tracker {
+                match tracker.record_activity(Utc::now()).await {
+                    Ok(session_id) => chunk_session_id = Some(session_id),
+                    Err(e) => error!("Failed to track recording session: {}", e),
+                }
+            }
+            chunk_cut = chunk_cuts();
+            chunk_app = focused_app(&first_frame);
+            chunk_quality = app_quality_for(chunk_app.as_deref());
+            chunk_size = (first_frame.image.width(), first_frame.image.height());
             let buffer = encode_frame(&first_frame);
             debug!("Got first frame for new chunk for monitor {}", monitor_id);
 
-            let output_file = create_output_file(output_path, monitor_id);
+            let output_file = match storage.video_chunk_path(monitor_id, Utc::now()) {
+                Ok(path) => path.to_string_lossy().into_owned(),
+                Err(e) => {
+                    error!(
+                        "Failed to create output directory for monitor {}: {}",
+                        monitor_id, e
+                    );
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
             info!(
                 "Starting new video chunk: {} for monitor {}",
                 output_file, monitor_id
             );
             new_chunk_callback(&output_file);
 
-            match start_ffmpeg_process(&output_file, fps).await {
+            let low_power = current_throttle().is_low_power();
+            // chunks end where the app changes, so the first frame stands for the chunk
+            let content = if dynamic_crf() {
+                classify_frame(&first_frame)
+            } else {
+                ContentKind::Mixed
+            };
+            match start_ffmpeg_encoder(
+                &output_file,
+                fps,
+                low_power,
+                content,
+                Some(chunk_size),
+                chunk_quality,
+            )
+            .await
+            {
                 Ok(mut child) => {
                     let mut stdin = child.stdin.take().expect("Failed to open stdin");
-                    spawn_ffmpeg_loggers(child.stderr.take(), child.stdout.take());
+                    spawn_ffmpeg_loggers(
+                        child.stderr.take(),
+                        child.stdout.take(),
+                        monitor_id,
+                        &output_file,
+                    );
 
                     debug!("Writing first frame to FFmpeg for monitor {}", monitor_id);
                     if let Err(e) = write_frame_to_ffmpeg(&mut stdin, &buffer).await {
@@ -464,13 +829,19 @@ async fn save_frames_as_video(
                             "Failed to write first frame to ffmpeg for monitor {}: {}",
                             monitor_id, e
                         );
+                        record_ffmpeg_restart(monitor_id);
                         continue;
                     }
                     frame_count += 1;
                     frames_total += 1;
+                    record_frame_written(monitor_id);
+                    if let Some(journal) = &journal {
+                        journal_encoded(journal, &first_frame, &output_file, 0, monitor_id);
+                    }
 
                     current_ffmpeg = Some(child);
                     current_stdin = Some(stdin);
+                    chunk_path = output_file.clone();
                     info!(
                         "New FFmpeg process started for file: {} (monitor {})",
                         output_file, monitor_id
@@ -481,6 +852,7 @@ async fn save_frames_as_video(
                         "Failed to start FFmpeg process for monitor {}: {}",
                         monitor_id, e
                     );
+                    record_ffmpeg_restart(monitor_id);
                     continue;
                 }
             }
@@ -505,14 +877,82 @@ async fn save_frames_as_video(
             "Processing frames for monitor {}, current count: {}/{}",
             monitor_id, frame_count, frames_per_video
         );
-        process_frames(
+        match process_frames(
             frame_queue,
+            monitor_id,
             &mut current_stdin,
             &mut frame_count,
-            frames_per_video,
+            &rotation,
+            &mut chunk_app,
+            chunk_size,
+            chunk_quality,
             fps,
+            chunk_cut,
+            session_tracker.as_deref().zip(chunk_session_id),
+            journal
+                .as_deref()
+                .map(|journal| (journal, chunk_path.as_str())),
         )
-        .await;
+        .await
+        {
+            ChunkEnd::Full => {}
+            ChunkEnd::Resized(frame) => {
+                info!(
+                    "Monitor {} changed from {}x{} to {}x{}, finalizing chunk after {} frames",
+                    monitor_id,
+                    chunk_size.0,
+                    chunk_size.1,
+                    frame.image.width(),
+                    frame.image.height(),
+                    frame_count
+                );
+                pending_frame = Some(frame);
+            }
+            ChunkEnd::SessionChanged(frame) => {
+                info!(
+                    "Recording session changed, finalizing chunk for monitor {}",
+                    monitor_id
+                );
+                pending_frame = Some(frame);
+            }
+            ChunkEnd::AppSwitched(frame) => {
+                info!(
+                    "Focused app changed, finalizing chunk for monitor {} after {} frames",
+                    monitor_id, frame_count
+                );
+                pending_frame = Some(frame);
+            }
+            ChunkEnd::Lull => {
+                if let Some(child) = current_ffmpeg.take() {
+                    info!(
+                        "Screen of monitor {} stopped changing, finalizing chunk after {} frames",
+                        monitor_id, frame_count
+                    );
+                    finish_ffmpeg_process(child, current_stdin.take()).await;
+                    chunks_total += 1;
+                }
+            }
+            ChunkEnd::Cut => {
+                if let Some(child) = current_ffmpeg.take() {
+                    info!(
+                        "Recording excised, finalizing chunk for monitor {} after {} frames",
+                        monitor_id, frame_count
+                    );
+                    finish_ffmpeg_process(child, current_stdin.take()).await;
+                    chunks_total += 1;
+                }
+            }
+            ChunkEnd::Idle => {
+                if let Some(child) = current_ffmpeg.take() {
+                    info!(
+                        "Monitor {} idle, finalizing chunk after {} frames",
+                        monitor_id, frame_count
+                    );
+                    finish_ffmpeg_process(child, current_stdin.take()).await;
+                    chunks_total += 1;
+                }
+            }
+        }
 
         // Update total frame count
         frames_total = frames_total.max(frame_count);
@@ -537,6 +977,48 @@ async fn wait_for_first_frame(
     }
 }
 
+/// Journals a frame written to the encoder of `chunk_path` at `offset_index`, ahead of its
+/// index rows.
+fn journal_encoded(
+    journal: &FrameJournal,
+    frame: &CaptureResult,
+    chunk_path: &str,
+    offset_index: usize,
+    monitor_id: u32,
+) {
+    let record = JournalRecord::Encoded(EncodedFrame {
+        frame_number: frame.frame_number,
+        chunk_path: chunk_path.to_string(),
+        offset_index: offset_index as i64,
+        device_name: format!("monitor_{}", monitor_id),
+        timestamp: captured_at(frame.timestamp),
+        windows: frame
+            .window_ocr_results
+            .iter()
+            .map(|window_result| JournalWindow {
+                app_name: window_result.app_name.clone(),
+                window_name: window_result.window_name.clone(),
+                browser_url: window_result.browser_url.clone(),
+                focused: window_result.focused,
+            })
+            .collect(),
+    });
+    if let Err(e) = journal.append(&record) {
+        warn!(
+            "failed to journal frame {} of monitor {}: {}",
+            frame.frame_number, monitor_id, e
+        );
+    }
+}
+
+fn focused_app(frame: &CaptureResult) -> Option<String> {
+    frame
+        .window_ocr_results
+        .iter()
+        .find(|window_result| window_result.focused)
+        .map(|window_result| window_result.app_name.clone())
+}
+
 fn encode_frame(frame: &CaptureResult) -> Vec<u8> {
     let mut buffer = Vec::new();
     frame
@@ -546,35 +1028,93 @@ fn encode_frame(frame: &CaptureResult) -> Vec<u8> {
     buffer
 }
 
-fn create_output_file(output_path: &str, monitor_id: u32) -> String {
-    let time = Utc::now();
-    let formatted_time = time.format("%Y-%m-%d_%H-%M-%S").to_string();
-    PathBuf::from(output_path)
-        .join(format!("monitor_{}_{}.mp4", monitor_id, formatted_time))
-        .to_str()
-        .expect("Failed to create valid path")
-        .to_string()
-}
-
-fn spawn_ffmpeg_loggers(stderr: Option<ChildStderr>, stdout: Option<ChildStdout>) {
+fn spawn_ffmpeg_loggers(
+    stderr: Option<ChildStderr>,
+    stdout: Option<ChildStdout>,
+    monitor_id: u32,
+    output_file: &str,
+) {
     if let Some(stderr) = stderr {
-        tokio::spawn(log_ffmpeg_output(BufReader::new(stderr), "stderr"));
+        let segment = std::path::Path::new(output_file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| output_file.to_string());
+        watch_ffmpeg_stderr(stderr, monitor_id, segment);
     }
     if let Some(stdout) = stdout {
         tokio::spawn(log_ffmpeg_output(BufReader::new(stdout), "stdout"));
     }
 }
 
+enum ChunkEnd {
+    Full,
+    /// The frame belongs to a new recording session and must open the next chunk.
+    SessionChanged(Arc<CaptureResult>),
+    /// No frame arrived for longer than the session idle gap, or capture was paused.
+    Idle,
+    /// The focused app changed, possibly to one encoded at another quality, near the end of
+    /// the chunk, the frame must open the next chunk.
+    AppSwitched(Arc<CaptureResult>),
+    /// The screen stopped changing near the end of the chunk.
+    Lull,
+    /// The end of the recording was excised and the chunk must be trimmed.
+    Cut,
+    /// The frame is another size than the chunk's, as after a monitor was rotated, and must
+    /// open the next chunk.
+    Resized(Arc<CaptureResult>),
+}
+
+/// Writes frames to the current chunk until `rotation` ends it, or until the recording
+/// session splits.
+#[allow(clippy::too_many_arguments)]
 async fn process_frames(
     frame_queue: &Arc<ArrayQueue<Arc<CaptureResult>>>,
+    monitor_id: u32,
     current_stdin: &mut Option<ChildStdin>,
     frame_count: &mut usize,
-    frames_per_video: usize,
+    rotation: &ChunkRotation,
+    chunk_app: &mut Option<String>,
+    chunk_size: (u32, u32),
+    chunk_quality: Option<CaptureQuality>,
     fps: f64,
-) {
+    chunk_cut: u64,
+    session: Option<(&SessionTracker, i64)>,
+    journal: Option<(&FrameJournal, &str)>,
+) -> ChunkEnd {
     let write_timeout = Duration::from_secs_f64(1.0 / fps);
-    while *frame_count < frames_per_video {
+    let mut last_frame_at = std::time::Instant::now();
+    while !rotation.is_full(*frame_count) {
+        if chunk_cuts() != chunk_cut {
+            return ChunkEnd::Cut;
+        }
         if let Some(frame) = frame_queue.pop() {
+            last_frame_at = std::time::Instant::now();
+            if let Some((tracker, chunk_session_id)) = session {
+                match tracker.record_activity(Utc::now()).await {
+                    Ok(session_id) if session_id != chunk_session_id => {
+                        return ChunkEnd::SessionChanged(frame)
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Failed to track recording session: {}", e),
+                }
+            }
+            if (frame.image.width(), frame.image.height()) != chunk_size {
+                return ChunkEnd::Resized(frame);
+            }
+            let app = focused_app(&frame);
+            if rotation.ends_at_app_switch(*frame_count, chunk_app.as_deref(), app.as_deref()) {
+                return ChunkEnd::AppSwitched(frame);
+            }
+            // the app's quality only applies from the chunk it opens
+            if app.is_some()
+                && app_quality_for(app.as_deref()) != chunk_quality
+                && rotation.ends_at_quality_change(*frame_count)
+            {
+                return ChunkEnd::AppSwitched(frame);
+            }
+            if app.is_some() {
+                *chunk_app = app;
+            }
             let buffer = encode_frame(&frame);
             if let Some(stdin) = current_stdin.as_mut() {
                 if let Err(e) = write_frame_with_retry(stdin, &buffer).await {
@@ -582,14 +1122,29 @@ async fn process_frames(
                     break;
                 }
                 *frame_count += 1;
+                record_frame_written(monitor_id);
+                if let Some((journal, chunk_path)) = journal {
+                    journal_encoded(journal, &frame, chunk_path, *frame_count - 1, monitor_id);
+                }
                 debug!("Wrote frame {} to FFmpeg", frame_count);
 
                 flush_ffmpeg_input(stdin, *frame_count, fps).await;
             }
         } else {
+            let idle_gap = session.and_then(|(tracker, _)| tracker.idle_gap());
+            if idle_gap.is_some_and(|gap| last_frame_at.elapsed() >= gap) {
+                return ChunkEnd::Idle;
+            }
+            if current_throttle() == ThrottleMode::Paused {
+                return ChunkEnd::Idle;
+            }
+            if rotation.ends_at_lull(*frame_count, last_frame_at.elapsed()) {
+                return ChunkEnd::Lull;
+            }
             tokio::time::sleep(write_timeout).await;
         }
     }
+    ChunkEnd::Full
 }
 
 async fn write_frame_with_retry(
@@ -62,6 +62,9 @@ pub struct FrameMetadata {
     pub window_name: String,
     pub transcription: String,
     pub ocr_text: String,
+    /// Category of the activity shown, missing in frames cached before categories existed.
+    #[serde(default)]
+    pub category: Option<String>,
 }
 
 type GetFrameResponse =
@@ -243,6 +246,7 @@ impl FrameDiskCache {
                     .collect::<Vec<_>>()
                     .join(" "),
                 ocr_text: device_data.text.clone(),
+                category: device_data.category.clone(),
             },
             frame_size: frame_data.len() as u64,
             compression: CompressionType::Jpeg {
@@ -791,6 +795,7 @@ async fn extract_frame(
                             .collect::<Vec<_>>()
                             .join(" "),
                         ocr_text: device_data.text.clone(),
+                        category: device_data.category.clone(),
                     },
                     audio_entries: chunk
                         .audio_entries
@@ -307,7 +307,7 @@ pub async fn extract_frames_from_video(
     Ok(frames)
 }
 
-async fn get_video_fps(ffmpeg_path: &PathBuf, video_path: &str) -> Result<f64> {
+pub(crate) async fn get_video_fps(ffmpeg_path: &PathBuf, video_path: &str) -> Result<f64> {
     let ffprobe_path = ffmpeg_path.with_file_name("ffprobe");
 
     let output = Command::new(&ffprobe_path)
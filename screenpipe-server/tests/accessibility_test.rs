@@ -0,0 +1,92 @@
+use chrono::Utc;
+use screenpipe_server::accessibility::{join_tree_text, TreeSnapshot, TreeSnapshotTracker};
+
+fn texts(texts: &[&str]) -> Vec<String> {
+    texts.iter().map(|text| text.to_string()).collect()
+}
+
+fn snapshot(window_name: &str, text: &str) -> TreeSnapshot {
+    TreeSnapshot {
+        app_name: "Mail".to_string(),
+        window_name: window_name.to_string(),
+        text: text.to_string(),
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_join_tree_text() {
+    assert_eq!(
+        join_tree_text(texts(&[
+            "Inbox",
+            "",
+            "  Reply  ",
+            "Reply",
+            "Invoice #4411 is due on Friday",
+            "Inbox",
+        ])),
+        "Inbox\nReply\nInvoice #4411 is due on Friday"
+    );
+    assert_eq!(join_tree_text(texts(&["", "   "])), "");
+}
+
+#[test]
+fn test_join_tree_text_is_bounded() {
+    let long = "a".repeat(15_000);
+    let other = "b".repeat(15_000);
+    let text = join_tree_text(vec![long.clone(), other, "end".to_string()]);
+    // the second text would go past the limit, the walk stops there
+    assert_eq!(text, long);
+}
+
+#[test]
+fn test_tree_snapshot_tracker() {
+    let mut tracker = TreeSnapshotTracker::default();
+    let stored = |snapshot: Option<TreeSnapshot>| snapshot.map(|snapshot| snapshot.text);
+
+    // stored once the text settles
+    assert_eq!(
+        tracker.observe(Some(snapshot("Inbox", "Inbox\nReply"))),
+        None
+    );
+    assert_eq!(
+        stored(tracker.observe(Some(snapshot("Inbox", "Inbox\nReply")))),
+        Some("Inbox\nReply".to_string())
+    );
+    assert_eq!(
+        tracker.observe(Some(snapshot("Inbox", "Inbox\nReply"))),
+        None
+    );
+    assert_eq!(
+        tracker.observe(Some(snapshot("Inbox", "Inbox\nReply"))),
+        None
+    );
+
+    // only what's new is stored
+    assert_eq!(
+        tracker.observe(Some(snapshot("Inbox", "Inbox\nReply\nNew message"))),
+        None
+    );
+    assert_eq!(
+        stored(tracker.observe(Some(snapshot("Inbox", "Inbox\nReply\nNew message")))),
+        Some("New message".to_string())
+    );
+
+    // typing in another window, stored as the focus moves on
+    assert_eq!(tracker.observe(Some(snapshot("Drafts", "Dear"))), None);
+    assert_eq!(tracker.observe(Some(snapshot("Drafts", "Dear Ann"))), None);
+    assert_eq!(stored(tracker.observe(None)), Some("Dear Ann".to_string()));
+    // nothing was read from the tree
+    assert_eq!(tracker.observe(Some(snapshot("Drafts", ""))), None);
+    assert_eq!(tracker.flush(), None);
+
+    // back to the first window
+    assert_eq!(
+        tracker.observe(Some(snapshot("Inbox", "Inbox\nReply\nNew message\nSent"))),
+        None
+    );
+    assert_eq!(
+        stored(tracker.observe(Some(snapshot("Drafts", "Dear Ann")))),
+        Some("Sent".to_string())
+    );
+}
@@ -0,0 +1,217 @@
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use screenpipe_db::AppActivity;
+use screenpipe_server::activity_stats::{
+    activity_stats, StatsOptions, TimeShare, TitleMode, UNCATEGORIZED,
+};
+use screenpipe_server::time_tracking::TimeTrackingConfig;
+
+fn frame(start: DateTime<Utc>, offset_secs: i64, app: &str, window: &str) -> AppActivity {
+    AppActivity {
+        timestamp: start + Duration::seconds(offset_secs),
+        app_name: app.to_string(),
+        window_name: window.to_string(),
+        category: None,
+    }
+}
+
+fn config() -> TimeTrackingConfig {
+    TimeTrackingConfig::parse(r#"{"rules": [{"app": "code", "project": "development"}]}"#).unwrap()
+}
+
+fn morning() -> DateTime<Utc> {
+    Local
+        .with_ymd_and_hms(2025, 3, 3, 9, 0, 0)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn test_stats_per_category() {
+    let start = morning();
+    let activity = [
+        frame(start, 0, "Code", "main.rs — secret-project"),
+        frame(start, 60, "Code", "lib.rs — secret-project"),
+        frame(start, 120, "Slack", "DM with Alice"),
+        frame(start, 180, "Code", "main.rs — secret-project"),
+    ];
+    let stats = activity_stats(
+        &activity,
+        start,
+        start + Duration::hours(1),
+        &config(),
+        &StatsOptions::default(),
+        &mut StdRng::seed_from_u64(1),
+    )
+    .unwrap();
+
+    assert_eq!(stats.days.len(), 1);
+    let day = &stats.days[0];
+    assert_eq!(day.date, "2025-03-03");
+    // the last frame counts for max_gap_secs
+    assert_eq!(day.active_secs, 300);
+    assert_eq!(day.idle_secs, 3300);
+    assert_eq!(
+        day.categories,
+        vec![
+            TimeShare {
+                name: "development".to_string(),
+                secs: 240
+            },
+            TimeShare {
+                name: UNCATEGORIZED.to_string(),
+                secs: 60
+            },
+        ]
+    );
+    assert!(day.windows.is_empty());
+    let json = serde_json::to_string(&stats).unwrap();
+    assert!(!json.contains("secret-project"));
+    assert!(!json.contains("Alice"));
+}
+
+#[test]
+fn test_hashed_titles() {
+    let start = morning();
+    let activity = [
+        frame(start, 0, "Slack", "DM with Alice"),
+        frame(start, 60, "Discord", "DM with Bob"),
+    ];
+    let export = |salt: Option<&str>, seed| {
+        let options = StatsOptions {
+            titles: TitleMode::Hash,
+            salt: salt.map(str::to_string),
+            ..Default::default()
+        };
+        activity_stats(
+            &activity,
+            start,
+            start + Duration::minutes(10),
+            &config(),
+            &options,
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .unwrap()
+    };
+
+    let windows = |stats: &ActivityStats| {
+        stats
+            .days
+            .iter()
+            .flat_map(|day| day.windows.iter().map(|window| window.name.clone()))
+            .collect::<Vec<_>>()
+    };
+    let salted = export(Some("study-42"), 1);
+    assert_eq!(windows(&salted).len(), 2);
+    assert!(!serde_json::to_string(&salted).unwrap().contains("Alice"));
+    assert_eq!(windows(&export(Some("study-42"), 2)), windows(&salted));
+    // a random key per export, hashes can't be matched across them
+    assert_ne!(windows(&export(None, 1)), windows(&export(None, 2)));
+}
+
+#[test]
+fn test_noised_stats() {
+    let start = morning();
+    let activity: Vec<_> = (0..60)
+        .map(|minute| frame(start, minute * 60, "Code", "main.rs"))
+        .collect();
+    let options = StatsOptions {
+        epsilon: Some(1.0),
+        ..Default::default()
+    };
+    let end = start + Duration::hours(1);
+    let exact = activity_stats(
+        &activity,
+        start,
+        end,
+        &config(),
+        &StatsOptions::default(),
+        &mut StdRng::seed_from_u64(7),
+    )
+    .unwrap();
+    let noised = activity_stats(
+        &activity,
+        start,
+        end,
+        &config(),
+        &options,
+        &mut StdRng::seed_from_u64(7),
+    )
+    .unwrap();
+
+    assert_eq!(noised.epsilon, Some(1.0));
+    let active = |stats: &ActivityStats| stats.days.iter().map(|day| day.active_secs).sum::<i64>();
+    assert_eq!(active(&exact), 3600);
+    assert_ne!(active(&noised), active(&exact));
+    assert!(noised
+        .days
+        .iter()
+        .all(|day| day.active_secs >= 0 && day.idle_secs >= 0));
+
+    let invalid = StatsOptions {
+        epsilon: Some(0.0),
+        ..Default::default()
+    };
+    assert!(activity_stats(
+        &activity,
+        start,
+        end,
+        &config(),
+        &invalid,
+        &mut StdRng::seed_from_u64(7)
+    )
+    .is_err());
+    assert!(activity_stats(
+        &activity,
+        end,
+        start,
+        &config(),
+        &options,
+        &mut StdRng::seed_from_u64(7)
+    )
+    .is_err());
+}
+
+#[test]
+fn test_stats_per_activity_category() {
+    let start = morning();
+    let categorized = |offset_secs, app: &str, category: Option<&str>| AppActivity {
+        category: category.map(str::to_string),
+        ..frame(start, offset_secs, app, "")
+    };
+    let activity = [
+        categorized(0, "Code", Some("coding")),
+        categorized(60, "Mail", Some("email")),
+        categorized(120, "Finder", None),
+    ];
+    let stats = activity_stats(
+        &activity,
+        start,
+        start + Duration::hours(1),
+        &config(),
+        &StatsOptions::default(),
+        &mut StdRng::seed_from_u64(1),
+    )
+    .unwrap();
+
+    let day = &stats.days[0];
+    assert_eq!(
+        day.activity_categories,
+        vec![
+            // the last frame counts for max_gap_secs
+            TimeShare {
+                name: UNCATEGORIZED.to_string(),
+                secs: 120
+            },
+            TimeShare {
+                name: "coding".to_string(),
+                secs: 60
+            },
+            TimeShare {
+                name: "email".to_string(),
+                secs: 60
+            },
+        ]
+    );
+}
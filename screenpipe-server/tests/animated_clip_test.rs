@@ -0,0 +1,36 @@
+use screenpipe_server::animated_clip::{
+    check_animated_clip_length, palette_filter, set_max_animated_clip_secs, ClipFormat,
+    DEFAULT_MAX_ANIMATED_CLIP_SECS,
+};
+
+#[test]
+fn test_clip_format_from_query() {
+    let format: ClipFormat = serde_json::from_str(r#""gif""#).unwrap();
+    assert_eq!(format, ClipFormat::Gif);
+    assert_eq!(format.extension(), "gif");
+    assert_eq!(ClipFormat::Apng.extension(), "png");
+    assert_eq!(ClipFormat::default(), ClipFormat::Mp4);
+    assert!(!ClipFormat::Mp4.is_animation());
+    assert!(serde_json::from_str::<ClipFormat>(r#""webm""#).is_err());
+}
+
+#[test]
+fn test_animated_clip_length_limit() {
+    set_max_animated_clip_secs(10);
+    // 20 frames at 2 fps play for 10s
+    assert!(check_animated_clip_length(20, 2.0).is_ok());
+    let error = check_animated_clip_length(21, 2.0).unwrap_err();
+    assert!(error.to_string().contains("export it as mp4"));
+    assert!(check_animated_clip_length(1, 0.0).is_err());
+    set_max_animated_clip_secs(DEFAULT_MAX_ANIMATED_CLIP_SECS);
+}
+
+#[test]
+fn test_palette_filter() {
+    assert_eq!(
+        palette_filter(2.0, 960),
+        "fps=2,scale=960:-2:flags=lanczos,split[frames][palette_input];\
+         [palette_input]palettegen=stats_mode=diff[palette];\
+         [frames][palette]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle"
+    );
+}
@@ -0,0 +1,70 @@
+use screenpipe_server::app_quality::{
+    app_quality_for, parse_app_quality, set_app_qualities, AppQualities, CaptureQuality,
+};
+
+#[test]
+fn test_parse_app_quality() {
+    assert_eq!(
+        parse_app_quality("Code=1:16").unwrap(),
+        (
+            "Code".to_string(),
+            CaptureQuality {
+                scale: 1.0,
+                crf: Some(16)
+            }
+        )
+    );
+    assert_eq!(
+        parse_app_quality("VLC media player=0.25").unwrap(),
+        (
+            "VLC media player".to_string(),
+            CaptureQuality {
+                scale: 0.25,
+                crf: None
+            }
+        )
+    );
+
+    assert!(parse_app_quality("Code").is_err());
+    assert!(parse_app_quality("=0.5").is_err());
+    assert!(parse_app_quality("Steam=0").is_err());
+    // upscaling wouldn't make text any sharper
+    assert!(parse_app_quality("Code=2").is_err());
+    assert!(parse_app_quality("Code=1:52").is_err());
+    assert!(parse_app_quality("Code=1:high").is_err());
+}
+
+#[test]
+fn test_quality_for_app() {
+    let sharp = CaptureQuality {
+        scale: 1.0,
+        crf: Some(16),
+    };
+    let small = CaptureQuality {
+        scale: 0.25,
+        crf: None,
+    };
+    let qualities = AppQualities::default()
+        .with_app("Code", sharp)
+        .with_app("VLC", small);
+
+    assert_eq!(qualities.for_app(Some("Visual Studio Code")), Some(sharp));
+    assert_eq!(qualities.for_app(Some("vlc")), Some(small));
+    assert_eq!(qualities.for_app(Some("Finder")), None);
+    assert_eq!(qualities.for_app(None), None);
+
+    set_app_qualities(qualities);
+    assert_eq!(app_quality_for(Some("VLC")), Some(small));
+    set_app_qualities(AppQualities::default());
+    assert_eq!(app_quality_for(Some("VLC")), None);
+}
+
+#[test]
+fn test_scaled_frame_size() {
+    let quality = CaptureQuality {
+        scale: 0.25,
+        crf: None,
+    };
+    assert_eq!(quality.frame_size((2560, 1440)), (640, 360));
+    assert_eq!(quality.frame_size((4, 4)), (2, 2));
+}
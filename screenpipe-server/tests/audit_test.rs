@@ -0,0 +1,65 @@
+use axum::http::{Method, Uri};
+use chrono::{TimeZone, Utc};
+use screenpipe_server::audit::{classify_access, client_fingerprint, requested_time_range};
+
+#[test]
+fn test_classify_access() {
+    assert_eq!(classify_access(&Method::GET, "/search"), Some("search"));
+    assert_eq!(
+        classify_access(&Method::GET, "/semantic-search"),
+        Some("search")
+    );
+    assert_eq!(classify_access(&Method::GET, "/frames/42"), Some("frame"));
+    assert_eq!(
+        classify_access(&Method::GET, "/frames/export"),
+        Some("export")
+    );
+    assert_eq!(
+        classify_access(&Method::POST, "/experimental/frames/merge"),
+        Some("export")
+    );
+    assert_eq!(
+        classify_access(&Method::GET, "/stream/mjpeg/1"),
+        Some("live")
+    );
+    assert_eq!(
+        classify_access(&Method::GET, "/mosaic/2025-03-01"),
+        Some("mosaic")
+    );
+    assert_eq!(
+        classify_access(&Method::GET, "/thumbnails/42/sprite"),
+        Some("thumbnails")
+    );
+
+    // rendering a mosaic, or reading settings and status, doesn't return recorded data
+    assert_eq!(classify_access(&Method::POST, "/mosaic/2025-03-01"), None);
+    assert_eq!(classify_access(&Method::GET, "/health"), None);
+    assert_eq!(classify_access(&Method::GET, "/access-log"), None);
+}
+
+#[test]
+fn test_requested_time_range() {
+    let uri: Uri = "/search?q=invoice&start_time=2025-03-01T10%3A00%3A00Z&end_time=2025-03-01T12:30:00%2B01:00"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        requested_time_range(&uri),
+        (
+            Some(Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2025, 3, 1, 11, 30, 0).unwrap()),
+        )
+    );
+
+    let uri: Uri = "/frames/42".parse().unwrap();
+    assert_eq!(requested_time_range(&uri), (None, None));
+}
+
+#[test]
+fn test_client_fingerprint_does_not_reveal_the_key() {
+    let fingerprint = client_fingerprint("s3cret-key");
+    assert!(fingerprint.starts_with("key:"));
+    assert_eq!(fingerprint.len(), "key:".len() + 8);
+    assert!(!fingerprint.contains("s3cret"));
+    assert_eq!(fingerprint, client_fingerprint("s3cret-key"));
+    assert_ne!(fingerprint, client_fingerprint("other-key"));
+}
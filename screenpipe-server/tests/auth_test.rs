@@ -0,0 +1,84 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_vision::OcrEngine;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{PipeManager, SCServer};
+
+async fn setup_test_app(api_key: Option<&str>) -> Router {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+
+    let app = SCServer::new(
+        db,
+        SocketAddr::from(([127, 0, 0, 1], 23948)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+        true,
+        Arc::new(OcrEngine::Tesseract),
+        api_key.map(String::from),
+        false,
+        None,
+    );
+
+    app.create_router(false).await
+}
+
+async fn status(app: &Router, uri: &str, bearer: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().uri(uri);
+    if let Some(bearer) = bearer {
+        request = request.header("Authorization", format!("Bearer {}", bearer));
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_api_key_is_required_when_configured() {
+    let app = setup_test_app(Some("secret")).await;
+
+    assert_eq!(
+        status(&app, "/bookmarks", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "/bookmarks", Some("wrong")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "/bookmarks", Some("secret")).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&app, "/bookmarks?api_key=secret", None).await,
+        StatusCode::OK
+    );
+    assert_eq!(status(&app, "/health", None).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_is_open_without_api_key() {
+    let app = setup_test_app(None).await;
+
+    assert_eq!(status(&app, "/bookmarks", None).await, StatusCode::OK);
+}
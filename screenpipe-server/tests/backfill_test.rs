@@ -0,0 +1,101 @@
+use chrono::{Duration, TimeZone, Utc};
+use screenpipe_server::backfill::{
+    activity_at, find_recordings, parse_recording_name, read_activity_log, spread_frame_times,
+};
+
+#[test]
+fn test_parse_recording_name() {
+    assert_eq!(
+        parse_recording_name("monitor_2_2024-11-28T01-00-00Z.mp4"),
+        Some((2, Utc.with_ymd_and_hms(2024, 11, 28, 1, 0, 0).unwrap()))
+    );
+    assert_eq!(
+        parse_recording_name("monitor_2_2024-11-28T01-00-00Z.jsonl"),
+        Some((2, Utc.with_ymd_and_hms(2024, 11, 28, 1, 0, 0).unwrap()))
+    );
+    // the first recordings were named in local time
+    let legacy = chrono::Local
+        .with_ymd_and_hms(2024, 6, 3, 10, 0, 0)
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!(
+        parse_recording_name("monitor_1_2024-06-03_10-00-00.mp4"),
+        Some((1, legacy))
+    );
+
+    assert_eq!(parse_recording_name("meeting.mp4"), None);
+    assert_eq!(
+        parse_recording_name("monitor_main_2024-11-28T01-00-00Z.mp4"),
+        None
+    );
+    assert_eq!(parse_recording_name("monitor_1_yesterday.mp4"), None);
+}
+
+#[test]
+fn test_spread_frame_times() {
+    let start = Utc.with_ymd_and_hms(2024, 11, 28, 1, 0, 0).unwrap();
+    let times = spread_frame_times(start, start + Duration::seconds(60), 4);
+    assert_eq!(
+        times,
+        vec![
+            start,
+            start + Duration::seconds(15),
+            start + Duration::seconds(30),
+            start + Duration::seconds(45),
+        ]
+    );
+    // an end before the start puts every frame at the start
+    assert_eq!(
+        spread_frame_times(start, start - Duration::seconds(1), 2),
+        vec![start, start]
+    );
+}
+
+#[test]
+fn test_find_recordings() {
+    let dir = tempfile::tempdir().unwrap();
+    let day = dir.path().join("2024-11-28");
+    std::fs::create_dir(&day).unwrap();
+    std::fs::write(day.join("monitor_1_2024-11-28T02-00-00Z.mp4"), b"").unwrap();
+    std::fs::write(day.join("monitor_1_2024-11-28T02-00-00Z.frames"), b"").unwrap();
+    std::fs::write(dir.path().join("monitor_2_2024-11-28T01-00-00Z.mp4"), b"").unwrap();
+    std::fs::write(
+        dir.path().join("monitor_2_2024-11-28T01-00-00Z.jsonl"),
+        concat!(
+            r#"{"start_time":"2024-11-28T01:00:00Z","end_time":"2024-11-28T01:10:00Z","app_name":"Firefox","window_title":"Docs","is_captured":true}"#,
+            "\n",
+            "not a block\n",
+            r#"{"start_time":"2024-11-28T01:10:00Z","end_time":"2024-11-28T01:20:00Z","app_name":"1Password","window_title":"Vault","is_captured":false}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+    // renamed by its recorder's naming template, found from its session file
+    std::fs::write(dir.path().join("standup.mp4"), b"").unwrap();
+    std::fs::write(
+        dir.path().join("standup.session.json"),
+        r#"{"monitor_id":3,"started_at":"2024-11-28T03:00:00Z","ended_at":null}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("meeting.mp4"), b"").unwrap();
+
+    let (recordings, others) = find_recordings(dir.path());
+    assert_eq!(
+        recordings
+            .iter()
+            .map(|recording| recording.monitor_id)
+            .collect::<Vec<_>>(),
+        vec![2, 1, 3]
+    );
+    assert_eq!(others, vec![dir.path().join("meeting.mp4")]);
+    assert!(recordings[0].frame_times.is_none());
+    assert!(recordings[1].frame_times.is_some());
+
+    let blocks = read_activity_log(recordings[0].activity_log.as_ref().unwrap()).unwrap();
+    assert_eq!(blocks.len(), 2);
+    let at = |minute| Utc.with_ymd_and_hms(2024, 11, 28, 1, minute, 0).unwrap();
+    assert_eq!(activity_at(&blocks, at(5)).unwrap().app_name, "Firefox");
+    // nothing was captured of the blocked app
+    assert!(activity_at(&blocks, at(15)).is_none());
+    assert!(activity_at(&blocks, at(30)).is_none());
+}
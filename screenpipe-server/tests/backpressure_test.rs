@@ -0,0 +1,39 @@
+use crossbeam::queue::ArrayQueue;
+use screenpipe_server::backpressure::{enqueue, BackpressurePolicy};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_enqueue_drop_oldest() {
+    let queue = ArrayQueue::new(2);
+
+    assert_eq!(enqueue(&queue, 1, BackpressurePolicy::DropOldest).await, 0);
+    assert_eq!(enqueue(&queue, 2, BackpressurePolicy::DropOldest).await, 0);
+    assert_eq!(enqueue(&queue, 3, BackpressurePolicy::DropOldest).await, 1);
+
+    assert_eq!(queue.pop(), Some(2));
+    assert_eq!(queue.pop(), Some(3));
+    assert_eq!(queue.pop(), None);
+}
+
+#[tokio::test]
+async fn test_enqueue_block_waits_for_room() {
+    let queue = Arc::new(ArrayQueue::new(1));
+    queue.push(1).unwrap();
+
+    let consumer = queue.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        consumer.pop()
+    });
+
+    let dropped = tokio::time::timeout(
+        Duration::from_secs(5),
+        enqueue(&queue, 2, BackpressurePolicy::Block),
+    )
+    .await
+    .expect("the frame waits for room rather than forever");
+
+    assert_eq!(dropped, 0);
+    assert_eq!(queue.pop(), Some(2));
+}
@@ -0,0 +1,181 @@
+use screenpipe_server::bench::{
+    bench_diff, recommend_profile, CaptureBench, CaptureMethod, DiffAlgorithm, DiffBench,
+    EncodeBench, OcrBench, Timing, DIFF_ALGORITHMS,
+};
+use screenpipe_vision::capture_backend::{render_synthetic_frame, SyntheticFrame};
+use std::time::Duration;
+
+fn timing(p95_ms: f64) -> Timing {
+    Timing {
+        samples: 10,
+        mean_ms: p95_ms,
+        p50_ms: p95_ms,
+        p95_ms,
+        max_ms: p95_ms,
+    }
+}
+
+fn capture(monitor_id: u32, method: CaptureMethod, p95_ms: Option<f64>) -> CaptureBench {
+    CaptureBench {
+        monitor_id,
+        width: 1920,
+        height: 1080,
+        method,
+        timing: p95_ms.map(timing),
+        error: p95_ms
+            .is_none()
+            .then(|| "monitor capture failed".to_string()),
+    }
+}
+
+fn diff(p95_ms: f64) -> Vec<DiffBench> {
+    vec![DiffBench {
+        algorithm: DiffAlgorithm::HistogramSsim,
+        width: 1920,
+        height: 1080,
+        timing: timing(p95_ms),
+    }]
+}
+
+fn ocr(p95_ms: Option<f64>) -> OcrBench {
+    OcrBench {
+        engine: "Tesseract".to_string(),
+        width: 1920,
+        height: 1080,
+        timing: p95_ms.map(timing),
+        error: p95_ms.is_none().then(|| "tesseract not found".to_string()),
+    }
+}
+
+fn encode(codec: &str, preset: &str, frames_per_second: f64) -> EncodeBench {
+    EncodeBench {
+        codec: codec.to_string(),
+        preset: preset.to_string(),
+        width: 1920,
+        height: 1080,
+        frames: 30,
+        frames_per_second: Some(frames_per_second),
+        error: None,
+    }
+}
+
+#[test]
+fn test_timing_from_samples() {
+    let samples: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+    let timing = Timing::from_samples(&samples).unwrap();
+    assert_eq!(timing.samples, 20);
+    assert_eq!(timing.p50_ms, 10.0);
+    assert_eq!(timing.p95_ms, 19.0);
+    assert_eq!(timing.max_ms, 20.0);
+    assert!((timing.mean_ms - 10.5).abs() < 1e-9);
+
+    assert_eq!(
+        Timing::from_samples(&[Duration::from_millis(7)])
+            .unwrap()
+            .p95_ms,
+        7.0
+    );
+    assert!(Timing::from_samples(&[]).is_none());
+}
+
+#[test]
+fn test_recommend_profile() {
+    // 40 ms capture, 20 ms diff, 150 ms ocr and 40 ms encode: 250 ms a frame, 2 fps at half
+    // the time
+    let profile = recommend_profile(
+        1,
+        &[
+            capture(1, CaptureMethod::MonitorAndWindows, Some(40.0)),
+            capture(1, CaptureMethod::FocusedWindow, Some(30.0)),
+        ],
+        &diff(20.0),
+        &ocr(Some(150.0)),
+        &[
+            encode("libx265", "ultrafast", 25.0),
+            encode("libx264", "ultrafast", 100.0),
+        ],
+    );
+    assert_eq!(profile.fps, 2.0);
+    assert!(!profile.focused_window_only);
+    assert!((profile.frame_cost_ms - 250.0).abs() < 1e-9);
+    assert_eq!(profile.command, "screenpipe --fps 2");
+
+    // every monitor is captured, compared, read and encoded each frame
+    let profile = recommend_profile(
+        2,
+        &[
+            capture(1, CaptureMethod::MonitorAndWindows, Some(40.0)),
+            capture(2, CaptureMethod::MonitorAndWindows, Some(60.0)),
+        ],
+        &diff(20.0),
+        &ocr(Some(50.0)),
+        &[encode("libx265", "ultrafast", 25.0)],
+    );
+    assert!((profile.frame_cost_ms - 320.0).abs() < 1e-9);
+    assert_eq!(profile.fps, 1.0);
+
+    // an engine that can't read is left out, with a note
+    let profile = recommend_profile(
+        1,
+        &[capture(1, CaptureMethod::MonitorAndWindows, Some(40.0))],
+        &diff(20.0),
+        &ocr(None),
+        &[encode("libx265", "ultrafast", 25.0)],
+    );
+    assert!((profile.frame_cost_ms - 100.0).abs() < 1e-9);
+    assert!(profile
+        .notes
+        .iter()
+        .any(|note| note.contains("Tesseract ocr failed")));
+}
+
+#[test]
+fn test_recommend_focused_window_only_when_windows_are_slow() {
+    let profile = recommend_profile(
+        1,
+        &[
+            capture(1, CaptureMethod::MonitorAndWindows, Some(900.0)),
+            capture(1, CaptureMethod::FocusedWindow, Some(80.0)),
+        ],
+        &diff(20.0),
+        &ocr(Some(50.0)),
+        &[encode("libx265", "ultrafast", 10.0)],
+    );
+    assert!(profile.focused_window_only);
+    assert_eq!(profile.fps, 2.0);
+    assert_eq!(profile.command, "screenpipe --fps 2 --focused-window-only");
+}
+
+#[test]
+fn test_recommend_profile_on_a_slow_machine() {
+    let profile = recommend_profile(
+        1,
+        &[capture(1, CaptureMethod::MonitorAndWindows, Some(3000.0))],
+        &diff(500.0),
+        &ocr(Some(1000.0)),
+        &[encode("libx265", "ultrafast", 0.5)],
+    );
+    // the lowest step, with a note that it's more than the share of the time
+    assert_eq!(profile.fps, 0.2);
+    assert!(profile
+        .notes
+        .iter()
+        .any(|note| note.contains("even 0.2 fps")));
+}
+
+#[test]
+fn test_bench_diff() {
+    let frames: Vec<_> = (0..3)
+        .map(|index| {
+            render_synthetic_frame(SyntheticFrame::Gradient { offset: index * 8 }, 0, 64, 32)
+                .unwrap()
+        })
+        .collect();
+    for algorithm in DIFF_ALGORITHMS {
+        let bench = bench_diff(&frames, algorithm, 5).unwrap();
+        assert_eq!(bench.algorithm, algorithm);
+        assert_eq!((bench.width, bench.height), (64, 32));
+        assert_eq!(bench.timing.samples, 5);
+    }
+    assert!(bench_diff(&[], DiffAlgorithm::Hash, 5).is_err());
+}
@@ -0,0 +1,74 @@
+use screenpipe_server::categorization::CategoriesConfig;
+
+fn config() -> CategoriesConfig {
+    CategoriesConfig::parse(
+        r##"{
+            "rules": [
+                {"category": "coding", "app": "^(code|iterm2)$"},
+                {"category": "email", "app": "mail|outlook"},
+                {"category": "email", "url": "mail\\.google\\.com"},
+                {"category": "client x", "title": "acme", "priority": 10},
+                {"category": "browsing", "app": "firefox|chrome"},
+                {"category": "other", "priority": -1}
+            ],
+            "colors": {"coding": "#59a14f"}
+        }"##,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_categorize() {
+    let categorizer = config().categorizer().unwrap();
+    let categorize = |app, title, url| categorizer.categorize(app, title, url);
+
+    assert_eq!(
+        categorize("Code", "main.rs — screenpipe", None),
+        Some("coding")
+    );
+    assert_eq!(categorize("Outlook", "Inbox", None), Some("email"));
+    // the higher priority wins over the order of the file
+    assert_eq!(
+        categorize("Code", "acme-invoicing — lib.rs", None),
+        Some("client x")
+    );
+    // ties go to the first rule
+    assert_eq!(
+        categorize("Firefox", "Inbox", Some("https://mail.google.com/u/0")),
+        Some("email")
+    );
+    assert_eq!(categorize("Firefox", "Hacker News", None), Some("browsing"));
+    // the fallback matches anything left
+    assert_eq!(categorize("Finder", "Downloads", None), Some("other"));
+
+    let without_fallback = CategoriesConfig::parse(
+        r#"{"rules": [{"category": "email", "url": "mail\\.google\\.com"}]}"#,
+    )
+    .unwrap()
+    .categorizer()
+    .unwrap();
+    // a url pattern never matches apps without one
+    assert_eq!(
+        without_fallback.categorize("Mail", "mail.google.com", None),
+        None
+    );
+}
+
+#[test]
+fn test_category_colors() {
+    let categorizer = config().categorizer().unwrap();
+    assert_eq!(categorizer.color("coding"), "#59a14f");
+    // picked by the name, the same every time
+    let color = categorizer.color("email");
+    assert_eq!(color.len(), 7);
+    assert_eq!(categorizer.color("email"), color);
+}
+
+#[test]
+fn test_parse_categories_config() {
+    assert!(CategoriesConfig::parse("{}").unwrap().rules.is_empty());
+    assert!(CategoriesConfig::parse(r#"{"rules": [{"category": "x", "app": "("}]}"#).is_err());
+    assert!(CategoriesConfig::parse(r#"{"rules": [{"category": " ", "app": "code"}]}"#).is_err());
+    assert!(CategoriesConfig::parse(r#"{"rules": [{"project": "x"}]}"#).is_err());
+    assert!(CategoriesConfig::parse(r#"{"colors": {"coding": "green"}}"#).is_err());
+}
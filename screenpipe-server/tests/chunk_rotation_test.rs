@@ -0,0 +1,46 @@
+use screenpipe_server::chunk_rotation::ChunkRotation;
+use std::time::Duration;
+
+#[test]
+fn test_adaptive_rotation_ends_at_boundaries_near_the_target() {
+    // 60 second chunks at 1 fps
+    let rotation = ChunkRotation::adaptive(60, 1.0);
+
+    // too early to cut
+    assert!(!rotation.ends_at_app_switch(30, Some("Slack"), Some("Code")));
+    assert!(!rotation.ends_at_lull(30, Duration::from_secs(60)));
+
+    assert!(rotation.ends_at_app_switch(45, Some("Slack"), Some("Code")));
+    assert!(!rotation.ends_at_app_switch(45, Some("Code"), Some("Code")));
+    // a frame without a focused window doesn't switch apps
+    assert!(!rotation.ends_at_app_switch(45, Some("Code"), None));
+
+    // an app encoded at another quality waits for the chunk to be long enough too
+    assert!(!rotation.ends_at_quality_change(30));
+    assert!(rotation.ends_at_quality_change(45));
+
+    assert!(rotation.ends_at_lull(50, Duration::from_secs(10)));
+    assert!(!rotation.ends_at_lull(50, Duration::from_secs(3)));
+
+    // without a boundary the chunk ends a quarter after the target
+    assert!(!rotation.is_full(74));
+    assert!(rotation.is_full(75));
+}
+
+#[test]
+fn test_lull_is_longer_than_the_capture_interval() {
+    // a frame every 5 seconds doesn't make every gap a lull
+    let rotation = ChunkRotation::adaptive(12, 0.2);
+    assert!(!rotation.ends_at_lull(12, Duration::from_secs(10)));
+    assert!(rotation.ends_at_lull(12, Duration::from_secs(15)));
+}
+
+#[test]
+fn test_fixed_rotation() {
+    let rotation = ChunkRotation::fixed(60);
+    assert!(!rotation.is_full(59));
+    assert!(rotation.is_full(60));
+    assert!(!rotation.ends_at_app_switch(59, Some("Slack"), Some("Code")));
+    assert!(!rotation.ends_at_lull(59, Duration::from_secs(3600)));
+    assert!(!rotation.ends_at_quality_change(59));
+}
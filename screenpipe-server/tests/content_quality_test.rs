@@ -0,0 +1,59 @@
+use image::{DynamicImage, Luma, RgbImage};
+use screenpipe_server::content_quality::{
+    chunk_crf, classify_content, edge_stats, ContentKind, EdgeStats,
+};
+
+fn gray(width: u32, height: u32, pixel: impl Fn(u32, u32) -> u8) -> DynamicImage {
+    DynamicImage::ImageLuma8(image::ImageBuffer::from_fn(width, height, |x, y| {
+        Luma([pixel(x, y)])
+    }))
+}
+
+#[test]
+fn test_classify_content() {
+    // lines of thin dark strokes on a white page
+    let document = gray(640, 400, |x, y| {
+        if (40..360).contains(&y) && (y / 16) % 2 == 0 && x % 8 == 0 {
+            0
+        } else {
+            255
+        }
+    });
+    let stats = edge_stats(&document);
+    assert!(stats.flat_ratio > 0.6, "{:?}", stats);
+    assert_eq!(classify_content(stats, 0), ContentKind::Text);
+
+    // smooth shading everywhere, like a photo or a video frame
+    let photo = gray(640, 400, |x, y| ((x * 7 + y * 13) % 256) as u8);
+    assert_eq!(
+        classify_content(edge_stats(&photo), 0),
+        ContentKind::Photographic
+    );
+
+    // an empty desktop, unless OCR found text in it
+    let blank = DynamicImage::ImageRgb8(RgbImage::from_pixel(640, 400, image::Rgb([40, 40, 40])));
+    let stats = edge_stats(&blank);
+    assert_eq!(
+        stats,
+        EdgeStats {
+            edge_ratio: 0.0,
+            flat_ratio: 1.0
+        }
+    );
+    assert_eq!(classify_content(stats, 0), ContentKind::Mixed);
+    assert_eq!(classify_content(stats, 2000), ContentKind::Text);
+}
+
+#[test]
+fn test_chunk_crf() {
+    // mixed content keeps the fixed quality of x265 and of x264 in low power mode
+    assert_eq!(chunk_crf(ContentKind::Mixed, false), 23);
+    assert_eq!(chunk_crf(ContentKind::Mixed, true), 28);
+    for low_power in [false, true] {
+        assert!(chunk_crf(ContentKind::Text, low_power) < chunk_crf(ContentKind::Mixed, low_power));
+        assert!(
+            chunk_crf(ContentKind::Photographic, low_power)
+                > chunk_crf(ContentKind::Mixed, low_power)
+        );
+    }
+}
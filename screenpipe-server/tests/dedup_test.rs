@@ -0,0 +1,118 @@
+use chrono::{TimeZone, Utc};
+use image::{DynamicImage, Rgb, RgbImage};
+use screenpipe_db::FrameHash;
+use screenpipe_server::dedup::{find_duplicates, hash_distance, perceptual_hash, RecentScreens};
+
+/// A screen with a gradient and, optionally, a shape drawn over it.
+fn screen(shape: Option<(u32, u32, u32, u32)>) -> DynamicImage {
+    let mut image = RgbImage::from_fn(1280, 720, |x, y| {
+        Rgb([(x * 255 / 1280) as u8, (y * 255 / 720) as u8, 80])
+    });
+    if let Some((x, y, width, height)) = shape {
+        for dx in 0..width {
+            for dy in 0..height {
+                image.put_pixel(x + dx, y + dy, Rgb([255, 255, 255]));
+            }
+        }
+    }
+    DynamicImage::ImageRgb8(image)
+}
+
+fn frame(id: i64, device_name: &str, window: &str, perceptual_hash: u64) -> FrameHash {
+    frame_with_text(id, device_name, window, perceptual_hash, None)
+}
+
+fn frame_with_text(
+    id: i64,
+    device_name: &str,
+    window: &str,
+    perceptual_hash: u64,
+    text: Option<&str>,
+) -> FrameHash {
+    FrameHash {
+        id,
+        device_name: device_name.to_string(),
+        app_name: Some("loginwindow".to_string()),
+        window_name: Some(window.to_string()),
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap(),
+        perceptual_hash: perceptual_hash as i64,
+        text: text.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_perceptual_hash_tolerates_small_changes() {
+    let lock_screen = perceptual_hash(&screen(None));
+    assert_eq!(lock_screen, perceptual_hash(&screen(None)));
+
+    // the clock ticking
+    let clock = perceptual_hash(&screen(Some((600, 40, 40, 16))));
+    assert!(hash_distance(lock_screen, clock) <= 3);
+
+    // a window opening over the lock screen
+    let window = perceptual_hash(&screen(Some((100, 100, 800, 500))));
+    assert!(hash_distance(lock_screen, window) > 3);
+}
+
+#[test]
+fn test_recent_screens_finds_the_closest_screen() {
+    let mut screens = RecentScreens::default();
+    screens.insert(0b0000, "first");
+    screens.insert(0b0111, "second");
+
+    assert_eq!(screens.find(0b0001), Some(&"first"));
+    assert_eq!(screens.find(0b1111), Some(&"second"));
+    assert_eq!(screens.find(u64::MAX), None);
+
+    // only the last screens are remembered
+    for i in 0..32 {
+        screens.insert(u64::MAX << i, "other");
+    }
+    assert_eq!(screens.find(0b0000), None);
+
+    let mut screens = RecentScreens::default();
+    screens.insert(0b0000, "$ ls");
+    screens.insert(0b0001, "$ ls\nCargo.toml");
+    assert_eq!(
+        screens.find_where(0b0000, |text| text.starts_with("$ ls\n")),
+        Some(&"$ ls\nCargo.toml")
+    );
+    assert_eq!(screens.find_where(0b0000, |text| text.is_empty()), None);
+}
+
+#[test]
+fn test_find_duplicates_per_monitor_and_window() {
+    let lock_screen = 0xF0F0_0F0F_AAAA_5555;
+    let desktop = !lock_screen;
+    let frames = vec![
+        frame(1, "monitor_1", "lock", lock_screen),
+        // another window of the same capture
+        frame(2, "monitor_1", "menu bar", lock_screen),
+        frame(3, "monitor_1", "lock", desktop),
+        frame(4, "monitor_1", "lock", lock_screen ^ 0b11),
+        frame(5, "monitor_1", "menu bar", lock_screen),
+        frame(6, "monitor_2", "lock", lock_screen),
+    ];
+
+    assert_eq!(find_duplicates(&frames), vec![(4, 1), (5, 2)]);
+}
+
+#[test]
+fn test_find_duplicates_compares_text() {
+    let terminal = 0xF0F0_0F0F_AAAA_5555;
+    let frames = vec![
+        frame_with_text(1, "monitor_1", "zsh", terminal, Some("$ ls")),
+        // a new line barely changes the image
+        frame_with_text(
+            2,
+            "monitor_1",
+            "zsh",
+            terminal ^ 0b1,
+            Some("$ ls\nCargo.toml"),
+        ),
+        frame_with_text(3, "monitor_1", "zsh", terminal, Some("$ ls\nCargo.toml")),
+        frame_with_text(4, "monitor_1", "zsh", terminal, Some("$ ls")),
+    ];
+
+    assert_eq!(find_duplicates(&frames), vec![(3, 2), (4, 1)]);
+}
@@ -0,0 +1,76 @@
+use futures::StreamExt;
+use image::DynamicImage;
+use screenpipe_db::DatabaseManager;
+use screenpipe_events::subscribe_to_event;
+use screenpipe_server::core::{store_window_text, FrameWritten};
+use screenpipe_server::deferred_ocr::{set_deferred_ocr, DeferredOcr};
+use screenpipe_vision::core::WindowOcrResult;
+use screenpipe_vision::OcrEngine;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_frame_written_while_ocr_is_deferred() {
+    set_deferred_ocr(DeferredOcr {
+        ocr_engine: Arc::new(OcrEngine::Tesseract),
+        languages: vec![],
+        subtitle_sidecars: false,
+    });
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    db.insert_video_chunk("monitor_1.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame(
+            "monitor_1",
+            None,
+            None,
+            Some("Code"),
+            Some("main.rs"),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+    let window = WindowOcrResult {
+        image: DynamicImage::new_rgb8(1, 1),
+        window_name: "main.rs".to_string(),
+        app_name: "Code".to_string(),
+        text: String::new(),
+        text_json: vec![],
+        focused: true,
+        confidence: 0.0,
+        browser_url: None,
+        bounds: None,
+    };
+
+    let mut events = subscribe_to_event::<FrameWritten>("frame_written");
+    store_window_text(
+        &db,
+        frame_id,
+        1,
+        &window,
+        "",
+        "[]",
+        &[],
+        &OcrEngine::Tesseract,
+    )
+    .await
+    .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("frame_written wasn't sent")
+        .unwrap()
+        .data;
+    assert_eq!(event.frame_id, frame_id);
+    assert_eq!(event.monitor_id, 1);
+    assert_eq!(event.app_name, "Code");
+    // the text is left to the job OCRing the chunk
+    let unread = db
+        .get_video_chunk_unread_frames("monitor_1.mp4")
+        .await
+        .unwrap();
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].frame_id, frame_id);
+}
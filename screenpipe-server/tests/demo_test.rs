@@ -0,0 +1,57 @@
+use clap::Parser;
+use screenpipe_server::cli::{CliAudioTranscriptionEngine, CliOcrEngine};
+use screenpipe_server::Cli;
+
+#[test]
+fn test_demo_forces_privacy() {
+    let mut cli = Cli::try_parse_from([
+        "screenpipe",
+        "--demo",
+        "--audio-transcription-engine",
+        "deepgram",
+        "--ocr-engine",
+        "unstructured",
+        "--stream-url",
+        "rtmp://example.com/live",
+        "--capture-unfocused-windows",
+        "--enable-pipe-manager",
+        "--hub-token",
+        "secret",
+        "--llm-api-url",
+        "https://api.openai.com/v1",
+        "--llm-api-key",
+        "sk-llm",
+        "--embedding-api-url",
+        "https://api.openai.com/v1",
+        "--embedding-api-key",
+        "sk-embedding",
+    ])
+    .unwrap();
+    assert!(cli.demo);
+
+    cli.apply_demo_privacy();
+    assert!(cli.use_pii_removal);
+    assert!(cli.disable_telemetry);
+    assert!(cli.mute_audio_on_ignored_windows);
+    assert!(cli.enable_access_log);
+    assert!(!cli.capture_unfocused_windows);
+    assert!(!cli.enable_pipe_manager);
+    assert_eq!(cli.stream_url, None);
+    assert_eq!(cli.hub_token, None);
+    assert_eq!(cli.llm_api_url, "http://localhost:11434/v1");
+    assert_eq!(cli.llm_api_key, None);
+    assert_eq!(cli.embedding_api_url, "http://localhost:11434/v1");
+    assert_eq!(cli.embedding_api_key, None);
+    assert_eq!(
+        cli.audio_transcription_engine,
+        CliAudioTranscriptionEngine::WhisperTinyQuantized
+    );
+    assert_ne!(cli.ocr_engine, CliOcrEngine::Unstructured);
+}
+
+#[test]
+fn test_demo_conflicts_with_data_dir() {
+    assert!(
+        Cli::try_parse_from(["screenpipe", "--demo", "--data-dir", "/tmp/screenpipe"]).is_err()
+    );
+}
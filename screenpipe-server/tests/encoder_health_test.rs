@@ -0,0 +1,73 @@
+use screenpipe_server::encoder_health::{
+    classify_ffmpeg_line, EncoderHealth, EncoderState, FfmpegLogLevel,
+};
+use std::time::Duration;
+
+#[test]
+fn test_classify_ffmpeg_line() {
+    assert_eq!(
+        classify_ffmpeg_line("[libx265 @ 0x7f8b1c004c00] [error] x265_encoder_open() failed"),
+        Some(FfmpegLogLevel::Error)
+    );
+    assert_eq!(
+        classify_ffmpeg_line("[fatal] Conversion failed!"),
+        Some(FfmpegLogLevel::Error)
+    );
+    assert_eq!(
+        classify_ffmpeg_line(
+            "[image2pipe @ 0x7f8b1c000e00] [warning] Thread message queue blocking"
+        ),
+        Some(FfmpegLogLevel::Warning)
+    );
+    // without a level prefix
+    assert_eq!(
+        classify_ffmpeg_line("Error while decoding stream #0:0: Invalid data found"),
+        Some(FfmpegLogLevel::Error)
+    );
+    assert_eq!(
+        classify_ffmpeg_line("frame=  120 fps= 30 q=-0.0 size=    1024kB"),
+        None
+    );
+}
+
+#[test]
+fn test_encoder_health_records_warnings_and_errors() {
+    let mut health = EncoderHealth::new(1, "monitor_1_2025-03-01_10-00-00.mp4");
+    assert_eq!(health.state, EncoderState::Ok);
+
+    assert!(health.record(FfmpegLogLevel::Warning, "[warning] past duration too large"));
+    assert!(!health.record(FfmpegLogLevel::Warning, "[warning] past duration too large"));
+    assert_eq!(health.state, EncoderState::Degraded);
+
+    for i in 0..7 {
+        let changed = health.record(FfmpegLogLevel::Error, &format!("[error] failure {}", i));
+        assert_eq!(changed, i == 0);
+    }
+    // warnings don't make a failing encoder look better
+    health.record(FfmpegLogLevel::Warning, "[warning] late");
+    assert_eq!(health.state, EncoderState::Unhealthy);
+    assert_eq!((health.warnings, health.errors), (3, 7));
+    assert_eq!(health.recent_errors.len(), 5);
+    assert_eq!(health.recent_errors[4], "[error] failure 6");
+}
+
+#[test]
+fn test_encoder_restarts_back_off_while_failing() {
+    let mut health = EncoderHealth::new(1, "monitor_1_2025-03-01_10-00-00.mp4");
+    assert_eq!(health.restart_backoff(), Duration::ZERO);
+
+    let mut backoffs = Vec::new();
+    for i in 0..8 {
+        health.record(FfmpegLogLevel::Error, "[error] x265_encoder_open() failed");
+        backoffs.push(health.restart_backoff().as_secs());
+        health = health.next_chunk(&format!("monitor_1_2025-03-01_10-0{}-00.mp4", i + 1));
+        assert_eq!(health.state, EncoderState::Ok);
+        assert_eq!(health.errors, 0);
+    }
+    assert_eq!(backoffs, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    assert_eq!(health.consecutive_failures, 8);
+
+    // a chunk encoded without errors ends the streak
+    let health = health.next_chunk("monitor_1_2025-03-01_10-09-00.mp4");
+    assert_eq!(health.consecutive_failures, 0);
+}
@@ -48,7 +48,11 @@ mod tests {
             false,
             false,
             audio_manager,
-            true
+            true,
+            Arc::new(OcrEngine::Tesseract),
+            None,
+            false,
+            None,
         );
 
         let router = app.create_router(true).await;
@@ -180,11 +184,11 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, None, None, true)
+            .insert_frame("test_device", None, None, None, None, true, None)
             .await
             .unwrap();
         let frame_id2 = db
-            .insert_frame("test_device", None, None, None, None, true)
+            .insert_frame("test_device", None, None, None, None, true, None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -254,6 +258,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -274,6 +280,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -294,6 +302,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -314,6 +324,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -334,6 +346,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -354,6 +368,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -374,6 +390,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -390,7 +408,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, None, None, true)
+            .insert_frame("test_device", None, None, None, None, true, None)
             .await
             .unwrap();
         let audio_chunk_id1 = db.insert_audio_chunk("test_audio1.wav").await.unwrap();
@@ -458,6 +476,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -479,6 +499,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -501,6 +523,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -527,6 +551,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -552,6 +578,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -571,6 +599,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -592,7 +622,7 @@ mod tests {
             .await
             .unwrap();
         let old_frame_id = db
-            .insert_frame("test_device", None, None, None, None, true)
+            .insert_frame("test_device", None, None, None, None, true, None)
             .await
             .unwrap();
 
@@ -602,7 +632,7 @@ mod tests {
             .await
             .unwrap();
         let recent_frame_id = db
-            .insert_frame("test_device", None, None, None, None, true)
+            .insert_frame("test_device", None, None, None, None, true, None)
             .await
             .unwrap();
 
@@ -656,6 +686,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -686,6 +718,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -732,6 +766,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -755,6 +791,8 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
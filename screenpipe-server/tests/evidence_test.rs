@@ -0,0 +1,156 @@
+use chrono::{TimeZone, Utc};
+use screenpipe_server::evidence::{
+    build_failed_manifest, build_manifest, chain_hash, encode_public_key,
+    load_or_create_signing_key, manifest_path, verify_chain, SegmentManifest, GENESIS_HASH,
+};
+use std::path::{Path, PathBuf};
+
+fn seal(
+    dir: &Path,
+    name: &str,
+    previous: Option<&SegmentManifest>,
+    key: &ring::signature::Ed25519KeyPair,
+) -> SegmentManifest {
+    let video = dir.join(name);
+    std::fs::write(&video, format!("frames of {}", name)).unwrap();
+    let manifest = build_manifest(
+        &video,
+        previous.map_or(1, |previous| previous.sequence + 1),
+        previous.map_or(GENESIS_HASH, |previous| previous.chain_hash.as_str()),
+        Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap(),
+        Some(key),
+    )
+    .unwrap();
+    std::fs::write(
+        manifest_path(&video),
+        serde_json::to_string(&manifest).unwrap(),
+    )
+    .unwrap();
+    manifest
+}
+
+#[test]
+fn test_verify_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = load_or_create_signing_key(&dir.path().join("evidence.key")).unwrap();
+    let public_key = encode_public_key(&key);
+    let first = seal(dir.path(), "a.mp4", None, &key);
+    let second = seal(dir.path(), "b.mp4", Some(&first), &key);
+    seal(dir.path(), "c.mp4", Some(&second), &key);
+
+    let paths = vec![dir.path().to_path_buf()];
+    let verified = verify_chain(&paths, Some(&public_key)).unwrap();
+    assert_eq!(
+        verified.iter().map(|m| m.sequence).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+
+    // the key is kept, not generated again
+    let reloaded = load_or_create_signing_key(&dir.path().join("evidence.key")).unwrap();
+    assert_eq!(encode_public_key(&reloaded), public_key);
+
+    // signed by another key
+    let other_dir = tempfile::tempdir().unwrap();
+    let other = load_or_create_signing_key(&other_dir.path().join("evidence.key")).unwrap();
+    assert!(verify_chain(&paths, Some(&encode_public_key(&other))).is_err());
+}
+
+#[test]
+fn test_verify_chain_detects_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = load_or_create_signing_key(&dir.path().join("evidence.key")).unwrap();
+    let first = seal(dir.path(), "a.mp4", None, &key);
+    let second = seal(dir.path(), "b.mp4", Some(&first), &key);
+    seal(dir.path(), "c.mp4", Some(&second), &key);
+    let paths = vec![dir.path().to_path_buf()];
+
+    std::fs::write(dir.path().join("b.mp4"), "other frames").unwrap();
+    assert!(verify_chain(&paths, None).is_err());
+
+    // a segment removed from the middle
+    std::fs::remove_file(dir.path().join("b.mp4")).unwrap();
+    std::fs::remove_file(manifest_path(&dir.path().join("b.mp4"))).unwrap();
+    assert!(verify_chain(&paths, None).is_err());
+
+    // the end of the chain alone still verifies
+    let last: Vec<PathBuf> = vec![manifest_path(&dir.path().join("c.mp4"))];
+    assert_eq!(verify_chain(&last, None).unwrap()[0].sequence, 3);
+}
+
+#[test]
+fn test_chain_hash() {
+    let sealed_at = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    let hash = chain_hash(1, "a.mp4", "abc", 3, GENESIS_HASH, sealed_at, None);
+    assert_eq!(hash.len(), 64);
+    assert_eq!(
+        hash,
+        chain_hash(1, "a.mp4", "abc", 3, GENESIS_HASH, sealed_at, None)
+    );
+    assert_ne!(
+        hash,
+        chain_hash(2, "a.mp4", "abc", 3, GENESIS_HASH, sealed_at, None)
+    );
+    assert_ne!(
+        hash,
+        chain_hash(1, "a.mp4", "abd", 3, GENESIS_HASH, sealed_at, None)
+    );
+    assert_ne!(
+        hash,
+        chain_hash(
+            1,
+            "a.mp4",
+            "abc",
+            3,
+            GENESIS_HASH,
+            sealed_at,
+            Some("ffmpeg failed")
+        )
+    );
+}
+
+#[test]
+fn test_verify_chain_with_failed_seal() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = load_or_create_signing_key(&dir.path().join("evidence.key")).unwrap();
+    let first = seal(dir.path(), "a.mp4", None, &key);
+
+    let video = dir.path().join("b.mp4");
+    std::fs::write(&video, "frames of b.mp4").unwrap();
+    let failed = build_failed_manifest(
+        &video,
+        2,
+        &first.chain_hash,
+        Utc.with_ymd_and_hms(2025, 3, 1, 10, 1, 0).unwrap(),
+        "ffmpeg exited with 1",
+        Some(&key),
+    )
+    .unwrap();
+    std::fs::write(
+        manifest_path(&video),
+        serde_json::to_string(&failed).unwrap(),
+    )
+    .unwrap();
+    seal(dir.path(), "c.mp4", Some(&failed), &key);
+    // the video of a failed link isn't vouched for, whatever happens to it
+    std::fs::write(&video, "other frames").unwrap();
+
+    let paths = vec![dir.path().to_path_buf()];
+    let verified = verify_chain(&paths, Some(&encode_public_key(&key))).unwrap();
+    assert_eq!(
+        verified
+            .iter()
+            .map(|m| (m.sequence, m.seal_error.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![(1, None), (2, Some("ffmpeg exited with 1")), (3, None)]
+    );
+
+    // the error can't be dropped to pass the link off as sealed
+    let mut forged = failed.clone();
+    forged.seal_error = None;
+    std::fs::write(
+        manifest_path(&video),
+        serde_json::to_string(&forged).unwrap(),
+    )
+    .unwrap();
+    assert!(verify_chain(&paths, None).is_err());
+}
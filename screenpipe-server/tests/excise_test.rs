@@ -0,0 +1,120 @@
+use chrono::Utc;
+use screenpipe_db::{DatabaseManager, FrameLocation};
+use screenpipe_server::excise::{
+    chunk_cuts, excise_last_minutes, excise_segment, request_chunk_cut, trimmed_frame_count,
+    ExciseSegmentPayload, EXCISE_SEGMENT_JOB,
+};
+use screenpipe_server::pyramid::pyramid_frame_path;
+use screenpipe_server::storage::{read_journal, EncodedFrame, JournalRecord, Storage};
+use screenpipe_server::subtitles::sidecar_path;
+use std::path::Path;
+
+fn frame(offset_index: i64) -> FrameLocation {
+    FrameLocation {
+        frame_id: offset_index,
+        timestamp: Utc::now(),
+        file_path: "chunk.mp4".to_string(),
+        offset_index,
+    }
+}
+
+#[test]
+fn test_trimmed_frame_count() {
+    assert_eq!(trimmed_frame_count(&[frame(0), frame(1), frame(2)]), 3);
+    // a frame deduplicated away still has its place in the video
+    assert_eq!(trimmed_frame_count(&[frame(0), frame(4)]), 5);
+    assert_eq!(trimmed_frame_count(&[]), 0);
+}
+
+#[test]
+fn test_request_chunk_cut() {
+    let before = chunk_cuts();
+    request_chunk_cut();
+    assert!(chunk_cuts() > before);
+}
+
+#[tokio::test]
+async fn test_excise_last_minutes() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path().join("data"));
+    assert!(excise_last_minutes(&db, &storage, 0, None).await.is_err());
+
+    let video = dir.path().join("monitor_1_2025-03-01_10-00-00.mp4");
+    std::fs::write(&video, b"video").unwrap();
+    let video = video.to_string_lossy().into_owned();
+    db.insert_video_chunk(&video, "monitor_1").await.unwrap();
+    db.insert_frame(
+        "monitor_1",
+        Some(Utc::now() - chrono::Duration::minutes(2)),
+        None,
+        Some("Bank"),
+        None,
+        true,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // a frame of the range still waiting to be indexed, and one from before it
+    let journal = storage.frame_journal(1).unwrap();
+    for (frame_number, minutes) in [(1, 10), (2, 2)] {
+        journal
+            .append(&JournalRecord::Encoded(EncodedFrame {
+                frame_number,
+                chunk_path: video.clone(),
+                offset_index: frame_number as i64 - 1,
+                device_name: "monitor_1".to_string(),
+                timestamp: Utc::now() - chrono::Duration::minutes(minutes),
+                windows: Vec::new(),
+            }))
+            .unwrap();
+    }
+    let timelapse = storage.host_dir().join("timelapses").join(format!(
+        "{}_monitor_1.mp4",
+        chrono::Local::now().format("%Y-%m-%d")
+    ));
+    std::fs::create_dir_all(timelapse.parent().unwrap()).unwrap();
+    std::fs::write(&timelapse, b"video").unwrap();
+
+    let cuts = chunk_cuts();
+    let redaction = excise_last_minutes(&db, &storage, 5, Some("hotkey"))
+        .await
+        .unwrap();
+    assert_eq!(redaction.frames, 1);
+    assert_eq!(redaction.reason.as_deref(), Some("hotkey"));
+    // the chunk being recorded is ended
+    assert!(chunk_cuts() > cuts);
+
+    let jobs = db.list_jobs(None, 10, 0).await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].kind, EXCISE_SEGMENT_JOB);
+    let payload: ExciseSegmentPayload = serde_json::from_str(&jobs[0].payload).unwrap();
+    assert_eq!(payload.video_path, video);
+    assert_eq!(payload.start_time, redaction.start_time);
+    let journaled: Vec<u64> = read_journal(&storage.journal_path(1))
+        .unwrap()
+        .into_iter()
+        .map(|record| match record {
+            JournalRecord::Encoded(frame) => frame.frame_number,
+            JournalRecord::Indexed { frame_number } => frame_number,
+        })
+        .collect();
+    assert_eq!(journaled, vec![1]);
+    assert!(!timelapse.exists());
+
+    // nothing of the chunk is left, it goes with the files next to it
+    let sidecar = sidecar_path(Path::new(&video));
+    std::fs::write(&sidecar, b"WEBVTT").unwrap();
+    let pyramid_frame = pyramid_frame_path(&video, 1, 0);
+    std::fs::create_dir_all(pyramid_frame.parent().unwrap()).unwrap();
+    std::fs::write(&pyramid_frame, b"jpg").unwrap();
+
+    let kept = excise_segment(&db, &video, payload.start_time, None)
+        .await
+        .unwrap();
+    assert_eq!(kept, 0);
+    assert!(!Path::new(&video).exists());
+    assert!(!sidecar.exists());
+    assert!(!pyramid_frame.exists());
+}
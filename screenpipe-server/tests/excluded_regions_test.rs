@@ -0,0 +1,40 @@
+use screenpipe_server::excluded_regions::{ExcludedRegionsConfig, EXCLUDED_REGIONS_CONFIG_FILE};
+
+#[test]
+fn test_parse_excluded_regions() {
+    let config = ExcludedRegionsConfig::parse(
+        r#"{
+            "regions": [
+                {"monitor_id": 1, "x": 1500, "y": 0, "width": 420, "height": 1080, "label": "password manager"},
+                {"x": 0, "y": 0, "width": 300, "height": 40}
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config.regions.len(), 2);
+    assert_eq!(config.regions[0].monitor_id, Some(1));
+    assert_eq!(config.regions[1].monitor_id, None);
+    assert_eq!(config.regions[1].label, None);
+
+    assert!(ExcludedRegionsConfig::parse(
+        r#"{"regions": [{"x": 0, "y": 0, "width": 0, "height": 40}]}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_save_and_load_excluded_regions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(EXCLUDED_REGIONS_CONFIG_FILE);
+    assert_eq!(
+        ExcludedRegionsConfig::load(&path).unwrap(),
+        ExcludedRegionsConfig::default()
+    );
+
+    let config = ExcludedRegionsConfig::parse(
+        r#"{"regions": [{"monitor_id": 2, "x": -10, "y": 5, "width": 100, "height": 50}]}"#,
+    )
+    .unwrap();
+    config.save(&path).unwrap();
+    assert_eq!(ExcludedRegionsConfig::load(&path).unwrap(), config);
+}
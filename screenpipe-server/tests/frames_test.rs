@@ -0,0 +1,19 @@
+use screenpipe_server::frames::{encode_png, seek_position};
+
+#[test]
+fn test_seek_position() {
+    // frame n of a 0.5 fps chunk is shown from 2n seconds
+    assert_eq!(seek_position(3, 0.5), 5.0);
+    assert_eq!(seek_position(0, 0.5), 0.0);
+    // between frames 7 and 8 of 30 fps, closer to 7
+    let position = seek_position(8, 30.0);
+    assert!(position > 7.0 / 30.0 && position < 8.0 / 30.0);
+}
+
+#[test]
+fn test_encode_png() {
+    let image = image::DynamicImage::new_rgb8(4, 2);
+    let png = encode_png(&image).unwrap();
+    let decoded = image::load_from_memory(&png).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (4, 2));
+}
@@ -0,0 +1,78 @@
+use chrono::{TimeZone, Utc};
+use screenpipe_db::{OCRResult, SearchResult};
+use screenpipe_server::core::FrameWritten;
+use screenpipe_server::grpc::proto::{search_hit::Content, FrameEvent, SearchHit};
+use screenpipe_server::grpc::{datetime, timestamp};
+use screenpipe_server::power::{
+    capture_paused, current_throttle, set_capture_paused, ThrottleMode,
+};
+
+#[test]
+fn test_timestamps_round_trip() {
+    let time =
+        Utc.with_ymd_and_hms(2025, 3, 1, 14, 0, 0).unwrap() + chrono::Duration::milliseconds(250);
+    let proto = timestamp(time);
+    assert_eq!(proto.seconds, time.timestamp());
+    assert_eq!(proto.nanos, 250_000_000);
+    assert_eq!(datetime(proto), Some(time));
+}
+
+#[test]
+fn test_frame_written_to_proto() {
+    let time = Utc::now();
+    let event: FrameEvent = FrameWritten {
+        frame_id: 7,
+        monitor_id: 2,
+        timestamp: time,
+        app_name: "Code".to_string(),
+        window_name: "main.rs".to_string(),
+        browser_url: None,
+        focused: true,
+        text_length: 120,
+    }
+    .into();
+    assert_eq!(event.frame_id, 7);
+    assert_eq!(event.monitor_id, 2);
+    assert_eq!(event.timestamp, Some(timestamp(time)));
+    assert_eq!(event.text_length, 120);
+}
+
+#[test]
+fn test_search_result_to_proto() {
+    let hit: SearchHit = SearchResult::OCR(OCRResult {
+        frame_id: 42,
+        frame_name: "monitor_1".to_string(),
+        ocr_text: "hello".to_string(),
+        text_json: "[]".to_string(),
+        timestamp: Utc::now(),
+        file_path: "/data/monitor_1.mp4".to_string(),
+        offset_index: 3,
+        app_name: "Safari".to_string(),
+        ocr_engine: "Tesseract".to_string(),
+        window_name: "news".to_string(),
+        tags: vec![],
+        browser_url: Some("https://example.com".to_string()),
+        focused: Some(true),
+        device_name: "monitor_1".to_string(),
+        language: Some("en".to_string()),
+        host_name: None,
+        category: None,
+    })
+    .into();
+    let Some(Content::Ocr(ocr)) = hit.content else {
+        panic!("expected an ocr hit");
+    };
+    assert_eq!(ocr.frame_id, 42);
+    assert_eq!(ocr.text, "hello");
+    assert_eq!(ocr.browser_url.as_deref(), Some("https://example.com"));
+}
+
+#[test]
+fn test_manual_pause_overrides_power_mode() {
+    set_capture_paused(true);
+    assert!(capture_paused());
+    assert_eq!(current_throttle(), ThrottleMode::Paused);
+
+    set_capture_paused(false);
+    assert_eq!(current_throttle(), ThrottleMode::Normal);
+}
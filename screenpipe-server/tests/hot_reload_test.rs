@@ -0,0 +1,122 @@
+use screenpipe_core::capture_policy::capture_policy;
+use screenpipe_server::hot_reload::{
+    subscribe_capture_fps, watch_configs, RecordingConfig, RecordingSettings, WatchedConfig,
+    RECORDING_CONFIG_FILE,
+};
+use screenpipe_vision::diff_threshold::diff_threshold_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn cli_settings() -> RecordingSettings {
+    RecordingSettings {
+        ignored_windows: vec!["bitwarden".to_string()],
+        included_windows: Vec::new(),
+        fps: 1.0,
+        diff_threshold: 0.02,
+        app_diff_thresholds: vec![("terminal".to_string(), 0.1)],
+        app_qualities: Vec::new(),
+    }
+}
+
+#[test]
+fn test_parse_recording_config() {
+    let config = RecordingConfig::parse(
+        r#"{"ignored_windows": ["1Password"], "fps": 0.5, "app_diff_thresholds": ["Figma=0.01"]}"#,
+    )
+    .unwrap();
+    assert_eq!(config.ignored_windows, Some(vec!["1Password".to_string()]));
+    assert_eq!(config.included_windows, None);
+    assert_eq!(config.fps, Some(0.5));
+
+    assert!(RecordingConfig::parse(r#"{"fps": 0}"#).is_err());
+    assert!(RecordingConfig::parse(r#"{"fps": 120}"#).is_err());
+    assert!(RecordingConfig::parse(r#"{"diff_threshold": 1.5}"#).is_err());
+    assert!(RecordingConfig::parse(r#"{"app_diff_thresholds": ["Figma"]}"#).is_err());
+    assert!(RecordingConfig::parse(r#"{"app_qualities": ["Code=1:16"]}"#).is_ok());
+    assert!(RecordingConfig::parse(r#"{"app_qualities": ["Code=3"]}"#).is_err());
+    // a typo shouldn't be ignored silently
+    assert!(RecordingConfig::parse(r#"{"ignore_windows": ["Slack"]}"#).is_err());
+}
+
+#[test]
+fn test_load_missing_recording_config_overrides_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = RecordingConfig::load(&dir.path().join(RECORDING_CONFIG_FILE)).unwrap();
+    assert_eq!(config, RecordingConfig::default());
+    assert_eq!(cli_settings().overridden_by(&config), cli_settings());
+}
+
+#[test]
+fn test_overrides_replace_only_the_settings_they_set() {
+    let config = RecordingConfig::parse(
+        r#"{"ignored_windows": [], "fps": 0.2, "app_diff_thresholds": ["Figma=0.01"]}"#,
+    )
+    .unwrap();
+    let settings = cli_settings().overridden_by(&config);
+    assert!(settings.ignored_windows.is_empty());
+    assert_eq!(settings.fps, 0.2);
+    assert_eq!(settings.diff_threshold, 0.02);
+    assert_eq!(
+        settings.app_diff_thresholds,
+        vec![("Figma".to_string(), 0.01)]
+    );
+}
+
+#[test]
+fn test_apply_recording_settings() {
+    let mut fps = subscribe_capture_fps();
+    let settings = cli_settings().overridden_by(
+        &RecordingConfig::parse(r#"{"ignored_windows": ["Slack"], "fps": 0.5}"#).unwrap(),
+    );
+    settings.apply();
+
+    assert!(fps.has_changed().unwrap());
+    assert_eq!(*fps.borrow_and_update(), Some(0.5));
+    assert!(!capture_policy().allows_window("Slack", "general"));
+    assert!(capture_policy().allows_window("Bitwarden", "vault"));
+    assert_eq!(diff_threshold_for(Some("Terminal")), 0.1);
+    assert_eq!(diff_threshold_for(Some("Finder")), 0.02);
+
+    // applying the same fps again doesn't restart the capture loops
+    settings.apply();
+    assert!(!fps.has_changed().unwrap());
+}
+
+#[tokio::test]
+async fn test_watch_configs_applies_changed_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(RECORDING_CONFIG_FILE);
+    let loaded = Arc::new(AtomicUsize::new(0));
+    let failed = Arc::new(AtomicUsize::new(0));
+
+    let counts = (loaded.clone(), failed.clone());
+    tokio::spawn(watch_configs(vec![WatchedConfig::new(
+        path.clone(),
+        move |path| {
+            let result = RecordingConfig::load(path).map(|_| ());
+            match result {
+                Ok(()) => counts.0.fetch_add(1, Ordering::SeqCst),
+                Err(_) => counts.1.fetch_add(1, Ordering::SeqCst),
+            };
+            result
+        },
+    )]));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    std::fs::write(&path, r#"{"fps": 0.5}"#).unwrap();
+    wait_for(&loaded, 1).await;
+
+    std::fs::write(&path, r#"{"fps": -1}"#).unwrap();
+    wait_for(&failed, 1).await;
+}
+
+async fn wait_for(count: &AtomicUsize, expected: usize) {
+    for _ in 0..50 {
+        if count.load(Ordering::SeqCst) >= expected {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("config was applied {} times", count.load(Ordering::SeqCst));
+}
@@ -0,0 +1,212 @@
+use axum::http::HeaderMap;
+use screenpipe_server::hub::{
+    body_digest, hub_file_path, hub_key, hub_signature, store_segment, verify_hub_request,
+    HubNonces, HUB_HOST_HEADER, HUB_NONCE_HEADER, HUB_SIGNATURE_HEADER, HUB_TIMESTAMP_HEADER,
+    MAX_CLOCK_SKEW_SECS,
+};
+use screenpipe_server::upload::{SyncConfig, SyncTarget};
+use std::path::Path;
+
+fn signed_headers(token: &str, path: &str, host: &str, timestamp: i64, body: &[u8]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let nonce = format!("{}-{}", timestamp, body.len());
+    let signature = hub_signature(
+        token,
+        "POST",
+        path,
+        host,
+        timestamp,
+        &nonce,
+        &body_digest(body),
+    );
+    headers.insert(HUB_SIGNATURE_HEADER, signature.parse().unwrap());
+    headers.insert(HUB_TIMESTAMP_HEADER, timestamp.into());
+    headers.insert(HUB_NONCE_HEADER, nonce.parse().unwrap());
+    headers.insert(HUB_HOST_HEADER, host.parse().unwrap());
+    headers
+}
+
+#[test]
+fn test_verify_hub_request() {
+    let now = 1_741_000_000;
+    let body = br#"{"frames":[]}"#;
+    let headers = signed_headers("secret", "/hub/frames", "laptop", now - 10, body);
+
+    let host = verify_hub_request(
+        "secret",
+        "POST",
+        "/hub/frames",
+        &headers,
+        &body_digest(body),
+        &HubNonces::default(),
+        now,
+    )
+    .unwrap();
+    assert_eq!(host, "laptop");
+
+    // another token, body or path
+    assert!(verify_hub_request(
+        "other",
+        "POST",
+        "/hub/frames",
+        &headers,
+        &body_digest(body),
+        &HubNonces::default(),
+        now
+    )
+    .is_err());
+    assert!(verify_hub_request(
+        "secret",
+        "POST",
+        "/hub/frames",
+        &headers,
+        &body_digest(b"{}"),
+        &HubNonces::default(),
+        now
+    )
+    .is_err());
+    assert!(verify_hub_request(
+        "secret",
+        "PUT",
+        "/hub/segments/laptop/a.mp4",
+        &headers,
+        &body_digest(body),
+        &HubNonces::default(),
+        now
+    )
+    .is_err());
+}
+
+#[test]
+fn test_verify_hub_request_rejects_replays_and_bad_hosts() {
+    let now = 1_741_000_000;
+    let body = b"{}";
+    let old = signed_headers(
+        "secret",
+        "/hub/frames",
+        "laptop",
+        now - MAX_CLOCK_SKEW_SECS - 1,
+        body,
+    );
+    assert!(verify_hub_request(
+        "secret",
+        "POST",
+        "/hub/frames",
+        &old,
+        &body_digest(body),
+        &HubNonces::default(),
+        now
+    )
+    .is_err());
+
+    let traversal = signed_headers("secret", "/hub/frames", "../etc", now, body);
+    assert!(verify_hub_request(
+        "secret",
+        "POST",
+        "/hub/frames",
+        &traversal,
+        &body_digest(body),
+        &HubNonces::default(),
+        now
+    )
+    .is_err());
+}
+
+#[test]
+fn test_verify_hub_request_rejects_reused_nonces() {
+    let now = 1_741_000_000;
+    let body = b"{}";
+    let headers = signed_headers("secret", "/hub/frames", "laptop", now, body);
+    let nonces = HubNonces::default();
+    let verify = |headers: &HeaderMap, now| {
+        verify_hub_request(
+            "secret",
+            "POST",
+            "/hub/frames",
+            headers,
+            &body_digest(body),
+            &nonces,
+            now,
+        )
+    };
+
+    assert!(verify(&headers, now).is_ok());
+    // the same request sent again while its timestamp is still accepted
+    assert!(verify(&headers, now + 10).is_err());
+
+    let mut unsigned = signed_headers("secret", "/hub/frames", "laptop", now + 1, body);
+    unsigned.remove(HUB_NONCE_HEADER);
+    assert!(verify(&unsigned, now).is_err());
+}
+
+#[tokio::test]
+async fn test_store_segment_checks_its_digest() {
+    let root = tempfile::tempdir().unwrap();
+    let body = || futures::stream::iter([Ok::<_, std::io::Error>(b"video".to_vec())]);
+
+    let error = store_segment(
+        root.path(),
+        "laptop",
+        "laptop/a.mp4",
+        &body_digest(b"other"),
+        body(),
+    )
+    .await;
+    assert!(error.is_err());
+    assert!(!root.path().join("laptop/a.mp4").exists());
+    assert!(!root.path().join("laptop/a.mp4.part").exists());
+
+    let written = store_segment(
+        root.path(),
+        "laptop",
+        "laptop/a.mp4",
+        &body_digest(b"video"),
+        body(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(written, 5);
+    assert_eq!(
+        std::fs::read(root.path().join("laptop/a.mp4")).unwrap(),
+        b"video"
+    );
+}
+
+#[test]
+fn test_hub_file_path() {
+    let root = Path::new("/hub/data");
+    assert_eq!(
+        hub_file_path(root, "laptop", "laptop/2025-03-01/monitor_1/a.mp4").unwrap(),
+        root.join("laptop/2025-03-01/monitor_1/a.mp4")
+    );
+    // peers only write into their own directory
+    assert!(hub_file_path(root, "laptop", "desktop/2025-03-01/a.mp4").is_err());
+    assert!(hub_file_path(root, "laptop", "laptop/../desktop/a.mp4").is_err());
+    assert!(hub_file_path(root, "laptop", "/laptop/a.mp4").is_err());
+    assert!(hub_file_path(root, "laptop", "laptop").is_err());
+
+    assert_eq!(hub_key("laptop", "laptop/a.mp4"), "laptop/a.mp4");
+    assert_eq!(hub_key("laptop", "a.mp4"), "laptop/a.mp4");
+}
+
+#[test]
+fn test_parse_hub_target() {
+    let config = SyncConfig::parse(
+        r#"{"target": {"type": "hub", "url": "http://desktop.local:3030", "token": "secret"}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.target,
+        SyncTarget::Hub {
+            url: "http://desktop.local:3030".to_string(),
+            token: "secret".to_string(),
+            segments: false,
+        }
+    );
+    assert!(!config.uploads_segments());
+
+    assert!(SyncConfig::parse(
+        r#"{"target": {"type": "hub", "url": "http://desktop.local:3030", "token": ""}}"#
+    )
+    .is_err());
+}
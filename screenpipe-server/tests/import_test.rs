@@ -0,0 +1,55 @@
+use chrono::{TimeZone, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::import::{
+    frame_timestamp, import_recording, ImportOptions, IMPORTED_DEVICE_NAME,
+};
+use screenpipe_server::Storage;
+use screenpipe_vision::OcrEngine;
+use std::path::Path;
+use std::sync::Arc;
+
+#[test]
+fn test_frame_timestamp() {
+    let start = Utc.with_ymd_and_hms(2024, 11, 20, 14, 0, 0).unwrap();
+
+    assert_eq!(frame_timestamp(start, 0, 1.0), start);
+    assert_eq!(
+        frame_timestamp(start, 90, 1.0),
+        Utc.with_ymd_and_hms(2024, 11, 20, 14, 1, 30).unwrap()
+    );
+    // frames decoded at 0.5 fps are two seconds apart, whatever the fps of the recording
+    assert_eq!(
+        frame_timestamp(start, 3, 0.5),
+        Utc.with_ymd_and_hms(2024, 11, 20, 14, 0, 6).unwrap()
+    );
+    assert_eq!(
+        frame_timestamp(start, 1, 3.0) - start,
+        chrono::Duration::milliseconds(333)
+    );
+}
+
+#[tokio::test]
+async fn test_import_rejects_zero_fps() {
+    let root = tempfile::tempdir().unwrap();
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let options = ImportOptions {
+        start_time: Utc::now(),
+        device_name: IMPORTED_DEVICE_NAME.to_string(),
+        fps: 0.0,
+        diff_threshold: 0.006,
+        ocr_engine: Arc::new(OcrEngine::Unstructured),
+        languages: Vec::new(),
+    };
+
+    let result = import_recording(
+        &db,
+        &Storage::with_hostname(root.path(), "workstation"),
+        Path::new("meeting.mov"),
+        &options,
+    )
+    .await;
+
+    assert!(result.is_err());
+    // nothing was added to the data dir
+    assert!(!root.path().join("workstation").exists());
+}
@@ -0,0 +1,60 @@
+use anyhow::anyhow;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::jobs::{enqueue_job, JobQueue, JobQueueConfig};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_workers_drain_and_retry_jobs() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+
+    let ok = enqueue_job(&db, "ok", &json!({ "value": 1 }), 1)
+        .await
+        .unwrap();
+    let failing = enqueue_job(&db, "failing", &json!({}), 2).await.unwrap();
+
+    JobQueue::new(
+        db.clone(),
+        JobQueueConfig {
+            workers: 2,
+            max_cpu_usage: 100.0,
+            retry_delay: Duration::ZERO,
+            poll_interval: Duration::from_millis(20),
+            ..Default::default()
+        },
+    )
+    .register("ok", |_job, progress| {
+        Box::pin(async move {
+            progress.report(0.5).await;
+            Ok(())
+        })
+    })
+    .register("failing", |_job, _progress| {
+        Box::pin(async { Err(anyhow!("boom")) })
+    })
+    .start()
+    .await
+    .unwrap();
+
+    let mut jobs = Vec::new();
+    for _ in 0..100 {
+        jobs = db.list_jobs(None, 10, 0).await.unwrap();
+        if jobs
+            .iter()
+            .all(|job| job.status == "done" || job.status == "failed")
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    let ok = jobs.iter().find(|job| job.id == ok.id).unwrap();
+    assert_eq!(ok.status, "done");
+    assert_eq!(ok.progress, 1.0);
+
+    let failing = jobs.iter().find(|job| job.id == failing.id).unwrap();
+    assert_eq!(failing.status, "failed");
+    assert_eq!(failing.attempts, 2);
+    assert_eq!(failing.error.as_deref(), Some("boom"));
+}
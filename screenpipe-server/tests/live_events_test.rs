@@ -0,0 +1,58 @@
+use screenpipe_core::capture_policy::CapturePolicy;
+use screenpipe_events::Event;
+use screenpipe_server::live_events::{BlockedWindowTracker, LiveEventFilter, LIVE_EVENTS};
+use serde_json::json;
+
+fn event(name: &str, data: serde_json::Value) -> Event {
+    Event {
+        name: name.to_string(),
+        data,
+    }
+}
+
+#[test]
+fn test_live_event_filter() {
+    let all = LiveEventFilter::new(None, None).unwrap();
+    for name in LIVE_EVENTS {
+        assert!(all.matches(&event(name, json!({"monitor_id": 1}))));
+    }
+    // internal events aren't streamed
+    assert!(!all.matches(&event("ocr_result", json!({"monitor_id": 1}))));
+    assert_eq!(LiveEventFilter::new(Some(""), None).unwrap(), all);
+
+    let filter = LiveEventFilter::new(Some("frame_written, app_blocked"), Some(2)).unwrap();
+    assert!(filter.matches(&event("app_blocked", json!({"monitor_id": 2}))));
+    assert!(!filter.matches(&event("app_blocked", json!({"monitor_id": 1}))));
+    assert!(!filter.matches(&event("segment_rotated", json!({"monitor_id": 2}))));
+
+    let error = LiveEventFilter::new(Some("frame_written,ocr_result"), None).unwrap_err();
+    assert!(error.contains("ocr_result"));
+}
+
+#[test]
+fn test_app_blocked_once_per_focus() {
+    let policy = CapturePolicy::new(&["1Password".to_string()], &[]);
+    let mut tracker = BlockedWindowTracker::default();
+    let window = |app: &str, title: &str| Some((app.to_string(), title.to_string()));
+
+    assert_eq!(tracker.observe(1, window("Code", "main.rs"), &policy), None);
+    let blocked = tracker
+        .observe(1, window("1Password", "Vault"), &policy)
+        .unwrap();
+    assert_eq!(blocked.monitor_id, 1);
+    // the window isn't told
+    let data = serde_json::to_value(&blocked).unwrap();
+    assert!(!data.to_string().contains("1Password"));
+    // still focused on the next frames
+    assert_eq!(
+        tracker.observe(1, window("1Password", "Vault"), &policy),
+        None
+    );
+
+    assert_eq!(tracker.observe(1, window("Code", "main.rs"), &policy), None);
+    assert!(tracker
+        .observe(1, window("1Password", "Vault"), &policy)
+        .is_some());
+    // focused on another monitor
+    assert_eq!(tracker.observe(1, None, &policy), None);
+}
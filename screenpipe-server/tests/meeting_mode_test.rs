@@ -0,0 +1,131 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::hot_reload::subscribe_capture_fps;
+use screenpipe_server::meeting_mode::{
+    is_meeting_window, MeetingMode, MeetingProfile, MeetingWindowChange, MeetingWindowTracker,
+    MEETING_SESSION_TAG,
+};
+use screenpipe_server::sessions::{SessionSplitConfig, SessionTracker};
+use screenpipe_server::Storage;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_meeting_profile_records_tagged_session() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let root = tempfile::tempdir().unwrap();
+    let tracker = SessionTracker::new(
+        db.clone(),
+        Storage::with_hostname(root.path(), "workstation"),
+        SessionSplitConfig::default(),
+    )
+    .await
+    .unwrap();
+    let meetings = MeetingMode::new(
+        MeetingProfile {
+            fps: Some(2.0),
+            audio: true,
+        },
+        None,
+        Some(tracker.clone()),
+    );
+    let mut fps = subscribe_capture_fps();
+
+    let now = Utc::now();
+    let before = tracker.record_activity(now).await.unwrap();
+    meetings.meeting_started("zoom.us", now).await;
+    assert_eq!(*fps.borrow_and_update(), Some(2.0));
+    // still the same meeting
+    meetings
+        .meeting_started("Google Meet", now + ChronoDuration::seconds(1))
+        .await;
+
+    let meeting = tracker
+        .record_activity(now + ChronoDuration::seconds(30))
+        .await
+        .unwrap();
+    assert_ne!(meeting, before);
+    let session = db.get_recording_session(meeting).await.unwrap();
+    assert_eq!(session.name, "meeting in zoom.us");
+    assert_eq!(session.tag.as_deref(), Some(MEETING_SESSION_TAG));
+
+    meetings.meeting_ended().await;
+    assert_eq!(*fps.borrow_and_update(), None);
+    let session = db.get_recording_session(meeting).await.unwrap();
+    assert_eq!(session.end_reason.as_deref(), Some("meeting_ended"));
+
+    let tagged = db
+        .list_recording_sessions(None, None, Some(MEETING_SESSION_TAG), 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        tagged.iter().map(|session| session.id).collect::<Vec<_>>(),
+        vec![meeting]
+    );
+
+    // the next frame is recorded outside of the meeting
+    let after = tracker
+        .record_activity(now + ChronoDuration::seconds(60))
+        .await
+        .unwrap();
+    assert_ne!(after, meeting);
+}
+
+#[test]
+fn test_meeting_windows() {
+    assert!(is_meeting_window("zoom.us", "Zoom Meeting"));
+    assert!(is_meeting_window(
+        "Microsoft Teams",
+        "Weekly sync | Meeting"
+    ));
+    assert!(is_meeting_window(
+        "Google Chrome",
+        "Meet - abc-defg-hij - Google Chrome"
+    ));
+    assert!(!is_meeting_window(
+        "Google Chrome",
+        "Google Meet - Google Chrome"
+    ));
+    assert!(!is_meeting_window("zoom.us", "Zoom Workplace"));
+    assert!(!is_meeting_window("Code", "meeting_mode.rs"));
+}
+
+#[test]
+fn test_meeting_window_tracker() {
+    let mut tracker = MeetingWindowTracker::default();
+    let now = Utc::now();
+    let at = |secs: i64| now + ChronoDuration::seconds(secs);
+    let shown = |monitors: &[(u32, &str)]| {
+        monitors
+            .iter()
+            .map(|(monitor_id, app)| (*monitor_id, app.to_string()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    assert_eq!(tracker.observe(&shown(&[]), at(0)), None);
+    assert_eq!(
+        tracker.observe(&shown(&[(2, "Google Chrome")]), at(1)),
+        Some(MeetingWindowChange::Started("Google Chrome".to_string()))
+    );
+    // moved to another monitor
+    assert_eq!(
+        tracker.observe(&shown(&[(1, "Google Chrome")]), at(2)),
+        None
+    );
+
+    // hidden for a moment
+    assert_eq!(tracker.observe(&shown(&[]), at(3)), None);
+    assert_eq!(
+        tracker.observe(&shown(&[(1, "Google Chrome")]), at(20)),
+        None
+    );
+
+    // gone
+    assert_eq!(tracker.observe(&shown(&[]), at(25)), None);
+    assert_eq!(tracker.observe(&shown(&[]), at(40)), None);
+    assert_eq!(
+        tracker.observe(&shown(&[]), at(55)),
+        Some(MeetingWindowChange::Ended)
+    );
+    assert_eq!(tracker.observe(&shown(&[]), at(90)), None);
+}
@@ -0,0 +1,74 @@
+use screenpipe_server::metrics::{render_metrics, RecorderStats};
+use screenpipe_vision::capture_stats::CaptureStats;
+use std::collections::HashMap;
+
+#[test]
+fn test_render_metrics() {
+    let capture = HashMap::from([(
+        1,
+        CaptureStats {
+            frames_captured: 120,
+            frames_skipped: 30,
+            capture_seconds: 6.5,
+        },
+    )]);
+    let recorder = HashMap::from([(
+        1,
+        RecorderStats {
+            frames_written: 90,
+            ffmpeg_restarts: 2,
+            video_queue_depth: 3,
+            ocr_queue_depth: 7,
+            video_frames_dropped: 4,
+            ocr_frames_dropped: 0,
+        },
+    )]);
+    let metrics = render_metrics(&capture, &recorder);
+
+    for line in [
+        "# TYPE screenpipe_frames_captured_total counter",
+        "screenpipe_frames_captured_total{monitor=\"1\"} 120",
+        "screenpipe_frames_skipped_total{monitor=\"1\"} 30",
+        "screenpipe_frames_written_total{monitor=\"1\"} 90",
+        "# TYPE screenpipe_capture_duration_seconds summary",
+        "screenpipe_capture_duration_seconds_sum{monitor=\"1\"} 6.5",
+        "screenpipe_capture_duration_seconds_count{monitor=\"1\"} 120",
+        "screenpipe_ffmpeg_restarts_total{monitor=\"1\"} 2",
+        "screenpipe_queue_depth{monitor=\"1\",queue=\"video\"} 3",
+        "screenpipe_queue_depth{monitor=\"1\",queue=\"ocr\"} 7",
+        "# TYPE screenpipe_queue_dropped_frames_total counter",
+        "screenpipe_queue_dropped_frames_total{monitor=\"1\",queue=\"video\"} 4",
+        "screenpipe_queue_dropped_frames_total{monitor=\"1\",queue=\"ocr\"} 0",
+    ] {
+        assert!(metrics.lines().any(|l| l == line), "missing {}", line);
+    }
+}
+
+#[test]
+fn test_render_metrics_of_monitor_without_recorder_stats() {
+    // a monitor whose frames are all skipped never reaches the recorder
+    let capture = HashMap::from([(
+        2,
+        CaptureStats {
+            frames_captured: 10,
+            frames_skipped: 10,
+            capture_seconds: 0.5,
+        },
+    )]);
+    let metrics = render_metrics(&capture, &HashMap::new());
+
+    assert!(metrics
+        .lines()
+        .any(|l| l == "screenpipe_frames_written_total{monitor=\"2\"} 0"));
+    assert!(metrics
+        .lines()
+        .any(|l| l == "screenpipe_queue_depth{monitor=\"2\",queue=\"ocr\"} 0"));
+}
+
+#[test]
+fn test_render_metrics_without_monitors() {
+    let metrics = render_metrics(&HashMap::new(), &HashMap::new());
+
+    assert!(metrics.contains("# TYPE screenpipe_queue_depth gauge"));
+    assert!(!metrics.lines().any(|l| !l.starts_with('#')));
+}
@@ -0,0 +1,41 @@
+use chrono::NaiveDate;
+use image::{DynamicImage, Rgb, RgbImage};
+use screenpipe_server::mosaic::{
+    local_day_bounds, mosaic_path, render_day_strip, MINUTES_PER_DAY, SLICE_WIDTH, STRIP_HEIGHT,
+};
+use screenpipe_server::Storage;
+use std::path::PathBuf;
+
+#[test]
+fn test_render_day_strip_places_slices_by_minute() {
+    let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 32, Rgb([255, 0, 0])));
+    let strip = render_day_strip(&[(0, red.clone()), (90, red), (MINUTES_PER_DAY, blank())]);
+
+    assert_eq!(strip.width(), MINUTES_PER_DAY * SLICE_WIDTH);
+    assert_eq!(strip.height(), STRIP_HEIGHT);
+    assert_eq!(strip.get_pixel(0, 0), &Rgb([255, 0, 0]));
+    assert_eq!(
+        strip.get_pixel(90 * SLICE_WIDTH + 1, STRIP_HEIGHT - 1),
+        &Rgb([255, 0, 0])
+    );
+    assert_ne!(strip.get_pixel(SLICE_WIDTH, 0), &Rgb([255, 0, 0]));
+}
+
+fn blank() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::new(8, 8))
+}
+
+#[test]
+fn test_mosaic_path_and_day_bounds() {
+    let storage = Storage::with_hostname("/data", "laptop");
+    let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+    assert_eq!(
+        mosaic_path(&storage, date),
+        PathBuf::from("/data/laptop/mosaics/2025-03-01.jpg")
+    );
+
+    let (start, end) = local_day_bounds(date);
+    assert!(end > start);
+    assert!((end - start).num_hours() >= 23 && (end - start).num_hours() <= 25);
+}
@@ -0,0 +1,29 @@
+use screenpipe_core::Language;
+use screenpipe_server::ocr_language::{set_ocr_languages, tag_block_languages, BLOCK_LANGUAGE_KEY};
+use std::collections::HashMap;
+
+fn block(text: &str) -> HashMap<String, String> {
+    HashMap::from([("text".to_string(), text.to_string())])
+}
+
+#[test]
+fn test_tag_block_languages() {
+    set_ocr_languages(vec![Language::Japanese, Language::English]);
+    let mut blocks = vec![
+        block("Compare the plans and pick the one that is right for you"),
+        block("ファイル"),
+        block("Settings"),
+        block("東京都渋谷区道玄坂一丁目"),
+        block("12:45"),
+        HashMap::new(),
+    ];
+
+    let counts = tag_block_languages(&mut blocks);
+
+    assert_eq!(counts, vec![("en".to_string(), 2), ("ja".to_string(), 2)]);
+    let languages: Vec<&str> = blocks
+        .iter()
+        .map(|block| block[BLOCK_LANGUAGE_KEY].as_str())
+        .collect();
+    assert_eq!(languages, vec!["en", "ja", "en", "ja", "und", "und"]);
+}
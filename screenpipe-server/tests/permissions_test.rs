@@ -0,0 +1,49 @@
+use screenpipe_server::permissions::{Capability, PermissionCheck, PermissionStatus};
+
+#[test]
+fn test_permission_check() {
+    let denied = PermissionCheck::new(Capability::ScreenRecording, PermissionStatus::Denied);
+    assert_eq!(
+        denied.settings_url.as_deref(),
+        Capability::ScreenRecording.settings_url()
+    );
+    // nothing to send the user to once it's granted
+    let granted = PermissionCheck::new(Capability::Accessibility, PermissionStatus::Granted);
+    assert_eq!(granted.settings_url, None);
+
+    assert_eq!(
+        serde_json::to_value(&denied).unwrap()["capability"],
+        "screen_recording"
+    );
+    assert!(PermissionStatus::NotNeeded.permitted());
+    assert!(!PermissionStatus::Denied.permitted());
+}
+
+#[cfg(target_os = "macos")]
+#[test]
+fn test_settings_url() {
+    assert!(Capability::Accessibility
+        .settings_url()
+        .unwrap()
+        .ends_with("Privacy_Accessibility"));
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_linux_needs_no_permission() {
+    use screenpipe_server::permissions::{check_permissions, wait_for_permission};
+    use std::time::Duration;
+
+    let checks = check_permissions();
+    assert_eq!(checks.len(), Capability::ALL.len());
+    assert!(checks
+        .iter()
+        .all(|check| check.status == PermissionStatus::NotNeeded && check.settings_url.is_none()));
+
+    tokio::time::timeout(
+        Duration::from_secs(1),
+        wait_for_permission(Capability::ScreenRecording, Duration::from_secs(60)),
+    )
+    .await
+    .unwrap();
+}
@@ -0,0 +1,160 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use image::DynamicImage;
+use screenpipe_server::plugins::{
+    ActivityChange, FinalizedSegment, FramePlugin, FrameResponse, FrameVerdict, PluginEvent,
+    PluginFrame, PluginHost,
+};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Redact(&'static str);
+
+impl FramePlugin for Redact {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn on_frame(&self, frame: &mut PluginFrame) -> Result<FrameVerdict> {
+        frame.text = frame.text.replace(self.0, "[redacted]");
+        Ok(FrameVerdict::Keep)
+    }
+}
+
+struct SkipApp(&'static str);
+
+impl FramePlugin for SkipApp {
+    fn name(&self) -> &str {
+        "skip"
+    }
+
+    fn on_frame(&self, frame: &mut PluginFrame) -> Result<FrameVerdict> {
+        Ok(if frame.app_name == self.0 {
+            FrameVerdict::Skip
+        } else {
+            FrameVerdict::Keep
+        })
+    }
+}
+
+struct Broken;
+
+impl FramePlugin for Broken {
+    fn name(&self) -> &str {
+        "broken"
+    }
+
+    fn on_frame(&self, frame: &mut PluginFrame) -> Result<FrameVerdict> {
+        frame.text.clear();
+        Err(anyhow!("out of cheese"))
+    }
+
+    fn on_activity_change(&self, _change: &ActivityChange) -> Result<()> {
+        panic!("plugins can panic too");
+    }
+}
+
+#[derive(Default)]
+struct Counter {
+    activity_changes: AtomicUsize,
+    segments: AtomicUsize,
+}
+
+impl FramePlugin for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn on_activity_change(&self, _change: &ActivityChange) -> Result<()> {
+        self.activity_changes.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn on_segment_finalized(&self, _segment: &FinalizedSegment) -> Result<()> {
+        self.segments.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn frame(app_name: &str, text: &str) -> PluginFrame {
+    PluginFrame {
+        monitor_id: 1,
+        timestamp: Utc::now(),
+        app_name: app_name.to_string(),
+        window_name: "window".to_string(),
+        browser_url: None,
+        focused: true,
+        text: text.to_string(),
+        image: Arc::new(DynamicImage::new_rgb8(4, 4)),
+    }
+}
+
+#[test]
+fn test_plugins_edit_and_skip_frames_in_order() {
+    let mut host = PluginHost::new();
+    host.register(Arc::new(Broken));
+    host.register(Arc::new(Redact("hunter2")));
+    host.register(Arc::new(SkipApp("1Password")));
+    assert_eq!(host.names(), vec!["broken", "redact", "skip"]);
+
+    // the failing plugin's edit is dropped, the redaction after it still applies
+    let mut kept = frame("Terminal", "password: hunter2");
+    assert_eq!(host.on_frame(&mut kept), FrameVerdict::Keep);
+    assert_eq!(kept.text, "password: [redacted]");
+
+    let mut skipped = frame("1Password", "hunter2");
+    assert_eq!(host.on_frame(&mut skipped), FrameVerdict::Skip);
+}
+
+#[test]
+fn test_plugin_panics_dont_stop_other_plugins() {
+    let counter = Arc::new(Counter::default());
+    let mut host = PluginHost::new();
+    host.register(Arc::new(Broken));
+    host.register(counter.clone());
+
+    host.on_activity_change(&ActivityChange {
+        monitor_id: 1,
+        timestamp: Utc::now(),
+        app_name: "Code".to_string(),
+        window_name: "main.rs".to_string(),
+        browser_url: None,
+        previous_app_name: None,
+        previous_window_name: None,
+    });
+    host.on_segment_finalized(&FinalizedSegment {
+        monitor_id: 1,
+        device_name: "monitor_1".to_string(),
+        video_path: "/data/monitor_1_2025-03-01_14-00-00.mp4".to_string(),
+        finalized_at: Utc::now(),
+    });
+
+    assert_eq!(counter.activity_changes.load(Ordering::SeqCst), 1);
+    assert_eq!(counter.segments.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_plugin_protocol_json() {
+    let frame = frame("Terminal", "ls");
+    let event = serde_json::to_value(PluginEvent::Frame(&frame)).unwrap();
+    assert_eq!(event["type"], "frame");
+    assert_eq!(event["app_name"], "Terminal");
+    assert_eq!(event["text"], "ls");
+    // the image is for in-process plugins only
+    assert!(event.get("image").is_none());
+
+    let response: FrameResponse = serde_json::from_str(r#"{"verdict":"skip"}"#).unwrap();
+    assert_eq!(response.verdict, Some(FrameVerdict::Skip));
+    assert!(response.text.is_none());
+}
+
+#[test]
+fn test_load_rejects_unknown_files() {
+    let mut host = PluginHost::new();
+    assert!(host.load(Path::new("plugin.txt")).is_err());
+    assert!(host
+        .load(Path::new("/does/not/exist/libplugin.so"))
+        .is_err());
+    assert!(host.is_empty());
+}
@@ -0,0 +1,63 @@
+use screenpipe_server::power::{parse_pmset_batt, PowerPolicy, PowerStatus, ThrottleMode};
+use std::time::Duration;
+
+fn status(on_battery: bool, battery_percent: f32, max_temperature: f32) -> PowerStatus {
+    PowerStatus {
+        on_battery,
+        battery_percent: Some(battery_percent),
+        max_temperature: Some(max_temperature),
+    }
+}
+
+#[test]
+fn test_policy_decisions() {
+    let policy = PowerPolicy::default();
+    let low_power = ThrottleMode::LowPower { fps_factor: 0.5 };
+
+    assert_eq!(
+        policy.decide(&status(false, 10.0, 50.0)),
+        ThrottleMode::Normal
+    );
+    assert_eq!(policy.decide(&status(true, 80.0, 50.0)), low_power);
+    assert_eq!(
+        policy.decide(&status(true, 10.0, 50.0)),
+        ThrottleMode::Paused
+    );
+    assert_eq!(policy.decide(&status(false, 100.0, 95.0)), low_power);
+    assert_eq!(policy.decide(&PowerStatus::default()), ThrottleMode::Normal);
+
+    let never_pause = PowerPolicy {
+        pause_below_percent: None,
+        thermal_limit: None,
+        ..Default::default()
+    };
+    assert_eq!(never_pause.decide(&status(true, 5.0, 50.0)), low_power);
+    assert_eq!(
+        never_pause.decide(&status(false, 100.0, 120.0)),
+        ThrottleMode::Normal
+    );
+}
+
+#[test]
+fn test_capture_interval() {
+    let interval = Duration::from_millis(500);
+
+    assert_eq!(
+        ThrottleMode::Normal.capture_interval(interval),
+        Some(interval)
+    );
+    assert_eq!(
+        ThrottleMode::LowPower { fps_factor: 0.25 }.capture_interval(interval),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(ThrottleMode::Paused.capture_interval(interval), None);
+}
+
+#[test]
+fn test_parse_pmset_batt() {
+    let output = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t84%; discharging; 5:12 remaining present: true\n";
+    assert_eq!(parse_pmset_batt(output), (true, Some(84.0)));
+
+    let output = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n";
+    assert_eq!(parse_pmset_batt(output), (false, Some(100.0)));
+}
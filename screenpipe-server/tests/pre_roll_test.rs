@@ -0,0 +1,24 @@
+use screenpipe_server::pre_roll::{captured_at, PreRollBuffer};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_pre_roll_keeps_last_frames() {
+    let mut buffer = PreRollBuffer::new(Duration::from_secs(30));
+    let start = Instant::now();
+    for second in 0..60 {
+        buffer.push(start + Duration::from_secs(second), second);
+    }
+
+    assert_eq!(buffer.len(), 31);
+    let frames: Vec<u64> = buffer.drain().collect();
+    assert_eq!(frames.first(), Some(&29));
+    assert_eq!(frames.last(), Some(&59));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_captured_at() {
+    let instant = Instant::now() - Duration::from_secs(20);
+    let age = chrono::Utc::now() - captured_at(instant);
+    assert!((age.num_milliseconds() - 20_000).abs() < 1_000);
+}
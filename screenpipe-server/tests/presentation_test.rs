@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use image::{DynamicImage, Rgb, RgbImage};
+use screenpipe_server::presentation::{
+    chapters_metadata, slides_pdf, video_offset, PdfPage, SlideDetector, SlideDetectorConfig,
+    Thumbnail,
+};
+
+/// A plain slide, optionally with a white block of (width, height) at (x, y).
+fn slide(color: [u8; 3], block: Option<(u32, u32, (u32, u32))>) -> Thumbnail {
+    let mut image = RgbImage::from_pixel(640, 360, Rgb(color));
+    if let Some((x, y, (width, height))) = block {
+        for dx in 0..width {
+            for dy in 0..height {
+                image.put_pixel(x + dx, y + dy, Rgb([255, 255, 255]));
+            }
+        }
+    }
+    Thumbnail::new(&DynamicImage::ImageRgb8(image))
+}
+
+fn at(seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap() + Duration::seconds(seconds)
+}
+
+#[test]
+fn test_thumbnail_difference() {
+    let dark = slide([10, 10, 10], None);
+    assert_eq!(dark.difference(&dark), 0.0);
+    assert_eq!(dark.difference(&slide([250, 250, 250], None)), 1.0);
+
+    // a pointer moving over the slide is a small change
+    let pointer = slide([10, 10, 10], Some((100, 100, (20, 20))));
+    assert!(dark.difference(&pointer) < 0.01);
+}
+
+#[test]
+fn test_slide_detector_takes_slides_that_stay_on_screen() {
+    let mut detector = SlideDetector::new(SlideDetectorConfig::default());
+
+    assert_eq!(
+        detector.observe(slide([10, 10, 10], None), "Keynote", at(0)),
+        None
+    );
+    // the pointer moves over the first slide
+    assert_eq!(
+        detector.observe(
+            slide([10, 10, 10], Some((50, 50, (20, 20)))),
+            "Keynote",
+            at(5)
+        ),
+        None
+    );
+    assert_eq!(
+        detector.observe(slide([120, 120, 120], None), "Keynote", at(30)),
+        Some(at(0))
+    );
+    // the transition frame, replaced right away, is not a slide
+    assert_eq!(
+        detector.observe(slide([200, 30, 30], None), "Keynote", at(30)),
+        None
+    );
+    assert_eq!(detector.app_name(), Some("Keynote"));
+
+    assert_eq!(
+        detector.observe(slide([30, 200, 30], None), "Terminal", at(90)),
+        Some(at(30))
+    );
+    // a demo in another app is not a slide
+    assert_eq!(
+        detector.observe(slide([30, 30, 200], None), "Keynote", at(150)),
+        None
+    );
+    assert_eq!(detector.finish(at(200)), Some(at(150)));
+    assert_eq!(detector.finish(at(210)), None);
+}
+
+#[test]
+fn test_slide_detector_ignores_small_changes_from_the_last_slide() {
+    let mut detector = SlideDetector::new(SlideDetectorConfig::default());
+
+    detector.observe(slide([10, 10, 10], None), "Keynote", at(0));
+    // a bullet point appearing keeps the same slide
+    assert_eq!(
+        detector.observe(
+            slide([10, 10, 10], Some((0, 0, (200, 100)))),
+            "Keynote",
+            at(10)
+        ),
+        Some(at(0))
+    );
+    assert_eq!(
+        detector.observe(slide([220, 220, 220], None), "Keynote", at(20)),
+        None
+    );
+    assert_eq!(detector.finish(at(40)), Some(at(20)));
+}
+
+#[test]
+fn test_slides_pdf_has_a_page_per_slide() {
+    let pages = vec![
+        PdfPage {
+            jpeg: vec![0xFF, 0xD8, 0xFF, 0xD9],
+            width: 1920,
+            height: 1080,
+        },
+        PdfPage {
+            jpeg: vec![0xFF, 0xD8, 0xFF, 0xD9],
+            width: 1080,
+            height: 1920,
+        },
+    ];
+    let pdf = slides_pdf(&pages);
+    let text = String::from_utf8_lossy(&pdf);
+
+    assert!(text.starts_with("%PDF-1.4"));
+    assert!(text.trim_end().ends_with("%%EOF"));
+    assert!(text.contains("/Count 2"));
+    assert_eq!(text.matches("/Type /Page ").count(), 2);
+    assert!(text.contains("/MediaBox [0 0 960.00 540.00]"));
+    assert!(text.contains("/MediaBox [0 0 960.00 1706.67]"));
+    assert_eq!(text.matches("/Filter /DCTDecode").count(), 2);
+
+    // the xref offsets point at the objects
+    let xref = text.rfind("startxref\n").unwrap();
+    let start: usize = text[xref + 10..].lines().next().unwrap().parse().unwrap();
+    let table = std::str::from_utf8(&pdf[start..]).unwrap();
+    assert!(table.starts_with("xref\n0 9\n"));
+    let first_offset: usize = table.lines().nth(3).unwrap()[..10].parse().unwrap();
+    assert!(pdf[first_offset..].starts_with(b"1 0 obj"));
+}
+
+#[test]
+fn test_video_offset_across_chunks() {
+    let chunks = [(at(0), 60.0), (at(65), 60.0)];
+
+    assert_eq!(video_offset(&chunks, at(30)), 30.0);
+    // between the chunks, the next one starts
+    assert_eq!(video_offset(&chunks, at(62)), 60.0);
+    assert_eq!(video_offset(&chunks, at(75)), 70.0);
+    assert_eq!(video_offset(&chunks, at(500)), 120.0);
+}
+
+#[test]
+fn test_chapters_metadata() {
+    let metadata = chapters_metadata("Q3 review; draft", &[0.0, 12.5, 40.0], 60.0);
+
+    assert!(metadata.starts_with(";FFMETADATA1\ntitle=Q3 review\\; draft\n"));
+    assert_eq!(metadata.matches("[CHAPTER]").count(), 3);
+    assert!(metadata.contains("START=12500\nEND=40000\ntitle=Slide 2\n"));
+    assert!(metadata.contains("START=40000\nEND=60000\ntitle=Slide 3\n"));
+}
@@ -0,0 +1,69 @@
+use screenpipe_server::pyramid::{
+    keyframe_offsets_from_times, pyramid_dir, pyramid_ffmpeg_args, pyramid_frame_path,
+    pyramid_keyframe_path,
+};
+use std::path::PathBuf;
+
+#[test]
+fn test_pyramid_paths_sit_next_to_the_chunk() {
+    let chunk = "/data/host/2025-03-01/monitor_1/monitor_1_2025-03-01_10-00-00.mp4";
+
+    assert_eq!(
+        pyramid_dir(chunk),
+        PathBuf::from("/data/host/2025-03-01/monitor_1/monitor_1_2025-03-01_10-00-00_pyramid")
+    );
+    assert_eq!(
+        pyramid_frame_path(chunk, 2, 42),
+        PathBuf::from(
+            "/data/host/2025-03-01/monitor_1/monitor_1_2025-03-01_10-00-00_pyramid/2/000042.jpg"
+        )
+    );
+}
+
+#[test]
+fn test_pyramid_ffmpeg_args_write_every_level() {
+    let args = pyramid_ffmpeg_args("/data/chunk.mp4");
+
+    // level 0 is the video itself
+    assert_eq!(args[0], "-filter_complex");
+    assert_eq!(
+        args[1],
+        "[0:v]split=2[in1][in2];[in1]scale=iw/4:-1[out1];[in2]scale=iw/16:-1[out2]"
+    );
+    assert_eq!(args.iter().filter(|arg| arg.as_str() == "-map").count(), 2);
+    assert_eq!(args.last().unwrap(), "/data/chunk_pyramid/2/%06d.jpg");
+}
+
+#[test]
+fn test_keyframe_offsets_from_times() {
+    assert_eq!(
+        keyframe_offsets_from_times("0.000000\n8.000000\n\n16.666667,\n", 1.5),
+        vec![0, 12, 25]
+    );
+}
+
+#[test]
+fn test_pyramid_keyframe_path_takes_the_previous_keyframe() {
+    let dir = tempfile::tempdir().unwrap();
+    let chunk = dir
+        .path()
+        .join("monitor_1.mp4")
+        .to_string_lossy()
+        .into_owned();
+    assert_eq!(pyramid_keyframe_path(&chunk, 1, 3), None);
+
+    for offset in [0, 12] {
+        let path = pyramid_frame_path(&chunk, 1, offset);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"jpg").unwrap();
+    }
+    assert_eq!(
+        pyramid_keyframe_path(&chunk, 1, 12),
+        Some(pyramid_frame_path(&chunk, 1, 12))
+    );
+    assert_eq!(
+        pyramid_keyframe_path(&chunk, 1, 11),
+        Some(pyramid_frame_path(&chunk, 1, 0))
+    );
+    assert_eq!(pyramid_keyframe_path(&chunk, 2, 11), None);
+}
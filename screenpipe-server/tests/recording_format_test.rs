@@ -0,0 +1,37 @@
+use chrono::{TimeZone, Utc};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use screenpipe_server::recording_format::{
+    recording_format, set_recording_format, write_frame_image, RecordingFormat,
+};
+use screenpipe_server::Storage;
+use tempfile::tempdir;
+
+#[test]
+fn test_recording_format_setting() {
+    assert_eq!(recording_format(), RecordingFormat::Video);
+    assert_eq!(RecordingFormat::Video.image_format(), None);
+
+    set_recording_format(RecordingFormat::Webp);
+    assert_eq!(recording_format(), RecordingFormat::Webp);
+    assert_eq!(recording_format().image_format(), Some(ImageFormat::WebP));
+
+    set_recording_format(RecordingFormat::Video);
+}
+
+#[test]
+fn test_write_frame_image_is_lossless() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let time = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 5).unwrap();
+    let frame = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 48, |x, y| {
+        Rgba([(x * 4) as u8, (y * 5) as u8, 120, 255])
+    }));
+
+    for (format, extension) in [(ImageFormat::WebP, "webp"), (ImageFormat::Png, "png")] {
+        let path = write_frame_image(&storage, 1, time, &frame, format).unwrap();
+        assert_eq!(path.extension().unwrap(), extension);
+
+        let written = image::open(&path).unwrap();
+        assert_eq!(written.to_rgba8(), frame.to_rgba8());
+    }
+}
@@ -0,0 +1,77 @@
+use screenpipe_server::power::{under_resource_pressure, ThrottleMode};
+use screenpipe_server::resource_guard::{ResourceLimits, ResourcePressure, ResourceStatus};
+
+const MB: u64 = 1024 * 1024;
+
+fn status(free_disk_mb: u64, memory_mb: u64) -> ResourceStatus {
+    ResourceStatus {
+        free_disk_bytes: Some(free_disk_mb * MB),
+        memory_bytes: Some(memory_mb * MB),
+    }
+}
+
+#[test]
+fn test_resource_pressure() {
+    let limits = ResourceLimits {
+        min_free_disk_bytes: Some(1000 * MB),
+        max_memory_bytes: Some(2000 * MB),
+        ..Default::default()
+    };
+
+    assert!(limits.pressure(&status(5000, 500), false).is_empty());
+    assert_eq!(
+        limits.pressure(&status(900, 500), false),
+        vec![ResourcePressure::LowDisk]
+    );
+    assert_eq!(
+        limits.pressure(&status(900, 2500), false),
+        vec![ResourcePressure::LowDisk, ResourcePressure::HighMemory]
+    );
+    // unknown figures and disabled limits never degrade
+    assert!(limits
+        .pressure(&ResourceStatus::default(), false)
+        .is_empty());
+    let no_limits = ResourceLimits {
+        min_free_disk_bytes: None,
+        max_memory_bytes: None,
+        ..Default::default()
+    };
+    assert!(no_limits.pressure(&status(0, 100_000), false).is_empty());
+}
+
+#[test]
+fn test_resource_pressure_recovers_with_a_margin() {
+    let limits = ResourceLimits {
+        min_free_disk_bytes: Some(1000 * MB),
+        max_memory_bytes: Some(2000 * MB),
+        ..Default::default()
+    };
+
+    // just over the limits isn't enough to leave the degraded profile
+    assert_eq!(
+        limits.pressure(&status(1050, 1900), true),
+        vec![ResourcePressure::LowDisk, ResourcePressure::HighMemory]
+    );
+    assert!(limits.pressure(&status(1050, 1900), false).is_empty());
+    assert!(limits.pressure(&status(1200, 1500), true).is_empty());
+}
+
+#[test]
+fn test_under_resource_pressure() {
+    assert_eq!(
+        under_resource_pressure(ThrottleMode::Normal, Some(0.5)),
+        ThrottleMode::LowPower { fps_factor: 0.5 }
+    );
+    assert_eq!(
+        under_resource_pressure(ThrottleMode::LowPower { fps_factor: 0.3 }, Some(0.5)),
+        ThrottleMode::LowPower { fps_factor: 0.3 }
+    );
+    assert_eq!(
+        under_resource_pressure(ThrottleMode::Paused, Some(0.5)),
+        ThrottleMode::Paused
+    );
+    assert_eq!(
+        under_resource_pressure(ThrottleMode::Normal, None),
+        ThrottleMode::Normal
+    );
+}
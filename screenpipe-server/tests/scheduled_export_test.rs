@@ -0,0 +1,95 @@
+use chrono::{Datelike, Local, TimeZone, Utc, Weekday};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::scheduled_export::{
+    run_scheduled_export, ExportConfig, ExportContent, ExportManifest, ScheduledExportPayload,
+    BOOKMARKS_FILE, MANIFEST_FILE,
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_parse_export_config() {
+    let config = ExportConfig::parse(
+        r#"{"exports": [{"name": "weekly", "schedule": "0 0 17 * * Fri", "destination": "/team/screenpipe"}]}"#,
+    )
+    .unwrap();
+    let export = &config.exports[0];
+    assert_eq!(export.lookback_days, 7);
+    assert_eq!(
+        export.contents,
+        vec![ExportContent::Bookmarks, ExportContent::Report]
+    );
+
+    assert!(ExportConfig::parse(
+        r#"{"exports": [{"name": "weekly", "schedule": "every friday", "destination": "/team"}]}"#
+    )
+    .is_err());
+    assert!(ExportConfig::parse(
+        r#"{"exports": [{"name": " ", "schedule": "0 0 17 * * Fri", "destination": "/team"}]}"#
+    )
+    .is_err());
+    assert!(ExportConfig::parse(r#"{}"#).unwrap().exports.is_empty());
+}
+
+#[test]
+fn test_next_run_and_export_dir() {
+    let config = ExportConfig::parse(
+        r#"{"exports": [{"name": "team/weekly", "schedule": "0 0 17 * * Fri", "destination": "/team"}]}"#,
+    )
+    .unwrap();
+    let export = &config.exports[0];
+
+    // a wednesday
+    let after = Local.with_ymd_and_hms(2025, 3, 5, 9, 0, 0).unwrap();
+    let next = export.next_run(after).unwrap();
+    assert_eq!(next, Local.with_ymd_and_hms(2025, 3, 7, 17, 0, 0).unwrap());
+    assert_eq!(next.weekday(), Weekday::Fri);
+
+    let dir = export.export_dir(next.with_timezone(&Utc));
+    assert_eq!(dir, std::path::Path::new("/team/team_weekly/2025-03-07"));
+}
+
+#[tokio::test]
+async fn test_run_scheduled_export_writes_bookmarks_and_manifest() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let timestamp = Utc.with_ymd_and_hms(2025, 3, 5, 10, 0, 0).unwrap();
+    db.insert_bookmark("standup", timestamp, None, None, Some("decided on q2"))
+        .await
+        .unwrap();
+    db.insert_bookmark(
+        "last month",
+        timestamp - chrono::Duration::days(30),
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let destination = tempdir().unwrap();
+    let mut config = ExportConfig::parse(&format!(
+        r#"{{"exports": [{{"name": "weekly", "schedule": "0 0 17 * * Fri", "destination": {:?}, "contents": ["bookmarks"]}}]}}"#,
+        destination.path()
+    ))
+    .unwrap();
+    let payload = ScheduledExportPayload {
+        export: config.exports.remove(0),
+        start_time: timestamp - chrono::Duration::days(2),
+        end_time: timestamp + chrono::Duration::days(2),
+    };
+
+    let manifest = run_scheduled_export(&db, &payload, None).await.unwrap();
+    assert_eq!(manifest.files, vec![BOOKMARKS_FILE.to_string()]);
+    assert!(manifest.errors.is_empty());
+
+    let dir = payload.export.export_dir(payload.end_time);
+    let bookmarks: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join(BOOKMARKS_FILE)).unwrap()).unwrap();
+    let bookmarks = bookmarks.as_array().unwrap();
+    assert_eq!(bookmarks.len(), 1);
+    assert_eq!(bookmarks[0]["name"], "standup");
+
+    let written: ExportManifest =
+        serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+    assert_eq!(written.name, "weekly");
+    assert_eq!(written.files, manifest.files);
+}
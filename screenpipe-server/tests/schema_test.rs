@@ -0,0 +1,54 @@
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::schema::{archive_schema, ARCHIVE_FORMAT_VERSION};
+
+#[tokio::test]
+async fn test_archive_schema() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let schema = archive_schema(&db).await.unwrap();
+    assert_eq!(schema.format_version, ARCHIVE_FORMAT_VERSION);
+
+    let paths: Vec<&str> = schema
+        .layout
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect();
+    assert!(paths.contains(&"db.sqlite"));
+    assert!(paths.contains(
+        &"data/{hostname}/{date}/monitor_{monitor_id}/monitor_{monitor_id}_{date}_{time}.mp4"
+    ));
+    assert!(paths.contains(
+        &"data/{hostname}/{date}/monitor_{monitor_id}/frames/{hour}/monitor_{monitor_id}_{date}_{time}-{millis}.{extension}"
+    ));
+    assert!(paths.contains(
+        &"data/{hostname}/{date}/monitor_{monitor_id}/monitor_{monitor_id}_{date}_{time}_pyramid/{level}/{offset_index}.jpg"
+    ));
+    assert!(paths.contains(&"data/{hostname}/sessions/{date}_{session_id}.json"));
+    // no value of the sample survives templating
+    assert!(paths.iter().all(|path| !path.contains("2001")));
+
+    // every placeholder used is described
+    for path in &paths {
+        for placeholder in path.split('{').skip(1) {
+            let name = format!("{{{}}}", placeholder.split('}').next().unwrap());
+            assert!(
+                schema.placeholders.iter().any(|p| p.name == name),
+                "{} is not described",
+                name
+            );
+        }
+    }
+
+    assert!(schema
+        .database
+        .objects
+        .iter()
+        .any(|object| object.name == "video_chunks"));
+
+    let session_manifest = schema
+        .manifests
+        .iter()
+        .find(|manifest| manifest.path.contains("sessions"))
+        .unwrap();
+    assert!(session_manifest.schema["properties"]["video_chunks"].is_object());
+    assert!(session_manifest.schema["properties"]["started_at"].is_object());
+}
@@ -0,0 +1,101 @@
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use screenpipe_db::ContentType;
+use screenpipe_server::search_query::{parse_search_query, search_filters_or_text, SearchFilters};
+
+fn local_midnight(year: i32, month: u32, day: u32) -> chrono::DateTime<Utc> {
+    let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn test_parse_search_query() {
+    let filters = parse_search_query(
+        r#"app:chrome title:"jira board" after:2024-05-01 before:2024-05-02 text:"retry policy""#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        filters,
+        SearchFilters {
+            text: "\"retry policy\"".to_string(),
+            app_name: Some("chrome".to_string()),
+            window_name: Some("jira board".to_string()),
+            start_time: Some(local_midnight(2024, 5, 1)),
+            end_time: Some(local_midnight(2024, 5, 2)),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_parse_search_query_keeps_free_text() {
+    let filters =
+        parse_search_query(r#"deploy "rollback plan" https://example.com 12:45"#).unwrap();
+
+    assert_eq!(
+        filters.text,
+        r#"deploy "rollback plan" https://example.com 12:45"#
+    );
+    assert_eq!(filters.app_name, None);
+}
+
+#[test]
+fn test_parse_search_query_other_filters() {
+    let filters =
+        parse_search_query("TYPE:audio+ui lang:JA is:focused url:github.com standup").unwrap();
+
+    assert_eq!(filters.content_type, Some(ContentType::AudioAndUi));
+    assert_eq!(filters.language.as_deref(), Some("ja"));
+    assert_eq!(filters.focused, Some(true));
+    assert_eq!(filters.browser_url.as_deref(), Some("github.com"));
+    assert_eq!(filters.text, "standup");
+
+    // the last of a repeated filter wins
+    let filters = parse_search_query("app:slack app:zoom").unwrap();
+    assert_eq!(filters.app_name.as_deref(), Some("zoom"));
+
+    let filters = parse_search_query(r#"category:"client x" invoice"#).unwrap();
+    assert_eq!(filters.category.as_deref(), Some("client x"));
+    assert_eq!(filters.text, "invoice");
+}
+
+#[test]
+fn test_parse_search_query_errors() {
+    for query in [
+        r#"title:"unterminated"#,
+        "app:",
+        "type:video",
+        "after:yesterday",
+        "is:pinned",
+        "after:2024-05-02 before:2024-05-01",
+    ] {
+        assert!(parse_search_query(query).is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn test_search_filters_or_text() {
+    assert_eq!(
+        search_filters_or_text("app:zoom standup")
+            .app_name
+            .as_deref(),
+        Some("zoom")
+    );
+    assert_eq!(
+        search_filters_or_text(r#"title:"unterminated"#),
+        SearchFilters {
+            text: "title:unterminated".to_string(),
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        search_filters_or_text(r#""retry policy" is:pinned"#),
+        SearchFilters {
+            text: r#""retry policy" is:pinned"#.to_string(),
+            ..Default::default()
+        }
+    );
+}
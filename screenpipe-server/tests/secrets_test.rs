@@ -0,0 +1,69 @@
+use clap::Parser;
+use screenpipe_server::secrets::{
+    apply_stored_secrets, resolve_secret, validate_secret_name, MemoryStore, SecretStore,
+    LLM_API_KEY,
+};
+use screenpipe_server::upload::{SyncConfig, SyncTarget};
+use screenpipe_server::Cli;
+
+#[test]
+fn test_resolve_secret() {
+    let store = MemoryStore::default();
+    store.set("s3-secret-access-key", "wJalrXUtnFEMI").unwrap();
+
+    assert_eq!(
+        resolve_secret("keychain:s3-secret-access-key", &store).unwrap(),
+        "wJalrXUtnFEMI"
+    );
+    // values that aren't references are used as they are
+    assert_eq!(resolve_secret("plain", &store).unwrap(), "plain");
+    assert!(resolve_secret("keychain:missing", &store).is_err());
+    assert!(resolve_secret("keychain:", &store).is_err());
+}
+
+#[test]
+fn test_validate_secret_name() {
+    assert!(validate_secret_name("llm-api-key").is_ok());
+    assert!(validate_secret_name("webdav.password_2").is_ok());
+    assert!(validate_secret_name("").is_err());
+    assert!(validate_secret_name("LLM key").is_err());
+}
+
+#[test]
+fn test_apply_stored_secrets() {
+    let store = MemoryStore::default();
+    store.set(LLM_API_KEY, "from-keychain").unwrap();
+    store.set("deepgram-api-key", "from-keychain").unwrap();
+
+    let mut cli = Cli::try_parse_from(["screenpipe", "--deepgram-api-key", "from-flag"]).unwrap();
+    apply_stored_secrets(&mut cli, &store);
+
+    assert_eq!(cli.llm_api_key.as_deref(), Some("from-keychain"));
+    // flags win over the keychain
+    assert_eq!(cli.deepgram_api_key.as_deref(), Some("from-flag"));
+    assert_eq!(cli.embedding_api_key, None);
+}
+
+#[test]
+fn test_sync_config_resolve_secrets() {
+    let store = MemoryStore::default();
+    store.set("r2-secret", "s3cr3t").unwrap();
+    let mut config = SyncConfig::parse(
+        r#"{"target": {"type": "s3", "endpoint": "https://r2.example.com", "bucket": "team", "access_key_id": "id", "secret_access_key": "keychain:r2-secret"}}"#,
+    )
+    .unwrap();
+
+    config.resolve_secrets(&store).unwrap();
+
+    match config.target {
+        SyncTarget::S3 {
+            access_key_id,
+            secret_access_key,
+            ..
+        } => {
+            assert_eq!(access_key_id, "id");
+            assert_eq!(secret_access_key, "s3cr3t");
+        }
+        target => panic!("unexpected target {:?}", target),
+    }
+}
@@ -0,0 +1,55 @@
+use screenpipe_server::service::{
+    launchd_plist, systemd_unit, windows_task_command, ServiceSpec, LAUNCHD_LABEL,
+};
+use std::path::PathBuf;
+
+fn spec(exe: &str, args: &[&str]) -> ServiceSpec {
+    ServiceSpec {
+        exe: PathBuf::from(exe),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        log_path: PathBuf::from("/home/me/.screenpipe/screenpipe-service.log"),
+        path_env: Some("/usr/local/bin:/usr/bin".to_string()),
+    }
+}
+
+#[test]
+fn test_systemd_unit_quotes_arguments() {
+    let unit = systemd_unit(&spec(
+        "/usr/local/bin/screenpipe",
+        &["--fps", "0.5", "--ignored-windows", "100% private $HOME"],
+    ));
+    assert!(unit.contains(
+        r#"ExecStart="/usr/local/bin/screenpipe" "--fps" "0.5" "--ignored-windows" "100%% private $$HOME""#
+    ));
+    assert!(unit.contains(r#"Environment="PATH=/usr/local/bin:/usr/bin""#));
+    // started with the desktop session, whose display it records
+    assert!(unit.contains("PartOf=graphical-session.target"));
+    assert!(unit.contains("WantedBy=graphical-session.target"));
+}
+
+#[test]
+fn test_launchd_plist_escapes_arguments() {
+    let plist = launchd_plist(&spec(
+        "/Applications/screenpipe.app/Contents/MacOS/screenpipe",
+        &["--ignored-windows", "<secret> & co"],
+    ));
+    assert!(plist.contains(&format!("<string>{}</string>", LAUNCHD_LABEL)));
+    assert!(
+        plist.contains("<string>/Applications/screenpipe.app/Contents/MacOS/screenpipe</string>")
+    );
+    assert!(plist.contains("<string>&lt;secret&gt; &amp; co</string>"));
+    assert!(plist.contains("<key>RunAtLoad</key>"));
+    assert!(plist.contains("<string>/home/me/.screenpipe/screenpipe-service.log</string>"));
+}
+
+#[test]
+fn test_windows_task_command_quotes_arguments() {
+    let command = windows_task_command(&spec(
+        r"C:\Program Files\screenpipe\screenpipe.exe",
+        &["--fps", "0.5", r#"say "hi""#, r"C:\data dir\"],
+    ));
+    assert_eq!(
+        command,
+        r#""C:\Program Files\screenpipe\screenpipe.exe" --fps 0.5 "say \"hi\"" "C:\data dir\\""#
+    );
+}
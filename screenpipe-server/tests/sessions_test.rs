@@ -0,0 +1,145 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local, TimeZone, Utc};
+use screenpipe_db::{AppActivity, DatabaseManager};
+use screenpipe_server::sessions::{
+    app_breakdown, session_details, split_reason, SessionSplitConfig, SessionTracker, SplitReason,
+};
+use screenpipe_server::Storage;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn test_split_on_idle_gap() {
+    let config = SessionSplitConfig {
+        idle_gap: Some(Duration::from_secs(600)),
+        split_on_day_boundary: false,
+    };
+    let started = Utc::now();
+    let last = started + ChronoDuration::minutes(5);
+
+    assert_eq!(
+        split_reason(&config, started, last, last + ChronoDuration::minutes(9)),
+        None
+    );
+    assert_eq!(
+        split_reason(&config, started, last, last + ChronoDuration::minutes(10)),
+        Some(SplitReason::Idle)
+    );
+}
+
+#[test]
+fn test_split_on_day_boundary() {
+    let config = SessionSplitConfig {
+        idle_gap: None,
+        split_on_day_boundary: true,
+    };
+    let evening = Local
+        .with_ymd_and_hms(2025, 3, 1, 23, 58, 0)
+        .unwrap()
+        .with_timezone(&Utc);
+    let after_midnight = evening + ChronoDuration::minutes(4);
+
+    assert_eq!(
+        split_reason(
+            &config,
+            evening,
+            evening,
+            evening + ChronoDuration::minutes(1)
+        ),
+        None
+    );
+    assert_eq!(
+        split_reason(&config, evening, evening, after_midnight),
+        Some(SplitReason::DayBoundary)
+    );
+
+    let disabled = SessionSplitConfig {
+        idle_gap: None,
+        split_on_day_boundary: false,
+    };
+    assert_eq!(
+        split_reason(&disabled, evening, evening, after_midnight),
+        None
+    );
+}
+
+fn activity(at: DateTime<Utc>, app_name: &str) -> AppActivity {
+    AppActivity {
+        timestamp: at,
+        app_name: app_name.to_string(),
+        window_name: String::new(),
+        category: None,
+    }
+}
+
+#[test]
+fn test_app_breakdown() {
+    let start = Utc::now();
+    let frames = [
+        activity(start, "code"),
+        activity(start + ChronoDuration::seconds(30), "firefox"),
+        activity(start + ChronoDuration::seconds(40), "code"),
+        // away from the computer for an hour
+        activity(start + ChronoDuration::seconds(100), "slack"),
+    ];
+    let apps = app_breakdown(&frames, start + ChronoDuration::minutes(61));
+
+    let summary: Vec<_> = apps
+        .iter()
+        .map(|app| (app.app_name.as_str(), app.seconds, app.frame_count))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("slack", 120, 1), ("code", 90, 2), ("firefox", 10, 1)]
+    );
+    assert!(app_breakdown(&[], start).is_empty());
+}
+
+#[tokio::test]
+async fn test_start_and_stop_sessions() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let root = tempfile::tempdir().unwrap();
+    let tracker = SessionTracker::new(
+        db.clone(),
+        Storage::with_hostname(root.path(), "workstation"),
+        SessionSplitConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let now = Utc::now();
+    let automatic = tracker.record_activity(now).await.unwrap();
+    let started = tracker
+        .start_session(Some("design review"), Some("with the mobile team"), now)
+        .await
+        .unwrap();
+    assert_ne!(started.id, automatic);
+    assert_eq!(started.name, "design review");
+    assert_eq!(started.notes.as_deref(), Some("with the mobile team"));
+    assert_eq!(
+        db.get_recording_session(automatic)
+            .await
+            .unwrap()
+            .end_reason
+            .as_deref(),
+        Some("manual")
+    );
+    assert_eq!(
+        tracker
+            .record_activity(now + ChronoDuration::seconds(5))
+            .await
+            .unwrap(),
+        started.id
+    );
+
+    let stopped = tracker.stop_session().await.unwrap();
+    assert_eq!(stopped.id, started.id);
+    assert_eq!(stopped.end_reason.as_deref(), Some("stopped"));
+    assert!(tracker.stop_session().await.is_none());
+
+    let details = session_details(&db, started.id, Utc::now()).await.unwrap();
+    assert_eq!(details.duration_secs, 5);
+    assert_eq!(
+        details.session.notes.as_deref(),
+        Some("with the mobile team")
+    );
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, TimeZone, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::storage::{
+    pending_frames, read_journal, EncodedFrame, FrameJournal, JournalRecord, JournalWindow,
+    SessionLockInfo,
+};
+use screenpipe_server::Storage;
+use tempfile::tempdir;
+
+fn encoded(frame_number: u64, chunk_path: &str) -> EncodedFrame {
+    EncodedFrame {
+        frame_number,
+        chunk_path: chunk_path.to_string(),
+        offset_index: frame_number as i64 - 1,
+        device_name: "monitor_1".to_string(),
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap()
+            + chrono::Duration::seconds(frame_number as i64),
+        windows: vec![JournalWindow {
+            app_name: "Code".to_string(),
+            window_name: "storage.rs".to_string(),
+            browser_url: None,
+            focused: true,
+        }],
+    }
+}
+
+#[test]
+fn test_video_chunk_path_layout() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let time = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 5).unwrap();
+
+    let path = storage.video_chunk_path(2, time).unwrap();
+
+    assert_eq!(
+        path,
+        root.path()
+            .join("workstation")
+            .join("2025-03-01")
+            .join("monitor_2")
+            .join("monitor_2_2025-03-01_09-30-05.mp4")
+    );
+    assert!(path.parent().unwrap().is_dir());
+}
+
+#[test]
+fn test_imported_video_path_layout() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let time = Utc.with_ymd_and_hms(2024, 11, 20, 14, 0, 0).unwrap();
+
+    let path = storage.imported_video_path(time).unwrap();
+
+    assert_eq!(
+        path,
+        root.path()
+            .join("workstation")
+            .join("2024-11-20")
+            .join("imported")
+            .join("imported_2024-11-20_14-00-00.mp4")
+    );
+    assert!(path.parent().unwrap().is_dir());
+}
+
+#[test]
+fn test_frame_image_path_layout() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let time =
+        Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 5).unwrap() + chrono::Duration::milliseconds(250);
+
+    let path = storage.frame_image_path(2, time, "webp").unwrap();
+
+    assert_eq!(
+        path,
+        root.path()
+            .join("workstation")
+            .join("2025-03-01")
+            .join("monitor_2")
+            .join("frames")
+            .join("09")
+            .join("monitor_2_2025-03-01_09-30-05-250.webp")
+    );
+    assert!(path.parent().unwrap().is_dir());
+}
+
+#[test]
+fn test_lock_is_released_on_drop() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+
+    let lock = storage.lock().unwrap();
+    assert_eq!(lock.info.pid, std::process::id());
+    assert!(storage.lock_path().exists());
+
+    drop(lock);
+    assert!(!storage.lock_path().exists());
+}
+
+#[test]
+fn test_stale_lock_is_taken_over() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    std::fs::create_dir_all(storage.host_dir()).unwrap();
+
+    let stale = SessionLockInfo {
+        pid: u32::MAX - 1,
+        session_id: "crashed".to_string(),
+        started_at: Utc::now(),
+    };
+    std::fs::write(storage.lock_path(), serde_json::to_string(&stale).unwrap()).unwrap();
+
+    let lock = storage.lock().unwrap();
+    assert_ne!(lock.info.session_id, "crashed");
+}
+
+#[test]
+fn test_lock_of_reused_pid_is_taken_over() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    std::fs::create_dir_all(storage.host_dir()).unwrap();
+
+    // pid 1 is alive, but started long after this lock was taken
+    let stale = SessionLockInfo {
+        pid: 1,
+        session_id: "crashed".to_string(),
+        started_at: DateTime::<Utc>::UNIX_EPOCH,
+    };
+    std::fs::write(storage.lock_path(), serde_json::to_string(&stale).unwrap()).unwrap();
+    assert_ne!(storage.lock().unwrap().info.session_id, "crashed");
+
+    // left empty by a crash
+    std::fs::write(storage.lock_path(), b"").unwrap();
+    let lock = storage.lock().unwrap();
+    assert_eq!(
+        std::fs::read_dir(storage.host_dir()).unwrap().count(),
+        1,
+        "only the lock is left in the tree"
+    );
+    drop(lock);
+}
+
+#[test]
+fn test_pending_frames() {
+    let records = vec![
+        JournalRecord::Encoded(encoded(1, "a.mp4")),
+        JournalRecord::Encoded(encoded(2, "a.mp4")),
+        JournalRecord::Indexed { frame_number: 1 },
+        // indexed before the encoder got to it
+        JournalRecord::Indexed { frame_number: 3 },
+        JournalRecord::Encoded(encoded(3, "a.mp4")),
+        // the capture restarted and numbers its frames from 1 again
+        JournalRecord::Encoded(encoded(1, "b.mp4")),
+    ];
+
+    let pending = pending_frames(records);
+
+    assert_eq!(pending, vec![encoded(2, "a.mp4"), encoded(1, "b.mp4")]);
+}
+
+#[test]
+fn test_frame_journal_survives_torn_record() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let path = storage.journal_path(1);
+
+    let journal = FrameJournal::open(&path).unwrap();
+    journal
+        .append(&JournalRecord::Encoded(encoded(1, "a.mp4")))
+        .unwrap();
+    journal
+        .append(&JournalRecord::Indexed { frame_number: 1 })
+        .unwrap();
+    drop(journal);
+    // the process was killed in the middle of a record
+    let mut content = std::fs::read_to_string(&path).unwrap();
+    content.push_str("{\"op\":\"encoded\",\"frame_nu");
+    std::fs::write(&path, content).unwrap();
+
+    assert_eq!(read_journal(&path).unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_recover_frame_journals() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+
+    let chunk = storage
+        .video_chunk_path(1, Utc::now())
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    std::fs::write(&chunk, b"").unwrap();
+    db.insert_video_chunk(&chunk, "monitor_1").await.unwrap();
+
+    let journal = FrameJournal::open(&storage.journal_path(1)).unwrap();
+    for record in [
+        JournalRecord::Encoded(encoded(1, &chunk)),
+        JournalRecord::Indexed { frame_number: 1 },
+        JournalRecord::Encoded(encoded(2, &chunk)),
+        JournalRecord::Encoded(encoded(3, &chunk)),
+        // the chunk of this one was never written
+        JournalRecord::Encoded(encoded(4, "missing.mp4")),
+    ] {
+        journal.append(&record).unwrap();
+    }
+    drop(journal);
+
+    assert_eq!(storage.recover_frame_journals(&db).await.unwrap(), 2);
+    assert_eq!(
+        db.get_total_frames(std::path::Path::new(&chunk))
+            .await
+            .unwrap(),
+        2
+    );
+    // at their own offsets, whatever the index holds of the chunk
+    let offsets: Vec<i64> = db
+        .get_video_chunk_frame_times(&chunk)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(offset, _)| offset)
+        .collect();
+    assert_eq!(offsets, vec![1, 2]);
+    assert!(!storage.journal_path(1).exists());
+
+    // nothing left to recover on the next start
+    assert_eq!(storage.recover_frame_journals(&db).await.unwrap(), 0);
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use screenpipe_db::VideoChunkFrameText;
+use screenpipe_server::subtitles::{build_cues, format_timestamp, notable_lines, render_webvtt};
+use std::time::Duration;
+
+fn frame(offset_secs: i64, window_name: &str, text: &str, focused: bool) -> VideoChunkFrameText {
+    VideoChunkFrameText {
+        frame_id: offset_secs,
+        offset_index: offset_secs,
+        timestamp: DateTime::<Utc>::UNIX_EPOCH + ChronoDuration::seconds(offset_secs),
+        app_name: Some("arc".to_string()),
+        window_name: Some(window_name.to_string()),
+        focused: Some(focused),
+        text: text.to_string(),
+        text_json: None,
+    }
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(format_timestamp(Duration::from_millis(0)), "00:00:00.000");
+    assert_eq!(
+        format_timestamp(Duration::from_millis(3_723_045)),
+        "01:02:03.045"
+    );
+}
+
+#[test]
+fn test_notable_lines_prefers_tall_lines() {
+    let text_json = r#"[
+        {"text": "Release notes", "height": "0.08"},
+        {"text": "fixed a crash when resizing", "height": "0.02"},
+        {"text": "added dark mode", "height": "0.02"}
+    ]"#;
+    assert_eq!(
+        notable_lines("", Some(text_json), 2),
+        vec!["Release notes".to_string()]
+    );
+
+    let text = "Getting Started\nthis paragraph explains how to install the tool.\nInstall";
+    assert_eq!(
+        notable_lines(text, None, 2),
+        vec!["Getting Started".to_string(), "Install".to_string()]
+    );
+}
+
+#[test]
+fn test_build_cues_merges_unchanged_frames() {
+    let frames = vec![
+        frame(0, "docs", "", true),
+        frame(1, "docs", "", true),
+        frame(1, "slack", "", false),
+        frame(4, "inbox", "", true),
+    ];
+    let cues = build_cues(&frames, DateTime::<Utc>::UNIX_EPOCH);
+
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].text, "arc - docs");
+    assert_eq!(cues[0].start, Duration::ZERO);
+    assert_eq!(cues[0].end, Duration::from_secs(4));
+    assert_eq!(cues[1].text, "arc - inbox");
+    assert_eq!(cues[1].start, Duration::from_secs(4));
+}
+
+#[test]
+fn test_build_cues_timed_from_chunk_start() {
+    // the chunk started with an unfocused frame, before the first focused one
+    let frames = vec![frame(3, "docs", "", true), frame(5, "inbox", "", true)];
+    let cues = build_cues(
+        &frames,
+        DateTime::<Utc>::UNIX_EPOCH + ChronoDuration::seconds(1),
+    );
+
+    assert_eq!(cues[0].start, Duration::from_secs(2));
+    assert_eq!(cues[0].end, Duration::from_secs(4));
+    assert_eq!(cues[1].start, Duration::from_secs(4));
+}
+
+#[test]
+fn test_render_webvtt_escapes_markup() {
+    let cues = build_cues(
+        &[frame(0, "a <b> & c", "", true)],
+        DateTime::<Utc>::UNIX_EPOCH,
+    );
+    let vtt = render_webvtt(&cues);
+
+    assert!(vtt.starts_with("WEBVTT\n\n1\n00:00:00.000 --> 00:00:02.000\n"));
+    assert!(vtt.contains("arc - a &lt;b&gt; &amp; c"));
+}
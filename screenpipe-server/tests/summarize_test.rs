@@ -0,0 +1,89 @@
+use chrono::{Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use screenpipe_db::{AppUsage, OcrTextSample};
+use screenpipe_server::summarize::{
+    build_summary_prompt, parse_time_arg, ActivityDigest, TranscriptEntry,
+};
+
+fn digest() -> ActivityDigest {
+    let start = Utc.with_ymd_and_hms(2025, 3, 1, 14, 0, 0).unwrap();
+    ActivityDigest {
+        apps: vec![AppUsage {
+            app_name: "Code".to_string(),
+            window_name: "summarize.rs".to_string(),
+            frame_count: 120,
+            first_seen: start,
+            last_seen: start + Duration::minutes(50),
+        }],
+        screen_text: vec![OcrTextSample {
+            frame_id: 1,
+            timestamp: start + Duration::minutes(10),
+            app_name: "Firefox".to_string(),
+            window_name: "Die Zeit".to_string(),
+            text: "die   Nachrichten\nvon heute".to_string(),
+            language: Some("de".to_string()),
+        }],
+        transcripts: vec![TranscriptEntry {
+            timestamp: start + Duration::minutes(5),
+            device_name: "MacBook Pro Microphone".to_string(),
+            text: "let's ship the summaries today".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_build_summary_prompt_orders_entries_and_hints_language() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 1, 14, 0, 0).unwrap();
+    let prompt = build_summary_prompt(start, start + Duration::hours(1), &digest(), 10_000);
+
+    assert!(prompt.contains("- Code — summarize.rs"));
+    assert!(prompt.contains("120 frames"));
+    assert!(prompt.contains("language 'de'"));
+    assert!(prompt.contains("Firefox — Die Zeit: die Nachrichten von heute"));
+
+    let audio = prompt.find("audio, MacBook Pro Microphone").unwrap();
+    let screen = prompt.find("screen, Firefox").unwrap();
+    assert!(audio < screen, "entries should be in chronological order");
+}
+
+#[test]
+fn test_build_summary_prompt_respects_budget() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 1, 14, 0, 0).unwrap();
+    let mut digest = digest();
+    digest.transcripts = (0..200)
+        .map(|i| TranscriptEntry {
+            timestamp: start + Duration::seconds(i),
+            device_name: "mic".to_string(),
+            text: "a fairly long sentence that was said in the meeting".repeat(10),
+        })
+        .collect();
+
+    let prompt = build_summary_prompt(start, start + Duration::hours(1), &digest, 4_000);
+    assert!(prompt.chars().count() <= 4_000 + "[later entries cut]\n".len());
+    assert!(prompt.ends_with("[later entries cut]\n"));
+}
+
+#[test]
+fn test_parse_time_arg() {
+    let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    let local_hour = |value: &str| {
+        let time = parse_time_arg(value, today).unwrap().with_timezone(&Local);
+        assert_eq!(time.date_naive(), today);
+        (time.hour(), time.minute())
+    };
+
+    assert_eq!(local_hour("14:00"), (14, 0));
+    assert_eq!(local_hour("2pm"), (14, 0));
+    assert_eq!(local_hour("2:30PM"), (14, 30));
+    assert_eq!(local_hour("12am"), (0, 0));
+    assert_eq!(local_hour("12pm"), (12, 0));
+    assert_eq!(local_hour("2025-03-01 09:15"), (9, 15));
+
+    assert_eq!(
+        parse_time_arg("2025-03-01T14:00:00Z", today).unwrap(),
+        Utc.with_ymd_and_hms(2025, 3, 1, 14, 0, 0).unwrap()
+    );
+
+    assert!(parse_time_arg("13pm", today).is_err());
+    assert!(parse_time_arg("yesterday", today).is_err());
+    assert!(parse_time_arg("25:00", today).is_err());
+}
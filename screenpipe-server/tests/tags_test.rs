@@ -38,6 +38,10 @@ async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
         false,
         audio_manager,
         true,
+        Arc::new(OcrEngine::Tesseract),
+        None,
+        false,
+        None,
     );
 
     let router = app.create_router(true).await;
@@ -372,6 +376,7 @@ async fn insert_test_data(db: &Arc<DatabaseManager>) {
             Some("test_app"),
             Some("test_window"),
             true,
+            None,
         )
         .await
         .unwrap();
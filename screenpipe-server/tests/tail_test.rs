@@ -0,0 +1,53 @@
+use screenpipe_events::Event;
+use screenpipe_server::tail::format_event;
+use serde_json::json;
+
+fn event(name: &str, data: serde_json::Value) -> Event {
+    Event {
+        name: name.to_string(),
+        data,
+    }
+}
+
+#[test]
+fn test_format_recording_events() {
+    let line = format_event(
+        &event(
+            "frame_written",
+            json!({
+                "frame_id": 42,
+                "monitor_id": 1,
+                "timestamp": "2025-03-01T14:00:00Z",
+                "app_name": "Code",
+                "window_name": "main.rs",
+                "focused": true,
+                "text_length": 120,
+            }),
+        ),
+        false,
+    )
+    .unwrap();
+    assert!(line.ends_with("frame 42 on monitor 1: Code — main.rs (120 chars)"));
+
+    let line = format_event(
+        &event(
+            "activity_changed",
+            json!({"monitor_id": 2, "app_name": "Safari", "window_name": ""}),
+        ),
+        false,
+    )
+    .unwrap();
+    assert!(line.ends_with("▸ Safari (monitor 2)"));
+
+    let line = format_event(&event("app_blocked", json!({"monitor_id": 1})), false).unwrap();
+    assert!(line.ends_with("▪ a blocked window is focused, not recorded (monitor 1)"));
+}
+
+#[test]
+fn test_other_events_only_shown_with_all() {
+    let other = event("ui_frame", json!({"x": 1}));
+    assert_eq!(format_event(&other, false), None);
+    assert!(format_event(&other, true)
+        .unwrap()
+        .ends_with(r#"ui_frame: {"x":1}"#));
+}
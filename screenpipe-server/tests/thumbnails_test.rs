@@ -0,0 +1,71 @@
+use chrono::{TimeZone, Utc};
+use image::{DynamicImage, Rgb, RgbImage};
+use screenpipe_db::FrameLocation;
+use screenpipe_server::thumbnails::{
+    render_thumbnail_sprite, select_thumbnail_frames, thumbnail_index_path, thumbnail_position,
+    thumbnail_sprite_path, THUMBNAIL_COLUMNS, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH,
+};
+use std::path::PathBuf;
+
+fn frame(frame_id: i64, seconds: i64) -> FrameLocation {
+    FrameLocation {
+        frame_id,
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap()
+            + chrono::Duration::seconds(seconds),
+        file_path: "chunk.mp4".to_string(),
+        offset_index: frame_id,
+    }
+}
+
+#[test]
+fn test_select_thumbnail_frames() {
+    let frames = [
+        frame(0, 0),
+        frame(1, 30),
+        frame(2, 59),
+        frame(3, 60),
+        frame(4, 200),
+        frame(5, 230),
+    ];
+    let selected = select_thumbnail_frames(&frames, chrono::Duration::seconds(60));
+    assert_eq!(
+        selected.iter().map(|f| f.frame_id).collect::<Vec<_>>(),
+        vec![0, 3, 4]
+    );
+    assert!(select_thumbnail_frames(&[], chrono::Duration::seconds(60)).is_empty());
+}
+
+#[test]
+fn test_render_thumbnail_sprite() {
+    let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(320, 180, Rgb([255, 0, 0])));
+    // taller than the cell, letterboxed on the sides
+    let tall = DynamicImage::ImageRgb8(RgbImage::from_pixel(90, 180, Rgb([0, 0, 255])));
+    let mut images = vec![red; THUMBNAIL_COLUMNS as usize];
+    images.push(tall);
+
+    let sprite = render_thumbnail_sprite(&images);
+    assert_eq!(sprite.width(), THUMBNAIL_COLUMNS * THUMBNAIL_WIDTH);
+    assert_eq!(sprite.height(), 2 * THUMBNAIL_HEIGHT);
+    assert_eq!(sprite.get_pixel(0, 0), &Rgb([255, 0, 0]));
+
+    let (x, y) = thumbnail_position(THUMBNAIL_COLUMNS as usize);
+    assert_eq!((x, y), (0, THUMBNAIL_HEIGHT));
+    assert_eq!(
+        sprite.get_pixel(x + THUMBNAIL_WIDTH / 2, y + THUMBNAIL_HEIGHT / 2),
+        &Rgb([0, 0, 255])
+    );
+    assert_ne!(sprite.get_pixel(x, y + 1), &Rgb([0, 0, 255]));
+}
+
+#[test]
+fn test_thumbnail_paths() {
+    let video = "/data/laptop/2025-03-01/monitor_1/monitor_1_10-00-00.mp4";
+    assert_eq!(
+        thumbnail_sprite_path(video),
+        PathBuf::from("/data/laptop/2025-03-01/monitor_1/monitor_1_10-00-00_thumbs.webp")
+    );
+    assert_eq!(
+        thumbnail_index_path(video),
+        PathBuf::from("/data/laptop/2025-03-01/monitor_1/monitor_1_10-00-00_thumbs.json")
+    );
+}
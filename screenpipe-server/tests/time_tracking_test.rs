@@ -0,0 +1,173 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use screenpipe_db::AppActivity;
+use screenpipe_server::time_tracking::{
+    time_entries, to_ical, to_timewarrior, to_toggl_csv, TimeTrackingConfig,
+};
+
+fn frame(start: DateTime<Utc>, offset_secs: i64, app: &str, window: &str) -> AppActivity {
+    AppActivity {
+        timestamp: start + Duration::seconds(offset_secs),
+        app_name: app.to_string(),
+        window_name: window.to_string(),
+        category: None,
+    }
+}
+
+fn config() -> TimeTrackingConfig {
+    TimeTrackingConfig::parse(
+        r#"{
+            "rules": [
+                {"app": "^code$", "window": "screenpipe", "project": "screenpipe", "tags": ["dev"]},
+                {"app": "firefox", "window": "github", "project": "screenpipe"},
+                {"app": "slack", "project": "meetings"}
+            ],
+            "min_entry_secs": 60,
+            "max_gap_secs": 120,
+            "toggl_email": "me@example.com"
+        }"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_time_entries() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap();
+    let activity = vec![
+        frame(start, 0, "Code", "main.rs — screenpipe"),
+        frame(start, 60, "Code", "main.rs — screenpipe"),
+        frame(start, 120, "Code", "lib.rs — screenpipe"),
+        // a glance at slack, too short to keep
+        frame(start, 150, "Slack", "general"),
+        frame(start, 170, "Firefox", "Pull requests · GitHub"),
+        frame(start, 200, "Firefox", "Pull requests · GitHub"),
+        frame(start, 260, "Firefox", "Pull requests · GitHub"),
+        // no rule and no default project
+        frame(start, 300, "Finder", "Downloads"),
+        // after a pause longer than max_gap_secs
+        frame(start, 1000, "Code", "main.rs — screenpipe"),
+        frame(start, 1100, "Code", "main.rs — screenpipe"),
+    ];
+    let entries = time_entries(&activity, &config()).unwrap();
+    assert_eq!(entries.len(), 3);
+
+    assert_eq!(entries[0].project, "screenpipe");
+    assert_eq!(entries[0].app_name, "Code");
+    assert_eq!(entries[0].window_name, "main.rs — screenpipe");
+    assert_eq!(entries[0].tags, vec!["dev".to_string()]);
+    assert_eq!(entries[0].start, start);
+    assert_eq!(entries[0].end, start + Duration::seconds(150));
+
+    assert_eq!(entries[1].app_name, "Firefox");
+    assert_eq!(entries[1].duration_secs(), 130);
+
+    // the last frame counts for max_gap_secs
+    assert_eq!(entries[2].start, start + Duration::seconds(1000));
+    assert_eq!(entries[2].end, start + Duration::seconds(1220));
+
+    let mut config = config();
+    config.default_project = Some("other".to_string());
+    let entries = time_entries(&activity, &config).unwrap();
+    assert!(entries.iter().any(|entry| entry.project == "other"));
+
+    assert!(TimeTrackingConfig::parse(r#"{"rules": [{"app": "(", "project": "x"}]}"#).is_err());
+}
+
+#[test]
+fn test_time_tracking_formats() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap();
+    let activity = vec![
+        frame(start, 0, "Code", "main.rs, screenpipe"),
+        frame(start, 3600, "Code", "main.rs, screenpipe"),
+    ];
+    let mut config = config();
+    config.max_gap_secs = 3600;
+    let entries = time_entries(&activity, &config).unwrap();
+    assert_eq!(entries.len(), 1);
+
+    let csv = to_toggl_csv(&entries, &config);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "Email,Project,Description,Start date,Start time,Duration,Tags"
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("me@example.com,screenpipe,\"Code – main.rs, screenpipe\","));
+    assert!(row.ends_with(",02:00:00,dev"));
+
+    let intervals: serde_json::Value =
+        serde_json::from_str(&to_timewarrior(&entries).unwrap()).unwrap();
+    assert_eq!(intervals[0]["start"], "20250303T090000Z");
+    assert_eq!(intervals[0]["end"], "20250303T110000Z");
+    assert_eq!(intervals[0]["tags"][0], "screenpipe");
+
+    let ical = to_ical(&entries, start);
+    assert!(ical.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ical.contains("DTSTART:20250303T090000Z\r\n"));
+    assert!(ical.contains("DESCRIPTION:main.rs\\, screenpipe\r\n"));
+    assert!(ical.ends_with("END:VCALENDAR\r\n"));
+    assert!(ical.split("\r\n").all(|line| line.len() <= 75));
+}
+
+#[test]
+fn test_time_entries_by_category() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap();
+    let categorized = |offset_secs, category: &str| AppActivity {
+        category: Some(category.to_string()),
+        ..frame(start, offset_secs, "Code", "main.rs — screenpipe")
+    };
+    let activity = vec![
+        categorized(0, "coding"),
+        categorized(60, "coding"),
+        // same app and project, another category
+        categorized(120, "client x"),
+        categorized(180, "client x"),
+    ];
+    let entries = time_entries(&activity, &config()).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].category.as_deref(), Some("coding"));
+    assert_eq!(entries[0].end, start + Duration::seconds(120));
+    assert_eq!(entries[1].category.as_deref(), Some("client x"));
+
+    // exported with the tags of the rule
+    assert_eq!(
+        entries[1].export_tags(),
+        vec!["dev".to_string(), "client x".to_string()]
+    );
+    let csv = to_toggl_csv(&entries, &config());
+    assert!(csv.lines().nth(2).unwrap().ends_with(",\"dev, client x\""));
+}
+
+#[test]
+fn test_short_category_stretch_is_kept() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap();
+    let categorized = |offset_secs, category: &str| AppActivity {
+        category: Some(category.to_string()),
+        ..frame(start, offset_secs, "Code", "main.rs — screenpipe")
+    };
+    let activity = vec![
+        categorized(0, "coding"),
+        categorized(60, "coding"),
+        // shorter than min_entry_secs on its own
+        categorized(120, "client x"),
+        categorized(150, "coding"),
+        categorized(210, "coding"),
+    ];
+    let entries = time_entries(&activity, &config()).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].category.as_deref(), Some("coding"));
+    assert_eq!(entries[0].end, start + Duration::seconds(150));
+    assert_eq!(entries[1].start, start + Duration::seconds(150));
+    let total: i64 = entries.iter().map(|entry| entry.duration_secs()).sum();
+    assert_eq!(total, 330);
+
+    // a short stretch first goes to the entry that follows it
+    let activity = vec![
+        categorized(0, "client x"),
+        categorized(30, "coding"),
+        categorized(90, "coding"),
+    ];
+    let entries = time_entries(&activity, &config()).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].category.as_deref(), Some("coding"));
+    assert_eq!(entries[0].start, start);
+}
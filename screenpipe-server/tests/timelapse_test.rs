@@ -0,0 +1,87 @@
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use screenpipe_server::timelapse::{
+    next_occurrence, parse_time_of_day, timelapse_frames, timelapse_part_args, timelapse_path,
+};
+use screenpipe_server::Storage;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[test]
+fn test_timelapse_path() {
+    let root = tempdir().unwrap();
+    let storage = Storage::with_hostname(root.path(), "workstation");
+    let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    assert_eq!(
+        timelapse_path(&storage, date, "monitor_2"),
+        root.path()
+            .join("workstation")
+            .join("timelapses")
+            .join("2025-03-01_monitor_2.mp4")
+    );
+}
+
+#[test]
+fn test_timelapse_part_args() {
+    let first_frame_at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+    let args = timelapse_part_args(
+        (1920, 1080),
+        Path::new("/tmp/00000.mp4"),
+        first_frame_at,
+        60,
+    );
+    let input = |flag: &str| &args[args.iter().position(|arg| arg == flag).unwrap() + 1];
+    assert_eq!(input("-s"), "1920x1080");
+    assert_eq!(input("-i"), "-");
+    assert_eq!(input("-framerate"), "30/60");
+    let filter = &args[args.iter().position(|arg| arg == "-vf").unwrap() + 1];
+    // the clock starts at the capture time of the first frame and is drawn before speeding up
+    assert!(filter.starts_with(&format!(
+        "drawtext=text='%{{pts\\:localtime\\:{}\\:%T}}'",
+        first_frame_at.timestamp()
+    )));
+    assert!(filter.ends_with(",setpts=PTS/60"));
+    assert!(args.contains(&"-an".to_string()));
+    assert_eq!(args.last().unwrap(), "/tmp/00000.mp4");
+
+    let args = timelapse_part_args((800, 600), Path::new("out.mp4"), first_frame_at, 0);
+    assert!(args.iter().any(|arg| arg.ends_with("setpts=PTS/1")));
+}
+
+#[test]
+fn test_timelapse_frames() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+    let at = |secs: i64| start + chrono::Duration::seconds(secs);
+    // a frame every second, then nothing captured for 10 seconds
+    let frame_times = [(0, at(0)), (1, at(1)), (2, at(2)), (3, at(12))];
+
+    // at 30x, each frame of the timelapse is a second of the recording
+    let shown = timelapse_frames(&frame_times, 30);
+    assert_eq!(shown, vec![0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3]);
+
+    // at 60x, frames shorter than a frame of the timelapse are left out
+    let shown = timelapse_frames(&frame_times, 60);
+    assert_eq!(shown, vec![0, 2, 2, 2, 2, 2, 3]);
+
+    assert!(timelapse_frames(&[], 60).is_empty());
+    assert_eq!(timelapse_frames(&[(0, at(0))], 60), vec![0]);
+}
+
+#[test]
+fn test_time_of_day() {
+    let at = parse_time_of_day("23:30").unwrap();
+    assert_eq!(at, NaiveTime::from_hms_opt(23, 30, 0).unwrap());
+    assert!(parse_time_of_day("25:00").is_err());
+    assert!(parse_time_of_day("evening").is_err());
+
+    let evening = Local.with_ymd_and_hms(2025, 3, 1, 20, 0, 0).unwrap();
+    let next = next_occurrence(evening, at);
+    assert_eq!(next.date_naive(), evening.date_naive());
+    assert_eq!(next.time(), at);
+
+    // once past, the next one is the following day
+    let night = Local.with_ymd_and_hms(2025, 3, 1, 23, 45, 0).unwrap();
+    assert_eq!(
+        next_occurrence(night, at).date_naive(),
+        NaiveDate::from_ymd_opt(2025, 3, 2).unwrap()
+    );
+}
@@ -0,0 +1,53 @@
+use chrono::Utc;
+use screenpipe_db::UiControl;
+use screenpipe_server::ui_events::{normalize_role, FocusedControl, UiEventTracker, UiSnapshot};
+
+fn snapshot(window_name: &str, focused: Option<(&str, &str)>) -> UiSnapshot {
+    UiSnapshot {
+        app_name: "Mail".to_string(),
+        window_name: window_name.to_string(),
+        focused: focused.map(|(role, name)| FocusedControl {
+            role: role.to_string(),
+            name: name.to_string(),
+            value: None,
+        }),
+        controls: vec![UiControl {
+            role: "button".to_string(),
+            name: "Reply".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_normalize_role() {
+    assert_eq!(normalize_role("AXButton"), "button");
+    assert_eq!(normalize_role("AXMenuItem"), "menuitem");
+    assert_eq!(normalize_role("Button"), "button");
+    assert_eq!(normalize_role("push button"), "pushbutton");
+    assert_eq!(normalize_role("Window"), "window");
+}
+
+#[test]
+fn test_tracker_emits_window_then_focus_changes() {
+    let mut tracker = UiEventTracker::default();
+
+    let events = tracker.observe(snapshot("Inbox", Some(("textfield", "Search"))), Utc::now());
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, "window");
+    assert_eq!(events[0].controls.len(), 1);
+    assert_eq!(events[0].element_name.as_deref(), Some("Search"));
+
+    // nothing changed
+    let events = tracker.observe(snapshot("Inbox", Some(("textfield", "Search"))), Utc::now());
+    assert!(events.is_empty());
+
+    let events = tracker.observe(snapshot("Inbox", Some(("button", "Reply"))), Utc::now());
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, "focus");
+    assert!(events[0].controls.is_empty());
+
+    let events = tracker.observe(snapshot("Drafts", None), Utc::now());
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, "window");
+    assert_eq!(events[0].element_name, None);
+}
@@ -1,14 +1,17 @@
 // cargo bench --bench vision_benchmark -- benchmark_continuous_capture
 // or
 // cargo bench --bench vision_benchmark
+// or, without a display
+// cargo bench --bench vision_benchmark -- synthetic_capture
 // ! not very useful bench
 
 use std::sync::Arc;
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use screenpipe_vision::capture_backend::{MockCaptureBackend, SyntheticFrame};
 use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
 use screenpipe_vision::monitor::get_default_monitor;
-use screenpipe_vision::{continuous_capture, OcrEngine};
+use screenpipe_vision::{continuous_capture, continuous_capture_with_backend, OcrEngine};
 use tokio::sync::mpsc;
 use tokio::time::Duration;
 
@@ -47,6 +50,27 @@ async fn benchmark_continuous_capture(duration_secs: u64) -> f64 {
     frame_count as f64 / duration_secs as f64
 }
 
+// runs the diff of `frames` changing 1080p frames of a synthetic monitor
+async fn benchmark_synthetic_capture(frames: u64) {
+    let (result_tx, mut result_rx) = mpsc::channel(frames as usize + 1);
+    let backend = MockCaptureBackend::new(0, 1920, 1080)
+        .then(SyntheticFrame::Counter, frames)
+        .then(SyntheticFrame::Fail, 1);
+
+    let _ = continuous_capture_with_backend(
+        &backend,
+        result_tx,
+        Duration::ZERO,
+        OcrEngine::Tesseract,
+        Arc::new(WindowFilters::new(&[], &[])),
+        vec![],
+        false,
+    )
+    .await;
+
+    while result_rx.try_recv().is_ok() {}
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let rt = tokio::runtime::Runtime::new().unwrap();
 
@@ -65,6 +89,15 @@ fn criterion_benchmark(c: &mut Criterion) {
         );
     }
     group.finish();
+
+    let mut group = c.benchmark_group("synthetic_capture");
+    group.sample_size(10);
+    for frames in [10, 50].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(frames), frames, |b, &frames| {
+            b.to_async(&rt).iter(|| benchmark_synthetic_capture(frames))
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,241 @@
+use crate::capture_scope::Bounds;
+use crate::capture_screenshot_by_window::{focused_window_on, CapturedWindow, WindowFilters};
+use crate::focus_trigger::FocusedWindow;
+use crate::monitor::SafeMonitor;
+use crate::utils::{calculate_hash, capture_screenshot};
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, Rgb, RgbImage};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A captured frame: the monitor's image, its windows, the image's hash and how long the
+/// capture took.
+pub type Screenshot = (DynamicImage, Vec<CapturedWindow>, u64, Duration);
+
+/// Where the capture loop gets its frames from, a real monitor or a [`MockCaptureBackend`].
+pub trait CaptureBackend: Send + Sync {
+    /// The monitor id the frames are recorded under.
+    fn id(&self) -> u32;
+
+    fn capture(
+        &self,
+        window_filters: &WindowFilters,
+        capture_unfocused_windows: bool,
+    ) -> impl Future<Output = Result<Screenshot>> + Send;
+
+    /// The window focused on the monitor, checked between frames to capture focus changes.
+    fn focused_window(&self) -> impl Future<Output = Option<FocusedWindow>> + Send;
+}
+
+impl CaptureBackend for SafeMonitor {
+    fn id(&self) -> u32 {
+        SafeMonitor::id(self)
+    }
+
+    fn capture(
+        &self,
+        window_filters: &WindowFilters,
+        capture_unfocused_windows: bool,
+    ) -> impl Future<Output = Result<Screenshot>> + Send {
+        capture_screenshot(self, window_filters, capture_unfocused_windows)
+    }
+
+    fn focused_window(&self) -> impl Future<Output = Option<FocusedWindow>> + Send {
+        std::future::ready(focused_window_on(self))
+    }
+}
+
+/// What a synthetic monitor shows for one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticFrame {
+    Solid([u8; 3]),
+    /// A left to right gray gradient, shifted right by `offset` pixels.
+    Gradient {
+        offset: u32,
+    },
+    /// The frame number in binary, as a grid of 8 by 4 cells lit for its set bits, so each
+    /// frame differs from the one before.
+    Counter,
+    /// A capture that fails, like the one of a monitor unplugged while recording.
+    Fail,
+}
+
+const COUNTER_COLUMNS: u32 = 8;
+const COUNTER_ROWS: u32 = 4;
+
+/// Draws `frame` as the `index`th frame of a `width` by `height` monitor, the same image for
+/// the same arguments. `None` for [`SyntheticFrame::Fail`].
+pub fn render_synthetic_frame(
+    frame: SyntheticFrame,
+    index: u64,
+    width: u32,
+    height: u32,
+) -> Option<DynamicImage> {
+    let image = match frame {
+        SyntheticFrame::Solid(color) => RgbImage::from_pixel(width, height, Rgb(color)),
+        SyntheticFrame::Gradient { offset } => RgbImage::from_fn(width, height, |x, _| {
+            let level = ((x + offset) % width) * 255 / width.saturating_sub(1).max(1);
+            Rgb([level as u8; 3])
+        }),
+        SyntheticFrame::Counter => RgbImage::from_fn(width, height, |x, y| {
+            let column = x * COUNTER_COLUMNS / width;
+            let row = y * COUNTER_ROWS / height;
+            let bit = row * COUNTER_COLUMNS + column;
+            if index >> bit & 1 == 1 {
+                Rgb([255; 3])
+            } else {
+                Rgb([0; 3])
+            }
+        }),
+        SyntheticFrame::Fail => return None,
+    };
+    Some(DynamicImage::ImageRgb8(image))
+}
+
+/// A window drawn over the whole synthetic monitor.
+#[derive(Debug, Clone)]
+struct MockWindow {
+    app_name: String,
+    window_name: String,
+    is_focused: bool,
+}
+
+/// A monitor that plays a script of [`SyntheticFrame`]s, for running the capture loop and
+/// what consumes its frames headless, in tests and benchmarks. The script's last step repeats
+/// once it has played, and an empty script is a [`SyntheticFrame::Counter`].
+#[derive(Debug)]
+pub struct MockCaptureBackend {
+    id: u32,
+    width: u32,
+    height: u32,
+    script: Vec<(SyntheticFrame, u64)>,
+    windows: Vec<MockWindow>,
+    captured: AtomicU64,
+    // set by `set_focus`, overriding the focused window of `windows`
+    focus: Mutex<Option<Option<FocusedWindow>>>,
+}
+
+impl MockCaptureBackend {
+    pub fn new(id: u32, width: u32, height: u32) -> Self {
+        MockCaptureBackend {
+            id,
+            width: width.max(1),
+            height: height.max(1),
+            script: Vec::new(),
+            windows: Vec::new(),
+            captured: AtomicU64::new(0),
+            focus: Mutex::new(None),
+        }
+    }
+
+    /// Shows `frame` for the next `count` captures.
+    pub fn then(mut self, frame: SyntheticFrame, count: u64) -> Self {
+        if count > 0 {
+            self.script.push((frame, count));
+        }
+        self
+    }
+
+    /// Reports a window covering the monitor on each frame. Its image is OCRed like a real
+    /// window's, so a loop with windows needs a working OCR engine.
+    pub fn with_window(mut self, app_name: &str, window_name: &str, is_focused: bool) -> Self {
+        self.windows.push(MockWindow {
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            is_focused,
+        });
+        self
+    }
+
+    /// What the `index`th capture shows.
+    pub fn frame_at(&self, index: u64) -> SyntheticFrame {
+        let mut remaining = index;
+        for (frame, count) in &self.script {
+            if remaining < *count {
+                return *frame;
+            }
+            remaining -= count;
+        }
+        self.script
+            .last()
+            .map_or(SyntheticFrame::Counter, |(frame, _)| *frame)
+    }
+
+    /// Moves the focus to another window, `None` to one off the monitor, as a user would
+    /// between frames. Only what [`CaptureBackend::focused_window`] reports changes.
+    pub fn set_focus(&self, focus: Option<(&str, &str)>) {
+        *self.focus.lock().unwrap() = Some(focus.map(|(app_name, window_name)| FocusedWindow {
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+        }));
+    }
+
+    /// How many times the monitor was captured.
+    pub fn captured_frames(&self) -> u64 {
+        self.captured.load(Ordering::SeqCst)
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl CaptureBackend for MockCaptureBackend {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    // window filters apply to the synthetic windows too, a test can check they're left out
+    fn capture(
+        &self,
+        window_filters: &WindowFilters,
+        capture_unfocused_windows: bool,
+    ) -> impl Future<Output = Result<Screenshot>> + Send {
+        let capture_start = Instant::now();
+        let index = self.captured.fetch_add(1, Ordering::SeqCst);
+        let frame = self.frame_at(index);
+        let screenshot = render_synthetic_frame(frame, index, self.width, self.height)
+            .ok_or_else(|| anyhow!("synthetic monitor {} failed frame {}", self.id, index))
+            .map(|image| {
+                let windows = self
+                    .windows
+                    .iter()
+                    .filter(|window| capture_unfocused_windows || window.is_focused)
+                    .filter(|window| {
+                        window_filters.allows_window(&window.app_name, &window.window_name)
+                    })
+                    .map(|window| CapturedWindow {
+                        image: image.clone(),
+                        app_name: window.app_name.clone(),
+                        window_name: window.window_name.clone(),
+                        process_id: 0,
+                        is_focused: window.is_focused,
+                        bounds: Some(self.bounds()),
+                    })
+                    .collect();
+                let image_hash = calculate_hash(&image);
+                (image, windows, image_hash, capture_start.elapsed())
+            });
+        std::future::ready(screenshot)
+    }
+
+    fn focused_window(&self) -> impl Future<Output = Option<FocusedWindow>> + Send {
+        let focus = self.focus.lock().unwrap().clone().unwrap_or_else(|| {
+            self.windows
+                .iter()
+                .find(|window| window.is_focused)
+                .map(|window| FocusedWindow {
+                    app_name: window.app_name.clone(),
+                    window_name: window.window_name.clone(),
+                })
+        });
+        std::future::ready(focus)
+    }
+}
@@ -0,0 +1,81 @@
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FOCUSED_WINDOW_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Whether frames show only the focused window rather than the whole monitor. The rest of
+/// the monitor is left black, which costs next to nothing to encode, and windows in the
+/// background are never captured.
+pub fn set_focused_window_only(enabled: bool) {
+    FOCUSED_WINDOW_ONLY.store(enabled, Ordering::SeqCst);
+}
+
+pub fn focused_window_only() -> bool {
+    FOCUSED_WINDOW_ONLY.load(Ordering::SeqCst)
+}
+
+/// Position and size of a window or monitor in desktop coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Bounds {
+    pub fn center(&self) -> (i64, i64) {
+        (
+            self.x as i64 + self.width as i64 / 2,
+            self.y as i64 + self.height as i64 / 2,
+        )
+    }
+
+    pub fn contains(&self, (x, y): (i64, i64)) -> bool {
+        x >= self.x as i64
+            && x < self.x as i64 + self.width as i64
+            && y >= self.y as i64
+            && y < self.y as i64 + self.height as i64
+    }
+}
+
+/// Returned instead of a frame while no focused window is on the monitor.
+#[derive(Debug)]
+pub struct NoFocusedWindow;
+
+impl fmt::Display for NoFocusedWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no focused window on the monitor")
+    }
+}
+
+impl std::error::Error for NoFocusedWindow {}
+
+/// Draws `window`, captured at `window_bounds`, where it sits on a black frame of `monitor`.
+/// Window captures are in pixels and bounds in points, so the frame takes the scale of the
+/// window capture.
+pub fn frame_of_window(
+    window: &DynamicImage,
+    window_bounds: Bounds,
+    monitor: Bounds,
+) -> DynamicImage {
+    let scale = if window_bounds.width > 0 {
+        window.width() as f64 / window_bounds.width as f64
+    } else {
+        1.0
+    };
+    let scaled = |points: i64| (points as f64 * scale).round() as i64;
+    let mut frame = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        scaled(monitor.width as i64).max(1) as u32,
+        scaled(monitor.height as i64).max(1) as u32,
+        Rgba([0, 0, 0, 255]),
+    ));
+    imageops::overlay(
+        &mut frame,
+        window,
+        scaled(window_bounds.x as i64 - monitor.x as i64),
+        scaled(window_bounds.y as i64 - monitor.y as i64),
+    );
+    frame
+}
@@ -1,12 +1,16 @@
 use image::DynamicImage;
 use once_cell::sync::Lazy;
+use screenpipe_core::capture_policy::{capture_policy, report_focused_window, CapturePolicy};
 use std::collections::HashSet;
 use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
 use tracing::{debug, error};
 
 use xcap::{Window, XCapError};
 
+use crate::capture_scope::{focused_window_only, Bounds};
+use crate::focus_trigger::FocusedWindow;
 use crate::monitor::SafeMonitor;
 
 #[derive(Debug)]
@@ -141,51 +145,56 @@ pub struct CapturedWindow {
     pub window_name: String,
     pub process_id: i32,
     pub is_focused: bool,
+    /// Where the window was on the desktop, when the platform reports it.
+    pub bounds: Option<Bounds>,
 }
 
+/// The window rules of a [`CapturePolicy`], which audio muting follows as well.
 pub struct WindowFilters {
-    ignore_set: HashSet<String>,
-    include_set: HashSet<String>,
+    /// `None` follows the policy of the process, which can be reloaded while recording.
+    policy: Option<Arc<CapturePolicy>>,
 }
 
 impl WindowFilters {
     pub fn new(ignore_list: &[String], include_list: &[String]) -> Self {
         Self {
-            ignore_set: ignore_list.iter().map(|s| s.to_lowercase()).collect(),
-            include_set: include_list.iter().map(|s| s.to_lowercase()).collect(),
+            policy: Some(Arc::new(CapturePolicy::new(ignore_list, include_list))),
         }
     }
 
+    /// Filters that follow [`capture_policy`] as it changes.
+    pub fn shared() -> Self {
+        Self { policy: None }
+    }
+
+    fn policy(&self) -> Arc<CapturePolicy> {
+        self.policy.clone().unwrap_or_else(capture_policy)
+    }
+
+    /// Whether a window passes the include list, every window does when it's empty. The ignore
+    /// list isn't checked, captured windows are filtered by [`WindowFilters::allows_window`].
     // O(n) - we could figure out a better way to do this
     pub fn is_valid(&self, app_name: &str, title: &str) -> bool {
+        let policy = self.policy();
         let app_name_lower = app_name.to_lowercase();
         let title_lower = title.to_lowercase();
 
         // If include list is empty, we're done
-        if self.include_set.is_empty() {
+        if policy.included_windows().is_empty() {
             return true;
         }
 
-        // Check include list
-        if self
-            .include_set
+        policy
+            .included_windows()
             .iter()
             .any(|include| app_name_lower.contains(include) || title_lower.contains(include))
-        {
-            return true;
-        }
-
-        // Check ignore list first (usually smaller)
-        if !self.ignore_set.is_empty()
-            && self
-                .ignore_set
-                .iter()
-                .any(|ignore| app_name_lower.contains(ignore) || title_lower.contains(ignore))
-        {
-            return false;
-        }
+    }
 
-        false
+    /// Whether a window may be captured by the [`CapturePolicy`]: included windows always
+    /// are, ignored ones never, and once an include list is set nothing else is. Audio
+    /// muting follows the same rules.
+    pub fn allows_window(&self, app_name: &str, title: &str) -> bool {
+        self.policy().allows_window(app_name, title)
     }
 }
 
@@ -244,6 +253,11 @@ pub async fn capture_all_visible_windows(
                 }
             };
 
+            // background windows aren't even captured in focused window mode
+            if !is_focused && focused_window_only() {
+                return None;
+            }
+
             let process_id = match window.pid() {
                 Ok(pid) => pid as i32,
                 Err(e) => {
@@ -255,9 +269,19 @@ pub async fn capture_all_visible_windows(
                 }
             };
 
+            let bounds = match (window.x(), window.y(), window.width(), window.height()) {
+                (Ok(x), Ok(y), Ok(width), Ok(height)) => Some(Bounds {
+                    x,
+                    y,
+                    width,
+                    height,
+                }),
+                _ => None,
+            };
+
             // Capture image immediately while we have access to the window
             match window.capture_image() {
-                Ok(buffer) => Some((app_name, title, is_focused, buffer, process_id)),
+                Ok(buffer) => Some((app_name, title, is_focused, buffer, process_id, bounds)),
                 Err(e) => {
                     error!(
                         "Failed to capture image for window {} ({}): {}",
@@ -274,7 +298,18 @@ pub async fn capture_all_visible_windows(
     }
 
     // Process the captured data
-    for (app_name, window_name, is_focused, buffer, process_id) in windows_data {
+    let monitor_bounds = monitor.bounds();
+    for (app_name, window_name, is_focused, buffer, process_id, bounds) in windows_data {
+        // audio muting needs the focused window even when it isn't recorded, the events of
+        // blocked windows the monitor it's on
+        if is_focused {
+            let on_monitor = match &bounds {
+                Some(bounds) => monitor_bounds.contains(bounds.center()),
+                None => monitor.is_primary(),
+            };
+            report_focused_window(&app_name, &window_name, on_monitor.then_some(monitor.id()));
+        }
+
         // Convert to DynamicImage
         let image = DynamicImage::ImageRgba8(
             image::ImageBuffer::from_raw(buffer.width(), buffer.height(), buffer.into_raw())
@@ -285,7 +320,7 @@ pub async fn capture_all_visible_windows(
         let is_valid = !SKIP_APPS.contains(app_name.as_str())
             && !SKIP_TITLES.contains(window_name.as_str())
             && (capture_unfocused_windows || (is_focused && monitor.id() == monitor.id()))
-            && window_filters.is_valid(&app_name, &window_name);
+            && window_filters.allows_window(&app_name, &window_name);
 
         if is_valid {
             all_captured_images.push(CapturedWindow {
@@ -294,9 +329,75 @@ pub async fn capture_all_visible_windows(
                 window_name,
                 process_id: process_id as i32,
                 is_focused,
+                bounds,
             });
         }
     }
 
     Ok(all_captured_images)
 }
+
+/// The window in the foreground, on whichever monitor, without capturing any window. `None`
+/// when it can't be told, nothing is focused or the focused window is part of the system.
+pub fn foreground_window() -> Option<FocusedWindow> {
+    let windows = match Window::all() {
+        Ok(windows) => windows,
+        Err(e) => {
+            debug!("failed to list windows: {}", e);
+            return None;
+        }
+    };
+    windows.into_iter().find_map(|window| {
+        if !window.is_focused().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+            return None;
+        }
+        let app_name = window.app_name().ok()?.to_string();
+        let window_name = window.title().ok()?.to_string();
+        if SKIP_APPS.contains(app_name.as_str()) || SKIP_TITLES.contains(window_name.as_str()) {
+            return None;
+        }
+        Some(FocusedWindow {
+            app_name,
+            window_name,
+        })
+    })
+}
+
+/// The window focused on `monitor`, without capturing any window. `None` while the focused
+/// window is on another monitor or is part of the system, like the menu bar.
+pub fn focused_window_on(monitor: &SafeMonitor) -> Option<FocusedWindow> {
+    let monitor_bounds = monitor.bounds();
+    let windows = match Window::all() {
+        Ok(windows) => windows,
+        Err(e) => {
+            debug!("failed to list windows: {}", e);
+            return None;
+        }
+    };
+    windows.into_iter().find_map(|window| {
+        if !window.is_focused().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+            return None;
+        }
+        let app_name = window.app_name().ok()?.to_string();
+        let window_name = window.title().ok()?.to_string();
+        if SKIP_APPS.contains(app_name.as_str()) || SKIP_TITLES.contains(window_name.as_str()) {
+            return None;
+        }
+        let on_monitor = match (window.x(), window.y(), window.width(), window.height()) {
+            (Ok(x), Ok(y), Ok(width), Ok(height)) => monitor_bounds.contains(
+                Bounds {
+                    x,
+                    y,
+                    width,
+                    height,
+                }
+                .center(),
+            ),
+            _ => monitor.is_primary(),
+        };
+        on_monitor.then_some(FocusedWindow {
+            app_name,
+            window_name,
+        })
+    })
+}
@@ -0,0 +1,39 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// What a monitor's capture loop did since startup, for the metrics endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CaptureStats {
+    pub frames_captured: u64,
+    /// Frames dropped because they barely differed from the previous one.
+    pub frames_skipped: u64,
+    /// Time spent capturing the frames, in seconds.
+    pub capture_seconds: f64,
+}
+
+static CAPTURE_STATS: Lazy<Mutex<HashMap<u32, CaptureStats>>> = Lazy::new(Default::default);
+
+/// Accounts for a frame of `monitor_id` that took `duration` to capture.
+pub fn record_capture(monitor_id: u32, duration: Duration) {
+    let mut stats = CAPTURE_STATS.lock().unwrap();
+    let stats = stats.entry(monitor_id).or_default();
+    stats.frames_captured += 1;
+    stats.capture_seconds += duration.as_secs_f64();
+}
+
+/// Accounts for a frame of `monitor_id` skipped by the frame diff.
+pub fn record_skipped_frame(monitor_id: u32) {
+    CAPTURE_STATS
+        .lock()
+        .unwrap()
+        .entry(monitor_id)
+        .or_default()
+        .frames_skipped += 1;
+}
+
+/// The stats of every monitor captured so far, by monitor id.
+pub fn capture_stats() -> HashMap<u32, CaptureStats> {
+    CAPTURE_STATS.lock().unwrap().clone()
+}
@@ -1,8 +1,14 @@
 #[cfg(target_os = "macos")]
 use crate::apple::perform_ocr_apple;
+use crate::capture_backend::CaptureBackend;
+use crate::capture_scope::{Bounds, NoFocusedWindow};
 use crate::capture_screenshot_by_window::CapturedWindow;
 use crate::capture_screenshot_by_window::WindowFilters;
+use crate::capture_stats::{record_capture, record_skipped_frame};
 use crate::custom_ocr::perform_ocr_custom;
+use crate::diff_threshold::diff_threshold_for;
+use crate::focus_trigger::{wait_for_next_frame, FocusDebounce};
+use crate::incremental_ocr::{incremental_ocr, IncrementalOcr};
 #[cfg(target_os = "windows")]
 use crate::microsoft::perform_ocr_windows;
 use crate::monitor::get_monitor_by_id;
@@ -20,6 +26,7 @@ use serde::Deserializer;
 use serde::Serialize;
 use serde::Serializer;
 use serde_json;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::{
     collections::HashMap,
@@ -116,6 +123,8 @@ pub struct WindowOcrResult {
     pub focused: bool,
     pub confidence: f64,
     pub browser_url: Option<String>,
+    /// Where the window was on the desktop, when the platform reports it.
+    pub bounds: Option<Bounds>,
 }
 
 pub struct OcrTaskData {
@@ -126,6 +135,30 @@ pub struct OcrTaskData {
     pub result_tx: Sender<CaptureResult>,
 }
 
+static SKIP_OCR: AtomicBool = AtomicBool::new(false);
+
+/// Whether the capture loop sends frames on without reading their text, to spare CPU and
+/// memory while resources run low. Frames captured on demand are still read.
+pub fn set_skip_ocr(skip: bool) {
+    SKIP_OCR.store(skip, Ordering::SeqCst);
+}
+
+pub fn skip_ocr() -> bool {
+    SKIP_OCR.load(Ordering::SeqCst)
+}
+
+static DEFER_OCR: AtomicBool = AtomicBool::new(false);
+
+/// Whether the capture loop leaves reading the text of frames to later, from the recorded
+/// video, rather than reading it as they're captured.
+pub fn set_defer_ocr(defer: bool) {
+    DEFER_OCR.store(defer, Ordering::SeqCst);
+}
+
+pub fn defer_ocr() -> bool {
+    DEFER_OCR.load(Ordering::SeqCst)
+}
+
 const BROWSER_NAMES: [&str; 9] = [
     "chrome", "firefox", "safari", "edge", "brave", "arc", "chromium", "vivaldi", "opera",
 ];
@@ -153,11 +186,6 @@ pub async fn continuous_capture(
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
 ) -> Result<(), ContinuousCaptureError> {
-    let mut frame_counter: u64 = 0;
-    let mut previous_image: Option<DynamicImage> = None;
-    let mut max_average: Option<MaxAverageFrame> = None;
-    let mut max_avg_value = 0.0;
-
     debug!(
         "continuous_capture: Starting using monitor: {:?}",
         monitor_id
@@ -171,21 +199,68 @@ pub async fn continuous_capture(
         }
     };
 
+    continuous_capture_with_backend(
+        &monitor,
+        result_tx,
+        interval,
+        ocr_engine,
+        window_filters,
+        languages,
+        capture_unfocused_windows,
+    )
+    .await
+}
+
+/// The capture loop of [`continuous_capture`] over any [`CaptureBackend`], such as a
+/// [`MockCaptureBackend`](crate::capture_backend::MockCaptureBackend) in tests. Returns when a
+/// capture fails or the results can't be sent.
+#[allow(clippy::too_many_arguments)]
+pub async fn continuous_capture_with_backend<B: CaptureBackend>(
+    backend: &B,
+    result_tx: Sender<CaptureResult>,
+    interval: Duration,
+    ocr_engine: OcrEngine,
+    window_filters: Arc<WindowFilters>,
+    languages: Vec<Language>,
+    capture_unfocused_windows: bool,
+) -> Result<(), ContinuousCaptureError> {
+    let monitor_id = backend.id();
+    let mut frame_counter: u64 = 0;
+    let mut previous_image: Option<DynamicImage> = None;
+    let mut max_average: Option<MaxAverageFrame> = None;
+    let mut max_avg_value = 0.0;
+    let mut focus = FocusDebounce::default();
+    let mut incremental = IncrementalOcr::default();
+    // whether the focus changing on the monitor triggered the frame
+    let mut focus_changed = false;
+
     loop {
         // 3. Capture screenshot
-        let capture_result =
-            match capture_screenshot(&monitor, &window_filters, capture_unfocused_windows).await {
-                Ok(result) => result,
-                Err(e) => {
-                    debug!("error capturing screenshot: {}", e);
-                    return Err(ContinuousCaptureError::ErrorCapturingScreenshot(
-                        e.to_string(),
-                    ));
-                }
-            };
+        let capture_result = match backend
+            .capture(&window_filters, capture_unfocused_windows)
+            .await
+        {
+            Ok(result) => result,
+            // nothing to record on this monitor until a window on it gets the focus
+            Err(e) if e.is::<NoFocusedWindow>() => {
+                focus_changed = wait_for_next_frame(backend, interval, &mut focus).await;
+                continue;
+            }
+            Err(e) => {
+                debug!("error capturing screenshot: {}", e);
+                return Err(ContinuousCaptureError::ErrorCapturingScreenshot(
+                    e.to_string(),
+                ));
+            }
+        };
 
         // 4. Process captured image
-        let (image, window_images, image_hash, _capture_duration) = capture_result;
+        let (image, window_images, image_hash, capture_duration) = capture_result;
+        record_capture(monitor_id, capture_duration);
+        let focused_app = window_images
+            .iter()
+            .find(|window| window.is_focused)
+            .map(|window| window.app_name.as_str());
 
         let should_skip = should_skip_frame(
             &previous_image,
@@ -196,12 +271,25 @@ pub async fn continuous_capture(
             &window_images,
             image_hash,
             result_tx.clone(),
+            // a window just focused is kept however much it looks like the one before
+            if focus_changed {
+                0.0
+            } else {
+                diff_threshold_for(focused_app)
+            },
         )
         .await;
+        if focus_changed {
+            debug!(
+                "captured frame {} of monitor {} on focus change to {:?}",
+                frame_counter, monitor_id, focused_app
+            );
+        }
 
         if should_skip {
+            record_skipped_frame(monitor_id);
             frame_counter += 1;
-            tokio::time::sleep(interval).await;
+            focus_changed = wait_for_next_frame(backend, interval, &mut focus).await;
             continue;
         }
 
@@ -209,8 +297,13 @@ pub async fn continuous_capture(
 
         // 5. Process max average frame if available
         if let Some(max_avg_frame) = max_average.take() {
-            if let Err(e) =
-                process_max_average_frame(max_avg_frame, &ocr_engine, languages.clone()).await
+            if let Err(e) = process_max_average_frame(
+                max_avg_frame,
+                &ocr_engine,
+                languages.clone(),
+                &mut incremental,
+            )
+            .await
             {
                 error!("Error processing max average frame: {}", e);
             }
@@ -219,10 +312,11 @@ pub async fn continuous_capture(
         }
 
         frame_counter += 1;
-        tokio::time::sleep(interval).await;
+        focus_changed = wait_for_next_frame(backend, interval, &mut focus).await;
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn should_skip_frame(
     previous_image: &Option<DynamicImage>,
     current_image: &DynamicImage,
@@ -232,6 +326,7 @@ async fn should_skip_frame(
     window_images: &Vec<CapturedWindow>,
     image_hash: u64,
     result_tx: Sender<CaptureResult>,
+    threshold: f64,
 ) -> bool {
     let current_average = match compare_with_previous_image(
         previous_image.as_ref(),
@@ -255,10 +350,10 @@ async fn should_skip_frame(
         current_average
     };
 
-    if current_average < 0.006 {
+    if current_average < threshold {
         debug!(
-            "Skipping frame {} due to low average difference: {:.3}",
-            frame_counter, current_average
+            "Skipping frame {} due to low average difference: {:.3} (threshold {:.3})",
+            frame_counter, current_average, threshold
         );
         true
     } else {
@@ -282,6 +377,7 @@ async fn process_max_average_frame(
     max_avg_frame: MaxAverageFrame,
     ocr_engine: &OcrEngine,
     languages: Vec<Language>,
+    incremental: &mut IncrementalOcr,
 ) -> Result<(), ContinuousCaptureError> {
     let ocr_task_data = OcrTaskData {
         image: max_avg_frame.image,
@@ -291,7 +387,20 @@ async fn process_max_average_frame(
         result_tx: max_avg_frame.result_tx,
     };
 
-    if let Err(e) = process_ocr_task(ocr_task_data, ocr_engine, languages).await {
+    let incremental = if incremental_ocr() {
+        Some(incremental)
+    } else {
+        None
+    };
+    if let Err(e) = run_ocr_task(
+        ocr_task_data,
+        ocr_engine,
+        languages,
+        incremental,
+        !defer_ocr(),
+    )
+    .await
+    {
         error!("Error processing OCR task: {}", e);
         return Err(ContinuousCaptureError::ErrorProcessingOcr(e.to_string()));
     }
@@ -313,6 +422,18 @@ pub async fn process_ocr_task(
     ocr_task_data: OcrTaskData,
     ocr_engine: &OcrEngine,
     languages: Vec<Language>,
+) -> Result<(), ContinuousCaptureError> {
+    run_ocr_task(ocr_task_data, ocr_engine, languages, None, true).await
+}
+
+/// [`process_ocr_task`], reading again only the changed bands of the windows `incremental`
+/// has OCR'd before when it's set. Nothing is read unless `read_text`.
+async fn run_ocr_task(
+    ocr_task_data: OcrTaskData,
+    ocr_engine: &OcrEngine,
+    languages: Vec<Language>,
+    mut incremental: Option<&mut IncrementalOcr>,
+    read_text: bool,
 ) -> Result<(), ContinuousCaptureError> {
     let OcrTaskData {
         image,
@@ -331,12 +452,15 @@ pub async fn process_ocr_task(
     let mut window_ocr_results = Vec::new();
     let mut total_confidence = 0.0;
     let mut window_count = 0;
+    let run_ocr = read_text && !skip_ocr();
 
     for captured_window in window_images {
         let ocr_result = process_window_ocr(
             captured_window,
             ocr_engine,
             &languages,
+            run_ocr,
+            incremental.as_deref_mut(),
             &mut total_confidence,
             &mut window_count,
         )
@@ -345,6 +469,9 @@ pub async fn process_ocr_task(
 
         window_ocr_results.push(ocr_result);
     }
+    if let Some(incremental) = incremental.filter(|_| run_ocr) {
+        incremental.retain_seen();
+    }
 
     // Create and send the result
     let capture_result = CaptureResult {
@@ -364,10 +491,51 @@ pub async fn process_ocr_task(
     Ok(())
 }
 
+/// Captures a single frame of `monitor_id` and runs OCR on its visible windows, outside of the
+/// continuous capture loop. Used to serve on-demand screenshots without recording.
+pub async fn capture_frame_with_ocr(
+    monitor_id: u32,
+    window_filters: &WindowFilters,
+    ocr_engine: &OcrEngine,
+    languages: Vec<Language>,
+    capture_unfocused_windows: bool,
+) -> Result<(DynamicImage, Vec<WindowOcrResult>), ContinuousCaptureError> {
+    let monitor = get_monitor_by_id(monitor_id)
+        .await
+        .ok_or(ContinuousCaptureError::MonitorNotFound)?;
+
+    let (image, window_images, _, _) =
+        capture_screenshot(&monitor, window_filters, capture_unfocused_windows)
+            .await
+            .map_err(|e| ContinuousCaptureError::ErrorCapturingScreenshot(e.to_string()))?;
+
+    let mut total_confidence = 0.0;
+    let mut window_count = 0;
+    let mut window_ocr_results = Vec::new();
+    for captured_window in window_images {
+        window_ocr_results.push(
+            process_window_ocr(
+                captured_window,
+                ocr_engine,
+                &languages,
+                true,
+                None,
+                &mut total_confidence,
+                &mut window_count,
+            )
+            .await?,
+        );
+    }
+
+    Ok((image, window_ocr_results))
+}
+
 async fn process_window_ocr(
     captured_window: CapturedWindow,
     ocr_engine: &OcrEngine,
     languages: &[Language],
+    run_ocr: bool,
+    incremental: Option<&mut IncrementalOcr>,
     total_confidence: &mut f64,
     window_count: &mut u32,
 ) -> Result<WindowOcrResult, ContinuousCaptureError> {
@@ -383,10 +551,24 @@ async fn process_window_ocr(
     .await;
 
     // Perform OCR based on the selected engine
-    let (window_text, window_json_output, confidence) =
+    let (window_text, window_json_output, confidence) = if !run_ocr {
+        (String::new(), "[]".to_string(), None)
+    } else if let Some(incremental) = incremental {
+        incremental
+            .ocr(
+                &captured_window.app_name,
+                &captured_window.window_name,
+                &captured_window.image,
+                |band| async move {
+                    perform_ocr_with_engine(ocr_engine, &band, languages.to_vec()).await
+                },
+            )
+            .await?
+    } else {
         perform_ocr_with_engine(ocr_engine, &captured_window.image, languages.to_vec())
             .await
-            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string()))?;
+            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string()))?
+    };
 
     // Update confidence metrics
     if let Some(conf) = confidence {
@@ -403,6 +585,7 @@ async fn process_window_ocr(
         focused: captured_window.is_focused,
         confidence: confidence.unwrap_or(0.0),
         browser_url,
+        bounds: captured_window.bounds,
     })
 }
 
@@ -0,0 +1,88 @@
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+/// Average difference to the previous frame below which a frame is dropped as unchanged.
+pub const DEFAULT_DIFF_THRESHOLD: f64 = 0.006;
+
+/// How much a frame must differ from the previous one to be kept, optionally per app: a
+/// terminal with a blinking cursor needs a higher threshold than a dashboard that barely moves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffThresholds {
+    default: f64,
+    /// Lowercased app name patterns, the first match wins.
+    apps: Vec<(String, f64)>,
+}
+
+impl Default for DiffThresholds {
+    fn default() -> Self {
+        DiffThresholds::new(DEFAULT_DIFF_THRESHOLD)
+    }
+}
+
+impl DiffThresholds {
+    pub fn new(default: f64) -> Self {
+        DiffThresholds {
+            default,
+            apps: Vec::new(),
+        }
+    }
+
+    /// Overrides the threshold while an app whose name contains `pattern` is focused.
+    pub fn with_app(mut self, pattern: &str, threshold: f64) -> Self {
+        self.apps.push((pattern.to_lowercase(), threshold));
+        self
+    }
+
+    pub fn default_threshold(&self) -> f64 {
+        self.default
+    }
+
+    /// The threshold while `app_name` is focused on the monitor.
+    pub fn for_app(&self, app_name: Option<&str>) -> f64 {
+        let Some(app_name) = app_name.map(str::to_lowercase) else {
+            return self.default;
+        };
+        self.apps
+            .iter()
+            .find(|(pattern, _)| app_name.contains(pattern.as_str()))
+            .map_or(self.default, |(_, threshold)| *threshold)
+    }
+}
+
+/// Parses an `APP=THRESHOLD` override, as given to `--app-diff-threshold`.
+pub fn parse_app_threshold(value: &str) -> Result<(String, f64), String> {
+    let (app, threshold) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected APP=THRESHOLD, got '{}'", value))?;
+    let threshold = threshold
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid threshold in '{}': {}", value, e))?;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!(
+            "threshold must be between 0 and 1, got {}",
+            threshold
+        ));
+    }
+    let app = app.trim();
+    if app.is_empty() {
+        return Err(format!("missing app name in '{}'", value));
+    }
+    Ok((app.to_string(), threshold))
+}
+
+static DIFF_THRESHOLDS: Lazy<RwLock<DiffThresholds>> = Lazy::new(Default::default);
+
+/// Sets the thresholds every monitor's capture loop uses from its next frame on.
+pub fn set_diff_thresholds(thresholds: DiffThresholds) {
+    *DIFF_THRESHOLDS.write().unwrap() = thresholds;
+}
+
+pub fn diff_thresholds() -> DiffThresholds {
+    DIFF_THRESHOLDS.read().unwrap().clone()
+}
+
+/// The threshold that applies while `app_name` is focused.
+pub fn diff_threshold_for(app_name: Option<&str>) -> f64 {
+    DIFF_THRESHOLDS.read().unwrap().for_app(app_name)
+}
@@ -0,0 +1,76 @@
+use crate::capture_scope::Bounds;
+use image::{DynamicImage, GenericImage, Rgba};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// A rectangle blacked out of every frame, like where a password manager docks, in points
+/// from the top left corner of its monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExcludedRegion {
+    /// The monitor the region is on, every monitor when `None`.
+    #[serde(default)]
+    pub monitor_id: Option<u32>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+static EXCLUDED_REGIONS: Lazy<RwLock<Vec<ExcludedRegion>>> = Lazy::new(Default::default);
+
+/// Sets the regions every monitor's capture loop blacks out from its next frame on.
+pub fn set_excluded_regions(regions: Vec<ExcludedRegion>) {
+    *EXCLUDED_REGIONS.write().unwrap() = regions;
+}
+
+pub fn excluded_regions() -> Vec<ExcludedRegion> {
+    EXCLUDED_REGIONS.read().unwrap().clone()
+}
+
+/// The regions excluded from the monitor `monitor_id` at `monitor`, in desktop coordinates.
+pub fn excluded_regions_on(monitor_id: u32, monitor: Bounds) -> Vec<Bounds> {
+    EXCLUDED_REGIONS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|region| region.monitor_id.map_or(true, |id| id == monitor_id))
+        .map(|region| Bounds {
+            x: monitor.x + region.x,
+            y: monitor.y + region.y,
+            width: region.width,
+            height: region.height,
+        })
+        .collect()
+}
+
+/// Paints `regions` black on `image`, a capture of the desktop area `area`. Captures are in
+/// pixels and areas in points, the edges are rounded outwards so that no pixel of a region
+/// is left.
+pub fn black_out(image: &mut DynamicImage, area: Bounds, regions: &[Bounds]) {
+    if area.width == 0 || area.height == 0 {
+        return;
+    }
+    let scale_x = image.width() as f64 / area.width as f64;
+    let scale_y = image.height() as f64 / area.height as f64;
+    let to_pixels = |points: i64, scale: f64, limit: u32, round: fn(f64) -> f64| {
+        (round(points as f64 * scale) as i64).clamp(0, limit as i64) as u32
+    };
+
+    for region in regions {
+        let left = region.x as i64 - area.x as i64;
+        let top = region.y as i64 - area.y as i64;
+        let right = left + region.width as i64;
+        let bottom = top + region.height as i64;
+
+        let x_start = to_pixels(left, scale_x, image.width(), f64::floor);
+        let x_end = to_pixels(right, scale_x, image.width(), f64::ceil);
+        let y_start = to_pixels(top, scale_y, image.height(), f64::floor);
+        let y_end = to_pixels(bottom, scale_y, image.height(), f64::ceil);
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                image.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
+    }
+}
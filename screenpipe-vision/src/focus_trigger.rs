@@ -0,0 +1,115 @@
+use crate::capture_backend::CaptureBackend;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How often the focused window is checked between frames when focus changes trigger captures.
+pub const FOCUS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a newly focused window must keep the focus before it's captured.
+pub const DEFAULT_FOCUS_DEBOUNCE: Duration = Duration::from_millis(250);
+
+// debounce in milliseconds plus one, 0 while focus changes don't trigger captures
+static FOCUS_TRIGGER: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the capture loop also grabs a frame as soon as the focused window of its monitor
+/// changes, once the new window has kept the focus for `debounce`, rather than only on its
+/// fixed interval. `None` captures on the interval alone.
+pub fn set_focus_trigger(debounce: Option<Duration>) {
+    let value = debounce.map_or(0, |debounce| debounce.as_millis() as u64 + 1);
+    FOCUS_TRIGGER.store(value, Ordering::SeqCst);
+}
+
+pub fn focus_trigger() -> Option<Duration> {
+    match FOCUS_TRIGGER.load(Ordering::SeqCst) {
+        0 => None,
+        value => Some(Duration::from_millis(value - 1)),
+    }
+}
+
+/// The window focused on a monitor, as far as telling a change of focus goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedWindow {
+    pub app_name: String,
+    pub window_name: String,
+}
+
+/// Tells when the focus of a monitor changed and settled, from the focused window seen at
+/// each poll. Switching through windows faster than the debounce, as alt-tabbing does,
+/// triggers a single capture, of the window the focus ends on.
+#[derive(Debug, Default)]
+pub struct FocusDebounce {
+    // `None` until the first poll, which sets what the focus changes from
+    last: Option<Option<FocusedWindow>>,
+    changed_at: Option<Instant>,
+}
+
+impl FocusDebounce {
+    /// Accounts for `focus` seen at `now`. True when a frame should be captured: the focus
+    /// moved to a window on the monitor and stayed on it for `debounce`.
+    pub fn observe(
+        &mut self,
+        focus: Option<FocusedWindow>,
+        now: Instant,
+        debounce: Duration,
+    ) -> bool {
+        match &self.last {
+            None => {
+                self.last = Some(focus);
+                false
+            }
+            Some(last) if *last != focus => {
+                self.last = Some(focus);
+                self.changed_at = Some(now);
+                false
+            }
+            Some(last) => match self.changed_at {
+                Some(changed_at) if last.is_some() && now - changed_at >= debounce => {
+                    self.changed_at = None;
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Accounts for a frame captured on the interval at `now`, which shows a change of focus
+    /// settled by then. One still settling is captured once it has.
+    pub fn captured(&mut self, now: Instant, debounce: Duration) {
+        if self
+            .changed_at
+            .is_some_and(|changed_at| now - changed_at >= debounce)
+        {
+            self.changed_at = None;
+        }
+    }
+}
+
+/// Waits `interval` for the next frame of `backend`, less when its focus changes and settles
+/// meanwhile. Returns whether the focus change cut the wait short. Intervals shorter than
+/// [`FOCUS_POLL_INTERVAL`] catch focus changes on their own and are waited out plainly.
+pub async fn wait_for_next_frame<B: CaptureBackend>(
+    backend: &B,
+    interval: Duration,
+    debounce: &mut FocusDebounce,
+) -> bool {
+    let settle = match focus_trigger() {
+        Some(settle) if interval > FOCUS_POLL_INTERVAL => settle,
+        _ => {
+            tokio::time::sleep(interval).await;
+            return false;
+        }
+    };
+
+    let deadline = Instant::now() + interval;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            debounce.captured(now, settle);
+            return false;
+        }
+        tokio::time::sleep(FOCUS_POLL_INTERVAL.min(deadline - now)).await;
+        if debounce.observe(backend.focused_window().await, Instant::now(), settle) {
+            return true;
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use image::{DynamicImage, GrayImage};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::debug;
+
+/// Side of the square tiles window images are compared in, in pixels.
+pub const TILE_SIZE: u32 = 32;
+
+/// Height from which a window is split into bands OCR'd on their own, in pixels.
+pub const MIN_BAND_HEIGHT: u32 = 96;
+
+/// Width from which a window is split into columns on its gutters, in pixels.
+pub const MIN_COLUMN_WIDTH: u32 = 96;
+
+/// Luma difference of a pixel to the last OCR'd image above which it changed, high enough
+/// to ignore antialiasing noise.
+const PIXEL_THRESHOLD: u8 = 32;
+
+/// Luma difference to a neighboring pixel above which there is an edge, of text or of the
+/// window's layout. A row or column without any but those of the layout is blank, a place
+/// to cut between text lines or columns.
+const EDGE_CONTRAST: u8 = 12;
+
+/// Share of the rows a column has an edge in, or of the columns a row has, from which it's a
+/// line of the window's layout, like the border of a sidebar, rather than text.
+const LAYOUT_LINE_SHARE: f64 = 0.75;
+
+/// Width of blank columns from which they are a gutter between columns of text, wider than
+/// the space between words. Borders of the layout are gutters whatever their width.
+const MIN_GUTTER_WIDTH: u32 = 16;
+
+static INCREMENTAL_OCR: AtomicBool = AtomicBool::new(false);
+
+/// Whether the capture loop OCRs windows band by band and only reads again the bands with a
+/// changed tile since the window was last OCR'd, keeping the text of the others. Frames
+/// captured on demand are always read whole.
+pub fn set_incremental_ocr(enabled: bool) {
+    INCREMENTAL_OCR.store(enabled, Ordering::SeqCst);
+}
+
+pub fn incremental_ocr() -> bool {
+    INCREMENTAL_OCR.load(Ordering::SeqCst)
+}
+
+/// A tile of a window image, or a region of it OCR'd on its own, in pixels of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The tiles of `current` differing from `previous`, every tile when their sizes differ.
+pub fn changed_tiles(previous: &GrayImage, current: &GrayImage, tile_size: u32) -> Vec<Tile> {
+    let (width, height) = current.dimensions();
+    let tile_size = tile_size.max(1);
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_size as usize) {
+        for x in (0..width).step_by(tile_size as usize) {
+            let tile = Tile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+            };
+            let changed = previous.dimensions() != current.dimensions()
+                || (tile.y..tile.y + tile.height).any(|y| {
+                    (tile.x..tile.x + tile.width).any(|x| {
+                        previous.get_pixel(x, y)[0].abs_diff(current.get_pixel(x, y)[0])
+                            > PIXEL_THRESHOLD
+                    })
+                });
+            if changed {
+                tiles.push(tile);
+            }
+        }
+    }
+    tiles
+}
+
+/// The edges of a window image, where a pixel differs from its left or top neighbor, and the
+/// lines of its layout: columns and rows with an edge across most of the image.
+struct Edges {
+    width: u32,
+    height: u32,
+    left: Vec<bool>,
+    top: Vec<bool>,
+    layout_columns: Vec<bool>,
+    layout_rows: Vec<bool>,
+}
+
+impl Edges {
+    fn new(image: &GrayImage) -> Self {
+        let (width, height) = image.dimensions();
+        let edge = |a: u8, b: u8| a.abs_diff(b) > EDGE_CONTRAST;
+        let mut left = vec![false; (width * height) as usize];
+        let mut top = vec![false; (width * height) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let luma = image.get_pixel(x, y)[0];
+                let i = (y * width + x) as usize;
+                left[i] = x > 0 && edge(luma, image.get_pixel(x - 1, y)[0]);
+                top[i] = y > 0 && edge(luma, image.get_pixel(x, y - 1)[0]);
+            }
+        }
+        let is_layout =
+            |count: usize, of: u32| of > 0 && count as f64 >= of as f64 * LAYOUT_LINE_SHARE;
+        let layout_columns = (0..width)
+            .map(|x| {
+                let count = (0..height)
+                    .filter(|y| left[(y * width + x) as usize])
+                    .count();
+                is_layout(count, height)
+            })
+            .collect();
+        let layout_rows = (0..height)
+            .map(|y| {
+                let row = &top[(y * width) as usize..((y + 1) * width) as usize];
+                is_layout(row.iter().filter(|edge| **edge).count(), width)
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            left,
+            top,
+            layout_columns,
+            layout_rows,
+        }
+    }
+
+    /// Whether row `y` has nothing but lines of the layout between `x` and `x + width`.
+    fn blank_row(&self, y: u32, x: u32, width: u32) -> bool {
+        (x + 1..x + width)
+            .all(|x| !self.left[(y * self.width + x) as usize] || self.layout_columns[x as usize])
+    }
+
+    /// Whether column `x` has nothing but lines of the layout from top to bottom.
+    fn blank_column(&self, x: u32) -> bool {
+        (1..self.height)
+            .all(|y| !self.top[(y * self.width + x) as usize] || self.layout_rows[y as usize])
+    }
+}
+
+/// The spans `(start, length)` of `0..total` between `cuts`.
+fn spans(cuts: &[u32], total: u32) -> Vec<(u32, u32)> {
+    std::iter::once(0)
+        .chain(cuts.iter().copied())
+        .zip(cuts.iter().copied().chain(std::iter::once(total)))
+        .filter(|(start, end)| end > start)
+        .map(|(start, end)| (start, end - start))
+        .collect()
+}
+
+/// The columns `(x, width)` of the window, cut in its gutters: on the border of the layout in
+/// a gutter when there is one, like that of a sidebar, else in its middle. None is narrower
+/// than half of `min_width`, and margins at the sides of the window aren't gutters.
+fn split_columns(edges: &Edges, min_width: u32) -> Vec<(u32, u32)> {
+    let width = edges.width;
+    let min_width = min_width.max(1);
+    let blank: Vec<bool> = (0..width).map(|x| edges.blank_column(x)).collect();
+    let mut cuts = Vec::new();
+    let mut x = 0;
+    while x < width {
+        if !blank[x as usize] {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && blank[x as usize] {
+            x += 1;
+        }
+        if start == 0 || x == width {
+            continue;
+        }
+        let border = (start..x).find(|&column| edges.layout_columns[column as usize]);
+        if border.is_none() && x - start < MIN_GUTTER_WIDTH {
+            continue;
+        }
+        let cut = border.unwrap_or((start + x) / 2);
+        let last = cuts.last().copied().unwrap_or(0);
+        if cut - last >= min_width / 2 && width - cut >= min_width / 2 {
+            cuts.push(cut);
+        }
+    }
+    spans(&cuts, width)
+}
+
+/// The bands `(y, height)` of the column `(x, width)` of the window, cut on rows blank across
+/// it. A cut is looked for from every multiple of `min_height` on, which keeps the cuts of
+/// the unchanged parts of a window where they were, and none leaves less than half a band at
+/// the bottom.
+fn split_rows(edges: &Edges, x: u32, width: u32, min_height: u32) -> Vec<(u32, u32)> {
+    let height = edges.height;
+    let min_height = min_height.max(1);
+    let last_cut = height.saturating_sub(min_height / 2);
+    let mut cuts = Vec::new();
+    let mut target = min_height;
+    while target < last_cut {
+        let last = cuts.last().copied().unwrap_or(0);
+        let found = (target.max(last + 1)..(target + min_height).min(last_cut))
+            .find(|&y| edges.blank_row(y, x, width));
+        if let Some(y) = found {
+            cuts.push(y);
+        }
+        target += min_height;
+    }
+    spans(&cuts, height)
+}
+
+/// Splits `image` into horizontal bands `(y, height)` cut on blank rows, so that no text line
+/// is split. Lines of the layout, like the border of a sidebar, don't keep a row from being
+/// blank.
+pub fn split_bands(image: &GrayImage, min_height: u32) -> Vec<(u32, u32)> {
+    split_rows(&Edges::new(image), 0, image.width(), min_height)
+}
+
+fn regions(edges: &Edges, min_width: u32, min_height: u32) -> Vec<Tile> {
+    split_columns(edges, min_width)
+        .into_iter()
+        .flat_map(|(x, width)| {
+            split_rows(edges, x, width, min_height)
+                .into_iter()
+                .map(move |(y, height)| Tile {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+        })
+        .collect()
+}
+
+/// Splits `image` into the regions OCR'd on their own, column by column and top to bottom:
+/// its columns cut in the gutters that run from top to bottom of it, each split into bands
+/// cut on rows blank across the column. A window with a sidebar or in columns is read a
+/// column at a time, its bands only spanning their column.
+pub fn split_regions(image: &GrayImage, min_width: u32, min_height: u32) -> Vec<Tile> {
+    regions(&Edges::new(image), min_width, min_height)
+}
+
+fn overlap(a: &Tile, b: &Tile) -> bool {
+    a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+/// The OCR result of a band of a column of a window image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandOcr {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub text: String,
+    /// Blocks of the engine's JSON output, their boxes in the band.
+    pub blocks: Vec<HashMap<String, String>>,
+    pub confidence: Option<f64>,
+}
+
+/// Moves the box of an OCR `block` of `band` to the window image of `window_width` by
+/// `window_height`. Apple's Vision reports boxes as fractions of the image from its bottom
+/// left corner, the other engines in pixels from its top left one, when they report them.
+fn offset_block(
+    block: &mut HashMap<String, String>,
+    band: &BandOcr,
+    window_width: u32,
+    window_height: u32,
+) {
+    let value = |key: &str| block.get(key)?.parse::<f64>().ok();
+    let (Some(top), Some(height)) = (value("top"), value("height")) else {
+        return;
+    };
+    let normalized = ["left", "top", "width", "height"]
+        .iter()
+        .all(|key| value(key).is_some_and(|value| (0.0..=1.0).contains(&value)));
+    let mut moved = Vec::with_capacity(4);
+    if normalized {
+        let (left, width) = (
+            value("left").unwrap_or_default(),
+            value("width").unwrap_or_default(),
+        );
+        let scale = band.width as f64 / window_width as f64;
+        let bottom_from_top = band.y as f64 + (1.0 - top) * band.height as f64;
+        moved.push((
+            "left",
+            (band.x as f64 + left * band.width as f64) / window_width as f64,
+        ));
+        moved.push(("width", width * scale));
+        moved.push(("top", 1.0 - bottom_from_top / window_height as f64));
+        moved.push(("height", height * band.height as f64 / window_height as f64));
+    } else {
+        if let Some(left) = value("left") {
+            moved.push(("left", left + band.x as f64));
+        }
+        moved.push(("top", top + band.y as f64));
+    }
+    for (key, value) in moved {
+        block.insert(key.to_string(), value.to_string());
+    }
+}
+
+/// The text, JSON output and confidence of a window from those of its bands, column by column
+/// and top to bottom, as an engine returns them for the whole window.
+pub fn merge_bands(
+    bands: &[BandOcr],
+    window_width: u32,
+    window_height: u32,
+) -> (String, String, Option<f64>) {
+    let text = bands
+        .iter()
+        .map(|band| band.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let blocks: Vec<_> = bands
+        .iter()
+        .flat_map(|band| {
+            band.blocks.iter().cloned().map(|mut block| {
+                offset_block(&mut block, band, window_width, window_height);
+                block
+            })
+        })
+        .collect();
+    let confidences: Vec<f64> = bands.iter().filter_map(|band| band.confidence).collect();
+    let confidence = (!confidences.is_empty())
+        .then(|| confidences.iter().sum::<f64>() / confidences.len() as f64);
+    (
+        text,
+        serde_json::to_string(&blocks).unwrap_or_else(|_| "[]".to_string()),
+        confidence,
+    )
+}
+
+struct WindowState {
+    image: GrayImage,
+    bands: Vec<BandOcr>,
+}
+
+/// The last OCR'd image and bands of each window of a monitor, by app and window name.
+#[derive(Default)]
+pub struct IncrementalOcr {
+    windows: HashMap<(String, String), WindowState>,
+    /// Windows OCR'd since the last [`Self::retain_seen`].
+    seen: HashSet<(String, String)>,
+}
+
+impl IncrementalOcr {
+    /// OCRs `image` of a window with `ocr`, only on the bands of its columns with a changed
+    /// tile since the window was last OCR'd. Bands without anything on them aren't read at all.
+    pub async fn ocr<F, Fut, E>(
+        &mut self,
+        app_name: &str,
+        window_name: &str,
+        image: &DynamicImage,
+        mut ocr: F,
+    ) -> Result<(String, String, Option<f64>), E>
+    where
+        F: FnMut(DynamicImage) -> Fut,
+        Fut: Future<Output = Result<(String, String, Option<f64>), E>>,
+    {
+        let key = (app_name.to_string(), window_name.to_string());
+        let luma = image.to_luma8();
+        let previous = self.windows.remove(&key);
+        let changed = previous
+            .as_ref()
+            .map(|state| changed_tiles(&state.image, &luma, TILE_SIZE));
+
+        let edges = Edges::new(&luma);
+        let regions = regions(&edges, MIN_COLUMN_WIDTH, MIN_BAND_HEIGHT);
+        let mut bands = Vec::with_capacity(regions.len());
+        let mut read = 0;
+        for region in regions {
+            let Tile {
+                x,
+                y,
+                width,
+                height,
+            } = region;
+            let reused = previous
+                .as_ref()
+                .zip(changed.as_ref())
+                .and_then(|(state, changed)| {
+                    let unchanged = !changed.iter().any(|tile| overlap(tile, &region));
+                    unchanged
+                        .then(|| {
+                            state.bands.iter().find(|band| {
+                                (band.x, band.y, band.width, band.height) == (x, y, width, height)
+                            })
+                        })
+                        .flatten()
+                });
+            if let Some(band) = reused {
+                bands.push(band.clone());
+                continue;
+            }
+            if (y..y + height).all(|row| edges.blank_row(row, x, width)) {
+                bands.push(BandOcr {
+                    x,
+                    y,
+                    width,
+                    height,
+                    text: String::new(),
+                    blocks: Vec::new(),
+                    confidence: None,
+                });
+                continue;
+            }
+            read += 1;
+            let (text, json, confidence) = ocr(image.crop_imm(x, y, width, height)).await?;
+            bands.push(BandOcr {
+                x,
+                y,
+                width,
+                height,
+                text,
+                blocks: serde_json::from_str(&json).unwrap_or_default(),
+                confidence,
+            });
+        }
+        debug!(
+            "incremental ocr read {} of {} bands of {:?}",
+            read,
+            bands.len(),
+            window_name
+        );
+
+        let result = merge_bands(&bands, luma.width(), luma.height());
+        self.seen.insert(key.clone());
+        self.windows.insert(key, WindowState { image: luma, bands });
+        Ok(result)
+    }
+
+    /// Forgets the windows not OCR'd since the last call, closed ones or ones that changed
+    /// name, so their images aren't kept.
+    pub fn retain_seen(&mut self) {
+        let seen = std::mem::take(&mut self.seen);
+        self.windows.retain(|key, _| seen.contains(key));
+    }
+}
@@ -1,7 +1,14 @@
 #[cfg(target_os = "macos")]
 pub mod apple;
+pub mod capture_backend;
+pub mod capture_scope;
+pub mod capture_stats;
 pub mod core;
 pub mod custom_ocr;
+pub mod diff_threshold;
+pub mod excluded_regions;
+pub mod focus_trigger;
+pub mod incremental_ocr;
 #[cfg(target_os = "windows")]
 pub mod microsoft;
 pub mod monitor;
@@ -11,7 +18,10 @@ pub mod tesseract;
 pub mod utils;
 #[cfg(target_os = "macos")]
 pub use apple::perform_ocr_apple;
-pub use core::{continuous_capture, process_ocr_task, CaptureResult, RealtimeVisionEvent, UIFrame};
+pub use core::{
+    continuous_capture, continuous_capture_with_backend, process_ocr_task, CaptureResult,
+    RealtimeVisionEvent, UIFrame,
+};
 // pub use types::CaptureResult;
 pub use utils::OcrEngine;
 pub mod capture_screenshot_by_window;
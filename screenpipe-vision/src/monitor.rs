@@ -1,32 +1,88 @@
+use crate::capture_scope::Bounds;
 use anyhow::{Error, Result};
 use image::DynamicImage;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tracing;
 use xcap::Monitor;
 
 #[derive(Clone)]
 pub struct SafeMonitor {
     monitor_id: u32,
-    monitor_data: Arc<MonitorData>,
+    /// Refreshed on each capture, as the monitor may be rotated or moved while recording.
+    monitor_data: Arc<RwLock<MonitorData>>,
 }
 
 #[derive(Clone)]
 pub struct MonitorData {
+    pub x: i32,
+    pub y: i32,
     pub width: u32,
     pub height: u32,
+    pub orientation: Orientation,
     pub name: String,
     pub is_primary: bool,
 }
 
-impl SafeMonitor {
-    pub fn new(monitor: Monitor) -> Self {
-        let monitor_id = monitor.id().unwrap();
-        let monitor_data = Arc::new(MonitorData {
+/// Which way a monitor is turned, from the rotation the platform reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    LandscapeFlipped,
+    PortraitFlipped,
+}
+
+impl Orientation {
+    /// The orientation of a monitor rotated clockwise by `degrees`, rounded to a quarter turn.
+    pub fn from_rotation(degrees: f32) -> Self {
+        match ((degrees / 90.0).round() as i64).rem_euclid(4) {
+            1 => Orientation::Portrait,
+            2 => Orientation::LandscapeFlipped,
+            3 => Orientation::PortraitFlipped,
+            _ => Orientation::Landscape,
+        }
+    }
+
+    pub fn is_portrait(&self) -> bool {
+        matches!(self, Orientation::Portrait | Orientation::PortraitFlipped)
+    }
+}
+
+impl MonitorData {
+    fn of(monitor: &Monitor) -> Self {
+        MonitorData {
+            x: monitor.x().unwrap_or(0),
+            y: monitor.y().unwrap_or(0),
             width: monitor.width().unwrap(),
             height: monitor.height().unwrap(),
+            orientation: Orientation::from_rotation(monitor.rotation().unwrap_or(0.0)),
             name: monitor.name().unwrap().to_string(),
             is_primary: monitor.is_primary().unwrap(),
-        });
+        }
+    }
+
+    /// Swaps the width and height when `image`, a capture of the monitor, is turned the other
+    /// way: some platforms report the size of the panel rather than of the rotated desktop.
+    fn match_capture(&mut self, image: &DynamicImage) {
+        let captured_portrait = image.height() > image.width();
+        if self.width != self.height && captured_portrait != (self.height > self.width) {
+            std::mem::swap(&mut self.width, &mut self.height);
+        }
+    }
+}
+
+fn find_monitor(monitor_id: u32) -> Result<Monitor> {
+    Monitor::all()
+        .map_err(Error::from)?
+        .into_iter()
+        .find(|m| m.id().unwrap() == monitor_id)
+        .ok_or_else(|| anyhow::anyhow!("Monitor not found"))
+}
+
+impl SafeMonitor {
+    pub fn new(monitor: Monitor) -> Self {
+        let monitor_id = monitor.id().unwrap();
+        let monitor_data = Arc::new(RwLock::new(MonitorData::of(&monitor)));
 
         Self {
             monitor_id,
@@ -37,26 +93,54 @@ impl SafeMonitor {
     pub async fn capture_image(&self) -> Result<DynamicImage> {
         let monitor_id = self.monitor_id;
 
-        let image = std::thread::spawn(move || -> Result<DynamicImage> {
-            let monitor = Monitor::all()
-                .map_err(Error::from)?
-                .into_iter()
-                .find(|m| m.id().unwrap() == monitor_id)
-                .ok_or_else(|| anyhow::anyhow!("Monitor not found"))?;
-
-            if monitor.width().unwrap() == 0 || monitor.height().unwrap() == 0 {
-                return Err(anyhow::anyhow!("Invalid monitor dimensions"));
-            }
+        let (image, mut monitor_data) =
+            std::thread::spawn(move || -> Result<(DynamicImage, MonitorData)> {
+                let monitor = find_monitor(monitor_id)?;
+
+                if monitor.width().unwrap() == 0 || monitor.height().unwrap() == 0 {
+                    return Err(anyhow::anyhow!("Invalid monitor dimensions"));
+                }
+
+                let image = monitor
+                    .capture_image()
+                    .map_err(Error::from)
+                    .map(DynamicImage::ImageRgba8)?;
+                Ok((image, MonitorData::of(&monitor)))
+            })
+            .join()
+            .unwrap()?;
+
+        monitor_data.match_capture(&image);
+        self.update(monitor_data);
+        Ok(image)
+    }
 
-            monitor
-                .capture_image()
-                .map_err(Error::from)
-                .map(DynamicImage::ImageRgba8)
+    /// Reads the position, size and orientation of the monitor again, for captures that
+    /// don't go through [`SafeMonitor::capture_image`].
+    pub async fn refresh(&self) -> Result<()> {
+        let monitor_id = self.monitor_id;
+        let monitor_data = tokio::task::spawn_blocking(move || {
+            find_monitor(monitor_id).map(|monitor| MonitorData::of(&monitor))
         })
-        .join()
-        .unwrap()?;
+        .await??;
+        self.update(monitor_data);
+        Ok(())
+    }
 
-        Ok(image)
+    fn update(&self, monitor_data: MonitorData) {
+        let mut current = self.monitor_data.write().unwrap();
+        if current.orientation != monitor_data.orientation
+            || (current.width, current.height) != (monitor_data.width, monitor_data.height)
+        {
+            tracing::info!(
+                "monitor {} is now {}x{} ({:?})",
+                self.monitor_id,
+                monitor_data.width,
+                monitor_data.height,
+                monitor_data.orientation
+            );
+        }
+        *current = monitor_data;
     }
 
     pub fn id(&self) -> u32 {
@@ -64,27 +148,43 @@ impl SafeMonitor {
     }
 
     pub fn dimensions(&self) -> (u32, u32) {
-        (self.monitor_data.width, self.monitor_data.height)
+        let data = self.monitor_data.read().unwrap();
+        (data.width, data.height)
     }
 
-    pub fn name(&self) -> &str {
-        &self.monitor_data.name
+    pub fn name(&self) -> String {
+        self.monitor_data.read().unwrap().name.clone()
     }
 
     pub fn width(&self) -> u32 {
-        self.monitor_data.width
+        self.monitor_data.read().unwrap().width
     }
 
     pub fn height(&self) -> u32 {
-        self.monitor_data.height
+        self.monitor_data.read().unwrap().height
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.monitor_data.read().unwrap().orientation
+    }
+
+    /// Where the monitor sits on the desktop.
+    pub fn bounds(&self) -> Bounds {
+        let data = self.monitor_data.read().unwrap();
+        Bounds {
+            x: data.x,
+            y: data.y,
+            width: data.width,
+            height: data.height,
+        }
     }
 
     pub fn is_primary(&self) -> bool {
-        self.monitor_data.is_primary
+        self.monitor_data.read().unwrap().is_primary
     }
 
     pub fn get_info(&self) -> MonitorData {
-        (*self.monitor_data).clone()
+        self.monitor_data.read().unwrap().clone()
     }
 }
 
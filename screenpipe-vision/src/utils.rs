@@ -1,10 +1,12 @@
+use crate::capture_scope::{focused_window_only, frame_of_window, Bounds, NoFocusedWindow};
 use crate::capture_screenshot_by_window::{
     capture_all_visible_windows, CapturedWindow, WindowFilters,
 };
 use crate::core::MaxAverageFrame;
 use crate::custom_ocr::CustomOcrConfig;
+use crate::excluded_regions::{black_out, excluded_regions_on};
 use crate::monitor::SafeMonitor;
-use image::DynamicImage;
+use image::{DynamicImage, Rgba, RgbaImage};
 use image_compare::{Algorithm, Metric, Similarity};
 use tracing::{debug, warn};
 use screenpipe_db::CustomOcrConfig as DBCustomOcrConfig;
@@ -77,16 +79,23 @@ pub async fn capture_screenshot(
     window_filters: &WindowFilters,
     capture_unfocused_windows: bool,
 ) -> Result<(DynamicImage, Vec<CapturedWindow>, u64, Duration), anyhow::Error> {
+    if focused_window_only() {
+        return capture_focused_window(monitor, window_filters).await;
+    }
+
     // info!("Starting screenshot capture for monitor: {:?}", monitor);
     let capture_start = Instant::now();
-    let image = monitor.capture_image().await.map_err(|e| {
+    let mut image = monitor.capture_image().await.map_err(|e| {
         debug!("failed to capture monitor image: {}", e);
         anyhow::anyhow!("monitor capture failed")
     })?;
+    let monitor_bounds = monitor.bounds();
+    let excluded = excluded_regions_on(monitor.id(), monitor_bounds);
+    black_out(&mut image, monitor_bounds, &excluded);
     let image_hash = calculate_hash(&image);
     let capture_duration = capture_start.elapsed();
 
-    let window_images =
+    let mut window_images =
         match capture_all_visible_windows(monitor, window_filters, capture_unfocused_windows).await
         {
             Ok(images) => images,
@@ -98,10 +107,68 @@ pub async fn capture_screenshot(
                 Vec::new()
             }
         };
+    black_out_windows(&mut window_images, &excluded);
 
     Ok((image, window_images, image_hash, capture_duration))
 }
 
+/// Captures the focused window alone, drawn where it is on a black frame of `monitor`. Fails
+/// with [`NoFocusedWindow`] while the focused window is on another monitor.
+async fn capture_focused_window(
+    monitor: &SafeMonitor,
+    window_filters: &WindowFilters,
+) -> Result<(DynamicImage, Vec<CapturedWindow>, u64, Duration), anyhow::Error> {
+    let capture_start = Instant::now();
+    // the monitor isn't captured itself, its bounds may be stale after a rotation
+    if let Err(e) = monitor.refresh().await {
+        debug!("failed to refresh monitor {}: {}", monitor.id(), e);
+    }
+    let monitor_bounds = monitor.bounds();
+    let mut window = capture_all_visible_windows(monitor, window_filters, false)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|window| {
+            window.is_focused
+                && match window.bounds {
+                    Some(bounds) => monitor_bounds.contains(bounds.center()),
+                    None => monitor.is_primary(),
+                }
+        })
+        .ok_or(NoFocusedWindow)?;
+
+    black_out_windows(
+        std::slice::from_mut(&mut window),
+        &excluded_regions_on(monitor.id(), monitor_bounds),
+    );
+    let image = match window.bounds {
+        Some(bounds) => frame_of_window(&window.image, bounds, monitor_bounds),
+        None => window.image.clone(),
+    };
+    let image_hash = calculate_hash(&image);
+    Ok((image, vec![window], image_hash, capture_start.elapsed()))
+}
+
+/// Blacks out the excluded regions a window covers before its own image is OCRed. Windows
+/// whose bounds are unknown may cover any of them and are blacked out whole.
+fn black_out_windows(windows: &mut [CapturedWindow], excluded: &[Bounds]) {
+    if excluded.is_empty() {
+        return;
+    }
+    for window in windows {
+        match window.bounds {
+            Some(bounds) => black_out(&mut window.image, bounds, excluded),
+            None => {
+                window.image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+                    window.image.width(),
+                    window.image.height(),
+                    Rgba([0, 0, 0, 255]),
+                ))
+            }
+        }
+    }
+}
+
 pub async fn compare_with_previous_image(
     previous_image: Option<&DynamicImage>,
     current_image: &DynamicImage,
@@ -110,7 +177,9 @@ pub async fn compare_with_previous_image(
     max_avg_value: &mut f64,
 ) -> anyhow::Result<f64> {
     let mut current_average = 0.0;
-    if let Some(prev_image) = previous_image {
+    if let Some(prev_image) = previous_image.filter(|prev_image| {
+        (prev_image.width(), prev_image.height()) == (current_image.width(), current_image.height())
+    }) {
         let histogram_diff = compare_images_histogram(prev_image, current_image)?;
         let ssim_diff = 1.0 - compare_images_ssim(prev_image, current_image);
         current_average = (histogram_diff + ssim_diff) / 2.0;
@@ -119,6 +188,10 @@ pub async fn compare_with_previous_image(
             "Frame {}: Histogram diff: {:.3}, SSIM diff: {:.3}, Current Average: {:.3}, Max_avr: {:.3} Fr: {}",
             frame_number, histogram_diff, ssim_diff, current_average, *max_avg_value, max_avg_frame_number
         );
+    } else if previous_image.is_some() {
+        // a rotated monitor changes everything and can't be compared pixel by pixel
+        debug!("Frame {} changed size, keeping it", frame_number);
+        current_average = 1.0;
     } else {
         debug!("No previous image to compare for frame {}", frame_number);
     }
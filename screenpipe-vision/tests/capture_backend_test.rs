@@ -0,0 +1,115 @@
+use screenpipe_vision::capture_backend::{
+    render_synthetic_frame, CaptureBackend, MockCaptureBackend, SyntheticFrame,
+};
+use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
+use screenpipe_vision::core::ContinuousCaptureError;
+use screenpipe_vision::{continuous_capture_with_backend, OcrEngine};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[test]
+fn test_synthetic_frames_are_deterministic() {
+    for frame in [
+        SyntheticFrame::Solid([10, 20, 30]),
+        SyntheticFrame::Gradient { offset: 7 },
+        SyntheticFrame::Counter,
+    ] {
+        let first = render_synthetic_frame(frame, 3, 64, 32).unwrap();
+        let again = render_synthetic_frame(frame, 3, 64, 32).unwrap();
+        assert_eq!(first.as_bytes(), again.as_bytes());
+        assert_eq!((first.width(), first.height()), (64, 32));
+    }
+
+    let counter = |index| render_synthetic_frame(SyntheticFrame::Counter, index, 64, 32).unwrap();
+    assert_ne!(counter(4).as_bytes(), counter(5).as_bytes());
+    assert!(render_synthetic_frame(SyntheticFrame::Fail, 0, 64, 32).is_none());
+}
+
+#[test]
+fn test_mock_backend_script() {
+    let backend = MockCaptureBackend::new(7, 16, 16)
+        .then(SyntheticFrame::Solid([0, 0, 0]), 2)
+        .then(SyntheticFrame::Gradient { offset: 0 }, 1);
+
+    assert_eq!(backend.frame_at(0), SyntheticFrame::Solid([0, 0, 0]));
+    assert_eq!(backend.frame_at(1), SyntheticFrame::Solid([0, 0, 0]));
+    assert_eq!(backend.frame_at(2), SyntheticFrame::Gradient { offset: 0 });
+    // the last step repeats
+    assert_eq!(backend.frame_at(10), SyntheticFrame::Gradient { offset: 0 });
+    assert_eq!(
+        MockCaptureBackend::new(1, 16, 16).frame_at(3),
+        SyntheticFrame::Counter
+    );
+}
+
+#[tokio::test]
+async fn test_mock_backend_windows() {
+    let backend = MockCaptureBackend::new(1, 16, 16)
+        .with_window("Code", "main.rs", true)
+        .with_window("Slack", "general", false)
+        .with_window("1Password", "vault", false);
+    let filters = WindowFilters::new(&["1password".to_string()], &[]);
+
+    let (_, windows, _, _) = backend.capture(&filters, false).await.unwrap();
+    let apps: Vec<&str> = windows.iter().map(|w| w.app_name.as_str()).collect();
+    assert_eq!(apps, vec!["Code"]);
+
+    let (_, windows, _, _) = backend.capture(&filters, true).await.unwrap();
+    let apps: Vec<&str> = windows.iter().map(|w| w.app_name.as_str()).collect();
+    assert_eq!(apps, vec!["Code", "Slack"]);
+    assert_eq!(backend.captured_frames(), 2);
+}
+
+#[test]
+fn test_window_filters() {
+    let filters = WindowFilters::new(&["private".to_string()], &[]);
+    assert!(filters.allows_window("Safari", "news"));
+    assert!(!filters.allows_window("Firefox", "Private Browsing"));
+    // is_valid only checks the include list
+    assert!(filters.is_valid("Firefox", "Private Browsing"));
+
+    let filters = WindowFilters::new(&["private".to_string()], &["firefox".to_string()]);
+    assert!(filters.allows_window("Firefox", "Private Browsing"));
+    assert!(!filters.allows_window("Safari", "news"));
+    assert!(filters.is_valid("Firefox", "Private Browsing"));
+    assert!(!filters.is_valid("Safari", "Private Browsing"));
+}
+
+#[tokio::test]
+async fn test_capture_loop_skips_unchanged_frames() {
+    let backend = MockCaptureBackend::new(1, 64, 32)
+        .then(SyntheticFrame::Solid([40, 40, 40]), 3)
+        .then(SyntheticFrame::Gradient { offset: 0 }, 3)
+        .then(SyntheticFrame::Fail, 1);
+    let (result_tx, mut result_rx) = mpsc::channel(16);
+
+    let result = continuous_capture_with_backend(
+        &backend,
+        result_tx,
+        Duration::from_millis(1),
+        OcrEngine::Tesseract,
+        Arc::new(WindowFilters::new(&[], &[])),
+        vec![],
+        false,
+    )
+    .await;
+
+    assert!(matches!(
+        result,
+        Err(ContinuousCaptureError::ErrorCapturingScreenshot(_))
+    ));
+    assert_eq!(backend.captured_frames(), 7);
+
+    // the first frame of each screen is kept, its repeats are dropped
+    let mut kept = Vec::new();
+    while let Ok(result) = result_rx.try_recv() {
+        kept.push(result.image);
+    }
+    assert_eq!(kept.len(), 2);
+    let solid = render_synthetic_frame(SyntheticFrame::Solid([40, 40, 40]), 0, 64, 32).unwrap();
+    let gradient =
+        render_synthetic_frame(SyntheticFrame::Gradient { offset: 0 }, 3, 64, 32).unwrap();
+    assert_eq!(kept[0].as_bytes(), solid.as_bytes());
+    assert_eq!(kept[1].as_bytes(), gradient.as_bytes());
+}
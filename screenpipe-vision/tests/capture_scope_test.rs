@@ -0,0 +1,71 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use screenpipe_vision::capture_scope::{frame_of_window, Bounds};
+
+#[test]
+fn test_bounds_contains_center() {
+    let left = Bounds {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    let right = Bounds {
+        x: 1920,
+        y: 0,
+        width: 2560,
+        height: 1440,
+    };
+    // mostly on the right monitor
+    let window = Bounds {
+        x: 1800,
+        y: 100,
+        width: 800,
+        height: 600,
+    };
+    assert_eq!(window.center(), (2200, 400));
+    assert!(!left.contains(window.center()));
+    assert!(right.contains(window.center()));
+
+    // monitors left of the primary one have negative coordinates
+    let above_left = Bounds {
+        x: -1280,
+        y: -1024,
+        width: 1280,
+        height: 1024,
+    };
+    assert!(above_left.contains((-1, -1)));
+    assert!(!above_left.contains((0, 0)));
+}
+
+#[test]
+fn test_frame_of_window() {
+    let monitor = Bounds {
+        x: 1920,
+        y: 0,
+        width: 400,
+        height: 300,
+    };
+    // a retina window: 100x50 points captured as 200x100 pixels
+    let window = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 100, Rgba([255, 0, 0, 255])));
+    let bounds = Bounds {
+        x: 1920 + 10,
+        y: 20,
+        width: 100,
+        height: 50,
+    };
+
+    let frame = frame_of_window(&window, bounds, monitor).to_rgba8();
+    assert_eq!(frame.dimensions(), (800, 600));
+    assert_eq!(frame.get_pixel(20, 40), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(219, 139), &Rgba([255, 0, 0, 255]));
+    // the rest of the monitor is opaque black
+    assert_eq!(frame.get_pixel(19, 40), &Rgba([0, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(220, 139), &Rgba([0, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(799, 599), &Rgba([0, 0, 0, 255]));
+
+    // partly off the monitor
+    let bounds = Bounds { x: 1900, ..bounds };
+    let frame = frame_of_window(&window, bounds, monitor).to_rgba8();
+    assert_eq!(frame.get_pixel(0, 40), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(160, 40), &Rgba([0, 0, 0, 255]));
+}
@@ -0,0 +1,30 @@
+use screenpipe_vision::diff_threshold::{
+    parse_app_threshold, DiffThresholds, DEFAULT_DIFF_THRESHOLD,
+};
+
+#[test]
+fn test_diff_threshold_per_app() {
+    let thresholds = DiffThresholds::default()
+        .with_app("Terminal", 0.05)
+        .with_app("grafana", 0.002);
+
+    assert_eq!(thresholds.for_app(None), DEFAULT_DIFF_THRESHOLD);
+    assert_eq!(thresholds.for_app(Some("Xcode")), DEFAULT_DIFF_THRESHOLD);
+    assert_eq!(thresholds.for_app(Some("Terminal")), 0.05);
+    // case-insensitive, anywhere in the app name
+    assert_eq!(thresholds.for_app(Some("Windows terminal")), 0.05);
+    assert_eq!(thresholds.for_app(Some("Grafana Desktop")), 0.002);
+    assert_eq!(DiffThresholds::new(0.01).for_app(Some("Terminal")), 0.01);
+}
+
+#[test]
+fn test_parse_app_threshold() {
+    assert_eq!(
+        parse_app_threshold("Visual Studio Code=0.02"),
+        Ok(("Visual Studio Code".to_string(), 0.02))
+    );
+    assert!(parse_app_threshold("Terminal").is_err());
+    assert!(parse_app_threshold("=0.02").is_err());
+    assert!(parse_app_threshold("Terminal=high").is_err());
+    assert!(parse_app_threshold("Terminal=2").is_err());
+}
@@ -0,0 +1,96 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use screenpipe_vision::capture_scope::Bounds;
+use screenpipe_vision::excluded_regions::{
+    black_out, excluded_regions_on, set_excluded_regions, ExcludedRegion,
+};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+#[test]
+fn test_black_out_scales_to_pixels() {
+    // a retina monitor right of the primary one: 400x300 points captured as 800x600 pixels
+    let monitor = Bounds {
+        x: 1920,
+        y: 0,
+        width: 400,
+        height: 300,
+    };
+    let mut image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(800, 600, WHITE));
+    let region = Bounds {
+        x: 1920 + 10,
+        y: 20,
+        width: 50,
+        height: 30,
+    };
+
+    black_out(&mut image, monitor, &[region]);
+    let image = image.to_rgba8();
+    assert_eq!(image.get_pixel(20, 40), &BLACK);
+    assert_eq!(image.get_pixel(119, 99), &BLACK);
+    assert_eq!(image.get_pixel(19, 40), &WHITE);
+    assert_eq!(image.get_pixel(120, 99), &WHITE);
+    assert_eq!(image.get_pixel(20, 100), &WHITE);
+}
+
+#[test]
+fn test_black_out_clips_to_image() {
+    let area = Bounds {
+        x: 100,
+        y: 100,
+        width: 50,
+        height: 50,
+    };
+    let mut image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(50, 50, WHITE));
+    // overlaps the window's top left corner only
+    let region = Bounds {
+        x: 90,
+        y: 90,
+        width: 20,
+        height: 20,
+    };
+
+    black_out(&mut image, area, &[region]);
+    let image = image.to_rgba8();
+    assert_eq!(image.get_pixel(0, 0), &BLACK);
+    assert_eq!(image.get_pixel(9, 9), &BLACK);
+    assert_eq!(image.get_pixel(10, 10), &WHITE);
+}
+
+#[test]
+fn test_excluded_regions_on_monitor() {
+    set_excluded_regions(vec![
+        ExcludedRegion {
+            monitor_id: Some(1),
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+        },
+        ExcludedRegion {
+            monitor_id: None,
+            x: 5,
+            y: 5,
+            width: 20,
+            height: 20,
+        },
+    ]);
+    let monitor = Bounds {
+        x: -1280,
+        y: 0,
+        width: 1280,
+        height: 1024,
+    };
+
+    assert_eq!(excluded_regions_on(1, monitor).len(), 2);
+    assert_eq!(
+        excluded_regions_on(2, monitor),
+        vec![Bounds {
+            x: -1275,
+            y: 5,
+            width: 20,
+            height: 20,
+        }]
+    );
+    set_excluded_regions(Vec::new());
+}
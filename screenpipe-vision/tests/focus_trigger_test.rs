@@ -0,0 +1,122 @@
+use screenpipe_vision::capture_backend::{MockCaptureBackend, SyntheticFrame};
+use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
+use screenpipe_vision::core::ContinuousCaptureError;
+use screenpipe_vision::focus_trigger::{
+    focus_trigger, set_focus_trigger, FocusDebounce, FocusedWindow, FOCUS_POLL_INTERVAL,
+};
+use screenpipe_vision::{continuous_capture_with_backend, OcrEngine};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+fn window(app_name: &str) -> Option<FocusedWindow> {
+    Some(FocusedWindow {
+        app_name: app_name.to_string(),
+        window_name: String::new(),
+    })
+}
+
+#[test]
+fn test_focus_debounce() {
+    let debounce = Duration::from_millis(250);
+    let start = Instant::now();
+    let at = |ms| start + Duration::from_millis(ms);
+    let mut focus = FocusDebounce::default();
+
+    // the first poll only sets what the focus changes from
+    assert!(!focus.observe(window("Code"), at(0), debounce));
+    assert!(!focus.observe(window("Code"), at(1000), debounce));
+
+    assert!(!focus.observe(window("Slack"), at(1100), debounce));
+    assert!(!focus.observe(window("Slack"), at(1200), debounce));
+    assert!(focus.observe(window("Slack"), at(1350), debounce));
+    // captured once
+    assert!(!focus.observe(window("Slack"), at(1450), debounce));
+
+    // alt-tabbing through windows captures the one it ends on
+    assert!(!focus.observe(window("Mail"), at(2000), debounce));
+    assert!(!focus.observe(window("Code"), at(2100), debounce));
+    assert!(!focus.observe(window("Notes"), at(2200), debounce));
+    assert!(!focus.observe(window("Notes"), at(2400), debounce));
+    assert!(focus.observe(window("Notes"), at(2450), debounce));
+
+    // the focus moving to another monitor isn't captured
+    assert!(!focus.observe(None, at(3000), debounce));
+    assert!(!focus.observe(None, at(3500), debounce));
+}
+
+#[test]
+fn test_focus_debounce_after_interval_frame() {
+    let debounce = Duration::from_millis(250);
+    let start = Instant::now();
+    let at = |ms| start + Duration::from_millis(ms);
+    let mut focus = FocusDebounce::default();
+    focus.observe(window("Code"), at(0), debounce);
+
+    // a frame on the interval after the change settled shows it already
+    focus.observe(window("Slack"), at(100), debounce);
+    focus.captured(at(400), debounce);
+    assert!(!focus.observe(window("Slack"), at(500), debounce));
+
+    // one still settling is captured once it has
+    focus.observe(window("Mail"), at(1000), debounce);
+    focus.captured(at(1100), debounce);
+    assert!(focus.observe(window("Mail"), at(1300), debounce));
+}
+
+#[tokio::test]
+async fn test_focus_change_triggers_capture() {
+    set_focus_trigger(Some(Duration::from_millis(50)));
+    assert_eq!(focus_trigger(), Some(Duration::from_millis(50)));
+
+    let backend = Arc::new(
+        MockCaptureBackend::new(1, 64, 32)
+            .then(SyntheticFrame::Solid([40, 40, 40]), 1)
+            .then(SyntheticFrame::Gradient { offset: 0 }, 1)
+            .then(SyntheticFrame::Fail, 1),
+    );
+    let (result_tx, mut result_rx) = mpsc::channel(16);
+    let capture = tokio::spawn({
+        let backend = backend.clone();
+        async move {
+            continuous_capture_with_backend(
+                &*backend,
+                result_tx,
+                // far longer than the test, only focus changes capture after the first frame
+                Duration::from_secs(600),
+                OcrEngine::Tesseract,
+                Arc::new(WindowFilters::new(&[], &[])),
+                vec![],
+                false,
+            )
+            .await
+        }
+    });
+
+    let first = timeout(Duration::from_secs(10), result_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(FOCUS_POLL_INTERVAL * 2).await;
+    backend.set_focus(Some(("Code", "main.rs")));
+    let second = timeout(Duration::from_secs(10), result_rx.recv())
+        .await
+        .expect("the focus change should trigger a capture")
+        .unwrap();
+    assert_ne!(first.image.as_bytes(), second.image.as_bytes());
+
+    backend.set_focus(Some(("Slack", "general")));
+    let result = timeout(Duration::from_secs(10), capture)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        result,
+        Err(ContinuousCaptureError::ErrorCapturingScreenshot(_))
+    ));
+    assert_eq!(backend.captured_frames(), 3);
+
+    set_focus_trigger(None);
+    assert_eq!(focus_trigger(), None);
+}
@@ -0,0 +1,303 @@
+use image::{DynamicImage, GrayImage, Luma};
+use screenpipe_vision::incremental_ocr::{
+    changed_tiles, merge_bands, split_bands, split_regions, BandOcr, IncrementalOcr, Tile,
+    MIN_BAND_HEIGHT, MIN_COLUMN_WIDTH,
+};
+use std::collections::HashMap;
+
+/// A white window of 200 by 300 with a dark "line of text" at each of `lines`.
+fn window(lines: &[u32]) -> GrayImage {
+    let mut image = GrayImage::from_pixel(200, 300, Luma([255]));
+    for &top in lines {
+        for y in top..top + 20 {
+            for x in (10..190).step_by(3) {
+                image.put_pixel(x, y, Luma([20]));
+            }
+        }
+    }
+    image
+}
+
+/// A window of 300 by 300 with a gray sidebar of 100 on its left, with a "line of text" at
+/// each of `sidebar_lines`, and the content on white, with one at each of `lines`.
+fn window_with_sidebar(sidebar_lines: &[u32], lines: &[u32]) -> GrayImage {
+    let mut image = GrayImage::from_pixel(300, 300, Luma([255]));
+    for y in 0..300 {
+        for x in 0..100 {
+            image.put_pixel(x, y, Luma([180]));
+        }
+    }
+    let mut write = |tops: &[u32], columns: std::ops::Range<u32>| {
+        for &top in tops {
+            for y in top..top + 20 {
+                for x in columns.clone().step_by(3) {
+                    image.put_pixel(x, y, Luma([20]));
+                }
+            }
+        }
+    };
+    write(sidebar_lines, 10..90);
+    write(lines, 110..290);
+    image
+}
+
+fn block(values: &[(&str, &str)]) -> HashMap<String, String> {
+    values
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_split_bands_on_blank_rows() {
+    let bands = split_bands(&window(&[20, 120, 220]), MIN_BAND_HEIGHT);
+    assert_eq!(bands, vec![(0, 96), (96, 96), (192, 108)]);
+
+    // a line across the multiple of the band height pushes the cut below it
+    let bands = split_bands(&window(&[20, 90, 220]), MIN_BAND_HEIGHT);
+    assert_eq!(bands, vec![(0, 110), (110, 82), (192, 108)]);
+
+    // windows smaller than a band are read whole
+    assert_eq!(
+        split_bands(&GrayImage::from_pixel(50, 60, Luma([255])), MIN_BAND_HEIGHT),
+        vec![(0, 60)]
+    );
+
+    // the border of a sidebar doesn't keep rows from being blank
+    let bands = split_bands(
+        &window_with_sidebar(&[50, 150], &[20, 120, 220]),
+        MIN_BAND_HEIGHT,
+    );
+    assert_eq!(bands, vec![(0, 96), (96, 96), (192, 108)]);
+}
+
+#[test]
+fn test_split_regions_in_columns() {
+    let regions = split_regions(
+        &window_with_sidebar(&[50, 150], &[20, 120, 220]),
+        MIN_COLUMN_WIDTH,
+        MIN_BAND_HEIGHT,
+    );
+    let columns: Vec<_> = regions
+        .iter()
+        .map(|region| (region.x, region.width))
+        .collect();
+    assert_eq!(
+        columns,
+        vec![
+            (0, 100),
+            (0, 100),
+            (0, 100),
+            (100, 200),
+            (100, 200),
+            (100, 200)
+        ]
+    );
+    assert_eq!(
+        regions[4],
+        Tile {
+            x: 100,
+            y: 96,
+            width: 200,
+            height: 96
+        }
+    );
+
+    // the space between words isn't a gutter, nor are the margins
+    let regions = split_regions(&window(&[20, 120, 220]), MIN_COLUMN_WIDTH, MIN_BAND_HEIGHT);
+    assert!(regions.iter().all(|region| region.width == 200));
+}
+
+#[test]
+fn test_changed_tiles() {
+    let before = window(&[20, 120]);
+    assert!(changed_tiles(&before, &before, 32).is_empty());
+
+    let mut after = before.clone();
+    after.put_pixel(70, 130, Luma([255]));
+    let tiles = changed_tiles(&before, &after, 32);
+    assert_eq!(tiles.len(), 1);
+    assert_eq!((tiles[0].x, tiles[0].y), (64, 128));
+
+    // noise below the pixel threshold isn't a change
+    let mut noisy = before.clone();
+    noisy.put_pixel(5, 5, Luma([240]));
+    assert!(changed_tiles(&before, &noisy, 32).is_empty());
+
+    // a resized window changed everywhere
+    assert_eq!(changed_tiles(&before, &GrayImage::new(64, 64), 32).len(), 4);
+}
+
+#[test]
+fn test_merge_bands() {
+    let band = |y, text: &str, blocks| BandOcr {
+        x: 0,
+        y,
+        width: 200,
+        height: 100,
+        text: text.to_string(),
+        blocks,
+        confidence: Some(0.8),
+    };
+    let (text, json, confidence) = merge_bands(
+        &[
+            band(
+                0,
+                "first",
+                vec![block(&[
+                    ("top", "10"),
+                    ("height", "5"),
+                    ("left", "3"),
+                    ("width", "40"),
+                ])],
+            ),
+            band(100, " ", Vec::new()),
+            band(
+                100,
+                "second",
+                vec![block(&[
+                    ("left", "0.1"),
+                    ("top", "0.5"),
+                    ("width", "0.5"),
+                    ("height", "0.2"),
+                ])],
+            ),
+        ],
+        200,
+        200,
+    );
+    assert_eq!(text, "first\nsecond");
+    assert!((confidence.unwrap() - 0.8).abs() < 1e-9);
+
+    let blocks: Vec<HashMap<String, String>> = serde_json::from_str(&json).unwrap();
+    // pixels from the top left corner
+    assert_eq!(blocks[0]["top"], "10");
+    // Vision's fractions from the bottom left corner, of the window now
+    let value = |key: &str| blocks[1][key].parse::<f64>().unwrap();
+    assert!((value("top") - 0.25).abs() < 1e-9);
+    assert!((value("height") - 0.1).abs() < 1e-9);
+    assert_eq!(blocks[1]["left"], "0.1");
+
+    // boxes of a column move across the window too
+    let column = BandOcr {
+        x: 100,
+        y: 0,
+        width: 100,
+        height: 200,
+        text: "aside".to_string(),
+        blocks: vec![
+            block(&[
+                ("left", "4"),
+                ("top", "8"),
+                ("width", "20"),
+                ("height", "6"),
+            ]),
+            block(&[
+                ("left", "0.5"),
+                ("top", "1"),
+                ("width", "0.5"),
+                ("height", "0.5"),
+            ]),
+        ],
+        confidence: None,
+    };
+    let (_, json, _) = merge_bands(&[column], 200, 200);
+    let blocks: Vec<HashMap<String, String>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        (blocks[0]["left"].as_str(), blocks[0]["top"].as_str()),
+        ("104", "8")
+    );
+    assert_eq!(blocks[0]["width"], "20");
+    let value = |key: &str| blocks[1][key].parse::<f64>().unwrap();
+    assert!((value("left") - 0.75).abs() < 1e-9);
+    assert!((value("width") - 0.25).abs() < 1e-9);
+}
+
+/// The text `incremental` reads on `image`, each band read giving its number and height.
+async fn read(incremental: &mut IncrementalOcr, image: &GrayImage, calls: &mut usize) -> String {
+    let image = DynamicImage::ImageLuma8(image.clone());
+    incremental
+        .ocr("Code", "main.rs", &image, |band| {
+            *calls += 1;
+            let text = format!("text {} of {}px", calls, band.height());
+            async move { Ok::<_, ()>((text, "[]".to_string(), Some(0.9))) }
+        })
+        .await
+        .unwrap()
+        .0
+}
+
+#[tokio::test]
+async fn test_only_changed_bands_are_read_again() {
+    let mut incremental = IncrementalOcr::default();
+    let mut calls = 0;
+
+    let first = window(&[20, 120, 220]);
+    assert_eq!(
+        read(&mut incremental, &first, &mut calls).await,
+        "text 1 of 96px\ntext 2 of 96px\ntext 3 of 108px"
+    );
+    // nothing changed, nothing read
+    assert_eq!(
+        read(&mut incremental, &first, &mut calls).await,
+        "text 1 of 96px\ntext 2 of 96px\ntext 3 of 108px"
+    );
+    assert_eq!(calls, 3);
+
+    let mut edited = first.clone();
+    edited.put_pixel(100, 125, Luma([255]));
+    assert_eq!(
+        read(&mut incremental, &edited, &mut calls).await,
+        "text 1 of 96px\ntext 4 of 96px\ntext 3 of 108px"
+    );
+
+    // a band erased to the background is blank, not read
+    assert_eq!(
+        read(&mut incremental, &window(&[20, 220]), &mut calls).await,
+        "text 1 of 96px\ntext 3 of 108px"
+    );
+    assert_eq!(calls, 4);
+
+    // windows gone since the last frame are read again from scratch
+    incremental.retain_seen();
+    incremental.retain_seen();
+    read(&mut incremental, &first, &mut calls).await;
+    assert_eq!(calls, 7);
+}
+
+/// Like [`read`], each band read giving its number and width.
+async fn read_columns(
+    incremental: &mut IncrementalOcr,
+    image: &GrayImage,
+    calls: &mut usize,
+) -> String {
+    let image = DynamicImage::ImageLuma8(image.clone());
+    incremental
+        .ocr("Mail", "Inbox", &image, |band| {
+            *calls += 1;
+            let text = format!("text {} of {}px wide", calls, band.width());
+            async move { Ok::<_, ()>((text, "[]".to_string(), None)) }
+        })
+        .await
+        .unwrap()
+        .0
+}
+
+#[tokio::test]
+async fn test_only_changed_column_is_read_again() {
+    let mut incremental = IncrementalOcr::default();
+    let mut calls = 0;
+
+    let first = window_with_sidebar(&[50], &[20, 120]);
+    assert_eq!(
+        read_columns(&mut incremental, &first, &mut calls).await,
+        "text 1 of 100px wide\ntext 2 of 200px wide\ntext 3 of 200px wide"
+    );
+    // an unread count changing in the sidebar doesn't read the content again
+    let mut edited = first.clone();
+    edited.put_pixel(40, 55, Luma([180]));
+    assert_eq!(
+        read_columns(&mut incremental, &edited, &mut calls).await,
+        "text 4 of 100px wide\ntext 2 of 200px wide\ntext 3 of 200px wide"
+    );
+}
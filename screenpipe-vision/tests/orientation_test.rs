@@ -0,0 +1,26 @@
+use screenpipe_vision::monitor::Orientation;
+
+#[test]
+fn test_orientation_from_rotation() {
+    assert_eq!(Orientation::from_rotation(0.0), Orientation::Landscape);
+    assert_eq!(Orientation::from_rotation(90.0), Orientation::Portrait);
+    assert_eq!(
+        Orientation::from_rotation(180.0),
+        Orientation::LandscapeFlipped
+    );
+    assert_eq!(
+        Orientation::from_rotation(270.0),
+        Orientation::PortraitFlipped
+    );
+    // some platforms report counterclockwise or unnormalized angles
+    assert_eq!(
+        Orientation::from_rotation(-90.0),
+        Orientation::PortraitFlipped
+    );
+    assert_eq!(Orientation::from_rotation(450.0), Orientation::Portrait);
+    assert_eq!(Orientation::from_rotation(89.6), Orientation::Portrait);
+
+    assert!(Orientation::Portrait.is_portrait());
+    assert!(Orientation::PortraitFlipped.is_portrait());
+    assert!(!Orientation::LandscapeFlipped.is_portrait());
+}
@@ -35,6 +35,7 @@ mod tests {
             image,
             is_focused: true,
             process_id: 1234,
+            bounds: None,
         }];
 
         let result = process_ocr_task(